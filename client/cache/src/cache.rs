@@ -0,0 +1,155 @@
+//! Latest market account data by slot, with lock free reads.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use arc_swap::ArcSwapOption;
+use manifest_client::{Market, Pubkey};
+
+/// Account data of a market as of a slot. Only data that parses as a market
+/// is cached.
+#[derive(Debug)]
+pub struct CachedMarket {
+    slot: u64,
+    data: Vec<u8>,
+}
+
+impl CachedMarket {
+    pub fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn market(&self) -> Market<'_> {
+        Market::try_from_bytes(&self.data).expect("Cached data is a market")
+    }
+}
+
+#[derive(Debug, Default)]
+struct MarketEntry {
+    market: ArcSwapOption<CachedMarket>,
+    stale: AtomicBool,
+}
+
+/// Read side of one tracked market. Cheap to clone and send to other threads.
+#[derive(Debug, Clone)]
+pub struct MarketHandle {
+    key: Pubkey,
+    entry: Arc<MarketEntry>,
+}
+
+impl MarketHandle {
+    pub fn key(&self) -> Pubkey {
+        self.key
+    }
+
+    /// Latest snapshot, None before the first update. Never waits on the
+    /// writer or other readers, and the snapshot stays valid while held.
+    pub fn load(&self) -> Option<Arc<CachedMarket>> {
+        self.entry.market.load_full()
+    }
+
+    /// True while updates may have been missed, from a disconnect until the
+    /// market is fetched or notified again. Markets start out stale.
+    pub fn is_stale(&self) -> bool {
+        self.entry.stale.load(Ordering::Acquire)
+    }
+}
+
+/// What [`MarketCache::apply`] did with an update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    Applied,
+    /// The cache already has this slot or a later one.
+    Outdated,
+    NotTracked,
+    /// The data is not a market.
+    Invalid,
+}
+
+/// Markets by address. The set of markets is fixed once subscribed, updates
+/// only need a shared reference.
+#[derive(Debug, Default)]
+pub struct MarketCache {
+    entries: HashMap<Pubkey, Arc<MarketEntry>>,
+}
+
+impl MarketCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking a market, or returns the handle if already tracked.
+    pub fn track(&mut self, key: Pubkey) -> MarketHandle {
+        let entry: &Arc<MarketEntry> = self.entries.entry(key).or_insert_with(|| {
+            Arc::new(MarketEntry {
+                market: ArcSwapOption::empty(),
+                stale: AtomicBool::new(true),
+            })
+        });
+        MarketHandle {
+            key,
+            entry: Arc::clone(entry),
+        }
+    }
+
+    pub fn handle(&self, key: &Pubkey) -> Option<MarketHandle> {
+        self.entries.get(key).map(|entry| MarketHandle {
+            key: *key,
+            entry: Arc::clone(entry),
+        })
+    }
+
+    pub fn keys(&self) -> Vec<Pubkey> {
+        self.entries.keys().copied().collect()
+    }
+
+    /// Stores data read at slot unless the cache already has a later slot,
+    /// so a slow fetch never overwrites a newer notification. Data at the
+    /// cached slot is the same state, so it is not stored again but still
+    /// clears stale.
+    pub fn apply(&self, key: &Pubkey, slot: u64, data: Vec<u8>) -> UpdateOutcome {
+        let Some(entry) = self.entries.get(key) else {
+            return UpdateOutcome::NotTracked;
+        };
+        if Market::try_from_bytes(&data).is_none() {
+            return UpdateOutcome::Invalid;
+        }
+        let update: Arc<CachedMarket> = Arc::new(CachedMarket { slot, data });
+        let mut outcome: UpdateOutcome = UpdateOutcome::Applied;
+        entry.market.rcu(|current| match current {
+            Some(current) if current.slot >= slot => {
+                outcome = UpdateOutcome::Outdated;
+                Some(Arc::clone(current))
+            }
+            _ => {
+                outcome = UpdateOutcome::Applied;
+                Some(Arc::clone(&update))
+            }
+        });
+        let is_current: bool = entry
+            .market
+            .load()
+            .as_ref()
+            .is_some_and(|current| current.slot == slot);
+        if is_current {
+            entry.stale.store(false, Ordering::Release);
+        }
+        outcome
+    }
+
+    /// Marks every market stale, for when notifications may have been missed.
+    pub fn mark_all_stale(&self) {
+        for entry in self.entries.values() {
+            entry.stale.store(true, Ordering::Release);
+        }
+    }
+}
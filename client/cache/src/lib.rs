@@ -0,0 +1,16 @@
+//! In memory Manifest markets for strategy threads.
+//!
+//! [`MarketCache`] keeps the latest account data of each tracked market and
+//! only moves forward in slots. Readers hold a [`MarketHandle`] and load the
+//! current snapshot without locks. [`subscribe`] keeps the cache current
+//! from websocket account notifications and refetches every market after a
+//! reconnect, since notifications sent while disconnected are lost.
+
+pub mod cache;
+pub mod subscribe;
+
+pub use cache::{CachedMarket, MarketCache, MarketHandle, UpdateOutcome};
+pub use subscribe::{resync, subscribe, SubscribeConfig};
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,115 @@
+//! Keeps a MarketCache current from websocket account notifications.
+//!
+//! Every connection subscribes to all tracked markets first and then fetches
+//! them over RPC, so changes after the fetch are notified and the fetch
+//! covers anything missed before. Slot ordering in the cache sorts out
+//! notifications and fetches that arrive out of order.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use futures_util::{stream::select_all, StreamExt};
+use manifest_client::Pubkey;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::RpcAccountInfoConfig,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use crate::cache::MarketCache;
+
+/// Most accounts getMultipleAccounts returns in one request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Settings for [`subscribe`].
+#[derive(Debug, Clone)]
+pub struct SubscribeConfig {
+    pub ws_url: String,
+    pub commitment: CommitmentConfig,
+    /// Wait before the first reconnect. Doubles on each failed reconnect.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl SubscribeConfig {
+    pub fn new(ws_url: String) -> Self {
+        SubscribeConfig {
+            ws_url,
+            commitment: CommitmentConfig::confirmed(),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Runs forever, reconnecting whenever the websocket drops. Markets are
+/// marked stale on every disconnect until they are fetched again. Returns
+/// right away when no markets are tracked.
+pub async fn subscribe(cache: &MarketCache, rpc: &RpcClient, config: &SubscribeConfig) {
+    if cache.keys().is_empty() {
+        return;
+    }
+    let mut backoff: Duration = config.initial_backoff;
+    loop {
+        let connected: bool = subscribe_once(cache, rpc, config).await.is_ok();
+        cache.mark_all_stale();
+        if connected {
+            backoff = config.initial_backoff;
+        }
+        tokio::time::sleep(backoff).await;
+        if !connected {
+            backoff = (backoff * 2).min(config.max_backoff);
+        }
+    }
+}
+
+async fn subscribe_once(
+    cache: &MarketCache,
+    rpc: &RpcClient,
+    config: &SubscribeConfig,
+) -> Result<()> {
+    let pubsub: PubsubClient = PubsubClient::new(&config.ws_url).await?;
+    let account_config: RpcAccountInfoConfig = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(config.commitment),
+        ..RpcAccountInfoConfig::default()
+    };
+    let mut streams = Vec::new();
+    for key in cache.keys() {
+        let (stream, _unsubscribe) = pubsub
+            .account_subscribe(&key, Some(account_config.clone()))
+            .await?;
+        streams.push(stream.map(move |response| (key, response)));
+    }
+    resync(cache, rpc, config.commitment).await?;
+
+    let mut updates = select_all(streams);
+    while let Some((key, response)) = updates.next().await {
+        if let Some(data) = response.value.data.decode() {
+            cache.apply(&key, response.context.slot, data);
+        }
+    }
+    Ok(())
+}
+
+/// Fetches every tracked market and applies it at the slot of the fetch.
+pub async fn resync(
+    cache: &MarketCache,
+    rpc: &RpcClient,
+    commitment: CommitmentConfig,
+) -> Result<()> {
+    let keys: Vec<Pubkey> = cache.keys();
+    for chunk in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let response = rpc
+            .get_multiple_accounts_with_commitment(chunk, commitment)
+            .await?;
+        let slot: u64 = response.context.slot;
+        for (key, account) in chunk.iter().zip(response.value) {
+            if let Some(account) = account {
+                cache.apply(key, slot, account.data);
+            }
+        }
+    }
+    Ok(())
+}
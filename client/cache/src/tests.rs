@@ -0,0 +1,83 @@
+use manifest_client::{Pubkey, MARKET_FIXED_DISCRIMINANT, MARKET_FIXED_SIZE};
+
+use crate::{MarketCache, MarketHandle, UpdateOutcome};
+
+fn market_data(base_mint: Pubkey) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![0; MARKET_FIXED_SIZE];
+    data[..8].copy_from_slice(&MARKET_FIXED_DISCRIMINANT.to_le_bytes());
+    // Base mint follows the discriminant, version, decimals, bumps and padding.
+    data[16..48].copy_from_slice(base_mint.as_ref());
+    data
+}
+
+#[test]
+fn test_apply_in_slot_order() {
+    let mut cache: MarketCache = MarketCache::new();
+    let key: Pubkey = Pubkey::new_unique();
+    let handle: MarketHandle = cache.track(key);
+    assert!(handle.load().is_none());
+    assert!(handle.is_stale());
+
+    let first: Pubkey = Pubkey::new_unique();
+    let second: Pubkey = Pubkey::new_unique();
+    assert_eq!(
+        cache.apply(&key, 10, market_data(first)),
+        UpdateOutcome::Applied
+    );
+    assert!(!handle.is_stale());
+    // A fetch that started before the notification lands after it.
+    assert_eq!(
+        cache.apply(&key, 9, market_data(second)),
+        UpdateOutcome::Outdated
+    );
+    assert_eq!(handle.load().unwrap().market().get_base_mint(), first);
+
+    // Readers keep their snapshot while the writer moves on.
+    let snapshot = handle.load().unwrap();
+    assert_eq!(
+        cache.apply(&key, 11, market_data(second)),
+        UpdateOutcome::Applied
+    );
+    assert_eq!(snapshot.slot(), 10);
+    assert_eq!(handle.load().unwrap().slot(), 11);
+    assert_eq!(handle.load().unwrap().market().get_base_mint(), second);
+}
+
+#[test]
+fn test_stale_until_resync() {
+    let mut cache: MarketCache = MarketCache::new();
+    let key: Pubkey = Pubkey::new_unique();
+    let handle: MarketHandle = cache.track(key);
+    cache.apply(&key, 10, market_data(Pubkey::new_unique()));
+
+    cache.mark_all_stale();
+    assert!(handle.is_stale());
+    assert_eq!(
+        cache.apply(&key, 8, market_data(Pubkey::new_unique())),
+        UpdateOutcome::Outdated
+    );
+    assert!(handle.is_stale());
+    // Fetching the same slot again confirms nothing was missed.
+    assert_eq!(
+        cache.apply(&key, 10, market_data(Pubkey::new_unique())),
+        UpdateOutcome::Outdated
+    );
+    assert!(!handle.is_stale());
+}
+
+#[test]
+fn test_rejected_updates() {
+    let mut cache: MarketCache = MarketCache::new();
+    let key: Pubkey = Pubkey::new_unique();
+    cache.track(key);
+    assert_eq!(
+        cache.apply(&Pubkey::new_unique(), 1, market_data(key)),
+        UpdateOutcome::NotTracked
+    );
+    assert_eq!(
+        cache.apply(&key, 1, vec![0; MARKET_FIXED_SIZE]),
+        UpdateOutcome::Invalid
+    );
+    assert!(cache.handle(&key).unwrap().load().is_none());
+    assert_eq!(cache.keys(), vec![key]);
+}
@@ -0,0 +1,196 @@
+//! Conversions between FIX decimal strings in token units and Manifest atoms.
+//!
+//! Prices and sizes are never rounded silently. A value that does not fit
+//! exactly is rejected so the OMS can correct it.
+
+/// Program limits on the price exponent.
+const MIN_PRICE_EXPONENT: i32 = -18;
+const MAX_PRICE_EXPONENT: i32 = 8;
+
+const D18: u128 = 1_000_000_000_000_000_000;
+
+/// Split a non negative decimal like "12.50" into digits and the number of
+/// digits after the point, i.e. (1250, 2).
+fn parse_decimal(value: &str) -> Option<(u128, u32)> {
+    let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if integer.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    if !integer
+        .bytes()
+        .chain(fraction.bytes())
+        .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut digits: u128 = 0;
+    for b in integer.bytes().chain(fraction.bytes()) {
+        digits = digits.checked_mul(10)?.checked_add(u128::from(b - b'0'))?;
+    }
+    Some((digits, fraction.len() as u32))
+}
+
+/// OrderQty or Price in tokens to atoms. None if it has more precision than
+/// the mint or does not fit in a u64.
+pub fn tokens_to_atoms(value: &str, decimals: u8) -> Option<u64> {
+    let (mut digits, mut scale) = parse_decimal(value)?;
+    while scale > u32::from(decimals) {
+        if digits % 10 != 0 {
+            return None;
+        }
+        digits /= 10;
+        scale -= 1;
+    }
+    let atoms: u128 = digits.checked_mul(10u128.checked_pow(u32::from(decimals) - scale)?)?;
+    u64::try_from(atoms).ok()
+}
+
+/// Price in quote tokens per base token to the mantissa and exponent used by
+/// PlaceOrderParams, in quote atoms per base atom.
+pub fn price_to_mantissa_exponent(
+    value: &str,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Option<(u32, i8)> {
+    let (mut mantissa, scale) = parse_decimal(value)?;
+    if mantissa == 0 {
+        return None;
+    }
+    let mut exponent: i32 = i32::from(quote_decimals) - i32::from(base_decimals) - scale as i32;
+    while mantissa % 10 == 0 && (mantissa > u128::from(u32::MAX) || exponent < MAX_PRICE_EXPONENT) {
+        mantissa /= 10;
+        exponent += 1;
+    }
+    if !(MIN_PRICE_EXPONENT..=MAX_PRICE_EXPONENT).contains(&exponent) {
+        return None;
+    }
+    Some((u32::try_from(mantissa).ok()?, exponent as i8))
+}
+
+/// Quote atoms for the base atoms at the mantissa and exponent price. Used to
+/// turn a market order's Price into a swap limit.
+pub fn quote_atoms_for_base(
+    base_atoms: u64,
+    mantissa: u32,
+    exponent: i8,
+    round_up: bool,
+) -> Option<u64> {
+    let product: u128 = u128::from(base_atoms) * u128::from(mantissa);
+    let quote_atoms: u128 = if exponent >= 0 {
+        product.checked_mul(10u128.pow(exponent as u32))?
+    } else {
+        let divisor: u128 = 10u128.pow(u32::from(exponent.unsigned_abs()));
+        match round_up {
+            true => product.div_ceil(divisor),
+            false => product / divisor,
+        }
+    };
+    u64::try_from(quote_atoms).ok()
+}
+
+/// Atoms to a decimal string in tokens, without trailing zeros.
+pub fn atoms_to_tokens(atoms: u64, decimals: u8) -> String {
+    format_scaled(u128::from(atoms), u32::from(decimals))
+}
+
+/// Price as stored onchain, quote atoms per base atom times 10^18, to quote
+/// tokens per base token.
+pub fn price_d18_to_tokens(price_d18: u128, base_decimals: u8, quote_decimals: u8) -> String {
+    // tokens = price_d18 / 10^18 * 10^base_decimals / 10^quote_decimals
+    let scale: i32 = 18 + i32::from(quote_decimals) - i32::from(base_decimals);
+    if scale >= 0 {
+        format_scaled(price_d18, scale as u32)
+    } else {
+        (price_d18 * 10u128.pow(scale.unsigned_abs())).to_string()
+    }
+}
+
+/// Average price of a fill or of all fills so far, in quote tokens per base
+/// token.
+pub fn average_price(
+    base_atoms: u64,
+    quote_atoms: u64,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> String {
+    if base_atoms == 0 {
+        return "0".to_string();
+    }
+    // Cannot overflow: u64::MAX * 10^18 < u128::MAX.
+    let price_d18: u128 = u128::from(quote_atoms) * D18 / u128::from(base_atoms);
+    price_d18_to_tokens(price_d18, base_decimals, quote_decimals)
+}
+
+fn format_scaled(value: u128, scale: u32) -> String {
+    if scale == 0 {
+        return value.to_string();
+    }
+    let divisor: u128 = 10u128.pow(scale);
+    let fraction: String = format!("{:0width$}", value % divisor, width = scale as usize);
+    let fraction: &str = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        (value / divisor).to_string()
+    } else {
+        format!("{}.{}", value / divisor, fraction)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tokens_to_atoms() {
+        assert_eq!(tokens_to_atoms("1.5", 9), Some(1_500_000_000));
+        assert_eq!(tokens_to_atoms("2", 6), Some(2_000_000));
+        assert_eq!(tokens_to_atoms(".25", 2), Some(25));
+        assert_eq!(tokens_to_atoms("1.2500", 2), Some(125));
+        assert_eq!(tokens_to_atoms("1.251", 2), None);
+        assert_eq!(tokens_to_atoms("-1", 2), None);
+        assert_eq!(tokens_to_atoms("", 2), None);
+        assert_eq!(tokens_to_atoms("100000000000", 9), None);
+    }
+
+    #[test]
+    fn test_price_to_mantissa_exponent() {
+        // SOL/USDC at 150.25 is 0.15025 quote atoms per base atom.
+        assert_eq!(
+            price_to_mantissa_exponent("150.25", 9, 6),
+            Some((15_025, -5))
+        );
+        assert_eq!(price_to_mantissa_exponent("100", 6, 6), Some((1, 2)));
+        assert_eq!(price_to_mantissa_exponent("0", 6, 6), None);
+        assert_eq!(
+            price_to_mantissa_exponent("4294967296", 6, 6),
+            None,
+            "mantissa does not fit in a u32"
+        );
+        assert_eq!(
+            price_to_mantissa_exponent("0.0000000000001", 6, 0),
+            None,
+            "exponent below the program minimum"
+        );
+    }
+
+    #[test]
+    fn test_quote_atoms_for_base() {
+        assert_eq!(quote_atoms_for_base(3, 1, -1, false), Some(0));
+        assert_eq!(quote_atoms_for_base(3, 1, -1, true), Some(1));
+        assert_eq!(
+            quote_atoms_for_base(1_000_000_000, 15_025, -5, false),
+            Some(150_250_000)
+        );
+        assert_eq!(quote_atoms_for_base(u64::MAX, 10, 0, false), None);
+    }
+
+    #[test]
+    fn test_formatting() {
+        assert_eq!(atoms_to_tokens(1_500_000_000, 9), "1.5");
+        assert_eq!(atoms_to_tokens(2_000_000, 6), "2");
+        assert_eq!(atoms_to_tokens(7, 0), "7");
+        // 0.15025 quote atoms per base atom.
+        assert_eq!(price_d18_to_tokens(150_250_000_000_000_000, 9, 6), "150.25");
+        assert_eq!(average_price(1_000_000_000, 150_250_000, 9, 6), "150.25");
+        assert_eq!(average_price(0, 1, 9, 6), "0");
+    }
+}
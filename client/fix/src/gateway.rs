@@ -0,0 +1,712 @@
+//! Order state and translation between FIX orders and Manifest transactions.
+//!
+//! The gateway trades for a single wallet on a single market. Limit orders go
+//! through batch_update against the wallet's seat, so the seat needs to be
+//! claimed and funded beforehand. Market orders go through swap against the
+//! wallet's token accounts. Every transaction on the market is fed back
+//! through [`Gateway::on_transaction`] in chain order, which is where
+//! ExecutionReports come from.
+
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use manifest_client::{
+    batch_update_instruction, swap_instruction, BatchUpdateParams, CancelOrderParams, DataIndex,
+    Instruction, OrderType, PlaceOrderParams, Pubkey, SwapParams, CANCEL_ORDER_LOG_DISCRIMINANT,
+    FILL_LOG_DISCRIMINANT, NIL, PLACE_ORDER_LOG_DISCRIMINANT, TOKEN_2022_PROGRAM_ID,
+};
+
+use crate::{
+    convert::{
+        atoms_to_tokens, average_price, price_d18_to_tokens, price_to_mantissa_exponent,
+        quote_atoms_for_base, tokens_to_atoms,
+    },
+    message::{msg_type, tags, FixError, FixMessage},
+};
+
+/// Everything needed to build transactions for one wallet on one market.
+#[derive(Debug, Clone)]
+pub struct MarketConfig {
+    /// Symbol (55) the OMS uses for this market. The market address is also
+    /// accepted.
+    pub symbol: String,
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub base_token_program: Pubkey,
+    pub quote_token_program: Pubkey,
+    /// Wallet token accounts, used by market orders.
+    pub trader_base: Pubkey,
+    pub trader_quote: Pubkey,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrdStatus {
+    PendingNew,
+    New,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    Rejected,
+}
+
+impl OrdStatus {
+    fn as_fix(&self) -> &'static str {
+        match self {
+            OrdStatus::PendingNew => "A",
+            OrdStatus::New => "0",
+            OrdStatus::PartiallyFilled => "1",
+            OrdStatus::Filled => "2",
+            OrdStatus::Canceled => "4",
+            OrdStatus::Rejected => "8",
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        matches!(
+            self,
+            OrdStatus::Filled | OrdStatus::Canceled | OrdStatus::Rejected
+        )
+    }
+}
+
+mod exec_type {
+    pub const NEW: &str = "0";
+    pub const CANCELED: &str = "4";
+    pub const REJECTED: &str = "8";
+    pub const TRADE: &str = "F";
+}
+
+#[derive(Debug, Clone)]
+pub struct OrderState {
+    pub cl_ord_id: String,
+    pub is_bid: bool,
+    pub order_qty_atoms: u64,
+    pub cum_base_atoms: u64,
+    pub cum_quote_atoms: u64,
+    /// Known once the transaction that placed it lands.
+    pub sequence_number: Option<u64>,
+    pub order_index: DataIndex,
+    /// Market orders and IOC limits. Whatever is not filled in the placing
+    /// transaction is reported as canceled.
+    pub is_immediate: bool,
+    pub status: OrdStatus,
+}
+
+impl OrderState {
+    fn leaves_atoms(&self) -> u64 {
+        match self.status.is_done() {
+            true => 0,
+            false => self.order_qty_atoms.saturating_sub(self.cum_base_atoms),
+        }
+    }
+}
+
+/// What a submitted transaction was for, keyed by its signature.
+#[derive(Debug, Clone)]
+enum Pending {
+    Order {
+        cl_ord_id: String,
+    },
+    Cancel {
+        cl_ord_id: String,
+        orig_cl_ord_id: String,
+    },
+}
+
+/// Events the gateway cares about, decoded from "Program data:" logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarketEvent {
+    Fill {
+        market: Pubkey,
+        maker: Pubkey,
+        taker: Pubkey,
+        price_d18: u128,
+        base_atoms: u64,
+        quote_atoms: u64,
+        maker_sequence_number: u64,
+        taker_sequence_number: u64,
+    },
+    PlaceOrder {
+        market: Pubkey,
+        trader: Pubkey,
+        order_sequence_number: u64,
+        order_index: DataIndex,
+    },
+    CancelOrder {
+        market: Pubkey,
+        trader: Pubkey,
+        order_sequence_number: u64,
+    },
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// Decode one log line. Offsets follow the repr(C) layouts of FillLog,
+/// PlaceOrderLog and CancelOrderLog after the 8 byte discriminant.
+pub fn decode_market_event(log: &str) -> Option<MarketEvent> {
+    let data: Vec<u8> = STANDARD.decode(log.strip_prefix("Program data: ")?).ok()?;
+    let (discriminant, data) = data.split_at_checked(8)?;
+    if discriminant == FILL_LOG_DISCRIMINANT && data.len() >= 224 {
+        Some(MarketEvent::Fill {
+            market: read_pubkey(data, 0),
+            maker: read_pubkey(data, 32),
+            taker: read_pubkey(data, 64),
+            price_d18: u128::from(read_u64(data, 160)) | (u128::from(read_u64(data, 168)) << 64),
+            base_atoms: read_u64(data, 176),
+            quote_atoms: read_u64(data, 184),
+            maker_sequence_number: read_u64(data, 192),
+            taker_sequence_number: read_u64(data, 200),
+        })
+    } else if discriminant == PLACE_ORDER_LOG_DISCRIMINANT && data.len() >= 112 {
+        Some(MarketEvent::PlaceOrder {
+            market: read_pubkey(data, 0),
+            trader: read_pubkey(data, 32),
+            order_sequence_number: read_u64(data, 88),
+            order_index: DataIndex::from_le_bytes(data[96..100].try_into().unwrap()),
+        })
+    } else if discriminant == CANCEL_ORDER_LOG_DISCRIMINANT && data.len() >= 72 {
+        Some(MarketEvent::CancelOrder {
+            market: read_pubkey(data, 0),
+            trader: read_pubkey(data, 32),
+            order_sequence_number: read_u64(data, 64),
+        })
+    } else {
+        None
+    }
+}
+
+pub struct Gateway {
+    config: MarketConfig,
+    orders: HashMap<String, OrderState>,
+    cl_ord_id_by_sequence_number: HashMap<u64, String>,
+    pending: HashMap<String, Pending>,
+    next_exec_id: u64,
+}
+
+impl Gateway {
+    pub fn new(config: MarketConfig) -> Self {
+        Gateway {
+            config,
+            orders: HashMap::new(),
+            cl_ord_id_by_sequence_number: HashMap::new(),
+            pending: HashMap::new(),
+            next_exec_id: 0,
+        }
+    }
+
+    pub fn config(&self) -> &MarketConfig {
+        &self.config
+    }
+
+    pub fn get_order(&self, cl_ord_id: &str) -> Option<&OrderState> {
+        self.orders.get(cl_ord_id)
+    }
+
+    /// Translate a NewOrderSingle into an instruction. On error, the returned
+    /// message is the rejecting ExecutionReport to send back.
+    pub fn handle_new_order_single(
+        &mut self,
+        message: &FixMessage,
+    ) -> Result<Instruction, FixMessage> {
+        let cl_ord_id: &str = message
+            .get(tags::CL_ORD_ID)
+            .ok_or_else(|| self.reject_unknown(message, "missing ClOrdID"))?;
+        if self.orders.contains_key(cl_ord_id) {
+            return Err(self.reject_unknown(message, "duplicate ClOrdID"));
+        }
+        let (is_bid, order_qty_atoms, is_immediate, instruction) = self
+            .translate_new_order_single(message)
+            .map_err(|reason| self.reject_unknown(message, &reason))?;
+        self.orders.insert(
+            cl_ord_id.to_string(),
+            OrderState {
+                cl_ord_id: cl_ord_id.to_string(),
+                is_bid,
+                order_qty_atoms,
+                cum_base_atoms: 0,
+                cum_quote_atoms: 0,
+                sequence_number: None,
+                order_index: NIL,
+                is_immediate,
+                status: OrdStatus::PendingNew,
+            },
+        );
+        Ok(instruction)
+    }
+
+    fn translate_new_order_single(
+        &self,
+        message: &FixMessage,
+    ) -> Result<(bool, u64, bool, Instruction), String> {
+        let config: &MarketConfig = &self.config;
+        let symbol: &str = message
+            .require(tags::SYMBOL)
+            .map_err(|err| err.to_string())?;
+        if symbol != config.symbol && symbol != config.market.to_string() {
+            return Err(format!("unknown symbol {}", symbol));
+        }
+        let is_bid: bool = match message.get(tags::SIDE) {
+            Some("1") => true,
+            Some("2") => false,
+            _ => return Err("Side must be 1 (buy) or 2 (sell)".to_string()),
+        };
+        let base_atoms: u64 = message
+            .get(tags::ORDER_QTY)
+            .and_then(|qty| tokens_to_atoms(qty, config.base_decimals))
+            .filter(|atoms| *atoms > 0)
+            .ok_or_else(|| FixError::InvalidField(tags::ORDER_QTY).to_string())?;
+        let price: Option<(u32, i8)> = match message.get(tags::PRICE) {
+            None => None,
+            Some(price) => Some(
+                price_to_mantissa_exponent(price, config.base_decimals, config.quote_decimals)
+                    .ok_or_else(|| FixError::InvalidField(tags::PRICE).to_string())?,
+            ),
+        };
+
+        match message.get(tags::ORD_TYPE) {
+            // Market. Price is optional and caps what is paid or received.
+            Some("1") => {
+                let params: SwapParams = if is_bid {
+                    let max_quote_in: u64 = match price {
+                        Some((mantissa, exponent)) => {
+                            quote_atoms_for_base(base_atoms, mantissa, exponent, true)
+                                .ok_or_else(|| FixError::InvalidField(tags::PRICE).to_string())?
+                        }
+                        None => u64::MAX,
+                    };
+                    SwapParams::new(max_quote_in, base_atoms, false, false)
+                } else {
+                    let min_quote_out: u64 = match price {
+                        Some((mantissa, exponent)) => {
+                            quote_atoms_for_base(base_atoms, mantissa, exponent, false)
+                                .ok_or_else(|| FixError::InvalidField(tags::PRICE).to_string())?
+                        }
+                        None => 0,
+                    };
+                    SwapParams::new(base_atoms, min_quote_out, true, true)
+                };
+                Ok((is_bid, base_atoms, true, self.swap_instruction(params)))
+            }
+            Some("2") => {
+                let (mantissa, exponent) =
+                    price.ok_or_else(|| FixError::MissingField(tags::PRICE).to_string())?;
+                let post_only: bool = message
+                    .get(tags::EXEC_INST)
+                    .is_some_and(|exec_inst| exec_inst.split(' ').any(|inst| inst == "6"));
+                let order_type: OrderType = match (message.get(tags::TIME_IN_FORCE), post_only) {
+                    (None | Some("0") | Some("1"), false) => OrderType::Limit,
+                    (None | Some("0") | Some("1"), true) => OrderType::PostOnly,
+                    (Some("3"), false) => OrderType::ImmediateOrCancel,
+                    _ => return Err("unsupported TimeInForce or ExecInst".to_string()),
+                };
+                let instruction: Instruction = batch_update_instruction(
+                    config.trader,
+                    config.market,
+                    BatchUpdateParams::new().add_order(PlaceOrderParams::new(
+                        base_atoms, mantissa, exponent, is_bid, order_type,
+                    )),
+                );
+                Ok((
+                    is_bid,
+                    base_atoms,
+                    order_type == OrderType::ImmediateOrCancel,
+                    instruction,
+                ))
+            }
+            _ => Err("OrdType must be 1 (market) or 2 (limit)".to_string()),
+        }
+    }
+
+    fn swap_instruction(&self, params: SwapParams) -> Instruction {
+        let config: &MarketConfig = &self.config;
+        swap_instruction(
+            config.trader,
+            config.market,
+            config.trader_base,
+            config.trader_quote,
+            config.base_mint,
+            config.quote_mint,
+            config.base_token_program,
+            (config.quote_token_program != config.base_token_program)
+                .then_some(config.quote_token_program),
+            config.base_token_program == TOKEN_2022_PROGRAM_ID,
+            config.quote_token_program == TOKEN_2022_PROGRAM_ID,
+            params,
+        )
+    }
+
+    /// Translate an OrderCancelRequest into an instruction. On error, the
+    /// returned message is the OrderCancelReject to send back.
+    pub fn handle_order_cancel_request(
+        &mut self,
+        message: &FixMessage,
+    ) -> Result<Instruction, FixMessage> {
+        let cl_ord_id: &str = message.get(tags::CL_ORD_ID).unwrap_or("NONE");
+        let orig_cl_ord_id: &str = message.get(tags::ORIG_CL_ORD_ID).unwrap_or("NONE");
+        let order: &OrderState = match self.orders.get(orig_cl_ord_id) {
+            Some(order) => order,
+            None => {
+                return Err(self.cancel_reject(cl_ord_id, orig_cl_ord_id, None, "unknown order"))
+            }
+        };
+        let sequence_number: u64 = match (order.sequence_number, order.status.is_done()) {
+            (Some(sequence_number), false) => sequence_number,
+            (_, true) => {
+                return Err(self.cancel_reject(
+                    cl_ord_id,
+                    orig_cl_ord_id,
+                    Some(order),
+                    "order is done",
+                ))
+            }
+            (None, false) => {
+                return Err(self.cancel_reject(
+                    cl_ord_id,
+                    orig_cl_ord_id,
+                    Some(order),
+                    "order is not acknowledged yet",
+                ))
+            }
+        };
+        let cancel: CancelOrderParams = match order.order_index {
+            NIL => CancelOrderParams::new(sequence_number),
+            order_index => CancelOrderParams::with_hint(sequence_number, order_index),
+        };
+        Ok(batch_update_instruction(
+            self.config.trader,
+            self.config.market,
+            BatchUpdateParams::new().add_cancel(cancel),
+        ))
+    }
+
+    /// Record the signature of the transaction sent for a request, before it
+    /// is sent, so it is recognized when it shows up on the market.
+    pub fn on_submitted(&mut self, message: &FixMessage, signature: String) {
+        let cl_ord_id: String = message.get(tags::CL_ORD_ID).unwrap_or_default().to_string();
+        let pending: Pending = match message.msg_type() {
+            msg_type::ORDER_CANCEL_REQUEST => Pending::Cancel {
+                cl_ord_id,
+                orig_cl_ord_id: message
+                    .get(tags::ORIG_CL_ORD_ID)
+                    .unwrap_or_default()
+                    .to_string(),
+            },
+            _ => Pending::Order { cl_ord_id },
+        };
+        self.pending.insert(signature, pending);
+    }
+
+    /// The transaction for a request failed or was never confirmed.
+    pub fn on_submit_failed(&mut self, signature: &str, error: &str) -> Vec<FixMessage> {
+        match self.pending.remove(signature) {
+            Some(Pending::Order { cl_ord_id }) => match self.orders.get_mut(&cl_ord_id) {
+                Some(order) => {
+                    order.status = OrdStatus::Rejected;
+                    let order: OrderState = order.clone();
+                    vec![self
+                        .execution_report(&order, exec_type::REJECTED)
+                        .with(tags::TEXT, error)]
+                }
+                None => Vec::new(),
+            },
+            Some(Pending::Cancel {
+                cl_ord_id,
+                orig_cl_ord_id,
+            }) => {
+                let order: Option<&OrderState> = self.orders.get(&orig_cl_ord_id);
+                vec![self.cancel_reject(&cl_ord_id, &orig_cl_ord_id, order, error)]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Process a successful transaction on the market. Returns the
+    /// ExecutionReports it causes, in order.
+    pub fn on_transaction(&mut self, signature: &str, logs: &[String]) -> Vec<FixMessage> {
+        let events: Vec<MarketEvent> = logs
+            .iter()
+            .filter_map(|log| decode_market_event(log))
+            .collect();
+        let mut reports: Vec<FixMessage> = Vec::new();
+        let pending: Option<Pending> = self.pending.remove(signature);
+
+        // Acknowledge before reporting any of the order's own fills.
+        let own_order: Option<String> = match &pending {
+            Some(Pending::Order { cl_ord_id }) if self.orders.contains_key(cl_ord_id) => {
+                self.acknowledge(cl_ord_id, &events);
+                let order: OrderState = self.orders[cl_ord_id].clone();
+                reports.push(self.execution_report(&order, exec_type::NEW));
+                Some(cl_ord_id.clone())
+            }
+            _ => None,
+        };
+
+        for event in events.iter() {
+            match *event {
+                MarketEvent::Fill {
+                    market,
+                    maker,
+                    taker,
+                    price_d18,
+                    base_atoms,
+                    quote_atoms,
+                    maker_sequence_number,
+                    ..
+                } if market == self.config.market => {
+                    if let Some(cl_ord_id) =
+                        own_order.as_ref().filter(|_| taker == self.config.trader)
+                    {
+                        reports.push(self.apply_fill(
+                            cl_ord_id,
+                            price_d18,
+                            base_atoms,
+                            quote_atoms,
+                        ));
+                    }
+                    if maker == self.config.trader {
+                        if let Some(cl_ord_id) = self
+                            .cl_ord_id_by_sequence_number
+                            .get(&maker_sequence_number)
+                            .cloned()
+                        {
+                            reports.push(self.apply_fill(
+                                &cl_ord_id,
+                                price_d18,
+                                base_atoms,
+                                quote_atoms,
+                            ));
+                        }
+                    }
+                }
+                MarketEvent::CancelOrder {
+                    market,
+                    trader,
+                    order_sequence_number,
+                } if market == self.config.market && trader == self.config.trader => {
+                    if let Some(report) = self.apply_cancel(order_sequence_number, &pending) {
+                        reports.push(report);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Whatever an immediate order did not fill in its own transaction is
+        // gone, and so is a limit order that neither filled nor rested.
+        if let Some(cl_ord_id) = own_order {
+            let order: &mut OrderState = self.orders.get_mut(&cl_ord_id).unwrap();
+            let rested: bool = order.order_index != NIL;
+            if order.leaves_atoms() > 0 && (order.is_immediate || !rested) {
+                order.status = OrdStatus::Canceled;
+                let order: OrderState = order.clone();
+                reports.push(self.execution_report(&order, exec_type::CANCELED));
+            }
+        }
+
+        self.forget_done_orders();
+        reports
+    }
+
+    fn acknowledge(&mut self, cl_ord_id: &str, events: &[MarketEvent]) {
+        let config: &MarketConfig = &self.config;
+        let placed: Option<(u64, DataIndex)> = events.iter().find_map(|event| match *event {
+            MarketEvent::PlaceOrder {
+                market,
+                trader,
+                order_sequence_number,
+                order_index,
+            } if market == config.market && trader == config.trader => {
+                Some((order_sequence_number, order_index))
+            }
+            _ => None,
+        });
+        // An order that did not rest still has a sequence number, which
+        // shows up as the taker side of its fills.
+        let taken: Option<u64> = events.iter().find_map(|event| match *event {
+            MarketEvent::Fill {
+                market,
+                taker,
+                taker_sequence_number,
+                ..
+            } if market == config.market && taker == config.trader => Some(taker_sequence_number),
+            _ => None,
+        });
+
+        let order: &mut OrderState = self.orders.get_mut(cl_ord_id).unwrap();
+        order.status = OrdStatus::New;
+        match (placed, taken) {
+            (Some((sequence_number, order_index)), _) => {
+                order.sequence_number = Some(sequence_number);
+                order.order_index = order_index;
+                self.cl_ord_id_by_sequence_number
+                    .insert(sequence_number, cl_ord_id.to_string());
+            }
+            (None, Some(sequence_number)) => order.sequence_number = Some(sequence_number),
+            (None, None) => {}
+        }
+    }
+
+    fn apply_fill(
+        &mut self,
+        cl_ord_id: &str,
+        price_d18: u128,
+        base_atoms: u64,
+        quote_atoms: u64,
+    ) -> FixMessage {
+        let order: &mut OrderState = self.orders.get_mut(cl_ord_id).unwrap();
+        order.cum_base_atoms = order.cum_base_atoms.saturating_add(base_atoms);
+        order.cum_quote_atoms = order.cum_quote_atoms.saturating_add(quote_atoms);
+        order.status = match order.cum_base_atoms >= order.order_qty_atoms {
+            true => OrdStatus::Filled,
+            false => OrdStatus::PartiallyFilled,
+        };
+        let order: OrderState = order.clone();
+        let config: &MarketConfig = &self.config;
+        let last_px: String =
+            price_d18_to_tokens(price_d18, config.base_decimals, config.quote_decimals);
+        let last_qty: String = atoms_to_tokens(base_atoms, config.base_decimals);
+        self.execution_report(&order, exec_type::TRADE)
+            .with(tags::LAST_QTY, last_qty)
+            .with(tags::LAST_PX, last_px)
+    }
+
+    fn apply_cancel(
+        &mut self,
+        sequence_number: u64,
+        pending: &Option<Pending>,
+    ) -> Option<FixMessage> {
+        let orig_cl_ord_id: String = self
+            .cl_ord_id_by_sequence_number
+            .get(&sequence_number)?
+            .clone();
+        let order: &mut OrderState = self.orders.get_mut(&orig_cl_ord_id)?;
+        order.status = OrdStatus::Canceled;
+        let order: OrderState = order.clone();
+        let report: FixMessage = self.execution_report(&order, exec_type::CANCELED);
+        // A cancel from another client of the same wallet is still reported,
+        // just without a ClOrdID of its own.
+        Some(match pending {
+            Some(Pending::Cancel {
+                cl_ord_id,
+                orig_cl_ord_id: requested,
+            }) if *requested == orig_cl_ord_id => report
+                .replace(tags::CL_ORD_ID, cl_ord_id)
+                .with(tags::ORIG_CL_ORD_ID, orig_cl_ord_id),
+            _ => report,
+        })
+    }
+
+    fn forget_done_orders(&mut self) {
+        let orders: &HashMap<String, OrderState> = &self.orders;
+        self.cl_ord_id_by_sequence_number
+            .retain(|_, cl_ord_id| !orders[cl_ord_id.as_str()].status.is_done());
+    }
+
+    fn next_exec_id(&mut self) -> String {
+        self.next_exec_id += 1;
+        self.next_exec_id.to_string()
+    }
+
+    fn execution_report(&mut self, order: &OrderState, exec_type: &str) -> FixMessage {
+        let exec_id: String = self.next_exec_id();
+        let config: &MarketConfig = &self.config;
+        FixMessage::new(msg_type::EXECUTION_REPORT)
+            .with(
+                tags::ORDER_ID,
+                order
+                    .sequence_number
+                    .map_or("NONE".to_string(), |sequence_number| {
+                        sequence_number.to_string()
+                    }),
+            )
+            .with(tags::CL_ORD_ID, &order.cl_ord_id)
+            .with(tags::EXEC_ID, exec_id)
+            .with(tags::EXEC_TYPE, exec_type)
+            .with(tags::ORD_STATUS, order.status.as_fix())
+            .with(tags::SYMBOL, &config.symbol)
+            .with(tags::SIDE, if order.is_bid { "1" } else { "2" })
+            .with(
+                tags::ORDER_QTY,
+                atoms_to_tokens(order.order_qty_atoms, config.base_decimals),
+            )
+            .with(
+                tags::LEAVES_QTY,
+                atoms_to_tokens(order.leaves_atoms(), config.base_decimals),
+            )
+            .with(
+                tags::CUM_QTY,
+                atoms_to_tokens(order.cum_base_atoms, config.base_decimals),
+            )
+            .with(
+                tags::AVG_PX,
+                average_price(
+                    order.cum_base_atoms,
+                    order.cum_quote_atoms,
+                    config.base_decimals,
+                    config.quote_decimals,
+                ),
+            )
+    }
+
+    /// Reject for a NewOrderSingle that never became an order.
+    fn reject_unknown(&mut self, message: &FixMessage, reason: &str) -> FixMessage {
+        let exec_id: String = self.next_exec_id();
+        FixMessage::new(msg_type::EXECUTION_REPORT)
+            .with(tags::ORDER_ID, "NONE")
+            .with(
+                tags::CL_ORD_ID,
+                message.get(tags::CL_ORD_ID).unwrap_or("NONE"),
+            )
+            .with(tags::EXEC_ID, exec_id)
+            .with(tags::EXEC_TYPE, exec_type::REJECTED)
+            .with(tags::ORD_STATUS, OrdStatus::Rejected.as_fix())
+            .with(
+                tags::SYMBOL,
+                message.get(tags::SYMBOL).unwrap_or(&self.config.symbol),
+            )
+            .with(tags::SIDE, message.get(tags::SIDE).unwrap_or("1"))
+            .with(tags::LEAVES_QTY, 0)
+            .with(tags::CUM_QTY, 0)
+            .with(tags::AVG_PX, 0)
+            .with(tags::TEXT, reason)
+    }
+
+    fn cancel_reject(
+        &self,
+        cl_ord_id: &str,
+        orig_cl_ord_id: &str,
+        order: Option<&OrderState>,
+        reason: &str,
+    ) -> FixMessage {
+        FixMessage::new(msg_type::ORDER_CANCEL_REJECT)
+            .with(
+                tags::ORDER_ID,
+                order
+                    .and_then(|order| order.sequence_number)
+                    .map_or("NONE".to_string(), |sequence_number| {
+                        sequence_number.to_string()
+                    }),
+            )
+            .with(tags::CL_ORD_ID, cl_ord_id)
+            .with(tags::ORIG_CL_ORD_ID, orig_cl_ord_id)
+            .with(
+                tags::ORD_STATUS,
+                order
+                    .map_or(OrdStatus::Rejected, |order| order.status)
+                    .as_fix(),
+            )
+            // Response to an OrderCancelRequest.
+            .with(tags::CXL_REJ_RESPONSE_TO, 1)
+            .with(tags::TEXT, reason)
+    }
+}
@@ -0,0 +1,12 @@
+//! FIX 4.4 gateway for Manifest.
+//!
+//! Translates NewOrderSingle and OrderCancelRequest into batch_update and
+//! swap transactions, and fill and cancel logs back into ExecutionReports.
+//! The `manifest-fix` binary runs this behind a FIX acceptor.
+
+pub mod convert;
+pub mod gateway;
+pub mod message;
+
+#[cfg(test)]
+mod tests;
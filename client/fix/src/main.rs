@@ -0,0 +1,490 @@
+//! manifest-fix: a FIX 4.4 acceptor that trades one wallet on one Manifest
+//! market. See the README for the supported messages and flags.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{anyhow, Context, Result};
+use manifest_client::{Instruction, MarketFixed};
+use manifest_fix::{
+    gateway::{Gateway, MarketConfig},
+    message::{frame_length, msg_type, tags, FixMessage},
+};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use solana_transaction_status_client_types::UiTransactionEncoding;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+};
+
+const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+const DEFAULT_HEART_BT_INT: u64 = 30;
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+struct Args {
+    rpc_url: String,
+    listen: String,
+    keypair: String,
+    market: Pubkey,
+    symbol: Option<String>,
+    sender_comp_id: String,
+    target_comp_id: String,
+    poll_interval: Duration,
+}
+
+impl Args {
+    fn parse() -> Result<Self> {
+        let mut flags: HashMap<String, String> = HashMap::new();
+        let mut args = std::env::args().skip(1);
+        while let Some(flag) = args.next() {
+            let name: &str = flag
+                .strip_prefix("--")
+                .ok_or_else(|| anyhow!("unexpected argument {}", flag))?;
+            let value: String = args
+                .next()
+                .ok_or_else(|| anyhow!("missing value for {}", flag))?;
+            flags.insert(name.to_string(), value);
+        }
+        let mut take = |name: &str, default: Option<&str>| -> Result<String> {
+            flags
+                .remove(name)
+                .or(default.map(str::to_string))
+                .ok_or_else(|| anyhow!("--{} is required", name))
+        };
+        let args: Args = Args {
+            rpc_url: take("rpc-url", Some("http://127.0.0.1:8899"))?,
+            listen: take("listen", Some("0.0.0.0:9878"))?,
+            keypair: take("keypair", None)?,
+            market: take("market", None)?.parse().context("invalid --market")?,
+            symbol: take("symbol", None).ok(),
+            sender_comp_id: take("sender-comp-id", Some("MANIFEST"))?,
+            target_comp_id: take("target-comp-id", Some("CLIENT"))?,
+            poll_interval: Duration::from_millis(
+                take("poll-ms", Some("500"))?
+                    .parse()
+                    .context("invalid --poll-ms")?,
+            ),
+        };
+        if let Some(unknown) = flags.keys().next() {
+            return Err(anyhow!("unknown flag --{}", unknown));
+        }
+        Ok(args)
+    }
+}
+
+/// Everything that changes gateway state arrives on one channel so it is
+/// applied in order. In particular a signature is always registered before
+/// the poller can see its transaction.
+enum Event {
+    Submitted {
+        message: FixMessage,
+        signature: String,
+    },
+    SubmitFailed {
+        signature: String,
+        error: String,
+    },
+    Transaction {
+        signature: String,
+        logs: Vec<String>,
+    },
+}
+
+fn get_associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+async fn load_market_config(rpc: &RpcClient, args: &Args, trader: Pubkey) -> Result<MarketConfig> {
+    let market_data: Vec<u8> = rpc
+        .get_account_data(&args.market)
+        .await
+        .context("fetching market")?;
+    let fixed: &MarketFixed =
+        MarketFixed::try_from_bytes(&market_data).ok_or_else(|| anyhow!("not a market"))?;
+    let base_mint: Pubkey = fixed.get_base_mint();
+    let quote_mint: Pubkey = fixed.get_quote_mint();
+    let base_token_program: Pubkey = rpc.get_account(&base_mint).await?.owner;
+    let quote_token_program: Pubkey = rpc.get_account(&quote_mint).await?.owner;
+    Ok(MarketConfig {
+        symbol: args
+            .symbol
+            .clone()
+            .unwrap_or_else(|| args.market.to_string()),
+        market: args.market,
+        trader,
+        base_mint,
+        quote_mint,
+        base_decimals: fixed.base_mint_decimals,
+        quote_decimals: fixed.quote_mint_decimals,
+        base_token_program,
+        quote_token_program,
+        trader_base: get_associated_token_address(&trader, &base_mint, &base_token_program),
+        trader_quote: get_associated_token_address(&trader, &quote_mint, &quote_token_program),
+    })
+}
+
+/// Feed every successful market transaction to the gateway, oldest first.
+/// Starts from the latest transaction at startup, history is not replayed.
+async fn poll_market(
+    rpc: Arc<RpcClient>,
+    market: Pubkey,
+    poll_interval: Duration,
+    events: UnboundedSender<Event>,
+) {
+    let mut last_seen: Option<Signature> = None;
+    let mut started: bool = false;
+    loop {
+        let config: GetConfirmedSignaturesForAddress2Config =
+            GetConfirmedSignaturesForAddress2Config {
+                until: last_seen,
+                limit: Some(if started { 1_000 } else { 1 }),
+                commitment: Some(CommitmentConfig::confirmed()),
+                ..Default::default()
+            };
+        let statuses = match rpc
+            .get_signatures_for_address_with_config(&market, config)
+            .await
+        {
+            Ok(statuses) => statuses,
+            Err(err) => {
+                eprintln!("fetching market signatures: {}", err);
+                tokio::time::sleep(poll_interval).await;
+                continue;
+            }
+        };
+        if let Some(newest) = statuses.first() {
+            last_seen = newest.signature.parse().ok();
+        }
+        if started {
+            for status in statuses.iter().rev().filter(|status| status.err.is_none()) {
+                let signature: Signature = match status.signature.parse() {
+                    Ok(signature) => signature,
+                    Err(_) => continue,
+                };
+                match fetch_logs(&rpc, &signature).await {
+                    Ok(logs) => {
+                        let _ = events.send(Event::Transaction {
+                            signature: status.signature.clone(),
+                            logs,
+                        });
+                    }
+                    Err(err) => eprintln!("fetching transaction {}: {}", signature, err),
+                }
+            }
+        }
+        started = true;
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+async fn fetch_logs(rpc: &RpcClient, signature: &Signature) -> Result<Vec<String>> {
+    let transaction = rpc
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+    let meta = transaction
+        .transaction
+        .meta
+        .ok_or_else(|| anyhow!("transaction without meta"))?;
+    Ok(Option::<Vec<String>>::from(meta.log_messages).unwrap_or_default())
+}
+
+/// Sign and send one instruction. The signature is registered with the
+/// gateway before sending so that fills can be matched to the order.
+async fn submit(
+    rpc: Arc<RpcClient>,
+    payer: Arc<Keypair>,
+    instruction: Instruction,
+    message: FixMessage,
+    placeholder_signature: String,
+    events: UnboundedSender<Event>,
+) {
+    let transaction: Transaction = match rpc.get_latest_blockhash().await {
+        Ok(blockhash) => Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer.as_ref()],
+            blockhash,
+        ),
+        Err(err) => {
+            let _ = events.send(Event::Submitted {
+                message,
+                signature: placeholder_signature.clone(),
+            });
+            let _ = events.send(Event::SubmitFailed {
+                signature: placeholder_signature,
+                error: err.to_string(),
+            });
+            return;
+        }
+    };
+    let signature: String = transaction.signatures[0].to_string();
+    let _ = events.send(Event::Submitted {
+        message,
+        signature: signature.clone(),
+    });
+    if let Err(err) = rpc.send_and_confirm_transaction(&transaction).await {
+        let _ = events.send(Event::SubmitFailed {
+            signature,
+            error: err.to_string(),
+        });
+    }
+}
+
+fn apply_event(gateway: &mut Gateway, event: Event) -> Vec<FixMessage> {
+    match event {
+        Event::Submitted { message, signature } => {
+            gateway.on_submitted(&message, signature);
+            Vec::new()
+        }
+        Event::SubmitFailed { signature, error } => gateway.on_submit_failed(&signature, &error),
+        Event::Transaction { signature, logs } => gateway.on_transaction(&signature, &logs),
+    }
+}
+
+struct Session<'a> {
+    stream: TcpStream,
+    args: &'a Args,
+    outgoing_seq_num: u64,
+    incoming_seq_num: u64,
+}
+
+impl Session<'_> {
+    async fn send(&mut self, message: &FixMessage) -> Result<()> {
+        let encoded: Vec<u8> = message.encode(
+            &self.args.sender_comp_id,
+            &self.args.target_comp_id,
+            self.outgoing_seq_num,
+            SystemTime::now(),
+        );
+        self.outgoing_seq_num += 1;
+        self.stream.write_all(&encoded).await?;
+        Ok(())
+    }
+
+    /// Read until there is a full message.
+    async fn read_message(&mut self, buffer: &mut Vec<u8>) -> Result<Option<FixMessage>> {
+        loop {
+            if let Some(length) = frame_length(buffer)? {
+                let message: FixMessage = FixMessage::decode(&buffer[..length])?;
+                buffer.drain(..length);
+                return Ok(Some(message));
+            }
+            if buffer.len() > MAX_MESSAGE_SIZE {
+                return Err(anyhow!("message too large"));
+            }
+            let mut chunk: [u8; 4096] = [0; 4096];
+            let read: usize = self.stream.read(&mut chunk).await?;
+            if read == 0 {
+                return Ok(None);
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+
+    /// Sequence numbers reset on every logon and resends are not supported, so
+    /// anything but the next number ends the session.
+    fn check_seq_num(&mut self, message: &FixMessage) -> Result<()> {
+        let seq_num: u64 = message
+            .get_seq_num()
+            .ok_or_else(|| anyhow!("missing MsgSeqNum"))?;
+        if seq_num != self.incoming_seq_num {
+            return Err(anyhow!(
+                "expected MsgSeqNum {} got {}",
+                self.incoming_seq_num,
+                seq_num
+            ));
+        }
+        self.incoming_seq_num += 1;
+        Ok(())
+    }
+}
+
+async fn run_session(
+    stream: TcpStream,
+    args: &Args,
+    rpc: &Arc<RpcClient>,
+    payer: &Arc<Keypair>,
+    gateway: &mut Gateway,
+    events_sender: &UnboundedSender<Event>,
+    events: &mut UnboundedReceiver<Event>,
+) -> Result<()> {
+    let mut session: Session = Session {
+        stream,
+        args,
+        outgoing_seq_num: 1,
+        incoming_seq_num: 1,
+    };
+    let mut buffer: Vec<u8> = Vec::new();
+
+    let logon: FixMessage = session
+        .read_message(&mut buffer)
+        .await?
+        .ok_or_else(|| anyhow!("closed before logon"))?;
+    if logon.msg_type() != msg_type::LOGON {
+        return Err(anyhow!("first message was not a Logon"));
+    }
+    if logon.get(tags::SENDER_COMP_ID) != Some(args.target_comp_id.as_str()) {
+        return Err(anyhow!("unexpected SenderCompID"));
+    }
+    session.check_seq_num(&logon)?;
+    let heart_bt_int: u64 = logon
+        .get(tags::HEART_BT_INT)
+        .and_then(|value| value.parse().ok())
+        .filter(|interval| *interval > 0)
+        .unwrap_or(DEFAULT_HEART_BT_INT);
+    session
+        .send(
+            &FixMessage::new(msg_type::LOGON)
+                .with(tags::ENCRYPT_METHOD, 0)
+                .with(tags::HEART_BT_INT, heart_bt_int)
+                .with(tags::RESET_SEQ_NUM_FLAG, "Y"),
+        )
+        .await?;
+
+    let mut heartbeat = tokio::time::interval(Duration::from_secs(heart_bt_int));
+    // The first tick is immediate and the Logon already told them we are here.
+    heartbeat.tick().await;
+    let mut placeholder_count: u64 = 0;
+    loop {
+        tokio::select! {
+            message = session.read_message(&mut buffer) => {
+                let message: FixMessage = match message? {
+                    Some(message) => message,
+                    None => return Ok(()),
+                };
+                if let Err(err) = session.check_seq_num(&message) {
+                    let logout: FixMessage =
+                        FixMessage::new(msg_type::LOGOUT).with(tags::TEXT, err.to_string());
+                    session.send(&logout).await?;
+                    return Err(err);
+                }
+                let instruction: Result<Instruction, FixMessage> = match message.msg_type() {
+                    msg_type::HEARTBEAT => continue,
+                    msg_type::TEST_REQUEST => {
+                        let mut heartbeat: FixMessage = FixMessage::new(msg_type::HEARTBEAT);
+                        if let Some(test_req_id) = message.get(tags::TEST_REQ_ID) {
+                            heartbeat = heartbeat.with(tags::TEST_REQ_ID, test_req_id);
+                        }
+                        session.send(&heartbeat).await?;
+                        continue;
+                    }
+                    msg_type::LOGOUT => {
+                        session.send(&FixMessage::new(msg_type::LOGOUT)).await?;
+                        return Ok(());
+                    }
+                    msg_type::NEW_ORDER_SINGLE => gateway.handle_new_order_single(&message),
+                    msg_type::ORDER_CANCEL_REQUEST => gateway.handle_order_cancel_request(&message),
+                    other => {
+                        let reject: FixMessage = FixMessage::new(msg_type::REJECT)
+                            .with(tags::REF_SEQ_NUM, message.get_seq_num().unwrap_or_default())
+                            .with(tags::TEXT, format!("unsupported MsgType {}", other));
+                        session.send(&reject).await?;
+                        continue;
+                    }
+                };
+                match instruction {
+                    Ok(instruction) => {
+                        placeholder_count += 1;
+                        tokio::spawn(submit(
+                            Arc::clone(rpc),
+                            Arc::clone(payer),
+                            instruction,
+                            message,
+                            format!("unsent-{}", placeholder_count),
+                            events_sender.clone(),
+                        ));
+                    }
+                    Err(reject) => session.send(&reject).await?,
+                }
+            }
+            Some(event) = events.recv() => {
+                for report in apply_event(gateway, event) {
+                    session.send(&report).await?;
+                }
+            }
+            _ = heartbeat.tick() => {
+                session.send(&FixMessage::new(msg_type::HEARTBEAT)).await?;
+            }
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Args = Args::parse()?;
+    let payer: Arc<Keypair> = Arc::new(
+        read_keypair_file(&args.keypair).map_err(|err| anyhow!("reading keypair: {}", err))?,
+    );
+    let rpc: Arc<RpcClient> = Arc::new(RpcClient::new_with_commitment(
+        args.rpc_url.clone(),
+        CommitmentConfig::confirmed(),
+    ));
+    let mut gateway: Gateway = Gateway::new(load_market_config(&rpc, &args, payer.pubkey()).await?);
+
+    let (events_sender, mut events) = unbounded_channel::<Event>();
+    tokio::spawn(poll_market(
+        Arc::clone(&rpc),
+        args.market,
+        args.poll_interval,
+        events_sender.clone(),
+    ));
+
+    let listener: TcpListener = TcpListener::bind(&args.listen).await?;
+    eprintln!(
+        "manifest-fix listening on {} for {} as {}",
+        args.listen,
+        gateway.config().symbol,
+        payer.pubkey()
+    );
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, peer) = accepted?;
+                eprintln!("session from {}", peer);
+                if let Err(err) = run_session(
+                    stream,
+                    &args,
+                    &rpc,
+                    &payer,
+                    &mut gateway,
+                    &events_sender,
+                    &mut events,
+                )
+                .await
+                {
+                    eprintln!("session from {} ended: {}", peer, err);
+                }
+            }
+            // Keep order state current between sessions. Reports produced
+            // while no one is connected are dropped.
+            Some(event) = events.recv() => {
+                apply_event(&mut gateway, event);
+            }
+        }
+    }
+}
@@ -0,0 +1,361 @@
+//! FIX 4.4 tag=value encoding.
+//!
+//! Only what the gateway needs: framing a byte stream into messages, checking
+//! BodyLength and CheckSum, and writing messages with the standard header.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SOH: u8 = 0x01;
+pub const BEGIN_STRING: &str = "FIX.4.4";
+
+pub mod tags {
+    pub const AVG_PX: u32 = 6;
+    pub const BEGIN_STRING: u32 = 8;
+    pub const BODY_LENGTH: u32 = 9;
+    pub const CHECK_SUM: u32 = 10;
+    pub const CL_ORD_ID: u32 = 11;
+    pub const CUM_QTY: u32 = 14;
+    pub const EXEC_ID: u32 = 17;
+    pub const EXEC_INST: u32 = 18;
+    pub const LAST_PX: u32 = 31;
+    pub const LAST_QTY: u32 = 32;
+    pub const MSG_SEQ_NUM: u32 = 34;
+    pub const MSG_TYPE: u32 = 35;
+    pub const ORDER_ID: u32 = 37;
+    pub const ORDER_QTY: u32 = 38;
+    pub const ORD_STATUS: u32 = 39;
+    pub const ORD_TYPE: u32 = 40;
+    pub const ORIG_CL_ORD_ID: u32 = 41;
+    pub const PRICE: u32 = 44;
+    pub const REF_SEQ_NUM: u32 = 45;
+    pub const SENDER_COMP_ID: u32 = 49;
+    pub const SENDING_TIME: u32 = 52;
+    pub const SIDE: u32 = 54;
+    pub const SYMBOL: u32 = 55;
+    pub const TARGET_COMP_ID: u32 = 56;
+    pub const TEXT: u32 = 58;
+    pub const TIME_IN_FORCE: u32 = 59;
+    pub const ENCRYPT_METHOD: u32 = 98;
+    pub const ORD_REJ_REASON: u32 = 103;
+    pub const HEART_BT_INT: u32 = 108;
+    pub const TEST_REQ_ID: u32 = 112;
+    pub const RESET_SEQ_NUM_FLAG: u32 = 141;
+    pub const EXEC_TYPE: u32 = 150;
+    pub const LEAVES_QTY: u32 = 151;
+    pub const CXL_REJ_RESPONSE_TO: u32 = 434;
+}
+
+pub mod msg_type {
+    pub const HEARTBEAT: &str = "0";
+    pub const TEST_REQUEST: &str = "1";
+    pub const REJECT: &str = "3";
+    pub const LOGOUT: &str = "5";
+    pub const EXECUTION_REPORT: &str = "8";
+    pub const ORDER_CANCEL_REJECT: &str = "9";
+    pub const LOGON: &str = "A";
+    pub const NEW_ORDER_SINGLE: &str = "D";
+    pub const ORDER_CANCEL_REQUEST: &str = "F";
+}
+
+/// Tags written by [`FixMessage::encode`] and skipped if set on the message.
+const HEADER_TAGS: [u32; 8] = [
+    tags::BEGIN_STRING,
+    tags::BODY_LENGTH,
+    tags::MSG_TYPE,
+    tags::SENDER_COMP_ID,
+    tags::TARGET_COMP_ID,
+    tags::MSG_SEQ_NUM,
+    tags::SENDING_TIME,
+    tags::CHECK_SUM,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixError {
+    Malformed(&'static str),
+    BadBodyLength,
+    BadCheckSum,
+    MissingField(u32),
+    InvalidField(u32),
+}
+
+impl std::fmt::Display for FixError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixError::Malformed(reason) => write!(f, "malformed message: {}", reason),
+            FixError::BadBodyLength => write!(f, "BodyLength does not match"),
+            FixError::BadCheckSum => write!(f, "CheckSum does not match"),
+            FixError::MissingField(tag) => write!(f, "missing required tag {}", tag),
+            FixError::InvalidField(tag) => write!(f, "invalid value for tag {}", tag),
+        }
+    }
+}
+
+impl std::error::Error for FixError {}
+
+/// A FIX message. BeginString, BodyLength and CheckSum are handled by
+/// encode and decode and are not kept in the fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixMessage {
+    msg_type: String,
+    fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    pub fn new(msg_type: &str) -> Self {
+        FixMessage {
+            msg_type: msg_type.to_string(),
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, tag: u32, value: impl ToString) -> Self {
+        self.fields.push((tag, value.to_string()));
+        self
+    }
+
+    /// Set the first value for the tag, or add it if it is not there.
+    pub fn replace(mut self, tag: u32, value: impl ToString) -> Self {
+        match self
+            .fields
+            .iter_mut()
+            .find(|(field_tag, _)| *field_tag == tag)
+        {
+            Some((_, field_value)) => *field_value = value.to_string(),
+            None => self.fields.push((tag, value.to_string())),
+        }
+        self
+    }
+
+    pub fn msg_type(&self) -> &str {
+        &self.msg_type
+    }
+
+    /// First value for the tag.
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field_tag, _)| *field_tag == tag)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn require(&self, tag: u32) -> Result<&str, FixError> {
+        self.get(tag).ok_or(FixError::MissingField(tag))
+    }
+
+    pub fn get_seq_num(&self) -> Option<u64> {
+        self.get(tags::MSG_SEQ_NUM)?.parse().ok()
+    }
+
+    /// Write the message with the standard header and trailer.
+    pub fn encode(
+        &self,
+        sender_comp_id: &str,
+        target_comp_id: &str,
+        msg_seq_num: u64,
+        sending_time: SystemTime,
+    ) -> Vec<u8> {
+        let mut body: Vec<u8> = Vec::with_capacity(256);
+        push_field(&mut body, tags::MSG_TYPE, &self.msg_type);
+        push_field(&mut body, tags::SENDER_COMP_ID, sender_comp_id);
+        push_field(&mut body, tags::TARGET_COMP_ID, target_comp_id);
+        push_field(&mut body, tags::MSG_SEQ_NUM, &msg_seq_num.to_string());
+        push_field(
+            &mut body,
+            tags::SENDING_TIME,
+            &format_utc_timestamp(sending_time),
+        );
+        for (tag, value) in self.fields.iter() {
+            if !HEADER_TAGS.contains(tag) {
+                push_field(&mut body, *tag, value);
+            }
+        }
+
+        let mut message: Vec<u8> = Vec::with_capacity(body.len() + 32);
+        push_field(&mut message, tags::BEGIN_STRING, BEGIN_STRING);
+        push_field(&mut message, tags::BODY_LENGTH, &body.len().to_string());
+        message.extend_from_slice(&body);
+        let check_sum: u8 = check_sum(&message);
+        push_field(&mut message, tags::CHECK_SUM, &format!("{:03}", check_sum));
+        message
+    }
+
+    /// Parse one complete message, as framed by [`frame_length`].
+    pub fn decode(raw: &[u8]) -> Result<Self, FixError> {
+        let trailer_start: usize = raw
+            .len()
+            .checked_sub(7)
+            .filter(|start| raw[*start..].starts_with(b"10="))
+            .ok_or(FixError::Malformed("missing CheckSum"))?;
+        let expected_check_sum: u8 = std::str::from_utf8(&raw[trailer_start + 3..raw.len() - 1])
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .ok_or(FixError::InvalidField(tags::CHECK_SUM))?;
+        if check_sum(&raw[..trailer_start]) != expected_check_sum {
+            return Err(FixError::BadCheckSum);
+        }
+
+        let (body_start, body_length) = parse_prefix(raw)?.ok_or(FixError::Malformed("short"))?;
+        if body_start + body_length != trailer_start {
+            return Err(FixError::BadBodyLength);
+        }
+
+        let body: &str = std::str::from_utf8(&raw[body_start..trailer_start])
+            .map_err(|_| FixError::Malformed("not utf8"))?;
+        let mut fields: Vec<(u32, String)> = Vec::new();
+        for field in body.split(SOH as char).filter(|field| !field.is_empty()) {
+            let (tag, value) = field
+                .split_once('=')
+                .ok_or(FixError::Malformed("field without ="))?;
+            let tag: u32 = tag.parse().map_err(|_| FixError::Malformed("bad tag"))?;
+            fields.push((tag, value.to_string()));
+        }
+        match fields.first() {
+            Some((tags::MSG_TYPE, _)) => {}
+            _ => return Err(FixError::MissingField(tags::MSG_TYPE)),
+        }
+        let (_, msg_type) = fields.remove(0);
+        Ok(FixMessage { msg_type, fields })
+    }
+}
+
+/// Length of the first complete message in the buffer, or None if more bytes
+/// are needed.
+pub fn frame_length(buffer: &[u8]) -> Result<Option<usize>, FixError> {
+    Ok(parse_prefix(buffer)?
+        .map(|(body_start, body_length)| body_start + body_length + 7)
+        .filter(|length| *length <= buffer.len()))
+}
+
+/// Returns where the body starts and how long it is, from the BeginString and
+/// BodyLength fields that every message starts with.
+fn parse_prefix(buffer: &[u8]) -> Result<Option<(usize, usize)>, FixError> {
+    let begin: &[u8] = b"8=FIX.4.4\x019=";
+    if buffer.len() < begin.len() {
+        return match begin.starts_with(buffer) {
+            true => Ok(None),
+            false => Err(FixError::Malformed("expected BeginString FIX.4.4")),
+        };
+    }
+    if !buffer.starts_with(begin) {
+        return Err(FixError::Malformed("expected BeginString FIX.4.4"));
+    }
+    let length_end: usize = match buffer[begin.len()..].iter().position(|b| *b == SOH) {
+        Some(position) => begin.len() + position,
+        // BodyLength is at most a few digits, anything longer is garbage.
+        None if buffer.len() - begin.len() > 8 => {
+            return Err(FixError::InvalidField(tags::BODY_LENGTH))
+        }
+        None => return Ok(None),
+    };
+    let body_length: usize = std::str::from_utf8(&buffer[begin.len()..length_end])
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .ok_or(FixError::InvalidField(tags::BODY_LENGTH))?;
+    Ok(Some((length_end + 1, body_length)))
+}
+
+fn push_field(buffer: &mut Vec<u8>, tag: u32, value: &str) {
+    buffer.extend_from_slice(tag.to_string().as_bytes());
+    buffer.push(b'=');
+    buffer.extend_from_slice(value.as_bytes());
+    buffer.push(SOH);
+}
+
+fn check_sum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+/// UTCTimestamp with milliseconds, e.g. 20240102-03:04:05.678.
+pub fn format_utc_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds: u64 = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let seconds_of_day: u64 = seconds % 86_400;
+    format!(
+        "{:04}{:02}{:02}-{:02}:{:02}:{:02}.{:03}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3_600,
+        seconds_of_day % 3_600 / 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_millis(),
+    )
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian date. From Howard Hinnant's
+// date algorithms.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z: i64 = days + 719_468;
+    let era: i64 = z.div_euclid(146_097);
+    let day_of_era: i64 = z.rem_euclid(146_097);
+    let year_of_era: i64 =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index: i64 = (5 * day_of_year + 2) / 153;
+    let day: u32 = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month: u32 = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year: i64 = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let message: FixMessage = FixMessage::new(msg_type::NEW_ORDER_SINGLE)
+            .with(tags::CL_ORD_ID, "order-1")
+            .with(tags::SIDE, 1)
+            .with(tags::ORDER_QTY, "1.5");
+        let encoded: Vec<u8> = message.encode(
+            "MANIFEST",
+            "CLIENT",
+            7,
+            UNIX_EPOCH + Duration::from_millis(1_704_164_645_678),
+        );
+        assert_eq!(frame_length(&encoded), Ok(Some(encoded.len())));
+        assert_eq!(frame_length(&encoded[..encoded.len() - 1]), Ok(None));
+
+        let decoded: FixMessage = FixMessage::decode(&encoded).unwrap();
+        assert_eq!(decoded.msg_type(), msg_type::NEW_ORDER_SINGLE);
+        assert_eq!(decoded.get(tags::CL_ORD_ID), Some("order-1"));
+        assert_eq!(decoded.get(tags::ORDER_QTY), Some("1.5"));
+        assert_eq!(decoded.get(tags::SENDER_COMP_ID), Some("MANIFEST"));
+        assert_eq!(decoded.get_seq_num(), Some(7));
+        assert_eq!(
+            decoded.get(tags::SENDING_TIME),
+            Some("20240102-03:04:05.678")
+        );
+    }
+
+    #[test]
+    fn test_decode_known_message() {
+        // Built by hand instead of with encode.
+        let body: &str = "35=0\x0149=A\x0156=B\x0134=1\x0152=20240102-03:04:05\x01";
+        let mut raw: Vec<u8> = format!("8=FIX.4.4\x019={}\x01{}", body.len(), body).into_bytes();
+        let sum: u8 = raw.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+        raw.extend_from_slice(format!("10={:03}\x01", sum).as_bytes());
+        assert_eq!(
+            FixMessage::decode(&raw).unwrap().msg_type(),
+            msg_type::HEARTBEAT
+        );
+
+        let last_digit: usize = raw.len() - 2;
+        raw[last_digit] = if raw[last_digit] == b'0' { b'1' } else { b'0' };
+        assert_eq!(FixMessage::decode(&raw), Err(FixError::BadCheckSum));
+    }
+
+    #[test]
+    fn test_frame_length_rejects_garbage() {
+        assert_eq!(frame_length(b"8=FIX"), Ok(None));
+        assert!(frame_length(b"8=FIX.4.2\x019=5\x01").is_err());
+        assert!(frame_length(b"8=FIX.4.4\x019=abc\x01").is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use manifest_client::{
+    ManifestInstruction, Pubkey, CANCEL_ORDER_LOG_DISCRIMINANT, FILL_LOG_DISCRIMINANT,
+    PLACE_ORDER_LOG_DISCRIMINANT, TOKEN_PROGRAM_ID,
+};
+
+use crate::{
+    gateway::{decode_market_event, Gateway, MarketConfig, MarketEvent, OrdStatus},
+    message::{msg_type, tags, FixMessage},
+};
+
+fn config() -> MarketConfig {
+    MarketConfig {
+        symbol: "SOL/USDC".to_string(),
+        market: Pubkey::new_unique(),
+        trader: Pubkey::new_unique(),
+        base_mint: Pubkey::new_unique(),
+        quote_mint: Pubkey::new_unique(),
+        base_decimals: 9,
+        quote_decimals: 6,
+        base_token_program: TOKEN_PROGRAM_ID,
+        quote_token_program: TOKEN_PROGRAM_ID,
+        trader_base: Pubkey::new_unique(),
+        trader_quote: Pubkey::new_unique(),
+    }
+}
+
+fn program_data(discriminant: [u8; 8], payload: &[u8]) -> String {
+    let mut data: Vec<u8> = discriminant.to_vec();
+    data.extend_from_slice(payload);
+    format!("Program data: {}", STANDARD.encode(data))
+}
+
+fn place_order_log(config: &MarketConfig, sequence_number: u64, order_index: u32) -> String {
+    let mut payload: Vec<u8> = vec![0; 112];
+    payload[0..32].copy_from_slice(config.market.as_ref());
+    payload[32..64].copy_from_slice(config.trader.as_ref());
+    payload[88..96].copy_from_slice(&sequence_number.to_le_bytes());
+    payload[96..100].copy_from_slice(&order_index.to_le_bytes());
+    program_data(PLACE_ORDER_LOG_DISCRIMINANT, &payload)
+}
+
+fn fill_log(
+    config: &MarketConfig,
+    maker: Pubkey,
+    taker: Pubkey,
+    base_atoms: u64,
+    quote_atoms: u64,
+    maker_sequence_number: u64,
+    taker_sequence_number: u64,
+) -> String {
+    let mut payload: Vec<u8> = vec![0; 224];
+    payload[0..32].copy_from_slice(config.market.as_ref());
+    payload[32..64].copy_from_slice(maker.as_ref());
+    payload[64..96].copy_from_slice(taker.as_ref());
+    // 0.15025 quote atoms per base atom, 150.25 USDC per SOL.
+    payload[160..168].copy_from_slice(&150_250_000_000_000_000u64.to_le_bytes());
+    payload[176..184].copy_from_slice(&base_atoms.to_le_bytes());
+    payload[184..192].copy_from_slice(&quote_atoms.to_le_bytes());
+    payload[192..200].copy_from_slice(&maker_sequence_number.to_le_bytes());
+    payload[200..208].copy_from_slice(&taker_sequence_number.to_le_bytes());
+    program_data(FILL_LOG_DISCRIMINANT, &payload)
+}
+
+fn cancel_order_log(config: &MarketConfig, sequence_number: u64) -> String {
+    let mut payload: Vec<u8> = vec![0; 72];
+    payload[0..32].copy_from_slice(config.market.as_ref());
+    payload[32..64].copy_from_slice(config.trader.as_ref());
+    payload[64..72].copy_from_slice(&sequence_number.to_le_bytes());
+    program_data(CANCEL_ORDER_LOG_DISCRIMINANT, &payload)
+}
+
+fn new_order_single(cl_ord_id: &str, side: &str, ord_type: &str, qty: &str) -> FixMessage {
+    FixMessage::new(msg_type::NEW_ORDER_SINGLE)
+        .with(tags::CL_ORD_ID, cl_ord_id)
+        .with(tags::SYMBOL, "SOL/USDC")
+        .with(tags::SIDE, side)
+        .with(tags::ORD_TYPE, ord_type)
+        .with(tags::ORDER_QTY, qty)
+}
+
+fn summary(report: &FixMessage) -> (&str, &str, &str, &str) {
+    (
+        report.get(tags::EXEC_TYPE).unwrap(),
+        report.get(tags::ORD_STATUS).unwrap(),
+        report.get(tags::CUM_QTY).unwrap(),
+        report.get(tags::LEAVES_QTY).unwrap(),
+    )
+}
+
+#[test]
+fn test_decode_market_event() {
+    let config: MarketConfig = config();
+    assert_eq!(
+        decode_market_event(&place_order_log(&config, 5, 80)),
+        Some(MarketEvent::PlaceOrder {
+            market: config.market,
+            trader: config.trader,
+            order_sequence_number: 5,
+            order_index: 80,
+        })
+    );
+    assert_eq!(decode_market_event("Program log: hello"), None);
+    assert_eq!(decode_market_event("Program data: AAAA"), None);
+}
+
+#[test]
+fn test_limit_order_lifecycle() {
+    let config: MarketConfig = config();
+    let mut gateway: Gateway = Gateway::new(config.clone());
+
+    let order: FixMessage = new_order_single("a", "1", "2", "1.5").with(tags::PRICE, "150.25");
+    let instruction = gateway.handle_new_order_single(&order).unwrap();
+    assert_eq!(instruction.data[0], ManifestInstruction::BatchUpdate as u8);
+    gateway.on_submitted(&order, "sig-a".to_string());
+
+    // Takes 0.5 from someone else and rests the rest.
+    let other: Pubkey = Pubkey::new_unique();
+    let reports: Vec<FixMessage> = gateway.on_transaction(
+        "sig-a",
+        &[
+            fill_log(&config, other, config.trader, 500_000_000, 75_125_000, 1, 5),
+            place_order_log(&config, 5, 80),
+        ],
+    );
+    assert_eq!(reports.len(), 2);
+    assert_eq!(summary(&reports[0]), ("0", "0", "0", "1.5"));
+    assert_eq!(reports[0].get(tags::ORDER_ID), Some("5"));
+    assert_eq!(summary(&reports[1]), ("F", "1", "0.5", "1"));
+    assert_eq!(reports[1].get(tags::LAST_PX), Some("150.25"));
+    assert_eq!(reports[1].get(tags::LAST_QTY), Some("0.5"));
+
+    // Someone else takes the rest.
+    let reports: Vec<FixMessage> = gateway.on_transaction(
+        "sig-other",
+        &[fill_log(
+            &config,
+            config.trader,
+            other,
+            1_000_000_000,
+            150_250_000,
+            5,
+            6,
+        )],
+    );
+    assert_eq!(reports.len(), 1);
+    assert_eq!(summary(&reports[0]), ("F", "2", "1.5", "0"));
+    assert_eq!(reports[0].get(tags::AVG_PX), Some("150.25"));
+    assert_eq!(gateway.get_order("a").unwrap().status, OrdStatus::Filled);
+
+    let cancel: FixMessage = FixMessage::new(msg_type::ORDER_CANCEL_REQUEST)
+        .with(tags::CL_ORD_ID, "b")
+        .with(tags::ORIG_CL_ORD_ID, "a");
+    let reject: FixMessage = gateway.handle_order_cancel_request(&cancel).unwrap_err();
+    assert_eq!(reject.msg_type(), msg_type::ORDER_CANCEL_REJECT);
+}
+
+#[test]
+fn test_cancel() {
+    let config: MarketConfig = config();
+    let mut gateway: Gateway = Gateway::new(config.clone());
+
+    let order: FixMessage = new_order_single("a", "2", "2", "1").with(tags::PRICE, "200");
+    gateway.handle_new_order_single(&order).unwrap();
+    gateway.on_submitted(&order, "sig-a".to_string());
+    gateway.on_transaction("sig-a", &[place_order_log(&config, 9, 160)]);
+
+    let cancel: FixMessage = FixMessage::new(msg_type::ORDER_CANCEL_REQUEST)
+        .with(tags::CL_ORD_ID, "b")
+        .with(tags::ORIG_CL_ORD_ID, "a");
+    gateway.handle_order_cancel_request(&cancel).unwrap();
+    gateway.on_submitted(&cancel, "sig-b".to_string());
+    let reports: Vec<FixMessage> = gateway.on_transaction("sig-b", &[cancel_order_log(&config, 9)]);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(summary(&reports[0]), ("4", "4", "0", "0"));
+    assert_eq!(reports[0].get(tags::CL_ORD_ID), Some("b"));
+    assert_eq!(reports[0].get(tags::ORIG_CL_ORD_ID), Some("a"));
+}
+
+#[test]
+fn test_market_order_partial_fill_and_rejects() {
+    let config: MarketConfig = config();
+    let mut gateway: Gateway = Gateway::new(config.clone());
+
+    let order: FixMessage = new_order_single("a", "2", "1", "2");
+    let instruction = gateway.handle_new_order_single(&order).unwrap();
+    assert_eq!(instruction.data[0], ManifestInstruction::Swap as u8);
+    gateway.on_submitted(&order, "sig-a".to_string());
+    let reports: Vec<FixMessage> = gateway.on_transaction(
+        "sig-a",
+        &[fill_log(
+            &config,
+            Pubkey::new_unique(),
+            config.trader,
+            1_000_000_000,
+            150_250_000,
+            1,
+            3,
+        )],
+    );
+    let summaries: Vec<_> = reports.iter().map(summary).collect();
+    assert_eq!(
+        summaries,
+        vec![
+            ("0", "0", "0", "2"),
+            ("F", "1", "1", "1"),
+            ("4", "4", "1", "0")
+        ]
+    );
+
+    // Duplicate ClOrdID, too precise a size and a failed transaction.
+    let rejects: [FixMessage; 2] = [
+        gateway.handle_new_order_single(&order).unwrap_err(),
+        gateway
+            .handle_new_order_single(
+                &new_order_single("b", "1", "2", "0.0000000001").with(tags::PRICE, "1"),
+            )
+            .unwrap_err(),
+    ];
+    for reject in rejects.iter() {
+        assert_eq!(reject.get(tags::EXEC_TYPE), Some("8"));
+    }
+    let order: FixMessage = new_order_single("c", "1", "2", "1").with(tags::PRICE, "1");
+    gateway.handle_new_order_single(&order).unwrap();
+    gateway.on_submitted(&order, "sig-c".to_string());
+    let reports: Vec<FixMessage> = gateway.on_submit_failed("sig-c", "insufficient funds");
+    assert_eq!(summary(&reports[0]), ("8", "8", "0", "0"));
+    assert_eq!(reports[0].get(tags::TEXT), Some("insufficient funds"));
+}
@@ -343,7 +343,7 @@ impl Amm for ManifestMarket {
 #[cfg(test)]
 mod test {
     use super::*;
-    use hypertree::{get_mut_helper, DataIndex};
+    use hypertree::{get_mut_helper, DataIndex, NIL};
     use jupiter_amm_interface::{ClockRef, SwapMode};
     use manifest::{
         quantities::{BaseAtoms, GlobalAtoms},
@@ -481,7 +481,7 @@ mod test {
         );
 
         let mut market_value: DynamicAccount<MarketFixed, Vec<u8>> = MarketValue {
-            fixed: MarketFixed::new_empty(&base_mint, &quote_mint, &MARKET_KEY),
+            fixed: MarketFixed::new_empty(&base_mint, &quote_mint, &MARKET_KEY, 0),
             // 5 because 2 extra, 1 seat, 2 orders.
             dynamic: vec![0; MARKET_BLOCK_SIZE * 5],
         };
@@ -509,6 +509,8 @@ mod test {
                 order_type: OrderType::Global,
                 global_trade_accounts_opts: &[None, quote_global_trade_accounts],
                 current_slot: None,
+                fill_memo: false,
+                insert_hint_index: NIL,
             })
             .unwrap();
 
@@ -525,6 +527,8 @@ mod test {
                 order_type: OrderType::Limit,
                 global_trade_accounts_opts: &[None, None],
                 current_slot: None,
+                fill_memo: false,
+                insert_hint_index: NIL,
             })
             .unwrap();
 
@@ -0,0 +1,115 @@
+//! Top of book statistics for strategy code.
+//!
+//! Depth is counted in price levels, so orders at the same price count once.
+//! With one level these are the usual top of book numbers. Prices are floats
+//! like [`RestingOrder::get_price_float`], quote atoms per base atom.
+//!
+//! [`RestingOrder::get_price_float`]: crate::RestingOrder::get_price_float
+
+use crate::state::Market;
+
+/// Size and average price of the best price levels on one side of the book.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookDepth {
+    /// Number of price levels included, at most the requested depth.
+    pub levels: usize,
+    pub base_atoms: u64,
+    /// Average price weighted by base atoms.
+    pub average_price: f64,
+}
+
+impl BookDepth {
+    /// Depth of the best `levels` price levels given (price, base atoms) from
+    /// best to worst. None when there are no orders or levels is zero.
+    pub fn from_orders(
+        orders: impl IntoIterator<Item = (f64, u64)>,
+        levels: usize,
+    ) -> Option<Self> {
+        let mut num_levels: usize = 0;
+        let mut last_price: Option<f64> = None;
+        let mut base_atoms: u64 = 0;
+        let mut notional: f64 = 0.0;
+        for (price, order_base_atoms) in orders {
+            if last_price != Some(price) {
+                if num_levels == levels {
+                    break;
+                }
+                num_levels += 1;
+                last_price = Some(price);
+            }
+            base_atoms = base_atoms.saturating_add(order_base_atoms);
+            notional += price * order_base_atoms as f64;
+        }
+        if base_atoms == 0 {
+            return None;
+        }
+        Some(BookDepth {
+            levels: num_levels,
+            base_atoms,
+            average_price: notional / base_atoms as f64,
+        })
+    }
+}
+
+/// (bid - ask) / (bid + ask) in base atoms, from -1 when there are only asks
+/// to 1 when there are only bids.
+pub fn book_imbalance(bids: &BookDepth, asks: &BookDepth) -> f64 {
+    let bid_atoms: f64 = bids.base_atoms as f64;
+    let ask_atoms: f64 = asks.base_atoms as f64;
+    (bid_atoms - ask_atoms) / (bid_atoms + ask_atoms)
+}
+
+/// Mid price weighted toward the side with less size, since that side is the
+/// one more likely to be taken out next.
+pub fn micro_price(bids: &BookDepth, asks: &BookDepth) -> f64 {
+    let bid_atoms: f64 = bids.base_atoms as f64;
+    let ask_atoms: f64 = asks.base_atoms as f64;
+    (bids.average_price * ask_atoms + asks.average_price * bid_atoms) / (bid_atoms + ask_atoms)
+}
+
+/// Distance between the average ask and bid prices in basis points of their
+/// mid. Negative if the book is crossed.
+pub fn spread_bps(bids: &BookDepth, asks: &BookDepth) -> f64 {
+    let mid: f64 = (bids.average_price + asks.average_price) / 2.0;
+    (asks.average_price - bids.average_price) / mid * 10_000.0
+}
+
+impl<'a> Market<'a> {
+    /// Size and average price of the best `levels` price levels of bids or
+    /// asks. Expired orders are included, like in [`Market::get_best_bid`].
+    pub fn get_depth(&'a self, is_bids: bool, levels: usize) -> Option<BookDepth> {
+        let orders = if is_bids {
+            self.iter_bids()
+        } else {
+            self.iter_asks()
+        };
+        BookDepth::from_orders(
+            orders.map(|(_, order)| (order.get_price_float(), order.get_visible_base_atoms())),
+            levels,
+        )
+    }
+
+    /// See [`book_imbalance`]. None unless both sides have orders.
+    pub fn get_book_imbalance(&'a self, levels: usize) -> Option<f64> {
+        Some(book_imbalance(
+            &self.get_depth(true, levels)?,
+            &self.get_depth(false, levels)?,
+        ))
+    }
+
+    /// See [`micro_price`]. None unless both sides have orders.
+    pub fn get_micro_price(&'a self, levels: usize) -> Option<f64> {
+        Some(micro_price(
+            &self.get_depth(true, levels)?,
+            &self.get_depth(false, levels)?,
+        ))
+    }
+
+    /// See [`spread_bps`]. None unless both sides have orders.
+    pub fn get_spread_bps(&'a self, levels: usize) -> Option<f64> {
+        Some(spread_bps(
+            &self.get_depth(true, levels)?,
+            &self.get_depth(false, levels)?,
+        ))
+    }
+}
@@ -0,0 +1,215 @@
+//! Jito bundles for flows that have to land together, like cancel and
+//! replace or a liquidation and its hedge.
+//!
+//! A bundle is up to five transactions that execute in order in the same
+//! slot, all or none. The tip is a plain SOL transfer to one of the Jito tip
+//! accounts. It goes last in the last transaction, so a bundle that fails
+//! midway pays nothing. Sizes are checked here because the block engine
+//! rejects the whole bundle when any transaction is over the packet size.
+
+use std::{collections::HashSet, fmt};
+
+use solana_instruction::{AccountMeta, Instruction};
+use solana_pubkey::Pubkey;
+
+use crate::constants::SYSTEM_PROGRAM_ID;
+
+/// Most transactions the block engine accepts in one bundle.
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+/// Largest serialized transaction, the size of a packet.
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+/// Smallest tip the block engine accepts.
+pub const MIN_JITO_TIP_LAMPORTS: u64 = 1_000;
+
+/// Mainnet Jito tip accounts. Picking one at random spreads the write locks.
+pub const JITO_TIP_ACCOUNTS: [Pubkey; 8] = [
+    Pubkey::from_str_const("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5"),
+    Pubkey::from_str_const("HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe"),
+    Pubkey::from_str_const("Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY"),
+    Pubkey::from_str_const("ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49"),
+    Pubkey::from_str_const("DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh"),
+    Pubkey::from_str_const("ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt"),
+    Pubkey::from_str_const("DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL"),
+    Pubkey::from_str_const("3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT"),
+];
+
+/// Why a bundle could not be built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleError {
+    /// No transactions, or a transaction without instructions.
+    Empty,
+    /// More than MAX_BUNDLE_TRANSACTIONS transactions.
+    TooManyTransactions(usize),
+    /// The transaction at index serializes to more than MAX_TRANSACTION_SIZE.
+    TransactionTooLarge { index: usize, size: usize },
+    /// Tip below MIN_JITO_TIP_LAMPORTS.
+    TipTooSmall,
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::Empty => f.write_str("Bundle has an empty transaction"),
+            BundleError::TooManyTransactions(count) => {
+                write!(f, "Bundle has {} transactions", count)
+            }
+            BundleError::TransactionTooLarge { index, size } => {
+                write!(f, "Transaction {} is {} bytes", index, size)
+            }
+            BundleError::TipTooSmall => f.write_str("Tip is below the minimum"),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+/// Addresses of an address lookup table, to size v0 transactions that use it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupTable {
+    pub key: Pubkey,
+    pub addresses: Vec<Pubkey>,
+}
+
+/// System transfer of lamports from payer to a tip account, built by hand to
+/// keep the SDK out.
+pub fn tip_instruction(payer: Pubkey, tip_account: Pubkey, lamports: u64) -> Instruction {
+    let mut data: Vec<u8> = 2u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&lamports.to_le_bytes());
+    Instruction {
+        program_id: SYSTEM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(tip_account, false),
+        ],
+        data,
+    }
+}
+
+/// Appends the tip to the last transaction and checks the bundle fits. Each
+/// transaction is paid by payer and sized as a v0 transaction using
+/// lookup_tables, or as a legacy one when there are none.
+pub fn build_bundle(
+    payer: Pubkey,
+    mut transactions: Vec<Vec<Instruction>>,
+    tip_account: Pubkey,
+    tip_lamports: u64,
+    lookup_tables: &[LookupTable],
+) -> Result<Vec<Vec<Instruction>>, BundleError> {
+    if tip_lamports < MIN_JITO_TIP_LAMPORTS {
+        return Err(BundleError::TipTooSmall);
+    }
+    let last: &mut Vec<Instruction> = transactions.last_mut().ok_or(BundleError::Empty)?;
+    last.push(tip_instruction(payer, tip_account, tip_lamports));
+    check_bundle(payer, &transactions, lookup_tables)?;
+    Ok(transactions)
+}
+
+/// Checks the number of transactions and the size of each one.
+pub fn check_bundle(
+    payer: Pubkey,
+    transactions: &[Vec<Instruction>],
+    lookup_tables: &[LookupTable],
+) -> Result<(), BundleError> {
+    if transactions.is_empty() {
+        return Err(BundleError::Empty);
+    }
+    if transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+        return Err(BundleError::TooManyTransactions(transactions.len()));
+    }
+    for (index, instructions) in transactions.iter().enumerate() {
+        if instructions.is_empty() {
+            return Err(BundleError::Empty);
+        }
+        let size: usize = transaction_size(payer, instructions, lookup_tables);
+        if size > MAX_TRANSACTION_SIZE {
+            return Err(BundleError::TransactionTooLarge { index, size });
+        }
+    }
+    Ok(())
+}
+
+/// Serialized size of the signed transaction, including signatures. With
+/// lookup_tables it is a v0 transaction where accounts that are not signers
+/// or programs are loaded from the first table that has them.
+pub fn transaction_size(
+    payer: Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[LookupTable],
+) -> usize {
+    let mut keys: Vec<Pubkey> = vec![payer];
+    let mut signers: HashSet<Pubkey> = HashSet::from([payer]);
+    let mut programs: HashSet<Pubkey> = HashSet::new();
+    for instruction in instructions {
+        programs.insert(instruction.program_id);
+        for account in instruction.accounts.iter() {
+            if account.is_signer {
+                signers.insert(account.pubkey);
+            }
+            if !keys.contains(&account.pubkey) {
+                keys.push(account.pubkey);
+            }
+        }
+        if !keys.contains(&instruction.program_id) {
+            keys.push(instruction.program_id);
+        }
+    }
+
+    // Accounts loaded through each table used.
+    let mut num_static_keys: usize = 0;
+    let mut table_lookups: Vec<usize> = vec![0; lookup_tables.len()];
+    for key in keys.iter() {
+        let table: Option<usize> = if signers.contains(key) || programs.contains(key) {
+            None
+        } else {
+            lookup_tables
+                .iter()
+                .position(|table| table.addresses.contains(key))
+        };
+        match table {
+            Some(table) => table_lookups[table] += 1,
+            None => num_static_keys += 1,
+        }
+    }
+
+    let instructions_size: usize = instructions
+        .iter()
+        .map(|instruction| {
+            1 + short_vec_size(instruction.accounts.len())
+                + instruction.accounts.len()
+                + short_vec_size(instruction.data.len())
+                + instruction.data.len()
+        })
+        .sum();
+    let mut size: usize = short_vec_size(signers.len())
+        + 64 * signers.len()
+        // Header and recent blockhash.
+        + 3
+        + 32
+        + short_vec_size(num_static_keys)
+        + 32 * num_static_keys
+        + short_vec_size(instructions.len())
+        + instructions_size;
+    if !lookup_tables.is_empty() {
+        let used_tables: Vec<usize> = table_lookups
+            .into_iter()
+            .filter(|lookups| *lookups > 0)
+            .collect();
+        // Version prefix, then per table its key and the writable and
+        // readonly index lists. Both lists have a length even when empty.
+        size += 1 + short_vec_size(used_tables.len());
+        size += used_tables
+            .iter()
+            .map(|lookups| 32 + 2 + lookups)
+            .sum::<usize>();
+    }
+    size
+}
+
+/// Bytes of the compact length prefix Solana uses for vectors.
+fn short_vec_size(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
@@ -16,6 +16,12 @@ pub enum OrderType {
     Global = 3,
     Reverse = 4,
     ReverseTight = 5,
+    /// Post only that rests one price step inside the opposing best instead
+    /// of failing when it would cross.
+    PostOnlySlide = 6,
+    /// Limit order that shows a display size at a time. Once the shown
+    /// tranche fills, the next goes to the back of its price level.
+    Iceberg = 7,
 }
 
 impl OrderType {
@@ -27,6 +33,8 @@ impl OrderType {
             3 => Some(OrderType::Global),
             4 => Some(OrderType::Reverse),
             5 => Some(OrderType::ReverseTight),
+            6 => Some(OrderType::PostOnlySlide),
+            7 => Some(OrderType::Iceberg),
             _ => None,
         }
     }
@@ -36,6 +44,37 @@ impl OrderType {
     }
 }
 
+/// What an order does when it would match a resting order from its own seat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum SelfTradePrevention {
+    /// Match like any other order. Fails on markets with
+    /// MARKET_FLAG_NO_INTERNAL_CROSS.
+    #[default]
+    None = 0,
+    /// Cancel the resting order and keep matching.
+    CancelProvide = 1,
+    /// Cancel the rest of the order. Earlier fills stay.
+    CancelTake = 2,
+    /// Reduce both orders by the smaller size without a trade.
+    DecrementBoth = 3,
+    /// Fail the order.
+    Reject = 4,
+}
+
+/// Price a pegged order follows, see [`place_pegged_order_instruction`].
+///
+/// [`place_pegged_order_instruction`]: crate::place_pegged_order_instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum PegReference {
+    #[default]
+    BestBid = 0,
+    BestAsk = 1,
+    /// Halfway between the best bid and the best ask. Needs both sides.
+    Mid = 2,
+}
+
 /// Manifest program ID: MNFSTqtC93rEfYHB6hF82sKdZpUDFWkViLByLd1k1Ms
 pub const MANIFEST_PROGRAM_ID: Pubkey =
     Pubkey::from_str_const("MNFSTqtC93rEfYHB6hF82sKdZpUDFWkViLByLd1k1Ms");
@@ -51,9 +90,33 @@ pub const TOKEN_PROGRAM_ID: Pubkey =
 pub const TOKEN_2022_PROGRAM_ID: Pubkey =
     Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
 
+/// SPL Associated Token Account program ID
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Compute budget program ID
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("ComputeBudget111111111111111111111111111111");
+
+/// Ed25519 signature verification program ID
+pub const ED25519_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("Ed25519SigVerify111111111111111111111111111");
+
 /// Market discriminant value.
 pub const MARKET_FIXED_DISCRIMINANT: u64 = 4859840929024028656;
 
+/// Signed order nonces account discriminant value.
+pub const SIGNED_ORDER_NONCES_DISCRIMINANT: u64 = 8149790891111600843;
+
+/// Size of a signed order nonces account in bytes.
+pub const SIGNED_ORDER_NONCES_SIZE: usize = 208;
+
+/// RFQ account discriminant value.
+pub const RFQ_DISCRIMINANT: u64 = 3578967816379351578;
+
+/// Size of an RFQ account in bytes.
+pub const RFQ_SIZE: usize = 96;
+
 /// Size of MarketFixed header in bytes.
 pub const MARKET_FIXED_SIZE: usize = 256;
 
@@ -66,5 +129,97 @@ pub const RESTING_ORDER_SIZE: usize = 64;
 /// Claimed seat size in bytes.
 pub const CLAIMED_SEAT_SIZE: usize = 64;
 
+/// Market flag: orders that would cross a resting order from the same seat
+/// fail instead of trading against it.
+pub const MARKET_FLAG_NO_INTERNAL_CROSS: u8 = 1 << 0;
+
+/// Market flag: swaps, RFQ fills and batch updates that place or replace
+/// orders only work through a CPI from a program on the market's allowlist.
+pub const MARKET_FLAG_CPI_ALLOWLIST: u8 = 1 << 1;
+
+/// Most programs on a market's CPI allowlist.
+pub const MAX_CPI_ALLOWLIST_PROGRAMS: usize = 2;
+
+/// Market flag: orders that could rest need a minimum base size and quote
+/// value.
+pub const MARKET_FLAG_MIN_ORDER_SIZE: u8 = 1 << 2;
+
+/// Market flag: takers cannot fill further than a set number of bps from the
+/// reference price.
+pub const MARKET_FLAG_PRICE_BAND: u8 = 1 << 3;
+
+/// Market flag: makers and takers pay a fee on what they receive from fills.
+pub const MARKET_FLAG_FEES: u8 = 1 << 4;
+
+/// Market flag: the market keeps totals of its fills in the block after the
+/// fees.
+pub const MARKET_FLAG_VOLUME_STATS: u8 = 1 << 5;
+
+/// Market flag: the market has an extension block after all its other
+/// blocks, with space for fields that do not fit in the MarketFixed.
+pub const MARKET_FLAG_EXTENSION: u8 = 1 << 6;
+
+/// Version of the extension of markets created now. Version 2 added the
+/// pauser, version 3 the count of fill records, version 4 the fees owed by the
+/// fee recipient.
+pub const MARKET_EXTENSION_VERSION: u8 = 4;
+
+/// Extension flag: the pauser has paused the market.
+pub const MARKET_EXTENSION_FLAG_PAUSED: u8 = 1 << 0;
+
+/// Extension flag set at creation: the market keeps records of its latest
+/// fills in the blocks after the extension, and only those fills can be
+/// busted.
+pub const MARKET_EXTENSION_FLAG_FILL_RECORDS: u8 = 1 << 1;
+
+/// Highest maker or taker fee in bps a market can be created with.
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Taker fee discounts by rolling taker volume, as the multiple of the
+/// market's fee tier quote atoms a seat needs and the discount in bps of the
+/// taker fee.
+pub const FEE_TIERS: [(u64, u16); 3] = [(1, 1_000), (10, 2_500), (100, 5_000)];
+
+/// Slots per bucket of the rolling taker volume of a seat, about 5 days.
+pub const SEAT_VOLUME_BUCKET_SLOTS: u32 = 1_080_000;
+
+/// Buckets the rolling taker volume of a seat is summed over.
+pub const SEAT_VOLUME_BUCKETS: usize = 6;
+
+/// Slots without a trade or reset after which anyone can move the price band
+/// reference of a market to the middle of its book.
+pub const PRICE_BAND_RESET_SLOTS: u32 = 150;
+
+/// Market flag: the program upgrade authority put the market in recovery.
+/// Only recovery withdraws work on it.
+pub const MARKET_FLAG_RECOVERY: u8 = 1 << 7;
+
+/// A fill can only be busted while its taker sequence number is among the
+/// last this many order sequence numbers of the market.
+pub const TRADE_BUST_MAX_SEQUENCE_NUMBERS: u64 = 10_000;
+
+/// Signed order nonces a maker can use out of order. Filling a nonce this far
+/// above the lowest unused one uses every nonce that falls below the window.
+pub const SIGNED_ORDER_NONCE_WINDOW: u64 = 1_024;
+
+/// Most maker quotes a FillRfq can pick the best of.
+pub const MAX_RFQ_RESPONSES: usize = 4;
+
+/// BPF upgradeable loader program ID, owner of the program data account
+pub const BPF_LOADER_UPGRADEABLE_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("BPFLoaderUpgradeab1e11111111111111111111111");
+
+/// Instructions sysvar, the last account of trading instructions on markets
+/// with a CPI allowlist.
+pub const INSTRUCTIONS_SYSVAR_ID: Pubkey =
+    Pubkey::from_str_const("Sysvar1nstructions1111111111111111111111111");
+
 /// No expiration sentinel for orders.
 pub const NO_EXPIRATION_LAST_VALID_SLOT: u32 = 0;
+
+/// Spread of a reverse order, passed as its last valid slot, that follows the
+/// market volatility estimate instead of a fixed spread.
+pub const DYNAMIC_REVERSE_SPREAD: u16 = u16::MAX;
+
+/// Cap on the market volatility estimate in basis points.
+pub const MAX_VOLATILITY_BPS: u16 = 5_000;
@@ -0,0 +1,256 @@
+//! Decoding of Manifest program errors from failed transactions.
+
+use std::fmt;
+
+use crate::constants::MANIFEST_PROGRAM_ID;
+
+/// Errors returned by the Manifest program as `ProgramError::Custom(code)`.
+/// Mirrors `ManifestError` in the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ManifestError {
+    InvalidMarketParameters = 0,
+    InvalidDepositAccounts = 1,
+    InvalidWithdrawAccounts = 2,
+    InvalidCancel = 3,
+    InvalidFreeList = 4,
+    AlreadyClaimedSeat = 5,
+    PostOnlyCrosses = 6,
+    AlreadyExpired = 7,
+    InsufficientOut = 8,
+    InvalidPlaceOrderFromWalletParams = 9,
+    WrongIndexHintParams = 10,
+    PriceNotPositive = 11,
+    OrderWouldOverflow = 12,
+    OrderTooSmall = 13,
+    Overflow = 14,
+    MissingGlobal = 15,
+    GlobalInsufficient = 16,
+    IncorrectAccount = 17,
+    InvalidMint = 18,
+    TooManyGlobalSeats = 19,
+    InvalidEvict = 20,
+    InvalidClean = 21,
+    InvalidSeatConfig = 22,
+    MissingCosigner = 23,
+    MarketTooLarge = 24,
+    InvalidRfq = 25,
+    InternalCross = 26,
+    MarketClosed = 27,
+    InvalidSeatArchive = 28,
+    InvalidSwapCommitment = 29,
+    CpiCallerNotAllowed = 30,
+    InvalidStopOrder = 31,
+    InvalidHeartbeat = 32,
+    InvalidRebalance = 33,
+    InvalidVolatilityUpdate = 34,
+    InvalidRecovery = 35,
+    PriceBandExceeded = 36,
+    InvalidPriceBandReset = 37,
+    InvalidFeeClaim = 38,
+    InvalidTradeBust = 39,
+    InvalidSignedOrder = 40,
+    InvalidMmp = 41,
+    MarketPaused = 42,
+    InvalidPauser = 43,
+    InvalidPeggedOrder = 44,
+}
+
+impl ManifestError {
+    /// Error for a custom program error code, None if the code is not a
+    /// Manifest error.
+    pub fn from_code(code: u32) -> Option<Self> {
+        Some(match code {
+            0 => ManifestError::InvalidMarketParameters,
+            1 => ManifestError::InvalidDepositAccounts,
+            2 => ManifestError::InvalidWithdrawAccounts,
+            3 => ManifestError::InvalidCancel,
+            4 => ManifestError::InvalidFreeList,
+            5 => ManifestError::AlreadyClaimedSeat,
+            6 => ManifestError::PostOnlyCrosses,
+            7 => ManifestError::AlreadyExpired,
+            8 => ManifestError::InsufficientOut,
+            9 => ManifestError::InvalidPlaceOrderFromWalletParams,
+            10 => ManifestError::WrongIndexHintParams,
+            11 => ManifestError::PriceNotPositive,
+            12 => ManifestError::OrderWouldOverflow,
+            13 => ManifestError::OrderTooSmall,
+            14 => ManifestError::Overflow,
+            15 => ManifestError::MissingGlobal,
+            16 => ManifestError::GlobalInsufficient,
+            17 => ManifestError::IncorrectAccount,
+            18 => ManifestError::InvalidMint,
+            19 => ManifestError::TooManyGlobalSeats,
+            20 => ManifestError::InvalidEvict,
+            21 => ManifestError::InvalidClean,
+            22 => ManifestError::InvalidSeatConfig,
+            23 => ManifestError::MissingCosigner,
+            24 => ManifestError::MarketTooLarge,
+            25 => ManifestError::InvalidRfq,
+            26 => ManifestError::InternalCross,
+            27 => ManifestError::MarketClosed,
+            28 => ManifestError::InvalidSeatArchive,
+            29 => ManifestError::InvalidSwapCommitment,
+            30 => ManifestError::CpiCallerNotAllowed,
+            31 => ManifestError::InvalidStopOrder,
+            32 => ManifestError::InvalidHeartbeat,
+            33 => ManifestError::InvalidRebalance,
+            34 => ManifestError::InvalidVolatilityUpdate,
+            35 => ManifestError::InvalidRecovery,
+            36 => ManifestError::PriceBandExceeded,
+            37 => ManifestError::InvalidPriceBandReset,
+            38 => ManifestError::InvalidFeeClaim,
+            39 => ManifestError::InvalidTradeBust,
+            40 => ManifestError::InvalidSignedOrder,
+            41 => ManifestError::InvalidMmp,
+            42 => ManifestError::MarketPaused,
+            43 => ManifestError::InvalidPauser,
+            44 => ManifestError::InvalidPeggedOrder,
+            _ => return None,
+        })
+    }
+
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// Same text as the program's error message.
+    pub fn description(self) -> &'static str {
+        match self {
+            ManifestError::InvalidMarketParameters => "Invalid market parameters error",
+            ManifestError::InvalidDepositAccounts => "Invalid deposit accounts error",
+            ManifestError::InvalidWithdrawAccounts => "Invalid withdraw accounts error",
+            ManifestError::InvalidCancel => "Invalid cancel error",
+            ManifestError::InvalidFreeList => "Internal free list corruption error",
+            ManifestError::AlreadyClaimedSeat => "Cannot claim a second seat for the same trader",
+            ManifestError::PostOnlyCrosses => "Matched on a post only order",
+            ManifestError::AlreadyExpired => "New order is already expired",
+            ManifestError::InsufficientOut => "Less than minimum out amount",
+            ManifestError::InvalidPlaceOrderFromWalletParams => {
+                "Invalid place order from wallet params"
+            }
+            ManifestError::WrongIndexHintParams => "Index hint did not match actual index",
+            ManifestError::PriceNotPositive => "Price is not positive",
+            ManifestError::OrderWouldOverflow => "Order settlement would overflow",
+            ManifestError::OrderTooSmall => "Order is too small to settle any value",
+            ManifestError::Overflow => "Overflow in token addition",
+            ManifestError::MissingGlobal => "Missing Global account",
+            ManifestError::GlobalInsufficient => {
+                "Insufficient funds on global account to rest an order"
+            }
+            ManifestError::IncorrectAccount => "Account key did not match expected",
+            ManifestError::InvalidMint => "Mint not allowed for market",
+            ManifestError::TooManyGlobalSeats => "Cannot claim a new global seat, use evict",
+            ManifestError::InvalidEvict => "Can only evict the lowest depositor",
+            ManifestError::InvalidClean => {
+                "Tried to clean order that was not eligible to be cleaned"
+            }
+            ManifestError::InvalidSeatConfig => "Invalid seat config",
+            ManifestError::MissingCosigner => "Missing required cosigner",
+            ManifestError::MarketTooLarge => "Market account is at its maximum size",
+            ManifestError::InvalidRfq => "Invalid RFQ fill",
+            ManifestError::InternalCross => "Order would cross an order from the same seat",
+            ManifestError::MarketClosed => "Market is outside its trading hours",
+            ManifestError::InvalidSeatArchive => "Invalid seat archive",
+            ManifestError::InvalidSwapCommitment => "Invalid swap commitment",
+            ManifestError::CpiCallerNotAllowed => "Caller is not on the market CPI allowlist",
+            ManifestError::InvalidStopOrder => "Invalid stop order",
+            ManifestError::InvalidHeartbeat => "Invalid heartbeat",
+            ManifestError::InvalidRebalance => "Invalid rebalance",
+            ManifestError::InvalidVolatilityUpdate => "Invalid volatility update",
+            ManifestError::InvalidRecovery => "Invalid recovery",
+            ManifestError::PriceBandExceeded => "Fill outside the market price band",
+            ManifestError::InvalidPriceBandReset => "Invalid price band reset",
+            ManifestError::InvalidFeeClaim => "Invalid fee claim",
+            ManifestError::InvalidTradeBust => "Invalid trade bust",
+            ManifestError::InvalidSignedOrder => "Invalid signed order",
+            ManifestError::InvalidMmp => "Invalid market maker protection",
+            ManifestError::MarketPaused => "Market is paused",
+            ManifestError::InvalidPauser => "Invalid pauser",
+            ManifestError::InvalidPeggedOrder => "Invalid pegged order",
+        }
+    }
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+/// A Manifest error with the details `require!` logged before returning it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedManifestError {
+    pub error: ManifestError,
+    /// Source location of the failed check, like `src/program/processor/swap.rs:120`.
+    pub location: Option<String>,
+    /// Message given to `require!`.
+    pub message: Option<String>,
+}
+
+impl fmt::Display for DecodedManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "{}: {}", self.error, message),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+impl std::error::Error for DecodedManifestError {}
+
+const PROGRAM_LOG_PREFIX: &str = "Program log: ";
+const CUSTOM_ERROR_PREFIX: &str = " failed: custom program error: 0x";
+
+/// Decode the custom error code of a failed Manifest instruction together
+/// with its simulation or transaction logs. Returns None when the code is not
+/// a Manifest error. Logs are optional, without them only `error` is set.
+pub fn decode_manifest_error(code: u32, logs: &[String]) -> Option<DecodedManifestError> {
+    let error: ManifestError = ManifestError::from_code(code)?;
+
+    // The require! message is the last program log before Manifest fails.
+    let failure: String = format!(
+        "Program {}{}{:x}",
+        MANIFEST_PROGRAM_ID, CUSTOM_ERROR_PREFIX, code
+    );
+    let end: usize = logs
+        .iter()
+        .rposition(|log| *log == failure)
+        .unwrap_or(logs.len());
+    let (location, message) = logs[..end]
+        .iter()
+        .rev()
+        .find_map(|log| parse_require_log(log))
+        .map_or((None, None), |(location, message)| {
+            (Some(location.to_string()), Some(message.to_string()))
+        });
+
+    Some(DecodedManifestError {
+        error,
+        location,
+        message,
+    })
+}
+
+/// Same as [`decode_manifest_error`] when only the logs are known. The code is
+/// read from the line logged when the Manifest instruction failed.
+pub fn decode_manifest_error_from_logs(logs: &[String]) -> Option<DecodedManifestError> {
+    let prefix: String = format!("Program {}{}", MANIFEST_PROGRAM_ID, CUSTOM_ERROR_PREFIX);
+    let code: u32 = logs.iter().rev().find_map(|log| {
+        log.strip_prefix(prefix.as_str())
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+    })?;
+    decode_manifest_error(code, logs)
+}
+
+/// Splits a `Program log: [file:line] message` line from `require!`.
+fn parse_require_log(log: &str) -> Option<(&str, &str)> {
+    let rest: &str = log.strip_prefix(PROGRAM_LOG_PREFIX)?.strip_prefix('[')?;
+    let (location, message) = rest.split_once("] ")?;
+    if !location.contains(".rs:") {
+        return None;
+    }
+    Some((location, message))
+}
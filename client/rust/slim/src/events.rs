@@ -1,7 +1,9 @@
 //! Event types emitted by the Manifest program.
 //!
 //! These are the log events that can be parsed from transaction logs.
-//! Each event has an 8-byte discriminant prefix followed by the struct data.
+//! Each event has an 8-byte discriminant prefix followed by the struct data
+//! and a version byte. Fields are only added at the end of a struct with a
+//! new version, so [`parse_event`] reads the fields it knows from any version.
 
 use crate::{constants::OrderType, Pubkey};
 
@@ -45,6 +47,7 @@ pub const CLAIM_SEAT_LOG_DISCRIMINANT: [u8; 8] = [129, 77, 152, 210, 218, 144, 1
 pub const DEPOSIT_LOG_DISCRIMINANT: [u8; 8] = [23, 214, 24, 34, 52, 104, 109, 188];
 pub const WITHDRAW_LOG_DISCRIMINANT: [u8; 8] = [112, 218, 111, 63, 18, 95, 136, 35];
 pub const FILL_LOG_DISCRIMINANT: [u8; 8] = [58, 230, 242, 3, 75, 113, 4, 169];
+pub const FEE_LOG_DISCRIMINANT: [u8; 8] = [215, 75, 152, 91, 245, 41, 203, 95];
 pub const PLACE_ORDER_LOG_DISCRIMINANT: [u8; 8] = [157, 118, 247, 213, 47, 19, 164, 120];
 pub const PLACE_ORDER_LOG_V2_DISCRIMINANT: [u8; 8] = [189, 97, 159, 235, 136, 5, 1, 141];
 pub const CANCEL_ORDER_LOG_DISCRIMINANT: [u8; 8] = [22, 65, 71, 33, 244, 235, 255, 215];
@@ -55,6 +58,22 @@ pub const GLOBAL_DEPOSIT_LOG_DISCRIMINANT: [u8; 8] = [16, 26, 72, 1, 145, 232, 1
 pub const GLOBAL_WITHDRAW_LOG_DISCRIMINANT: [u8; 8] = [206, 118, 67, 64, 124, 109, 157, 201];
 pub const GLOBAL_EVICT_LOG_DISCRIMINANT: [u8; 8] = [250, 180, 155, 38, 98, 223, 82, 223];
 pub const GLOBAL_CLEANUP_LOG_DISCRIMINANT: [u8; 8] = [193, 249, 115, 186, 42, 126, 196, 82];
+pub const ARCHIVE_SEAT_LOG_DISCRIMINANT: [u8; 8] = [239, 158, 31, 208, 70, 84, 50, 63];
+pub const RESTORE_SEAT_LOG_DISCRIMINANT: [u8; 8] = [246, 221, 72, 247, 241, 13, 212, 178];
+pub const DEPTH_SNAPSHOT_LOG_DISCRIMINANT: [u8; 8] = [253, 103, 137, 33, 243, 53, 76, 86];
+pub const POINTS_CLAIM_LOG_DISCRIMINANT: [u8; 8] = [193, 139, 177, 82, 119, 234, 89, 72];
+pub const REPLACE_ORDER_LOG_DISCRIMINANT: [u8; 8] = [253, 39, 91, 196, 183, 42, 212, 80];
+pub const STOP_ORDER_TRIGGERED_LOG_DISCRIMINANT: [u8; 8] = [153, 90, 232, 184, 87, 114, 199, 140];
+pub const CLAIM_FEES_LOG_DISCRIMINANT: [u8; 8] = [237, 221, 199, 208, 60, 38, 140, 13];
+pub const TRADE_BUST_LOG_DISCRIMINANT: [u8; 8] = [224, 34, 84, 100, 252, 51, 100, 19];
+pub const REFERRAL_FEE_LOG_DISCRIMINANT: [u8; 8] = [68, 157, 166, 254, 44, 208, 77, 212];
+pub const MMP_TRIGGERED_LOG_DISCRIMINANT: [u8; 8] = [178, 235, 216, 126, 23, 124, 192, 25];
+pub const MARKET_PAUSED_LOG_DISCRIMINANT: [u8; 8] = [162, 4, 62, 225, 179, 177, 40, 58];
+pub const RFQ_LOG_DISCRIMINANT: [u8; 8] = [187, 131, 190, 235, 9, 179, 203, 125];
+pub const PEGGED_ORDER_PLACED_LOG_DISCRIMINANT: [u8; 8] = [136, 171, 64, 239, 127, 12, 218, 59];
+
+/// Most price levels per side in a [`DepthSnapshotLog`].
+pub const DEPTH_SNAPSHOT_MAX_LEVELS: usize = 10;
 
 /// Emitted when a new market is created.
 #[derive(Clone, Copy, Debug)]
@@ -111,7 +130,43 @@ pub struct FillLog {
     pub taker_sequence_number: u64,
     pub taker_is_buy: PodBool,
     pub is_maker_global: PodBool,
-    pub _padding: [u8; 14],
+    /// Maker and taker are the same seat
+    pub self_trade: PodBool,
+    /// The taker's swap paid the referral share of the taker fee to a
+    /// referrer. Added in version 3, false in earlier logs
+    pub referred: PodBool,
+    /// Tag of the resting order that was filled
+    pub maker_tag: u32,
+    /// Tag of the taking order
+    pub taker_tag: u32,
+    /// Taker fee rate charged on the fill, after any fee tier discount. Added
+    /// in version 2, zero in earlier logs
+    pub taker_fee_bps: u16,
+    pub _padding2: [u8; 2],
+}
+
+/// Emitted after the FillLog of every fill on a market with fees, matched to
+/// it by the sequence numbers. The maker fee is in the mint the maker
+/// received and the taker fee in the other. The taker fee splits into the
+/// referral share, paid out when the swap includes a referrer and kept by the
+/// protocol otherwise, and the protocol share.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct FeeLog {
+    pub market: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub maker_sequence_number: u64,
+    pub taker_sequence_number: u64,
+    pub maker_fee_atoms: u64,
+    pub taker_fee_atoms: u64,
+    /// Referral share of the taker fee
+    pub referral_fee_atoms: u64,
+    /// Taker fee less the referral share
+    pub protocol_fee_atoms: u64,
+    /// The taker bought base, so its fee is in base and the maker's in quote
+    pub taker_is_buy: PodBool,
+    pub _padding: [u8; 7],
 }
 
 /// Emitted when an order is placed on the book.
@@ -156,6 +211,21 @@ pub struct CancelOrderLog {
     pub order_sequence_number: u64,
 }
 
+/// Emitted when a resting order is resized or repriced in place. Keeps the
+/// order_sequence_number when the order kept its priority.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct ReplaceOrderLog {
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    pub price: QuoteAtomsPerBaseAtom,
+    pub base_atoms: BaseAtoms,
+    pub old_order_sequence_number: u64,
+    pub order_sequence_number: u64,
+    pub order_index: u32,
+    pub _padding: [u8; 4],
+}
+
 /// Emitted when a global account is created.
 #[derive(Clone, Copy, Debug)]
 #[repr(C)]
@@ -218,3 +288,290 @@ pub struct GlobalCleanupLog {
     pub amount_desired: GlobalAtoms,
     pub amount_deposited: GlobalAtoms,
 }
+
+/// Emitted when a seat is moved into the seat archive. Keep these to build
+/// proofs for restoring seats.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct ArchiveSeatLog {
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    pub base_atoms: BaseAtoms,
+    pub quote_atoms: QuoteAtoms,
+    pub quote_volume: QuoteAtoms,
+    pub leaf_index: u64,
+}
+
+/// Emitted when an archived seat is restored.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct RestoreSeatLog {
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    pub leaf_index: u64,
+}
+
+/// Emitted when a seat claims its points of a finished epoch.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct PointsClaimLog {
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    pub points: u64,
+    pub epoch: u32,
+    pub _padding: [u8; 4],
+}
+
+/// Emitted when a stop order is placed by the trigger crank, right after the
+/// PlaceOrderLog of the order with the same order_sequence_number.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct StopOrderTriggeredLog {
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    pub trigger_price: QuoteAtomsPerBaseAtom,
+    pub order_sequence_number: u64,
+    pub stop_index: u8,
+    pub _padding: [u8; 7],
+}
+
+/// Emitted when ClaimFees pays the accrued fees of one mint to a token
+/// account of the fee recipient.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct ClaimFeesLog {
+    pub market: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub amount_atoms: u64,
+}
+
+/// Emitted when BustTrade reverses a fill. The fill is the FillLog with the
+/// same sequence numbers, the fee atoms are what each side got refunded.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct TradeBustLog {
+    pub market: Pubkey,
+    pub authority: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub base_atoms: BaseAtoms,
+    pub quote_atoms: QuoteAtoms,
+    pub maker_fee_atoms: u64,
+    pub taker_fee_atoms: u64,
+    pub maker_sequence_number: u64,
+    pub taker_sequence_number: u64,
+    pub taker_is_buy: PodBool,
+    pub _padding: [u8; 7],
+}
+
+/// Emitted when a swap pays the referral share of its taker fee to the
+/// referrer token account it included. referrer owns that token account.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct ReferralFeeLog {
+    pub market: Pubkey,
+    pub referrer: Pubkey,
+    pub mint: Pubkey,
+    pub amount_atoms: u64,
+}
+
+/// Emitted when the market maker protection of a seat pulls its orders during
+/// matching, after a CancelOrderLog for each of them.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct MmpTriggeredLog {
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    pub window_fills: u32,
+    pub num_orders_pulled: u32,
+    pub window_quote_atoms: QuoteAtoms,
+}
+
+/// Emitted when the pauser of a market pauses or resumes it.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct MarketPausedLog {
+    pub market: Pubkey,
+    pub pauser: Pubkey,
+    pub paused: PodBool,
+    pub _padding: [u8; 7],
+}
+
+/// Emitted when a taker posts, replaces or cancels a request for quote. Zero
+/// base_atoms is a cancel.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct RfqLog {
+    pub market: Pubkey,
+    pub taker: Pubkey,
+    pub rfq: Pubkey,
+    pub base_atoms: BaseAtoms,
+    pub sequence_number: u64,
+    pub last_valid_slot: u32,
+    pub taker_is_buy: PodBool,
+    pub _padding: [u8; 3],
+}
+
+/// Emitted when the reprice crank places or moves a pegged order, right after
+/// the PlaceOrderLog of the order with the same order_sequence_number. The
+/// order it replaced has a CancelOrderLog before that.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct PeggedOrderPlacedLog {
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    pub order_sequence_number: u64,
+    pub pegged_index: u8,
+    pub _padding: [u8; 7],
+}
+
+/// Total size of the orders at one price.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct DepthLevel {
+    pub price: QuoteAtomsPerBaseAtom,
+    pub base_atoms: BaseAtoms,
+}
+
+/// Emitted by the depth snapshot crank. Only the first num_bids and num_asks
+/// levels are set, best first, the rest are zero.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct DepthSnapshotLog {
+    pub market: Pubkey,
+    pub slot: u32,
+    pub num_bids: u8,
+    pub num_asks: u8,
+    pub _padding: [u8; 2],
+    pub bids: [DepthLevel; DEPTH_SNAPSHOT_MAX_LEVELS],
+    pub asks: [DepthLevel; DEPTH_SNAPSHOT_MAX_LEVELS],
+}
+
+/// Version of logs emitted before they ended with a version byte. Their
+/// fields are the same as version 1.
+pub const LOG_VERSION_UNVERSIONED: u8 = 0;
+
+/// A log of the Manifest program.
+#[derive(Clone, Debug)]
+pub enum ManifestEvent {
+    CreateMarket(CreateMarketLog),
+    ClaimSeat(ClaimSeatLog),
+    Deposit(DepositLog),
+    Withdraw(WithdrawLog),
+    Fill(FillLog),
+    Fee(FeeLog),
+    PlaceOrder(PlaceOrderLog),
+    PlaceOrderV2(PlaceOrderLogV2),
+    CancelOrder(CancelOrderLog),
+    ReplaceOrder(ReplaceOrderLog),
+    GlobalCreate(GlobalCreateLog),
+    GlobalAddTrader(GlobalAddTraderLog),
+    GlobalClaimSeat(GlobalClaimSeatLog),
+    GlobalDeposit(GlobalDepositLog),
+    GlobalWithdraw(GlobalWithdrawLog),
+    GlobalEvict(GlobalEvictLog),
+    GlobalCleanup(GlobalCleanupLog),
+    ArchiveSeat(ArchiveSeatLog),
+    RestoreSeat(RestoreSeatLog),
+    PointsClaim(PointsClaimLog),
+    StopOrderTriggered(StopOrderTriggeredLog),
+    ClaimFees(ClaimFeesLog),
+    TradeBust(TradeBustLog),
+    ReferralFee(ReferralFeeLog),
+    MmpTriggered(MmpTriggeredLog),
+    MarketPaused(MarketPausedLog),
+    Rfq(RfqLog),
+    PeggedOrderPlaced(PeggedOrderPlacedLog),
+    DepthSnapshot(Box<DepthSnapshotLog>),
+}
+
+/// Reads a log struct from the start of `body` and the version from its last
+/// byte. A body of exactly the struct size is [`LOG_VERSION_UNVERSIONED`].
+///
+/// # Safety
+/// Every bit pattern of the first `size_of::<T>()` bytes must be a valid `T`.
+unsafe fn read_log<T: Copy>(body: &[u8]) -> Option<(T, u8)> {
+    let size: usize = std::mem::size_of::<T>();
+    let version: u8 = match body.len() {
+        len if len == size => LOG_VERSION_UNVERSIONED,
+        len if len > size => body[len - 1],
+        _ => return None,
+    };
+    Some((std::ptr::read_unaligned(body.as_ptr() as *const T), version))
+}
+
+/// Whether the order type byte at `offset` of `body` is a known order type.
+fn has_valid_order_type(body: &[u8], offset: usize) -> bool {
+    body.get(offset)
+        .is_some_and(|order_type| OrderType::from_u8(*order_type).is_some())
+}
+
+/// Parses the decoded data of a `Program data:` log into the event and its
+/// version. Fields added by versions newer than this client are skipped.
+/// Returns None for other programs' logs and truncated data.
+pub fn parse_event(data: &[u8]) -> Option<(ManifestEvent, u8)> {
+    if data.len() < 8 {
+        return None;
+    }
+    let (discriminant, body) = data.split_at(8);
+    let discriminant: [u8; 8] = discriminant.try_into().ok()?;
+
+    macro_rules! read {
+        ($variant:ident, $log:ty) => {
+            // Safety: logs are plain data apart from the order type, which is
+            // checked before reading.
+            unsafe { read_log::<$log>(body) }
+                .map(|(log, version)| (ManifestEvent::$variant(log), version))
+        };
+    }
+
+    match discriminant {
+        CREATE_MARKET_LOG_DISCRIMINANT => read!(CreateMarket, CreateMarketLog),
+        CLAIM_SEAT_LOG_DISCRIMINANT => read!(ClaimSeat, ClaimSeatLog),
+        DEPOSIT_LOG_DISCRIMINANT => read!(Deposit, DepositLog),
+        WITHDRAW_LOG_DISCRIMINANT => read!(Withdraw, WithdrawLog),
+        FILL_LOG_DISCRIMINANT => read!(Fill, FillLog),
+        FEE_LOG_DISCRIMINANT => read!(Fee, FeeLog),
+        PLACE_ORDER_LOG_DISCRIMINANT => {
+            if !has_valid_order_type(body, std::mem::offset_of!(PlaceOrderLog, order_type)) {
+                return None;
+            }
+            read!(PlaceOrder, PlaceOrderLog)
+        }
+        PLACE_ORDER_LOG_V2_DISCRIMINANT => {
+            if !has_valid_order_type(body, std::mem::offset_of!(PlaceOrderLogV2, order_type)) {
+                return None;
+            }
+            read!(PlaceOrderV2, PlaceOrderLogV2)
+        }
+        CANCEL_ORDER_LOG_DISCRIMINANT => read!(CancelOrder, CancelOrderLog),
+        REPLACE_ORDER_LOG_DISCRIMINANT => read!(ReplaceOrder, ReplaceOrderLog),
+        GLOBAL_CREATE_LOG_DISCRIMINANT => read!(GlobalCreate, GlobalCreateLog),
+        GLOBAL_ADD_TRADER_LOG_DISCRIMINANT => read!(GlobalAddTrader, GlobalAddTraderLog),
+        GLOBAL_CLAIM_SEAT_LOG_DISCRIMINANT => read!(GlobalClaimSeat, GlobalClaimSeatLog),
+        GLOBAL_DEPOSIT_LOG_DISCRIMINANT => read!(GlobalDeposit, GlobalDepositLog),
+        GLOBAL_WITHDRAW_LOG_DISCRIMINANT => read!(GlobalWithdraw, GlobalWithdrawLog),
+        GLOBAL_EVICT_LOG_DISCRIMINANT => read!(GlobalEvict, GlobalEvictLog),
+        GLOBAL_CLEANUP_LOG_DISCRIMINANT => read!(GlobalCleanup, GlobalCleanupLog),
+        ARCHIVE_SEAT_LOG_DISCRIMINANT => read!(ArchiveSeat, ArchiveSeatLog),
+        RESTORE_SEAT_LOG_DISCRIMINANT => read!(RestoreSeat, RestoreSeatLog),
+        POINTS_CLAIM_LOG_DISCRIMINANT => read!(PointsClaim, PointsClaimLog),
+        STOP_ORDER_TRIGGERED_LOG_DISCRIMINANT => {
+            read!(StopOrderTriggered, StopOrderTriggeredLog)
+        }
+        CLAIM_FEES_LOG_DISCRIMINANT => read!(ClaimFees, ClaimFeesLog),
+        TRADE_BUST_LOG_DISCRIMINANT => read!(TradeBust, TradeBustLog),
+        REFERRAL_FEE_LOG_DISCRIMINANT => read!(ReferralFee, ReferralFeeLog),
+        MMP_TRIGGERED_LOG_DISCRIMINANT => read!(MmpTriggered, MmpTriggeredLog),
+        MARKET_PAUSED_LOG_DISCRIMINANT => read!(MarketPaused, MarketPausedLog),
+        RFQ_LOG_DISCRIMINANT => read!(Rfq, RfqLog),
+        PEGGED_ORDER_PLACED_LOG_DISCRIMINANT => read!(PeggedOrderPlaced, PeggedOrderPlacedLog),
+        DEPTH_SNAPSHOT_LOG_DISCRIMINANT => {
+            // Safety: the log is plain data.
+            unsafe { read_log::<DepthSnapshotLog>(body) }
+                .map(|(log, version)| (ManifestEvent::DepthSnapshot(Box::new(log)), version))
+        }
+        _ => None,
+    }
+}
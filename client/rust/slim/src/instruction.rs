@@ -1,8 +1,11 @@
 //! Instruction builders for Manifest operations.
 
 use crate::constants::{
-    DataIndex, OrderType, MANIFEST_PROGRAM_ID, NO_EXPIRATION_LAST_VALID_SLOT, SYSTEM_PROGRAM_ID,
-    TOKEN_PROGRAM_ID,
+    DataIndex, OrderType, PegReference, SelfTradePrevention, ASSOCIATED_TOKEN_PROGRAM_ID,
+    BPF_LOADER_UPGRADEABLE_PROGRAM_ID, ED25519_PROGRAM_ID, INSTRUCTIONS_SYSVAR_ID,
+    MANIFEST_PROGRAM_ID, MARKET_FLAG_CPI_ALLOWLIST, MARKET_FLAG_EXTENSION, MARKET_FLAG_FEES,
+    MARKET_FLAG_MIN_ORDER_SIZE, MARKET_FLAG_PRICE_BAND, NO_EXPIRATION_LAST_VALID_SLOT,
+    SYSTEM_PROGRAM_ID, TOKEN_PROGRAM_ID,
 };
 use solana_instruction::{AccountMeta, Instruction};
 use solana_pubkey::Pubkey;
@@ -25,6 +28,39 @@ pub enum ManifestInstruction {
     GlobalEvict = 11,
     GlobalClean = 12,
     SwapV2 = 13,
+    SetSeatConfig = 14,
+    DepositIdempotent = 15,
+    SwapToSeat = 16,
+    FillRfq = 17,
+    ArchiveSeat = 18,
+    RestoreSeat = 19,
+    CommitSwap = 20,
+    RevealSwap = 21,
+    GetMarketPrices = 22,
+    EmitDepthSnapshot = 23,
+    ClaimPoints = 24,
+    GetSeatPoints = 25,
+    PlaceStopOrder = 26,
+    CancelStopOrder = 27,
+    TriggerStopOrders = 28,
+    WithdrawAsQuote = 29,
+    Heartbeat = 30,
+    CancelOnHeartbeatTimeout = 31,
+    Rebalance = 32,
+    UpdateVolatility = 33,
+    EnterRecovery = 34,
+    RecoveryWithdraw = 35,
+    ResetPriceBand = 36,
+    ClaimFees = 37,
+    BustTrade = 38,
+    FillSignedOrder = 39,
+    CancelSignedOrders = 40,
+    SetMmp = 41,
+    SetPaused = 42,
+    PostRfq = 43,
+    PlacePeggedOrder = 44,
+    CancelPeggedOrder = 45,
+    RepricePeggedOrders = 46,
 }
 
 /// Get the vault PDA for a market and mint.
@@ -45,6 +81,82 @@ pub fn get_global_vault_address(mint: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[b"global-vault", mint.as_ref()], &MANIFEST_PROGRAM_ID)
 }
 
+/// Get the seat archive PDA for a market.
+pub fn get_seat_archive_address(market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"seat-archive", market.as_ref()], &MANIFEST_PROGRAM_ID)
+}
+
+/// Get the swap commitment PDA of a trader on a market.
+pub fn get_swap_commitment_address(market: &Pubkey, trader: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"swap-commitment", market.as_ref(), trader.as_ref()],
+        &MANIFEST_PROGRAM_ID,
+    )
+}
+
+/// Get the stop orders PDA of a trader on a market.
+pub fn get_stop_orders_address(market: &Pubkey, trader: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"stop-orders", market.as_ref(), trader.as_ref()],
+        &MANIFEST_PROGRAM_ID,
+    )
+}
+
+/// Get the heartbeat PDA of a trader on a market.
+pub fn get_heartbeat_address(market: &Pubkey, trader: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"heartbeat", market.as_ref(), trader.as_ref()],
+        &MANIFEST_PROGRAM_ID,
+    )
+}
+
+/// Get the volatility estimate PDA of a market.
+pub fn get_market_volatility_address(market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"volatility", market.as_ref()], &MANIFEST_PROGRAM_ID)
+}
+
+/// Get the PDA that records a busted fill, from the sequence numbers in its
+/// FillLog.
+pub fn get_trade_bust_address(
+    market: &Pubkey,
+    maker_sequence_number: u64,
+    taker_sequence_number: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"trade-bust",
+            market.as_ref(),
+            &maker_sequence_number.to_le_bytes(),
+            &taker_sequence_number.to_le_bytes(),
+        ],
+        &MANIFEST_PROGRAM_ID,
+    )
+}
+
+/// Get the signed order nonces PDA of a maker on a market.
+pub fn get_signed_order_nonces_address(market: &Pubkey, maker: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"signed-order-nonces", market.as_ref(), maker.as_ref()],
+        &MANIFEST_PROGRAM_ID,
+    )
+}
+
+/// Get the pegged orders PDA of a trader on a market.
+pub fn get_pegged_orders_address(market: &Pubkey, trader: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"pegged-orders", market.as_ref(), trader.as_ref()],
+        &MANIFEST_PROGRAM_ID,
+    )
+}
+
+/// Get the RFQ PDA of a taker on a market.
+pub fn get_rfq_address(market: &Pubkey, taker: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"rfq", market.as_ref(), taker.as_ref()],
+        &MANIFEST_PROGRAM_ID,
+    )
+}
+
 /// Create a CreateMarket instruction.
 ///
 /// # Accounts
@@ -64,13 +176,558 @@ pub fn create_market_instruction(
     quote_mint: Pubkey,
     token_program: Pubkey,
     token_program_22: Pubkey,
+) -> Instruction {
+    create_market_with_flags_instruction(
+        payer,
+        market,
+        base_mint,
+        quote_mint,
+        token_program,
+        token_program_22,
+        0,
+    )
+}
+
+/// Create a CreateMarket instruction with MARKET_FLAG_* bits. Same accounts
+/// as create_market_instruction. The flags cannot be changed later.
+pub fn create_market_with_flags_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    token_program: Pubkey,
+    token_program_22: Pubkey,
+    market_flags: u8,
+) -> Instruction {
+    create_market_with_trading_hours_instruction(
+        payer,
+        market,
+        base_mint,
+        quote_mint,
+        token_program,
+        token_program_22,
+        market_flags,
+        0,
+        0,
+        0,
+    )
+}
+
+/// Create a CreateMarket instruction for a market that only takes new orders
+/// and swaps on trading_days (bit 0 is Monday) from open_minute until
+/// close_minute of the UTC day. Zero trading_days is always open. Same
+/// accounts as create_market_instruction. Cannot be changed later.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_trading_hours_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    token_program: Pubkey,
+    token_program_22: Pubkey,
+    market_flags: u8,
+    trading_days: u8,
+    open_minute: u16,
+    close_minute: u16,
+) -> Instruction {
+    create_market_with_min_resting_slots_instruction(
+        payer,
+        market,
+        base_mint,
+        quote_mint,
+        token_program,
+        token_program_22,
+        market_flags,
+        trading_days,
+        open_minute,
+        close_minute,
+        0,
+    )
+}
+
+/// Same as create_market_with_trading_hours_instruction for a market where
+/// makers cannot cancel an order until it has rested min_resting_slots. Zero
+/// is off. Cannot be changed later.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_min_resting_slots_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    token_program: Pubkey,
+    token_program_22: Pubkey,
+    market_flags: u8,
+    trading_days: u8,
+    open_minute: u16,
+    close_minute: u16,
+    min_resting_slots: u32,
+) -> Instruction {
+    create_market_with_taker_delay_instruction(
+        payer,
+        market,
+        base_mint,
+        quote_mint,
+        token_program,
+        token_program_22,
+        market_flags,
+        trading_days,
+        open_minute,
+        close_minute,
+        min_resting_slots,
+        0,
+    )
+}
+
+/// Same as create_market_with_min_resting_slots_instruction for a market
+/// where takers commit to a swap and can only execute it taker_delay_slots
+/// later, see commit_swap_instruction. Zero is off. Cannot be changed later.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_taker_delay_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    token_program: Pubkey,
+    token_program_22: Pubkey,
+    market_flags: u8,
+    trading_days: u8,
+    open_minute: u16,
+    close_minute: u16,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+) -> Instruction {
+    create_market_with_points_instruction(
+        payer,
+        market,
+        base_mint,
+        quote_mint,
+        token_program,
+        token_program_22,
+        market_flags,
+        trading_days,
+        open_minute,
+        close_minute,
+        min_resting_slots,
+        taker_delay_slots,
+        0,
+        0,
+    )
+}
+
+/// Same as create_market_with_taker_delay_instruction for a market that
+/// gives seats with a seat config points per quote atom filled, weighted as
+/// maker and as taker, see claim_points_instruction. Zero for both is off.
+/// Cannot be changed later.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_points_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    token_program: Pubkey,
+    token_program_22: Pubkey,
+    market_flags: u8,
+    trading_days: u8,
+    open_minute: u16,
+    close_minute: u16,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+) -> Instruction {
+    create_market_with_cpi_allowlist_instruction(
+        payer,
+        market,
+        base_mint,
+        quote_mint,
+        token_program,
+        token_program_22,
+        market_flags,
+        trading_days,
+        open_minute,
+        close_minute,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        &[],
+    )
+}
+
+/// Same as create_market_with_points_instruction for a market where swaps,
+/// RFQ fills and batch updates that place or replace orders only work through
+/// a CPI from one of the cpi_allowlist programs, at most
+/// MAX_CPI_ALLOWLIST_PROGRAMS. Those calls need INSTRUCTIONS_SYSVAR_ID as
+/// their last account. Empty is off. Cannot be changed later.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_cpi_allowlist_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    token_program: Pubkey,
+    token_program_22: Pubkey,
+    market_flags: u8,
+    trading_days: u8,
+    open_minute: u16,
+    close_minute: u16,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist: &[Pubkey],
+) -> Instruction {
+    create_market_with_min_order_size_instruction(
+        payer,
+        market,
+        base_mint,
+        quote_mint,
+        token_program,
+        token_program_22,
+        market_flags,
+        trading_days,
+        open_minute,
+        close_minute,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        cpi_allowlist,
+        0,
+        0,
+    )
+}
+
+/// Same as create_market_with_cpi_allowlist_instruction for a market where
+/// orders that could rest need at least min_base_atoms and a value of at least
+/// min_quote_atoms at their price. Both zero is off. Cannot be changed later.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_min_order_size_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    token_program: Pubkey,
+    token_program_22: Pubkey,
+    market_flags: u8,
+    trading_days: u8,
+    open_minute: u16,
+    close_minute: u16,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist: &[Pubkey],
+    min_base_atoms: u64,
+    min_quote_atoms: u64,
+) -> Instruction {
+    create_market_with_price_band_instruction(
+        payer,
+        market,
+        base_mint,
+        quote_mint,
+        token_program,
+        token_program_22,
+        market_flags,
+        trading_days,
+        open_minute,
+        close_minute,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        cpi_allowlist,
+        min_base_atoms,
+        min_quote_atoms,
+        0,
+    )
+}
+
+/// Same as create_market_with_min_order_size_instruction for a market where
+/// takers cannot fill further than price_band_bps from the last trade price.
+/// Zero is off. Cannot be changed later. See reset_price_band_instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_price_band_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    token_program: Pubkey,
+    token_program_22: Pubkey,
+    market_flags: u8,
+    trading_days: u8,
+    open_minute: u16,
+    close_minute: u16,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist: &[Pubkey],
+    min_base_atoms: u64,
+    min_quote_atoms: u64,
+    price_band_bps: u16,
+) -> Instruction {
+    create_market_with_fees_instruction(
+        payer,
+        market,
+        base_mint,
+        quote_mint,
+        token_program,
+        token_program_22,
+        market_flags,
+        trading_days,
+        open_minute,
+        close_minute,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        cpi_allowlist,
+        min_base_atoms,
+        min_quote_atoms,
+        price_band_bps,
+        0,
+        0,
+        0,
+        Pubkey::default(),
+        0,
+    )
+}
+
+/// Same as create_market_with_price_band_instruction for a market where
+/// makers and takers pay maker_fee_bps and taker_fee_bps of what they receive
+/// from fills, up to MAX_FEE_BPS. Both zero is off. Swaps with a referrer pay
+/// it referral_fee_bps of the taker fee, see [`with_referrer`].
+/// claim_fees_instruction pays the rest to token accounts owned by
+/// fee_recipient. Takers whose rolling volume reaches a multiple of
+/// fee_tier_quote_atoms in FEE_TIERS get a taker fee discount, zero is no
+/// tiers. Cannot be changed later.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_fees_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    token_program: Pubkey,
+    token_program_22: Pubkey,
+    market_flags: u8,
+    trading_days: u8,
+    open_minute: u16,
+    close_minute: u16,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist: &[Pubkey],
+    min_base_atoms: u64,
+    min_quote_atoms: u64,
+    price_band_bps: u16,
+    maker_fee_bps: u16,
+    taker_fee_bps: u16,
+    referral_fee_bps: u16,
+    fee_recipient: Pubkey,
+    fee_tier_quote_atoms: u64,
+) -> Instruction {
+    create_market_with_pauser_instruction(
+        payer,
+        market,
+        base_mint,
+        quote_mint,
+        token_program,
+        token_program_22,
+        market_flags,
+        trading_days,
+        open_minute,
+        close_minute,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        cpi_allowlist,
+        min_base_atoms,
+        min_quote_atoms,
+        price_band_bps,
+        maker_fee_bps,
+        taker_fee_bps,
+        referral_fee_bps,
+        fee_recipient,
+        fee_tier_quote_atoms,
+        Pubkey::default(),
+    )
+}
+
+/// Same as create_market_with_fees_instruction for a market whose pauser can
+/// stop new orders, swaps and fills with [`set_paused_instruction`]. The
+/// default pubkey is no pauser. Sets MARKET_FLAG_EXTENSION when there is one.
+/// Cannot be changed later.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_pauser_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    token_program: Pubkey,
+    token_program_22: Pubkey,
+    market_flags: u8,
+    trading_days: u8,
+    open_minute: u16,
+    close_minute: u16,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist: &[Pubkey],
+    min_base_atoms: u64,
+    min_quote_atoms: u64,
+    price_band_bps: u16,
+    maker_fee_bps: u16,
+    taker_fee_bps: u16,
+    referral_fee_bps: u16,
+    fee_recipient: Pubkey,
+    fee_tier_quote_atoms: u64,
+    pauser: Pubkey,
+) -> Instruction {
+    create_market_with_extension_instruction(
+        payer,
+        market,
+        base_mint,
+        quote_mint,
+        token_program,
+        token_program_22,
+        market_flags,
+        trading_days,
+        open_minute,
+        close_minute,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        cpi_allowlist,
+        min_base_atoms,
+        min_quote_atoms,
+        price_band_bps,
+        maker_fee_bps,
+        taker_fee_bps,
+        referral_fee_bps,
+        fee_recipient,
+        fee_tier_quote_atoms,
+        pauser,
+        0,
+    )
+}
+
+/// Same as create_market_with_pauser_instruction with the extension flags
+/// the market starts with. Only MARKET_EXTENSION_FLAG_FILL_RECORDS can be
+/// set, which keeps the latest fills on the market so they can be busted
+/// with [`bust_trade_instruction`]. Sets MARKET_FLAG_EXTENSION when there
+/// are any.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_extension_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    token_program: Pubkey,
+    token_program_22: Pubkey,
+    market_flags: u8,
+    trading_days: u8,
+    open_minute: u16,
+    close_minute: u16,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist: &[Pubkey],
+    min_base_atoms: u64,
+    min_quote_atoms: u64,
+    price_band_bps: u16,
+    maker_fee_bps: u16,
+    taker_fee_bps: u16,
+    referral_fee_bps: u16,
+    fee_recipient: Pubkey,
+    fee_tier_quote_atoms: u64,
+    pauser: Pubkey,
+    extension_flags: u8,
 ) -> Instruction {
     let (base_vault, _) = get_vault_address(&market, &base_mint);
     let (quote_vault, _) = get_vault_address(&market, &quote_mint);
+    let has_allowlist: bool = !cpi_allowlist.is_empty();
+    let has_min_order_size: bool = min_base_atoms != 0 || min_quote_atoms != 0;
+    let has_price_band: bool = price_band_bps != 0;
+    let has_fees: bool = maker_fee_bps != 0 || taker_fee_bps != 0;
+    let mut market_flags: u8 = market_flags;
+    if has_allowlist {
+        market_flags |= MARKET_FLAG_CPI_ALLOWLIST;
+    }
+    if has_min_order_size {
+        market_flags |= MARKET_FLAG_MIN_ORDER_SIZE;
+    }
+    if has_price_band {
+        market_flags |= MARKET_FLAG_PRICE_BAND;
+    }
+    if has_fees {
+        market_flags |= MARKET_FLAG_FEES;
+    }
+    let has_extension: bool = market_flags & MARKET_FLAG_EXTENSION != 0
+        || pauser != Pubkey::default()
+        || extension_flags != 0;
+    if has_extension {
+        market_flags |= MARKET_FLAG_EXTENSION;
+    }
+    // The minimum, the band, the fees, the extension and the allowlist come
+    // after all the other settings.
+    let has_points: bool = maker_points_weight != 0
+        || taker_points_weight != 0
+        || has_allowlist
+        || has_min_order_size
+        || has_price_band
+        || has_fees
+        || has_extension;
+    let has_delay: bool = taker_delay_slots != 0 || has_points;
+    let mut data = vec![ManifestInstruction::CreateMarket as u8];
+    if market_flags != 0 || trading_days != 0 || min_resting_slots != 0 || has_delay {
+        data.push(market_flags);
+    }
+    if trading_days != 0 || min_resting_slots != 0 || has_delay {
+        data.push(trading_days);
+        data.extend_from_slice(&open_minute.to_le_bytes());
+        data.extend_from_slice(&close_minute.to_le_bytes());
+    }
+    if min_resting_slots != 0 || has_delay {
+        data.extend_from_slice(&min_resting_slots.to_le_bytes());
+    }
+    if has_delay {
+        data.extend_from_slice(&taker_delay_slots.to_le_bytes());
+    }
+    if has_points {
+        data.extend_from_slice(&maker_points_weight.to_le_bytes());
+        data.extend_from_slice(&taker_points_weight.to_le_bytes());
+    }
+    if has_min_order_size {
+        data.extend_from_slice(&min_base_atoms.to_le_bytes());
+        data.extend_from_slice(&min_quote_atoms.to_le_bytes());
+    }
+    if has_price_band {
+        data.extend_from_slice(&price_band_bps.to_le_bytes());
+    }
+    if has_fees {
+        data.extend_from_slice(&maker_fee_bps.to_le_bytes());
+        data.extend_from_slice(&taker_fee_bps.to_le_bytes());
+        data.extend_from_slice(&referral_fee_bps.to_le_bytes());
+        data.extend_from_slice(fee_recipient.as_ref());
+        data.extend_from_slice(&fee_tier_quote_atoms.to_le_bytes());
+    }
+    if has_extension {
+        data.extend_from_slice(pauser.as_ref());
+        data.push(extension_flags);
+    }
+    for program in cpi_allowlist {
+        data.extend_from_slice(program.as_ref());
+    }
 
     Instruction::new_with_bytes(
         MANIFEST_PROGRAM_ID,
-        &[ManifestInstruction::CreateMarket as u8],
+        &data,
         vec![
             AccountMeta::new(payer, true),
             AccountMeta::new(market, true),
@@ -85,7 +742,8 @@ pub fn create_market_instruction(
     )
 }
 
-/// Create a ClaimSeat instruction.
+/// Create a ClaimSeat instruction. Succeeds without changes if the payer
+/// already has a seat.
 ///
 /// # Accounts
 /// 0. `[writable, signer]` payer - The trader claiming a seat
@@ -177,6 +835,45 @@ pub fn deposit_instruction(
     )
 }
 
+/// Create a DepositIdempotent instruction. Same as deposit, but claims a seat
+/// for the payer first if it does not have one.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - The trader depositing
+/// 1. `[writable]` market - The market account
+/// 2. `[writable]` trader_token - Trader's token account
+/// 3. `[writable]` vault - Market vault PDA
+/// 4. `[]` token_program - Token program
+/// 5. `[]` mint - Token mint (required for token-2022)
+/// 6. `[]` system_program - System program
+pub fn deposit_idempotent_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    trader_token: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+    params: DepositParams,
+) -> Instruction {
+    let (vault, _) = get_vault_address(&market, &mint);
+
+    let mut data = vec![ManifestInstruction::DepositIdempotent as u8];
+    data.extend_from_slice(&params.serialize());
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new(trader_token, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+    )
+}
+
 /// Parameters for withdraw instruction.
 #[derive(Debug, Clone)]
 pub struct WithdrawParams {
@@ -220,7 +917,8 @@ impl WithdrawParams {
 /// # Accounts
 /// 0. `[writable, signer]` payer - The trader withdrawing
 /// 1. `[writable]` market - The market account
-/// 2. `[writable]` trader_token - Trader's token account
+/// 2. `[writable]` trader_token - Destination token account, can be owned by
+///    any wallet
 /// 3. `[writable]` vault - Market vault PDA
 /// 4. `[]` token_program - Token program
 /// 5. `[]` mint - Token mint
@@ -251,6 +949,48 @@ pub fn withdraw_instruction(
     )
 }
 
+/// Create a WithdrawAsQuote instruction that sells the payer's withdrawable
+/// base into the bids, no lower than `max_price_impact_bps` below the best
+/// bid, and withdraws all the quote. Base the bids do not take stays on the
+/// seat.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - The trader withdrawing
+/// 1. `[writable]` market - The market account
+/// 2. `[writable]` trader_token - Destination quote token account, can be
+///    owned by any wallet
+/// 3. `[writable]` vault - Market quote vault PDA
+/// 4. `[]` token_program - Quote token program
+/// 5. `[]` quote_mint - Quote mint
+pub fn withdraw_as_quote_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    trader_token: Pubkey,
+    quote_mint: Pubkey,
+    token_program: Pubkey,
+    max_price_impact_bps: u16,
+) -> Instruction {
+    let (vault, _) = get_vault_address(&market, &quote_mint);
+
+    let mut data = vec![ManifestInstruction::WithdrawAsQuote as u8];
+    data.extend_from_slice(&max_price_impact_bps.to_le_bytes());
+    // No trader index hint.
+    data.push(0);
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new(trader_token, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(quote_mint, false),
+        ],
+    )
+}
+
 /// Parameters for swap instruction.
 #[derive(Debug, Clone)]
 pub struct SwapParams {
@@ -258,6 +998,16 @@ pub struct SwapParams {
     pub out_atoms: u64,
     pub is_base_in: bool,
     pub is_exact_in: bool,
+    /// Skip makers that would fill fewer base atoms than this. Zero for no
+    /// minimum.
+    pub min_fill_atoms: u64,
+    /// Pass over global orders instead of stopping at them when the global
+    /// accounts are not included.
+    pub skip_global_orders: bool,
+    /// Exact out is what the trader receives after Token-2022 transfer fees,
+    /// and the swap fails if less arrives. The output mint must be included.
+    /// Only for exact out.
+    pub exact_out_net_of_fees: bool,
 }
 
 impl SwapParams {
@@ -267,15 +1017,44 @@ impl SwapParams {
             out_atoms,
             is_base_in,
             is_exact_in,
+            min_fill_atoms: 0,
+            skip_global_orders: false,
+            exact_out_net_of_fees: false,
         }
     }
 
+    pub fn with_min_fill_atoms(mut self, min_fill_atoms: u64) -> Self {
+        self.min_fill_atoms = min_fill_atoms;
+        self
+    }
+
+    pub fn with_skip_global_orders(mut self) -> Self {
+        self.skip_global_orders = true;
+        self
+    }
+
+    pub fn with_exact_out_net_of_fees(mut self) -> Self {
+        self.exact_out_net_of_fees = true;
+        self
+    }
+
+    fn flags(&self) -> u8 {
+        (self.skip_global_orders as u8) | (self.exact_out_net_of_fees as u8) << 1
+    }
+
     fn serialize(&self) -> Vec<u8> {
-        let mut data = Vec::with_capacity(18);
+        let mut data = Vec::with_capacity(26);
         data.extend_from_slice(&self.in_atoms.to_le_bytes());
         data.extend_from_slice(&self.out_atoms.to_le_bytes());
         data.push(self.is_base_in as u8);
         data.push(self.is_exact_in as u8);
+        let flags: u8 = self.flags();
+        if self.min_fill_atoms != 0 || flags != 0 {
+            data.extend_from_slice(&self.min_fill_atoms.to_le_bytes());
+        }
+        if flags != 0 {
+            data.push(flags);
+        }
         data
     }
 }
@@ -294,6 +1073,10 @@ impl SwapParams {
 /// 8. `[]` base_mint (optional, for token-2022)
 /// 9. `[]` token_program_quote (optional, if different from base)
 /// 10. `[]` quote_mint (optional, for token-2022)
+/// 11. `[]` associated_token_program (optional, see [`with_create_token_accounts`])
+///
+/// Appending the SPL memo program sends every fill to it as a text memo.
+/// A referrer token account goes after it, see [`with_referrer`].
 pub fn swap_instruction(
     payer: Pubkey,
     market: Pubkey,
@@ -340,23 +1123,93 @@ pub fn swap_instruction(
     Instruction::new_with_bytes(MANIFEST_PROGRAM_ID, &data, accounts)
 }
 
-/// Parameters for placing a single order.
-#[derive(Debug, Clone)]
-pub struct PlaceOrderParams {
-    pub base_atoms: u64,
-    pub price_mantissa: u32,
-    pub price_exponent: i8,
-    pub is_bid: bool,
-    pub last_valid_slot: u32,
-    pub order_type: OrderType,
+/// Make a Swap or SwapToSeat instruction create the trader's associated token
+/// accounts that do not exist yet, paid by the payer. The instruction must
+/// have been built with both include_base_mint and include_quote_mint.
+pub fn with_create_token_accounts(mut instruction: Instruction) -> Instruction {
+    instruction.accounts[0].is_writable = true;
+    instruction.accounts.push(AccountMeta::new_readonly(
+        ASSOCIATED_TOKEN_PROGRAM_ID,
+        false,
+    ));
+    instruction
 }
 
-impl PlaceOrderParams {
-    pub fn new(
-        base_atoms: u64,
-        price_mantissa: u32,
-        price_exponent: i8,
-        is_bid: bool,
+/// Make a Swap or SwapToSeat instruction pay the market's referral share of
+/// the taker fee to referrer_token, a token account in the output mint. The
+/// SPL memo program, when used, has to be appended first.
+pub fn with_referrer(mut instruction: Instruction, referrer_token: Pubkey) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new(referrer_token, false));
+    instruction
+}
+
+/// Create a SwapToSeat instruction. Same accounts and params as swap, but when
+/// the payer already has a seat the output is credited to the seat instead of
+/// the trader's token account.
+#[allow(clippy::too_many_arguments)]
+pub fn swap_to_seat_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    trader_base: Pubkey,
+    trader_quote: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    token_program_base: Pubkey,
+    token_program_quote: Option<Pubkey>,
+    include_base_mint: bool,
+    include_quote_mint: bool,
+    params: SwapParams,
+) -> Instruction {
+    let mut instruction = swap_instruction(
+        payer,
+        market,
+        trader_base,
+        trader_quote,
+        base_mint,
+        quote_mint,
+        token_program_base,
+        token_program_quote,
+        include_base_mint,
+        include_quote_mint,
+        params,
+    );
+    instruction.data[0] = ManifestInstruction::SwapToSeat as u8;
+    instruction
+}
+
+/// Parameters for placing a single order.
+#[derive(Debug, Clone)]
+pub struct PlaceOrderParams {
+    pub base_atoms: u64,
+    pub price_mantissa: u32,
+    pub price_exponent: i8,
+    pub is_bid: bool,
+    pub last_valid_slot: u32,
+    pub order_type: OrderType,
+    /// Base atoms an ImmediateOrCancel order has to fill, or the whole batch
+    /// fails. Zero for no minimum, the only value other order types allow.
+    pub min_fill_base_atoms: u64,
+    /// What the order does when it would match one from the same seat.
+    pub self_trade_prevention: SelfTradePrevention,
+    /// Base atoms an Iceberg order shows at a time. Zero for other order
+    /// types.
+    pub display_base_atoms: u64,
+    /// Quote atoms to buy or sell at the limit price, converted by the
+    /// program to base atoms rounded down. base_atoms is zero when set.
+    pub quote_atoms: u64,
+    /// Unix timestamp the order is valid until, in place of last_valid_slot.
+    /// Zero for none.
+    pub last_valid_unix_timestamp: u32,
+}
+
+impl PlaceOrderParams {
+    pub fn new(
+        base_atoms: u64,
+        price_mantissa: u32,
+        price_exponent: i8,
+        is_bid: bool,
         order_type: OrderType,
     ) -> Self {
         Self {
@@ -366,6 +1219,25 @@ impl PlaceOrderParams {
             is_bid,
             last_valid_slot: NO_EXPIRATION_LAST_VALID_SLOT,
             order_type,
+            min_fill_base_atoms: 0,
+            self_trade_prevention: SelfTradePrevention::None,
+            display_base_atoms: 0,
+            quote_atoms: 0,
+            last_valid_unix_timestamp: 0,
+        }
+    }
+
+    /// An order sized by the quote atoms it buys or sells at the limit price.
+    pub fn new_quote_sized(
+        quote_atoms: u64,
+        price_mantissa: u32,
+        price_exponent: i8,
+        is_bid: bool,
+        order_type: OrderType,
+    ) -> Self {
+        Self {
+            quote_atoms,
+            ..Self::new(0, price_mantissa, price_exponent, is_bid, order_type)
         }
     }
 
@@ -374,6 +1246,34 @@ impl PlaceOrderParams {
         self
     }
 
+    /// Expires the order after a unix timestamp instead of a slot. Not for
+    /// reverse orders.
+    pub fn with_expiration_unix_timestamp(mut self, last_valid_unix_timestamp: u32) -> Self {
+        self.last_valid_slot = NO_EXPIRATION_LAST_VALID_SLOT;
+        self.last_valid_unix_timestamp = last_valid_unix_timestamp;
+        self
+    }
+
+    pub fn with_min_fill(mut self, min_fill_base_atoms: u64) -> Self {
+        self.min_fill_base_atoms = min_fill_base_atoms;
+        self
+    }
+
+    pub fn with_self_trade_prevention(
+        mut self,
+        self_trade_prevention: SelfTradePrevention,
+    ) -> Self {
+        self.self_trade_prevention = self_trade_prevention;
+        self
+    }
+
+    /// Makes the order an Iceberg that shows display_base_atoms at a time.
+    pub fn with_display_size(mut self, display_base_atoms: u64) -> Self {
+        self.order_type = OrderType::Iceberg;
+        self.display_base_atoms = display_base_atoms;
+        self
+    }
+
     fn serialize(&self) -> Vec<u8> {
         let mut data = Vec::with_capacity(16);
         data.extend_from_slice(&self.base_atoms.to_le_bytes());
@@ -424,12 +1324,139 @@ impl CancelOrderParams {
     }
 }
 
+/// Parameters for changing the size and optionally the price of a resting
+/// order. Reducing the size at the same price keeps the order's queue
+/// priority, zero base atoms cancels it.
+#[derive(Debug, Clone)]
+pub struct ReplaceOrderParams {
+    pub order_sequence_number: u64,
+    pub order_index_hint: Option<DataIndex>,
+    pub base_atoms: u64,
+    /// New price mantissa and exponent, None to keep the price.
+    pub price: Option<(u32, i8)>,
+}
+
+impl ReplaceOrderParams {
+    /// Keeps the price and sets the size to base_atoms.
+    pub fn new(order_sequence_number: u64, base_atoms: u64) -> Self {
+        Self {
+            order_sequence_number,
+            order_index_hint: None,
+            base_atoms,
+            price: None,
+        }
+    }
+
+    pub fn with_price(mut self, price_mantissa: u32, price_exponent: i8) -> Self {
+        self.price = Some((price_mantissa, price_exponent));
+        self
+    }
+
+    pub fn with_hint(mut self, order_index_hint: DataIndex) -> Self {
+        self.order_index_hint = Some(order_index_hint);
+        self
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(27);
+        data.extend_from_slice(&self.order_sequence_number.to_le_bytes());
+        match self.order_index_hint {
+            Some(hint) => {
+                data.push(1);
+                data.extend_from_slice(&hint.to_le_bytes());
+            }
+            None => {
+                data.push(0);
+            }
+        }
+        data.extend_from_slice(&self.base_atoms.to_le_bytes());
+        match self.price {
+            Some((price_mantissa, price_exponent)) => {
+                data.push(1);
+                data.extend_from_slice(&price_mantissa.to_le_bytes());
+                data.push(price_exponent as u8);
+            }
+            None => {
+                data.push(0);
+            }
+        }
+        data
+    }
+}
+
+/// Parameters for reducing a resting order in place. It keeps its sequence
+/// number and queue priority, and never grows even when it was partly filled
+/// in the meantime. Reducing by at least what is left cancels it.
+#[derive(Debug, Clone)]
+pub struct AmendOrderParams {
+    pub order_sequence_number: u64,
+    pub order_index_hint: Option<DataIndex>,
+    pub reduce_base_atoms: u64,
+}
+
+impl AmendOrderParams {
+    pub fn new(order_sequence_number: u64, reduce_base_atoms: u64) -> Self {
+        Self {
+            order_sequence_number,
+            order_index_hint: None,
+            reduce_base_atoms,
+        }
+    }
+
+    pub fn with_hint(mut self, order_index_hint: DataIndex) -> Self {
+        self.order_index_hint = Some(order_index_hint);
+        self
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(21);
+        data.extend_from_slice(&self.order_sequence_number.to_le_bytes());
+        match self.order_index_hint {
+            Some(hint) => {
+                data.push(1);
+                data.extend_from_slice(&hint.to_le_bytes());
+            }
+            None => {
+                data.push(0);
+            }
+        }
+        data.extend_from_slice(&self.reduce_base_atoms.to_le_bytes());
+        data
+    }
+}
+
 /// Parameters for batch update (place orders and cancel orders).
 #[derive(Debug, Clone, Default)]
 pub struct BatchUpdateParams {
     pub trader_index_hint: Option<DataIndex>,
     pub cancels: Vec<CancelOrderParams>,
+    /// Applied after the cancels and before the replaces.
+    pub amends: Vec<AmendOrderParams>,
+    /// Applied after the cancels and before the orders. Their results are
+    /// returned after those of the orders.
+    pub replaces: Vec<ReplaceOrderParams>,
     pub orders: Vec<PlaceOrderParams>,
+    /// Have the payer fund a block for every order before placing.
+    pub reserve_blocks: bool,
+    /// Read each order's last_valid_slot as slots from when the transaction
+    /// lands instead of an absolute slot. Does not apply to reverse orders.
+    pub relative_expiry: bool,
+    /// Cancel every resting order of the payer before the cancels and
+    /// orders, except ones that have not rested for the minimum resting time.
+    pub cancel_all: bool,
+    /// Skip makers that would fill fewer base atoms than this. Only applies
+    /// to orders that cannot rest.
+    pub min_fill_atoms: u64,
+    /// Takers that would fill fewer base atoms than this skip the orders that
+    /// rest, unless they take all of one.
+    pub min_taker_atoms: u64,
+    /// One tag per order, stored on it and echoed in its fills. Empty for
+    /// no tags.
+    pub tags: Vec<u32>,
+    /// Pairs of indices into orders. Once both rest, a fill on either
+    /// cancels the other. Each order can be in one pair, and global and
+    /// reverse orders cannot be in any.
+    pub one_cancels_others: Vec<(u8, u8)>,
 }
 
 impl BatchUpdateParams {
@@ -437,6 +1464,36 @@ impl BatchUpdateParams {
         Self::default()
     }
 
+    pub fn with_reserve_blocks(mut self) -> Self {
+        self.reserve_blocks = true;
+        self
+    }
+
+    pub fn with_relative_expiry(mut self) -> Self {
+        self.relative_expiry = true;
+        self
+    }
+
+    pub fn with_cancel_all(mut self) -> Self {
+        self.cancel_all = true;
+        self
+    }
+
+    pub fn with_min_fill_atoms(mut self, min_fill_atoms: u64) -> Self {
+        self.min_fill_atoms = min_fill_atoms;
+        self
+    }
+
+    pub fn with_min_taker_atoms(mut self, min_taker_atoms: u64) -> Self {
+        self.min_taker_atoms = min_taker_atoms;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<u32>) -> Self {
+        self.tags = tags;
+        self
+    }
+
     pub fn with_hint(mut self, trader_index_hint: DataIndex) -> Self {
         self.trader_index_hint = Some(trader_index_hint);
         self
@@ -447,11 +1504,28 @@ impl BatchUpdateParams {
         self
     }
 
+    pub fn add_amend(mut self, amend: AmendOrderParams) -> Self {
+        self.amends.push(amend);
+        self
+    }
+
+    pub fn add_replace(mut self, replace: ReplaceOrderParams) -> Self {
+        self.replaces.push(replace);
+        self
+    }
+
     pub fn add_order(mut self, order: PlaceOrderParams) -> Self {
         self.orders.push(order);
         self
     }
 
+    /// Links the orders at indices first and second, so a fill on one
+    /// cancels the other.
+    pub fn add_one_cancels_other(mut self, first: u8, second: u8) -> Self {
+        self.one_cancels_others.push((first, second));
+        self
+    }
+
     fn serialize(&self) -> Vec<u8> {
         let mut data = Vec::new();
 
@@ -480,6 +1554,120 @@ impl BatchUpdateParams {
             data.extend_from_slice(&order.serialize());
         }
 
+        // Optional trailing flags, replaces, IOC min fills, self trade
+        // prevention, one cancels other pairs, display sizes, amends, quote
+        // sizes, last valid unix timestamps, min fill, min taker and tags.
+        // Each is only included when its flag bit is set. The display sizes,
+        // amends, quote sizes, timestamps, min fill, min taker and tags have
+        // their bits in a second flags byte.
+        let has_tags: bool = !self.tags.is_empty();
+        let has_replaces: bool = !self.replaces.is_empty();
+        let has_ioc_min_fills: bool = self
+            .orders
+            .iter()
+            .any(|order| order.min_fill_base_atoms != 0);
+        let has_self_trade_prevention: bool = self
+            .orders
+            .iter()
+            .any(|order| order.self_trade_prevention != SelfTradePrevention::None);
+        let has_one_cancels_others: bool = !self.one_cancels_others.is_empty();
+        let has_display_sizes: bool = self
+            .orders
+            .iter()
+            .any(|order| order.display_base_atoms != 0);
+        let has_amends: bool = !self.amends.is_empty();
+        let has_quote_sizes: bool = self.orders.iter().any(|order| order.quote_atoms != 0);
+        let has_last_valid_unix_timestamps: bool = self
+            .orders
+            .iter()
+            .any(|order| order.last_valid_unix_timestamp != 0);
+        let more_flags: u8 = u8::from(has_display_sizes)
+            | (u8::from(has_amends) << 1)
+            | (u8::from(has_quote_sizes) << 2)
+            | (u8::from(has_last_valid_unix_timestamps) << 3)
+            | (u8::from(self.min_fill_atoms != 0) << 4)
+            | (u8::from(self.min_taker_atoms != 0) << 5)
+            | (u8::from(has_tags) << 6);
+        let flags: u8 = u8::from(self.reserve_blocks)
+            | (u8::from(self.relative_expiry) << 1)
+            | (u8::from(self.cancel_all) << 2)
+            | (u8::from(has_replaces) << 3)
+            | (u8::from(has_ioc_min_fills) << 4)
+            | (u8::from(has_self_trade_prevention) << 5)
+            | (u8::from(has_one_cancels_others) << 6)
+            | (u8::from(more_flags != 0) << 7);
+        if flags != 0 {
+            data.push(flags);
+        }
+        if more_flags != 0 {
+            data.push(more_flags);
+        }
+        if has_replaces {
+            let replaces_len = self.replaces.len() as u32;
+            data.extend_from_slice(&replaces_len.to_le_bytes());
+            for replace in &self.replaces {
+                data.extend_from_slice(&replace.serialize());
+            }
+        }
+        if has_ioc_min_fills {
+            data.extend_from_slice(&orders_len.to_le_bytes());
+            for order in &self.orders {
+                data.extend_from_slice(&order.min_fill_base_atoms.to_le_bytes());
+            }
+        }
+        if has_self_trade_prevention {
+            data.extend_from_slice(&orders_len.to_le_bytes());
+            for order in &self.orders {
+                data.push(order.self_trade_prevention as u8);
+            }
+        }
+        if has_one_cancels_others {
+            let one_cancels_others_len = self.one_cancels_others.len() as u32;
+            data.extend_from_slice(&one_cancels_others_len.to_le_bytes());
+            for (first, second) in &self.one_cancels_others {
+                data.push(*first);
+                data.push(*second);
+            }
+        }
+        if has_display_sizes {
+            data.extend_from_slice(&orders_len.to_le_bytes());
+            for order in &self.orders {
+                data.extend_from_slice(&order.display_base_atoms.to_le_bytes());
+            }
+        }
+        if has_amends {
+            let amends_len = self.amends.len() as u32;
+            data.extend_from_slice(&amends_len.to_le_bytes());
+            for amend in &self.amends {
+                data.extend_from_slice(&amend.serialize());
+            }
+        }
+        if has_quote_sizes {
+            data.extend_from_slice(&orders_len.to_le_bytes());
+            for order in &self.orders {
+                data.extend_from_slice(&order.quote_atoms.to_le_bytes());
+            }
+        }
+        if has_last_valid_unix_timestamps {
+            data.extend_from_slice(&orders_len.to_le_bytes());
+            for order in &self.orders {
+                data.extend_from_slice(&order.last_valid_unix_timestamp.to_le_bytes());
+            }
+        }
+        if self.min_fill_atoms != 0 {
+            data.extend_from_slice(&self.min_fill_atoms.to_le_bytes());
+        }
+        if self.min_taker_atoms != 0 {
+            data.extend_from_slice(&self.min_taker_atoms.to_le_bytes());
+        }
+        if has_tags {
+            let tags_len = self.tags.len() as u32;
+            data.extend_from_slice(&tags_len.to_le_bytes());
+            for tag in &self.tags {
+                data.extend_from_slice(&tag.to_le_bytes());
+            }
+        }
+
         data
     }
 }
@@ -492,6 +1680,11 @@ impl BatchUpdateParams {
 /// 2. `[]` system_program - System program
 /// 3-7. Optional global accounts for base if placing global orders
 /// 8-12. Optional global accounts for quote if placing global orders
+///
+/// Seats with a cosigner configured also need the cosigner appended as a
+/// readonly signer when the orders placed exceed the notional threshold.
+///
+/// Appending the SPL memo program sends every fill to it as a text memo.
 pub fn batch_update_instruction(
     payer: Pubkey,
     market: Pubkey,
@@ -578,3 +1771,1095 @@ pub fn expand_instruction(payer: Pubkey, market: Pubkey) -> Instruction {
         ],
     )
 }
+
+/// Create an Expand instruction that makes sure the market has at least
+/// `num_free_blocks` free blocks, expanding by however many are missing in one
+/// call. The payer covers the rent and does not have to be a trader.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - Pays for expansion
+/// 1. `[writable]` market - The market account
+/// 2. `[]` system_program - System program
+pub fn expand_n_instruction(payer: Pubkey, market: Pubkey, num_free_blocks: u32) -> Instruction {
+    let mut data = vec![ManifestInstruction::Expand as u8];
+    data.extend_from_slice(&num_free_blocks.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Create a SetSeatConfig instruction. Passing the default pubkey as the
+/// cosigner removes the config from the seat.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - The trader that owns the seat
+/// 1. `[writable]` market - The market account
+/// 2. `[]` system_program - System program
+/// 3. `[signer]` current_cosigner - Only if the seat already has a cosigner
+pub fn set_seat_config_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    cosigner: Pubkey,
+    cosign_notional_threshold_atoms: u64,
+    current_cosigner: Option<Pubkey>,
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::SetSeatConfig as u8];
+    data.extend_from_slice(cosigner.as_ref());
+    data.extend_from_slice(&cosign_notional_threshold_atoms.to_le_bytes());
+    // trader_index_hint
+    data.push(0);
+
+    let mut accounts = vec![
+        AccountMeta::new(payer, true),
+        AccountMeta::new(market, false),
+        AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+    ];
+    if let Some(current_cosigner) = current_cosigner {
+        accounts.push(AccountMeta::new_readonly(current_cosigner, true));
+    }
+
+    Instruction::new_with_bytes(MANIFEST_PROGRAM_ID, &data, accounts)
+}
+
+/// Create a PostRfq instruction that posts a request for quote of the taker,
+/// replacing their open one on the market. Makers answer with an [`RfqQuote`]
+/// for the sequence number in the [`RfqLog`] or the [`Rfq`] account. Zero
+/// `base_atoms` cancels the open request.
+///
+/// # Accounts
+/// 0. `[writable, signer]` taker - The trader asking for quotes, funds the RFQ account
+/// 1. `[]` market - The market account
+/// 2. `[]` system_program - System program
+/// 3. `[writable]` rfq - RFQ PDA of the taker
+///
+/// [`RfqLog`]: crate::RfqLog
+/// [`Rfq`]: crate::Rfq
+pub fn post_rfq_instruction(
+    taker: Pubkey,
+    market: Pubkey,
+    base_atoms: u64,
+    taker_is_buy: bool,
+    last_valid_slot: u32,
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::PostRfq as u8];
+    data.extend_from_slice(&base_atoms.to_le_bytes());
+    data.push(taker_is_buy as u8);
+    data.extend_from_slice(&last_valid_slot.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(taker, true),
+            AccountMeta::new_readonly(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(get_rfq_address(&market, &taker).0, false),
+        ],
+    )
+}
+
+/// A maker's quote for the whole size and side of an open RFQ, signed off
+/// chain for [`fill_rfq_instruction`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RfqQuote {
+    /// RFQ PDA of the taker, see [`get_rfq_address`].
+    pub rfq: Pubkey,
+    /// Sequence number of the request the quote answers.
+    pub sequence_number: u64,
+    pub price_mantissa: u32,
+    pub price_exponent: i8,
+    /// Last slot the quote can be filled in.
+    pub last_valid_slot: u32,
+}
+
+impl RfqQuote {
+    /// Bytes the maker signs, the borsh serialization of the quote.
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = Vec::with_capacity(49);
+        message.extend_from_slice(self.rfq.as_ref());
+        message.extend_from_slice(&self.sequence_number.to_le_bytes());
+        message.extend_from_slice(&self.price_mantissa.to_le_bytes());
+        message.push(self.price_exponent as u8);
+        message.extend_from_slice(&self.last_valid_slot.to_le_bytes());
+        message
+    }
+}
+
+/// Create a FillRfq instruction that fills the open RFQ of the taker against
+/// the best of `responses`, each a maker and their quote, settling from the
+/// seats of both sides. Only the taker signs the transaction, and right before
+/// it must come one [`ed25519_verify_instruction`] per response, in the same
+/// order, for the maker's signature of `quote.message()`. Quotes that expired,
+/// answer an older request, or whose maker lacks a seat or the balance are
+/// passed over. At most [`MAX_RFQ_RESPONSES`] responses.
+///
+/// # Accounts
+/// 0. `[signer]` taker - The trader that asked for the quotes
+/// 1. `[writable]` market - The market account
+/// 2. `[writable]` rfq - RFQ PDA of the taker
+/// 3. `[]` instructions_sysvar - Instructions sysvar
+///
+/// [`MAX_RFQ_RESPONSES`]: crate::MAX_RFQ_RESPONSES
+pub fn fill_rfq_instruction(
+    taker: Pubkey,
+    market: Pubkey,
+    responses: &[(Pubkey, RfqQuote)],
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::FillRfq as u8];
+    data.extend_from_slice(&(responses.len() as u32).to_le_bytes());
+    for (maker, quote) in responses {
+        data.extend_from_slice(maker.as_ref());
+        data.extend_from_slice(&quote.message());
+    }
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new_readonly(taker, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new(get_rfq_address(&market, &taker).0, false),
+            AccountMeta::new_readonly(INSTRUCTIONS_SYSVAR_ID, false),
+        ],
+    )
+}
+
+/// An order a maker signs off chain for [`fill_signed_order_instruction`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignedOrder {
+    pub market: Pubkey,
+    pub base_atoms: u64,
+    pub price_mantissa: u32,
+    pub price_exponent: i8,
+    /// Side of the maker. The taker takes the other side.
+    pub is_bid: bool,
+    /// Last slot the order can be filled in. Cannot be zero.
+    pub last_valid_slot: u32,
+    /// Each nonce of a maker fills once, see [`cancel_signed_orders_instruction`].
+    pub nonce: u64,
+}
+
+impl SignedOrder {
+    /// Bytes the maker signs, the borsh serialization of the order.
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = Vec::with_capacity(58);
+        message.extend_from_slice(self.market.as_ref());
+        message.extend_from_slice(&self.base_atoms.to_le_bytes());
+        message.extend_from_slice(&self.price_mantissa.to_le_bytes());
+        message.push(self.price_exponent as u8);
+        message.push(self.is_bid as u8);
+        message.extend_from_slice(&self.last_valid_slot.to_le_bytes());
+        message.extend_from_slice(&self.nonce.to_le_bytes());
+        message
+    }
+}
+
+/// Create a FillSignedOrder instruction that fills the whole of an order the
+/// maker signed off chain, settling from the seats of both sides like a
+/// FillRfq. Only the taker signs the transaction, and the instruction right
+/// before it must be [`ed25519_verify_instruction`] for the maker's signature
+/// of `order.message()`. The maker needs a nonces account, see
+/// [`cancel_signed_orders_instruction`].
+///
+/// # Accounts
+/// 0. `[signer]` taker - The trader filling the order
+/// 1. `[writable]` market - The market account
+/// 2. `[writable]` signed_order_nonces - Signed order nonces PDA of the maker
+/// 3. `[]` instructions_sysvar - Instructions sysvar
+pub fn fill_signed_order_instruction(
+    taker: Pubkey,
+    market: Pubkey,
+    maker: Pubkey,
+    order: &SignedOrder,
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::FillSignedOrder as u8];
+    data.extend_from_slice(maker.as_ref());
+    data.extend_from_slice(&order.message());
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new_readonly(taker, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new(get_signed_order_nonces_address(&market, &maker).0, false),
+            AccountMeta::new_readonly(INSTRUCTIONS_SYSVAR_ID, false),
+        ],
+    )
+}
+
+/// Create a CancelSignedOrders instruction so that no signed order of maker
+/// with a nonce below `min_nonce` can be filled. The first one creates the
+/// nonces account fills need, so makers send one with a zero `min_nonce`
+/// before handing out signed orders.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - Maker, funds the nonces account
+/// 1. `[]` market - The market account
+/// 2. `[]` system_program - System program
+/// 3. `[writable]` signed_order_nonces - Signed order nonces PDA of the maker
+pub fn cancel_signed_orders_instruction(
+    maker: Pubkey,
+    market: Pubkey,
+    min_nonce: u64,
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::CancelSignedOrders as u8];
+    data.extend_from_slice(&min_nonce.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(maker, true),
+            AccountMeta::new_readonly(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(get_signed_order_nonces_address(&market, &maker).0, false),
+        ],
+    )
+}
+
+/// Create a SetMmp instruction that sets the payer's market maker protection.
+/// Once the fills against the seat's orders within `window_slots` reach
+/// `max_fills` or `max_quote_atoms`, matching pulls all its orders and emits
+/// a [`MmpTriggeredLog`]. Zero limits are no limit, and both zero clears it.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - The trader that owns the seat
+/// 1. `[writable]` market - The market account
+/// 2. `[]` system_program - System program
+///
+/// [`MmpTriggeredLog`]: crate::MmpTriggeredLog
+pub fn set_mmp_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    window_slots: u32,
+    max_fills: u32,
+    max_quote_atoms: u64,
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::SetMmp as u8];
+    data.extend_from_slice(&window_slots.to_le_bytes());
+    data.extend_from_slice(&max_fills.to_le_bytes());
+    data.extend_from_slice(&max_quote_atoms.to_le_bytes());
+    // trader_index_hint
+    data.push(0);
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Create a SetPaused instruction that pauses or resumes trading on a market
+/// created with a pauser, see [`create_market_with_pauser_instruction`].
+/// Paused markets fail new orders, swaps and fills with MarketPaused, while
+/// cancels, deposits and withdraws still work. Emits a [`MarketPausedLog`].
+///
+/// # Accounts
+/// 0. `[signer]` pauser - The pauser set at market creation
+/// 1. `[writable]` market - The market account
+///
+/// [`MarketPausedLog`]: crate::MarketPausedLog
+pub fn set_paused_instruction(pauser: Pubkey, market: Pubkey, paused: bool) -> Instruction {
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::SetPaused as u8, paused as u8],
+        vec![
+            AccountMeta::new_readonly(pauser, true),
+            AccountMeta::new(market, false),
+        ],
+    )
+}
+
+/// Create an ed25519 program instruction that verifies one signature, with
+/// the public key, signature and message all in its own data.
+pub fn ed25519_verify_instruction(
+    signer: Pubkey,
+    signature: &[u8; 64],
+    message: &[u8],
+) -> Instruction {
+    // Two bytes of header and seven u16 offsets.
+    let public_key_offset: u16 = 16;
+    let signature_offset: u16 = public_key_offset + 32;
+    let message_offset: u16 = signature_offset + 64;
+    let mut data = vec![1, 0];
+    for offset in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+
+    Instruction::new_with_bytes(ED25519_PROGRAM_ID, &data, vec![])
+}
+
+/// Create an ArchiveSeat instruction that moves a seat with no resting orders
+/// into the seat archive of the market and frees its block. Anyone can archive
+/// a seat that only holds dust, otherwise the payer must be the trader.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - Funds the seat archive on first use
+/// 1. `[writable]` market - The market account
+/// 2. `[]` system_program - System program
+/// 3. `[writable]` seat_archive - Seat archive PDA
+pub fn archive_seat_instruction(payer: Pubkey, market: Pubkey, trader: Pubkey) -> Instruction {
+    let mut data = vec![ManifestInstruction::ArchiveSeat as u8];
+    data.extend_from_slice(trader.as_ref());
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(get_seat_archive_address(&market).0, false),
+        ],
+    )
+}
+
+/// Create a RestoreSeat instruction that puts an archived seat back. The
+/// amounts and leaf_index are the ones in its ArchiveSeatLog, and the proof is
+/// built from every ArchiveSeatLog of the market, for example with
+/// `seat_archive_proof` in the manifest-dex crate.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - Any wallet, pays if the market has to grow
+/// 1. `[writable]` market - The market account
+/// 2. `[]` system_program - System program
+/// 3. `[writable]` seat_archive - Seat archive PDA
+#[allow(clippy::too_many_arguments)]
+pub fn restore_seat_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    trader: Pubkey,
+    base_atoms: u64,
+    quote_atoms: u64,
+    quote_volume: u64,
+    leaf_index: u64,
+    proof: &[[u8; 32]],
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::RestoreSeat as u8];
+    data.extend_from_slice(trader.as_ref());
+    data.extend_from_slice(&base_atoms.to_le_bytes());
+    data.extend_from_slice(&quote_atoms.to_le_bytes());
+    data.extend_from_slice(&quote_volume.to_le_bytes());
+    data.extend_from_slice(&leaf_index.to_le_bytes());
+    data.extend_from_slice(&(proof.len() as u32).to_le_bytes());
+    for node in proof {
+        data.extend_from_slice(node);
+    }
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(get_seat_archive_address(&market).0, false),
+        ],
+    )
+}
+
+/// Create a CommitSwap instruction for a market with a taker delay. The
+/// commitment is sha256(salt || swap instruction data) of a Swap, SwapV2 or
+/// SwapToSeat instruction whose owner is trader. Committing again replaces the
+/// pending swap and restarts the delay.
+///
+/// # Accounts
+/// 0. `[writable, signer]` trader - Owner of the swap, funds the PDA on first use
+/// 1. `[]` market - The market account
+/// 2. `[]` system_program - System program
+/// 3. `[writable]` swap_commitment - Swap commitment PDA
+pub fn commit_swap_instruction(
+    trader: Pubkey,
+    market: Pubkey,
+    commitment: [u8; 32],
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::CommitSwap as u8];
+    data.extend_from_slice(&commitment);
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(trader, true),
+            AccountMeta::new_readonly(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(get_swap_commitment_address(&market, &trader).0, false),
+        ],
+    )
+}
+
+/// Create a RevealSwap instruction that executes swap_instruction once the
+/// taker delay has passed since its commit_swap_instruction with the same
+/// salt.
+///
+/// # Accounts
+/// 0. `[writable]` swap_commitment - Swap commitment PDA
+/// 1. ... The accounts of swap_instruction
+pub fn reveal_swap_instruction(
+    trader: Pubkey,
+    market: Pubkey,
+    swap_instruction: &Instruction,
+    salt: &[u8; 32],
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::RevealSwap as u8];
+    data.extend_from_slice(salt);
+    data.extend_from_slice(&swap_instruction.data);
+
+    let mut accounts: Vec<AccountMeta> = vec![AccountMeta::new(
+        get_swap_commitment_address(&market, &trader).0,
+        false,
+    )];
+    accounts.extend_from_slice(&swap_instruction.accounts);
+    Instruction::new_with_bytes(MANIFEST_PROGRAM_ID, &data, accounts)
+}
+
+/// Create a GetMarketPrices instruction. It changes nothing and writes
+/// [`MarketPrices`] as return data, read it from a simulation or after a CPI.
+///
+/// # Accounts
+/// 0. `[]` market - The market account
+pub fn get_market_prices_instruction(market: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::GetMarketPrices as u8],
+        vec![AccountMeta::new_readonly(market, false)],
+    )
+}
+
+/// Return data of GetMarketPrices. Prices are quote atoms per base atom times
+/// 10^18, zero when there is none. Expired and global orders are not counted
+/// in the best prices and depth.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MarketPrices {
+    pub slot: u32,
+    pub best_bid_price: u128,
+    pub best_ask_price: u128,
+    pub last_trade_price: u128,
+    pub last_trade_slot: u32,
+    pub twap_price: u128,
+    /// Size resting within 2% of the best bid and ask.
+    pub bid_depth_base_atoms: u64,
+    pub bid_depth_quote_atoms: u64,
+    pub ask_depth_base_atoms: u64,
+    pub ask_depth_quote_atoms: u64,
+}
+
+impl MarketPrices {
+    /// Parse the borsh encoded return data.
+    pub fn try_from_bytes(data: &[u8]) -> Option<Self> {
+        let mut offset: usize = 0;
+        let mut take = |len: usize| -> Option<&[u8]> {
+            let bytes: &[u8] = data.get(offset..offset + len)?;
+            offset += len;
+            Some(bytes)
+        };
+        let u32_at = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().unwrap());
+        let u64_at = |bytes: &[u8]| u64::from_le_bytes(bytes.try_into().unwrap());
+        let u128_at = |bytes: &[u8]| u128::from_le_bytes(bytes.try_into().unwrap());
+        Some(MarketPrices {
+            slot: u32_at(take(4)?),
+            best_bid_price: u128_at(take(16)?),
+            best_ask_price: u128_at(take(16)?),
+            last_trade_price: u128_at(take(16)?),
+            last_trade_slot: u32_at(take(4)?),
+            twap_price: u128_at(take(16)?),
+            bid_depth_base_atoms: u64_at(take(8)?),
+            bid_depth_quote_atoms: u64_at(take(8)?),
+            ask_depth_base_atoms: u64_at(take(8)?),
+            ask_depth_quote_atoms: u64_at(take(8)?),
+        })
+    }
+}
+
+/// Create an EmitDepthSnapshot instruction, which logs the best num_levels
+/// price levels of each side as a [`DepthSnapshotLog`]. Anyone can send it.
+///
+/// # Accounts
+/// 0. `[]` market - The market account
+///
+/// [`DepthSnapshotLog`]: crate::DepthSnapshotLog
+pub fn emit_depth_snapshot_instruction(market: Pubkey, num_levels: u8) -> Instruction {
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::EmitDepthSnapshot as u8, num_levels],
+        vec![AccountMeta::new_readonly(market, false)],
+    )
+}
+
+/// Create a ClaimPoints instruction. Claims the payer's points of the latest
+/// finished epoch as a [`PointsClaimLog`], and gives the seat a seat config on
+/// the first call so it starts earning points.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - The trader that owns the seat
+/// 1. `[writable]` market - The market account
+/// 2. `[]` system_program - System program
+///
+/// [`PointsClaimLog`]: crate::PointsClaimLog
+pub fn claim_points_instruction(payer: Pubkey, market: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        // trader_index_hint
+        &[ManifestInstruction::ClaimPoints as u8, 0],
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ],
+    )
+}
+
+/// Create a GetSeatPoints instruction. It changes nothing and writes
+/// [`SeatPoints`] of the trader's seat as return data.
+///
+/// # Accounts
+/// 0. `[]` market - The market account
+pub fn get_seat_points_instruction(market: Pubkey, trader: Pubkey) -> Instruction {
+    let mut data = vec![ManifestInstruction::GetSeatPoints as u8];
+    data.extend_from_slice(trader.as_ref());
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![AccountMeta::new_readonly(market, false)],
+    )
+}
+
+/// Return data of GetSeatPoints, all zero for seats that do not earn points.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SeatPoints {
+    /// Current epoch and the points earned in it so far.
+    pub epoch: u32,
+    pub points: u64,
+    /// Latest finished epoch with points that have not been claimed.
+    pub claimable_epoch: u32,
+    pub claimable_points: u64,
+}
+
+impl SeatPoints {
+    /// Parse the borsh encoded return data.
+    pub fn try_from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() != 24 {
+            return None;
+        }
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        Some(SeatPoints {
+            epoch: u32_at(0),
+            points: u64_at(4),
+            claimable_epoch: u32_at(12),
+            claimable_points: u64_at(16),
+        })
+    }
+}
+
+/// Create a PlaceStopOrder instruction. order is placed by
+/// trigger_stop_orders_instruction once the last trade price reaches the
+/// trigger price, rising to it for bids and falling to it for asks. Only
+/// Limit and ImmediateOrCancel orders, and min_fill_base_atoms and
+/// self_trade_prevention are not used.
+///
+/// # Accounts
+/// 0. `[writable, signer]` trader - Owner of the seat, funds the PDA on first use
+/// 1. `[]` market - The market account
+/// 2. `[]` system_program - System program
+/// 3. `[writable]` stop_orders - Stop orders PDA
+pub fn place_stop_order_instruction(
+    trader: Pubkey,
+    market: Pubkey,
+    order: &PlaceOrderParams,
+    trigger_price_mantissa: u32,
+    trigger_price_exponent: i8,
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::PlaceStopOrder as u8];
+    data.extend_from_slice(&order.serialize());
+    data.extend_from_slice(&trigger_price_mantissa.to_le_bytes());
+    data.push(trigger_price_exponent as u8);
+    // trader_index_hint
+    data.push(0);
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(trader, true),
+            AccountMeta::new_readonly(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(get_stop_orders_address(&market, &trader).0, false),
+        ],
+    )
+}
+
+/// Same as place_stop_order_instruction for a trailing stop. Each
+/// trigger_stop_orders_instruction first moves the trigger to trailing_bps
+/// below the last trade price for asks and above it for bids, when that is
+/// closer to the market. The limit price of order does not move.
+pub fn place_trailing_stop_order_instruction(
+    trader: Pubkey,
+    market: Pubkey,
+    order: &PlaceOrderParams,
+    trigger_price_mantissa: u32,
+    trigger_price_exponent: i8,
+    trailing_bps: u16,
+) -> Instruction {
+    let mut instruction: Instruction = place_stop_order_instruction(
+        trader,
+        market,
+        order,
+        trigger_price_mantissa,
+        trigger_price_exponent,
+    );
+    instruction
+        .data
+        .extend_from_slice(&trailing_bps.to_le_bytes());
+    instruction
+}
+
+/// Create a CancelStopOrder instruction for the stop order in slot
+/// stop_index of the trader's stop orders account.
+///
+/// # Accounts
+/// 0. `[signer]` trader - Owner of the stop orders
+/// 1. `[writable]` stop_orders - Stop orders PDA
+pub fn cancel_stop_order_instruction(
+    trader: Pubkey,
+    market: Pubkey,
+    stop_index: u8,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::CancelStopOrder as u8, stop_index],
+        vec![
+            AccountMeta::new_readonly(trader, true),
+            AccountMeta::new(get_stop_orders_address(&market, &trader).0, false),
+        ],
+    )
+}
+
+/// Create a TriggerStopOrders instruction that places the triggered stop
+/// orders of trader and clears the expired ones. Anyone can send it.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - Pays for market expansion
+/// 1. `[writable]` market - The market account
+/// 2. `[]` system_program - System program
+/// 3. `[writable]` stop_orders - Stop orders PDA of trader
+pub fn trigger_stop_orders_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    trader: Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::TriggerStopOrders as u8],
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(get_stop_orders_address(&market, &trader).0, false),
+        ],
+    )
+}
+
+/// Create a Heartbeat instruction that restarts the dead man's switch of
+/// payer. After `timeout_slots` without another heartbeat, anyone can cancel
+/// all of the seat's orders for `bounty_lamports`. A zero timeout disarms it.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - Trader, funds the heartbeat account and
+///    bounty
+/// 1. `[]` market - The market account
+/// 2. `[]` system_program - System program
+/// 3. `[writable]` heartbeat - Heartbeat PDA of payer
+pub fn heartbeat_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    timeout_slots: u32,
+    bounty_lamports: u64,
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::Heartbeat as u8];
+    data.extend_from_slice(&timeout_slots.to_le_bytes());
+    data.extend_from_slice(&bounty_lamports.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(get_heartbeat_address(&market, &payer).0, false),
+        ],
+    )
+}
+
+/// Create a CancelOnHeartbeatTimeout instruction that cancels all orders of
+/// trader once their heartbeat timed out. Anyone can send it and the payer
+/// gets the bounty.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - Receives the bounty
+/// 1. `[writable]` market - The market account
+/// 2. `[writable]` heartbeat - Heartbeat PDA of trader
+pub fn cancel_on_heartbeat_timeout_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    trader: Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::CancelOnHeartbeatTimeout as u8],
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new(get_heartbeat_address(&market, &trader).0, false),
+        ],
+    )
+}
+
+/// Create a Rebalance instruction that trades the withdrawable balance of the
+/// payer's seat towards `target_base_atoms` against the book, no further than
+/// `max_slippage_bps` through the best price. The cosigner of the seat config
+/// must be included when the trade is above its cosign threshold.
+///
+/// # Accounts
+/// 0. `[signer]` payer - Trader whose seat is rebalanced
+/// 1. `[writable]` market - The market account
+/// 2. `[signer]` cosigner - (Optional) Cosigner of the seat
+pub fn rebalance_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    target_base_atoms: u64,
+    max_slippage_bps: u16,
+    cosigner: Option<Pubkey>,
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::Rebalance as u8];
+    data.extend_from_slice(&target_base_atoms.to_le_bytes());
+    data.extend_from_slice(&max_slippage_bps.to_le_bytes());
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(payer, true),
+        AccountMeta::new(market, false),
+    ];
+    if let Some(cosigner) = cosigner {
+        accounts.push(AccountMeta::new_readonly(cosigner, true));
+    }
+
+    Instruction::new_with_bytes(MANIFEST_PROGRAM_ID, &data, accounts)
+}
+
+/// Create an UpdateVolatility instruction that samples the last trade price
+/// into the volatility estimate of a market. Anyone can send it, at most once
+/// per about a minute.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - Funds the volatility account on the first update
+/// 1. `[writable]` market - The market account
+/// 2. `[]` system_program - System program
+/// 3. `[writable]` market_volatility - Volatility PDA of the market
+pub fn update_volatility_instruction(payer: Pubkey, market: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::UpdateVolatility as u8],
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(get_market_volatility_address(&market).0, false),
+        ],
+    )
+}
+
+/// Create an EnterRecovery instruction that freezes a market whose state may
+/// be damaged so traders can only take their share of the vaults out. Signed
+/// by the upgrade authority of the program. There is no way back.
+///
+/// # Accounts
+/// 0. `[signer]` authority - Upgrade authority of the program
+/// 1. `[writable]` market - The market account
+/// 2. `[]` program_data - Program data account of the program
+pub fn enter_recovery_instruction(authority: Pubkey, market: Pubkey) -> Instruction {
+    let (program_data, _) = Pubkey::find_program_address(
+        &[MANIFEST_PROGRAM_ID.as_ref()],
+        &BPF_LOADER_UPGRADEABLE_PROGRAM_ID,
+    );
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::EnterRecovery as u8],
+        vec![
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new_readonly(program_data, false),
+        ],
+    )
+}
+
+/// Create a RecoveryWithdraw instruction for a market in recovery. Pays the
+/// payer's seat its share of the mint vault: what the seat and its orders
+/// hold of the mint, scaled down if the vault holds less than all seats are
+/// owed.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - The trader withdrawing
+/// 1. `[writable]` market - The market account
+/// 2. `[writable]` trader_token - Destination token account, can be owned by
+///    any wallet
+/// 3. `[writable]` vault - Market vault PDA for the mint
+/// 4. `[]` token_program - Token program of the mint
+/// 5. `[]` mint - The mint
+pub fn recovery_withdraw_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    trader_token: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+) -> Instruction {
+    let (vault, _) = get_vault_address(&market, &mint);
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::RecoveryWithdraw as u8],
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new(trader_token, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(mint, false),
+        ],
+    )
+}
+
+/// Create a ResetPriceBand instruction that moves the price band reference of
+/// a market to the middle of its book. Anyone can send it once there has been
+/// no trade or reset for PRICE_BAND_RESET_SLOTS, so a market whose price moved
+/// past the band can trade again.
+///
+/// # Accounts
+/// 0. `[signer]` payer - Anyone
+/// 1. `[writable]` market - The market account
+pub fn reset_price_band_instruction(payer: Pubkey, market: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::ResetPriceBand as u8],
+        vec![
+            AccountMeta::new_readonly(payer, true),
+            AccountMeta::new(market, false),
+        ],
+    )
+}
+
+/// Create a ClaimFees instruction that pays the fees a market has accrued in
+/// mint to recipient_token and zeroes them. Anyone can send it, the token
+/// account has to be owned by the fee recipient of the market.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - Anyone
+/// 1. `[writable]` market - The market account
+/// 2. `[writable]` recipient_token - Token account of the fee recipient
+/// 3. `[writable]` vault - Market vault PDA for the mint
+/// 4. `[]` token_program - Token program of the mint
+/// 5. `[]` mint - The mint
+pub fn claim_fees_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    recipient_token: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+) -> Instruction {
+    let (vault, _) = get_vault_address(&market, &mint);
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::ClaimFees as u8],
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new(recipient_token, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(mint, false),
+        ],
+    )
+}
+
+/// Create a BustTrade instruction that reverses the seat balance changes,
+/// fees, volumes, points and market maker protection counts of an erroneous
+/// fill. The fields are the ones in its FillLog and fill_slot is the slot of
+/// its transaction. The taker fee is refunded at its taker_fee_bps, less the
+/// referral share when the fill is referred. Signed by
+/// the upgrade authority of the program, which pays for the record that keeps
+/// the fill from being busted twice. Only fills still kept by a market
+/// created with MARKET_EXTENSION_FLAG_FILL_RECORDS, and among its last
+/// TRADE_BUST_MAX_SEQUENCE_NUMBERS orders, can be busted.
+///
+/// # Accounts
+/// 0. `[writable, signer]` authority - Upgrade authority of the program
+/// 1. `[writable]` market - The market account
+/// 2. `[]` program_data - Program data account of the program
+/// 3. `[]` system_program - System program
+/// 4. `[writable]` trade_bust - Trade bust PDA of the fill
+#[allow(clippy::too_many_arguments)]
+pub fn bust_trade_instruction(
+    authority: Pubkey,
+    market: Pubkey,
+    maker: Pubkey,
+    taker: Pubkey,
+    base_atoms: u64,
+    quote_atoms: u64,
+    taker_is_buy: bool,
+    maker_sequence_number: u64,
+    taker_sequence_number: u64,
+    taker_fee_bps: u16,
+    referred: bool,
+    fill_slot: u32,
+) -> Instruction {
+    let (program_data, _) = Pubkey::find_program_address(
+        &[MANIFEST_PROGRAM_ID.as_ref()],
+        &BPF_LOADER_UPGRADEABLE_PROGRAM_ID,
+    );
+    let (trade_bust, _) =
+        get_trade_bust_address(&market, maker_sequence_number, taker_sequence_number);
+    let mut data = vec![ManifestInstruction::BustTrade as u8];
+    data.extend_from_slice(maker.as_ref());
+    data.extend_from_slice(taker.as_ref());
+    data.extend_from_slice(&base_atoms.to_le_bytes());
+    data.extend_from_slice(&quote_atoms.to_le_bytes());
+    data.push(taker_is_buy as u8);
+    data.extend_from_slice(&maker_sequence_number.to_le_bytes());
+    data.extend_from_slice(&taker_sequence_number.to_le_bytes());
+    data.extend_from_slice(&taker_fee_bps.to_le_bytes());
+    data.push(referred as u8);
+    data.extend_from_slice(&fill_slot.to_le_bytes());
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(authority, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new_readonly(program_data, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(trade_bust, false),
+        ],
+    )
+}
+
+/// Create a PlacePeggedOrder instruction. The order's price is offset_bps
+/// from the reference price of the book, positive being higher, and never
+/// worse than the limit price. It goes on the book, and follows the
+/// reference, with each [`reprice_pegged_orders_instruction`].
+///
+/// # Accounts
+/// 0. `[writable, signer]` trader - Owner of the seat, funds the PDA on first use
+/// 1. `[]` market - The market account
+/// 2. `[]` system_program - System program
+/// 3. `[writable]` pegged_orders - Pegged orders PDA
+#[allow(clippy::too_many_arguments)]
+pub fn place_pegged_order_instruction(
+    trader: Pubkey,
+    market: Pubkey,
+    base_atoms: u64,
+    is_bid: bool,
+    reference: PegReference,
+    offset_bps: i16,
+    limit_price_mantissa: u32,
+    limit_price_exponent: i8,
+    last_valid_slot: u32,
+) -> Instruction {
+    let mut data = vec![ManifestInstruction::PlacePeggedOrder as u8];
+    data.extend_from_slice(&base_atoms.to_le_bytes());
+    data.push(is_bid as u8);
+    data.push(reference as u8);
+    data.extend_from_slice(&offset_bps.to_le_bytes());
+    data.extend_from_slice(&limit_price_mantissa.to_le_bytes());
+    data.push(limit_price_exponent as u8);
+    data.extend_from_slice(&last_valid_slot.to_le_bytes());
+    // trader_index_hint
+    data.push(0);
+
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &data,
+        vec![
+            AccountMeta::new(trader, true),
+            AccountMeta::new_readonly(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(get_pegged_orders_address(&market, &trader).0, false),
+        ],
+    )
+}
+
+/// Create a CancelPeggedOrder instruction for the pegged order in slot
+/// pegged_index of the trader's pegged orders account, which also cancels
+/// its resting order.
+///
+/// # Accounts
+/// 0. `[signer]` trader - Owner of the pegged orders
+/// 1. `[writable]` market - The market account
+/// 2. `[writable]` pegged_orders - Pegged orders PDA
+pub fn cancel_pegged_order_instruction(
+    trader: Pubkey,
+    market: Pubkey,
+    pegged_index: u8,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::CancelPeggedOrder as u8, pegged_index],
+        vec![
+            AccountMeta::new_readonly(trader, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new(get_pegged_orders_address(&market, &trader).0, false),
+        ],
+    )
+}
+
+/// Create a RepricePeggedOrders instruction that moves the pegged orders of
+/// trader to their prices against the book and clears the filled, cancelled
+/// and expired ones. Anyone can send it.
+///
+/// # Accounts
+/// 0. `[writable, signer]` payer - Pays for market expansion
+/// 1. `[writable]` market - The market account
+/// 2. `[]` system_program - System program
+/// 3. `[writable]` pegged_orders - Pegged orders PDA of trader
+pub fn reprice_pegged_orders_instruction(
+    payer: Pubkey,
+    market: Pubkey,
+    trader: Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        MANIFEST_PROGRAM_ID,
+        &[ManifestInstruction::RepricePeggedOrders as u8],
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(market, false),
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            AccountMeta::new(get_pegged_orders_address(&market, &trader).0, false),
+        ],
+    )
+}
@@ -3,46 +3,130 @@
 //! This crate provides instruction builders and state parsing for the Manifest
 //! exchange with minimal dependencies.
 
+mod book;
+mod bundle;
 mod constants;
+mod error;
 mod events;
 mod instruction;
+mod reverse;
+#[cfg(feature = "rpc")]
+mod send;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+mod spread;
 mod state;
 
 pub use solana_instruction::{AccountMeta, Instruction};
 pub use solana_pubkey::Pubkey;
 
 pub use constants::{
-    DataIndex, OrderType, CLAIMED_SEAT_SIZE, MANIFEST_PROGRAM_ID, MARKET_BLOCK_SIZE,
-    MARKET_FIXED_DISCRIMINANT, MARKET_FIXED_SIZE, NIL, NO_EXPIRATION_LAST_VALID_SLOT,
-    RESTING_ORDER_SIZE, SYSTEM_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID,
+    DataIndex, OrderType, PegReference, SelfTradePrevention, ASSOCIATED_TOKEN_PROGRAM_ID,
+    BPF_LOADER_UPGRADEABLE_PROGRAM_ID, CLAIMED_SEAT_SIZE, COMPUTE_BUDGET_PROGRAM_ID,
+    DYNAMIC_REVERSE_SPREAD, ED25519_PROGRAM_ID, FEE_TIERS, INSTRUCTIONS_SYSVAR_ID,
+    MANIFEST_PROGRAM_ID, MARKET_BLOCK_SIZE, MARKET_EXTENSION_FLAG_FILL_RECORDS,
+    MARKET_EXTENSION_FLAG_PAUSED, MARKET_EXTENSION_VERSION, MARKET_FIXED_DISCRIMINANT,
+    MARKET_FIXED_SIZE, MARKET_FLAG_CPI_ALLOWLIST, MARKET_FLAG_EXTENSION, MARKET_FLAG_FEES,
+    MARKET_FLAG_MIN_ORDER_SIZE, MARKET_FLAG_NO_INTERNAL_CROSS, MARKET_FLAG_PRICE_BAND,
+    MARKET_FLAG_RECOVERY, MARKET_FLAG_VOLUME_STATS, MAX_CPI_ALLOWLIST_PROGRAMS, MAX_FEE_BPS,
+    MAX_RFQ_RESPONSES, MAX_VOLATILITY_BPS, NIL, NO_EXPIRATION_LAST_VALID_SLOT,
+    PRICE_BAND_RESET_SLOTS, RESTING_ORDER_SIZE, RFQ_DISCRIMINANT, RFQ_SIZE, SEAT_VOLUME_BUCKETS,
+    SEAT_VOLUME_BUCKET_SLOTS, SIGNED_ORDER_NONCES_DISCRIMINANT, SIGNED_ORDER_NONCES_SIZE,
+    SIGNED_ORDER_NONCE_WINDOW, SYSTEM_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID,
+    TRADE_BUST_MAX_SEQUENCE_NUMBERS,
 };
 
 pub use instruction::{
-    batch_update_instruction, batch_update_with_global_instruction, claim_seat_instruction,
-    create_market_instruction, deposit_instruction, expand_instruction, get_global_address,
-    get_global_vault_address, get_vault_address, swap_instruction, withdraw_instruction,
-    BatchUpdateParams, CancelOrderParams, DepositParams, ManifestInstruction, PlaceOrderParams,
+    archive_seat_instruction, batch_update_instruction, batch_update_with_global_instruction,
+    bust_trade_instruction, cancel_on_heartbeat_timeout_instruction,
+    cancel_pegged_order_instruction, cancel_signed_orders_instruction,
+    cancel_stop_order_instruction, claim_fees_instruction, claim_points_instruction,
+    claim_seat_instruction, commit_swap_instruction, create_market_instruction,
+    create_market_with_cpi_allowlist_instruction, create_market_with_extension_instruction,
+    create_market_with_fees_instruction, create_market_with_flags_instruction,
+    create_market_with_min_order_size_instruction,
+    create_market_with_min_resting_slots_instruction, create_market_with_pauser_instruction,
+    create_market_with_points_instruction, create_market_with_price_band_instruction,
+    create_market_with_taker_delay_instruction, create_market_with_trading_hours_instruction,
+    deposit_idempotent_instruction, deposit_instruction, ed25519_verify_instruction,
+    emit_depth_snapshot_instruction, enter_recovery_instruction, expand_instruction,
+    expand_n_instruction, fill_rfq_instruction, fill_signed_order_instruction, get_global_address,
+    get_global_vault_address, get_heartbeat_address, get_market_prices_instruction,
+    get_market_volatility_address, get_pegged_orders_address, get_rfq_address,
+    get_seat_archive_address, get_seat_points_instruction, get_signed_order_nonces_address,
+    get_stop_orders_address, get_swap_commitment_address, get_trade_bust_address,
+    get_vault_address, heartbeat_instruction, place_pegged_order_instruction,
+    place_stop_order_instruction, place_trailing_stop_order_instruction, post_rfq_instruction,
+    rebalance_instruction, recovery_withdraw_instruction, reprice_pegged_orders_instruction,
+    reset_price_band_instruction, restore_seat_instruction, reveal_swap_instruction,
+    set_mmp_instruction, set_paused_instruction, set_seat_config_instruction, swap_instruction,
+    swap_to_seat_instruction, trigger_stop_orders_instruction, update_volatility_instruction,
+    with_create_token_accounts, with_referrer, withdraw_as_quote_instruction, withdraw_instruction,
+    AmendOrderParams, BatchUpdateParams, CancelOrderParams, DepositParams, ManifestInstruction,
+    MarketPrices, PlaceOrderParams, ReplaceOrderParams, RfqQuote, SeatPoints, SignedOrder,
     SwapParams, WithdrawParams,
 };
 
-pub use state::{ClaimedSeat, Market, MarketFixed, OrderIterator, RBNodeHeader, RestingOrder};
+pub use state::{
+    ClaimedSeat, Market, MarketFixed, OrderIterator, RBNodeHeader, RestingOrder, Rfq,
+    SignedOrderNonces,
+};
+
+pub use book::{book_imbalance, micro_price, spread_bps, BookDepth};
+
+pub use bundle::{
+    build_bundle, check_bundle, tip_instruction, transaction_size, BundleError, LookupTable,
+    JITO_TIP_ACCOUNTS, MAX_BUNDLE_TRANSACTIONS, MAX_TRANSACTION_SIZE, MIN_JITO_TIP_LAMPORTS,
+};
+
+pub use reverse::{ReverseBook, ReverseFlip, SimulatedOrder, SimulatedSwap};
+
+pub use spread::{
+    fill_quote_atoms, plan_from_quotes, plan_spread_trade, spread_trade_instructions, SpreadError,
+    SpreadLeg, SpreadPlan, SWAP_BASE_COMPUTE_UNITS, SWAP_FILL_COMPUTE_UNITS,
+};
+
+pub use error::{
+    decode_manifest_error, decode_manifest_error_from_logs, DecodedManifestError, ManifestError,
+};
+
+#[cfg(feature = "rpc")]
+pub use send::{
+    manifest_error_code, next_compute_unit_price, send_with_retry, versioned_transaction,
+    SendConfig, SendError,
+};
+
+#[cfg(feature = "snapshot")]
+pub use snapshot::{MarketSnapshot, SnapshotError, SNAPSHOT_DEFAULT_COMPRESSION_LEVEL};
 
 // Event types
-pub use events::{BaseAtoms, GlobalAtoms, PodBool, QuoteAtoms, QuoteAtomsPerBaseAtom};
+pub use events::{
+    parse_event, BaseAtoms, GlobalAtoms, ManifestEvent, PodBool, QuoteAtoms, QuoteAtomsPerBaseAtom,
+    LOG_VERSION_UNVERSIONED,
+};
 // Event discriminants
 pub use events::{
-    CANCEL_ORDER_LOG_DISCRIMINANT, CLAIM_SEAT_LOG_DISCRIMINANT, CREATE_MARKET_LOG_DISCRIMINANT,
-    DEPOSIT_LOG_DISCRIMINANT, FILL_LOG_DISCRIMINANT, GLOBAL_ADD_TRADER_LOG_DISCRIMINANT,
-    GLOBAL_CLAIM_SEAT_LOG_DISCRIMINANT, GLOBAL_CLEANUP_LOG_DISCRIMINANT,
-    GLOBAL_CREATE_LOG_DISCRIMINANT, GLOBAL_DEPOSIT_LOG_DISCRIMINANT, GLOBAL_EVICT_LOG_DISCRIMINANT,
-    GLOBAL_WITHDRAW_LOG_DISCRIMINANT, PLACE_ORDER_LOG_DISCRIMINANT,
-    PLACE_ORDER_LOG_V2_DISCRIMINANT, WITHDRAW_LOG_DISCRIMINANT,
+    ARCHIVE_SEAT_LOG_DISCRIMINANT, CANCEL_ORDER_LOG_DISCRIMINANT, CLAIM_FEES_LOG_DISCRIMINANT,
+    CLAIM_SEAT_LOG_DISCRIMINANT, CREATE_MARKET_LOG_DISCRIMINANT, DEPOSIT_LOG_DISCRIMINANT,
+    DEPTH_SNAPSHOT_LOG_DISCRIMINANT, DEPTH_SNAPSHOT_MAX_LEVELS, FEE_LOG_DISCRIMINANT,
+    FILL_LOG_DISCRIMINANT, GLOBAL_ADD_TRADER_LOG_DISCRIMINANT, GLOBAL_CLAIM_SEAT_LOG_DISCRIMINANT,
+    GLOBAL_CLEANUP_LOG_DISCRIMINANT, GLOBAL_CREATE_LOG_DISCRIMINANT,
+    GLOBAL_DEPOSIT_LOG_DISCRIMINANT, GLOBAL_EVICT_LOG_DISCRIMINANT,
+    GLOBAL_WITHDRAW_LOG_DISCRIMINANT, MARKET_PAUSED_LOG_DISCRIMINANT,
+    MMP_TRIGGERED_LOG_DISCRIMINANT, PEGGED_ORDER_PLACED_LOG_DISCRIMINANT,
+    PLACE_ORDER_LOG_DISCRIMINANT, PLACE_ORDER_LOG_V2_DISCRIMINANT, POINTS_CLAIM_LOG_DISCRIMINANT,
+    REFERRAL_FEE_LOG_DISCRIMINANT, REPLACE_ORDER_LOG_DISCRIMINANT, RESTORE_SEAT_LOG_DISCRIMINANT,
+    RFQ_LOG_DISCRIMINANT, STOP_ORDER_TRIGGERED_LOG_DISCRIMINANT, TRADE_BUST_LOG_DISCRIMINANT,
+    WITHDRAW_LOG_DISCRIMINANT,
 };
 // Event structs
 pub use events::{
-    CancelOrderLog, ClaimSeatLog, CreateMarketLog, DepositLog, FillLog, GlobalAddTraderLog,
-    GlobalClaimSeatLog, GlobalCleanupLog, GlobalCreateLog, GlobalDepositLog, GlobalEvictLog,
-    GlobalWithdrawLog, PlaceOrderLog, PlaceOrderLogV2, WithdrawLog,
+    ArchiveSeatLog, CancelOrderLog, ClaimFeesLog, ClaimSeatLog, CreateMarketLog, DepositLog,
+    DepthLevel, DepthSnapshotLog, FeeLog, FillLog, GlobalAddTraderLog, GlobalClaimSeatLog,
+    GlobalCleanupLog, GlobalCreateLog, GlobalDepositLog, GlobalEvictLog, GlobalWithdrawLog,
+    MarketPausedLog, MmpTriggeredLog, PeggedOrderPlacedLog, PlaceOrderLog, PlaceOrderLogV2,
+    PointsClaimLog, ReferralFeeLog, ReplaceOrderLog, RestoreSeatLog, RfqLog, StopOrderTriggeredLog,
+    TradeBustLog, WithdrawLog,
 };
 
 #[cfg(test)]
@@ -0,0 +1,280 @@
+//! Simulation of Reverse and ReverseTight orders flipping as swaps fill them.
+//!
+//! A filled reverse order comes back on the other side of the book at its
+//! price moved by the spread, so a grid of reverse orders keeps quoting
+//! around the last trade. [`ReverseBook`] runs swaps against a copy of the
+//! book, matching and flipping like the program does, to show where a grid
+//! ends up after a sequence of swaps without sending them.
+//!
+//! Global orders match like any other order, and self trade prevention,
+//! iceberg display sizes and minimum taker sizes are not modeled. Prices are
+//! raw like [`RestingOrder::get_price_raw`], quote atoms per base atom
+//! times 10^18.
+
+use crate::{
+    constants::{DataIndex, OrderType, DYNAMIC_REVERSE_SPREAD, MAX_VOLATILITY_BPS},
+    state::{Market, RestingOrder},
+};
+
+const D18: u128 = 1_000_000_000_000_000_000;
+
+/// An order on a [`ReverseBook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulatedOrder {
+    pub trader_index: DataIndex,
+    pub is_bid: bool,
+    pub price: u128,
+    pub num_base_atoms: u64,
+    pub order_type: OrderType,
+    /// Spread of a reverse order in units of 1/100_000 for Reverse and
+    /// 1/100_000_000 for ReverseTight, or DYNAMIC_REVERSE_SPREAD.
+    pub reverse_spread: u16,
+}
+
+impl SimulatedOrder {
+    pub fn from_resting_order(order: &RestingOrder) -> Self {
+        SimulatedOrder {
+            trader_index: order.trader_index,
+            is_bid: order.is_bid(),
+            price: order.get_price_raw(),
+            num_base_atoms: order.num_base_atoms,
+            order_type: order.get_order_type(),
+            reverse_spread: order.reverse_spread,
+        }
+    }
+
+    /// Price the order comes back at on the other side once filled, rounded
+    /// like the program. Orders with DYNAMIC_REVERSE_SPREAD use the market
+    /// volatility estimate. None for orders that do not flip.
+    pub fn reverse_price(&self, volatility_bps: u16) -> Option<u128> {
+        let base: u128 = match self.order_type {
+            OrderType::Reverse => 100_000,
+            OrderType::ReverseTight => 100_000_000,
+            _ => return None,
+        };
+        let spread: u128 = if self.reverse_spread == DYNAMIC_REVERSE_SPREAD {
+            volatility_bps.min(MAX_VOLATILITY_BPS) as u128 * (base / 10_000)
+        } else {
+            self.reverse_spread as u128
+        };
+        if self.is_bid {
+            // Bid @P comes back as an ask @P / (1 - spread).
+            Some(self.price.checked_mul(base)? / (base - spread))
+        } else {
+            // Ask @P comes back as a bid @P * (1 - spread).
+            Some(self.price.checked_mul(base - spread)?.div_ceil(base))
+        }
+    }
+
+    /// Reverse orders of the same trader within one raw price unit coalesce
+    /// instead of resting next to each other.
+    fn coalesces_with(&self, other: &SimulatedOrder) -> bool {
+        self.trader_index == other.trader_index
+            && self.order_type == other.order_type
+            && self.price.abs_diff(other.price) <= 1
+    }
+}
+
+/// A reverse order placed or grown by a swap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReverseFlip {
+    pub trader_index: DataIndex,
+    /// Side of the flipped order, opposite to the order that was filled.
+    pub is_bid: bool,
+    pub price: u128,
+    /// Base atoms added to the book.
+    pub num_base_atoms: u64,
+    /// Whether the flip grew a resting order instead of placing a new one.
+    pub coalesced: bool,
+}
+
+/// Result of a swap against a [`ReverseBook`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulatedSwap {
+    /// Base atoms filled, less than requested if the book ran out.
+    pub base_atoms: u64,
+    /// Quote atoms paid when buying or received when selling.
+    pub quote_atoms: u64,
+    /// Reverse orders placed or grown, in the order of the fills.
+    pub flips: Vec<ReverseFlip>,
+}
+
+/// Both sides of a book, best order first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReverseBook {
+    pub bids: Vec<SimulatedOrder>,
+    pub asks: Vec<SimulatedOrder>,
+    /// Market volatility estimate the dynamic spread orders flip at. Set it
+    /// to see how a grid behaves at another estimate.
+    pub volatility_bps: u16,
+}
+
+impl ReverseBook {
+    /// Book of the orders given best first.
+    pub fn new(bids: Vec<SimulatedOrder>, asks: Vec<SimulatedOrder>, volatility_bps: u16) -> Self {
+        ReverseBook {
+            bids,
+            asks,
+            volatility_bps,
+        }
+    }
+
+    /// Fills base_atoms against the asks when buying or the bids when
+    /// selling, and flips every reverse order it fills onto the other side.
+    /// Later swaps see the flipped orders, which is how a cascade plays out.
+    pub fn swap(&mut self, base_atoms: u64, is_buy: bool) -> SimulatedSwap {
+        let mut remaining_base_atoms: u64 = base_atoms;
+        let mut quote_atoms: u128 = 0;
+        let mut flips: Vec<ReverseFlip> = Vec::new();
+        while remaining_base_atoms > 0 {
+            let makers: &mut Vec<SimulatedOrder> = if is_buy {
+                &mut self.asks
+            } else {
+                &mut self.bids
+            };
+            let Some(maker) = makers.first_mut() else {
+                break;
+            };
+            let base_atoms_traded: u64 = maker.num_base_atoms.min(remaining_base_atoms);
+            let did_fully_match: bool = base_atoms_traded == maker.num_base_atoms;
+            // Rounds for the taker on full fills and for the maker on partial
+            // fills.
+            let product: u128 = maker.price.saturating_mul(base_atoms_traded as u128);
+            let quote_atoms_traded: u128 = if is_buy != did_fully_match {
+                product.div_ceil(D18)
+            } else {
+                product / D18
+            };
+            maker.num_base_atoms -= base_atoms_traded;
+            let filled: SimulatedOrder = *maker;
+            if did_fully_match {
+                makers.remove(0);
+            }
+            remaining_base_atoms -= base_atoms_traded;
+            quote_atoms += quote_atoms_traded;
+
+            if let Some(flip) = self.flip(&filled, base_atoms_traded, quote_atoms_traded) {
+                flips.push(flip);
+            }
+        }
+        SimulatedSwap {
+            base_atoms: base_atoms - remaining_base_atoms,
+            quote_atoms: quote_atoms.min(u64::MAX as u128) as u64,
+            flips,
+        }
+    }
+
+    fn flip(
+        &mut self,
+        filled: &SimulatedOrder,
+        base_atoms_traded: u64,
+        quote_atoms_traded: u128,
+    ) -> Option<ReverseFlip> {
+        let price: u128 = filled.reverse_price(self.volatility_bps)?;
+        let is_bid: bool = !filled.is_bid;
+        // A flipped bid buys back with exactly the quote the ask received.
+        let num_base_atoms: u64 = if is_bid {
+            quote_atoms_traded
+                .saturating_mul(D18)
+                .checked_div(price)
+                .unwrap_or(0)
+                .min(u64::MAX as u128) as u64
+        } else {
+            base_atoms_traded
+        };
+        let reverse_order: SimulatedOrder = SimulatedOrder {
+            is_bid,
+            price,
+            num_base_atoms,
+            ..*filled
+        };
+
+        let side: &mut Vec<SimulatedOrder> = if is_bid {
+            &mut self.bids
+        } else {
+            &mut self.asks
+        };
+        if let Some(existing) = side
+            .iter_mut()
+            .find(|order| order.coalesces_with(&reverse_order))
+        {
+            let num_base_atoms_added: u64 = if is_bid {
+                coalesced_bid_base_atoms(
+                    existing.price,
+                    existing.num_base_atoms,
+                    num_base_atoms,
+                    quote_atoms_traded,
+                )
+            } else {
+                num_base_atoms
+            };
+            existing.num_base_atoms += num_base_atoms_added;
+            return Some(ReverseFlip {
+                trader_index: filled.trader_index,
+                is_bid,
+                price: existing.price,
+                num_base_atoms: num_base_atoms_added,
+                coalesced: true,
+            });
+        }
+
+        // One atom fills can flip into nothing.
+        if num_base_atoms == 0 {
+            return None;
+        }
+        // Behind orders at the same price, like any new order.
+        let position: usize = side
+            .iter()
+            .position(|order| {
+                if is_bid {
+                    order.price < price
+                } else {
+                    order.price > price
+                }
+            })
+            .unwrap_or(side.len());
+        side.insert(position, reverse_order);
+        Some(ReverseFlip {
+            trader_index: filled.trader_index,
+            is_bid,
+            price,
+            num_base_atoms,
+            coalesced: false,
+        })
+    }
+}
+
+/// Base atoms a flipped bid adds to a resting bid it coalesces into, so that
+/// what the order backs grows by no more than the quote the maker received.
+fn coalesced_bid_base_atoms(
+    price: u128,
+    old_base_atoms: u64,
+    requested_base_atoms: u64,
+    quote_atoms_received: u128,
+) -> u64 {
+    let quote_for_base = |base_atoms: u64| price.saturating_mul(base_atoms as u128).div_ceil(D18);
+    let previous_quote: u128 = quote_for_base(old_base_atoms);
+    let requested_total_base_atoms: u64 = old_base_atoms.saturating_add(requested_base_atoms);
+    if quote_for_base(requested_total_base_atoms) - previous_quote <= quote_atoms_received {
+        return requested_base_atoms;
+    }
+    let affordable_total_base_atoms: u128 =
+        (previous_quote + quote_atoms_received).saturating_mul(D18) / price;
+    (affordable_total_base_atoms.saturating_sub(old_base_atoms as u128) as u64)
+        .min(requested_base_atoms)
+}
+
+impl<'a> Market<'a> {
+    /// Copy of the book for [`ReverseBook::swap`]. Orders expired at
+    /// current_slot are left out, since swaps skip them.
+    pub fn get_reverse_book(&'a self, current_slot: u32) -> ReverseBook {
+        let live_orders = |(_, order): (_, RestingOrder)| {
+            (!order.is_expired(current_slot)).then(|| SimulatedOrder::from_resting_order(&order))
+        };
+        ReverseBook::new(
+            self.iter_bids().filter_map(live_orders).collect(),
+            self.iter_asks().filter_map(live_orders).collect(),
+            self.fixed.volatility_bps,
+        )
+    }
+}
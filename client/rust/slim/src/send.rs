@@ -0,0 +1,331 @@
+//! Transaction sending with retries, blockhash refresh and priority fee
+//! escalation. Requires the `rpc` feature.
+
+use std::{fmt, time::Duration};
+
+use solana_client::{
+    client_error::ClientError,
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::{Instruction, InstructionError},
+    message::{v0, AddressLookupTableAccount, CompileError, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{Signer, SignerError},
+    transaction::{Transaction, TransactionError, VersionedTransaction},
+};
+
+use crate::{
+    bundle::LookupTable,
+    constants::MANIFEST_PROGRAM_ID,
+    error::{decode_manifest_error, DecodedManifestError},
+};
+
+/// How often the signature status is polled while waiting for confirmation.
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Settings for [`send_with_retry`].
+#[derive(Debug, Clone)]
+pub struct SendConfig {
+    /// Number of transactions to send before giving up. Each attempt uses a
+    /// fresh blockhash.
+    pub max_attempts: u32,
+    /// Wait before the second attempt. Doubles on each attempt after that.
+    pub initial_backoff: Duration,
+    /// Compute unit price of the first attempt in micro lamports. Zero sends
+    /// without a priority fee and never escalates.
+    pub compute_unit_price: u64,
+    /// Compute unit price is doubled on every retry up to this cap.
+    pub max_compute_unit_price: u64,
+    /// Compute unit limit instruction to add, if any.
+    pub compute_unit_limit: Option<u32>,
+    /// Commitment used for the blockhash, simulation and confirmation.
+    pub commitment: CommitmentConfig,
+}
+
+impl Default for SendConfig {
+    fn default() -> Self {
+        SendConfig {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            compute_unit_price: 1_000,
+            max_compute_unit_price: 1_000_000,
+            compute_unit_limit: None,
+            commitment: CommitmentConfig::confirmed(),
+        }
+    }
+}
+
+/// Why [`send_with_retry`] did not land a transaction.
+#[derive(Debug)]
+pub enum SendError {
+    /// Simulation failed inside a Manifest instruction. Retrying cannot help,
+    /// so this is returned on the first attempt. `code` is the ManifestError
+    /// discriminant and `logs` has the message from the failed require, see
+    /// [`SendError::manifest_error`].
+    Manifest {
+        instruction_index: u8,
+        code: u32,
+        logs: Vec<String>,
+    },
+    /// Simulation failed for any other reason.
+    Simulation {
+        error: TransactionError,
+        logs: Vec<String>,
+    },
+    /// The transaction landed but failed.
+    Failed {
+        signature: Signature,
+        error: TransactionError,
+    },
+    /// Signing failed.
+    Signer(SignerError),
+    /// The instructions do not compile to a v0 message.
+    Compile(CompileError),
+    /// The last attempt hit an RPC error.
+    Rpc(ClientError),
+    /// No attempt was confirmed before its blockhash expired.
+    Expired { attempts: u32 },
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Manifest {
+                instruction_index,
+                code,
+                ..
+            } => write!(
+                f,
+                "manifest error {} in instruction {}",
+                code, instruction_index
+            ),
+            SendError::Simulation { error, .. } => write!(f, "simulation failed: {}", error),
+            SendError::Failed { signature, error } => {
+                write!(f, "transaction {} failed: {}", signature, error)
+            }
+            SendError::Signer(error) => write!(f, "signing failed: {}", error),
+            SendError::Compile(error) => write!(f, "compiling message failed: {}", error),
+            SendError::Rpc(error) => write!(f, "rpc error: {}", error),
+            SendError::Expired { attempts } => {
+                write!(f, "not confirmed after {} attempts", attempts)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
+
+impl SendError {
+    /// Typed Manifest error with the require message, if simulation failed in
+    /// a Manifest instruction.
+    pub fn manifest_error(&self) -> Option<DecodedManifestError> {
+        match self {
+            SendError::Manifest { code, logs, .. } => decode_manifest_error(*code, logs),
+            _ => None,
+        }
+    }
+}
+
+/// Compute unit price for the attempt after one sent at `price`.
+pub fn next_compute_unit_price(price: u64, max_price: u64) -> u64 {
+    price.saturating_mul(2).min(max_price)
+}
+
+/// Manifest error code from a failed transaction, if the failing instruction
+/// was a Manifest instruction returning a custom error.
+pub fn manifest_error_code(
+    transaction: &Transaction,
+    error: &TransactionError,
+) -> Option<(u8, u32)> {
+    match error {
+        TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+            let program_id: &Pubkey = transaction.message.program_id(*index as usize)?;
+            if program_id.to_bytes() == MANIFEST_PROGRAM_ID.to_bytes() {
+                Some((*index, *code))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn build_transaction(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+    blockhash: Hash,
+    compute_unit_price: u64,
+    compute_unit_limit: Option<u32>,
+) -> Result<Transaction, SendError> {
+    let mut all_instructions: Vec<Instruction> = Vec::with_capacity(instructions.len() + 2);
+    if let Some(limit) = compute_unit_limit {
+        all_instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(limit));
+    }
+    if compute_unit_price > 0 {
+        all_instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
+            compute_unit_price,
+        ));
+    }
+    all_instructions.extend_from_slice(instructions);
+
+    let mut transaction: Transaction = Transaction::new_with_payer(&all_instructions, Some(payer));
+    transaction
+        .try_sign(signers, blockhash)
+        .map_err(SendError::Signer)?;
+    Ok(transaction)
+}
+
+/// Signed v0 transaction that loads accounts through lookup_tables, for
+/// example one transaction of a [`build_bundle`](crate::build_bundle) bundle.
+pub fn versioned_transaction(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+    blockhash: Hash,
+    lookup_tables: &[LookupTable],
+) -> Result<VersionedTransaction, SendError> {
+    let lookup_table_accounts: Vec<AddressLookupTableAccount> = lookup_tables
+        .iter()
+        .map(|table| AddressLookupTableAccount {
+            key: table.key,
+            addresses: table.addresses.clone(),
+        })
+        .collect();
+    let message: v0::Message =
+        v0::Message::try_compile(payer, instructions, &lookup_table_accounts, blockhash)
+            .map_err(SendError::Compile)?;
+    VersionedTransaction::try_new(VersionedMessage::V0(message), signers).map_err(SendError::Signer)
+}
+
+/// Send instructions in one transaction and wait for confirmation.
+///
+/// Every attempt fetches a new blockhash and is simulated before sending, so
+/// program errors come back as [`SendError::Manifest`] or
+/// [`SendError::Simulation`] right away instead of being retried. A sent
+/// attempt is only replaced once its blockhash has expired, so the same
+/// instructions cannot land twice. Compute budget instructions are prepended,
+/// which shifts `instruction_index` in errors by their count.
+pub async fn send_with_retry(
+    rpc: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+    config: &SendConfig,
+) -> Result<Signature, SendError> {
+    let mut compute_unit_price: u64 = config.compute_unit_price;
+    let mut backoff: Duration = config.initial_backoff;
+    let mut last_rpc_error: Option<ClientError> = None;
+
+    for attempt in 0..config.max_attempts {
+        last_rpc_error = None;
+        if attempt > 0 {
+            tokio::time::sleep(backoff).await;
+            backoff = backoff.saturating_mul(2);
+            compute_unit_price =
+                next_compute_unit_price(compute_unit_price, config.max_compute_unit_price);
+        }
+
+        let blockhash: Hash = match rpc
+            .get_latest_blockhash_with_commitment(config.commitment)
+            .await
+        {
+            Ok((blockhash, _last_valid_block_height)) => blockhash,
+            Err(error) => {
+                last_rpc_error = Some(error);
+                continue;
+            }
+        };
+        let transaction: Transaction = build_transaction(
+            instructions,
+            payer,
+            signers,
+            blockhash,
+            compute_unit_price,
+            config.compute_unit_limit,
+        )?;
+
+        // Preflight is done here rather than by the RPC so that the logs are
+        // available and program errors are not retried.
+        match rpc
+            .simulate_transaction_with_config(
+                &transaction,
+                RpcSimulateTransactionConfig {
+                    commitment: Some(config.commitment),
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )
+            .await
+        {
+            Ok(response) => {
+                if let Some(error) = response.value.err {
+                    if error == TransactionError::BlockhashNotFound {
+                        continue;
+                    }
+                    let logs: Vec<String> = response.value.logs.unwrap_or_default();
+                    return Err(match manifest_error_code(&transaction, &error) {
+                        Some((instruction_index, code)) => SendError::Manifest {
+                            instruction_index,
+                            code,
+                            logs,
+                        },
+                        None => SendError::Simulation { error, logs },
+                    });
+                }
+            }
+            Err(error) => {
+                last_rpc_error = Some(error);
+                continue;
+            }
+        }
+
+        let signature: Signature = match rpc
+            .send_transaction_with_config(
+                &transaction,
+                RpcSendTransactionConfig {
+                    skip_preflight: true,
+                    ..RpcSendTransactionConfig::default()
+                },
+            )
+            .await
+        {
+            Ok(signature) => signature,
+            Err(error) => {
+                last_rpc_error = Some(error);
+                continue;
+            }
+        };
+
+        loop {
+            match rpc
+                .get_signature_status_with_commitment(&signature, config.commitment)
+                .await
+            {
+                Ok(Some(Ok(()))) => return Ok(signature),
+                Ok(Some(Err(error))) => return Err(SendError::Failed { signature, error }),
+                Ok(None) => {}
+                Err(error) => last_rpc_error = Some(error),
+            }
+            // Only move on once this attempt can no longer land.
+            match rpc.is_blockhash_valid(&blockhash, config.commitment).await {
+                Ok(false) => break,
+                Ok(true) => {}
+                Err(error) => last_rpc_error = Some(error),
+            }
+            tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+        }
+    }
+
+    Err(match last_rpc_error {
+        Some(error) => SendError::Rpc(error),
+        None => SendError::Expired {
+            attempts: config.max_attempts,
+        },
+    })
+}
@@ -0,0 +1,145 @@
+//! Compact archival snapshots of market accounts.
+//!
+//! A snapshot is the raw market account at a slot, with the stale bytes in
+//! free blocks cleared and the whole thing compressed with zstd. Decoding
+//! gives back account data that parses with [`Market::try_from_bytes`], so
+//! historical books can be read with the same view structs as live ones.
+//!
+//! Layout:
+//! - magic: `b"MNFS"`
+//! - version: u8
+//! - slot: u64 LE
+//! - market: 32 byte pubkey
+//! - zstd frame of the account data
+
+use crate::{
+    constants::{MARKET_BLOCK_SIZE, MARKET_FIXED_SIZE},
+    state::{Market, MarketFixed},
+};
+use hypertree::{DataIndex, NIL};
+use solana_pubkey::Pubkey;
+
+const SNAPSHOT_MAGIC: [u8; 4] = *b"MNFS";
+const SNAPSHOT_VERSION: u8 = 1;
+const SNAPSHOT_HEADER_SIZE: usize = 4 + 1 + 8 + 32;
+
+/// Default zstd level. Market accounts are mostly zeroes and repeated keys so
+/// higher levels gain little.
+pub const SNAPSHOT_DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+
+/// Errors from encoding or decoding a snapshot.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The account data is not a market.
+    InvalidMarket,
+    /// The snapshot header is missing or has the wrong magic.
+    InvalidHeader,
+    /// The snapshot was written by a newer version of this crate.
+    UnsupportedVersion(u8),
+    /// zstd failed to compress or decompress.
+    Compression(std::io::Error),
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::InvalidMarket => write!(f, "account data is not a market"),
+            SnapshotError::InvalidHeader => write!(f, "invalid snapshot header"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::Compression(err) => write!(f, "zstd error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(err: std::io::Error) -> Self {
+        SnapshotError::Compression(err)
+    }
+}
+
+/// A decoded market snapshot.
+#[derive(Debug, Clone)]
+pub struct MarketSnapshot {
+    /// Slot the account was read at.
+    pub slot: u64,
+    /// Market account address.
+    pub market: Pubkey,
+    /// Account data, with free blocks cleared.
+    pub data: Vec<u8>,
+}
+
+impl MarketSnapshot {
+    /// Copy a market account for archiving. Fails if the data is not a market.
+    pub fn new(slot: u64, market: Pubkey, account_data: &[u8]) -> Result<Self, SnapshotError> {
+        let fixed: &MarketFixed =
+            MarketFixed::try_from_bytes(account_data).ok_or(SnapshotError::InvalidMarket)?;
+        let free_list_head_index: DataIndex = fixed.free_list_head_index;
+        let mut data: Vec<u8> = account_data.to_vec();
+        clear_free_blocks(&mut data[MARKET_FIXED_SIZE..], free_list_head_index);
+        Ok(MarketSnapshot { slot, market, data })
+    }
+
+    /// View the snapshot with the same parser used for live accounts.
+    pub fn market(&self) -> Option<Market<'_>> {
+        Market::try_from_bytes(&self.data)
+    }
+
+    /// Serialize and compress at [`SNAPSHOT_DEFAULT_COMPRESSION_LEVEL`].
+    pub fn encode(&self) -> Result<Vec<u8>, SnapshotError> {
+        self.encode_with_level(SNAPSHOT_DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    /// Serialize and compress at the given zstd level.
+    pub fn encode_with_level(&self, level: i32) -> Result<Vec<u8>, SnapshotError> {
+        let mut encoded: Vec<u8> = Vec::with_capacity(SNAPSHOT_HEADER_SIZE + self.data.len() / 4);
+        encoded.extend_from_slice(&SNAPSHOT_MAGIC);
+        encoded.push(SNAPSHOT_VERSION);
+        encoded.extend_from_slice(&self.slot.to_le_bytes());
+        encoded.extend_from_slice(self.market.as_ref());
+        zstd::stream::copy_encode(&self.data[..], &mut encoded, level)?;
+        Ok(encoded)
+    }
+
+    /// Decompress and parse a snapshot produced by [`MarketSnapshot::encode`].
+    pub fn decode(encoded: &[u8]) -> Result<Self, SnapshotError> {
+        if encoded.len() < SNAPSHOT_HEADER_SIZE || encoded[0..4] != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::InvalidHeader);
+        }
+        let version: u8 = encoded[4];
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let slot: u64 = u64::from_le_bytes(encoded[5..13].try_into().unwrap());
+        let market: Pubkey = Pubkey::new_from_array(encoded[13..45].try_into().unwrap());
+        let data: Vec<u8> = zstd::stream::decode_all(&encoded[SNAPSHOT_HEADER_SIZE..])?;
+        if MarketFixed::try_from_bytes(&data).is_none() {
+            return Err(SnapshotError::InvalidMarket);
+        }
+        Ok(MarketSnapshot { slot, market, data })
+    }
+}
+
+/// Zero everything but the next pointer in each free block. Released blocks
+/// keep whatever order or seat was last there, which is noise for archiving
+/// and compresses poorly.
+fn clear_free_blocks(dynamic: &mut [u8], free_list_head_index: DataIndex) {
+    let mut index: DataIndex = free_list_head_index;
+    // Bounded by the number of blocks in case the list is corrupt.
+    let mut remaining: usize = dynamic.len() / MARKET_BLOCK_SIZE;
+    while index != NIL && remaining > 0 {
+        let start: usize = index as usize;
+        let end: usize = start + MARKET_BLOCK_SIZE;
+        if end > dynamic.len() {
+            return;
+        }
+        let next_index: DataIndex =
+            DataIndex::from_le_bytes(dynamic[start..start + 4].try_into().unwrap());
+        dynamic[start + 4..end].fill(0);
+        index = next_index;
+        remaining -= 1;
+    }
+}
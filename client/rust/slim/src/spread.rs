@@ -0,0 +1,215 @@
+//! Spread trades that buy base on one market and sell the same amount on
+//! another in one transaction, for basis and cross market traders.
+//!
+//! Both swaps are in the same transaction, so either both legs fill or
+//! neither does. Each swap only has its own limit, so the combined check is
+//! split between them: the buy may cost at most `buy_max_quote_in` and the
+//! sell has to return at least that plus the minimum edge. Whatever the buy
+//! costs, the sell then returns at least the cost plus the edge.
+
+use std::fmt;
+
+use solana_instruction::{AccountMeta, Instruction};
+use solana_pubkey::Pubkey;
+
+use crate::{
+    constants::{COMPUTE_BUDGET_PROGRAM_ID, TOKEN_2022_PROGRAM_ID},
+    instruction::{swap_instruction, SwapParams},
+    state::Market,
+};
+
+/// Compute units of a swap that fills nothing, with headroom.
+pub const SWAP_BASE_COMPUTE_UNITS: u32 = 50_000;
+/// Compute units per resting order a swap fills against, with headroom.
+pub const SWAP_FILL_COMPUTE_UNITS: u32 = 6_000;
+
+/// Why a spread trade could not be planned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpreadError {
+    /// The legs do not trade the same base and quote mints.
+    MismatchedMints,
+    /// One of the books does not have enough size.
+    InsufficientLiquidity,
+    /// The books give less than the minimum edge.
+    EdgeTooSmall,
+}
+
+impl fmt::Display for SpreadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SpreadError::MismatchedMints => "Legs trade different mints",
+            SpreadError::InsufficientLiquidity => "Not enough size on the book",
+            SpreadError::EdgeTooSmall => "Spread is less than the minimum edge",
+        })
+    }
+}
+
+impl std::error::Error for SpreadError {}
+
+/// Accounts of the trader on one market of a spread trade.
+#[derive(Debug, Clone, Copy)]
+pub struct SpreadLeg {
+    pub market: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub trader_base: Pubkey,
+    pub trader_quote: Pubkey,
+    pub token_program_base: Pubkey,
+    pub token_program_quote: Pubkey,
+}
+
+/// Limits of both legs and the compute units the transaction needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpreadPlan {
+    pub base_atoms: u64,
+    /// Quote the buy leg may spend.
+    pub buy_max_quote_in: u64,
+    /// Quote the sell leg has to return.
+    pub sell_min_quote_out: u64,
+    /// Proceeds minus cost at the current books.
+    pub expected_edge_quote_atoms: i128,
+    pub compute_units: u32,
+}
+
+/// Quote atoms to fill base_atoms against (raw price, base atoms) orders from
+/// best to worst, rounded up per fill when buying like the program does, and
+/// the number of orders filled. None if the orders do not have enough size.
+pub fn fill_quote_atoms(
+    orders: impl IntoIterator<Item = (u128, u64)>,
+    base_atoms: u64,
+    is_buy: bool,
+) -> Option<(u64, u32)> {
+    let mut remaining_base_atoms: u64 = base_atoms;
+    let mut quote_atoms: u128 = 0;
+    let mut num_fills: u32 = 0;
+    for (price, order_base_atoms) in orders {
+        if remaining_base_atoms == 0 {
+            break;
+        }
+        let fill_base_atoms: u64 = order_base_atoms.min(remaining_base_atoms);
+        let product: u128 = price.checked_mul(fill_base_atoms as u128)?;
+        quote_atoms += if is_buy {
+            product.div_ceil(1_000_000_000_000_000_000)
+        } else {
+            product / 1_000_000_000_000_000_000
+        };
+        remaining_base_atoms -= fill_base_atoms;
+        num_fills += 1;
+    }
+    if remaining_base_atoms > 0 {
+        return None;
+    }
+    Some((u64::try_from(quote_atoms).ok()?, num_fills))
+}
+
+/// Splits the slack between the books and min_edge_quote_atoms evenly
+/// between the legs. min_edge_quote_atoms can be negative to pay for a hedge.
+pub fn plan_spread_trade(
+    buy_market: &Market,
+    sell_market: &Market,
+    base_atoms: u64,
+    min_edge_quote_atoms: i64,
+    current_slot: u32,
+) -> Result<SpreadPlan, SpreadError> {
+    if buy_market.get_base_mint() != sell_market.get_base_mint()
+        || buy_market.get_quote_mint() != sell_market.get_quote_mint()
+    {
+        return Err(SpreadError::MismatchedMints);
+    }
+    let live_orders = |(_, order): (_, crate::RestingOrder)| {
+        (!order.is_expired(current_slot)).then(|| (order.get_price_raw(), order.num_base_atoms))
+    };
+    let (buy_quote_atoms, buy_fills) = fill_quote_atoms(
+        buy_market.iter_asks().filter_map(live_orders),
+        base_atoms,
+        true,
+    )
+    .ok_or(SpreadError::InsufficientLiquidity)?;
+    let (sell_quote_atoms, sell_fills) = fill_quote_atoms(
+        sell_market.iter_bids().filter_map(live_orders),
+        base_atoms,
+        false,
+    )
+    .ok_or(SpreadError::InsufficientLiquidity)?;
+    plan_from_quotes(
+        base_atoms,
+        buy_quote_atoms,
+        sell_quote_atoms,
+        min_edge_quote_atoms,
+        buy_fills + sell_fills,
+    )
+}
+
+/// Same as [`plan_spread_trade`] given the quote each leg costs or returns at
+/// the current books and how many orders both legs fill.
+pub fn plan_from_quotes(
+    base_atoms: u64,
+    buy_quote_atoms: u64,
+    sell_quote_atoms: u64,
+    min_edge_quote_atoms: i64,
+    num_fills: u32,
+) -> Result<SpreadPlan, SpreadError> {
+    let expected_edge_quote_atoms: i128 = sell_quote_atoms as i128 - buy_quote_atoms as i128;
+    let slack: i128 = expected_edge_quote_atoms - min_edge_quote_atoms as i128;
+    if slack < 0 {
+        return Err(SpreadError::EdgeTooSmall);
+    }
+    let buy_max_quote_in: i128 = buy_quote_atoms as i128 + slack / 2;
+    let sell_min_quote_out: i128 = buy_max_quote_in + min_edge_quote_atoms as i128;
+    Ok(SpreadPlan {
+        base_atoms,
+        buy_max_quote_in: buy_max_quote_in as u64,
+        sell_min_quote_out: sell_min_quote_out.max(0) as u64,
+        expected_edge_quote_atoms,
+        compute_units: 2 * SWAP_BASE_COMPUTE_UNITS
+            + num_fills.saturating_mul(SWAP_FILL_COMPUTE_UNITS),
+    })
+}
+
+/// Instructions of a spread trade: a compute unit limit, an exact out swap
+/// buying plan.base_atoms on the buy leg and an exact in swap selling them on
+/// the sell leg. Base mints with a transfer fee arrive short and make the
+/// sell fail.
+pub fn spread_trade_instructions(
+    payer: Pubkey,
+    buy_leg: &SpreadLeg,
+    sell_leg: &SpreadLeg,
+    plan: &SpreadPlan,
+) -> Vec<Instruction> {
+    vec![
+        set_compute_unit_limit_instruction(plan.compute_units),
+        leg_swap_instruction(
+            payer,
+            buy_leg,
+            SwapParams::new(plan.buy_max_quote_in, plan.base_atoms, false, false),
+        ),
+        leg_swap_instruction(
+            payer,
+            sell_leg,
+            SwapParams::new(plan.base_atoms, plan.sell_min_quote_out, true, true),
+        ),
+    ]
+}
+
+fn leg_swap_instruction(payer: Pubkey, leg: &SpreadLeg, params: SwapParams) -> Instruction {
+    swap_instruction(
+        payer,
+        leg.market,
+        leg.trader_base,
+        leg.trader_quote,
+        leg.base_mint,
+        leg.quote_mint,
+        leg.token_program_base,
+        (leg.token_program_quote != leg.token_program_base).then_some(leg.token_program_quote),
+        leg.token_program_base == TOKEN_2022_PROGRAM_ID,
+        leg.token_program_quote == TOKEN_2022_PROGRAM_ID,
+        params,
+    )
+}
+
+/// ComputeBudget SetComputeUnitLimit, built by hand to keep the SDK out.
+fn set_compute_unit_limit_instruction(units: u32) -> Instruction {
+    let mut data: Vec<u8> = vec![2];
+    data.extend_from_slice(&units.to_le_bytes());
+    Instruction::new_with_bytes(COMPUTE_BUDGET_PROGRAM_ID, &data, Vec::<AccountMeta>::new())
+}
@@ -1,8 +1,13 @@
 //! Market state parsing for Manifest.
 
 use crate::constants::{
-    OrderType, CLAIMED_SEAT_SIZE, MARKET_FIXED_DISCRIMINANT, MARKET_FIXED_SIZE,
-    NO_EXPIRATION_LAST_VALID_SLOT, RESTING_ORDER_SIZE,
+    OrderType, CLAIMED_SEAT_SIZE, FEE_TIERS, MARKET_BLOCK_SIZE, MARKET_EXTENSION_FLAG_FILL_RECORDS,
+    MARKET_EXTENSION_FLAG_PAUSED, MARKET_FIXED_DISCRIMINANT, MARKET_FIXED_SIZE,
+    MARKET_FLAG_CPI_ALLOWLIST, MARKET_FLAG_EXTENSION, MARKET_FLAG_FEES, MARKET_FLAG_MIN_ORDER_SIZE,
+    MARKET_FLAG_NO_INTERNAL_CROSS, MARKET_FLAG_PRICE_BAND, MARKET_FLAG_RECOVERY,
+    MARKET_FLAG_VOLUME_STATS, MAX_CPI_ALLOWLIST_PROGRAMS, NO_EXPIRATION_LAST_VALID_SLOT,
+    RESTING_ORDER_SIZE, RFQ_DISCRIMINANT, RFQ_SIZE, SEAT_VOLUME_BUCKETS, SEAT_VOLUME_BUCKET_SLOTS,
+    SIGNED_ORDER_NONCES_DISCRIMINANT, SIGNED_ORDER_NONCES_SIZE, SIGNED_ORDER_NONCE_WINDOW,
 };
 use hypertree::{DataIndex, NIL, RBTREE_OVERHEAD_BYTES};
 use solana_pubkey::Pubkey;
@@ -20,7 +25,10 @@ pub struct MarketFixed {
     pub quote_mint_decimals: u8,
     pub base_vault_bump: u8,
     pub quote_vault_bump: u8,
-    pub _padding1: [u8; 3],
+    pub _padding1: [u8; 1],
+    /// Volatility estimate in basis points, kept by UpdateVolatility. Reverse
+    /// orders with DYNAMIC_REVERSE_SPREAD flip at this spread.
+    pub volatility_bps: u16,
 
     /// Base mint
     pub base_mint: [u8; 32],
@@ -52,12 +60,39 @@ pub struct MarketFixed {
     /// LinkedList representing all free blocks
     pub free_list_head_index: DataIndex,
 
-    pub _padding2: [u32; 1],
+    /// Slots an order rests before its maker can cancel it, zero is off.
+    pub min_resting_slots: u32,
 
     /// Quote volume traded over lifetime, can overflow.
     pub quote_volume: u64,
 
-    pub _padding3: [u64; 8],
+    /// MARKET_FLAG_* bits set at creation.
+    pub market_flags: u8,
+    /// Trading hours set at creation. Bit 0 of the days is Monday, zero days
+    /// means always open. Minutes are into the UTC day, close is exclusive.
+    pub trading_days: u8,
+    pub trading_open_minute: u16,
+    pub trading_close_minute: u16,
+    /// Slots between committing to a swap and executing it, zero is off.
+    pub taker_delay_slots: u16,
+
+    /// Quote atoms fills rounded in favor of the taker and of the maker over
+    /// the lifetime of the market, can overflow.
+    pub taker_rounding_quote_atoms: u64,
+    pub maker_rounding_quote_atoms: u64,
+
+    /// Price of the latest fill against the book and its slot, see
+    /// [`RestingOrder::get_price_raw`] for the encoding. Zero before the first.
+    pub last_trade_price: [u64; 2],
+    /// Time weighted average price as of last_trade_slot. The program rolls
+    /// it forward to the current slot in GetMarketPrices.
+    pub twap_price: [u64; 2],
+    pub last_trade_slot: u32,
+
+    /// Points per quote atom filled for seats with a seat config, as maker
+    /// and as taker. Both zero means the market does not award points.
+    pub maker_points_weight: u16,
+    pub taker_points_weight: u16,
 }
 
 impl MarketFixed {
@@ -97,10 +132,82 @@ impl MarketFixed {
         Pubkey::from(self.quote_vault)
     }
 
+    /// Check if orders that would cross the same seat fail on this market.
+    pub fn is_no_internal_cross(&self) -> bool {
+        self.market_flags & MARKET_FLAG_NO_INTERNAL_CROSS != 0
+    }
+
+    /// Check if trading on this market has to come through a CPI from a
+    /// program on its allowlist.
+    pub fn has_cpi_allowlist(&self) -> bool {
+        self.market_flags & MARKET_FLAG_CPI_ALLOWLIST != 0
+    }
+
+    /// Check if orders that could rest on this market need a minimum size.
+    pub fn has_min_order_size(&self) -> bool {
+        self.market_flags & MARKET_FLAG_MIN_ORDER_SIZE != 0
+    }
+
+    /// Check if takers on this market cannot fill far from the reference price.
+    pub fn has_price_band(&self) -> bool {
+        self.market_flags & MARKET_FLAG_PRICE_BAND != 0
+    }
+
+    /// Check if makers and takers on this market pay fees on fills.
+    pub fn has_fees(&self) -> bool {
+        self.market_flags & MARKET_FLAG_FEES != 0
+    }
+
+    /// Check if the market keeps totals of its fills.
+    pub fn has_volume_stats(&self) -> bool {
+        self.market_flags & MARKET_FLAG_VOLUME_STATS != 0
+    }
+
+    /// Check if the market has an extension block.
+    pub fn has_extension(&self) -> bool {
+        self.market_flags & MARKET_FLAG_EXTENSION != 0
+    }
+
+    /// Check if the market is in recovery, where only recovery withdraws work.
+    pub fn is_in_recovery(&self) -> bool {
+        self.market_flags & MARKET_FLAG_RECOVERY != 0
+    }
+
+    /// Check if the market takes new orders and swaps at a unix timestamp.
+    /// Cancels and withdraws are allowed at any time.
+    pub fn is_open_at(&self, unix_timestamp: i64) -> bool {
+        if self.trading_days == 0 {
+            return true;
+        }
+        let days_since_epoch: i64 = unix_timestamp.div_euclid(86_400);
+        // 1970-01-01 was a Thursday.
+        let weekday: i64 = (days_since_epoch + 3).rem_euclid(7);
+        let minute: i64 = unix_timestamp.rem_euclid(86_400) / 60;
+        self.trading_days & (1 << weekday) != 0
+            && minute >= self.trading_open_minute as i64
+            && minute < self.trading_close_minute as i64
+    }
+
     /// Check if there's a free block available.
     pub fn has_free_block(&self) -> bool {
         self.free_list_head_index != NIL
     }
+
+    /// Number of blocks in the dynamic part of the account, used or free.
+    pub fn get_num_allocated_blocks(&self) -> u32 {
+        self.num_bytes_allocated / MARKET_BLOCK_SIZE as u32
+    }
+
+    /// Get the last trade price as a float (approximate), None before the
+    /// first trade.
+    pub fn get_last_trade_price_float(&self) -> Option<f64> {
+        let raw: u128 =
+            u128::from(self.last_trade_price[0]) | (u128::from(self.last_trade_price[1]) << 64);
+        if raw == 0 {
+            return None;
+        }
+        Some((raw as f64) / 1e18)
+    }
 }
 
 /// A resting order on the book.
@@ -121,9 +228,18 @@ pub struct RestingOrder {
     pub is_bid: u8,
     /// Order type
     pub order_type: u8,
-    /// Spread for reverse orders
+    /// Spread for reverse orders, or 1 when last_valid_slot is a unix
+    /// timestamp
     pub reverse_spread: u16,
-    pub _padding: [u8; 20],
+    /// Opaque tag set by the trader, echoed in fills
+    pub tag: u32,
+    /// Smallest fill a taker may make without taking the whole order, or the
+    /// display size of an iceberg order
+    pub min_taker_atoms: u64,
+    /// Slot the order was put on the book, zero if placed before it was tracked
+    pub placed_slot: u32,
+    /// Index of the linked one cancels other order plus one, zero if none
+    pub linked_index_plus_one: u32,
 }
 
 impl RestingOrder {
@@ -142,9 +258,62 @@ impl RestingOrder {
         self.get_order_type() == OrderType::Global
     }
 
-    /// Check if the order is expired.
+    /// Unix timestamp the order is valid until, for orders that expire by
+    /// the clock instead of a slot.
+    pub fn get_last_valid_unix_timestamp(&self) -> Option<u32> {
+        let is_reversible: bool = matches!(
+            self.get_order_type(),
+            OrderType::Reverse | OrderType::ReverseTight
+        );
+        (!is_reversible
+            && self.reverse_spread == 1
+            && self.last_valid_slot != NO_EXPIRATION_LAST_VALID_SLOT)
+            .then_some(self.last_valid_slot)
+    }
+
+    /// Check if the order is expired. Orders that expire by unix timestamp
+    /// need [`RestingOrder::is_expired_at`].
     pub fn is_expired(&self, current_slot: u32) -> bool {
-        self.last_valid_slot != NO_EXPIRATION_LAST_VALID_SLOT && self.last_valid_slot < current_slot
+        self.is_expired_at(current_slot, i64::MIN)
+    }
+
+    /// Check if the order is expired at a slot and unix timestamp.
+    pub fn is_expired_at(&self, current_slot: u32, now_unix_timestamp: i64) -> bool {
+        match self.get_last_valid_unix_timestamp() {
+            Some(last_valid_unix_timestamp) => {
+                (last_valid_unix_timestamp as i64) < now_unix_timestamp
+            }
+            None => {
+                self.last_valid_slot != NO_EXPIRATION_LAST_VALID_SLOT
+                    && self.last_valid_slot < current_slot
+            }
+        }
+    }
+
+    /// Get the size an iceberg order shows at a time, zero for other orders.
+    pub fn get_display_base_atoms(&self) -> u64 {
+        if self.get_order_type() == OrderType::Iceberg {
+            self.min_taker_atoms
+        } else {
+            0
+        }
+    }
+
+    /// Get the size that trades before the order goes to the back of its
+    /// price level. Icebergs show the odd lot over whole display sizes first.
+    pub fn get_visible_base_atoms(&self) -> u64 {
+        let display_base_atoms: u64 = self.get_display_base_atoms();
+        if display_base_atoms == 0 || self.num_base_atoms == 0 {
+            self.num_base_atoms
+        } else {
+            (self.num_base_atoms - 1) % display_base_atoms + 1
+        }
+    }
+
+    /// Check if the maker may cancel the order under the market's minimum
+    /// resting time.
+    pub fn has_rested(&self, current_slot: u32, min_resting_slots: u32) -> bool {
+        min_resting_slots == 0 || current_slot >= self.placed_slot.saturating_add(min_resting_slots)
     }
 
     /// Get the price as a u128.
@@ -171,7 +340,11 @@ pub struct ClaimedSeat {
     pub quote_withdrawable_balance: u64,
     /// Quote volume traded by this trader
     pub quote_volume: u64,
-    pub _padding: [u8; 8],
+    /// Index of the seat config block, only valid if has_seat_config is 1
+    pub seat_config_index: DataIndex,
+    pub has_seat_config: u8,
+    /// Index of the seat volume block plus one, little endian. Zero is none.
+    pub seat_volume_block: [u8; 3],
 }
 
 impl ClaimedSeat {
@@ -179,6 +352,17 @@ impl ClaimedSeat {
     pub fn get_trader(&self) -> Pubkey {
         Pubkey::from(self.trader)
     }
+
+    /// Index of the block with the rolling taker volume of the seat, None if
+    /// the market has no fee tiers or the seat was restored without one.
+    pub fn get_seat_volume_index(&self) -> Option<DataIndex> {
+        let [b0, b1, b2] = self.seat_volume_block;
+        let block: u32 = u32::from_le_bytes([b0, b1, b2, 0]);
+        if block == 0 {
+            return None;
+        }
+        Some((block - 1) * MARKET_BLOCK_SIZE as u32)
+    }
 }
 
 /// Red-black tree node header (comes before the payload).
@@ -217,6 +401,265 @@ impl<'a> Market<'a> {
         self.fixed.get_quote_mint()
     }
 
+    /// Programs allowed to CPI trading instructions, None if the market takes
+    /// them from anyone. The allowlist is the payload of the first block.
+    pub fn get_cpi_allowlist(&self) -> Option<Vec<Pubkey>> {
+        if !self.fixed.has_cpi_allowlist() {
+            return None;
+        }
+        let start: usize = RBTREE_OVERHEAD_BYTES;
+        let payload: &[u8] = self
+            .dynamic
+            .get(start..start + 32 * MAX_CPI_ALLOWLIST_PROGRAMS)?;
+        Some(
+            payload
+                .chunks_exact(32)
+                .map(|program| Pubkey::try_from(program).unwrap())
+                .filter(|program| *program != Pubkey::default())
+                .collect(),
+        )
+    }
+
+    /// Minimum base atoms and quote atoms at the order price for orders that
+    /// could rest, None if the market takes any size. The minimum is the
+    /// payload of the block after the allowlist, or of the first block.
+    pub fn get_min_order_size(&self) -> Option<(u64, u64)> {
+        if !self.fixed.has_min_order_size() {
+            return None;
+        }
+        let block: usize = if self.fixed.has_cpi_allowlist() {
+            MARKET_BLOCK_SIZE
+        } else {
+            0
+        };
+        let start: usize = block + RBTREE_OVERHEAD_BYTES;
+        let payload: &[u8] = self.dynamic.get(start..start + 16)?;
+        Some((
+            u64::from_le_bytes(payload[..8].try_into().unwrap()),
+            u64::from_le_bytes(payload[8..].try_into().unwrap()),
+        ))
+    }
+
+    /// Band in bps around the reference price that takers can fill within,
+    /// None if the market has no band. The band is the payload of the block
+    /// after the allowlist and the minimum order size.
+    pub fn get_price_band_bps(&self) -> Option<u16> {
+        if !self.fixed.has_price_band() {
+            return None;
+        }
+        let block: usize = (self.fixed.has_cpi_allowlist() as usize
+            + self.fixed.has_min_order_size() as usize)
+            * MARKET_BLOCK_SIZE;
+        let start: usize = block + RBTREE_OVERHEAD_BYTES;
+        let payload: &[u8] = self.dynamic.get(start..start + 2)?;
+        Some(u16::from_le_bytes(payload.try_into().unwrap()))
+    }
+
+    /// Payload of the fees block, the block after the allowlist, minimum
+    /// order size and price band the market has.
+    fn get_market_fees_payload(&self) -> Option<&[u8]> {
+        if !self.fixed.has_fees() {
+            return None;
+        }
+        let block: usize = (self.fixed.has_cpi_allowlist() as usize
+            + self.fixed.has_min_order_size() as usize
+            + self.fixed.has_price_band() as usize)
+            * MARKET_BLOCK_SIZE;
+        let start: usize = block + RBTREE_OVERHEAD_BYTES;
+        self.dynamic.get(start..start + 64)
+    }
+
+    /// Maker and taker fee in bps of what each receives from a fill, None if
+    /// the market charges no fees. Buyers pay in base, sellers in quote.
+    pub fn get_fee_bps(&self) -> Option<(u16, u16)> {
+        let payload: &[u8] = self.get_market_fees_payload()?;
+        Some((
+            u16::from_le_bytes(payload[..2].try_into().unwrap()),
+            u16::from_le_bytes(payload[2..4].try_into().unwrap()),
+        ))
+    }
+
+    /// Share of the taker fee in bps of the fee that swaps with a referrer
+    /// pay it, None if the market charges no fees.
+    pub fn get_referral_fee_bps(&self) -> Option<u16> {
+        let payload: &[u8] = self.get_market_fees_payload()?;
+        Some(u16::from_le_bytes(payload[4..6].try_into().unwrap()))
+    }
+
+    /// Base atoms and quote atoms of fees the market has charged and not
+    /// paid out, None if the market charges no fees.
+    pub fn get_accrued_fees(&self) -> Option<(u64, u64)> {
+        let payload: &[u8] = self.get_market_fees_payload()?;
+        Some((
+            u64::from_le_bytes(payload[8..16].try_into().unwrap()),
+            u64::from_le_bytes(payload[16..24].try_into().unwrap()),
+        ))
+    }
+
+    /// Owner of the token accounts claim_fees_instruction pays fees to, None
+    /// if the market charges no fees.
+    pub fn get_fee_recipient(&self) -> Option<Pubkey> {
+        let payload: &[u8] = self.get_market_fees_payload()?;
+        Some(Pubkey::new_from_array(payload[24..56].try_into().unwrap()))
+    }
+
+    /// Base atoms and quote atoms filled and the number of fills since the
+    /// market was created, None if the market keeps no stats. The stats are
+    /// the payload of the block after the fees. The last trade price is in
+    /// MarketFixed.
+    pub fn get_market_stats(&self) -> Option<(u64, u64, u64)> {
+        if !self.fixed.has_volume_stats() {
+            return None;
+        }
+        let block: usize = (self.fixed.has_cpi_allowlist() as usize
+            + self.fixed.has_min_order_size() as usize
+            + self.fixed.has_price_band() as usize
+            + self.fixed.has_fees() as usize)
+            * MARKET_BLOCK_SIZE;
+        let start: usize = block + RBTREE_OVERHEAD_BYTES;
+        let payload: &[u8] = self.dynamic.get(start..start + 24)?;
+        Some((
+            u64::from_le_bytes(payload[..8].try_into().unwrap()),
+            u64::from_le_bytes(payload[8..16].try_into().unwrap()),
+            u64::from_le_bytes(payload[16..24].try_into().unwrap()),
+        ))
+    }
+
+    /// Payload of the extension block, the block after every other block the
+    /// market was created with.
+    fn get_market_extension_payload(&self) -> Option<&[u8]> {
+        if !self.fixed.has_extension() {
+            return None;
+        }
+        let block: usize = (self.fixed.has_cpi_allowlist() as usize
+            + self.fixed.has_min_order_size() as usize
+            + self.fixed.has_price_band() as usize
+            + self.fixed.has_fees() as usize
+            + self.fixed.has_volume_stats() as usize)
+            * MARKET_BLOCK_SIZE;
+        let start: usize = block + RBTREE_OVERHEAD_BYTES;
+        self.dynamic.get(start..start + 64)
+    }
+
+    /// Version of the market's extension, None if it has none. Fields newer
+    /// than the version read as zero.
+    pub fn get_market_extension_version(&self) -> Option<u8> {
+        Some(self.get_market_extension_payload()?[0])
+    }
+
+    /// Key that can pause and resume the market with set_paused_instruction,
+    /// None if it has none.
+    pub fn get_pauser(&self) -> Option<Pubkey> {
+        let payload: &[u8] = self.get_market_extension_payload()?;
+        let pauser: Pubkey = Pubkey::new_from_array(payload[8..40].try_into().unwrap());
+        (pauser != Pubkey::default()).then_some(pauser)
+    }
+
+    /// Whether the pauser has paused the market. Paused markets take no new
+    /// orders, swaps or fills.
+    pub fn is_paused(&self) -> bool {
+        self.get_market_extension_payload()
+            .is_some_and(|payload| payload[1] & MARKET_EXTENSION_FLAG_PAUSED != 0)
+    }
+
+    /// Whether the market keeps records of its latest fills, which only
+    /// markets created with MARKET_EXTENSION_FLAG_FILL_RECORDS do. Only those
+    /// fills can be busted.
+    pub fn has_fill_records(&self) -> bool {
+        self.get_market_extension_payload()
+            .is_some_and(|payload| payload[1] & MARKET_EXTENSION_FLAG_FILL_RECORDS != 0)
+    }
+
+    /// Base atoms and quote atoms of fees that busts refunded after they were
+    /// claimed. Claims pay them back before paying out. None if the market
+    /// has no extension.
+    pub fn get_fees_owed(&self) -> Option<(u64, u64)> {
+        let payload: &[u8] = self.get_market_extension_payload()?;
+        Some((
+            u64::from_le_bytes(payload[48..56].try_into().unwrap()),
+            u64::from_le_bytes(payload[56..64].try_into().unwrap()),
+        ))
+    }
+
+    /// Rolling taker volume in quote atoms a seat needs for the first fee
+    /// tier, see FEE_TIERS. None if the market has no fee tiers.
+    pub fn get_fee_tier_quote_atoms(&self) -> Option<u64> {
+        let payload: &[u8] = self.get_market_fees_payload()?;
+        let fee_tier_quote_atoms: u64 = u64::from_le_bytes(payload[56..64].try_into().unwrap());
+        (fee_tier_quote_atoms != 0).then_some(fee_tier_quote_atoms)
+    }
+
+    /// Taker volume of the seat at index over the last SEAT_VOLUME_BUCKETS
+    /// buckets of SEAT_VOLUME_BUCKET_SLOTS as of now_slot. Zero for seats
+    /// without a seat volume block.
+    pub fn get_seat_rolling_quote_atoms(&self, index: DataIndex, now_slot: u32) -> u64 {
+        let Some(start) = self
+            .get_seat(index)
+            .and_then(ClaimedSeat::get_seat_volume_index)
+            .map(|volume_index| volume_index as usize + RBTREE_OVERHEAD_BYTES)
+        else {
+            return 0;
+        };
+        let Some(payload) = self.dynamic.get(start..start + 52) else {
+            return 0;
+        };
+        let bucket_quote_atoms =
+            |i: usize| u64::from_le_bytes(payload[i * 8..i * 8 + 8].try_into().unwrap());
+        let latest_bucket: u32 = u32::from_le_bytes(payload[48..52].try_into().unwrap());
+        let now_bucket: u32 = now_slot / SEAT_VOLUME_BUCKET_SLOTS;
+        (0..SEAT_VOLUME_BUCKETS as u32)
+            .filter_map(|age| now_bucket.checked_sub(age))
+            .filter(|bucket| {
+                *bucket <= latest_bucket && latest_bucket - bucket < SEAT_VOLUME_BUCKETS as u32
+            })
+            .map(|bucket| bucket_quote_atoms(bucket as usize % SEAT_VOLUME_BUCKETS))
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Market maker protection of the seat at index as (window_slots,
+    /// max_fills, max_quote_atoms), None if it has none. Its block is linked
+    /// from the seat volume block, see set_mmp_instruction.
+    pub fn get_seat_mmp_limits(&self, index: DataIndex) -> Option<(u32, u32, u64)> {
+        let volume_start: usize =
+            self.get_seat(index)
+                .and_then(ClaimedSeat::get_seat_volume_index)? as usize
+                + RBTREE_OVERHEAD_BYTES;
+        let [b0, b1, b2] = self
+            .dynamic
+            .get(volume_start + 52..volume_start + 55)?
+            .try_into()
+            .unwrap();
+        let block: u32 = u32::from_le_bytes([b0, b1, b2, 0]);
+        if block == 0 {
+            return None;
+        }
+        let start: usize = (block as usize - 1) * MARKET_BLOCK_SIZE + RBTREE_OVERHEAD_BYTES;
+        let payload: &[u8] = self.dynamic.get(start..start + 16)?;
+        Some((
+            u32::from_le_bytes(payload[0..4].try_into().unwrap()),
+            u32::from_le_bytes(payload[4..8].try_into().unwrap()),
+            u64::from_le_bytes(payload[8..16].try_into().unwrap()),
+        ))
+    }
+
+    /// Taker fee in bps the seat at index pays as of now_slot, after its fee
+    /// tier discount. None if the market charges no fees.
+    pub fn get_seat_taker_fee_bps(&self, index: DataIndex, now_slot: u32) -> Option<u16> {
+        let (_, taker_fee_bps) = self.get_fee_bps()?;
+        let Some(fee_tier_quote_atoms) = self.get_fee_tier_quote_atoms() else {
+            return Some(taker_fee_bps);
+        };
+        let rolling_quote_atoms: u64 = self.get_seat_rolling_quote_atoms(index, now_slot);
+        let discount_bps: u16 = FEE_TIERS
+            .iter()
+            .take_while(|(multiple, _)| {
+                rolling_quote_atoms >= fee_tier_quote_atoms.saturating_mul(*multiple)
+            })
+            .last()
+            .map_or(0, |(_, discount_bps)| *discount_bps);
+        Some((taker_fee_bps as u32 * (10_000 - discount_bps as u32) / 10_000) as u16)
+    }
+
     /// Get a resting order at the given index.
     pub fn get_order(&self, index: DataIndex) -> Option<&RestingOrder> {
         if index == NIL {
@@ -411,3 +854,96 @@ impl<'a> OrderIterator<'a> {
         NIL
     }
 }
+
+/// Nonces of the signed orders of a maker on a market that can no longer be
+/// filled. Every nonce below min_nonce is used, and the bitmap holds the
+/// SIGNED_ORDER_NONCE_WINDOW nonces from min_nonce on.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct SignedOrderNonces {
+    pub discriminant: u64,
+    pub market: [u8; 32],
+    pub trader: [u8; 32],
+    pub min_nonce: u64,
+    /// Bit nonce % SIGNED_ORDER_NONCE_WINDOW is set once nonce is used.
+    pub used_bits: [u64; 16],
+}
+
+impl SignedOrderNonces {
+    /// Parse a SignedOrderNonces account from bytes.
+    pub fn try_from_bytes(data: &[u8]) -> Option<&Self> {
+        if data.len() < SIGNED_ORDER_NONCES_SIZE {
+            return None;
+        }
+
+        // Safety: We've verified the length is sufficient
+        let nonces = unsafe { &*(data.as_ptr() as *const SignedOrderNonces) };
+
+        if nonces.discriminant != SIGNED_ORDER_NONCES_DISCRIMINANT {
+            return None;
+        }
+
+        Some(nonces)
+    }
+
+    /// Whether a signed order with this nonce can no longer be filled.
+    pub fn is_used(&self, nonce: u64) -> bool {
+        if nonce < self.min_nonce {
+            return true;
+        }
+        if nonce - self.min_nonce >= SIGNED_ORDER_NONCE_WINDOW {
+            return false;
+        }
+        let position = nonce % SIGNED_ORDER_NONCE_WINDOW;
+        self.used_bits[(position / 64) as usize] & (1 << (position % 64)) != 0
+    }
+
+    /// Lowest unused nonce, a safe nonce for the next signed order.
+    pub fn next_unused_nonce(&self) -> u64 {
+        (self.min_nonce..self.min_nonce.saturating_add(SIGNED_ORDER_NONCE_WINDOW))
+            .find(|nonce| !self.is_used(*nonce))
+            .unwrap_or(self.min_nonce.saturating_add(SIGNED_ORDER_NONCE_WINDOW))
+    }
+}
+
+/// Request for quote of a taker on a market, see [`post_rfq_instruction`].
+/// Makers quote for its sequence number, see [`RfqQuote`].
+///
+/// [`post_rfq_instruction`]: crate::post_rfq_instruction
+/// [`RfqQuote`]: crate::RfqQuote
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Rfq {
+    pub discriminant: u64,
+    pub market: [u8; 32],
+    pub taker: [u8; 32],
+    /// Zero when there is no open request.
+    pub base_atoms: u64,
+    pub sequence_number: u64,
+    pub last_valid_slot: u32,
+    pub taker_is_buy: u8,
+    pub _padding: [u8; 3],
+}
+
+impl Rfq {
+    /// Parse an RFQ account from bytes.
+    pub fn try_from_bytes(data: &[u8]) -> Option<&Self> {
+        if data.len() < RFQ_SIZE {
+            return None;
+        }
+
+        // Safety: We've verified the length is sufficient
+        let rfq = unsafe { &*(data.as_ptr() as *const Rfq) };
+
+        if rfq.discriminant != RFQ_DISCRIMINANT {
+            return None;
+        }
+
+        Some(rfq)
+    }
+
+    /// Whether the request can still be filled at `now_slot`.
+    pub fn is_open(&self, now_slot: u32) -> bool {
+        self.base_atoms > 0 && now_slot <= self.last_valid_slot
+    }
+}
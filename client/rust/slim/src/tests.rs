@@ -756,3 +756,790 @@ mod integration_tests {
         banks_client.process_transaction(tx).await.unwrap();
     }
 }
+
+#[cfg(all(test, feature = "snapshot"))]
+mod snapshot_tests {
+    use crate::{
+        MarketSnapshot, SnapshotError, MARKET_BLOCK_SIZE, MARKET_FIXED_DISCRIMINANT,
+        MARKET_FIXED_SIZE, NIL,
+    };
+    use solana_pubkey::Pubkey;
+
+    fn market_data(num_blocks: usize) -> Vec<u8> {
+        let mut data: Vec<u8> = vec![0; MARKET_FIXED_SIZE + num_blocks * MARKET_BLOCK_SIZE];
+        data[0..8].copy_from_slice(&MARKET_FIXED_DISCRIMINANT.to_le_bytes());
+        for index in [156, 160, 164, 168, 172] {
+            // No orders or seats.
+            data[index..index + 4].copy_from_slice(&NIL.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_snapshot_round_trip_clears_free_blocks() {
+        let mut data: Vec<u8> = market_data(3);
+        // Free list is blocks 0 -> 2, both with stale payloads. Block 1 is in
+        // use and must be kept as is.
+        data[176..180].copy_from_slice(&0u32.to_le_bytes());
+        let dynamic: &mut [u8] = &mut data[MARKET_FIXED_SIZE..];
+        dynamic[0..4].copy_from_slice(&(2 * MARKET_BLOCK_SIZE as u32).to_le_bytes());
+        dynamic[2 * MARKET_BLOCK_SIZE..2 * MARKET_BLOCK_SIZE + 4]
+            .copy_from_slice(&NIL.to_le_bytes());
+        dynamic[4..MARKET_BLOCK_SIZE].fill(7);
+        dynamic[MARKET_BLOCK_SIZE..2 * MARKET_BLOCK_SIZE].fill(8);
+        dynamic[2 * MARKET_BLOCK_SIZE + 4..].fill(9);
+
+        let market_key: Pubkey = Pubkey::new_unique();
+        let snapshot: MarketSnapshot = MarketSnapshot::new(1234, market_key, &data).unwrap();
+        let encoded: Vec<u8> = snapshot.encode().unwrap();
+        assert!(encoded.len() < data.len());
+
+        let decoded: MarketSnapshot = MarketSnapshot::decode(&encoded).unwrap();
+        assert_eq!(decoded.slot, 1234);
+        assert_eq!(decoded.market, market_key);
+        assert_eq!(decoded.data.len(), data.len());
+        assert!(decoded.market().is_some());
+
+        let dynamic: &[u8] = &decoded.data[MARKET_FIXED_SIZE..];
+        assert_eq!(dynamic[0..4], (2 * MARKET_BLOCK_SIZE as u32).to_le_bytes());
+        assert!(dynamic[4..MARKET_BLOCK_SIZE].iter().all(|b| *b == 0));
+        assert!(dynamic[MARKET_BLOCK_SIZE..2 * MARKET_BLOCK_SIZE]
+            .iter()
+            .all(|b| *b == 8));
+        assert!(dynamic[2 * MARKET_BLOCK_SIZE + 4..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_snapshot_rejects_bad_input() {
+        assert!(matches!(
+            MarketSnapshot::new(0, Pubkey::new_unique(), &[0; MARKET_FIXED_SIZE]),
+            Err(SnapshotError::InvalidMarket)
+        ));
+        assert!(matches!(
+            MarketSnapshot::decode(b"not a snapshot"),
+            Err(SnapshotError::InvalidHeader)
+        ));
+
+        let mut encoded: Vec<u8> = MarketSnapshot::new(0, Pubkey::new_unique(), &market_data(1))
+            .unwrap()
+            .encode()
+            .unwrap();
+        encoded[4] = 2;
+        assert!(matches!(
+            MarketSnapshot::decode(&encoded),
+            Err(SnapshotError::UnsupportedVersion(2))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod error_tests {
+    use crate::{
+        decode_manifest_error, decode_manifest_error_from_logs, DecodedManifestError,
+        ManifestError, MANIFEST_PROGRAM_ID,
+    };
+
+    fn failed_swap_logs() -> Vec<String> {
+        vec![
+            format!("Program {} invoke [1]", MANIFEST_PROGRAM_ID),
+            "Program log: [src/program/processor/swap.rs:312] In: 100 Out: 5 Min out: 10"
+                .to_string(),
+            format!(
+                "Program {} consumed 21000 of 200000 compute units",
+                MANIFEST_PROGRAM_ID
+            ),
+            format!(
+                "Program {} failed: custom program error: 0x8",
+                MANIFEST_PROGRAM_ID
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_decode_manifest_error() {
+        let expected: DecodedManifestError = DecodedManifestError {
+            error: ManifestError::InsufficientOut,
+            location: Some("src/program/processor/swap.rs:312".to_string()),
+            message: Some("In: 100 Out: 5 Min out: 10".to_string()),
+        };
+        assert_eq!(
+            decode_manifest_error(8, &failed_swap_logs()),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            decode_manifest_error_from_logs(&failed_swap_logs()),
+            Some(expected)
+        );
+
+        let without_logs: DecodedManifestError = decode_manifest_error(26, &[]).unwrap();
+        assert_eq!(without_logs.error, ManifestError::InternalCross);
+        assert_eq!(without_logs.message, None);
+
+        assert_eq!(decode_manifest_error(1_000, &failed_swap_logs()), None);
+        for code in 0..=44 {
+            assert_eq!(ManifestError::from_code(code).unwrap().code(), code);
+        }
+    }
+}
+
+#[cfg(test)]
+mod book_tests {
+    use crate::{book_imbalance, micro_price, spread_bps, BookDepth};
+
+    #[test]
+    fn test_book_depth_levels() {
+        let bids: Vec<(f64, u64)> = vec![(9.0, 100), (9.0, 100), (8.0, 200), (7.0, 1_000)];
+        let top: BookDepth = BookDepth::from_orders(bids.clone(), 1).unwrap();
+        assert_eq!(top.levels, 1);
+        assert_eq!(top.base_atoms, 200);
+        assert_eq!(top.average_price, 9.0);
+
+        let two: BookDepth = BookDepth::from_orders(bids.clone(), 2).unwrap();
+        assert_eq!(two.levels, 2);
+        assert_eq!(two.base_atoms, 400);
+        assert_eq!(two.average_price, 8.5);
+
+        assert_eq!(BookDepth::from_orders(bids.clone(), 10).unwrap().levels, 3);
+        assert_eq!(BookDepth::from_orders(bids, 0), None);
+        assert_eq!(BookDepth::from_orders(Vec::new(), 1), None);
+    }
+
+    #[test]
+    fn test_imbalance_micro_price_and_spread() {
+        let bids: BookDepth = BookDepth::from_orders(vec![(9.9, 300)], 1).unwrap();
+        let asks: BookDepth = BookDepth::from_orders(vec![(10.1, 100)], 1).unwrap();
+        assert_eq!(book_imbalance(&bids, &asks), 0.5);
+        // Thin asks pull the micro price toward the ask.
+        assert!((micro_price(&bids, &asks) - 10.05).abs() < 1e-9);
+        assert!((spread_bps(&bids, &asks) - 200.0).abs() < 1e-9);
+        assert_eq!(book_imbalance(&asks, &asks), 0.0);
+        assert!((micro_price(&asks, &asks) - 10.1).abs() < 1e-9);
+    }
+}
+
+#[cfg(test)]
+mod spread_tests {
+    use crate::{
+        fill_quote_atoms, plan_from_quotes, spread_trade_instructions, ManifestInstruction, Pubkey,
+        SpreadError, SpreadLeg, SpreadPlan, COMPUTE_BUDGET_PROGRAM_ID, MANIFEST_PROGRAM_ID,
+        SWAP_BASE_COMPUTE_UNITS, SWAP_FILL_COMPUTE_UNITS, TOKEN_PROGRAM_ID,
+    };
+
+    const D18: u128 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn test_fill_quote_atoms() {
+        // 1.5 and 2 quote atoms per base atom.
+        let asks: Vec<(u128, u64)> = vec![(3 * D18 / 2, 3), (2 * D18, 10)];
+        assert_eq!(fill_quote_atoms(asks.clone(), 3, true), Some((5, 1)));
+        assert_eq!(fill_quote_atoms(asks.clone(), 5, true), Some((9, 2)));
+        assert_eq!(fill_quote_atoms(asks.clone(), 3, false), Some((4, 1)));
+        assert_eq!(fill_quote_atoms(asks, 14, true), None);
+    }
+
+    #[test]
+    fn test_plan_from_quotes() {
+        let plan: SpreadPlan = plan_from_quotes(100, 1_000, 1_100, 40, 3).unwrap();
+        assert_eq!(plan.expected_edge_quote_atoms, 100);
+        // Slack of 60 is split, so any buy within its limit leaves the edge.
+        assert_eq!(plan.buy_max_quote_in, 1_030);
+        assert_eq!(plan.sell_min_quote_out, 1_070);
+        assert_eq!(
+            plan.compute_units,
+            2 * SWAP_BASE_COMPUTE_UNITS + 3 * SWAP_FILL_COMPUTE_UNITS
+        );
+
+        // Paying up to 50 to hedge.
+        let hedge: SpreadPlan = plan_from_quotes(100, 1_000, 980, -50, 2).unwrap();
+        assert_eq!(hedge.buy_max_quote_in, 1_015);
+        assert_eq!(hedge.sell_min_quote_out, 965);
+
+        assert_eq!(
+            plan_from_quotes(100, 1_000, 1_030, 40, 2),
+            Err(SpreadError::EdgeTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_spread_trade_instructions() {
+        let leg = |market: Pubkey| SpreadLeg {
+            market,
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            trader_base: Pubkey::new_unique(),
+            trader_quote: Pubkey::new_unique(),
+            token_program_base: TOKEN_PROGRAM_ID,
+            token_program_quote: TOKEN_PROGRAM_ID,
+        };
+        let buy_leg: SpreadLeg = leg(Pubkey::new_unique());
+        let sell_leg: SpreadLeg = leg(Pubkey::new_unique());
+        let plan: SpreadPlan = plan_from_quotes(100, 1_000, 1_100, 40, 3).unwrap();
+        let instructions =
+            spread_trade_instructions(Pubkey::new_unique(), &buy_leg, &sell_leg, &plan);
+
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(instructions[0].program_id, COMPUTE_BUDGET_PROGRAM_ID);
+        assert_eq!(instructions[0].data[1..], plan.compute_units.to_le_bytes());
+        for (instruction, leg) in instructions[1..].iter().zip([buy_leg, sell_leg]) {
+            assert_eq!(instruction.program_id, MANIFEST_PROGRAM_ID);
+            assert_eq!(instruction.data[0], ManifestInstruction::Swap as u8);
+            assert_eq!(instruction.accounts[1].pubkey, leg.market);
+        }
+        // Exact out base on the buy, exact in base on the sell.
+        assert_eq!(instructions[1].data[1..9], 1_030u64.to_le_bytes());
+        assert_eq!(instructions[1].data[17..19], [0, 0]);
+        assert_eq!(instructions[2].data[9..17], 1_070u64.to_le_bytes());
+        assert_eq!(instructions[2].data[17..19], [1, 1]);
+    }
+}
+
+#[cfg(test)]
+mod reverse_tests {
+    use crate::{OrderType, ReverseBook, ReverseFlip, SimulatedOrder, DYNAMIC_REVERSE_SPREAD};
+
+    const D18: u128 = 1_000_000_000_000_000_000;
+
+    fn order(
+        trader_index: u32,
+        is_bid: bool,
+        price: u128,
+        num_base_atoms: u64,
+        order_type: OrderType,
+        reverse_spread: u16,
+    ) -> SimulatedOrder {
+        SimulatedOrder {
+            trader_index,
+            is_bid,
+            price,
+            num_base_atoms,
+            order_type,
+            reverse_spread,
+        }
+    }
+
+    #[test]
+    fn test_reverse_price() {
+        // 1% on Reverse, 0.05% on ReverseTight.
+        let ask: SimulatedOrder = order(0, false, 10 * D18, 1, OrderType::Reverse, 1_000);
+        assert_eq!(ask.reverse_price(0), Some(99 * D18 / 10));
+        let bid: SimulatedOrder = order(0, true, 99 * D18 / 10, 1, OrderType::Reverse, 1_000);
+        assert_eq!(bid.reverse_price(0), Some(10 * D18));
+        let tight: SimulatedOrder = order(0, false, 3 * D18, 1, OrderType::ReverseTight, 50_000);
+        assert_eq!(tight.reverse_price(0), Some(29_985 * D18 / 10_000));
+        assert_eq!(
+            order(0, false, D18, 1, OrderType::Limit, 0).reverse_price(0),
+            None
+        );
+
+        // The market estimate, 1%, on a dynamic spread.
+        let dynamic: SimulatedOrder = order(
+            0,
+            false,
+            10 * D18,
+            1,
+            OrderType::Reverse,
+            DYNAMIC_REVERSE_SPREAD,
+        );
+        assert_eq!(dynamic.reverse_price(0), Some(10 * D18));
+        assert_eq!(dynamic.reverse_price(100), Some(99 * D18 / 10));
+    }
+
+    #[test]
+    fn test_reverse_cascade() {
+        // Same fills as reverse_order_tight_type_test in the program: a
+        // ReverseTight ask of 3 SOL at 3.0 with a 0.05% spread.
+        let mut book: ReverseBook = ReverseBook::new(
+            vec![order(1, true, 2 * D18, 1_000, OrderType::Limit, 0)],
+            vec![order(
+                0,
+                false,
+                3 * D18,
+                3_000_000_000,
+                OrderType::ReverseTight,
+                50_000,
+            )],
+            0,
+        );
+        let bid_price: u128 = 29_985 * D18 / 10_000;
+
+        let first = book.swap(1_000_000_000, true);
+        assert_eq!(first.base_atoms, 1_000_000_000);
+        assert_eq!(first.quote_atoms, 3_000_000_000);
+        assert_eq!(
+            first.flips,
+            vec![ReverseFlip {
+                trader_index: 0,
+                is_bid: true,
+                price: bid_price,
+                num_base_atoms: 1_000_500_250,
+                coalesced: false,
+            }]
+        );
+        // The flipped bid goes ahead of the worse limit bid.
+        assert_eq!(book.bids.len(), 2);
+        assert_eq!(book.bids[1].trader_index, 1);
+
+        // The rest of the ask coalesces into the flipped bid, sized so the
+        // bid backs no more than the 9_000_000_000 quote received.
+        let second = book.swap(3_000_000_000, true);
+        assert_eq!(second.base_atoms, 2_000_000_000);
+        assert_eq!(second.quote_atoms, 6_000_000_000);
+        assert_eq!(second.flips[0].num_base_atoms, 2_001_000_500);
+        assert!(second.flips[0].coalesced);
+        assert!(book.asks.is_empty());
+        assert_eq!(book.bids[0].num_base_atoms, 3_001_500_750);
+
+        // Selling into the grid flips it back to an ask at 3.0, then hits the
+        // limit bid, which does not flip.
+        let third = book.swap(3_001_500_850, false);
+        assert_eq!(third.base_atoms, 3_001_500_850);
+        assert_eq!(third.quote_atoms, 8_999_999_999 + 200);
+        assert_eq!(third.flips.len(), 1);
+        assert_eq!(third.flips[0].price, 3 * D18);
+        assert_eq!(third.flips[0].num_base_atoms, 3_001_500_750);
+        assert_eq!(book.bids.len(), 1);
+        assert_eq!(book.bids[0].num_base_atoms, 900);
+        assert_eq!(book.asks[0].price, 3 * D18);
+        assert_eq!(book.asks[0].order_type, OrderType::ReverseTight);
+    }
+}
+
+#[cfg(test)]
+mod bundle_tests {
+    use crate::{
+        build_bundle, tip_instruction, transaction_size, BundleError, Instruction, LookupTable,
+        Pubkey, JITO_TIP_ACCOUNTS, MIN_JITO_TIP_LAMPORTS, SYSTEM_PROGRAM_ID,
+    };
+
+    #[test]
+    fn test_tip_instruction() {
+        let payer: Pubkey = Pubkey::new_unique();
+        let instruction: Instruction = tip_instruction(payer, JITO_TIP_ACCOUNTS[0], 5_000);
+        assert_eq!(instruction.program_id, SYSTEM_PROGRAM_ID);
+        assert_eq!(instruction.data[..4], [2, 0, 0, 0]);
+        assert_eq!(instruction.data[4..], 5_000u64.to_le_bytes());
+        assert!(instruction.accounts[0].is_signer && instruction.accounts[0].is_writable);
+        assert_eq!(instruction.accounts[1].pubkey, JITO_TIP_ACCOUNTS[0]);
+    }
+
+    #[test]
+    fn test_transaction_size() {
+        let payer: Pubkey = Pubkey::new_unique();
+        let tip: Vec<Instruction> = vec![tip_instruction(payer, JITO_TIP_ACCOUNTS[0], 5_000)];
+        // A legacy SOL transfer.
+        assert_eq!(transaction_size(payer, &tip, &[]), 215);
+
+        // The tip account is loaded through the table, 31 bytes less than a
+        // static key but the table costs 37.
+        let table: LookupTable = LookupTable {
+            key: Pubkey::new_unique(),
+            addresses: vec![JITO_TIP_ACCOUNTS[0], payer],
+        };
+        assert_eq!(transaction_size(payer, &tip, &[table]), 220);
+
+        // Unused tables are left out of the message.
+        let unused: LookupTable = LookupTable {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique()],
+        };
+        assert_eq!(transaction_size(payer, &tip, &[unused]), 217);
+    }
+
+    #[test]
+    fn test_build_bundle() {
+        let payer: Pubkey = Pubkey::new_unique();
+        let noop =
+            |len: usize| Instruction::new_with_bytes(Pubkey::new_unique(), &vec![0; len], vec![]);
+
+        let bundle: Vec<Vec<Instruction>> = build_bundle(
+            payer,
+            vec![vec![noop(8)], vec![noop(8)]],
+            JITO_TIP_ACCOUNTS[1],
+            MIN_JITO_TIP_LAMPORTS,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(bundle[0].len(), 1);
+        assert_eq!(bundle[1].len(), 2);
+        assert_eq!(bundle[1][1].accounts[1].pubkey, JITO_TIP_ACCOUNTS[1]);
+
+        assert_eq!(
+            build_bundle(payer, vec![vec![noop(8)]], JITO_TIP_ACCOUNTS[1], 1, &[]),
+            Err(BundleError::TipTooSmall)
+        );
+        assert_eq!(
+            build_bundle(payer, vec![], JITO_TIP_ACCOUNTS[1], 10_000, &[]),
+            Err(BundleError::Empty)
+        );
+        assert_eq!(
+            build_bundle(
+                payer,
+                vec![vec![noop(8)]; 6],
+                JITO_TIP_ACCOUNTS[1],
+                10_000,
+                &[]
+            ),
+            Err(BundleError::TooManyTransactions(6))
+        );
+        assert!(matches!(
+            build_bundle(
+                payer,
+                vec![vec![noop(1_000)]],
+                JITO_TIP_ACCOUNTS[1],
+                10_000,
+                &[]
+            ),
+            Err(BundleError::TransactionTooLarge { index: 0, .. })
+        ));
+    }
+}
+
+#[cfg(test)]
+mod event_tests {
+    use crate::{
+        parse_event, CancelOrderLog, ManifestEvent, CANCEL_ORDER_LOG_DISCRIMINANT,
+        LOG_VERSION_UNVERSIONED, PLACE_ORDER_LOG_DISCRIMINANT,
+    };
+
+    fn cancel_order_log_data() -> Vec<u8> {
+        let mut data: Vec<u8> = CANCEL_ORDER_LOG_DISCRIMINANT.to_vec();
+        data.extend_from_slice(&[1; 32]);
+        data.extend_from_slice(&[2; 32]);
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_parse_event_versions() {
+        let check = |data: &[u8], expected_version: u8| match parse_event(data) {
+            Some((ManifestEvent::CancelOrder(log), version)) => {
+                assert_eq!(log.order_sequence_number, 7);
+                assert_eq!(log.trader.to_bytes(), [2; 32]);
+                assert_eq!(version, expected_version);
+            }
+            other => panic!("unexpected {:?}", other),
+        };
+
+        // Before logs had a version byte.
+        let mut data: Vec<u8> = cancel_order_log_data();
+        check(&data, LOG_VERSION_UNVERSIONED);
+
+        data.push(1);
+        check(&data, 1);
+
+        // A later version with a field appended.
+        let mut data: Vec<u8> = cancel_order_log_data();
+        data.extend_from_slice(&[9; 8]);
+        data.push(2);
+        check(&data, 2);
+
+        assert_eq!(
+            cancel_order_log_data().len(),
+            8 + std::mem::size_of::<CancelOrderLog>()
+        );
+        assert!(parse_event(&cancel_order_log_data()[..40]).is_none());
+        assert!(parse_event(&[0; 80]).is_none());
+    }
+
+    #[test]
+    fn test_parse_event_rejects_unknown_order_type() {
+        let mut data: Vec<u8> = PLACE_ORDER_LOG_DISCRIMINANT.to_vec();
+        data.extend_from_slice(&[0; 112]);
+        data.push(1);
+        assert!(matches!(
+            parse_event(&data),
+            Some((ManifestEvent::PlaceOrder(_), 1))
+        ));
+
+        data[8 + 104] = 200;
+        assert!(parse_event(&data).is_none());
+    }
+}
+
+#[cfg(test)]
+mod signed_order_tests {
+    use crate::{SignedOrder, SignedOrderNonces, SIGNED_ORDER_NONCES_DISCRIMINANT};
+    use solana_pubkey::Pubkey;
+
+    #[test]
+    fn test_signed_order_message() {
+        let order: SignedOrder = SignedOrder {
+            market: Pubkey::new_from_array([7; 32]),
+            base_atoms: 1,
+            price_mantissa: 2,
+            price_exponent: -3,
+            is_bid: true,
+            last_valid_slot: 4,
+            nonce: 5,
+        };
+        let message: Vec<u8> = order.message();
+        assert_eq!(message.len(), 58);
+        assert_eq!(&message[..32], &[7; 32]);
+        assert_eq!(&message[32..44], &[1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(&message[44..50], &[253, 1, 4, 0, 0, 0]);
+        assert_eq!(&message[50..], &[5, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_signed_order_nonces() {
+        let mut used_bits: [u64; 16] = [0; 16];
+        used_bits[0] = (1 << 4) | (1 << 5) | (1 << 7);
+        let nonces: SignedOrderNonces = SignedOrderNonces {
+            discriminant: SIGNED_ORDER_NONCES_DISCRIMINANT,
+            market: [0; 32],
+            trader: [0; 32],
+            min_nonce: 4,
+            used_bits,
+        };
+        assert!(nonces.is_used(3));
+        assert!(nonces.is_used(5));
+        assert!(!nonces.is_used(6));
+        assert!(nonces.is_used(7));
+        assert!(!nonces.is_used(2_000));
+        assert_eq!(nonces.next_unused_nonce(), 6);
+    }
+}
+
+#[cfg(test)]
+mod rfq_tests {
+    use crate::{fill_rfq_instruction, get_rfq_address, Rfq, RfqQuote, RFQ_DISCRIMINANT, RFQ_SIZE};
+    use solana_pubkey::Pubkey;
+
+    #[test]
+    fn test_rfq_quote_message() {
+        let quote: RfqQuote = RfqQuote {
+            rfq: Pubkey::new_from_array([7; 32]),
+            sequence_number: 1,
+            price_mantissa: 2,
+            price_exponent: -3,
+            last_valid_slot: 4,
+        };
+        let message: Vec<u8> = quote.message();
+        assert_eq!(message.len(), 49);
+        assert_eq!(&message[..32], &[7; 32]);
+        assert_eq!(&message[32..44], &[1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(&message[44..], &[253, 4, 0, 0, 0]);
+
+        let taker: Pubkey = Pubkey::new_unique();
+        let market: Pubkey = Pubkey::new_unique();
+        let maker: Pubkey = Pubkey::new_unique();
+        let ix = fill_rfq_instruction(taker, market, &[(maker, quote), (maker, quote)]);
+        assert_eq!(&ix.data[..5], &[17, 2, 0, 0, 0]);
+        assert_eq!(ix.data.len(), 5 + 2 * (32 + 49));
+        assert_eq!(&ix.data[5..37], maker.as_ref());
+        assert_eq!(ix.accounts[2].pubkey, get_rfq_address(&market, &taker).0);
+    }
+
+    #[test]
+    fn test_rfq() {
+        let mut data: Vec<u8> = vec![0; RFQ_SIZE];
+        assert!(Rfq::try_from_bytes(&data).is_none());
+        data[0..8].copy_from_slice(&RFQ_DISCRIMINANT.to_le_bytes());
+        data[72..80].copy_from_slice(&5u64.to_le_bytes());
+        data[80..88].copy_from_slice(&2u64.to_le_bytes());
+        data[88..92].copy_from_slice(&100u32.to_le_bytes());
+        data[92] = 1;
+        let rfq: &Rfq = Rfq::try_from_bytes(&data).unwrap();
+        assert_eq!(rfq.sequence_number, 2);
+        assert_eq!(rfq.taker_is_buy, 1);
+        assert!(rfq.is_open(100));
+        assert!(!rfq.is_open(101));
+    }
+}
+
+#[cfg(test)]
+mod fee_tier_tests {
+    use crate::{
+        Market, MARKET_BLOCK_SIZE, MARKET_FIXED_DISCRIMINANT, MARKET_FIXED_SIZE, MARKET_FLAG_FEES,
+        NIL, SEAT_VOLUME_BUCKETS, SEAT_VOLUME_BUCKET_SLOTS,
+    };
+    use hypertree::RBTREE_OVERHEAD_BYTES;
+
+    /// Market with 30 bps taker fees and tiers at 1_000 quote atoms, whose
+    /// seat in block 1 took 12_000 quote atoms in bucket 0, kept in block 2.
+    fn market_data() -> Vec<u8> {
+        let mut data: Vec<u8> = vec![0; MARKET_FIXED_SIZE + 3 * MARKET_BLOCK_SIZE];
+        data[0..8].copy_from_slice(&MARKET_FIXED_DISCRIMINANT.to_le_bytes());
+        for index in [156, 160, 164, 168, 172, 176] {
+            data[index..index + 4].copy_from_slice(&NIL.to_le_bytes());
+        }
+        data[192] = MARKET_FLAG_FEES;
+        let fees: usize = MARKET_FIXED_SIZE + RBTREE_OVERHEAD_BYTES;
+        data[fees + 2..fees + 4].copy_from_slice(&30u16.to_le_bytes());
+        data[fees + 56..fees + 64].copy_from_slice(&1_000u64.to_le_bytes());
+        let seat: usize = MARKET_FIXED_SIZE + MARKET_BLOCK_SIZE + RBTREE_OVERHEAD_BYTES;
+        data[seat + 61..seat + 64].copy_from_slice(&[3, 0, 0]);
+        let volume: usize = MARKET_FIXED_SIZE + 2 * MARKET_BLOCK_SIZE + RBTREE_OVERHEAD_BYTES;
+        data[volume..volume + 8].copy_from_slice(&12_000u64.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_seat_taker_fee_bps() {
+        let data: Vec<u8> = market_data();
+        let market: Market = Market::try_from_bytes(&data).unwrap();
+        let seat_index: u32 = MARKET_BLOCK_SIZE as u32;
+        assert_eq!(market.get_fee_tier_quote_atoms(), Some(1_000));
+        assert_eq!(
+            market.get_seat(seat_index).unwrap().get_seat_volume_index(),
+            Some(2 * MARKET_BLOCK_SIZE as u32)
+        );
+
+        // 12 times the tier volume is the 10x tier, 25% off rounded down.
+        assert_eq!(market.get_seat_rolling_quote_atoms(seat_index, 0), 12_000);
+        assert_eq!(market.get_seat_taker_fee_bps(seat_index, 0), Some(22));
+
+        // The bucket falls out of the window.
+        let later: u32 = SEAT_VOLUME_BUCKETS as u32 * SEAT_VOLUME_BUCKET_SLOTS;
+        assert_eq!(market.get_seat_rolling_quote_atoms(seat_index, later), 0);
+        assert_eq!(market.get_seat_taker_fee_bps(seat_index, later), Some(30));
+
+        // Blocks that are not seats with a volume block count as no volume.
+        assert_eq!(market.get_seat_rolling_quote_atoms(0, 0), 0);
+    }
+
+    #[test]
+    fn test_seat_mmp_limits() {
+        let mut data: Vec<u8> = market_data();
+        let seat_index: u32 = MARKET_BLOCK_SIZE as u32;
+        assert_eq!(
+            Market::try_from_bytes(&data)
+                .unwrap()
+                .get_seat_mmp_limits(seat_index),
+            None
+        );
+
+        // The volume block links the MMP in block 3.
+        data.extend_from_slice(&[0; MARKET_BLOCK_SIZE]);
+        let volume: usize = MARKET_FIXED_SIZE + 2 * MARKET_BLOCK_SIZE + RBTREE_OVERHEAD_BYTES;
+        data[volume + 52..volume + 55].copy_from_slice(&[4, 0, 0]);
+        let mmp: usize = MARKET_FIXED_SIZE + 3 * MARKET_BLOCK_SIZE + RBTREE_OVERHEAD_BYTES;
+        data[mmp..mmp + 4].copy_from_slice(&100u32.to_le_bytes());
+        data[mmp + 4..mmp + 8].copy_from_slice(&2u32.to_le_bytes());
+        data[mmp + 8..mmp + 16].copy_from_slice(&5_000u64.to_le_bytes());
+        let market: Market = Market::try_from_bytes(&data).unwrap();
+        assert_eq!(
+            market.get_seat_mmp_limits(seat_index),
+            Some((100, 2, 5_000))
+        );
+        assert_eq!(market.get_seat_mmp_limits(0), None);
+    }
+}
+
+#[cfg(test)]
+mod market_stats_tests {
+    use crate::{
+        Market, Pubkey, MARKET_BLOCK_SIZE, MARKET_EXTENSION_FLAG_FILL_RECORDS,
+        MARKET_EXTENSION_FLAG_PAUSED, MARKET_EXTENSION_VERSION, MARKET_FIXED_DISCRIMINANT,
+        MARKET_FIXED_SIZE, MARKET_FLAG_EXTENSION, MARKET_FLAG_FEES, MARKET_FLAG_VOLUME_STATS, NIL,
+    };
+    use hypertree::RBTREE_OVERHEAD_BYTES;
+
+    #[test]
+    fn test_market_stats() {
+        let mut data: Vec<u8> = vec![0; MARKET_FIXED_SIZE + 2 * MARKET_BLOCK_SIZE];
+        data[0..8].copy_from_slice(&MARKET_FIXED_DISCRIMINANT.to_le_bytes());
+        for index in [156, 160, 164, 168, 172, 176] {
+            data[index..index + 4].copy_from_slice(&NIL.to_le_bytes());
+        }
+        data[192] = MARKET_FLAG_FEES;
+        assert_eq!(
+            Market::try_from_bytes(&data).unwrap().get_market_stats(),
+            None
+        );
+
+        // The stats are the block after the fees.
+        data[192] |= MARKET_FLAG_VOLUME_STATS;
+        let stats: usize = MARKET_FIXED_SIZE + MARKET_BLOCK_SIZE + RBTREE_OVERHEAD_BYTES;
+        data[stats..stats + 8].copy_from_slice(&1_500u64.to_le_bytes());
+        data[stats + 8..stats + 16].copy_from_slice(&3_000u64.to_le_bytes());
+        data[stats + 16..stats + 24].copy_from_slice(&2u64.to_le_bytes());
+        assert_eq!(
+            Market::try_from_bytes(&data).unwrap().get_market_stats(),
+            Some((1_500, 3_000, 2))
+        );
+
+        // And the extension the block after the stats.
+        data[192] |= MARKET_FLAG_EXTENSION;
+        data.extend_from_slice(&[0; MARKET_BLOCK_SIZE]);
+        let extension: usize = MARKET_FIXED_SIZE + 2 * MARKET_BLOCK_SIZE + RBTREE_OVERHEAD_BYTES;
+        data[extension] = MARKET_EXTENSION_VERSION;
+        let market: Market = Market::try_from_bytes(&data).unwrap();
+        assert_eq!(
+            market.get_market_extension_version(),
+            Some(MARKET_EXTENSION_VERSION)
+        );
+        assert_eq!(market.get_market_stats(), Some((1_500, 3_000, 2)));
+        assert_eq!(market.get_pauser(), None);
+        assert!(!market.is_paused());
+
+        // The pauser is after the version and the extension flags.
+        let pauser: Pubkey = Pubkey::new_unique();
+        data[extension + 1] = MARKET_EXTENSION_FLAG_PAUSED;
+        data[extension + 8..extension + 40].copy_from_slice(pauser.as_ref());
+        let market: Market = Market::try_from_bytes(&data).unwrap();
+        assert_eq!(market.get_pauser(), Some(pauser));
+        assert!(market.is_paused());
+        assert!(!market.has_fill_records());
+
+        assert_eq!(market.get_fees_owed(), Some((0, 0)));
+
+        data[extension + 1] |= MARKET_EXTENSION_FLAG_FILL_RECORDS;
+        assert!(Market::try_from_bytes(&data).unwrap().has_fill_records());
+
+        // Fees owed are the last two words.
+        data[extension + 48..extension + 56].copy_from_slice(&7u64.to_le_bytes());
+        data[extension + 56..extension + 64].copy_from_slice(&9u64.to_le_bytes());
+        assert_eq!(
+            Market::try_from_bytes(&data).unwrap().get_fees_owed(),
+            Some((7, 9))
+        );
+    }
+}
+
+#[cfg(test)]
+mod pegged_order_tests {
+    use crate::{
+        cancel_pegged_order_instruction, get_pegged_orders_address, place_pegged_order_instruction,
+        PegReference,
+    };
+    use solana_pubkey::Pubkey;
+
+    #[test]
+    fn test_pegged_order_instructions() {
+        let trader: Pubkey = Pubkey::new_unique();
+        let market: Pubkey = Pubkey::new_unique();
+        let ix = place_pegged_order_instruction(
+            trader,
+            market,
+            5,
+            true,
+            PegReference::Mid,
+            -5,
+            150,
+            -1,
+            100,
+        );
+        assert_eq!(
+            ix.data,
+            [
+                vec![44],
+                5u64.to_le_bytes().to_vec(),
+                vec![1, 2],
+                (-5i16).to_le_bytes().to_vec(),
+                150u32.to_le_bytes().to_vec(),
+                vec![255],
+                100u32.to_le_bytes().to_vec(),
+                vec![0],
+            ]
+            .concat()
+        );
+        let pegged_orders: Pubkey = get_pegged_orders_address(&market, &trader).0;
+        assert_eq!(ix.accounts[3].pubkey, pegged_orders);
+
+        let ix = cancel_pegged_order_instruction(trader, market, 3);
+        assert_eq!(ix.data, vec![45, 3]);
+        assert!(ix.accounts[1].is_writable);
+        assert_eq!(ix.accounts[2].pubkey, pegged_orders);
+    }
+}
@@ -0,0 +1,179 @@
+import { Market, RestingOrder } from './market';
+import { FillLogResult } from './types';
+
+/**
+ * Market structure in the shape of ccxt `fetchMarkets`. Only the fields that
+ * have a meaning on Manifest are filled in.
+ */
+export type CcxtMarket = {
+  /** Market address as base58. */
+  id: string;
+  /** Symbol like SOL/USDC. */
+  symbol: string;
+  /** Base mint as base58. */
+  base: string;
+  /** Quote mint as base58. */
+  quote: string;
+  baseId: string;
+  quoteId: string;
+  type: 'spot';
+  spot: true;
+  active: true;
+  /** Manifest has no fees. */
+  maker: number;
+  taker: number;
+  precision: {
+    /** Smallest base increment in tokens. */
+    amount: number;
+    /** Smallest quote increment in tokens. */
+    price: number;
+  };
+};
+
+/**
+ * Orderbook in the shape of ccxt `fetchOrderBook`. Levels are [price, amount]
+ * in tokens, most competitive first.
+ */
+export type CcxtOrderBook = {
+  symbol: string;
+  bids: [number, number][];
+  asks: [number, number][];
+  timestamp: number;
+  datetime: string;
+  /** Slot the market was read at. */
+  nonce: number | undefined;
+};
+
+/**
+ * Trade in the shape of ccxt `fetchTrades`. Side is the taker side.
+ */
+export type CcxtTrade = {
+  /** Taker and maker sequence numbers, unique per market. */
+  id: string;
+  /** Taker order sequence number. */
+  order: string;
+  symbol: string;
+  timestamp: number | undefined;
+  datetime: string | undefined;
+  side: 'buy' | 'sell';
+  takerOrMaker: 'taker';
+  price: number;
+  amount: number;
+  cost: number;
+  info: FillLogResult;
+};
+
+/**
+ * Normalize a market into the ccxt market structure.
+ *
+ * @param market Market
+ * @param symbol string like SOL/USDC
+ *
+ * @returns CcxtMarket
+ */
+export function toCcxtMarket(market: Market, symbol: string): CcxtMarket {
+  const base: string = market.baseMint().toBase58();
+  const quote: string = market.quoteMint().toBase58();
+  return {
+    id: market.address.toBase58(),
+    symbol,
+    base,
+    quote,
+    baseId: base,
+    quoteId: quote,
+    type: 'spot',
+    spot: true,
+    active: true,
+    maker: 0,
+    taker: 0,
+    precision: {
+      amount: 10 ** -market.baseDecimals(),
+      price: 10 ** -market.quoteDecimals(),
+    },
+  };
+}
+
+/**
+ * Aggregate resting orders into [price, amount] levels. Orders must already
+ * be sorted from most competitive to least.
+ */
+function toLevels(orders: RestingOrder[], limit?: number): [number, number][] {
+  const levels: [number, number][] = [];
+  for (const order of orders) {
+    const amount: number = Number(order.numBaseTokens);
+    const last: [number, number] | undefined = levels[levels.length - 1];
+    if (last && last[0] == order.tokenPrice) {
+      last[1] += amount;
+      continue;
+    }
+    if (limit != undefined && levels.length == limit) {
+      break;
+    }
+    levels.push([order.tokenPrice, amount]);
+  }
+  return levels;
+}
+
+/**
+ * Normalize the orderbook of a loaded market into the ccxt orderbook
+ * structure.
+ *
+ * @param market Market
+ * @param symbol string like SOL/USDC
+ * @param limit Optional number of price levels per side
+ * @param slot Optional slot the market was read at
+ *
+ * @returns CcxtOrderBook
+ */
+export function toCcxtOrderBook(
+  market: Market,
+  symbol: string,
+  limit?: number,
+  slot?: number,
+): CcxtOrderBook {
+  const timestamp: number = Date.now();
+  return {
+    symbol,
+    bids: toLevels(market.bidsL2(), limit),
+    asks: toLevels(market.asksL2(), limit),
+    timestamp,
+    datetime: new Date(timestamp).toISOString(),
+    nonce: slot,
+  };
+}
+
+/**
+ * Normalize a fill into the ccxt trade structure.
+ *
+ * @param fill FillLogResult
+ * @param symbol string like SOL/USDC
+ * @param baseDecimals number
+ * @param quoteDecimals number
+ *
+ * @returns CcxtTrade
+ */
+export function toCcxtTrade(
+  fill: FillLogResult,
+  symbol: string,
+  baseDecimals: number,
+  quoteDecimals: number,
+): CcxtTrade {
+  const amount: number = Number(fill.baseAtoms) / 10 ** baseDecimals;
+  const cost: number = Number(fill.quoteAtoms) / 10 ** quoteDecimals;
+  const timestamp: number | undefined =
+    fill.blockTime != undefined ? fill.blockTime * 1_000 : undefined;
+  return {
+    id: `${fill.takerSequenceNumber}-${fill.makerSequenceNumber}`,
+    order: fill.takerSequenceNumber,
+    symbol,
+    timestamp,
+    datetime:
+      timestamp != undefined ? new Date(timestamp).toISOString() : undefined,
+    side: fill.takerIsBuy ? 'buy' : 'sell',
+    takerOrMaker: 'taker',
+    price: fill.priceAtoms * 10 ** (baseDecimals - quoteDecimals),
+    amount,
+    cost,
+    info: fill,
+  };
+}
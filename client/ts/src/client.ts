@@ -20,6 +20,7 @@ import {
 } from '@solana/spl-token';
 import {
   createCreateMarketInstruction,
+  createExpandInstruction,
   createGlobalAddTraderInstruction,
   createGlobalCreateInstruction,
   createGlobalDepositInstruction,
@@ -48,6 +49,7 @@ import { getVaultAddress } from './utils/market';
 import { genAccDiscriminator } from './utils/discriminator';
 import { getGlobalAddress, getGlobalVaultAddress } from './utils/global';
 import { Global } from './global';
+import { buildDurableNonceTx, getCurrentNonce } from './durableNonce';
 
 export interface SetupData {
   setupNeeded: boolean;
@@ -830,6 +832,35 @@ export class ManifestClient {
     });
   }
 
+  /**
+   * Expand instruction. Makes sure the market has at least numFreeBlocks free
+   * blocks in a single instruction. See estimateMarketBlocksNeeded.
+   *
+   * @param payer PublicKey that pays rent for the new space. Does not need
+   *              to be a trader on the market.
+   * @param market PublicKey of the market
+   * @param numFreeBlocks Number of free blocks the market should have. When
+   *                      omitted, adds one block if fewer than two are free.
+   *
+   * @returns TransactionInstruction
+   */
+  public static expandMarketIx(
+    payer: PublicKey,
+    market: PublicKey,
+    numFreeBlocks?: number,
+  ): TransactionInstruction {
+    const ix: TransactionInstruction = createExpandInstruction({
+      payer,
+      market,
+    });
+    if (numFreeBlocks !== undefined) {
+      const numFreeBlocksData: Buffer = Buffer.alloc(4);
+      numFreeBlocksData.writeUInt32LE(numFreeBlocks, 0);
+      ix.data = Buffer.concat([ix.data, numFreeBlocksData]);
+    }
+    return ix;
+  }
+
   /**
    * Deposit instruction
    *
@@ -888,6 +919,8 @@ export class ManifestClient {
    * @param payer PublicKey of the trader
    * @param mint PublicKey for withdraw mint. Must be either the base or quote
    * @param amountTokens Number of tokens to withdraw.
+   * @param destinationTokenAccount Optional token account to send the tokens
+   * to. Can be owned by any wallet. Defaults to the payer's ATA.
    *
    * @returns TransactionInstruction
    */
@@ -895,6 +928,7 @@ export class ManifestClient {
     payer: PublicKey,
     mint: PublicKey,
     amountTokens: number,
+    destinationTokenAccount?: PublicKey,
   ): TransactionInstruction {
     if (!this.wrapper || !this.payer) {
       throw new Error('Read only');
@@ -903,12 +937,14 @@ export class ManifestClient {
     const is22: boolean =
       (mint.equals(this.baseMint.address) && this.isBase22) ||
       (mint.equals(this.quoteMint.address) && this.isQuote22);
-    const traderTokenAccount: PublicKey = getAssociatedTokenAddressSync(
-      mint,
-      payer,
-      true,
-      is22 ? TOKEN_2022_PROGRAM_ID : TOKEN_PROGRAM_ID,
-    );
+    const traderTokenAccount: PublicKey =
+      destinationTokenAccount ??
+      getAssociatedTokenAddressSync(
+        mint,
+        payer,
+        true,
+        is22 ? TOKEN_2022_PROGRAM_ID : TOKEN_PROGRAM_ID,
+      );
     const mintDecimals =
       this.market.quoteMint().toBase58() === mint.toBase58()
         ? this.market.quoteDecimals()
@@ -1629,6 +1665,34 @@ export class ManifestClient {
     );
   }
 
+  /**
+   * Durable nonce cancel all transaction. Uses the wrapper cancel all, which
+   * does not list orders, so it still cancels everything when it lands long
+   * after it was built. Sign it with preSignTransaction and give it to a
+   * watchdog to send with sendPreSignedTransaction.
+   *
+   * @param nonceAccount PublicKey of an initialized nonce account
+   * @param nonceAuthority PublicKey of the nonce authority, the payer if not given
+   *
+   * @returns Promise<Transaction>
+   */
+  public async cancelAllDurableNonceTx(
+    nonceAccount: PublicKey,
+    nonceAuthority?: PublicKey,
+  ): Promise<Transaction> {
+    if (!this.payer) {
+      throw new Error('Read only');
+    }
+    const nonce: string = await getCurrentNonce(this.connection, nonceAccount);
+    return buildDurableNonceTx(
+      [this.cancelAllIx()],
+      this.payer,
+      nonceAccount,
+      nonceAuthority ?? this.payer,
+      nonce,
+    );
+  }
+
   /**
    * CancelAllOnCore instruction. Cancels all orders on a market directly on the core program,
    * including reverse orders and global orders with rent prepayment.
@@ -1,8 +1,10 @@
 export const FIXED_MANIFEST_HEADER_SIZE: number = 256;
 export const FIXED_GLOBAL_HEADER_SIZE: number = 96;
 export const FIXED_WRAPPER_HEADER_SIZE: number = 64;
+export const MARKET_BLOCK_SIZE: number = 80;
 export const NIL: number = 4_294_967_295;
 export const NO_EXPIRATION_LAST_VALID_SLOT = 0;
+export const MARKET_FLAG_NO_INTERNAL_CROSS = 1 << 0;
 export const U32_MAX = 4_294_967_295;
 export const PRICE_MIN_EXP = -18;
 export const PRICE_MAX_EXP = 8;
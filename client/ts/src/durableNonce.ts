@@ -0,0 +1,154 @@
+import {
+  Connection,
+  Keypair,
+  NONCE_ACCOUNT_LENGTH,
+  NonceInformation,
+  PublicKey,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+  TransactionSignature,
+} from '@solana/web3.js';
+
+// Durable nonce transactions do not expire with the blockhash, so a maker can
+// sign one ahead of time and hand it to a watchdog that holds no keys. The
+// watchdog only has to submit it, from anywhere with RPC access, to pull the
+// maker's quotes. A nonce can only be used once, and advancing it by any other
+// transaction invalidates everything signed against it.
+
+/**
+ * Instructions to create and initialize a nonce account. The nonce account
+ * keypair has to sign the transaction they are in.
+ *
+ * @param connection Connection
+ * @param payer PublicKey funding the nonce account
+ * @param nonceAccount PublicKey of the new nonce account
+ * @param nonceAuthority PublicKey allowed to advance the nonce
+ *
+ * @returns Promise<TransactionInstruction[]>
+ */
+export async function createNonceAccountIxs(
+  connection: Connection,
+  payer: PublicKey,
+  nonceAccount: PublicKey,
+  nonceAuthority: PublicKey,
+): Promise<TransactionInstruction[]> {
+  const lamports: number = await connection.getMinimumBalanceForRentExemption(
+    NONCE_ACCOUNT_LENGTH,
+  );
+  return SystemProgram.createNonceAccount({
+    fromPubkey: payer,
+    noncePubkey: nonceAccount,
+    authorizedPubkey: nonceAuthority,
+    lamports,
+  }).instructions;
+}
+
+/**
+ * Read the current nonce stored in a nonce account.
+ *
+ * @param connection Connection
+ * @param nonceAccount PublicKey
+ *
+ * @returns Promise<string>
+ */
+export async function getCurrentNonce(
+  connection: Connection,
+  nonceAccount: PublicKey,
+): Promise<string> {
+  const nonceAccountData = await connection.getNonce(nonceAccount);
+  if (!nonceAccountData) {
+    throw new Error(`Nonce account ${nonceAccount.toBase58()} not found`);
+  }
+  return nonceAccountData.nonce;
+}
+
+/**
+ * Build a transaction that uses a durable nonce instead of a recent
+ * blockhash. The nonce advance is put first, as the runtime requires.
+ *
+ * @param instructions TransactionInstruction[]
+ * @param feePayer PublicKey
+ * @param nonceAccount PublicKey
+ * @param nonceAuthority PublicKey
+ * @param nonce string current nonce of the nonce account
+ *
+ * @returns Transaction
+ */
+export function buildDurableNonceTx(
+  instructions: TransactionInstruction[],
+  feePayer: PublicKey,
+  nonceAccount: PublicKey,
+  nonceAuthority: PublicKey,
+  nonce: string,
+): Transaction {
+  const nonceInfo: NonceInformation = {
+    nonce,
+    nonceInstruction: SystemProgram.nonceAdvance({
+      noncePubkey: nonceAccount,
+      authorizedPubkey: nonceAuthority,
+    }),
+  };
+  const transaction: Transaction = new Transaction();
+  transaction.feePayer = feePayer;
+  transaction.nonceInfo = nonceInfo;
+  transaction.recentBlockhash = nonce;
+  return transaction.add(nonceInfo.nonceInstruction, ...instructions);
+}
+
+/**
+ * Sign a durable nonce transaction and serialize it for storage by a
+ * watchdog. Every signer the transaction needs must be given.
+ *
+ * @param transaction Transaction built with buildDurableNonceTx
+ * @param signers Keypair[]
+ *
+ * @returns string base64 wire transaction
+ */
+export function preSignTransaction(
+  transaction: Transaction,
+  signers: Keypair[],
+): string {
+  transaction.sign(...signers);
+  return transaction.serialize().toString('base64');
+}
+
+/**
+ * Whether a pre-signed transaction can still land, meaning the nonce account
+ * still holds the nonce it was signed against. The watchdog should check this
+ * periodically and ask for a new one when it turns false.
+ *
+ * @param connection Connection
+ * @param serialized string base64 wire transaction
+ * @param nonceAccount PublicKey
+ *
+ * @returns Promise<boolean>
+ */
+export async function isPreSignedTransactionCurrent(
+  connection: Connection,
+  serialized: string,
+  nonceAccount: PublicKey,
+): Promise<boolean> {
+  const transaction: Transaction = Transaction.from(
+    Buffer.from(serialized, 'base64'),
+  );
+  return (
+    transaction.recentBlockhash ==
+    (await getCurrentNonce(connection, nonceAccount))
+  );
+}
+
+/**
+ * Submit a pre-signed transaction. Needs no keys, so it can run on a watchdog.
+ *
+ * @param connection Connection
+ * @param serialized string base64 wire transaction
+ *
+ * @returns Promise<TransactionSignature>
+ */
+export async function sendPreSignedTransaction(
+  connection: Connection,
+  serialized: string,
+): Promise<TransactionSignature> {
+  return connection.sendRawTransaction(Buffer.from(serialized, 'base64'));
+}
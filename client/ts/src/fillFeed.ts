@@ -59,6 +59,7 @@ export class FillFeed {
   constructor(
     private connection: Connection,
     private onTruncatedLogs?: (signature: string, slot: number) => void,
+    private onFill?: (fill: FillLogResult, inferred: boolean) => void,
   ) {
     this.wsManager = new WebSocketManager(1234, 30000);
   }
@@ -306,6 +307,7 @@ export class FillFeed {
         takerIsBuy: deserializedFillLog.takerIsBuy.toString(),
       });
       this.wsManager.broadcast(JSON.stringify(fillResult));
+      this.onFill?.(fillResult, false);
       if (signature.blockTime) {
         fillLag.set(Date.now() / 1000 - signature.blockTime);
       }
@@ -326,6 +328,7 @@ export class FillFeed {
         console.log('Inferred a fill', JSON.stringify(inferredFill));
         inferredFills.inc({ market: inferredFill.market });
         this.wsManager.broadcast(JSON.stringify(inferredFill));
+        this.onFill?.(inferredFill, true);
       }
     }
   }
@@ -402,6 +405,11 @@ export function toFillLogResult(
     isMakerGlobal: fillLog.isMakerGlobal,
     makerSequenceNumber: fillLog.makerSequenceNumber.toString(),
     takerSequenceNumber: fillLog.takerSequenceNumber.toString(),
+    makerTag: fillLog.makerTag,
+    takerTag: fillLog.takerTag,
+    selfTrade: fillLog.selfTrade,
+    takerFeeBps: fillLog.takerFeeBps,
+    referred: fillLog.referred,
     signature,
     slot,
   };
@@ -48,6 +48,7 @@ export class FillFeedBlockSub {
     private connection: Connection,
     wsPort: number = 1234,
     private onTruncatedLogs?: (signature: string, slot: number) => void,
+    private onFill?: (fill: FillLogResult, inferred: boolean) => void,
   ) {
     this.wsManager = new WebSocketManager(wsPort, 30000);
   }
@@ -345,6 +346,7 @@ export class FillFeedBlockSub {
 
       // Send to all connected clients
       this.wsManager.broadcast(JSON.stringify(fillResult));
+      this.onFill?.(fillResult, false);
     }
 
     if (truncated) {
@@ -362,6 +364,7 @@ export class FillFeedBlockSub {
         console.log('Inferred a fill', JSON.stringify(inferredFill));
         inferredFills.inc({ market: inferredFill.market });
         this.wsManager.broadcast(JSON.stringify(inferredFill));
+        this.onFill?.(inferredFill, true);
       }
     }
   }
@@ -1,7 +1,13 @@
 export * from './client';
 export * from './market';
 export * from './global';
+export * from './solanaPay';
 export * from './types';
+export * from './ccxt';
+export * from './logs';
+export * from './positionHistory';
+export * from './durableNonce';
+export * from './snapshot';
 export * from './utils';
 
 // Do not export all of manifest because names collide with wrapper. Force users
@@ -0,0 +1,78 @@
+import { CancelOrderLog } from './manifest/accounts/CancelOrderLog';
+import { ClaimSeatLog } from './manifest/accounts/ClaimSeatLog';
+import { DepositLog } from './manifest/accounts/DepositLog';
+import { FillLog } from './manifest/accounts/FillLog';
+import { PlaceOrderLog } from './manifest/accounts/PlaceOrderLog';
+import { WithdrawLog } from './manifest/accounts/WithdrawLog';
+import { genAccDiscriminator } from './utils/discriminator';
+
+/**
+ * ManifestLog is an event the program emitted in a transaction.
+ */
+export type ManifestLog =
+  | { type: 'fill'; log: FillLog }
+  | { type: 'deposit'; log: DepositLog }
+  | { type: 'withdraw'; log: WithdrawLog }
+  | { type: 'placeOrder'; log: PlaceOrderLog }
+  | { type: 'claimSeat'; log: ClaimSeatLog }
+  | { type: 'cancelOrder'; log: CancelOrderLog };
+
+const fillLogDiscriminant: Buffer = genAccDiscriminator(
+  'manifest::logs::FillLog',
+);
+const depositLogDiscriminant: Buffer = genAccDiscriminator(
+  'manifest::logs::DepositLog',
+);
+const withdrawLogDiscriminant: Buffer = genAccDiscriminator(
+  'manifest::logs::WithdrawLog',
+);
+const placeOrderLogDiscriminant: Buffer = genAccDiscriminator(
+  'manifest::logs::PlaceOrderLog',
+);
+const claimSeatLogDiscriminant: Buffer = genAccDiscriminator(
+  'manifest::logs::ClaimSeatLog',
+);
+const cancelOrderLogDiscriminant: Buffer = genAccDiscriminator(
+  'manifest::logs::CancelOrderLog',
+);
+
+/**
+ * Parse the fill, deposit, withdraw, place order, claim seat and cancel order
+ * events out of the log messages of a transaction, in the order they were
+ * emitted. Other program data is ignored.
+ *
+ * @param logMessages string[]
+ *
+ * @returns ManifestLog[]
+ */
+export function parseManifestLogs(logMessages: string[]): ManifestLog[] {
+  const logs: ManifestLog[] = [];
+  for (const message of logMessages) {
+    if (!message.startsWith('Program data: ')) {
+      continue;
+    }
+    const buffer: Buffer = Buffer.from(message.split(' ')[2], 'base64');
+    const discriminant: Buffer = buffer.subarray(0, 8);
+    const data: Buffer = buffer.subarray(8);
+    if (discriminant.equals(fillLogDiscriminant)) {
+      logs.push({ type: 'fill', log: FillLog.deserialize(data)[0] });
+    } else if (discriminant.equals(depositLogDiscriminant)) {
+      logs.push({ type: 'deposit', log: DepositLog.deserialize(data)[0] });
+    } else if (discriminant.equals(withdrawLogDiscriminant)) {
+      logs.push({ type: 'withdraw', log: WithdrawLog.deserialize(data)[0] });
+    } else if (discriminant.equals(placeOrderLogDiscriminant)) {
+      logs.push({
+        type: 'placeOrder',
+        log: PlaceOrderLog.deserialize(data)[0],
+      });
+    } else if (discriminant.equals(claimSeatLogDiscriminant)) {
+      logs.push({ type: 'claimSeat', log: ClaimSeatLog.deserialize(data)[0] });
+    } else if (discriminant.equals(cancelOrderLogDiscriminant)) {
+      logs.push({
+        type: 'cancelOrder',
+        log: CancelOrderLog.deserialize(data)[0],
+      });
+    }
+  }
+  return logs;
+}
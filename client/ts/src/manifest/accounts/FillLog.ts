@@ -33,7 +33,12 @@ export type FillLogArgs = {
   takerSequenceNumber: beet.bignum;
   takerIsBuy: boolean;
   isMakerGlobal: boolean;
-  padding: number[] /* size: 14 */;
+  selfTrade: boolean;
+  referred: boolean;
+  makerTag: number;
+  takerTag: number;
+  takerFeeBps: number;
+  padding2: number[] /* size: 2 */;
 };
 /**
  * Holds the data for the {@link FillLog} Account and provides de/serialization
@@ -56,7 +61,12 @@ export class FillLog implements FillLogArgs {
     readonly takerSequenceNumber: beet.bignum,
     readonly takerIsBuy: boolean,
     readonly isMakerGlobal: boolean,
-    readonly padding: number[] /* size: 14 */,
+    readonly selfTrade: boolean,
+    readonly referred: boolean,
+    readonly makerTag: number,
+    readonly takerTag: number,
+    readonly takerFeeBps: number,
+    readonly padding2: number[] /* size: 2 */,
   ) {}
 
   /**
@@ -76,7 +86,12 @@ export class FillLog implements FillLogArgs {
       args.takerSequenceNumber,
       args.takerIsBuy,
       args.isMakerGlobal,
-      args.padding,
+      args.selfTrade,
+      args.referred,
+      args.makerTag,
+      args.takerTag,
+      args.takerFeeBps,
+      args.padding2,
     );
   }
 
@@ -212,7 +227,12 @@ export class FillLog implements FillLogArgs {
       })(),
       takerIsBuy: this.takerIsBuy,
       isMakerGlobal: this.isMakerGlobal,
-      padding: this.padding,
+      selfTrade: this.selfTrade,
+      referred: this.referred,
+      makerTag: this.makerTag,
+      takerTag: this.takerTag,
+      takerFeeBps: this.takerFeeBps,
+      padding2: this.padding2,
     };
   }
 }
@@ -235,7 +255,12 @@ export const fillLogBeet = new beet.BeetStruct<FillLog, FillLogArgs>(
     ['takerSequenceNumber', beet.u64],
     ['takerIsBuy', beet.bool],
     ['isMakerGlobal', beet.bool],
-    ['padding', beet.uniformFixedSizeArray(beet.u8, 14)],
+    ['selfTrade', beet.bool],
+    ['referred', beet.bool],
+    ['makerTag', beet.u32],
+    ['takerTag', beet.u32],
+    ['takerFeeBps', beet.u16],
+    ['padding2', beet.uniformFixedSizeArray(beet.u8, 2)],
   ],
   FillLog.fromArgs,
   'FillLog',
@@ -15,6 +15,7 @@ import { deserializeRedBlackTree } from './utils/redBlackTree';
 import { convertU128, toBigInt, toNum } from './utils/numbers';
 import {
   FIXED_MANIFEST_HEADER_SIZE,
+  MARKET_FLAG_NO_INTERNAL_CROSS,
   NIL,
   NO_EXPIRATION_LAST_VALID_SLOT,
 } from './constants';
@@ -53,6 +54,12 @@ export type RestingOrder = {
   orderType: OrderType;
   /** Spread in bps for reverse orders. Reverse: [0.1-6553.5] bps (0.1 bps increments). ReverseTight: [0.0001-6.5535] bps (0.0001 bps increments). Only set for orderType === Reverse || ReverseTight. */
   spreadBps?: number;
+  /** Takers filling fewer base atoms than this skip the order, unless they take all of it. Zero for no minimum. */
+  minTakerAtoms: bignum;
+  /** Opaque tag set by the trader when placing, echoed in fills. Zero when untagged. */
+  tag: number;
+  /** Slot the order was put on the book. Zero for orders placed before it was tracked. */
+  placedSlot: number;
 };
 
 /**
@@ -95,6 +102,28 @@ export interface MarketData {
   claimedSeats: ClaimedSeat[];
   /** Quote volume in atoms. */
   quoteVolumeAtoms: bigint;
+  /** Slots an order rests before its maker can cancel it. Zero is off. */
+  minRestingSlots: number;
+  /** Market mode bits set at creation, see MARKET_FLAG_NO_INTERNAL_CROSS. */
+  marketFlags: number;
+  /** Weekdays the market trades on, bit 0 is Monday. Zero is always open. */
+  tradingDays: number;
+  /** First minute of the UTC day the market is open. */
+  tradingOpenMinute: number;
+  /** First minute of the UTC day the market is closed again. */
+  tradingCloseMinute: number;
+  /** Slots between committing to a swap and executing it. Zero is off. */
+  takerDelaySlots: number;
+  /** Quote atoms fills rounded in favor of the taker, can overflow. */
+  takerRoundingQuoteAtoms: bigint;
+  /** Quote atoms fills rounded in favor of the maker, can overflow. */
+  makerRoundingQuoteAtoms: bigint;
+  /** Raw price of the latest fill against the book, scaled by 10^18. Zero before the first. */
+  lastTradePrice: bigint;
+  /** Raw time weighted average price as of lastTradeSlot, scaled by 10^18. */
+  twapPrice: bigint;
+  /** Slot of the latest fill against the book. */
+  lastTradeSlot: number;
 }
 
 /**
@@ -614,6 +643,92 @@ export class Market {
     return this.data.quoteVolumeAtoms;
   }
 
+  /**
+   * Gets the quote atoms that fills rounded to the taker and to the maker over
+   * the lifetime of the market.
+   *
+   * @returns { taker: bigint; maker: bigint }
+   */
+  public roundingQuoteAtoms(): { taker: bigint; maker: bigint } {
+    return {
+      taker: this.data.takerRoundingQuoteAtoms,
+      maker: this.data.makerRoundingQuoteAtoms,
+    };
+  }
+
+  /**
+   * Price of the latest fill against the book in tokens of quote per token of
+   * base, undefined before the first. RFQ fills are not included. The TWAP is
+   * read with the GetMarketPrices instruction, which rolls it forward to the
+   * current slot.
+   *
+   * @returns number | undefined
+   */
+  public lastTradePrice(): number | undefined {
+    if (this.data.lastTradePrice == 0n) {
+      return undefined;
+    }
+    return (
+      (Number(this.data.lastTradePrice) / 10 ** 18) *
+      10 ** (this.data.baseMintDecimals - this.data.quoteMintDecimals)
+    );
+  }
+
+  /**
+   * Slots an order has to rest before its maker can cancel it. Zero is off.
+   *
+   * @returns number
+   */
+  public minRestingSlots(): number {
+    return this.data.minRestingSlots;
+  }
+
+  /**
+   * Slots a taker has to wait between committing to a swap and executing it.
+   * Zero is off, otherwise swaps go through CommitSwap and RevealSwap.
+   *
+   * @returns number
+   */
+  public takerDelaySlots(): number {
+    return this.data.takerDelaySlots;
+  }
+
+  /**
+   * Whether orders that would cross an order from the same seat fail instead
+   * of trading against it.
+   *
+   * @returns boolean
+   */
+  public isNoInternalCross(): boolean {
+    return (this.data.marketFlags & MARKET_FLAG_NO_INTERNAL_CROSS) != 0;
+  }
+
+  /**
+   * Whether the market takes new orders and swaps at a time. Cancels and
+   * withdraws are allowed outside trading hours.
+   *
+   * @param unixTimestamp number of seconds, usually the cluster clock
+   *
+   * @returns boolean
+   */
+  public isOpenAt(unixTimestamp: number): boolean {
+    if (this.data.tradingDays == 0) {
+      return true;
+    }
+    const secondsPerDay: number = 86_400;
+    const daysSinceEpoch: number = Math.floor(unixTimestamp / secondsPerDay);
+    // 1970-01-01 was a Thursday.
+    const weekday: number = (((daysSinceEpoch + 3) % 7) + 7) % 7;
+    const minute: number = Math.floor(
+      (unixTimestamp - daysSinceEpoch * secondsPerDay) / 60,
+    );
+    return (
+      (this.data.tradingDays & (1 << weekday)) != 0 &&
+      minute >= this.data.tradingOpenMinute &&
+      minute < this.data.tradingCloseMinute
+    );
+  }
+
   /**
    * Print all information loaded about the market in a human readable format.
    */
@@ -709,13 +824,34 @@ export class Market {
     const _freeListHeadIndex = data.readUInt32LE(offset);
     offset += 4;
 
-    const _padding2 = data.readUInt32LE(offset);
+    const minRestingSlots: number = data.readUInt32LE(offset);
     offset += 4;
 
     const quoteVolumeAtoms: bigint = data.readBigUInt64LE(offset);
     offset += 8;
 
-    // _padding3: [u64; 8],
+    const marketFlags: number = data.readUInt8(offset);
+    const tradingDays: number = data.readUInt8(offset + 1);
+    const tradingOpenMinute: number = data.readUInt16LE(offset + 2);
+    const tradingCloseMinute: number = data.readUInt16LE(offset + 4);
+    const takerDelaySlots: number = data.readUInt16LE(offset + 6);
+    offset += 8;
+
+    const takerRoundingQuoteAtoms: bigint = data.readBigUInt64LE(offset);
+    offset += 8;
+    const makerRoundingQuoteAtoms: bigint = data.readBigUInt64LE(offset);
+    offset += 8;
+
+    const lastTradePrice: bigint =
+      data.readBigUInt64LE(offset) + (data.readBigUInt64LE(offset + 8) << 64n);
+    offset += 16;
+    const twapPrice: bigint =
+      data.readBigUInt64LE(offset) + (data.readBigUInt64LE(offset + 8) << 64n);
+    offset += 16;
+    const lastTradeSlot: number = data.readUInt32LE(offset);
+    offset += 4;
+
+    // _padding3: [u8; 4],
 
     const bids: RestingOrder[] =
       bidsRootIndex != NIL
@@ -743,6 +879,17 @@ export class Market {
                   convertU128(restingOrderInternal.price) *
                   10 ** (baseMintDecimals - quoteMintDecimals),
                 ...restingOrderInternal,
+                minTakerAtoms: new BN(
+                  restingOrderInternal.padding.slice(6, 14),
+                  10,
+                  'le',
+                ),
+                tag: Buffer.from(
+                  restingOrderInternal.padding.slice(2, 6),
+                ).readUInt32LE(0),
+                placedSlot: Buffer.from(
+                  restingOrderInternal.padding.slice(14, 18),
+                ).readUInt32LE(0),
               };
 
               if (
@@ -750,11 +897,7 @@ export class Market {
                 restingOrderInternal.orderType === OrderType.ReverseTight
               ) {
                 const paddingBytes = restingOrderInternal.padding;
-                const spreadRaw =
-                  paddingBytes[0] |
-                  (paddingBytes[1] << 8) |
-                  (paddingBytes[2] << 16) |
-                  (paddingBytes[3] << 24);
+                const spreadRaw = paddingBytes[0] | (paddingBytes[1] << 8);
 
                 // Convert spreadRaw to bps based on order type
                 // Reverse: spreadRaw is in units of 1/100,000 (base), divide by 10 to get bps
@@ -806,6 +949,17 @@ export class Market {
                   convertU128(restingOrderInternal.price) *
                   10 ** (baseMintDecimals - quoteMintDecimals),
                 ...restingOrderInternal,
+                minTakerAtoms: new BN(
+                  restingOrderInternal.padding.slice(6, 14),
+                  10,
+                  'le',
+                ),
+                tag: Buffer.from(
+                  restingOrderInternal.padding.slice(2, 6),
+                ).readUInt32LE(0),
+                placedSlot: Buffer.from(
+                  restingOrderInternal.padding.slice(14, 18),
+                ).readUInt32LE(0),
               };
 
               if (
@@ -813,11 +967,7 @@ export class Market {
                 restingOrderInternal.orderType === OrderType.ReverseTight
               ) {
                 const paddingBytes = restingOrderInternal.padding;
-                const spreadRaw =
-                  paddingBytes[0] |
-                  (paddingBytes[1] << 8) |
-                  (paddingBytes[2] << 16) |
-                  (paddingBytes[3] << 24);
+                const spreadRaw = paddingBytes[0] | (paddingBytes[1] << 8);
 
                 // Convert spreadRaw to bps based on order type
                 // Reverse: spreadRaw is in units of 1/100,000 (base), divide by 10 to get bps
@@ -871,6 +1021,17 @@ export class Market {
       asks,
       claimedSeats,
       quoteVolumeAtoms,
+      minRestingSlots,
+      marketFlags,
+      tradingDays,
+      tradingOpenMinute,
+      tradingCloseMinute,
+      takerDelaySlots,
+      takerRoundingQuoteAtoms,
+      makerRoundingQuoteAtoms,
+      lastTradePrice,
+      twapPrice,
+      lastTradeSlot,
     };
   }
 
@@ -0,0 +1,175 @@
+import { FillLogResult } from './types';
+
+// Rebuilds a trader's position on one market from its fills, using average
+// cost for the entry price. Manifest markets are spot, so there is no funding
+// or liquidation to account for. Amounts are in atoms, as strings so they
+// survive JSON.
+
+/**
+ * PositionHistoryEntry is one fill seen from the trader's side, with the
+ * position after it.
+ */
+export type PositionHistoryEntry = {
+  /** Slot number of the fill. */
+  slot: number;
+  /** Signature of the tx where the fill happened. */
+  signature: string;
+  /** Block time in seconds since Unix epoch, when known. */
+  blockTime?: number;
+  /** Side the trader was on. */
+  side: 'buy' | 'sell';
+  /** Whether the trader was the maker. */
+  isMaker: boolean;
+  /** Number of base atoms traded. */
+  baseAtoms: string;
+  /** Number of quote atoms traded. */
+  quoteAtoms: string;
+  /** Price as float. Quote atoms per base atom. */
+  priceAtoms: number;
+  /** Base atoms held after the fill, negative when net sold. */
+  positionBaseAtoms: string;
+  /** Average entry in quote atoms per base atom, zero when flat. */
+  averageEntryPriceAtoms: number;
+  /** Quote atoms realized by this fill. */
+  realizedPnlQuoteAtoms: string;
+  /** Quote atoms realized up to and including this fill. */
+  totalRealizedPnlQuoteAtoms: string;
+};
+
+/**
+ * PositionHistory is the full statement for one trader on one market.
+ */
+export type PositionHistory = {
+  /** Public key for the market as base58. */
+  market: string;
+  /** Public key for the trader as base58. */
+  trader: string;
+  /** Entries oldest first. */
+  entries: PositionHistoryEntry[];
+};
+
+const CSV_COLUMNS: (keyof PositionHistoryEntry)[] = [
+  'slot',
+  'signature',
+  'blockTime',
+  'side',
+  'isMaker',
+  'baseAtoms',
+  'quoteAtoms',
+  'priceAtoms',
+  'positionBaseAtoms',
+  'averageEntryPriceAtoms',
+  'realizedPnlQuoteAtoms',
+  'totalRealizedPnlQuoteAtoms',
+];
+
+function abs(value: bigint): bigint {
+  return value < 0n ? -value : value;
+}
+
+/**
+ * Reconstruct the position of a trader on a market from fills, oldest first.
+ * Fills on other markets, fills the trader is not part of and fills against
+ * themselves are skipped.
+ *
+ * @param fills FillLogResult[]
+ * @param market string
+ * @param trader string
+ *
+ * @returns PositionHistory
+ */
+export function reconstructPositionHistory(
+  fills: FillLogResult[],
+  market: string,
+  trader: string,
+): PositionHistory {
+  const entries: PositionHistoryEntry[] = [];
+  // Signed base position and the quote atoms it cost, or was sold for when
+  // negative.
+  let position: bigint = 0n;
+  let costBasis: bigint = 0n;
+  let totalRealized: bigint = 0n;
+
+  for (const fill of fills) {
+    const isMaker: boolean = fill.maker == trader;
+    const isTaker: boolean = fill.taker == trader;
+    if (fill.market != market || isMaker == isTaker) {
+      continue;
+    }
+    const isBuy: boolean = isTaker ? fill.takerIsBuy : !fill.takerIsBuy;
+    const baseAtoms: bigint = BigInt(fill.baseAtoms);
+    const quoteAtoms: bigint = BigInt(fill.quoteAtoms);
+    const direction: bigint = isBuy ? 1n : -1n;
+
+    const isLong: boolean = position > 0n;
+    let realized: bigint = 0n;
+    if (position == 0n || isLong == isBuy) {
+      position += direction * baseAtoms;
+      costBasis += quoteAtoms;
+    } else {
+      // Close against the open position first, anything left opens a new
+      // one on the other side at this fill's price.
+      const open: bigint = abs(position);
+      const closing: bigint = baseAtoms < open ? baseAtoms : open;
+      const released: bigint = (costBasis * closing) / open;
+      const closingQuote: bigint = (quoteAtoms * closing) / baseAtoms;
+      realized = isBuy ? released - closingQuote : closingQuote - released;
+      costBasis -= released;
+      position += direction * closing;
+      if (baseAtoms > closing) {
+        position += direction * (baseAtoms - closing);
+        costBasis = quoteAtoms - closingQuote;
+      }
+    }
+    totalRealized += realized;
+
+    const entry: PositionHistoryEntry = {
+      slot: fill.slot,
+      signature: fill.signature,
+      side: isBuy ? 'buy' : 'sell',
+      isMaker,
+      baseAtoms: fill.baseAtoms,
+      quoteAtoms: fill.quoteAtoms,
+      priceAtoms: fill.priceAtoms,
+      positionBaseAtoms: position.toString(),
+      averageEntryPriceAtoms:
+        position == 0n ? 0 : Number(costBasis) / Number(abs(position)),
+      realizedPnlQuoteAtoms: realized.toString(),
+      totalRealizedPnlQuoteAtoms: totalRealized.toString(),
+    };
+    if (fill.blockTime !== undefined) {
+      entry.blockTime = fill.blockTime;
+    }
+    entries.push(entry);
+  }
+
+  return { market, trader, entries };
+}
+
+/**
+ * Render a position history as CSV with a header row.
+ *
+ * @param history PositionHistory
+ *
+ * @returns string
+ */
+export function positionHistoryToCsv(history: PositionHistory): string {
+  const rows: string[] = [CSV_COLUMNS.join(',')];
+  for (const entry of history.entries) {
+    rows.push(
+      CSV_COLUMNS.map((column) => String(entry[column] ?? '')).join(','),
+    );
+  }
+  return rows.join('\n') + '\n';
+}
+
+/**
+ * Render a position history as indented JSON.
+ *
+ * @param history PositionHistory
+ *
+ * @returns string
+ */
+export function positionHistoryToJson(history: PositionHistory): string {
+  return JSON.stringify(history, null, 2);
+}
@@ -0,0 +1,146 @@
+import {
+  AccountInfo,
+  Commitment,
+  Connection,
+  PublicKey,
+  RpcResponseAndContext,
+} from '@solana/web3.js';
+import { Account, unpackAccount } from '@solana/spl-token';
+import { Market } from './market';
+import { Global } from './global';
+import { getVaultAddress } from './utils/market';
+import { getGlobalAddress } from './utils/global';
+
+// Quoting from a market read at one slot and vault or global balances read at
+// another can show liquidity that is not there. Everything in a snapshot comes
+// from a single getMultipleAccounts response, which the RPC serves from one
+// slot.
+
+/**
+ * Market, vaults and global accounts as of one slot.
+ */
+export type MarketSnapshot = {
+  /** Slot all accounts were read at. */
+  slot: number;
+  market: Market;
+  baseVault: Account;
+  quoteVault: Account;
+  /** Null when the global account for the base mint does not exist. */
+  baseGlobal: Global | null;
+  /** Null when the global account for the quote mint does not exist. */
+  quoteGlobal: Global | null;
+};
+
+/**
+ * Fetch a market, both of its vaults and both global accounts at the same
+ * slot. Throws instead of returning data older than minContextSlot, so a
+ * caller polling snapshots never goes back in time.
+ *
+ * Mints are needed to find the other accounts. When baseMint and quoteMint
+ * are not given, the market is read once first to get them.
+ *
+ * @param connection Connection
+ * @param address PublicKey of the market
+ * @param options.commitment Commitment, defaults to confirmed
+ * @param options.minContextSlot number, usually the slot of the last snapshot
+ * @param options.baseMint PublicKey, saves the first read when known
+ * @param options.quoteMint PublicKey, saves the first read when known
+ *
+ * @returns Promise<MarketSnapshot>
+ */
+export async function fetchMarketSnapshot(
+  connection: Connection,
+  address: PublicKey,
+  options: {
+    commitment?: Commitment;
+    minContextSlot?: number;
+    baseMint?: PublicKey;
+    quoteMint?: PublicKey;
+  } = {},
+): Promise<MarketSnapshot> {
+  const commitment: Commitment = options.commitment ?? 'confirmed';
+  let { baseMint, quoteMint } = options;
+  if (!baseMint || !quoteMint) {
+    // Mints never change, so this read does not have to be at the same slot.
+    const marketAccountInfo: AccountInfo<Buffer> | null =
+      await connection.getAccountInfo(address, commitment);
+    if (!marketAccountInfo) {
+      throw new Error(`Market ${address.toBase58()} not found`);
+    }
+    const market: Market = Market.loadFromBuffer({
+      address,
+      buffer: marketAccountInfo.data,
+    });
+    baseMint = market.baseMint();
+    quoteMint = market.quoteMint();
+  }
+
+  const addresses: PublicKey[] = [
+    address,
+    getVaultAddress(address, baseMint),
+    getVaultAddress(address, quoteMint),
+    getGlobalAddress(baseMint),
+    getGlobalAddress(quoteMint),
+  ];
+  const response: RpcResponseAndContext<(AccountInfo<Buffer> | null)[]> =
+    await connection.getMultipleAccountsInfoAndContext(addresses, {
+      commitment,
+      minContextSlot: options.minContextSlot,
+    });
+  const slot: number = response.context.slot;
+  // Not every RPC honors minContextSlot.
+  if (options.minContextSlot !== undefined && slot < options.minContextSlot) {
+    throw new Error(
+      `Snapshot slot ${slot} is older than min context slot ${options.minContextSlot}`,
+    );
+  }
+
+  const [
+    marketAccountInfo,
+    baseVaultAccountInfo,
+    quoteVaultAccountInfo,
+    baseGlobalAccountInfo,
+    quoteGlobalAccountInfo,
+  ] = response.value;
+  if (!marketAccountInfo || !baseVaultAccountInfo || !quoteVaultAccountInfo) {
+    throw new Error(`Market ${address.toBase58()} or its vaults not found`);
+  }
+  const market: Market = Market.loadFromBuffer({
+    address,
+    buffer: marketAccountInfo.data,
+    slot,
+  });
+  if (
+    !market.baseMint().equals(baseMint) ||
+    !market.quoteMint().equals(quoteMint)
+  ) {
+    throw new Error(`Mints given do not match market ${address.toBase58()}`);
+  }
+
+  return {
+    slot,
+    market,
+    baseVault: unpackAccount(
+      addresses[1],
+      baseVaultAccountInfo,
+      baseVaultAccountInfo.owner,
+    ),
+    quoteVault: unpackAccount(
+      addresses[2],
+      quoteVaultAccountInfo,
+      quoteVaultAccountInfo.owner,
+    ),
+    baseGlobal:
+      baseGlobalAccountInfo &&
+      Global.loadFromBuffer({
+        address: addresses[3],
+        buffer: baseGlobalAccountInfo.data,
+      }),
+    quoteGlobal:
+      quoteGlobalAccountInfo &&
+      Global.loadFromBuffer({
+        address: addresses[4],
+        buffer: quoteGlobalAccountInfo.data,
+      }),
+  };
+}
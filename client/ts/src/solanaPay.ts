@@ -0,0 +1,192 @@
+import { bignum } from '@metaplex-foundation/beet';
+import {
+  Connection,
+  PublicKey,
+  Transaction,
+  TransactionInstruction,
+} from '@solana/web3.js';
+import {
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccountIdempotentInstruction,
+  getAssociatedTokenAddressSync,
+} from '@solana/spl-token';
+import BN from 'bn.js';
+import { ManifestClient } from './client';
+import { Market, RestingOrder } from './market';
+import { toBigInt } from './utils/numbers';
+
+// RestingOrder.price is quote atoms per base atom scaled by 10^18.
+const PRICE_SCALE: bigint = 10n ** 18n;
+const BPS_DENOMINATOR: bigint = 10_000n;
+
+/**
+ * Response to the GET request of the Solana Pay transaction request spec.
+ */
+export type TransactionRequestGetResponse = {
+  label: string;
+  icon: string;
+};
+
+/**
+ * Response to the POST request of the Solana Pay transaction request spec.
+ */
+export type TransactionRequestPostResponse = {
+  /** Base64 encoded, unsigned, serialized transaction. */
+  transaction: string;
+  message?: string;
+};
+
+export type SwapTransactionRequestParams = {
+  connection: Connection;
+  /** Market to swap on. */
+  market: PublicKey;
+  /** Wallet from the POST request body. Pays fees and signs the swap. */
+  account: PublicKey;
+  /** Exact number of atoms to send in. */
+  inAtoms: bignum;
+  /** True to sell base for quote, false to buy base with quote. */
+  isBaseIn: boolean;
+  /** Max slippage from the quoted output in basis points. */
+  slippageBps: number;
+};
+
+/**
+ * Estimate the output of an exact in swap by walking the resting orders. Does
+ * not account for expired or unbacked global orders, so the result can be
+ * slightly better than what the program will fill.
+ *
+ * @param market Market to quote against
+ * @param inAtoms Atoms sent in
+ * @param isBaseIn Whether the input is base
+ *
+ * @returns bigint out atoms
+ */
+export function quoteSwapExactIn(
+  market: Market,
+  inAtoms: bigint,
+  isBaseIn: boolean,
+): bigint {
+  // Best order is last.
+  const orders: RestingOrder[] = isBaseIn ? market.bids() : market.asks();
+  let remaining: bigint = inAtoms;
+  let out: bigint = 0n;
+  for (let i = orders.length - 1; i >= 0 && remaining > 0n; i--) {
+    const orderBaseAtoms: bigint = toBigInt(orders[i].numBaseAtoms);
+    const price: bigint = toBigInt(orders[i].price);
+    if (isBaseIn) {
+      const baseAtoms: bigint =
+        remaining < orderBaseAtoms ? remaining : orderBaseAtoms;
+      // Taker receives rounded down quote when selling.
+      out += (baseAtoms * price) / PRICE_SCALE;
+      remaining -= baseAtoms;
+    } else {
+      // Taker pays rounded up quote when buying.
+      const orderQuoteAtoms: bigint =
+        (orderBaseAtoms * price + PRICE_SCALE - 1n) / PRICE_SCALE;
+      if (remaining >= orderQuoteAtoms) {
+        out += orderBaseAtoms;
+        remaining -= orderQuoteAtoms;
+      } else {
+        out += (remaining * PRICE_SCALE) / price;
+        remaining = 0n;
+      }
+    }
+  }
+  return out;
+}
+
+/**
+ * Minimum out after applying slippage, rounded down.
+ *
+ * @param expectedOutAtoms Quoted output
+ * @param slippageBps Slippage in basis points
+ */
+export function applySlippage(
+  expectedOutAtoms: bigint,
+  slippageBps: number,
+): bigint {
+  if (slippageBps < 0 || slippageBps > 10_000) {
+    throw new Error(`Invalid slippage ${slippageBps} bps`);
+  }
+  return (
+    (expectedOutAtoms * (BPS_DENOMINATOR - BigInt(Math.floor(slippageBps)))) /
+    BPS_DENOMINATOR
+  );
+}
+
+/**
+ * Build the POST response for a Solana Pay transaction request that swaps on a
+ * manifest market. The transaction creates the wallet token accounts if they
+ * do not exist yet and fails on chain if the output is below the slippage
+ * bound.
+ *
+ * @param params SwapTransactionRequestParams
+ *
+ * @returns TransactionRequestPostResponse
+ */
+export async function createSwapTransactionRequest({
+  connection,
+  market,
+  account,
+  inAtoms,
+  isBaseIn,
+  slippageBps,
+}: SwapTransactionRequestParams): Promise<TransactionRequestPostResponse> {
+  const client: ManifestClient = await ManifestClient.getClientReadOnly(
+    connection,
+    market,
+  );
+  const inAtomsBigInt: bigint = toBigInt(inAtoms);
+  const expectedOutAtoms: bigint = quoteSwapExactIn(
+    client.market,
+    inAtomsBigInt,
+    isBaseIn,
+  );
+  if (expectedOutAtoms == 0n) {
+    throw new Error(`No liquidity to swap on ${market.toBase58()}`);
+  }
+  const minOutAtoms: bigint = applySlippage(expectedOutAtoms, slippageBps);
+
+  const instructions: TransactionInstruction[] = [];
+  for (const [mint, is22] of [
+    [client.market.baseMint(), client.isBase22],
+    [client.market.quoteMint(), client.isQuote22],
+  ] as [PublicKey, boolean][]) {
+    const tokenProgram: PublicKey = is22
+      ? TOKEN_2022_PROGRAM_ID
+      : TOKEN_PROGRAM_ID;
+    instructions.push(
+      createAssociatedTokenAccountIdempotentInstruction(
+        account,
+        getAssociatedTokenAddressSync(mint, account, true, tokenProgram),
+        account,
+        mint,
+        tokenProgram,
+      ),
+    );
+  }
+  instructions.push(
+    client.swapIx(account, {
+      inAtoms: new BN(inAtomsBigInt.toString()),
+      outAtoms: new BN(minOutAtoms.toString()),
+      isBaseIn,
+      isExactIn: true,
+    }),
+  );
+
+  const { blockhash, lastValidBlockHeight } =
+    await connection.getLatestBlockhash();
+  const transaction: Transaction = new Transaction({
+    feePayer: account,
+    blockhash,
+    lastValidBlockHeight,
+  }).add(...instructions);
+
+  return {
+    transaction: transaction
+      .serialize({ requireAllSignatures: false, verifySignatures: false })
+      .toString('base64'),
+    message: `Swap ${inAtomsBigInt} ${isBaseIn ? 'base' : 'quote'} atoms for at least ${minOutAtoms} ${isBaseIn ? 'quote' : 'base'} atoms`,
+  };
+}
@@ -22,6 +22,16 @@ export type FillLogResult = {
   makerSequenceNumber: string;
   /** Sequential number for every order placed / matched wraps around at u64::MAX */
   takerSequenceNumber: string;
+  /** Tag the maker placed its order with. Optional for backwards compatibility. */
+  makerTag?: number;
+  /** Tag the taker placed its order with. Optional for backwards compatibility. */
+  takerTag?: number;
+  /** Maker and taker are the same seat. Optional for backwards compatibility. */
+  selfTrade?: boolean;
+  /** Taker fee rate charged on the fill in bps, after any fee tier discount. Optional for backwards compatibility. */
+  takerFeeBps?: number;
+  /** The taker's swap paid the referral share of the taker fee to a referrer. Optional for backwards compatibility. */
+  referred?: boolean;
   /** Slot number of the fill. */
   slot: number;
   /** Signature of the tx where the fill happened. */
@@ -1,6 +1,7 @@
 export * from './beet';
 export * from './discriminator';
 export * from './global';
+export * from './instructionSummary';
 export * from './market';
 export * from './numbers';
 export * from './redBlackTree';
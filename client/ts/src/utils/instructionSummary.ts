@@ -0,0 +1,274 @@
+import { bignum } from '@metaplex-foundation/beet';
+import { PublicKey, TransactionInstruction } from '@solana/web3.js';
+import { PROGRAM_ID as MANIFEST_PROGRAM_ID } from '../manifest';
+import { PROGRAM_ID as WRAPPER_PROGRAM_ID } from '../wrapper';
+import {
+  BatchUpdateStruct as CoreBatchUpdateStruct,
+} from '../manifest/instructions/BatchUpdate';
+import {
+  DepositStruct as CoreDepositStruct,
+} from '../manifest/instructions/Deposit';
+import { SwapStruct } from '../manifest/instructions/Swap';
+import {
+  WithdrawStruct as CoreWithdrawStruct,
+} from '../manifest/instructions/Withdraw';
+import { OrderType } from '../manifest/types/OrderType';
+import {
+  BatchUpdateStruct as WrapperBatchUpdateStruct,
+} from '../wrapper/instructions/BatchUpdate';
+import {
+  DepositStruct as WrapperDepositStruct,
+} from '../wrapper/instructions/Deposit';
+import {
+  WithdrawStruct as WrapperWithdrawStruct,
+} from '../wrapper/instructions/Withdraw';
+
+// Indexed by instruction discriminator.
+const MANIFEST_INSTRUCTION_NAMES: string[] = [
+  'CreateMarket',
+  'ClaimSeat',
+  'Deposit',
+  'Withdraw',
+  'Swap',
+  'Expand',
+  'BatchUpdate',
+  'GlobalCreate',
+  'GlobalAddTrader',
+  'GlobalDeposit',
+  'GlobalWithdraw',
+  'GlobalEvict',
+  'GlobalClean',
+  'SwapV2',
+  'SetSeatConfig',
+  'DepositIdempotent',
+  'SwapToSeat',
+];
+
+const WRAPPER_INSTRUCTION_NAMES: string[] = [
+  'CreateWrapper',
+  'ClaimSeat',
+  'Deposit',
+  'Withdraw',
+  'BatchUpdate',
+  'BatchUpdateBaseGlobal',
+  'BatchUpdateQuoteGlobal',
+  'Collect',
+];
+
+// Position of the market account for instructions that have one.
+const MANIFEST_MARKET_ACCOUNT_INDEX: Record<string, number> = {
+  CreateMarket: 1,
+  ClaimSeat: 1,
+  Deposit: 1,
+  Withdraw: 1,
+  Swap: 1,
+  Expand: 1,
+  BatchUpdate: 1,
+  GlobalClean: 1,
+  SwapV2: 2,
+  SetSeatConfig: 1,
+  DepositIdempotent: 1,
+  SwapToSeat: 2,
+};
+
+const WRAPPER_MARKET_ACCOUNT_INDEX: Record<string, number> = {
+  ClaimSeat: 2,
+  Deposit: 2,
+  Withdraw: 2,
+  BatchUpdate: 3,
+  BatchUpdateBaseGlobal: 3,
+  BatchUpdateQuoteGlobal: 3,
+};
+
+type OrderSummaryParams = {
+  baseAtoms: bignum;
+  priceMantissa: number;
+  priceExponent: number;
+  isBid: boolean;
+  orderType: OrderType;
+};
+
+/**
+ * Exact decimal string for a price given as mantissa and exponent. Does not go
+ * through floating point so the output is the same on every platform.
+ *
+ * @param priceMantissa Mantissa as used in place order params
+ * @param priceExponent Exponent as used in place order params
+ */
+export function formatMantissaAndExponent(
+  priceMantissa: number,
+  priceExponent: number,
+): string {
+  const digits: string = priceMantissa.toString();
+  if (priceExponent >= 0) {
+    return priceMantissa == 0 ? '0' : digits + '0'.repeat(priceExponent);
+  }
+  const padded: string = digits.padStart(-priceExponent + 1, '0');
+  const integerPart: string = padded.slice(0, padded.length + priceExponent);
+  const fractionalPart: string = padded
+    .slice(padded.length + priceExponent)
+    .replace(/0+$/, '');
+  return fractionalPart.length > 0
+    ? `${integerPart}.${fractionalPart}`
+    : integerPart;
+}
+
+function plural(count: number, noun: string): string {
+  return `${count} ${noun}${count == 1 ? '' : 's'}`;
+}
+
+function summarizeOrder(order: OrderSummaryParams): string {
+  return `${order.isBid ? 'BUY' : 'SELL'} ${order.baseAtoms.toString()} @ ${formatMantissaAndExponent(order.priceMantissa, order.priceExponent)} ${OrderType[order.orderType]}`;
+}
+
+function summarizeBatch(
+  header: string,
+  numCancels: number,
+  cancelAll: boolean,
+  orders: OrderSummaryParams[],
+): string {
+  const parts: string[] = [];
+  if (cancelAll) {
+    parts.push('cancel all');
+  }
+  parts.push(plural(numCancels, 'cancel'));
+  parts.push(plural(orders.length, 'place'));
+  const placed: string =
+    orders.length > 0 ? `: ${orders.map(summarizeOrder).join(', ')}` : '';
+  return `${header}: ${parts.join(', ')}${placed}`;
+}
+
+function accountAt(ix: TransactionInstruction, index: number): string {
+  const key: PublicKey | undefined = ix.keys[index]?.pubkey;
+  return key ? key.toBase58() : 'unknown';
+}
+
+function instructionHeader(
+  ix: TransactionInstruction,
+  prefix: string,
+  name: string,
+  marketAccountIndex: number | undefined,
+): string {
+  return marketAccountIndex === undefined
+    ? `${prefix} ${name}`
+    : `${prefix} ${name} market ${accountAt(ix, marketAccountIndex)}`;
+}
+
+function summarizeManifestInstruction(ix: TransactionInstruction): string {
+  const discriminator: number = ix.data[0];
+  const name: string =
+    MANIFEST_INSTRUCTION_NAMES[discriminator] ?? `Unknown(${discriminator})`;
+  const header: string = instructionHeader(
+    ix,
+    'Manifest',
+    name,
+    MANIFEST_MARKET_ACCOUNT_INDEX[name],
+  );
+
+  switch (name) {
+    case 'BatchUpdate': {
+      const [{ params }] = CoreBatchUpdateStruct.deserialize(ix.data);
+      return summarizeBatch(
+        header,
+        params.cancels.length,
+        false,
+        params.orders,
+      );
+    }
+    case 'Deposit':
+    case 'DepositIdempotent': {
+      // Both versions share the same params.
+      const [{ params }] = CoreDepositStruct.deserialize(ix.data);
+      return `${header}: ${params.amountAtoms.toString()} atoms of mint ${accountAt(ix, 5)}`;
+    }
+    case 'Withdraw': {
+      const [{ params }] = CoreWithdrawStruct.deserialize(ix.data);
+      return `${header}: ${params.amountAtoms.toString()} atoms of mint ${accountAt(ix, 5)}`;
+    }
+    case 'Swap':
+    case 'SwapV2':
+    case 'SwapToSeat': {
+      // All versions share the same params.
+      const [{ params }] = SwapStruct.deserialize(ix.data);
+      const inSide: string = params.isBaseIn ? 'base' : 'quote';
+      const outSide: string = params.isBaseIn ? 'quote' : 'base';
+      return params.isExactIn
+        ? `${header}: ${params.inAtoms.toString()} ${inSide} atoms in for at least ${params.outAtoms.toString()} ${outSide} atoms out`
+        : `${header}: at most ${params.inAtoms.toString()} ${inSide} atoms in for ${params.outAtoms.toString()} ${outSide} atoms out`;
+    }
+    default:
+      return header;
+  }
+}
+
+function summarizeWrapperInstruction(ix: TransactionInstruction): string {
+  const discriminator: number = ix.data[0];
+  const name: string =
+    WRAPPER_INSTRUCTION_NAMES[discriminator] ?? `Unknown(${discriminator})`;
+  const header: string = instructionHeader(
+    ix,
+    'Manifest wrapper',
+    name,
+    WRAPPER_MARKET_ACCOUNT_INDEX[name],
+  );
+
+  switch (name) {
+    case 'BatchUpdate':
+    case 'BatchUpdateBaseGlobal':
+    case 'BatchUpdateQuoteGlobal': {
+      // All three variants share the same params.
+      const [{ params }] = WrapperBatchUpdateStruct.deserialize(ix.data);
+      return summarizeBatch(
+        header,
+        params.cancels.length,
+        params.cancelAll,
+        params.orders,
+      );
+    }
+    case 'Deposit': {
+      const [{ params }] = WrapperDepositStruct.deserialize(ix.data);
+      return `${header}: ${params.amountAtoms.toString()} atoms of mint ${accountAt(ix, 7)}`;
+    }
+    case 'Withdraw': {
+      const [{ params }] = WrapperWithdrawStruct.deserialize(ix.data);
+      return `${header}: ${params.amountAtoms.toString()} atoms of mint ${accountAt(ix, 7)}`;
+    }
+    default:
+      return header;
+  }
+}
+
+/**
+ * Short human readable description of a manifest or wrapper instruction, for
+ * example "Manifest BatchUpdate market <key>: 1 cancel, 2 places: BUY 100 @
+ * 0.5 Limit, SELL 100 @ 0.6 PostOnly". Sizes are in base atoms and prices in
+ * quote atoms per base atom, so the output only depends on the instruction and
+ * can be reproduced by a wallet from the raw bytes.
+ *
+ * @param ix Instruction to describe
+ */
+export function summarizeInstruction(ix: TransactionInstruction): string {
+  if (ix.data.length == 0) {
+    return `Program ${ix.programId.toBase58()}`;
+  }
+  try {
+    if (ix.programId.equals(MANIFEST_PROGRAM_ID)) {
+      return summarizeManifestInstruction(ix);
+    }
+    if (ix.programId.equals(WRAPPER_PROGRAM_ID)) {
+      return summarizeWrapperInstruction(ix);
+    }
+  } catch (err) {
+    return `Undecodable instruction for program ${ix.programId.toBase58()}`;
+  }
+  return `Program ${ix.programId.toBase58()}`;
+}
+
+/**
+ * Summaries of all instructions in order, one per line.
+ *
+ * @param ixs Instructions to describe
+ */
+export function summarizeInstructions(ixs: TransactionInstruction[]): string {
+  return ixs.map(summarizeInstruction).join('\n');
+}
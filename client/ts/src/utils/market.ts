@@ -9,3 +9,26 @@ export function getVaultAddress(market: PublicKey, mint: PublicKey): PublicKey {
   );
   return vaultAddress;
 }
+
+/**
+ * Estimate how many free blocks a market needs for new seats and orders. Each
+ * seat and each resting order uses one block. A reverse order can use a
+ * second one when it flips while still partially resting.
+ *
+ * @param numOrders Number of orders that will rest
+ * @param numSeats Number of seats that will be claimed
+ * @param numReverseOrders Number of those orders that are reverse orders
+ *
+ * @returns number of blocks, suitable for ManifestClient.expandMarketIx
+ */
+export function estimateMarketBlocksNeeded({
+  numOrders,
+  numSeats,
+  numReverseOrders = 0,
+}: {
+  numOrders: number;
+  numSeats: number;
+  numReverseOrders?: number;
+}): number {
+  return numOrders + numSeats + numReverseOrders;
+}
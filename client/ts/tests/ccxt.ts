@@ -0,0 +1,119 @@
+import { Connection, Keypair, PublicKey } from '@solana/web3.js';
+import { assert } from 'chai';
+import {
+  CcxtOrderBook,
+  CcxtTrade,
+  ManifestClient,
+  Market,
+  toCcxtMarket,
+  toCcxtOrderBook,
+  toCcxtTrade,
+} from '../src';
+import { OrderType } from '../src/manifest';
+import { createMarket } from './createMarket';
+import { deposit } from './deposit';
+import { placeOrder } from './placeOrder';
+import { describeIfDirectTest } from './helpers/mocha';
+
+async function testCcxt(): Promise<void> {
+  const connection: Connection = new Connection(
+    'http://127.0.0.1:8899',
+    'confirmed',
+  );
+  const payerKeypair: Keypair = Keypair.generate();
+  const marketAddress: PublicKey = await createMarket(connection, payerKeypair);
+  const market: Market = await Market.loadFromAddress({
+    connection,
+    address: marketAddress,
+  });
+
+  await ManifestClient.getClientForMarket(
+    connection,
+    marketAddress,
+    payerKeypair,
+  );
+  await deposit(
+    connection,
+    payerKeypair,
+    marketAddress,
+    market.quoteMint(),
+    100,
+  );
+  await deposit(
+    connection,
+    payerKeypair,
+    marketAddress,
+    market.baseMint(),
+    100,
+  );
+
+  // Two orders at the same price collapse into one level.
+  for (const [numBaseTokens, tokenPrice, isBid] of [
+    [1, 9, true],
+    [2, 9, true],
+    [1, 8, true],
+    [3, 11, false],
+    [1, 12, false],
+  ] as [number, number, boolean][]) {
+    await placeOrder(
+      connection,
+      payerKeypair,
+      marketAddress,
+      numBaseTokens,
+      tokenPrice,
+      isBid,
+      OrderType.Limit,
+      0,
+    );
+  }
+  await market.reload(connection);
+
+  const orderBook: CcxtOrderBook = toCcxtOrderBook(market, 'BASE/QUOTE');
+  assert.deepEqual(orderBook.bids, [
+    [9, 3],
+    [8, 1],
+  ]);
+  assert.deepEqual(orderBook.asks, [
+    [11, 3],
+    [12, 1],
+  ]);
+  assert.deepEqual(toCcxtOrderBook(market, 'BASE/QUOTE', 1).bids, [[9, 3]]);
+
+  const ccxtMarket = toCcxtMarket(market, 'BASE/QUOTE');
+  assert.equal(ccxtMarket.id, marketAddress.toBase58());
+  assert.equal(ccxtMarket.precision.price, 10 ** -6);
+
+  // 1.5 base at 10 quote per base, 0.01 quote atoms per base atom.
+  const trade: CcxtTrade = toCcxtTrade(
+    {
+      market: marketAddress.toBase58(),
+      maker: payerKeypair.publicKey.toBase58(),
+      taker: payerKeypair.publicKey.toBase58(),
+      baseAtoms: '1500000000',
+      quoteAtoms: '15000000',
+      priceAtoms: 0.01,
+      takerIsBuy: false,
+      isMakerGlobal: false,
+      makerSequenceNumber: '1',
+      takerSequenceNumber: '2',
+      slot: 10,
+      signature: 'sig',
+      blockTime: 1_700_000_000,
+    },
+    'BASE/QUOTE',
+    market.baseDecimals(),
+    market.quoteDecimals(),
+  );
+  assert.equal(trade.id, '2-1');
+  assert.equal(trade.side, 'sell');
+  assert.equal(trade.amount, 1.5);
+  assert.equal(trade.cost, 15);
+  assert.equal(trade.price, 10);
+  assert.equal(trade.timestamp, 1_700_000_000_000);
+}
+
+describeIfDirectTest(module, 'CCXT test', () => {
+  it('CCXT normalization', async () => {
+    await testCcxt();
+  });
+});
@@ -0,0 +1,109 @@
+import {
+  Connection,
+  Keypair,
+  PublicKey,
+  Transaction,
+  sendAndConfirmTransaction,
+} from '@solana/web3.js';
+import { ManifestClient } from '../src/client';
+import {
+  createNonceAccountIxs,
+  isPreSignedTransactionCurrent,
+  preSignTransaction,
+  sendPreSignedTransaction,
+} from '../src/durableNonce';
+import { createMarket } from './createMarket';
+import { deposit } from './deposit';
+import { Market } from '../src/market';
+import { assert } from 'chai';
+import { placeOrder } from './placeOrder';
+import { OrderType } from '../src/manifest/types';
+import { describeIfDirectTest } from './helpers/mocha';
+
+async function testDurableNonceCancelAll(): Promise<void> {
+  const connection: Connection = new Connection(
+    'http://127.0.0.1:8899',
+    'confirmed',
+  );
+  const payerKeypair: Keypair = Keypair.generate();
+
+  const marketAddress: PublicKey = await createMarket(connection, payerKeypair);
+  const market: Market = await Market.loadFromAddress({
+    connection,
+    address: marketAddress,
+  });
+
+  await deposit(connection, payerKeypair, marketAddress, market.baseMint(), 10);
+
+  const nonceKeypair: Keypair = Keypair.generate();
+  await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(
+      ...(await createNonceAccountIxs(
+        connection,
+        payerKeypair.publicKey,
+        nonceKeypair.publicKey,
+        payerKeypair.publicKey,
+      )),
+    ),
+    [payerKeypair, nonceKeypair],
+  );
+
+  // Signed before the order exists, like a watchdog would hold it.
+  const client: ManifestClient = await ManifestClient.getClientForMarket(
+    connection,
+    marketAddress,
+    payerKeypair,
+  );
+  const serialized: string = preSignTransaction(
+    await client.cancelAllDurableNonceTx(nonceKeypair.publicKey),
+    [payerKeypair],
+  );
+  assert(
+    await isPreSignedTransactionCurrent(
+      connection,
+      serialized,
+      nonceKeypair.publicKey,
+    ),
+    'pre-signed cancel all should be current',
+  );
+
+  await placeOrder(
+    connection,
+    payerKeypair,
+    marketAddress,
+    5,
+    5,
+    false,
+    OrderType.Limit,
+    0,
+  );
+  await market.reload(connection);
+  assert(market.openOrders().length == 1, 'order was not placed');
+
+  const signature: string = await sendPreSignedTransaction(
+    connection,
+    serialized,
+  );
+  await connection.confirmTransaction(signature, 'confirmed');
+  await market.reload(connection);
+
+  assert(
+    market.openOrders().length == 0,
+    `cancel did not cancel all orders ${market.openOrders().length}`,
+  );
+  assert(
+    !(await isPreSignedTransactionCurrent(
+      connection,
+      serialized,
+      nonceKeypair.publicKey,
+    )),
+    'nonce should have advanced',
+  );
+}
+
+describeIfDirectTest(module, 'Durable nonce test', () => {
+  it('Cancel all with durable nonce', async () => {
+    await testDurableNonceCancelAll();
+  });
+});
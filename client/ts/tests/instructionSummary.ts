@@ -0,0 +1,62 @@
+import { Keypair, PublicKey, TransactionInstruction } from '@solana/web3.js';
+import { assert } from 'chai';
+import {
+  createBatchUpdateInstruction,
+  formatMantissaAndExponent,
+  OrderType,
+  summarizeInstruction,
+} from '../src';
+import { describeIfDirectTest } from './helpers/mocha';
+
+function testFormatMantissaAndExponent(): void {
+  assert.equal(formatMantissaAndExponent(15, -2), '0.15');
+  assert.equal(formatMantissaAndExponent(1500, -2), '15');
+  assert.equal(formatMantissaAndExponent(1, -9), '0.000000001');
+  assert.equal(formatMantissaAndExponent(12, 3), '12000');
+  assert.equal(formatMantissaAndExponent(0, 3), '0');
+}
+
+function testSummarizeBatchUpdate(): void {
+  const payer: PublicKey = Keypair.generate().publicKey;
+  const market: PublicKey = Keypair.generate().publicKey;
+  const ix: TransactionInstruction = createBatchUpdateInstruction(
+    { payer, market },
+    {
+      params: {
+        traderIndexHint: null,
+        cancels: [{ orderSequenceNumber: 3, orderIndexHint: null }],
+        orders: [
+          {
+            baseAtoms: 100,
+            priceMantissa: 5,
+            priceExponent: -1,
+            isBid: true,
+            lastValidSlot: 0,
+            orderType: OrderType.Limit,
+          },
+          {
+            baseAtoms: 200,
+            priceMantissa: 6,
+            priceExponent: -1,
+            isBid: false,
+            lastValidSlot: 0,
+            orderType: OrderType.PostOnly,
+          },
+        ],
+      },
+    },
+  );
+  assert.equal(
+    summarizeInstruction(ix),
+    `Manifest BatchUpdate market ${market.toBase58()}: 1 cancel, 2 places: BUY 100 @ 0.5 Limit, SELL 200 @ 0.6 PostOnly`,
+  );
+}
+
+describeIfDirectTest(module, 'Instruction summary test', () => {
+  it('Format price', async () => {
+    testFormatMantissaAndExponent();
+  });
+  it('Summarize batch update', async () => {
+    testSummarizeBatchUpdate();
+  });
+});
@@ -0,0 +1,82 @@
+import { assert } from 'chai';
+import {
+  FillLogResult,
+  PositionHistory,
+  positionHistoryToCsv,
+  reconstructPositionHistory,
+} from '../src';
+import { describeIfDirectTest } from './helpers/mocha';
+
+const MARKET: string = 'market';
+const TRADER: string = 'trader';
+const OTHER: string = 'other';
+
+function fill(
+  slot: number,
+  maker: string,
+  taker: string,
+  takerIsBuy: boolean,
+  baseAtoms: number,
+  quoteAtoms: number,
+): FillLogResult {
+  return {
+    market: MARKET,
+    maker,
+    taker,
+    baseAtoms: baseAtoms.toString(),
+    quoteAtoms: quoteAtoms.toString(),
+    priceAtoms: quoteAtoms / baseAtoms,
+    takerIsBuy,
+    isMakerGlobal: false,
+    makerSequenceNumber: '0',
+    takerSequenceNumber: '0',
+    slot,
+    signature: `sig${slot}`,
+  };
+}
+
+function testReconstructPositionHistory(): void {
+  const history: PositionHistory = reconstructPositionHistory(
+    [
+      // Buy 10 @ 10 as taker.
+      fill(1, OTHER, TRADER, true, 10, 100),
+      // Buy 10 @ 20 as maker.
+      fill(2, TRADER, OTHER, false, 10, 200),
+      // Not involved.
+      fill(3, OTHER, OTHER, true, 10, 100),
+      // Sell 15 @ 20, closes against an entry of 15.
+      fill(4, OTHER, TRADER, false, 15, 300),
+      // Sell 10 @ 10, closes the last 5 and opens 5 short.
+      fill(5, TRADER, OTHER, true, 10, 100),
+    ],
+    MARKET,
+    TRADER,
+  );
+
+  assert.equal(history.entries.length, 4);
+  assert.deepEqual(
+    history.entries.map((entry) => entry.positionBaseAtoms),
+    ['10', '20', '5', '-5'],
+  );
+  assert.deepEqual(
+    history.entries.map((entry) => entry.averageEntryPriceAtoms),
+    [10, 15, 15, 10],
+  );
+  assert.deepEqual(
+    history.entries.map((entry) => entry.realizedPnlQuoteAtoms),
+    ['0', '0', '75', '-25'],
+  );
+  assert.equal(history.entries[3].totalRealizedPnlQuoteAtoms, '50');
+  assert.isTrue(history.entries[1].isMaker);
+  assert.equal(history.entries[3].side, 'sell');
+
+  const csv: string[] = positionHistoryToCsv(history).trim().split('\n');
+  assert.equal(csv.length, 5);
+  assert.equal(csv[1], '1,sig1,,buy,false,10,100,10,10,10,0,0');
+}
+
+describeIfDirectTest(module, 'Position history test', () => {
+  it('Reconstruct position history', async () => {
+    testReconstructPositionHistory();
+  });
+});
@@ -0,0 +1,66 @@
+import { Connection, Keypair, PublicKey } from '@solana/web3.js';
+import { fetchMarketSnapshot, MarketSnapshot } from '../src/snapshot';
+import { createMarket } from './createMarket';
+import { deposit } from './deposit';
+import { Market } from '../src/market';
+import { assert } from 'chai';
+import { describeIfDirectTest } from './helpers/mocha';
+
+async function testFetchMarketSnapshot(): Promise<void> {
+  const connection: Connection = new Connection(
+    'http://127.0.0.1:8899',
+    'confirmed',
+  );
+  const payerKeypair: Keypair = Keypair.generate();
+
+  const marketAddress: PublicKey = await createMarket(connection, payerKeypair);
+  const market: Market = await Market.loadFromAddress({
+    connection,
+    address: marketAddress,
+  });
+  await deposit(connection, payerKeypair, marketAddress, market.baseMint(), 10);
+
+  const snapshot: MarketSnapshot = await fetchMarketSnapshot(
+    connection,
+    marketAddress,
+  );
+  assert(
+    snapshot.baseVault.amount ==
+      BigInt(10 * 10 ** snapshot.market.baseDecimals()),
+    `unexpected base vault amount ${snapshot.baseVault.amount}`,
+  );
+  assert(snapshot.quoteVault.amount == BigInt(0), 'quote vault not empty');
+
+  // Passing the mints skips the first read and gives the same accounts.
+  const secondSnapshot: MarketSnapshot = await fetchMarketSnapshot(
+    connection,
+    marketAddress,
+    {
+      minContextSlot: snapshot.slot,
+      baseMint: market.baseMint(),
+      quoteMint: market.quoteMint(),
+    },
+  );
+  assert(secondSnapshot.slot >= snapshot.slot, 'snapshot went back in time');
+  assert(
+    secondSnapshot.baseVault.amount == snapshot.baseVault.amount,
+    'base vault changed',
+  );
+
+  let threw: boolean = false;
+  try {
+    await fetchMarketSnapshot(connection, marketAddress, {
+      baseMint: market.quoteMint(),
+      quoteMint: market.baseMint(),
+    });
+  } catch {
+    threw = true;
+  }
+  assert(threw, 'wrong mints should be refused');
+}
+
+describeIfDirectTest(module, 'Market snapshot test', () => {
+  it('Fetch market snapshot', async () => {
+    await testFetchMarketSnapshot();
+  });
+});
@@ -0,0 +1,18 @@
+import { assert } from 'chai';
+import { applySlippage } from '../src';
+import { describeIfDirectTest } from './helpers/mocha';
+
+function testApplySlippage(): void {
+  assert.equal(applySlippage(10_000n, 0), 10_000n);
+  assert.equal(applySlippage(10_000n, 50), 9_950n);
+  // Rounds down so the bound is never looser than requested.
+  assert.equal(applySlippage(999n, 1), 998n);
+  assert.equal(applySlippage(10_000n, 10_000), 0n);
+  assert.throws(() => applySlippage(10_000n, 10_001));
+}
+
+describeIfDirectTest(module, 'Solana Pay test', () => {
+  it('Slippage', async () => {
+    testApplySlippage();
+  });
+});
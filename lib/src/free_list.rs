@@ -1,6 +1,6 @@
 use bytemuck::{Pod, Zeroable};
 
-use crate::{get_mut_helper, DataIndex, Get, NIL};
+use crate::{get_helper, get_mut_helper, DataIndex, Get, NIL};
 
 // FreeList is a linked list that keeps track of all the available nodes that
 // can be filled with ClaimedSeats and RestingOrders.
@@ -35,6 +35,38 @@ impl<T: Pod> FreeListNode<T> {
     }
 }
 
+/// Summary of a free list, for monitoring how full an account is.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct FreeListStats {
+    /// Number of nodes on the free list.
+    pub num_free: u32,
+    /// Lowest and highest index on the free list. NIL when it is empty.
+    pub lowest_index: DataIndex,
+    pub highest_index: DataIndex,
+}
+
+/// Walk a free list without mutating it. This is linear in the number of free
+/// nodes, so it is meant for clients and not for use onchain.
+pub fn get_free_list_stats<T: Pod>(data: &[u8], head_index: DataIndex) -> FreeListStats {
+    let mut stats: FreeListStats = FreeListStats {
+        num_free: 0,
+        lowest_index: NIL,
+        highest_index: NIL,
+    };
+    let mut current_index: DataIndex = head_index;
+    while current_index != END {
+        stats.num_free += 1;
+        stats.lowest_index = stats.lowest_index.min(current_index);
+        stats.highest_index = if stats.highest_index == NIL {
+            current_index
+        } else {
+            stats.highest_index.max(current_index)
+        };
+        current_index = get_helper::<FreeListNode<T>>(data, current_index).next_index;
+    }
+    stats
+}
+
 impl<'a, T: Pod> FreeList<'a, T> {
     /// Create a new free list. Assumes that the data within data is already a well
     /// formed FreeList.
@@ -59,6 +91,15 @@ impl<'a, T: Pod> FreeList<'a, T> {
         self.head_index = index;
     }
 
+    /// Free num_nodes consecutive nodes starting at start_index. They are
+    /// added highest first so that the lowest one is handed out first and the
+    /// used part of the account grows from the front.
+    pub fn add_run(&mut self, start_index: DataIndex, node_size: DataIndex, num_nodes: u32) {
+        for i in (0..num_nodes).rev() {
+            self.add(start_index + i * node_size);
+        }
+    }
+
     /// Free the node at index
     pub fn remove(&mut self) -> DataIndex {
         if self.head_index == END {
@@ -100,4 +141,34 @@ mod test {
         assert_eq!(64, free_list.remove());
         assert_eq!(END, free_list.remove());
     }
+
+    #[test]
+    fn test_free_list_run_and_stats() {
+        let mut data: [u8; 100000] = [0; 100000];
+        let mut free_list: FreeList<UnusedFreeListPadding1> = FreeList::new(&mut data, END);
+        free_list.add(640);
+        free_list.add_run(64, 64, 3);
+        let head: DataIndex = free_list.get_head();
+
+        assert_eq!(
+            get_free_list_stats::<UnusedFreeListPadding1>(free_list.data, head),
+            FreeListStats {
+                num_free: 4,
+                lowest_index: 64,
+                highest_index: 640,
+            }
+        );
+        assert_eq!(64, free_list.remove());
+        assert_eq!(128, free_list.remove());
+        assert_eq!(192, free_list.remove());
+        assert_eq!(640, free_list.remove());
+        assert_eq!(
+            get_free_list_stats::<UnusedFreeListPadding1>(&data, END),
+            FreeListStats {
+                num_free: 0,
+                lowest_index: NIL,
+                highest_index: NIL,
+            }
+        );
+    }
 }
@@ -1153,6 +1153,50 @@ impl<'a, V: Payload> HyperTreeWriteOperations<'a, V> for RedBlackTree<'a, V> {
 }
 
 impl<'a, V: Payload> RedBlackTree<'a, V> {
+    /// Insert next to a node that is expected to be adjacent to the new one,
+    /// such as the previous insert when inserting a sorted run of values.
+    /// Skips the descent from the root when the hint is right and falls back
+    /// to a normal insert otherwise. The resulting order is the same as
+    /// insert, including FIFO for equal values. The hint must be NIL or the
+    /// index of a node currently in this tree.
+    pub fn insert_with_hint(&mut self, index: DataIndex, value: V, hint_index: DataIndex) {
+        if self.root_index == NIL || hint_index == NIL {
+            return self.insert(index, value);
+        }
+        let parent_index: DataIndex = self.get_parent_index_from_hint(&value, hint_index);
+        if parent_index == NIL {
+            return self.insert(index, value);
+        }
+        trace!("TREE insert {index} hint {hint_index}");
+
+        let new_node: RBNode<V> = RBNode {
+            left: NIL,
+            right: NIL,
+            parent: NIL,
+            color: Color::Red,
+            value,
+            payload_type: 0,
+            _unused_padding: 0,
+        };
+
+        if self.max_index != NIL && *get_helper::<RBNode<V>>(self.data, self.max_index) < new_node {
+            self.max_index = index;
+        }
+
+        self.attach_node_no_fix(new_node, index, parent_index);
+
+        let mut node_to_fix: DataIndex = index;
+        loop {
+            node_to_fix = self.insert_fix(node_to_fix);
+            if node_to_fix == NIL {
+                break;
+            }
+        }
+
+        #[cfg(test)]
+        self.verify_rb_tree::<V>()
+    }
+
     /// Creates a new RedBlackTree. Does not mutate data yet. Assumes the actual
     /// data in data is already well formed as a red black tree.
     pub fn new(data: &'a mut [u8], root_index: DataIndex, max_index: DataIndex) -> Self {
@@ -1325,17 +1369,74 @@ impl<'a, V: Payload> RedBlackTree<'a, V> {
             }
         }
         // We ended at a leaf and need to add below.
-        if *self.get_node(current_parent_index) < node_to_insert {
-            self.set_right_index::<V>(current_parent_index, new_node_index);
+        self.attach_node_no_fix(node_to_insert, new_node_index, current_parent_index);
+    }
+
+    /// Put a new leaf below a parent that has an open slot on the side the
+    /// node belongs on.
+    fn attach_node_no_fix(
+        &mut self,
+        node_to_insert: RBNode<V>,
+        new_node_index: DataIndex,
+        parent_index: DataIndex,
+    ) {
+        if *self.get_node(parent_index) < node_to_insert {
+            self.set_right_index::<V>(parent_index, new_node_index);
         } else {
-            self.set_left_index::<V>(current_parent_index, new_node_index);
+            self.set_left_index::<V>(parent_index, new_node_index);
         }
 
         // Put the leaf in the tree and update its parent.
         {
             let new_node: &mut RBNode<V> = get_mut_helper::<RBNode<V>>(self.data, new_node_index);
             *new_node = node_to_insert;
-            new_node.parent = current_parent_index;
+            new_node.parent = parent_index;
+        }
+    }
+
+    /// Find the parent for a new node from a node that should be its in-order
+    /// neighbor, without walking down from the root. Returns NIL if the hint
+    /// is not adjacent to where the value belongs.
+    fn get_parent_index_from_hint(&self, value: &V, hint_index: DataIndex) -> DataIndex {
+        let hint: &RBNode<V> = get_helper::<RBNode<V>>(self.data, hint_index);
+        if hint.value < *value {
+            // Belongs right after the hint. Equal values go before existing
+            // ones like in a normal insert, so the successor may be equal.
+            let successor_index: DataIndex = if hint.right != NIL {
+                self.get_next_higher_index::<V>(hint_index)
+            } else {
+                // Successor is above, keep going up while we are the right child.
+                let mut current_index: DataIndex = hint_index;
+                loop {
+                    let parent_index: DataIndex = self.get_parent_index::<V>(current_index);
+                    if parent_index == NIL
+                        || self.get_left_index::<V>(parent_index) == current_index
+                    {
+                        break parent_index;
+                    }
+                    current_index = parent_index;
+                }
+            };
+            if successor_index != NIL && self.get_node::<V>(successor_index).value < *value {
+                return NIL;
+            }
+            if hint.right == NIL {
+                hint_index
+            } else {
+                // Successor is the leftmost node of the right subtree.
+                successor_index
+            }
+        } else {
+            let predecessor_index: DataIndex = self.get_next_lower_index::<V>(hint_index);
+            if predecessor_index != NIL && *value <= self.get_node::<V>(predecessor_index).value {
+                return NIL;
+            }
+            if hint.left == NIL {
+                hint_index
+            } else {
+                // Predecessor is the rightmost node of the left subtree.
+                predecessor_index
+            }
         }
     }
 
@@ -1703,6 +1804,72 @@ pub(crate) mod test {
         tree.verify_rb_tree::<TestOrderBid>();
     }
 
+    fn assert_same_order(hinted: &RedBlackTree<TestOrderBid>, plain: &RedBlackTree<TestOrderBid>) {
+        let hinted_indices: Vec<DataIndex> = hinted
+            .iter::<TestOrderBid>()
+            .map(|(index, _)| index)
+            .collect();
+        let plain_indices: Vec<DataIndex> = plain
+            .iter::<TestOrderBid>()
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(hinted_indices, plain_indices);
+        assert_eq!(hinted.get_max_index(), plain.get_max_index());
+    }
+
+    #[test]
+    fn test_insert_with_hint_ascending() {
+        let mut hinted_data: [u8; 100000] = [0; 100000];
+        let mut plain_data: [u8; 100000] = [0; 100000];
+        let mut hinted: RedBlackTree<TestOrderBid> = init_simple_tree(&mut hinted_data);
+        let mut plain: RedBlackTree<TestOrderBid> = init_simple_tree(&mut plain_data);
+
+        let mut hint_index: DataIndex = NIL;
+        for i in 12..32 {
+            let value: TestOrderBid = TestOrderBid::new(((i - 12) * 700 + 500).into());
+            hinted.insert_with_hint(TEST_BLOCK_WIDTH * i, value, hint_index);
+            plain.insert(TEST_BLOCK_WIDTH * i, value);
+            hint_index = TEST_BLOCK_WIDTH * i;
+        }
+        assert_same_order(&hinted, &plain);
+    }
+
+    #[test]
+    fn test_insert_with_hint_descending() {
+        let mut hinted_data: [u8; 100000] = [0; 100000];
+        let mut plain_data: [u8; 100000] = [0; 100000];
+        let mut hinted: RedBlackTree<TestOrderBid> = init_simple_tree(&mut hinted_data);
+        let mut plain: RedBlackTree<TestOrderBid> = init_simple_tree(&mut plain_data);
+
+        let mut hint_index: DataIndex = NIL;
+        for i in 12..32 {
+            let value: TestOrderBid = TestOrderBid::new(((32 - i) * 700).into());
+            hinted.insert_with_hint(TEST_BLOCK_WIDTH * i, value, hint_index);
+            plain.insert(TEST_BLOCK_WIDTH * i, value);
+            hint_index = TEST_BLOCK_WIDTH * i;
+        }
+        assert_same_order(&hinted, &plain);
+    }
+
+    #[test]
+    fn test_insert_with_hint_equal_and_wrong_hint() {
+        let mut hinted_data: [u8; 100000] = [0; 100000];
+        let mut plain_data: [u8; 100000] = [0; 100000];
+        let mut hinted: RedBlackTree<TestOrderBid> = init_simple_tree(&mut hinted_data);
+        let mut plain: RedBlackTree<TestOrderBid> = init_simple_tree(&mut plain_data);
+
+        // Equal to existing values, then far from the hint so it falls back.
+        let values: [u64; 6] = [5_000, 5_000, 6_000, 1_000, 11_000, 3_500];
+        let mut hint_index: DataIndex = TEST_BLOCK_WIDTH * 5;
+        for (i, value) in values.iter().enumerate() {
+            let index: DataIndex = TEST_BLOCK_WIDTH * (12 + i as DataIndex);
+            hinted.insert_with_hint(index, TestOrderBid::new(*value), hint_index);
+            plain.insert(index, TestOrderBid::new(*value));
+            hint_index = index;
+        }
+        assert_same_order(&hinted, &plain);
+    }
+
     #[test]
     fn test_max() {
         let mut data: [u8; 100000] = [0; 100000];
@@ -35,6 +35,32 @@ impl From<bool> for PodBool {
     }
 }
 
+/// Serialized as a plain bool.
+#[cfg(feature = "serde")]
+impl serde::Serialize for PodBool {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bool(self.0 == 1)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PodBool {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <bool as serde::Deserialize>::deserialize(deserializer).map(PodBool::from_bool)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl schemars::JsonSchema for PodBool {
+    fn schema_name() -> String {
+        bool::schema_name()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        bool::json_schema(gen)
+    }
+}
+
 #[test]
 fn test_pod_bool() {
     assert_eq!(PodBool::from_bool(false).0 == 1, false);
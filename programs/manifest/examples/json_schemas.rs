@@ -0,0 +1,26 @@
+//! Writes the JSON schema of every serializable program type to
+//! <name>.json in the directory given as the first argument, and the
+//! discriminant, version and size of every log to logs.json.
+//!
+//! cargo run -p manifest-dex --features serde --example json_schemas -- schemas
+
+use std::{fs, path::PathBuf};
+
+fn main() -> anyhow::Result<()> {
+    let out_dir: PathBuf = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("schemas"));
+    fs::create_dir_all(&out_dir)?;
+    for (name, schema) in manifest::schema::json_schemas() {
+        fs::write(
+            out_dir.join(format!("{}.json", name)),
+            serde_json::to_string_pretty(&schema)? + "\n",
+        )?;
+    }
+    fs::write(
+        out_dir.join("logs.json"),
+        serde_json::to_string_pretty(&manifest::schema::log_schemas())? + "\n",
+    )?;
+    Ok(())
+}
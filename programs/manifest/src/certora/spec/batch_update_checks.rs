@@ -8,6 +8,7 @@ use vectors::{cvt_no_resizable_vec, no_resizable_vec::NoResizableVec};
 
 use crate::{
     program::{batch_update::*, get_mut_dynamic_account},
+    quantities::BaseAtoms,
     state::*,
     *,
 };
@@ -80,7 +81,25 @@ pub fn rule_integrity_of_batch_update_cancel<const IS_BID: bool>() {
 
     let program_id: &Pubkey = &crate::id();
     // Important: by passing only three accounts, we won't have global trade accounts
-    process_batch_update_core(&program_id, &used_acc_infos, params).unwrap();
+    process_batch_update_core(
+        &program_id,
+        &used_acc_infos,
+        params,
+        false,
+        false,
+        false,
+        &[],
+        &[],
+        BaseAtoms::ZERO,
+        BaseAtoms::ZERO,
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+    )
+    .unwrap();
 
     cvt_assert!(last_called_cancel_order());
     cvt_vacuity_check!();
@@ -118,7 +137,25 @@ pub fn rule_integrity_of_batch_update_cancel_hint<const IS_BID: bool>() {
 
     let program_id: &Pubkey = &crate::id();
     // Important: by passing only three accounts, we won't have global trade accounts
-    process_batch_update_core(&program_id, &used_acc_infos, params).unwrap();
+    process_batch_update_core(
+        &program_id,
+        &used_acc_infos,
+        params,
+        false,
+        false,
+        false,
+        &[],
+        &[],
+        BaseAtoms::ZERO,
+        BaseAtoms::ZERO,
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+    )
+    .unwrap();
 
     cvt_assert!(last_called_cancel_order_by_index());
     // Our mocks produce always aligned order indexes
@@ -148,7 +185,25 @@ pub fn rule_integrity_of_batch_update_place_order<const IS_BID: bool>() {
 
     let program_id: &Pubkey = &crate::id();
     // Important: by passing only three accounts, we won't have global trade accounts
-    process_batch_update_core(&program_id, &used_acc_infos, params).unwrap();
+    process_batch_update_core(
+        &program_id,
+        &used_acc_infos,
+        params,
+        false,
+        false,
+        false,
+        &[],
+        &[],
+        BaseAtoms::ZERO,
+        BaseAtoms::ZERO,
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+        &[],
+    )
+    .unwrap();
 
     cvt_assert!(last_called_place_order());
     cvt_vacuity_check!();
@@ -14,7 +14,7 @@ use crate::{
     quantities::{BaseAtoms, QuoteAtoms},
     state::{get_helper_order, AddOrderToMarketArgs, DynamicAccount, MarketRefMut, RestingOrder},
 };
-use hypertree::DataIndex;
+use hypertree::{DataIndex, NIL};
 
 use crate::certora::spec::no_funds_loss_util::*;
 use state::{main_trader_index, second_trader_index};
@@ -280,6 +280,14 @@ fn rest_remaining_check<const IS_BID: bool>() {
         order_type: state::OrderType::Limit,
         global_trade_accounts_opts: &[None, None],
         current_slot: Some(nondet()),
+        fill_memo: false,
+        insert_hint_index: NIL,
+        min_fill_atoms: BaseAtoms::ZERO,
+        min_taker_atoms: BaseAtoms::ZERO,
+        tag: 0,
+        skip_global_orders: false,
+        self_trade_prevention: state::SelfTradePrevention::None,
+        referred: false,
     };
 
     let remaining_base_atoms_arg: BaseAtoms = nondet();
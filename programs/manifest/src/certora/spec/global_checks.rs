@@ -41,7 +41,7 @@ use crate::{
     },
     validation::loaders::GlobalTradeAccounts,
 };
-use hypertree::DataIndex;
+use hypertree::{DataIndex, NIL};
 use solana_cvt::token::spl_token_account_get_amount;
 
 /// The market vault on the side the global maker backs their order with. A
@@ -365,6 +365,14 @@ pub fn rest_remaining_global_check<const IS_BID: bool>() {
         order_type: state::OrderType::Global,
         global_trade_accounts_opts: &global_trade_accounts_opts,
         current_slot: Some(nondet()),
+        fill_memo: false,
+        insert_hint_index: NIL,
+        min_fill_atoms: BaseAtoms::ZERO,
+        min_taker_atoms: BaseAtoms::ZERO,
+        tag: 0,
+        skip_global_orders: false,
+        self_trade_prevention: state::SelfTradePrevention::None,
+        referred: false,
     };
 
     rest_remaining!(
@@ -367,7 +367,7 @@ pub fn cvt_assume_reverse_coalesce_preconditions<const IS_BID: bool, const IS_TI
 
     // -- the price the maker comes back at. Deterministic, so the same value
     // -- is recomputed inside the matching code.
-    let price_reverse: QuoteAtomsPerBaseAtom = maker_order.reverse_price().unwrap();
+    let price_reverse: QuoteAtomsPerBaseAtom = maker_order.reverse_price(0).unwrap();
 
     // -- the maker's resting order on the taker side sits within one price
     // -- increment of that price, so RestingOrder::eq matches and the
@@ -32,10 +32,11 @@ use crate::{
         get_helper_order, main_trader_index,
         market::market_helpers::{AddOrderToMarketInnerResult, AddSingleOrderCtx},
         AddOrderToMarketArgs, DynamicAccount, MarketRefMut, OrderType, RestingOrder,
+        SelfTradePrevention,
     },
     validation::loaders::GlobalTradeAccounts,
 };
-use hypertree::DataIndex;
+use hypertree::{DataIndex, NIL};
 
 /// The arithmetic a trade performs must not overflow.
 ///
@@ -108,7 +109,7 @@ fn cvt_assume_coalesce_arithmetic_does_not_overflow<const IS_BID: bool>(
             .get_price()
             .checked_quote_for_base(maker_base, true);
         cvt_assume!(maker_max_quote_or.is_ok());
-        let price_reverse: QuoteAtomsPerBaseAtom = maker_order.reverse_price().unwrap();
+        let price_reverse: QuoteAtomsPerBaseAtom = maker_order.reverse_price(0).unwrap();
         let max_reverse_or: Result<BaseAtoms, _> =
             price_reverse.checked_base_for_quote(maker_max_quote_or.unwrap(), false);
         cvt_assume!(max_reverse_or.is_ok());
@@ -330,6 +331,14 @@ pub fn rest_remaining_global_no_revert_check<const IS_BID: bool>() {
         order_type: OrderType::Global,
         global_trade_accounts_opts: &global_trade_accounts_opts,
         current_slot: Some(nondet()),
+        fill_memo: false,
+        insert_hint_index: NIL,
+        min_fill_atoms: BaseAtoms::ZERO,
+        min_taker_atoms: BaseAtoms::ZERO,
+        tag: 0,
+        skip_global_orders: false,
+        self_trade_prevention: SelfTradePrevention::None,
+        referred: false,
     };
 
     let market_data: &mut std::cell::RefMut<&mut [u8]> =
@@ -17,7 +17,7 @@ use crate::{
     },
     validation::loaders::GlobalTradeAccounts,
 };
-use hypertree::DataIndex;
+use hypertree::{DataIndex, NIL};
 
 pub fn place_single_order_nondet_inputs<const IS_BID: bool>(
     market_info: &AccountInfo,
@@ -46,6 +46,14 @@ pub fn place_single_order_nondet_inputs_with_type<'a, const IS_BID: bool>(
         order_type,
         global_trade_accounts_opts,
         current_slot: Some(nondet()),
+        fill_memo: false,
+        insert_hint_index: NIL,
+        min_fill_atoms: BaseAtoms::ZERO,
+        min_taker_atoms: BaseAtoms::ZERO,
+        tag: 0,
+        skip_global_orders: false,
+        self_trade_prevention: state::SelfTradePrevention::None,
+        referred: false,
     };
     let remaining_base_atoms: BaseAtoms = nondet();
     let now_slot: u32 = nondet();
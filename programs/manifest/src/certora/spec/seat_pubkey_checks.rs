@@ -45,7 +45,7 @@ use crate::{
         MarketRefMut,
     },
 };
-use hypertree::{get_mut_helper, DataIndex};
+use hypertree::{get_mut_helper, DataIndex, NIL};
 
 /// The pubkeys currently stored in the two seat nodes.
 fn record_seat_pubkeys() -> (Pubkey, Pubkey) {
@@ -283,6 +283,14 @@ fn seat_pubkey_preserved_by_rest_remaining_check<const IS_BID: bool>() {
         order_type: state::OrderType::Limit,
         global_trade_accounts_opts: &[None, None],
         current_slot: Some(nondet()),
+        fill_memo: false,
+        insert_hint_index: NIL,
+        min_fill_atoms: BaseAtoms::ZERO,
+        min_taker_atoms: BaseAtoms::ZERO,
+        tag: 0,
+        skip_global_orders: false,
+        self_trade_prevention: state::SelfTradePrevention::None,
+        referred: false,
     };
 
     rest_remaining!(
@@ -542,6 +550,14 @@ fn seat_pubkey_preserved_by_rest_remaining_global_check<const IS_BID: bool>() {
         order_type: state::OrderType::Global,
         global_trade_accounts_opts: &global_trade_accounts_opts,
         current_slot: Some(nondet()),
+        fill_memo: false,
+        insert_hint_index: NIL,
+        min_fill_atoms: BaseAtoms::ZERO,
+        min_taker_atoms: BaseAtoms::ZERO,
+        tag: 0,
+        skip_global_orders: false,
+        self_trade_prevention: state::SelfTradePrevention::None,
+        referred: false,
     };
 
     rest_remaining!(
@@ -13,6 +13,7 @@ use solana_cvt::token::spl_token_account_get_amount;
 
 use crate::{
     program::{process_swap_core, SwapParams},
+    quantities::BaseAtoms,
     state::MarketFixed,
 };
 use hypertree::get_mut_helper;
@@ -40,7 +41,15 @@ pub fn rule_integrity_swap() {
     let trader_base_amount_old: u64 = spl_token_account_get_amount(trader_base_info);
     let trader_quote_amount_old: u64 = spl_token_account_get_amount(trader_quote_info);
 
-    process_swap_core(&crate::id(), &used_acc_infos, params).unwrap();
+    process_swap_core(
+        &crate::id(),
+        &used_acc_infos,
+        params,
+        BaseAtoms::ZERO,
+        false,
+        false,
+    )
+    .unwrap();
 
     let trader_base_amount: u64 = spl_token_account_get_amount(trader_base_info);
     let trader_quote_amount: u64 = spl_token_account_get_amount(trader_quote_info);
@@ -113,7 +122,15 @@ fn rule_swap_check<const IS_BASE: bool, const IS_EXACT: bool>() {
     }
 
     let params: SwapParams = SwapParams::new(in_atoms, out_atoms, IS_BASE, IS_EXACT);
-    process_swap_core(&crate::id(), &used_acc_infos, params).unwrap();
+    process_swap_core(
+        &crate::id(),
+        &used_acc_infos,
+        params,
+        BaseAtoms::ZERO,
+        false,
+        false,
+    )
+    .unwrap();
 
     let new_balances = record_all_balances_without_order(
         market,
@@ -4,6 +4,8 @@
 pub mod logs;
 pub mod program;
 pub mod quantities;
+#[cfg(feature = "serde")]
+pub mod schema;
 pub mod state;
 pub mod utils;
 pub mod validation;
@@ -18,12 +20,44 @@ pub mod certora;
 
 use hypertree::trace;
 use program::{
-    batch_update::process_batch_update, claim_seat::process_claim_seat,
-    create_market::process_create_market, deposit::process_deposit,
-    expand_market::process_expand_market, global_add_trader::process_global_add_trader,
-    global_clean::process_global_clean, global_create::process_global_create,
-    global_deposit::process_global_deposit, global_evict::process_global_evict,
-    global_withdraw::process_global_withdraw, process_swap, withdraw::process_withdraw,
+    archive_seat::process_archive_seat,
+    batch_update::process_batch_update,
+    bust_trade::process_bust_trade,
+    claim_fees::process_claim_fees,
+    claim_points::process_claim_points,
+    claim_seat::process_claim_seat,
+    commit_swap::process_commit_swap,
+    create_market::process_create_market,
+    deposit::{process_deposit, process_deposit_idempotent},
+    emit_depth_snapshot::process_emit_depth_snapshot,
+    expand_market::process_expand_market,
+    fill_rfq::{process_fill_rfq, process_post_rfq},
+    fill_signed_order::{process_cancel_signed_orders, process_fill_signed_order},
+    get_market_prices::process_get_market_prices,
+    get_seat_points::process_get_seat_points,
+    global_add_trader::process_global_add_trader,
+    global_clean::process_global_clean,
+    global_create::process_global_create,
+    global_deposit::process_global_deposit,
+    global_evict::process_global_evict,
+    global_withdraw::process_global_withdraw,
+    heartbeat::{process_cancel_on_heartbeat_timeout, process_heartbeat},
+    pegged_order::{
+        process_cancel_pegged_order, process_place_pegged_order, process_reprice_pegged_orders,
+    },
+    process_reveal_swap, process_swap, process_swap_to_seat,
+    rebalance::process_rebalance,
+    recovery::{process_enter_recovery, process_recovery_withdraw},
+    reset_price_band::process_reset_price_band,
+    restore_seat::process_restore_seat,
+    set_mmp::process_set_mmp,
+    set_paused::process_set_paused,
+    set_seat_config::process_set_seat_config,
+    stop_order::{
+        process_cancel_stop_order, process_place_stop_order, process_trigger_stop_orders,
+    },
+    update_volatility::process_update_volatility,
+    withdraw::{process_withdraw, process_withdraw_as_quote},
     ManifestInstruction,
 };
 use solana_program::{
@@ -155,6 +189,105 @@ pub fn process_instruction(
         ManifestInstruction::GlobalClean => {
             process_global_clean(program_id, accounts, data)?;
         }
+        ManifestInstruction::SetSeatConfig => {
+            process_set_seat_config(program_id, accounts, data)?;
+        }
+        ManifestInstruction::DepositIdempotent => {
+            process_deposit_idempotent(program_id, accounts, data)?;
+        }
+        ManifestInstruction::SwapToSeat => {
+            process_swap_to_seat(program_id, accounts, data)?;
+        }
+        ManifestInstruction::FillRfq => {
+            process_fill_rfq(program_id, accounts, data)?;
+        }
+        ManifestInstruction::ArchiveSeat => {
+            process_archive_seat(program_id, accounts, data)?;
+        }
+        ManifestInstruction::RestoreSeat => {
+            process_restore_seat(program_id, accounts, data)?;
+        }
+        ManifestInstruction::CommitSwap => {
+            process_commit_swap(program_id, accounts, data)?;
+        }
+        ManifestInstruction::RevealSwap => {
+            process_reveal_swap(program_id, accounts, data)?;
+        }
+        ManifestInstruction::GetMarketPrices => {
+            process_get_market_prices(program_id, accounts, data)?;
+        }
+        ManifestInstruction::EmitDepthSnapshot => {
+            process_emit_depth_snapshot(program_id, accounts, data)?;
+        }
+        ManifestInstruction::ClaimPoints => {
+            process_claim_points(program_id, accounts, data)?;
+        }
+        ManifestInstruction::GetSeatPoints => {
+            process_get_seat_points(program_id, accounts, data)?;
+        }
+        ManifestInstruction::PlaceStopOrder => {
+            process_place_stop_order(program_id, accounts, data)?;
+        }
+        ManifestInstruction::CancelStopOrder => {
+            process_cancel_stop_order(program_id, accounts, data)?;
+        }
+        ManifestInstruction::TriggerStopOrders => {
+            process_trigger_stop_orders(program_id, accounts, data)?;
+        }
+        ManifestInstruction::WithdrawAsQuote => {
+            process_withdraw_as_quote(program_id, accounts, data)?;
+        }
+        ManifestInstruction::Heartbeat => {
+            process_heartbeat(program_id, accounts, data)?;
+        }
+        ManifestInstruction::CancelOnHeartbeatTimeout => {
+            process_cancel_on_heartbeat_timeout(program_id, accounts, data)?;
+        }
+        ManifestInstruction::Rebalance => {
+            process_rebalance(program_id, accounts, data)?;
+        }
+        ManifestInstruction::UpdateVolatility => {
+            process_update_volatility(program_id, accounts, data)?;
+        }
+        ManifestInstruction::EnterRecovery => {
+            process_enter_recovery(program_id, accounts, data)?;
+        }
+        ManifestInstruction::RecoveryWithdraw => {
+            process_recovery_withdraw(program_id, accounts, data)?;
+        }
+        ManifestInstruction::ResetPriceBand => {
+            process_reset_price_band(program_id, accounts, data)?;
+        }
+        ManifestInstruction::ClaimFees => {
+            process_claim_fees(program_id, accounts, data)?;
+        }
+        ManifestInstruction::BustTrade => {
+            process_bust_trade(program_id, accounts, data)?;
+        }
+        ManifestInstruction::FillSignedOrder => {
+            process_fill_signed_order(program_id, accounts, data)?;
+        }
+        ManifestInstruction::CancelSignedOrders => {
+            process_cancel_signed_orders(program_id, accounts, data)?;
+        }
+        ManifestInstruction::SetMmp => {
+            process_set_mmp(program_id, accounts, data)?;
+        }
+        ManifestInstruction::SetPaused => {
+            process_set_paused(program_id, accounts, data)?;
+        }
+        ManifestInstruction::PostRfq => {
+            process_post_rfq(program_id, accounts, data)?;
+        }
+        ManifestInstruction::PlacePeggedOrder => {
+            process_place_pegged_order(program_id, accounts, data)?;
+        }
+        ManifestInstruction::CancelPeggedOrder => {
+            process_cancel_pegged_order(program_id, accounts, data)?;
+        }
+        ManifestInstruction::RepricePeggedOrders => {
+            process_reprice_pegged_orders(program_id, accounts, data)?;
+        }
     }
 
     Ok(())
@@ -1,11 +1,11 @@
 use bytemuck::{Pod, Zeroable};
 use hypertree::PodBool;
-use shank::ShankAccount;
+use shank::{ShankAccount, ShankType};
 use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
 use crate::{
     quantities::{BaseAtoms, GlobalAtoms, QuoteAtoms, QuoteAtomsPerBaseAtom},
-    state::OrderType,
+    state::{constants::DEPTH_SNAPSHOT_MAX_LEVELS, OrderType},
 };
 
 /// Serialize and log an event
@@ -16,15 +16,23 @@ use crate::{
 /// because the goal of this program is to minimize the number of input
 /// accounts, so including the signer for the self CPI is not worth it.
 /// Also, be compatible with anchor parsing clients.
+///
+/// The log is the discriminant, the struct and then its version byte. Logs
+/// from before versions were added end without it and have version 0, which
+/// has the same fields as version 1. New fields only ever go at the end of a
+/// struct with a new version, so parsers that read the fields they know from
+/// the start keep working.
 #[cfg(not(feature = "certora"))]
 #[inline(never)] // ensure fresh stack frame
 pub fn emit_stack<T: bytemuck::Pod + Discriminant>(e: T) -> Result<(), ProgramError> {
     // stack buffer, stack frames are 4kb
     let mut buffer: [u8; 3000] = [0u8; 3000];
+    let size: usize = std::mem::size_of::<T>();
     buffer[..8].copy_from_slice(&T::discriminant());
-    *bytemuck::from_bytes_mut::<T>(&mut buffer[8..8 + std::mem::size_of::<T>()]) = e;
+    *bytemuck::from_bytes_mut::<T>(&mut buffer[8..8 + size]) = e;
+    buffer[8 + size] = T::version();
 
-    solana_program::log::sol_log_data(&[&buffer[..(std::mem::size_of::<T>() + 8)]]);
+    solana_program::log::sol_log_data(&[&buffer[..(size + 9)]]);
     Ok(())
 }
 
@@ -34,47 +42,183 @@ pub fn emit_stack<T: bytemuck::Pod + Discriminant>(_e: T) -> Result<(), ProgramE
     Ok(())
 }
 
+/// Send a short text description of a fill to the SPL memo program. Wallets
+/// and explorers already index memos, so this lets makers get notified of
+/// fills without running an indexer for the binary FillLog. Only valid when
+/// the memo program is one of the accounts of the current instruction.
+#[cfg(not(feature = "certora"))]
+pub fn emit_fill_memo(fill: &FillLog) -> Result<(), ProgramError> {
+    let memo: String =
+        format!(
+        "manifest fill market:{} maker:{} maker_side:{} base_atoms:{} quote_atoms:{} maker_seq:{}",
+        fill.market,
+        fill.maker,
+        if fill.taker_is_buy.0 == 1 { "sell" } else { "buy" },
+        fill.base_atoms,
+        fill.quote_atoms,
+        fill.maker_sequence_number,
+    );
+    crate::program::invoke(
+        &solana_program::instruction::Instruction {
+            program_id: crate::validation::SPL_MEMO_PROGRAM_ID,
+            accounts: vec![],
+            data: memo.into_bytes(),
+        },
+        &[],
+    )
+}
+
+#[cfg(feature = "certora")]
+pub fn emit_fill_memo(_fill: &FillLog) -> Result<(), ProgramError> {
+    Ok(())
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct CreateMarketLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub creator: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub base_mint: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub quote_mint: Pubkey,
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct ClaimSeatLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub trader: Pubkey,
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct DepositLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub trader: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub mint: Pubkey,
     pub amount_atoms: u64,
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct WithdrawLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub trader: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub mint: Pubkey,
     pub amount_atoms: u64,
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct FillLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub maker: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub taker: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub base_mint: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub quote_mint: Pubkey,
     pub price: QuoteAtomsPerBaseAtom,
     pub base_atoms: BaseAtoms,
@@ -83,13 +227,39 @@ pub struct FillLog {
     pub taker_sequence_number: u64,
     pub taker_is_buy: PodBool,
     pub is_maker_global: PodBool,
-    pub _padding: [u8; 14],
+    /// Maker and taker are the same seat. Lets analytics leave wash volume
+    /// out.
+    pub self_trade: PodBool,
+    /// The taker's swap paid the referral share of the taker fee to a
+    /// referrer. Added in version 3, false in earlier logs.
+    pub referred: PodBool,
+    pub maker_tag: u32,
+    pub taker_tag: u32,
+    /// Taker fee rate charged on the fill, after any fee tier discount. Added
+    /// in version 2, zero in earlier logs.
+    pub taker_fee_bps: u16,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding2: [u8; 2],
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct PlaceOrderLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub trader: Pubkey,
     pub price: QuoteAtomsPerBaseAtom,
     pub base_atoms: BaseAtoms,
@@ -98,14 +268,34 @@ pub struct PlaceOrderLog {
     pub last_valid_slot: u32,
     pub order_type: OrderType,
     pub is_bid: PodBool,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub _padding: [u8; 6],
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct PlaceOrderLogV2 {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub trader: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub payer: Pubkey,
     pub price: QuoteAtomsPerBaseAtom,
     pub base_atoms: BaseAtoms,
@@ -114,59 +304,193 @@ pub struct PlaceOrderLogV2 {
     pub last_valid_slot: u32,
     pub order_type: OrderType,
     pub is_bid: PodBool,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub _padding: [u8; 6],
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct CancelOrderLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub trader: Pubkey,
+    pub order_sequence_number: u64,
+}
+
+/// A resting order changed in place by a batch update. The sequence number is
+/// only new when the order lost its priority.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct ReplaceOrderLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub trader: Pubkey,
+    pub price: QuoteAtomsPerBaseAtom,
+    pub base_atoms: BaseAtoms,
+    pub old_order_sequence_number: u64,
     pub order_sequence_number: u64,
+    pub order_index: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding: [u8; 4],
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct GlobalCreateLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub global: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub creator: Pubkey,
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct GlobalAddTraderLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub global: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub trader: Pubkey,
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct GlobalClaimSeatLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub global: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub trader: Pubkey,
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct GlobalDepositLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub global: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub trader: Pubkey,
     pub global_atoms: GlobalAtoms,
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct GlobalWithdrawLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub global: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub trader: Pubkey,
     pub global_atoms: GlobalAtoms,
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct GlobalEvictLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub evictor: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub evictee: Pubkey,
     pub evictor_atoms: GlobalAtoms,
     pub evictee_atoms: GlobalAtoms,
@@ -174,23 +498,447 @@ pub struct GlobalEvictLog {
 
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct GlobalCleanupLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub cleaner: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
     pub maker: Pubkey,
     pub amount_desired: GlobalAtoms,
     pub amount_deposited: GlobalAtoms,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct ArchiveSeatLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub trader: Pubkey,
+    pub base_atoms: BaseAtoms,
+    pub quote_atoms: QuoteAtoms,
+    pub quote_volume: QuoteAtoms,
+    pub leaf_index: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct RestoreSeatLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub trader: Pubkey,
+    pub leaf_index: u64,
+}
+
+/// Points of a finished epoch claimed by a seat.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct PointsClaimLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub trader: Pubkey,
+    pub points: u64,
+    pub epoch: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding: [u8; 4],
+}
+
+/// Stop order placed by TriggerStopOrders. The order itself is in the
+/// PlaceOrderLog before it, with the same order_sequence_number.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct StopOrderTriggeredLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub trader: Pubkey,
+    pub trigger_price: QuoteAtomsPerBaseAtom,
+    pub order_sequence_number: u64,
+    pub stop_index: u8,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding: [u8; 7],
+}
+
+/// Accrued fees of one mint paid out by ClaimFees.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct ClaimFeesLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub recipient: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub mint: Pubkey,
+    pub amount_atoms: u64,
+}
+
+/// Fill reversed by BustTrade. The fill itself is in its FillLog, with the
+/// same sequence numbers. The fee atoms are what was refunded to each side.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct TradeBustLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub authority: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub maker: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub taker: Pubkey,
+    pub base_atoms: BaseAtoms,
+    pub quote_atoms: QuoteAtoms,
+    pub maker_fee_atoms: u64,
+    pub taker_fee_atoms: u64,
+    pub maker_sequence_number: u64,
+    pub taker_sequence_number: u64,
+    pub taker_is_buy: PodBool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding: [u8; 7],
+}
+
+/// Fees of one fill on a market with MarketFees, emitted after its FillLog
+/// and matched to it by the sequence numbers. The maker fee is in the mint the
+/// maker received and all goes to the protocol. The taker fee is in the other
+/// mint and splits into the referral share, paid out when the swap includes
+/// a referrer (see ReferralFeeLog) and kept by the protocol otherwise, and
+/// the protocol share.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct FeeLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub maker: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub taker: Pubkey,
+    pub maker_sequence_number: u64,
+    pub taker_sequence_number: u64,
+    pub maker_fee_atoms: u64,
+    pub taker_fee_atoms: u64,
+    /// Referral share of the taker fee.
+    pub referral_fee_atoms: u64,
+    /// Protocol share of the taker fee, the taker fee less the referral
+    /// share.
+    pub protocol_fee_atoms: u64,
+    /// The taker bought base, so its fee is in base and the maker's in quote.
+    pub taker_is_buy: PodBool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding: [u8; 7],
+}
+
+/// Share of the taker fee of a swap paid to the referrer token account it
+/// included. referrer is the owner of that token account.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct ReferralFeeLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub referrer: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub mint: Pubkey,
+    pub amount_atoms: u64,
+}
+
+/// Market maker protection of a seat tripped during matching. Each order it
+/// pulled has a CancelOrderLog before this one. The window is what tripped
+/// it.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct MmpTriggeredLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub trader: Pubkey,
+    pub window_fills: u32,
+    pub num_orders_pulled: u32,
+    pub window_quote_atoms: QuoteAtoms,
+}
+
+/// Market paused or resumed by its pauser with SetPaused.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct MarketPausedLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub pauser: Pubkey,
+    pub paused: PodBool,
+    pub _padding: [u8; 7],
+}
+
+/// Request for quote posted, replaced or cancelled with PostRfq. Makers sign
+/// an RfqQuote for rfq and sequence_number to answer it. Zero base_atoms is a
+/// cancel.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct RfqLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub taker: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub rfq: Pubkey,
+    pub base_atoms: BaseAtoms,
+    pub sequence_number: u64,
+    pub last_valid_slot: u32,
+    pub taker_is_buy: PodBool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding: [u8; 3],
+}
+
+/// Pegged order placed or moved by RepricePeggedOrders. The order itself is in
+/// the PlaceOrderLog before it, with the same order_sequence_number, and the
+/// order it replaced in the CancelOrderLog before that.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct PeggedOrderPlacedLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub trader: Pubkey,
+    pub order_sequence_number: u64,
+    pub pegged_index: u8,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding: [u8; 7],
+}
+
+/// Total size of the orders at one price.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankType)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct DepthLevel {
+    pub price: QuoteAtomsPerBaseAtom,
+    pub base_atoms: BaseAtoms,
+}
+
+/// Best price levels of both sides, best first. Only the first num_bids and
+/// num_asks levels are set, the rest are zero.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct DepthSnapshotLog {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    pub slot: u32,
+    pub num_bids: u8,
+    pub num_asks: u8,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding: [u8; 2],
+    pub bids: [DepthLevel; DEPTH_SNAPSHOT_MAX_LEVELS],
+    pub asks: [DepthLevel; DEPTH_SNAPSHOT_MAX_LEVELS],
+}
+
 pub trait Discriminant {
     fn discriminant() -> [u8; 8];
+    /// Layout version, logged after the struct. Bumped when fields are
+    /// appended.
+    fn version() -> u8;
 }
 
 macro_rules! discriminant {
     ($type_name:ident, $value:ident, $test_name:ident) => {
+        discriminant!($type_name, $value, 1, $test_name);
+    };
+    ($type_name:ident, $value:ident, $version:literal, $test_name:ident) => {
         impl Discriminant for $type_name {
             fn discriminant() -> [u8; 8] {
                 $value
             }
+            fn version() -> u8 {
+                $version
+            }
         }
 
         #[test]
@@ -199,6 +947,7 @@ macro_rules! discriminant {
             let discriminant: u64 = crate::utils::get_discriminant::<$type_name>().unwrap();
             buffer[..8].copy_from_slice(&u64::to_le_bytes(discriminant));
             assert_eq!(buffer, $type_name::discriminant());
+            assert!($type_name::version() >= 1);
         }
     };
 }
@@ -208,6 +957,7 @@ const CLAIM_SEAT_LOG_DISCRIMINANT: [u8; 8] = [129, 77, 152, 210, 218, 144, 163,
 const DEPOSIT_LOG_DISCRIMINANT: [u8; 8] = [23, 214, 24, 34, 52, 104, 109, 188];
 const WITHDRAW_LOG_DISCRIMINANT: [u8; 8] = [112, 218, 111, 63, 18, 95, 136, 35];
 const FILL_LOG_DISCRIMINANT: [u8; 8] = [58, 230, 242, 3, 75, 113, 4, 169];
+const FEE_LOG_DISCRIMINANT: [u8; 8] = [215, 75, 152, 91, 245, 41, 203, 95];
 const PLACE_ORDER_LOG_DISCRIMINANT: [u8; 8] = [157, 118, 247, 213, 47, 19, 164, 120];
 const PLACE_ORDER_LOG_V2_DISCRIMINANT: [u8; 8] = [189, 97, 159, 235, 136, 5, 1, 141];
 const CANCEL_ORDER_LOG_DISCRIMINANT: [u8; 8] = [22, 65, 71, 33, 244, 235, 255, 215];
@@ -218,6 +968,19 @@ const GLOBAL_DEPOSIT_LOG_DISCRIMINANT: [u8; 8] = [16, 26, 72, 1, 145, 232, 182,
 const GLOBAL_WITHDRAW_LOG_DISCRIMINANT: [u8; 8] = [206, 118, 67, 64, 124, 109, 157, 201];
 const GLOBAL_EVICT_LOG_DISCRIMINANT: [u8; 8] = [250, 180, 155, 38, 98, 223, 82, 223];
 const GLOBAL_CLEANUP_LOG_DISCRIMINANT: [u8; 8] = [193, 249, 115, 186, 42, 126, 196, 82];
+const ARCHIVE_SEAT_LOG_DISCRIMINANT: [u8; 8] = [239, 158, 31, 208, 70, 84, 50, 63];
+const RESTORE_SEAT_LOG_DISCRIMINANT: [u8; 8] = [246, 221, 72, 247, 241, 13, 212, 178];
+const DEPTH_SNAPSHOT_LOG_DISCRIMINANT: [u8; 8] = [253, 103, 137, 33, 243, 53, 76, 86];
+const POINTS_CLAIM_LOG_DISCRIMINANT: [u8; 8] = [193, 139, 177, 82, 119, 234, 89, 72];
+const REPLACE_ORDER_LOG_DISCRIMINANT: [u8; 8] = [253, 39, 91, 196, 183, 42, 212, 80];
+const STOP_ORDER_TRIGGERED_LOG_DISCRIMINANT: [u8; 8] = [153, 90, 232, 184, 87, 114, 199, 140];
+const CLAIM_FEES_LOG_DISCRIMINANT: [u8; 8] = [237, 221, 199, 208, 60, 38, 140, 13];
+const TRADE_BUST_LOG_DISCRIMINANT: [u8; 8] = [224, 34, 84, 100, 252, 51, 100, 19];
+const REFERRAL_FEE_LOG_DISCRIMINANT: [u8; 8] = [68, 157, 166, 254, 44, 208, 77, 212];
+const MMP_TRIGGERED_LOG_DISCRIMINANT: [u8; 8] = [178, 235, 216, 126, 23, 124, 192, 25];
+const MARKET_PAUSED_LOG_DISCRIMINANT: [u8; 8] = [162, 4, 62, 225, 179, 177, 40, 58];
+const RFQ_LOG_DISCRIMINANT: [u8; 8] = [187, 131, 190, 235, 9, 179, 203, 125];
+const PEGGED_ORDER_PLACED_LOG_DISCRIMINANT: [u8; 8] = [136, 171, 64, 239, 127, 12, 218, 59];
 
 discriminant!(
     CreateMarketLog,
@@ -231,7 +994,8 @@ discriminant!(
 );
 discriminant!(DepositLog, DEPOSIT_LOG_DISCRIMINANT, test_deposit_log);
 discriminant!(WithdrawLog, WITHDRAW_LOG_DISCRIMINANT, test_withdraw_log);
-discriminant!(FillLog, FILL_LOG_DISCRIMINANT, test_fill_log);
+discriminant!(FillLog, FILL_LOG_DISCRIMINANT, 3, test_fill_log);
+discriminant!(FeeLog, FEE_LOG_DISCRIMINANT, test_fee_log);
 discriminant!(
     PlaceOrderLog,
     PLACE_ORDER_LOG_DISCRIMINANT,
@@ -282,3 +1046,64 @@ discriminant!(
     GLOBAL_CLEANUP_LOG_DISCRIMINANT,
     test_global_cleanup_log
 );
+discriminant!(
+    ArchiveSeatLog,
+    ARCHIVE_SEAT_LOG_DISCRIMINANT,
+    test_archive_seat_log
+);
+discriminant!(
+    RestoreSeatLog,
+    RESTORE_SEAT_LOG_DISCRIMINANT,
+    test_restore_seat_log
+);
+discriminant!(
+    DepthSnapshotLog,
+    DEPTH_SNAPSHOT_LOG_DISCRIMINANT,
+    test_depth_snapshot_log
+);
+discriminant!(
+    ReplaceOrderLog,
+    REPLACE_ORDER_LOG_DISCRIMINANT,
+    test_replace_order_log
+);
+discriminant!(
+    PointsClaimLog,
+    POINTS_CLAIM_LOG_DISCRIMINANT,
+    test_points_claim_log
+);
+discriminant!(
+    StopOrderTriggeredLog,
+    STOP_ORDER_TRIGGERED_LOG_DISCRIMINANT,
+    test_stop_order_triggered_log
+);
+discriminant!(
+    ClaimFeesLog,
+    CLAIM_FEES_LOG_DISCRIMINANT,
+    test_claim_fees_log
+);
+discriminant!(
+    TradeBustLog,
+    TRADE_BUST_LOG_DISCRIMINANT,
+    test_trade_bust_log
+);
+discriminant!(
+    ReferralFeeLog,
+    REFERRAL_FEE_LOG_DISCRIMINANT,
+    test_referral_fee_log
+);
+discriminant!(
+    MmpTriggeredLog,
+    MMP_TRIGGERED_LOG_DISCRIMINANT,
+    test_mmp_triggered_log
+);
+discriminant!(
+    MarketPausedLog,
+    MARKET_PAUSED_LOG_DISCRIMINANT,
+    test_market_paused_log
+);
+discriminant!(RfqLog, RFQ_LOG_DISCRIMINANT, test_rfq_log);
+discriminant!(
+    PeggedOrderPlacedLog,
+    PEGGED_ORDER_PLACED_LOG_DISCRIMINANT,
+    test_pegged_order_placed_log
+);
@@ -48,6 +48,52 @@ pub enum ManifestError {
     InvalidEvict = 20,
     #[error("Tried to clean order that was not eligible to be cleaned")]
     InvalidClean = 21,
+    #[error("Invalid seat config")]
+    InvalidSeatConfig = 22,
+    #[error("Missing required cosigner")]
+    MissingCosigner = 23,
+    #[error("Market account is at its maximum size")]
+    MarketTooLarge = 24,
+    #[error("Invalid RFQ fill")]
+    InvalidRfq = 25,
+    #[error("Order would cross an order from the same seat")]
+    InternalCross = 26,
+    #[error("Market is outside its trading hours")]
+    MarketClosed = 27,
+    #[error("Invalid seat archive")]
+    InvalidSeatArchive = 28,
+    #[error("Invalid swap commitment")]
+    InvalidSwapCommitment = 29,
+    #[error("Caller is not on the market CPI allowlist")]
+    CpiCallerNotAllowed = 30,
+    #[error("Invalid stop order")]
+    InvalidStopOrder = 31,
+    #[error("Invalid heartbeat")]
+    InvalidHeartbeat = 32,
+    #[error("Invalid rebalance")]
+    InvalidRebalance = 33,
+    #[error("Invalid volatility update")]
+    InvalidVolatilityUpdate = 34,
+    #[error("Invalid recovery")]
+    InvalidRecovery = 35,
+    #[error("Fill outside the market price band")]
+    PriceBandExceeded = 36,
+    #[error("Invalid price band reset")]
+    InvalidPriceBandReset = 37,
+    #[error("Invalid fee claim")]
+    InvalidFeeClaim = 38,
+    #[error("Invalid trade bust")]
+    InvalidTradeBust = 39,
+    #[error("Invalid signed order")]
+    InvalidSignedOrder = 40,
+    #[error("Invalid market maker protection")]
+    InvalidMmp = 41,
+    #[error("Market is paused")]
+    MarketPaused = 42,
+    #[error("Invalid pauser")]
+    InvalidPauser = 43,
+    #[error("Invalid pegged order")]
+    InvalidPeggedOrder = 44,
 }
 
 impl From<ManifestError> for ProgramError {
@@ -6,7 +6,9 @@ use shank::ShankInstruction;
 #[derive(TryFromPrimitive, Debug, Copy, Clone, ShankInstruction, PartialEq, Eq)]
 #[rustfmt::skip]
 pub enum ManifestInstruction {
-    /// Create a market
+    /// Create a market. Data is optionally one byte of MARKET_FLAG_* bits,
+    /// which may be followed by the market settings and the CPI allowlist,
+    /// see create_market.
     #[account(0, writable, signer, name = "payer", desc = "Payer")]
     #[account(1, writable, name = "market", desc = "Account holding all market state")]
     #[account(2, name = "system_program", desc = "System program")]
@@ -37,13 +39,19 @@ pub enum ManifestInstruction {
     /// Withdraw
     #[account(0, writable, signer, name = "payer", desc = "Payer")]
     #[account(1, writable, name = "market", desc = "Account holding all market state")]
-    #[account(2, writable, name = "trader_token", desc = "Trader token account")]
+    #[account(2, writable, name = "trader_token", desc = "Destination token account, can be owned by any wallet")]
     #[account(3, writable, name = "vault", desc = "Vault PDA, seeds are [b'vault', market, mint]")]
     #[account(4, name = "token_program", desc = "Token program(22), should be the version that aligns with the token being used")]
     #[account(5, name = "mint", desc = "Required for token22 transfer_checked")]
     Withdraw = 3,
 
-    /// Places an order using funds in a wallet instead of on deposit
+    /// Places an order using funds in a wallet instead of on deposit.
+    ///
+    /// Optional data after the params is a little endian u64 min_fill_atoms.
+    /// Makers that would fill less than it are skipped and stay on the book.
+    /// It may be followed by a flags byte. Bit 0 passes over global orders
+    /// when the global accounts are left out, instead of stopping at them.
+    /// Bit 1 makes exact out the amount received after transfer fees.
     #[account(0, writable, signer, name = "payer", desc = "Payer")]
     #[account(1, writable, name = "market", desc = "Account holding all market state")]
     #[account(2, name = "system_program", desc = "System program")]
@@ -55,8 +63,12 @@ pub enum ManifestInstruction {
     #[account(8, optional, name = "base_mint", desc = "Base mint, only included if base is Token22, otherwise not required")]
     #[account(9, optional, name = "token_program_quote", desc = "Token program(22) quote. Optional. Only include if different from base")]
     #[account(10, optional, name = "quote_mint", desc = "Quote mint, only included if base is Token22, otherwise not required")]
-    #[account(11, writable, optional, name = "global", desc = "Global account")]
-    #[account(12, writable, optional, name = "global_vault", desc = "Global vault")]
+    #[account(11, optional, name = "associated_token_program", desc = "Associated token account program. When included, trader token accounts that do not exist are created, paid by a writable payer. Both mints are required for that")]
+    #[account(12, writable, optional, name = "referrer_token", desc = "Token account in the output mint paid the market's referral share of the taker fee")]
+    #[account(13, writable, optional, name = "global", desc = "Global account")]
+    #[account(14, writable, optional, name = "global_vault", desc = "Global vault")]
+    #[account(15, optional, name = "memo_program", desc = "SPL memo program. When included, each fill is also sent as a memo")]
+    #[account(16, optional, name = "instructions_sysvar", desc = "Instructions sysvar, last account on markets with a CPI allowlist")]
     Swap = 4,
 
     /// Expand a market.
@@ -64,12 +76,34 @@ pub enum ManifestInstruction {
     /// This is not used in normal operations because expansion happens within
     /// instructions that could require it.
     /// This is useful for when rent payer != transaction signer.
+    /// Optional data is a little endian u32 number of free blocks to make sure
+    /// the market has. Without it, expands by one block when fewer than two are
+    /// free. One call adds at most MAX_MARKET_BLOCKS_PER_EXPAND blocks and never
+    /// grows the market past MAX_MARKET_SIZE.
     #[account(0, writable, signer, name = "payer", desc = "Payer")]
     #[account(1, writable, name = "market", desc = "Account holding all market state")]
     #[account(2, name = "system_program", desc = "System program")]
     Expand = 5,
 
     /// Batch update with multiple place orders and cancels.
+    ///
+    /// An optional trailing flags byte follows the params. Bit 0 has the payer
+    /// fund a block for every place before placing. Bit 1 makes last_valid_slot
+    /// a number of slots from the slot the transaction lands in. Bit 2 cancels
+    /// every resting order of the payer first, except ones that have not
+    /// rested for the market's minimum resting time. Bit 3 means a borsh
+    /// Vec<ReplaceOrderParams> follows the flags byte. Replaces run after the
+    /// cancels and change the size and optionally the price of resting orders
+    /// in place. Reducing the size at the same price keeps queue priority, and
+    /// their results are returned after those of the places. Bit 4 means a
+    /// borsh Vec<u64> with one min_fill_base_atoms per place follows, after
+    /// the replaces. ImmediateOrCancel places that fill less than it fail the
+    /// instruction. The flags may be followed by a little endian u64
+    /// min_fill_atoms for places that cannot rest. Makers that would fill less
+    /// than it are skipped. After that, a u64 min_taker_atoms is stored on every order that rests. Takers that
+    /// would fill less than it and not take the whole order skip it, and do
+    /// not rest themselves afterwards. Last, one little endian u32 tag per
+    /// place, in order, is stored on the order and echoed in its fills.
     #[account(0, writable, signer, name = "payer", desc = "Payer")]
     #[account(1, writable, name = "market", desc = "Account holding all market state")]
     #[account(2, name = "system_program", desc = "System program")]
@@ -83,6 +117,9 @@ pub enum ManifestInstruction {
     #[account(10, optional, name = "quote_global_vault", desc = "Quote global vault")]
     #[account(11, optional, name = "quote_market_vault", desc = "Quote market vault")]
     #[account(12, optional, name = "quote_token_program", desc = "Token program(22)")]
+    #[account(13, optional, signer, name = "cosigner", desc = "Seat cosigner, required above the seat config notional threshold")]
+    #[account(14, optional, name = "memo_program", desc = "SPL memo program. When included, each fill is also sent as a memo")]
+    #[account(15, optional, name = "instructions_sysvar", desc = "Instructions sysvar, last account on markets with a CPI allowlist")]
     BatchUpdate = 6,
 
     /// Create global account for a given token.
@@ -146,7 +183,8 @@ pub enum ManifestInstruction {
     /// Places an order using funds in a wallet instead of on deposit. Separates
     /// the owner of the token accounts and the payer. This allows routers to
     /// swap and have intermediate hops go through PDAs, rather than all token
-    /// accounts owned by the user.
+    /// accounts owned by the user. Takes the same optional min_fill_atoms and
+    /// flags as Swap.
     #[account(0, writable, signer, name = "payer", desc = "Payer")]
     #[account(1, writable, signer, name = "owner", desc = "Owner")]
     #[account(2, writable, name = "market", desc = "Account holding all market state")]
@@ -159,9 +197,319 @@ pub enum ManifestInstruction {
     #[account(9, optional, name = "base_mint", desc = "Base mint, only included if base is Token22, otherwise not required")]
     #[account(10, optional, name = "token_program_quote", desc = "Token program(22) quote. Optional. Only include if different from base")]
     #[account(11, optional, name = "quote_mint", desc = "Quote mint, only included if base is Token22, otherwise not required")]
-    #[account(12, writable, optional, name = "global", desc = "Global account")]
-    #[account(13, writable, optional, name = "global_vault", desc = "Global vault")]
+    #[account(12, optional, name = "associated_token_program", desc = "Associated token account program. When included, trader token accounts that do not exist are created, paid by a writable payer. Both mints are required for that")]
+    #[account(13, writable, optional, name = "referrer_token", desc = "Token account in the output mint paid the market's referral share of the taker fee")]
+    #[account(14, writable, optional, name = "global", desc = "Global account")]
+    #[account(15, writable, optional, name = "global_vault", desc = "Global vault")]
+    #[account(16, optional, name = "memo_program", desc = "SPL memo program. When included, each fill is also sent as a memo")]
+    #[account(17, optional, name = "instructions_sysvar", desc = "Instructions sysvar, last account on markets with a CPI allowlist")]
     SwapV2 = 13,
+
+    /// Set or clear the config for the payer's seat. Currently this is the
+    /// cosigner that has to sign batch updates above a notional threshold.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, optional, signer, name = "cosigner", desc = "Current cosigner, required if the seat already has one")]
+    SetSeatConfig = 14,
+
+    /// Deposit that claims a seat for the payer first if it does not have one
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, writable, name = "trader_token", desc = "Trader token account")]
+    #[account(3, writable, name = "vault", desc = "Vault PDA, seeds are [b'vault', market, mint]")]
+    #[account(4, name = "token_program", desc = "Token program(22), should be the version that aligns with the token being used")]
+    #[account(5, name = "mint", desc = "Required for token22 transfer_checked")]
+    #[account(6, name = "system_program", desc = "System program")]
+    DepositIdempotent = 15,
+
+    /// Same as SwapV2, except that when the owner has a seat, the output is
+    /// credited to the seat instead of the owner's token account. Saves a
+    /// deposit for traders that recycle taken inventory into resting orders.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, signer, name = "owner", desc = "Owner")]
+    #[account(2, writable, name = "market", desc = "Account holding all market state")]
+    #[account(3, name = "system_program", desc = "System program")]
+    #[account(4, writable, name = "trader_base", desc = "Trader base token account")]
+    #[account(5, writable, name = "trader_quote", desc = "Trader quote token account")]
+    #[account(6, writable, name = "base_vault", desc = "Base vault PDA, seeds are [b'vault', market_address, base_mint]")]
+    #[account(7, writable, name = "quote_vault", desc = "Quote vault PDA, seeds are [b'vault', market_address, quote_mint]")]
+    #[account(8, name = "token_program_base", desc = "Token program(22) base")]
+    #[account(9, optional, name = "base_mint", desc = "Base mint, only included if base is Token22, otherwise not required")]
+    #[account(10, optional, name = "token_program_quote", desc = "Token program(22) quote. Optional. Only include if different from base")]
+    #[account(11, optional, name = "quote_mint", desc = "Quote mint, only included if base is Token22, otherwise not required")]
+    #[account(12, optional, name = "associated_token_program", desc = "Associated token account program. When included, trader token accounts that do not exist are created, paid by a writable payer. Both mints are required for that")]
+    #[account(13, writable, optional, name = "referrer_token", desc = "Token account in the output mint paid the market's referral share of the taker fee")]
+    #[account(14, writable, optional, name = "global", desc = "Global account")]
+    #[account(15, writable, optional, name = "global_vault", desc = "Global vault")]
+    #[account(16, optional, name = "memo_program", desc = "SPL memo program. When included, each fill is also sent as a memo")]
+    #[account(17, optional, name = "instructions_sysvar", desc = "Instructions sysvar, last account on markets with a CPI allowlist")]
+    SwapToSeat = 16,
+
+    /// Fill the open RFQ of the taker, see PostRfq, against the best of the
+    /// maker quotes given, settling from the seat balances of both sides.
+    /// The instructions right before have to be one ed25519 program
+    /// instruction per quote, in order, verifying the maker's signature of
+    /// RfqQuote::message. Not available on markets with fees or a taker
+    /// delay.
+    #[account(0, signer, name = "taker", desc = "Taker")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, writable, name = "rfq", desc = "RFQ PDA of the taker, seeds are [b'rfq', market, taker]")]
+    #[account(3, name = "instructions_sysvar", desc = "Instructions sysvar, to find the ed25519 instructions")]
+    FillRfq = 17,
+
+    /// Move a seat with no resting orders and no seat config into the seat
+    /// archive of the market and free its block. Its balances and volume are
+    /// kept in a leaf of the archive and logged in ArchiveSeatLog. Anyone can
+    /// archive a seat holding at most SEAT_ARCHIVE_MAX_DUST_ATOMS of each
+    /// token, the trader can archive their own at any balance.
+    #[account(0, writable, signer, name = "payer", desc = "Payer, funds the seat archive if it does not exist yet")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "seat_archive", desc = "Seat archive PDA, seeds are [b'seat-archive', market]")]
+    ArchiveSeat = 18,
+
+    /// Put an archived seat back on the market given a proof of its leaf.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "seat_archive", desc = "Seat archive PDA, seeds are [b'seat-archive', market]")]
+    RestoreSeat = 19,
+
+    /// Commit to a swap on a market with a taker delay. Data is the 32 byte
+    /// hash of a salt and the swap instruction data, see swap_commitment.
+    /// Replaces a pending commitment of the payer on the market.
+    #[account(0, writable, signer, name = "payer", desc = "Trader, funds the swap commitment if it does not exist yet")]
+    #[account(1, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "swap_commitment", desc = "Swap commitment PDA, seeds are [b'swap-commitment', market, trader]")]
+    CommitSwap = 20,
+
+    /// Execute a committed swap once the taker delay of the market has passed.
+    /// Data is the salt followed by the Swap, SwapV2 or SwapToSeat instruction
+    /// data, and the accounts are the swap commitment followed by the accounts
+    /// of that swap.
+    #[account(0, writable, name = "swap_commitment", desc = "Swap commitment PDA, seeds are [b'swap-commitment', market, trader]")]
+    RevealSwap = 21,
+
+    /// Read only prices of a market for programs that use it as a price
+    /// source through CPI: best bid and ask, last trade, TWAP and the size
+    /// within 2% of the best prices. Returned as GetMarketPricesReturn in the
+    /// return data.
+    #[account(0, name = "market", desc = "Account holding all market state")]
+    GetMarketPrices = 22,
+
+    /// Log the best price levels of both sides of the book as a
+    /// DepthSnapshotLog. Permissionless and read only, for cranks that record
+    /// depth history. Optional data is a u8 number of levels per side, at most
+    /// DEPTH_SNAPSHOT_MAX_LEVELS.
+    #[account(0, name = "market", desc = "Account holding all market state")]
+    EmitDepthSnapshot = 23,
+
+    /// Claim the payer's points of the latest finished epoch as a
+    /// PointsClaimLog on a market that awards points. The first claim gives
+    /// the seat a SeatConfig, which is what makes it earn points.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    ClaimPoints = 24,
+
+    /// Read only points of the seat of the trader given as data, returned as
+    /// GetSeatPointsReturn in the return data.
+    #[account(0, name = "market", desc = "Account holding all market state")]
+    GetSeatPoints = 25,
+
+    /// Add a stop order that TriggerStopOrders places once the last trade
+    /// price reaches its trigger, see StopOrders. Only limit and immediate or
+    /// cancel orders. The payer needs a seat on the market.
+    #[account(0, writable, signer, name = "payer", desc = "Trader, funds the stop orders account if it does not exist yet")]
+    #[account(1, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "stop_orders", desc = "Stop orders PDA, seeds are [b'stop-orders', market, trader]")]
+    #[account(4, optional, name = "instructions_sysvar", desc = "Instructions sysvar, last account on markets with a CPI allowlist")]
+    PlaceStopOrder = 26,
+
+    /// Remove a stop order of the payer. Data is the u8 slot of the order.
+    #[account(0, signer, name = "payer", desc = "Trader")]
+    #[account(1, writable, name = "stop_orders", desc = "Stop orders PDA, seeds are [b'stop-orders', market, trader]")]
+    CancelStopOrder = 27,
+
+    /// Place every stop order of a trader whose trigger the last trade price
+    /// has reached and clear the expired ones. Permissionless, so keepers can
+    /// crank it after trades.
+    #[account(0, writable, signer, name = "payer", desc = "Payer, funds market expansion")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "stop_orders", desc = "Stop orders PDA, seeds are [b'stop-orders', market, trader]")]
+    TriggerStopOrders = 28,
+
+    /// Sell the payer's withdrawable base into the bids, no lower than
+    /// max_price_impact_bps below the best bid, and withdraw all the quote.
+    /// Base the bids cannot take stays on the seat.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, writable, name = "trader_token", desc = "Destination quote token account, can be owned by any wallet")]
+    #[account(3, writable, name = "vault", desc = "Quote vault PDA, seeds are [b'vault', market, quote_mint]")]
+    #[account(4, name = "token_program", desc = "Token program(22), should be the version that aligns with the quote token")]
+    #[account(5, name = "mint", desc = "Required for token22 transfer_checked")]
+    #[account(6, optional, name = "instructions_sysvar", desc = "Instructions sysvar, last account on markets with a CPI allowlist")]
+    WithdrawAsQuote = 29,
+
+    /// Restart the payer's dead man's switch on a market, see Heartbeat. The
+    /// heartbeat account holds the bounty above rent.
+    #[account(0, writable, signer, name = "payer", desc = "Trader, funds the heartbeat account and bounty")]
+    #[account(1, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "heartbeat", desc = "Heartbeat PDA, seeds are [b'heartbeat', market, trader]")]
+    Heartbeat = 30,
+
+    /// Cancel all orders of a seat whose heartbeat timed out and take the
+    /// bounty. Permissionless, so keepers can crank it.
+    #[account(0, writable, signer, name = "payer", desc = "Receives the bounty")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, writable, name = "heartbeat", desc = "Heartbeat PDA, seeds are [b'heartbeat', market, trader]")]
+    CancelOnHeartbeatTimeout = 31,
+
+    /// Trade the withdrawable balance of a seat towards a target base balance
+    /// against the book, no further than max_slippage_bps through the best
+    /// price. Signed by the trader, and by the cosigner of their seat when the
+    /// trade is above its cosign threshold.
+    #[account(0, signer, name = "payer", desc = "Trader whose seat is rebalanced")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, optional, signer, name = "cosigner", desc = "Cosigner of the seat, required above its cosign threshold")]
+    #[account(3, optional, name = "instructions_sysvar", desc = "Instructions sysvar, last account on markets with a CPI allowlist")]
+    Rebalance = 32,
+
+    /// Sample the last trade price into the volatility estimate of the market,
+    /// see MarketVolatility. Permissionless, at most once per
+    /// VOLATILITY_SAMPLE_SLOTS.
+    #[account(0, writable, signer, name = "payer", desc = "Funds the market volatility account on the first update")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "market_volatility", desc = "Market volatility PDA, seeds are [b'volatility', market]")]
+    UpdateVolatility = 33,
+
+    /// Put a market whose state may be damaged in recovery. Freezes all
+    /// trading, deposits and withdraws except RecoveryWithdraw and RestoreSeat.
+    /// One way.
+    #[account(0, signer, name = "authority", desc = "Upgrade authority of the program")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "program_data", desc = "Program data account of the program")]
+    EnterRecovery = 34,
+
+    /// Withdraw the share of the vault owed to the seat of the payer on a
+    /// market in recovery, see Market::recovery_withdraw.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, writable, name = "trader_token", desc = "Destination token account, can be owned by any wallet")]
+    #[account(3, writable, name = "vault", desc = "Vault PDA, seeds are [b'vault', market, mint]")]
+    #[account(4, name = "token_program", desc = "Token program(22), should be the version that aligns with the token being used")]
+    #[account(5, name = "mint", desc = "Required for token22 transfer_checked")]
+    RecoveryWithdraw = 35,
+
+    /// Reset the reference price of the market price band to the middle of
+    /// the book, see PriceBand. Permissionless, once there has been no trade
+    /// or reset for PRICE_BAND_RESET_SLOTS.
+    #[account(0, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    ResetPriceBand = 36,
+
+    /// Pay the fees a market has accrued in one mint to a token account of
+    /// its fee recipient, see MarketFees. Permissionless.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, writable, name = "trader_token", desc = "Destination token account, owned by the fee recipient")]
+    #[account(3, writable, name = "vault", desc = "Vault PDA, seeds are [b'vault', market, mint]")]
+    #[account(4, name = "token_program", desc = "Token program(22), should be the version that aligns with the token being used")]
+    #[account(5, name = "mint", desc = "Required for token22 transfer_checked")]
+    ClaimFees = 37,
+
+    /// Reverse the seat balance changes, fees, volumes, points and market
+    /// maker protection counts of an erroneous fill, see Market::bust_trade.
+    /// Only for fills still in the FillRecords of markets that keep them,
+    /// among the last TRADE_BUST_MAX_SEQUENCE_NUMBERS orders, and once per
+    /// fill.
+    #[account(0, writable, signer, name = "authority", desc = "Upgrade authority of the program")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "program_data", desc = "Program data account of the program")]
+    #[account(3, name = "system_program", desc = "System program")]
+    #[account(4, writable, name = "trade_bust", desc = "Trade bust PDA, seeds are [b'trade-bust', market, maker_sequence_number, taker_sequence_number]")]
+    BustTrade = 38,
+
+    /// Fill the whole of an order a maker signed off chain, from the seat
+    /// balances of both sides like FillRfq. The instruction right before has
+    /// to be an ed25519 program instruction verifying the maker's signature
+    /// of SignedOrder::message. Uses the nonce of the order.
+    #[account(0, signer, name = "taker", desc = "Taker")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, writable, name = "signed_order_nonces", desc = "Signed order nonces PDA of the maker, seeds are [b'signed-order-nonces', market, maker]")]
+    #[account(3, name = "instructions_sysvar", desc = "Instructions sysvar, to find the ed25519 instruction")]
+    FillSignedOrder = 39,
+
+    /// Cancel the payer's signed orders with a nonce below min_nonce, see
+    /// SignedOrderNonces. The first call creates the nonces account, which
+    /// FillSignedOrder needs.
+    #[account(0, writable, signer, name = "payer", desc = "Maker, funds the nonces account")]
+    #[account(1, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "signed_order_nonces", desc = "Signed order nonces PDA, seeds are [b'signed-order-nonces', market, maker]")]
+    CancelSignedOrders = 40,
+
+    /// Set the payer's market maker protection, see SeatMmp. Once the fills
+    /// against the seat's orders within window_slots reach max_fills or
+    /// max_quote_atoms, matching pulls all its orders and emits a
+    /// MmpTriggeredLog. Zero limits clear it.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    SetMmp = 41,
+
+    /// Pause or resume trading on a market created with a pauser, see
+    /// MarketExtension. Paused markets take no new orders, swaps or fills,
+    /// while cancels, deposits and withdraws still work. The pauser has no
+    /// other power over the market.
+    #[account(0, signer, name = "pauser", desc = "Pauser set at market creation")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    SetPaused = 42,
+
+    /// Post a request for quote of the payer on a market, replacing their
+    /// open one, see Rfq. Makers answer off chain with signed quotes for its
+    /// sequence number and the taker fills the best with FillRfq. Zero
+    /// base_atoms cancels the open request.
+    #[account(0, writable, signer, name = "taker", desc = "Taker, funds the RFQ account")]
+    #[account(1, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "rfq", desc = "RFQ PDA, seeds are [b'rfq', market, taker]")]
+    PostRfq = 43,
+
+    /// Add a pegged order whose price follows the best bid, best ask or mid
+    /// of the book by offset_bps, capped at a limit price, see PeggedOrders.
+    /// It goes on the book with the next RepricePeggedOrders, which clients
+    /// can add to the same transaction. The payer needs a seat on the market.
+    #[account(0, writable, signer, name = "payer", desc = "Trader, funds the pegged orders account if it does not exist yet")]
+    #[account(1, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "pegged_orders", desc = "Pegged orders PDA, seeds are [b'pegged-orders', market, trader]")]
+    #[account(4, optional, name = "instructions_sysvar", desc = "Instructions sysvar, last account on markets with a CPI allowlist")]
+    PlacePeggedOrder = 44,
+
+    /// Remove a pegged order of the payer and cancel its resting order if
+    /// it is on the book. Data is the u8 slot of the order.
+    #[account(0, signer, name = "payer", desc = "Trader")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, writable, name = "pegged_orders", desc = "Pegged orders PDA, seeds are [b'pegged-orders', market, trader]")]
+    CancelPeggedOrder = 45,
+
+    /// Move every pegged order of a trader to its price against the current
+    /// book, placing the ones that are off it, and clear the filled,
+    /// cancelled and expired ones. Orders already at their price keep their
+    /// place in the queue. Permissionless, so keepers can crank it when the
+    /// book moves.
+    #[account(0, writable, signer, name = "payer", desc = "Payer, funds market expansion")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "pegged_orders", desc = "Pegged orders PDA, seeds are [b'pegged-orders', market, trader]")]
+    RepricePeggedOrders = 46,
 }
 
 impl ManifestInstruction {
@@ -172,7 +520,7 @@ impl ManifestInstruction {
 
 #[test]
 fn test_instruction_serialization() {
-    let num_instructions: u8 = 13;
+    let num_instructions: u8 = 46;
     for i in 0..=255 {
         let instruction: ManifestInstruction = match ManifestInstruction::try_from(i) {
             Ok(j) => {
@@ -0,0 +1,28 @@
+use crate::{
+    program::{archive_seat::ArchiveSeatParams, ManifestInstruction},
+    validation::get_seat_archive_address,
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+pub fn archive_seat_instruction(market: &Pubkey, payer: &Pubkey, trader: &Pubkey) -> Instruction {
+    let (seat_archive, _seat_archive_bump) = get_seat_archive_address(market);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(seat_archive, false),
+        ],
+        data: [
+            ManifestInstruction::ArchiveSeat.to_vec(),
+            ArchiveSeatParams::new(*trader).try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
@@ -3,9 +3,18 @@ use crate::program::batch_update::{CancelOrderParams, PlaceOrderParams};
 #[cfg(not(feature = "certora"))]
 use crate::{
     program::{
-        batch_update::{BatchUpdateParams, CancelOrderParams, PlaceOrderParams},
+        batch_update::{
+            AmendOrderParams, BatchUpdateParams, CancelOrderParams, PlaceOrderParams,
+            ReplaceOrderParams, BATCH_UPDATE_AMENDS, BATCH_UPDATE_CANCEL_ALL,
+            BATCH_UPDATE_DISPLAY_SIZES, BATCH_UPDATE_IOC_MIN_FILLS,
+            BATCH_UPDATE_LAST_VALID_UNIX_TIMESTAMPS, BATCH_UPDATE_MIN_FILL_ATOMS,
+            BATCH_UPDATE_MIN_TAKER_ATOMS, BATCH_UPDATE_MORE_FLAGS, BATCH_UPDATE_ONE_CANCELS_OTHER,
+            BATCH_UPDATE_QUOTE_SIZES, BATCH_UPDATE_REPLACES, BATCH_UPDATE_RESERVE_BLOCKS,
+            BATCH_UPDATE_SELF_TRADE_PREVENTION, BATCH_UPDATE_TAGS,
+        },
         ManifestInstruction,
     },
+    state::SelfTradePrevention,
     validation::{get_global_address, get_global_vault_address, get_vault_address},
 };
 #[cfg(not(feature = "certora"))]
@@ -22,6 +31,7 @@ use solana_program::{
 
 // Token programs are needed for global orders with token22. Only include if
 // this is global or could match with global. Defaults to normal token program.
+// Seats with a cosigner append it as a readonly signer after these accounts.
 #[cfg(not(feature = "certora"))]
 pub fn batch_update_instruction(
     market: &Pubkey,
@@ -92,3 +102,516 @@ pub fn batch_update_instruction(
     // Empty intentionally, just here so it compiles.
     todo!()
 }
+
+/// Same as batch_update_instruction, but the payer also funds one block for
+/// every place up front, so the places never wait on a separate expand.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_reserve_blocks_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+) -> Instruction {
+    batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        BATCH_UPDATE_RESERVE_BLOCKS,
+    )
+}
+
+/// Cancels every resting order of the payer, then places orders. Global
+/// orders only refund their gas prepayment when their mint is given.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_cancel_all_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+) -> Instruction {
+    batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        vec![],
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        BATCH_UPDATE_CANCEL_ALL,
+    )
+}
+
+/// Same as batch_update_instruction with the trailing flags byte, a
+/// combination of BATCH_UPDATE_RESERVE_BLOCKS, BATCH_UPDATE_RELATIVE_EXPIRY
+/// and BATCH_UPDATE_CANCEL_ALL.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_flags_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    flags: u8,
+) -> Instruction {
+    let mut instruction: Instruction = batch_update_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+    );
+    instruction.data.push(flags);
+    instruction
+}
+
+/// Same as batch_update_with_flags_instruction with BATCH_UPDATE_REPLACES
+/// added to the flags, followed by the replaces. Replaces are applied after
+/// the cancels and before the places.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_replaces_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    replaces: Vec<ReplaceOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    flags: u8,
+) -> Instruction {
+    let mut instruction: Instruction = batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        flags | BATCH_UPDATE_REPLACES,
+    );
+    instruction.data.extend(replaces.try_to_vec().unwrap());
+    instruction
+}
+
+/// Same as batch_update_with_flags_instruction with BATCH_UPDATE_IOC_MIN_FILLS
+/// added to the flags, followed by the minimum base atoms each place has to
+/// fill. Only ImmediateOrCancel places can have a minimum. Does not combine
+/// with BATCH_UPDATE_REPLACES, which goes before the minimums.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_ioc_min_fills_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    flags: u8,
+    ioc_min_fills: Vec<u64>,
+) -> Instruction {
+    let mut instruction: Instruction = batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        flags | BATCH_UPDATE_IOC_MIN_FILLS,
+    );
+    instruction.data.extend(ioc_min_fills.try_to_vec().unwrap());
+    instruction
+}
+
+/// Same as batch_update_with_flags_instruction with
+/// BATCH_UPDATE_SELF_TRADE_PREVENTION added to the flags, followed by what
+/// each place does when it would match an order from the same seat. Does not
+/// combine with BATCH_UPDATE_REPLACES or BATCH_UPDATE_IOC_MIN_FILLS, which go
+/// before the policies.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_self_trade_prevention_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    flags: u8,
+    self_trade_preventions: Vec<SelfTradePrevention>,
+) -> Instruction {
+    let mut instruction: Instruction = batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        flags | BATCH_UPDATE_SELF_TRADE_PREVENTION,
+    );
+    instruction
+        .data
+        .extend(self_trade_preventions.try_to_vec().unwrap());
+    instruction
+}
+
+/// Same as batch_update_with_flags_instruction with
+/// BATCH_UPDATE_ONE_CANCELS_OTHER added to the flags, followed by pairs of
+/// place indices whose orders cancel each other on a fill. Does not combine
+/// with the trailing data of the other flags, which goes before the pairs.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_one_cancels_other_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    flags: u8,
+    one_cancels_others: Vec<(u8, u8)>,
+) -> Instruction {
+    let mut instruction: Instruction = batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        flags | BATCH_UPDATE_ONE_CANCELS_OTHER,
+    );
+    instruction
+        .data
+        .extend(one_cancels_others.try_to_vec().unwrap());
+    instruction
+}
+
+/// Same as batch_update_with_flags_instruction with BATCH_UPDATE_MORE_FLAGS
+/// added to the flags and a second flags byte with BATCH_UPDATE_DISPLAY_SIZES,
+/// followed by the display size of each place. Iceberg
+/// places need one and other places zero. Does not combine with the trailing
+/// data of the other flags, which goes before the display sizes.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_display_sizes_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    flags: u8,
+    display_sizes: Vec<u64>,
+) -> Instruction {
+    let mut instruction: Instruction = batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        flags | BATCH_UPDATE_MORE_FLAGS,
+    );
+    instruction.data.push(BATCH_UPDATE_DISPLAY_SIZES);
+    instruction.data.extend(display_sizes.try_to_vec().unwrap());
+    instruction
+}
+
+/// Same as batch_update_with_flags_instruction with BATCH_UPDATE_MORE_FLAGS
+/// added to the flags and a second flags byte with BATCH_UPDATE_AMENDS,
+/// followed by the amends. Amends are applied after the cancels and before
+/// the replaces. Does not combine with the trailing data of the other flags,
+/// which goes before the amends.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_amends_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    amends: Vec<AmendOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    flags: u8,
+) -> Instruction {
+    let mut instruction: Instruction = batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        flags | BATCH_UPDATE_MORE_FLAGS,
+    );
+    instruction.data.push(BATCH_UPDATE_AMENDS);
+    instruction.data.extend(amends.try_to_vec().unwrap());
+    instruction
+}
+
+/// Same as batch_update_with_flags_instruction with BATCH_UPDATE_MORE_FLAGS
+/// added to the flags and a second flags byte with BATCH_UPDATE_QUOTE_SIZES,
+/// followed by the quote size of each place. Places with a quote size have
+/// zero base atoms, the others a quote size of zero. Does not combine with
+/// the trailing data of the other flags, which goes before the quote sizes.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_quote_sizes_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    flags: u8,
+    quote_sizes: Vec<u64>,
+) -> Instruction {
+    let mut instruction: Instruction = batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        flags | BATCH_UPDATE_MORE_FLAGS,
+    );
+    instruction.data.push(BATCH_UPDATE_QUOTE_SIZES);
+    instruction.data.extend(quote_sizes.try_to_vec().unwrap());
+    instruction
+}
+
+/// Same as batch_update_with_flags_instruction with BATCH_UPDATE_MORE_FLAGS
+/// added to the flags and a second flags byte with
+/// BATCH_UPDATE_LAST_VALID_UNIX_TIMESTAMPS, followed by the last valid unix
+/// timestamp of each place, zero for none. Does not combine with the trailing
+/// data of the other flags, which goes before the timestamps.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_last_valid_unix_timestamps_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    flags: u8,
+    last_valid_unix_timestamps: Vec<u32>,
+) -> Instruction {
+    let mut instruction: Instruction = batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        flags | BATCH_UPDATE_MORE_FLAGS,
+    );
+    instruction
+        .data
+        .push(BATCH_UPDATE_LAST_VALID_UNIX_TIMESTAMPS);
+    instruction
+        .data
+        .extend(last_valid_unix_timestamps.try_to_vec().unwrap());
+    instruction
+}
+
+/// Same as batch_update_with_flags_instruction with BATCH_UPDATE_MORE_FLAGS
+/// added to the flags and a second flags byte with
+/// BATCH_UPDATE_MIN_FILL_ATOMS, followed by the minimum fill against any one
+/// maker for places that cannot rest. Does not combine with the trailing data
+/// of the other flags, which goes before it.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_min_fill_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    flags: u8,
+    min_fill_atoms: u64,
+) -> Instruction {
+    let mut instruction: Instruction = batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        flags | BATCH_UPDATE_MORE_FLAGS,
+    );
+    instruction.data.push(BATCH_UPDATE_MIN_FILL_ATOMS);
+    instruction
+        .data
+        .extend_from_slice(&min_fill_atoms.to_le_bytes());
+    instruction
+}
+
+/// Same as batch_update_with_min_fill_instruction with
+/// BATCH_UPDATE_MIN_TAKER_ATOMS also in the second flags byte, followed by
+/// the minimum taker fill stored on every order that rests.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_min_taker_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    flags: u8,
+    min_fill_atoms: u64,
+    min_taker_atoms: u64,
+) -> Instruction {
+    let mut instruction: Instruction = batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        flags | BATCH_UPDATE_MORE_FLAGS,
+    );
+    instruction
+        .data
+        .push(BATCH_UPDATE_MIN_FILL_ATOMS | BATCH_UPDATE_MIN_TAKER_ATOMS);
+    instruction
+        .data
+        .extend_from_slice(&min_fill_atoms.to_le_bytes());
+    instruction
+        .data
+        .extend_from_slice(&min_taker_atoms.to_le_bytes());
+    instruction
+}
+
+/// Same as batch_update_with_min_taker_instruction with BATCH_UPDATE_TAGS
+/// also in the second flags byte, followed by a tag for every place, stored
+/// on the order and echoed in its fills. The program rejects the instruction
+/// unless there is exactly one tag per place.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_tags_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    flags: u8,
+    min_fill_atoms: u64,
+    min_taker_atoms: u64,
+    tags: &[u32],
+) -> Instruction {
+    let mut instruction: Instruction = batch_update_with_flags_instruction(
+        market,
+        payer,
+        trader_index_hint,
+        cancels,
+        orders,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        flags | BATCH_UPDATE_MORE_FLAGS,
+    );
+    instruction
+        .data
+        .push(BATCH_UPDATE_MIN_FILL_ATOMS | BATCH_UPDATE_MIN_TAKER_ATOMS | BATCH_UPDATE_TAGS);
+    instruction
+        .data
+        .extend_from_slice(&min_fill_atoms.to_le_bytes());
+    instruction
+        .data
+        .extend_from_slice(&min_taker_atoms.to_le_bytes());
+    instruction.data.extend(tags.try_to_vec().unwrap());
+    instruction
+}
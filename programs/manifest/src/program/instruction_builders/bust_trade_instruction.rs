@@ -0,0 +1,42 @@
+use crate::{
+    program::{bust_trade::BustTradeParams, ManifestInstruction},
+    validation::get_trade_bust_address,
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Reverse the fill in params on market. Signed by the upgrade authority of
+/// the program, which pays for the trade bust record.
+pub fn bust_trade_instruction(
+    market: &Pubkey,
+    authority: &Pubkey,
+    params: BustTradeParams,
+) -> Instruction {
+    let (program_data, _program_data_bump) =
+        Pubkey::find_program_address(&[crate::id().as_ref()], &bpf_loader_upgradeable::id());
+    let (trade_bust, _trade_bust_bump) = get_trade_bust_address(
+        market,
+        params.maker_sequence_number,
+        params.taker_sequence_number,
+    );
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(program_data, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(trade_bust, false),
+        ],
+        data: [
+            ManifestInstruction::BustTrade.to_vec(),
+            params.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
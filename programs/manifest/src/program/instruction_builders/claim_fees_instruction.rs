@@ -0,0 +1,30 @@
+use crate::{program::ManifestInstruction, validation::get_vault_address};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+/// Pay the fees market has accrued in mint to recipient_token_account, which
+/// has to be owned by the fee recipient of the market.
+pub fn claim_fees_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    recipient_token_account: &Pubkey,
+    token_program: Pubkey,
+) -> Instruction {
+    let (vault_address, _) = get_vault_address(market, mint);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*recipient_token_account, false),
+            AccountMeta::new(vault_address, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(*mint, false),
+        ],
+        data: ManifestInstruction::ClaimFees.to_vec(),
+    }
+}
@@ -0,0 +1,32 @@
+use crate::program::{claim_points::ClaimPointsParams, ManifestInstruction};
+use borsh::BorshSerialize;
+use hypertree::DataIndex;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Claim the payer's points of the latest finished epoch, opting the seat in
+/// to earning points on the first call.
+pub fn claim_points_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [
+            ManifestInstruction::ClaimPoints.to_vec(),
+            ClaimPointsParams::new(trader_index_hint)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{
+    program::{commit_swap::CommitSwapParams, ManifestInstruction},
+    state::swap_commitment_hash,
+    validation::get_swap_commitment_address,
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Commit to swap_instruction, a Swap, SwapV2 or SwapToSeat instruction whose
+/// owner is trader. Execute it with reveal_swap_instruction and the same salt
+/// once the taker delay of the market has passed.
+pub fn commit_swap_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    swap_instruction: &Instruction,
+    salt: &[u8; 32],
+) -> Instruction {
+    let (swap_commitment, _swap_commitment_bump) = get_swap_commitment_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*trader, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(swap_commitment, false),
+        ],
+        data: [
+            ManifestInstruction::CommitSwap.to_vec(),
+            CommitSwapParams::new(swap_commitment_hash(salt, &swap_instruction.data))
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+/// Executes swap_instruction after commit_swap_instruction committed to it.
+pub fn reveal_swap_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    swap_instruction: &Instruction,
+    salt: &[u8; 32],
+) -> Instruction {
+    let (swap_commitment, _swap_commitment_bump) = get_swap_commitment_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: [
+            vec![AccountMeta::new(swap_commitment, false)],
+            swap_instruction.accounts.clone(),
+        ]
+        .concat(),
+        data: [
+            ManifestInstruction::RevealSwap.to_vec(),
+            salt.to_vec(),
+            swap_instruction.data.clone(),
+        ]
+        .concat(),
+    }
+}
@@ -1,5 +1,11 @@
 use crate::{
-    program::ManifestInstruction, state::MarketFixed, validation::get_vault_address, ProgramError,
+    program::ManifestInstruction,
+    state::{
+        MarketFixed, TradingHours, MARKET_FLAG_CPI_ALLOWLIST, MARKET_FLAG_EXTENSION,
+        MARKET_FLAG_FEES, MARKET_FLAG_MIN_ORDER_SIZE, MARKET_FLAG_PRICE_BAND,
+    },
+    validation::get_vault_address,
+    ProgramError,
 };
 use solana_program::{
     instruction::{AccountMeta, Instruction},
@@ -34,6 +40,462 @@ pub fn create_market_instruction(
     quote_mint: &Pubkey,
     market_creator: &Pubkey,
 ) -> Instruction {
+    create_market_with_flags_instruction(market, base_mint, quote_mint, market_creator, 0)
+}
+
+/// Same as create_market_instruction with MARKET_FLAG_* bits for the market
+/// modes. The flags cannot be changed after creation.
+pub fn create_market_with_flags_instruction(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    market_flags: u8,
+) -> Instruction {
+    create_market_with_trading_hours_instruction(
+        market,
+        base_mint,
+        quote_mint,
+        market_creator,
+        market_flags,
+        TradingHours::default(),
+    )
+}
+
+/// Same as create_market_with_flags_instruction for a market that only takes
+/// new orders and swaps inside trading_hours. The hours cannot be changed
+/// after creation.
+pub fn create_market_with_trading_hours_instruction(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    market_flags: u8,
+    trading_hours: TradingHours,
+) -> Instruction {
+    create_market_with_min_resting_slots_instruction(
+        market,
+        base_mint,
+        quote_mint,
+        market_creator,
+        market_flags,
+        trading_hours,
+        0,
+    )
+}
+
+/// Same as create_market_with_trading_hours_instruction for a market where
+/// orders cannot be cancelled by their maker until they have rested for
+/// min_resting_slots. Zero is off. Cannot be changed after creation.
+pub fn create_market_with_min_resting_slots_instruction(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    market_flags: u8,
+    trading_hours: TradingHours,
+    min_resting_slots: u32,
+) -> Instruction {
+    create_market_with_taker_delay_instruction(
+        market,
+        base_mint,
+        quote_mint,
+        market_creator,
+        market_flags,
+        trading_hours,
+        min_resting_slots,
+        0,
+    )
+}
+
+/// Same as create_market_with_min_resting_slots_instruction for a market where
+/// takers commit to a swap and execute it taker_delay_slots later, see
+/// commit_swap_instruction. Orders placed through batch update cannot take.
+/// Zero is off. Cannot be changed after creation.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_taker_delay_instruction(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    market_flags: u8,
+    trading_hours: TradingHours,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+) -> Instruction {
+    create_market_with_points_instruction(
+        market,
+        base_mint,
+        quote_mint,
+        market_creator,
+        market_flags,
+        trading_hours,
+        min_resting_slots,
+        taker_delay_slots,
+        0,
+        0,
+    )
+}
+
+/// Same as create_market_with_taker_delay_instruction for a market that
+/// awards seats with a SeatConfig points per quote atom filled, weighted
+/// separately as maker and taker. Points are claimed per epoch with
+/// claim_points_instruction. Both zero is off. Cannot be changed after
+/// creation.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_points_instruction(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    market_flags: u8,
+    trading_hours: TradingHours,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+) -> Instruction {
+    create_market_with_cpi_allowlist_instruction(
+        market,
+        base_mint,
+        quote_mint,
+        market_creator,
+        market_flags,
+        trading_hours,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        &[],
+    )
+}
+
+/// Same as create_market_with_points_instruction for a market where swaps,
+/// RFQ fills and batch updates that place or replace orders only work through
+/// a CPI from one of the cpi_allowlist programs, at most
+/// MAX_CPI_ALLOWLIST_PROGRAMS. Those calls take the instructions sysvar as
+/// their last account. Empty is off. Cannot be changed after creation.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_cpi_allowlist_instruction(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    market_flags: u8,
+    trading_hours: TradingHours,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist: &[Pubkey],
+) -> Instruction {
+    create_market_with_min_order_size_instruction(
+        market,
+        base_mint,
+        quote_mint,
+        market_creator,
+        market_flags,
+        trading_hours,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        cpi_allowlist,
+        0,
+        0,
+    )
+}
+
+/// Same as create_market_with_cpi_allowlist_instruction for a market where
+/// orders that could rest need at least min_base_atoms and a value of at least
+/// min_quote_atoms at their price, so the book cannot fill up with dust. Both
+/// zero is off. Cannot be changed after creation.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_min_order_size_instruction(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    market_flags: u8,
+    trading_hours: TradingHours,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist: &[Pubkey],
+    min_base_atoms: u64,
+    min_quote_atoms: u64,
+) -> Instruction {
+    create_market_with_price_band_instruction(
+        market,
+        base_mint,
+        quote_mint,
+        market_creator,
+        market_flags,
+        trading_hours,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        cpi_allowlist,
+        min_base_atoms,
+        min_quote_atoms,
+        0,
+    )
+}
+
+/// Same as create_market_with_min_order_size_instruction for a market where
+/// takers cannot fill further than price_band_bps from the last trade price,
+/// see PriceBand. Zero is off. Cannot be changed after creation.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_price_band_instruction(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    market_flags: u8,
+    trading_hours: TradingHours,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist: &[Pubkey],
+    min_base_atoms: u64,
+    min_quote_atoms: u64,
+    price_band_bps: u16,
+) -> Instruction {
+    create_market_with_fees_instruction(
+        market,
+        base_mint,
+        quote_mint,
+        market_creator,
+        market_flags,
+        trading_hours,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        cpi_allowlist,
+        min_base_atoms,
+        min_quote_atoms,
+        price_band_bps,
+        0,
+        0,
+        0,
+        &Pubkey::default(),
+        0,
+    )
+}
+
+/// Same as create_market_with_price_band_instruction for a market where
+/// makers and takers pay maker_fee_bps and taker_fee_bps of what they receive
+/// from fills, see MarketFees. Both zero is off. Swaps with a referrer pay it
+/// referral_fee_bps of the taker fee. ClaimFees pays the rest out to token
+/// accounts owned by fee_recipient. Takers whose rolling volume reaches a
+/// multiple of fee_tier_quote_atoms in FEE_TIERS get a taker fee discount,
+/// zero is no tiers. Cannot be changed after creation.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_fees_instruction(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    market_flags: u8,
+    trading_hours: TradingHours,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist: &[Pubkey],
+    min_base_atoms: u64,
+    min_quote_atoms: u64,
+    price_band_bps: u16,
+    maker_fee_bps: u16,
+    taker_fee_bps: u16,
+    referral_fee_bps: u16,
+    fee_recipient: &Pubkey,
+    fee_tier_quote_atoms: u64,
+) -> Instruction {
+    create_market_with_pauser_instruction(
+        market,
+        base_mint,
+        quote_mint,
+        market_creator,
+        market_flags,
+        trading_hours,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        cpi_allowlist,
+        min_base_atoms,
+        min_quote_atoms,
+        price_band_bps,
+        maker_fee_bps,
+        taker_fee_bps,
+        referral_fee_bps,
+        fee_recipient,
+        fee_tier_quote_atoms,
+        &Pubkey::default(),
+    )
+}
+
+/// Same as create_market_with_fees_instruction for a market with a
+/// MarketExtension whose pauser can stop new orders, swaps and fills with
+/// SetPaused. The default pubkey is no pauser. The pauser cannot be changed
+/// after creation.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_pauser_instruction(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    market_flags: u8,
+    trading_hours: TradingHours,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist: &[Pubkey],
+    min_base_atoms: u64,
+    min_quote_atoms: u64,
+    price_band_bps: u16,
+    maker_fee_bps: u16,
+    taker_fee_bps: u16,
+    referral_fee_bps: u16,
+    fee_recipient: &Pubkey,
+    fee_tier_quote_atoms: u64,
+    pauser: &Pubkey,
+) -> Instruction {
+    create_market_with_extension_instruction(
+        market,
+        base_mint,
+        quote_mint,
+        market_creator,
+        market_flags,
+        trading_hours,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        cpi_allowlist,
+        min_base_atoms,
+        min_quote_atoms,
+        price_band_bps,
+        maker_fee_bps,
+        taker_fee_bps,
+        referral_fee_bps,
+        fee_recipient,
+        fee_tier_quote_atoms,
+        pauser,
+        0,
+    )
+}
+
+/// Same as create_market_with_pauser_instruction with the
+/// MARKET_EXTENSION_FLAG_* bits the extension starts with. Only
+/// MARKET_EXTENSION_FLAG_FILL_RECORDS can be set, which adds the blocks that
+/// BustTrade checks fills against.
+#[allow(clippy::too_many_arguments)]
+pub fn create_market_with_extension_instruction(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    market_flags: u8,
+    trading_hours: TradingHours,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist: &[Pubkey],
+    min_base_atoms: u64,
+    min_quote_atoms: u64,
+    price_band_bps: u16,
+    maker_fee_bps: u16,
+    taker_fee_bps: u16,
+    referral_fee_bps: u16,
+    fee_recipient: &Pubkey,
+    fee_tier_quote_atoms: u64,
+    pauser: &Pubkey,
+    extension_flags: u8,
+) -> Instruction {
+    let has_allowlist: bool = !cpi_allowlist.is_empty();
+    let has_min_order_size: bool = min_base_atoms != 0 || min_quote_atoms != 0;
+    let has_price_band: bool = price_band_bps != 0;
+    let has_fees: bool = maker_fee_bps != 0 || taker_fee_bps != 0;
+    let mut market_flags: u8 = market_flags;
+    if has_allowlist {
+        market_flags |= MARKET_FLAG_CPI_ALLOWLIST;
+    }
+    if has_min_order_size {
+        market_flags |= MARKET_FLAG_MIN_ORDER_SIZE;
+    }
+    if has_price_band {
+        market_flags |= MARKET_FLAG_PRICE_BAND;
+    }
+    if has_fees {
+        market_flags |= MARKET_FLAG_FEES;
+    }
+    let has_extension: bool = market_flags & MARKET_FLAG_EXTENSION != 0
+        || *pauser != Pubkey::default()
+        || extension_flags != 0;
+    if has_extension {
+        market_flags |= MARKET_FLAG_EXTENSION;
+    }
+    // The minimum, the band, the fees, the extension and the allowlist come
+    // after all the other settings.
+    let has_points: bool = maker_points_weight != 0
+        || taker_points_weight != 0
+        || has_allowlist
+        || has_min_order_size
+        || has_price_band
+        || has_fees
+        || has_extension;
+    let has_options: bool = !trading_hours.is_always_open()
+        || min_resting_slots != 0
+        || taker_delay_slots != 0
+        || has_points;
+    let mut data: Vec<u8> = ManifestInstruction::CreateMarket.to_vec();
+    if market_flags != 0 || has_options {
+        data.push(market_flags);
+    }
+    if has_options {
+        data.push(trading_hours.days);
+        data.extend_from_slice(&trading_hours.open_minute.to_le_bytes());
+        data.extend_from_slice(&trading_hours.close_minute.to_le_bytes());
+    }
+    if min_resting_slots != 0 || taker_delay_slots != 0 || has_points {
+        data.extend_from_slice(&min_resting_slots.to_le_bytes());
+    }
+    if taker_delay_slots != 0 || has_points {
+        data.extend_from_slice(&taker_delay_slots.to_le_bytes());
+    }
+    if has_points {
+        data.extend_from_slice(&maker_points_weight.to_le_bytes());
+        data.extend_from_slice(&taker_points_weight.to_le_bytes());
+    }
+    if has_min_order_size {
+        data.extend_from_slice(&min_base_atoms.to_le_bytes());
+        data.extend_from_slice(&min_quote_atoms.to_le_bytes());
+    }
+    if has_price_band {
+        data.extend_from_slice(&price_band_bps.to_le_bytes());
+    }
+    if has_fees {
+        data.extend_from_slice(&maker_fee_bps.to_le_bytes());
+        data.extend_from_slice(&taker_fee_bps.to_le_bytes());
+        data.extend_from_slice(&referral_fee_bps.to_le_bytes());
+        data.extend_from_slice(fee_recipient.as_ref());
+        data.extend_from_slice(&fee_tier_quote_atoms.to_le_bytes());
+    }
+    if has_extension {
+        data.extend_from_slice(pauser.as_ref());
+        data.push(extension_flags);
+    }
+    for program in cpi_allowlist {
+        data.extend_from_slice(program.as_ref());
+    }
     let (base_vault, _) = get_vault_address(market, base_mint);
     let (quote_vault, _) = get_vault_address(market, quote_mint);
     Instruction {
@@ -49,6 +511,6 @@ pub fn create_market_instruction(
             AccountMeta::new_readonly(spl_token::id(), false),
             AccountMeta::new_readonly(spl_token_2022::id(), false),
         ],
-        data: [ManifestInstruction::CreateMarket.to_vec()].concat(),
+        data,
     }
 }
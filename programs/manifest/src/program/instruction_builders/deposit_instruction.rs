@@ -7,6 +7,7 @@ use hypertree::DataIndex;
 use solana_program::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
+    system_program,
 };
 
 pub fn deposit_instruction(
@@ -39,3 +40,36 @@ pub fn deposit_instruction(
         .concat(),
     }
 }
+
+/// Deposit that also claims the seat if the payer does not have one yet.
+pub fn deposit_idempotent_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    amount_atoms: u64,
+    trader_token_account: &Pubkey,
+    token_program: Pubkey,
+    trader_index_hint: Option<DataIndex>,
+) -> Instruction {
+    let (vault_address, _) = get_vault_address(market, mint);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*trader_token_account, false),
+            AccountMeta::new(vault_address, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [
+            ManifestInstruction::DepositIdempotent.to_vec(),
+            DepositParams::new(amount_atoms, trader_index_hint)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
@@ -0,0 +1,19 @@
+use crate::program::ManifestInstruction;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+/// Log the best num_levels price levels of each side of a market, see
+/// DepthSnapshotLog.
+pub fn emit_depth_snapshot_instruction(market: &Pubkey, num_levels: u8) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![AccountMeta::new_readonly(*market, false)],
+        data: [
+            ManifestInstruction::EmitDepthSnapshot.to_vec(),
+            vec![num_levels],
+        ]
+        .concat(),
+    }
+}
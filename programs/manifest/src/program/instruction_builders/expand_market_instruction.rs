@@ -16,3 +16,26 @@ pub fn expand_market_instruction(market: &Pubkey, payer: &Pubkey) -> Instruction
         data: [ManifestInstruction::Expand.to_vec()].concat(),
     }
 }
+
+/// Expand until the market has at least num_free_blocks free blocks. Does
+/// nothing if it already has them. Rent comes from payer, which does not need
+/// to be the trader, so one wallet can pre-fund space for several markets.
+pub fn expand_market_n_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    num_free_blocks: u32,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [
+            ManifestInstruction::Expand.to_vec(),
+            num_free_blocks.to_le_bytes().to_vec(),
+        ]
+        .concat(),
+    }
+}
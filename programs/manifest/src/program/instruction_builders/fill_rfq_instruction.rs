@@ -0,0 +1,66 @@
+use crate::{
+    program::{
+        fill_rfq::{FillRfqParams, PostRfqParams, RfqResponse},
+        ManifestInstruction,
+    },
+    validation::get_rfq_address,
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+/// Post a request for quote of taker on market, replacing their open one.
+/// Zero base_atoms cancels it.
+pub fn post_rfq_instruction(
+    market: &Pubkey,
+    taker: &Pubkey,
+    base_atoms: u64,
+    taker_is_buy: bool,
+    last_valid_slot: u32,
+) -> Instruction {
+    let (rfq, _rfq_bump) = get_rfq_address(market, taker);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*taker, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(rfq, false),
+        ],
+        data: [
+            ManifestInstruction::PostRfq.to_vec(),
+            PostRfqParams::new(base_atoms, taker_is_buy, last_valid_slot)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+/// Only the taker signs the transaction. It has to come right after one
+/// ed25519_verify_instruction per response, in the same order, for the
+/// maker's signature of quote.message().
+pub fn fill_rfq_instruction(
+    market: &Pubkey,
+    taker: &Pubkey,
+    responses: Vec<RfqResponse>,
+) -> Instruction {
+    let (rfq, _rfq_bump) = get_rfq_address(market, taker);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*taker, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(rfq, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: [
+            ManifestInstruction::FillRfq.to_vec(),
+            FillRfqParams::new(responses).try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{
+    program::{
+        fill_signed_order::{CancelSignedOrdersParams, FillSignedOrderParams, SignedOrder},
+        ManifestInstruction,
+    },
+    validation::get_signed_order_nonces_address,
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    ed25519_program,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+/// Only the taker signs the transaction. It has to come right after
+/// ed25519_verify_instruction for the maker's signature of order.message().
+pub fn fill_signed_order_instruction(
+    market: &Pubkey,
+    taker: &Pubkey,
+    maker: &Pubkey,
+    order: SignedOrder,
+) -> Instruction {
+    let (signed_order_nonces, _signed_order_nonces_bump) =
+        get_signed_order_nonces_address(market, maker);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*taker, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(signed_order_nonces, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+        data: [
+            ManifestInstruction::FillSignedOrder.to_vec(),
+            FillSignedOrderParams::new(*maker, order)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+/// Cancel the signed orders of maker on market with a nonce below min_nonce.
+/// The first one creates the nonces account that fills need.
+pub fn cancel_signed_orders_instruction(
+    market: &Pubkey,
+    maker: &Pubkey,
+    min_nonce: u64,
+) -> Instruction {
+    let (signed_order_nonces, _signed_order_nonces_bump) =
+        get_signed_order_nonces_address(market, maker);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*maker, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(signed_order_nonces, false),
+        ],
+        data: [
+            ManifestInstruction::CancelSignedOrders.to_vec(),
+            CancelSignedOrdersParams::new(min_nonce)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+/// Ed25519 program instruction verifying one signature, with the public key,
+/// signature and message all in its own data.
+pub fn ed25519_verify_instruction(
+    signer: &Pubkey,
+    signature: &[u8; 64],
+    message: &[u8],
+) -> Instruction {
+    // Two bytes of header and seven u16 offsets.
+    let public_key_offset: u16 = 16;
+    let signature_offset: u16 = public_key_offset + 32;
+    let message_offset: u16 = signature_offset + 64;
+    let mut data: Vec<u8> = vec![1, 0];
+    for offset in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    Instruction {
+        program_id: ed25519_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
@@ -0,0 +1,14 @@
+use crate::program::ManifestInstruction;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+/// Read the prices of a market, see GetMarketPricesReturn.
+pub fn get_market_prices_instruction(market: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![AccountMeta::new_readonly(*market, false)],
+        data: ManifestInstruction::GetMarketPrices.to_vec(),
+    }
+}
@@ -0,0 +1,18 @@
+use crate::program::ManifestInstruction;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+/// Read the points of trader's seat, returned as GetSeatPointsReturn.
+pub fn get_seat_points_instruction(market: &Pubkey, trader: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![AccountMeta::new_readonly(*market, false)],
+        data: [
+            ManifestInstruction::GetSeatPoints.to_vec(),
+            trader.to_bytes().to_vec(),
+        ]
+        .concat(),
+    }
+}
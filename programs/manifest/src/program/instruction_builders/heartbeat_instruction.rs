@@ -0,0 +1,57 @@
+use crate::{
+    program::{heartbeat::HeartbeatParams, ManifestInstruction},
+    validation::get_heartbeat_address,
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Restart the dead man's switch of trader on market. After timeout_slots
+/// without another heartbeat, anyone can cancel the seat's orders for
+/// bounty_lamports.
+pub fn heartbeat_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    timeout_slots: u32,
+    bounty_lamports: u64,
+) -> Instruction {
+    let (heartbeat, _heartbeat_bump) = get_heartbeat_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*trader, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(heartbeat, false),
+        ],
+        data: [
+            ManifestInstruction::Heartbeat.to_vec(),
+            HeartbeatParams::new(timeout_slots, bounty_lamports)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+/// Cancel all orders of trader on market once their heartbeat timed out. The
+/// bounty goes to payer.
+pub fn cancel_on_heartbeat_timeout_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader: &Pubkey,
+) -> Instruction {
+    let (heartbeat, _heartbeat_bump) = get_heartbeat_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(heartbeat, false),
+        ],
+        data: ManifestInstruction::CancelOnHeartbeatTimeout.to_vec(),
+    }
+}
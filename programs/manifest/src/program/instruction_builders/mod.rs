@@ -1,29 +1,73 @@
+pub mod archive_seat_instruction;
 pub mod batch_update_instruction;
+pub mod bust_trade_instruction;
+pub mod claim_fees_instruction;
+pub mod claim_points_instruction;
 pub mod claim_seat_instruction;
+pub mod commit_swap_instruction;
 pub mod create_market_instructions;
 pub mod deposit_instruction;
+pub mod emit_depth_snapshot_instruction;
 pub mod expand_market_instruction;
+pub mod fill_rfq_instruction;
+pub mod fill_signed_order_instruction;
+pub mod get_market_prices_instruction;
+pub mod get_seat_points_instruction;
 pub mod global_add_trader_instruction;
 pub mod global_clean_instruction;
 pub mod global_create_instruction;
 pub mod global_deposit_instruction;
 pub mod global_evict_instruction;
 pub mod global_withdraw_instruction;
+pub mod heartbeat_instruction;
+pub mod pegged_order_instruction;
+pub mod rebalance_instruction;
+pub mod recovery_instruction;
+pub mod reset_price_band_instruction;
+pub mod restore_seat_instruction;
+pub mod set_mmp_instruction;
+pub mod set_paused_instruction;
+pub mod set_seat_config_instruction;
+pub mod stop_order_instruction;
 pub mod swap_instruction;
+pub mod swap_to_seat_instruction;
 pub mod swap_v2_instruction;
+pub mod update_volatility_instruction;
 pub mod withdraw_instruction;
 
+pub use archive_seat_instruction::*;
 pub use batch_update_instruction::*;
+pub use bust_trade_instruction::*;
+pub use claim_fees_instruction::*;
+pub use claim_points_instruction::*;
 pub use claim_seat_instruction::*;
+pub use commit_swap_instruction::*;
 pub use create_market_instructions::*;
 pub use deposit_instruction::*;
+pub use emit_depth_snapshot_instruction::*;
 pub use expand_market_instruction::*;
+pub use fill_rfq_instruction::*;
+pub use fill_signed_order_instruction::*;
+pub use get_market_prices_instruction::*;
+pub use get_seat_points_instruction::*;
 pub use global_add_trader_instruction::*;
 pub use global_clean_instruction::*;
 pub use global_create_instruction::*;
 pub use global_deposit_instruction::*;
 pub use global_evict_instruction::*;
 pub use global_withdraw_instruction::*;
+pub use heartbeat_instruction::*;
+pub use pegged_order_instruction::*;
+pub use rebalance_instruction::*;
+pub use recovery_instruction::*;
+pub use reset_price_band_instruction::*;
+pub use restore_seat_instruction::*;
+pub use set_mmp_instruction::*;
+pub use set_paused_instruction::*;
+pub use set_seat_config_instruction::*;
+pub use stop_order_instruction::*;
 pub use swap_instruction::*;
+pub use swap_to_seat_instruction::*;
 pub use swap_v2_instruction::*;
+pub use update_volatility_instruction::*;
 pub use withdraw_instruction::*;
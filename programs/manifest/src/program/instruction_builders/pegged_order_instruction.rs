@@ -0,0 +1,102 @@
+use crate::{
+    program::{
+        pegged_order::{CancelPeggedOrderParams, PlacePeggedOrderParams},
+        ManifestInstruction,
+    },
+    state::PegReference,
+    validation::get_pegged_orders_address,
+};
+use borsh::BorshSerialize;
+use hypertree::DataIndex;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Add a pegged order for trader at offset_bps from reference, never worse
+/// than the limit price. Follow it with reprice_pegged_orders_instruction to
+/// put it on the book.
+#[allow(clippy::too_many_arguments)]
+pub fn place_pegged_order_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    base_atoms: u64,
+    is_bid: bool,
+    reference: PegReference,
+    offset_bps: i16,
+    limit_price_mantissa: u32,
+    limit_price_exponent: i8,
+    last_valid_slot: u32,
+    trader_index_hint: Option<DataIndex>,
+) -> Instruction {
+    let (pegged_orders, _pegged_orders_bump) = get_pegged_orders_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*trader, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(pegged_orders, false),
+        ],
+        data: [
+            ManifestInstruction::PlacePeggedOrder.to_vec(),
+            PlacePeggedOrderParams::new(
+                base_atoms,
+                is_bid,
+                reference,
+                offset_bps,
+                limit_price_mantissa,
+                limit_price_exponent,
+                last_valid_slot,
+                trader_index_hint,
+            )
+            .try_to_vec()
+            .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn cancel_pegged_order_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    pegged_index: u8,
+) -> Instruction {
+    let (pegged_orders, _pegged_orders_bump) = get_pegged_orders_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*trader, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(pegged_orders, false),
+        ],
+        data: [
+            ManifestInstruction::CancelPeggedOrder.to_vec(),
+            CancelPeggedOrderParams::new(pegged_index)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+/// Move the pegged orders of trader to their prices against the book. Anyone
+/// can send it, payer only pays for market expansion.
+pub fn reprice_pegged_orders_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (pegged_orders, _pegged_orders_bump) = get_pegged_orders_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(pegged_orders, false),
+        ],
+        data: ManifestInstruction::RepricePeggedOrders.to_vec(),
+    }
+}
@@ -0,0 +1,36 @@
+use crate::program::{rebalance::RebalanceParams, ManifestInstruction};
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+/// Trade the seat of payer towards target_base_atoms against the book. The
+/// cosigner of the seat must be included when the trade is above its cosign
+/// threshold.
+pub fn rebalance_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    target_base_atoms: u64,
+    max_slippage_bps: u16,
+    cosigner_opt: Option<&Pubkey>,
+) -> Instruction {
+    let mut account_metas: Vec<AccountMeta> = vec![
+        AccountMeta::new_readonly(*payer, true),
+        AccountMeta::new(*market, false),
+    ];
+    if let Some(cosigner) = cosigner_opt {
+        account_metas.push(AccountMeta::new_readonly(*cosigner, true));
+    }
+    Instruction {
+        program_id: crate::id(),
+        accounts: account_metas,
+        data: [
+            ManifestInstruction::Rebalance.to_vec(),
+            RebalanceParams::new(target_base_atoms, max_slippage_bps)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
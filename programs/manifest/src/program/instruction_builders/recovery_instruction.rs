@@ -0,0 +1,46 @@
+use crate::{program::ManifestInstruction, validation::get_vault_address};
+use solana_program::{
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+/// Put market in recovery. Signed by the upgrade authority of the program.
+pub fn enter_recovery_instruction(market: &Pubkey, authority: &Pubkey) -> Instruction {
+    let (program_data, _program_data_bump) =
+        Pubkey::find_program_address(&[crate::id().as_ref()], &bpf_loader_upgradeable::id());
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(program_data, false),
+        ],
+        data: ManifestInstruction::EnterRecovery.to_vec(),
+    }
+}
+
+/// Withdraw the share of the mint vault owed to the seat of payer on a market
+/// in recovery.
+pub fn recovery_withdraw_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    trader_token_account: &Pubkey,
+    token_program: Pubkey,
+) -> Instruction {
+    let (vault_address, _) = get_vault_address(market, mint);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*trader_token_account, false),
+            AccountMeta::new(vault_address, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(*mint, false),
+        ],
+        data: ManifestInstruction::RecoveryWithdraw.to_vec(),
+    }
+}
@@ -0,0 +1,18 @@
+use crate::program::ManifestInstruction;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+/// Reset the reference price of the market price band to the middle of the
+/// book.
+pub fn reset_price_band_instruction(market: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*payer, true),
+            AccountMeta::new(*market, false),
+        ],
+        data: ManifestInstruction::ResetPriceBand.to_vec(),
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{
+    program::{restore_seat::RestoreSeatParams, ManifestInstruction},
+    state::SeatArchiveNode,
+    validation::get_seat_archive_address,
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Proof is from seat_archive_proof over every ArchiveSeatLog of the market.
+#[allow(clippy::too_many_arguments)]
+pub fn restore_seat_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader: &Pubkey,
+    base_atoms: u64,
+    quote_atoms: u64,
+    quote_volume: u64,
+    leaf_index: u64,
+    proof: Vec<SeatArchiveNode>,
+) -> Instruction {
+    let (seat_archive, _seat_archive_bump) = get_seat_archive_address(market);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(seat_archive, false),
+        ],
+        data: [
+            ManifestInstruction::RestoreSeat.to_vec(),
+            RestoreSeatParams::new(
+                *trader,
+                base_atoms,
+                quote_atoms,
+                quote_volume,
+                leaf_index,
+                proof,
+            )
+            .try_to_vec()
+            .unwrap(),
+        ]
+        .concat(),
+    }
+}
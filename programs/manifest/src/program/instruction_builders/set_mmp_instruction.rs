@@ -0,0 +1,34 @@
+use crate::program::{set_mmp::SetMmpParams, ManifestInstruction};
+use borsh::BorshSerialize;
+use hypertree::DataIndex;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Set the payer's market maker protection. Zero limits clear it.
+pub fn set_mmp_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    window_slots: u32,
+    max_fills: u32,
+    max_quote_atoms: u64,
+    trader_index_hint: Option<DataIndex>,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [
+            ManifestInstruction::SetMmp.to_vec(),
+            SetMmpParams::new(window_slots, max_fills, max_quote_atoms, trader_index_hint)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
@@ -0,0 +1,22 @@
+use crate::program::{set_paused::SetPausedParams, ManifestInstruction};
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+/// Pause or resume trading on a market. Only its pauser can sign.
+pub fn set_paused_instruction(market: &Pubkey, pauser: &Pubkey, paused: bool) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*pauser, true),
+            AccountMeta::new(*market, false),
+        ],
+        data: [
+            ManifestInstruction::SetPaused.to_vec(),
+            SetPausedParams::new(paused).try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
@@ -0,0 +1,43 @@
+use crate::program::{set_seat_config::SetSeatConfigParams, ManifestInstruction};
+use borsh::BorshSerialize;
+use hypertree::DataIndex;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Setting the cosigner to the default pubkey clears the config. The current
+/// cosigner must be included when the seat already has one.
+pub fn set_seat_config_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    cosigner: &Pubkey,
+    cosign_notional_threshold_atoms: u64,
+    trader_index_hint: Option<DataIndex>,
+    current_cosigner_opt: Option<&Pubkey>,
+) -> Instruction {
+    let mut account_metas: Vec<AccountMeta> = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*market, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if let Some(current_cosigner) = current_cosigner_opt {
+        account_metas.push(AccountMeta::new_readonly(*current_cosigner, true));
+    }
+    Instruction {
+        program_id: crate::id(),
+        accounts: account_metas,
+        data: [
+            ManifestInstruction::SetSeatConfig.to_vec(),
+            SetSeatConfigParams::new(
+                *cosigner,
+                cosign_notional_threshold_atoms,
+                trader_index_hint,
+            )
+            .try_to_vec()
+            .unwrap(),
+        ]
+        .concat(),
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    program::{
+        batch_update::PlaceOrderParams,
+        stop_order::{CancelStopOrderParams, PlaceStopOrderParams},
+        ManifestInstruction,
+    },
+    validation::get_stop_orders_address,
+};
+use borsh::BorshSerialize;
+use hypertree::DataIndex;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Add a stop order for trader that places order once the last trade price
+/// reaches the trigger price.
+pub fn place_stop_order_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    order: PlaceOrderParams,
+    trigger_price_mantissa: u32,
+    trigger_price_exponent: i8,
+    trader_index_hint: Option<DataIndex>,
+) -> Instruction {
+    let (stop_orders, _stop_orders_bump) = get_stop_orders_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*trader, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(stop_orders, false),
+        ],
+        data: [
+            ManifestInstruction::PlaceStopOrder.to_vec(),
+            PlaceStopOrderParams::new(
+                order,
+                trigger_price_mantissa,
+                trigger_price_exponent,
+                trader_index_hint,
+            )
+            .try_to_vec()
+            .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+/// Same as place_stop_order_instruction for a trailing stop, whose trigger
+/// follows later trades at trailing_bps away from them.
+pub fn place_trailing_stop_order_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    order: PlaceOrderParams,
+    trigger_price_mantissa: u32,
+    trigger_price_exponent: i8,
+    trailing_bps: u16,
+    trader_index_hint: Option<DataIndex>,
+) -> Instruction {
+    let mut instruction: Instruction = place_stop_order_instruction(
+        market,
+        trader,
+        order,
+        trigger_price_mantissa,
+        trigger_price_exponent,
+        trader_index_hint,
+    );
+    instruction.data.extend(trailing_bps.to_le_bytes());
+    instruction
+}
+
+pub fn cancel_stop_order_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    stop_index: u8,
+) -> Instruction {
+    let (stop_orders, _stop_orders_bump) = get_stop_orders_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*trader, true),
+            AccountMeta::new(stop_orders, false),
+        ],
+        data: [
+            ManifestInstruction::CancelStopOrder.to_vec(),
+            CancelStopOrderParams::new(stop_index).try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+/// Place the triggered stop orders of trader. Anyone can send it, payer only
+/// pays for market expansion.
+pub fn trigger_stop_orders_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (stop_orders, _stop_orders_bump) = get_stop_orders_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(stop_orders, false),
+        ],
+        data: ManifestInstruction::TriggerStopOrders.to_vec(),
+    }
+}
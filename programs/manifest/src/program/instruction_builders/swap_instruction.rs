@@ -1,6 +1,9 @@
 use crate::{
     program::{swap::SwapParams, ManifestInstruction},
-    validation::{get_global_address, get_global_vault_address, get_vault_address},
+    validation::{
+        get_global_address, get_global_vault_address, get_vault_address,
+        SPL_ASSOCIATED_TOKEN_ACCOUNT_PROGRAM_ID,
+    },
 };
 use borsh::BorshSerialize;
 use solana_program::{
@@ -65,3 +68,85 @@ pub fn swap_instruction(
         .concat(),
     }
 }
+
+/// Appends min_fill_atoms to a swap, swap_v2 or swap_to_seat instruction.
+pub fn with_min_fill_atoms(mut instruction: Instruction, min_fill_atoms: u64) -> Instruction {
+    instruction
+        .data
+        .extend_from_slice(&min_fill_atoms.to_le_bytes());
+    instruction
+}
+
+/// Appends min_fill_atoms and the flags byte to a swap, swap_v2 or
+/// swap_to_seat instruction.
+pub fn with_swap_flags(instruction: Instruction, min_fill_atoms: u64, flags: u8) -> Instruction {
+    let mut instruction: Instruction = with_min_fill_atoms(instruction, min_fill_atoms);
+    instruction.data.push(flags);
+    instruction
+}
+
+/// Makes a swap, swap_v2 or swap_to_seat instruction create the owner's
+/// associated token accounts that do not exist yet, with the payer funding the
+/// rent. Both mints are included since the associated token account program
+/// needs them, and the payer becomes writable.
+pub fn with_create_token_accounts(
+    mut instruction: Instruction,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+) -> Instruction {
+    instruction.accounts[0].is_writable = true;
+    let token_program_index: usize = instruction
+        .accounts
+        .iter()
+        .position(|meta| meta.pubkey == spl_token::id() || meta.pubkey == spl_token_2022::id())
+        .unwrap();
+    let token_program_base: AccountMeta = instruction.accounts[token_program_index].clone();
+    let mut rest: Vec<AccountMeta> = instruction.accounts.split_off(token_program_index + 1);
+    instruction.accounts.pop();
+
+    let token_program_quote_opt: Option<AccountMeta> = rest
+        .iter()
+        .position(|meta| meta.pubkey == spl_token::id() || meta.pubkey == spl_token_2022::id())
+        .map(|index| rest.remove(index));
+    rest.retain(|meta| meta.pubkey != *base_mint && meta.pubkey != *quote_mint);
+
+    instruction.accounts.push(token_program_base);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*base_mint, false));
+    instruction.accounts.extend(token_program_quote_opt);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*quote_mint, false));
+    instruction.accounts.push(AccountMeta::new_readonly(
+        SPL_ASSOCIATED_TOKEN_ACCOUNT_PROGRAM_ID,
+        false,
+    ));
+    instruction.accounts.extend(rest);
+    instruction
+}
+
+/// Makes a swap, swap_v2 or swap_to_seat instruction pay the referral share of
+/// the taker fee to referrer_token_account, which has to be in the output
+/// mint. It goes before the global accounts.
+pub fn with_referrer(
+    mut instruction: Instruction,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    referrer_token_account: &Pubkey,
+) -> Instruction {
+    let global_keys: [Pubkey; 2] = [
+        get_global_address(base_mint).0,
+        get_global_address(quote_mint).0,
+    ];
+    let referrer_index: usize = instruction
+        .accounts
+        .iter()
+        .position(|meta| global_keys.contains(&meta.pubkey))
+        .unwrap_or(instruction.accounts.len());
+    instruction.accounts.insert(
+        referrer_index,
+        AccountMeta::new(*referrer_token_account, false),
+    );
+    instruction
+}
@@ -0,0 +1,41 @@
+use crate::program::{swap_v2_instruction, ManifestInstruction};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+/// Same accounts and params as swap_v2, but output is credited to the owner's
+/// seat if they have one.
+#[allow(clippy::too_many_arguments)]
+pub fn swap_to_seat_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    trader_base_account: &Pubkey,
+    trader_quote_account: &Pubkey,
+    in_atoms: u64,
+    out_atoms: u64,
+    is_base_in: bool,
+    is_exact_in: bool,
+    token_program_base: Pubkey,
+    token_program_quote: Pubkey,
+    include_global: bool,
+) -> Instruction {
+    let mut instruction: Instruction = swap_v2_instruction(
+        market,
+        payer,
+        owner,
+        base_mint,
+        quote_mint,
+        trader_base_account,
+        trader_quote_account,
+        in_atoms,
+        out_atoms,
+        is_base_in,
+        is_exact_in,
+        token_program_base,
+        token_program_quote,
+        include_global,
+    );
+    instruction.data[0] = ManifestInstruction::SwapToSeat as u8;
+    instruction
+}
@@ -0,0 +1,22 @@
+use crate::{program::ManifestInstruction, validation::get_market_volatility_address};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Sample the last trade price of market into its volatility estimate. The
+/// payer funds the market volatility account on the first update.
+pub fn update_volatility_instruction(market: &Pubkey, payer: &Pubkey) -> Instruction {
+    let (market_volatility, _market_volatility_bump) = get_market_volatility_address(market);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(market_volatility, false),
+        ],
+        data: ManifestInstruction::UpdateVolatility.to_vec(),
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{
-    program::{withdraw::WithdrawParams, ManifestInstruction},
+    program::{
+        withdraw::{WithdrawAsQuoteParams, WithdrawParams},
+        ManifestInstruction,
+    },
     validation::get_vault_address,
 };
 use borsh::BorshSerialize;
@@ -39,3 +42,34 @@ pub fn withdraw_instruction(
         .concat(),
     }
 }
+
+pub fn withdraw_as_quote_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    quote_mint: &Pubkey,
+    max_price_impact_bps: u16,
+    trader_token_account: &Pubkey,
+    token_program: Pubkey,
+    trader_index_hint: Option<DataIndex>,
+) -> Instruction {
+    let (vault_address, _) = get_vault_address(market, quote_mint);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*trader_token_account, false),
+            AccountMeta::new(vault_address, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+        ],
+        data: [
+            ManifestInstruction::WithdrawAsQuote.to_vec(),
+            WithdrawAsQuoteParams::new(max_price_impact_bps, trader_index_hint)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
@@ -0,0 +1,119 @@
+use std::{cell::RefMut, mem::size_of};
+
+use crate::{
+    logs::{emit_stack, ArchiveSeatLog},
+    program::ManifestError,
+    quantities::WrapperU64,
+    require,
+    state::{
+        claimed_seat::ClaimedSeat, constants::SEAT_ARCHIVE_MAX_DUST_ATOMS, seat_archive_leaf,
+        utils::assert_not_in_recovery, MarketRefMut, SeatArchive,
+    },
+    utils::create_account,
+    validation::{get_seat_archive_address, loaders::ArchiveSeatContext, ManifestAccountInfo},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{get_mut_helper, trace};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey, rent::Rent,
+    sysvar::Sysvar,
+};
+
+use super::shared::get_mut_dynamic_account;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct ArchiveSeatParams {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub trader: Pubkey,
+}
+
+impl ArchiveSeatParams {
+    pub fn new(trader: Pubkey) -> Self {
+        ArchiveSeatParams { trader }
+    }
+}
+
+/// Moves a dormant seat into the seat archive of the market and puts its block
+/// back on the free list. The market account does not shrink, the block is
+/// reused by the next seat or order instead of growing the market. The payer
+/// funds the seat archive when it is the first archive on the market.
+pub(crate) fn process_archive_seat(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    trace!("process_archive_seat accs={accounts:?}");
+    let params: ArchiveSeatParams = ArchiveSeatParams::try_from_slice(data)?;
+    let archive_seat_context: ArchiveSeatContext = ArchiveSeatContext::load(accounts)?;
+    let ArchiveSeatContext {
+        payer,
+        market,
+        system_program,
+        seat_archive,
+    } = archive_seat_context;
+    let ArchiveSeatParams { trader } = params;
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+
+    if seat_archive.data_is_empty() {
+        let (_expected_seat_archive_key, seat_archive_bump) = get_seat_archive_address(market.key);
+        create_account(
+            payer.as_ref(),
+            seat_archive,
+            system_program.as_ref(),
+            &crate::id(),
+            &Rent::get()?,
+            size_of::<SeatArchive>() as u64,
+            vec![
+                b"seat-archive".to_vec(),
+                market.key.as_ref().to_vec(),
+                vec![seat_archive_bump],
+            ],
+        )?;
+        let seat_archive_bytes: &mut [u8] = &mut seat_archive.try_borrow_mut_data()?[..];
+        *get_mut_helper::<SeatArchive>(seat_archive_bytes, 0_u32) =
+            SeatArchive::new_empty(market.key);
+    }
+    let seat_archive: ManifestAccountInfo<SeatArchive> =
+        ManifestAccountInfo::<SeatArchive>::new(seat_archive)?;
+
+    let claimed_seat: ClaimedSeat = {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.archive_seat(&trader)?
+    };
+    require!(
+        *payer.key == trader
+            || (claimed_seat.base_withdrawable_balance.as_u64() <= SEAT_ARCHIVE_MAX_DUST_ATOMS
+                && claimed_seat.quote_withdrawable_balance.as_u64() <= SEAT_ARCHIVE_MAX_DUST_ATOMS),
+        ManifestError::InvalidSeatArchive,
+        "Only the trader can archive a seat with more than dust",
+    )?;
+
+    let seat_archive_data: &mut RefMut<&mut [u8]> = &mut seat_archive.try_borrow_mut_data()?;
+    let archive: &mut SeatArchive = get_mut_helper::<SeatArchive>(seat_archive_data, 0_u32);
+    let leaf_index: u64 = archive.append(seat_archive_leaf(
+        &trader,
+        claimed_seat.base_withdrawable_balance,
+        claimed_seat.quote_withdrawable_balance,
+        claimed_seat.quote_volume,
+    ))?;
+
+    emit_stack(ArchiveSeatLog {
+        market: *market.key,
+        trader,
+        base_atoms: claimed_seat.base_withdrawable_balance,
+        quote_atoms: claimed_seat.quote_withdrawable_balance,
+        quote_volume: claimed_seat.quote_volume,
+        leaf_index,
+    })?;
+
+    Ok(())
+}
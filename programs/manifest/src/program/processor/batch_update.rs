@@ -2,25 +2,44 @@ use std::cell::RefMut;
 
 use crate::{
     logs::{emit_stack, CancelOrderLog, PlaceOrderLog},
-    program::get_trader_index_with_hint,
-    quantities::{BaseAtoms, PriceConversionError, QuoteAtomsPerBaseAtom, WrapperU64},
+    program::{get_trader_index_with_hint, ManifestError},
+    quantities::{BaseAtoms, PriceConversionError, QuoteAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
     require,
     state::{
-        utils::{get_now_slot, settle_global_gas_refunds, try_to_pay_all_global_gas_prepayment},
-        AddOrderToMarketArgs, AddOrderToMarketResult, MarketRefMut, OrderType, RestingOrder,
-        MARKET_BLOCK_SIZE,
+        order_type_can_rest, order_type_can_take,
+        utils::{
+            assert_not_in_recovery, get_now_slot, get_now_unix_timestamp,
+            resolve_relative_last_valid_slot, settle_global_gas_refunds,
+            try_to_pay_all_global_gas_prepayment,
+        },
+        AddOrderToMarketArgs, AddOrderToMarketResult, MarketFixed, MarketRefMut, OrderType,
+        RestingOrder, SelfTradePrevention, MARKET_BLOCK_SIZE, NO_EXPIRATION_LAST_VALID_SLOT,
     },
-    validation::loaders::BatchUpdateContext,
+    validation::{loaders::BatchUpdateContext, ManifestAccountInfo, Signer},
 };
 use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(not(feature = "certora"))]
+use {
+    super::{batch_expand_market, shared::get_dynamic_account},
+    crate::{
+        logs::ReplaceOrderLog,
+        state::{seat_config::SeatConfig, utils::assert_market_open, MarketRef},
+    },
+    std::cell::Ref,
+};
 
-use hypertree::{get_helper, trace, DataIndex, PodBool, RBNode};
+use hypertree::{get_helper, trace, DataIndex, PodBool, RBNode, NIL};
 use solana_program::{
     account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
     pubkey::Pubkey,
 };
 
-use super::{expand_market_if_needed, shared::get_mut_dynamic_account};
+use super::{
+    expand_market_if_needed,
+    shared::{
+        get_mut_dynamic_account, split_instructions_sysvar, verify_cpi_caller, verify_not_paused,
+    },
+};
 
 use crate::validation::loaders::GlobalTradeAccounts;
 #[cfg(feature = "certora")]
@@ -31,6 +50,10 @@ use {
 };
 
 #[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct CancelOrderParams {
     order_sequence_number: u64,
     order_index_hint: Option<DataIndex>,
@@ -57,7 +80,106 @@ impl CancelOrderParams {
     }
 }
 
+/// Changes the size and optionally the price of a resting order of the payer.
+/// Reducing the size at the same price keeps the order's queue priority, zero
+/// base atoms cancels it. Anything else gives it a new sequence number.
 #[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct ReplaceOrderParams {
+    order_sequence_number: u64,
+    order_index_hint: Option<DataIndex>,
+    base_atoms: u64,
+    /// New price mantissa and exponent, None to keep the price.
+    price: Option<(u32, i8)>,
+}
+
+impl ReplaceOrderParams {
+    pub fn new(
+        order_sequence_number: u64,
+        order_index_hint: Option<DataIndex>,
+        base_atoms: u64,
+        price: Option<(u32, i8)>,
+    ) -> Self {
+        ReplaceOrderParams {
+            order_sequence_number,
+            order_index_hint,
+            base_atoms,
+            price,
+        }
+    }
+    pub fn order_sequence_number(&self) -> u64 {
+        self.order_sequence_number
+    }
+    pub fn order_index_hint(&self) -> Option<DataIndex> {
+        self.order_index_hint
+    }
+    pub fn base_atoms(&self) -> u64 {
+        self.base_atoms
+    }
+
+    /// New price of an order of order_type, None to keep the price.
+    pub fn try_price(
+        &self,
+        order_type: OrderType,
+    ) -> Result<Option<QuoteAtomsPerBaseAtom>, PriceConversionError> {
+        let (price_mantissa, price_exponent) = match self.price {
+            Some(price) => price,
+            None => return Ok(None),
+        };
+        if price_exponent > order_type.max_exponent() {
+            return Err(PriceConversionError(0x3));
+        }
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(price_mantissa, price_exponent)
+            .map(Some)
+    }
+}
+
+/// Reduces a resting order of the payer by reduce_base_atoms in place, so it
+/// keeps its sequence number and queue priority. Unlike a replace it never
+/// adds size, even when the order was partly filled since it was sent.
+/// Reducing by at least what is left cancels the order.
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct AmendOrderParams {
+    order_sequence_number: u64,
+    order_index_hint: Option<DataIndex>,
+    reduce_base_atoms: u64,
+}
+
+impl AmendOrderParams {
+    pub fn new(
+        order_sequence_number: u64,
+        order_index_hint: Option<DataIndex>,
+        reduce_base_atoms: u64,
+    ) -> Self {
+        AmendOrderParams {
+            order_sequence_number,
+            order_index_hint,
+            reduce_base_atoms,
+        }
+    }
+    pub fn order_sequence_number(&self) -> u64 {
+        self.order_sequence_number
+    }
+    pub fn order_index_hint(&self) -> Option<DataIndex> {
+        self.order_index_hint
+    }
+    pub fn reduce_base_atoms(&self) -> u64 {
+        self.reduce_base_atoms
+    }
+}
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct PlaceOrderParams {
     base_atoms: u64,
     price_mantissa: u32,
@@ -107,9 +229,22 @@ impl PlaceOrderParams {
     pub fn order_type(&self) -> OrderType {
         self.order_type
     }
+
+    /// Base atoms that quote_atoms buy or sell at the limit price, rounded
+    /// down so they never cost or return more than quote_atoms at that price.
+    pub fn base_atoms_for_quote(&self, quote_atoms: u64) -> Result<u64, ProgramError> {
+        Ok(self
+            .try_price()?
+            .checked_base_for_quote(QuoteAtoms::new(quote_atoms), false)?
+            .as_u64())
+    }
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct BatchUpdateParams {
     /// Optional hint for what index the trader's ClaimedSeat is at.
     pub trader_index_hint: Option<DataIndex>,
@@ -139,9 +274,70 @@ impl BatchUpdateParams {
     }
 }
 
+/// Bits of the optional trailing flags byte after the BatchUpdateParams.
+///
+/// Have the payer fund a block for every place up front.
+pub const BATCH_UPDATE_RESERVE_BLOCKS: u8 = 1 << 0;
+/// Read last_valid_slot of every non reverse place as the number of slots the
+/// order stays valid for after the slot the transaction lands in.
+pub const BATCH_UPDATE_RELATIVE_EXPIRY: u8 = 1 << 1;
+/// Cancel every resting order of the payer before the cancels and places.
+pub const BATCH_UPDATE_CANCEL_ALL: u8 = 1 << 2;
+/// The flags byte is followed by a Vec<ReplaceOrderParams>, applied after the
+/// cancels and before the places.
+pub const BATCH_UPDATE_REPLACES: u8 = 1 << 3;
+/// The flags byte is followed, after the replaces if any, by a Vec<u64> with
+/// one min_fill_base_atoms per place. An ImmediateOrCancel place that fills
+/// less than it fails the instruction. Other places must have zero.
+pub const BATCH_UPDATE_IOC_MIN_FILLS: u8 = 1 << 4;
+/// The flags byte is followed, after the IOC min fills if any, by a
+/// Vec<SelfTradePrevention> with one policy per place, applied when the place
+/// would match a resting order from the payer's own seat.
+pub const BATCH_UPDATE_SELF_TRADE_PREVENTION: u8 = 1 << 5;
+/// The flags byte is followed, after the self trade preventions if any, by a
+/// Vec<(u8, u8)> of pairs of place indices. The two orders of a pair are
+/// linked once both rest, so a fill on one cancels the other.
+pub const BATCH_UPDATE_ONE_CANCELS_OTHER: u8 = 1 << 6;
+/// The flags byte is followed by a second flags byte with the bits below.
+pub const BATCH_UPDATE_MORE_FLAGS: u8 = 1 << 7;
+
+/// Bits of the second flags byte.
+///
+/// The flags are followed, after the one cancels other pairs if any, by a
+/// Vec<u64> with one display size per place. Iceberg places need one and
+/// other places must have zero.
+pub const BATCH_UPDATE_DISPLAY_SIZES: u8 = 1 << 0;
+/// The flags are followed, after the display sizes if any, by a
+/// Vec<AmendOrderParams>, applied after the cancels and before the replaces.
+pub const BATCH_UPDATE_AMENDS: u8 = 1 << 1;
+/// The flags are followed, after the amends if any, by a Vec<u64> with one
+/// quote size per place. A place with a quote size has zero base atoms and is
+/// sized to the base atoms the quote size buys or sells at its limit price.
+pub const BATCH_UPDATE_QUOTE_SIZES: u8 = 1 << 2;
+/// The flags are followed, after the quote sizes if any, by a Vec<u32> with
+/// one last valid unix timestamp per place, zero for none. Places with one
+/// cannot reverse or have a last_valid_slot, and expire by the clock instead.
+pub const BATCH_UPDATE_LAST_VALID_UNIX_TIMESTAMPS: u8 = 1 << 3;
+/// The flags are followed, after the timestamps if any, by a u64
+/// min_fill_atoms that applies to every place that cannot rest. Fills against
+/// a single maker smaller than it are skipped.
+pub const BATCH_UPDATE_MIN_FILL_ATOMS: u8 = 1 << 4;
+/// The flags are followed, after min_fill_atoms if any, by a u64
+/// min_taker_atoms stored on every order that rests, so takers filling less
+/// than it skip the order.
+pub const BATCH_UPDATE_MIN_TAKER_ATOMS: u8 = 1 << 5;
+/// The flags are followed, after min_taker_atoms if any, by a Vec<u32> with
+/// one tag per place, stored on the order and echoed in its fills.
+pub const BATCH_UPDATE_TAGS: u8 = 1 << 6;
+
 #[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct BatchUpdateReturn {
-    /// Vector of tuples of (order_sequence_number, DataIndex)
+    /// Vector of tuples of (order_sequence_number, DataIndex), one per place
+    /// followed by one per replace. Replaces that cancel have a NIL index.
     pub orders: Vec<(u64, DataIndex)>,
 }
 
@@ -153,6 +349,16 @@ pub enum MarketDataTreeNodeType {
     #[default]
     ClaimedSeat = 1,
     RestingOrder = 2,
+    SeatConfig = 3,
+    CpiAllowlist = 4,
+    MinOrderSize = 5,
+    PriceBand = 6,
+    MarketFees = 7,
+    SeatVolume = 8,
+    SeatMmp = 9,
+    MarketStats = 10,
+    MarketExtension = 11,
+    FillRecords = 12,
 }
 
 pub(crate) fn process_batch_update(
@@ -160,8 +366,189 @@ pub(crate) fn process_batch_update(
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
-    let params: BatchUpdateParams = BatchUpdateParams::try_from_slice(data)?;
-    process_batch_update_core(program_id, accounts, params)
+    let mut params_data: &[u8] = data;
+    let mut params: BatchUpdateParams = BatchUpdateParams::deserialize(&mut params_data)?;
+    // Optional trailing flags bytes, replaces, IOC min fills, self trade
+    // prevention, one cancels other pairs, display sizes, amends, quote
+    // sizes, last valid unix timestamps, min_fill_atoms, min_taker_atoms and
+    // tags, each only present when its flag bit is set. The first byte has
+    // no unused bits left, so new options go in the second, where unknown
+    // bits are rejected so they can be given a meaning later.
+    let num_places: usize = params.orders.len();
+    let flags: u8 = match params_data.split_first() {
+        Some((flags, rest)) => {
+            params_data = rest;
+            *flags
+        }
+        None => 0,
+    };
+    let more_flags: u8 = if flags & BATCH_UPDATE_MORE_FLAGS != 0 {
+        let (more_flags, rest) = params_data
+            .split_first()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        params_data = rest;
+        *more_flags
+    } else {
+        0
+    };
+    if more_flags
+        & !(BATCH_UPDATE_DISPLAY_SIZES
+            | BATCH_UPDATE_AMENDS
+            | BATCH_UPDATE_QUOTE_SIZES
+            | BATCH_UPDATE_LAST_VALID_UNIX_TIMESTAMPS
+            | BATCH_UPDATE_MIN_FILL_ATOMS
+            | BATCH_UPDATE_MIN_TAKER_ATOMS
+            | BATCH_UPDATE_TAGS)
+        != 0
+    {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let replaces: Vec<ReplaceOrderParams> = if flags & BATCH_UPDATE_REPLACES != 0 {
+        Vec::<ReplaceOrderParams>::deserialize(&mut params_data)?
+    } else {
+        Vec::new()
+    };
+    let ioc_min_fills: Vec<u64> = if flags & BATCH_UPDATE_IOC_MIN_FILLS != 0 {
+        let ioc_min_fills: Vec<u64> = Vec::<u64>::deserialize(&mut params_data)?;
+        if ioc_min_fills.len() != num_places {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        ioc_min_fills
+    } else {
+        Vec::new()
+    };
+    let self_trade_preventions: Vec<SelfTradePrevention> =
+        if flags & BATCH_UPDATE_SELF_TRADE_PREVENTION != 0 {
+            let self_trade_preventions: Vec<SelfTradePrevention> =
+                Vec::<SelfTradePrevention>::deserialize(&mut params_data)?;
+            if self_trade_preventions.len() != num_places {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            self_trade_preventions
+        } else {
+            Vec::new()
+        };
+    let one_cancels_others: Vec<(u8, u8)> = if flags & BATCH_UPDATE_ONE_CANCELS_OTHER != 0 {
+        let one_cancels_others: Vec<(u8, u8)> = Vec::<(u8, u8)>::deserialize(&mut params_data)?;
+        // Each place has room for one link.
+        let mut is_paired: Vec<bool> = vec![false; num_places];
+        for &(first, second) in one_cancels_others.iter() {
+            for place_index in [first, second] {
+                match is_paired.get_mut(place_index as usize) {
+                    Some(is_paired) if !*is_paired => *is_paired = true,
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                }
+            }
+        }
+        one_cancels_others
+    } else {
+        Vec::new()
+    };
+    let display_sizes: Vec<u64> = if more_flags & BATCH_UPDATE_DISPLAY_SIZES != 0 {
+        let display_sizes: Vec<u64> = Vec::<u64>::deserialize(&mut params_data)?;
+        if display_sizes.len() != num_places {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        display_sizes
+    } else {
+        Vec::new()
+    };
+    let amends: Vec<AmendOrderParams> = if more_flags & BATCH_UPDATE_AMENDS != 0 {
+        Vec::<AmendOrderParams>::deserialize(&mut params_data)?
+    } else {
+        Vec::new()
+    };
+    if more_flags & BATCH_UPDATE_QUOTE_SIZES != 0 {
+        let quote_sizes: Vec<u64> = Vec::<u64>::deserialize(&mut params_data)?;
+        if quote_sizes.len() != num_places {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        resolve_quote_sizes(&mut params, &quote_sizes)?;
+    }
+    let last_valid_unix_timestamps: Vec<u32> =
+        if more_flags & BATCH_UPDATE_LAST_VALID_UNIX_TIMESTAMPS != 0 {
+            let last_valid_unix_timestamps: Vec<u32> = Vec::<u32>::deserialize(&mut params_data)?;
+            if last_valid_unix_timestamps.len() != num_places {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            last_valid_unix_timestamps
+        } else {
+            Vec::new()
+        };
+    let min_fill_atoms: u64 = if more_flags & BATCH_UPDATE_MIN_FILL_ATOMS != 0 {
+        u64::deserialize(&mut params_data)?
+    } else {
+        0
+    };
+    let min_taker_atoms: u64 = if more_flags & BATCH_UPDATE_MIN_TAKER_ATOMS != 0 {
+        u64::deserialize(&mut params_data)?
+    } else {
+        0
+    };
+    let tags: Vec<u32> = if more_flags & BATCH_UPDATE_TAGS != 0 {
+        let tags: Vec<u32> = Vec::<u32>::deserialize(&mut params_data)?;
+        if tags.len() != num_places {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        tags
+    } else {
+        Vec::new()
+    };
+    // Anything after the data of the set flags is not an option.
+    if !params_data.is_empty() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    process_batch_update_core(
+        program_id,
+        accounts,
+        params,
+        flags & BATCH_UPDATE_RESERVE_BLOCKS != 0,
+        flags & BATCH_UPDATE_RELATIVE_EXPIRY != 0,
+        flags & BATCH_UPDATE_CANCEL_ALL != 0,
+        &amends,
+        &replaces,
+        BaseAtoms::new(min_fill_atoms),
+        BaseAtoms::new(min_taker_atoms),
+        &tags,
+        &ioc_min_fills,
+        &self_trade_preventions,
+        &one_cancels_others,
+        &display_sizes,
+        &last_valid_unix_timestamps,
+    )
+}
+
+/// Sizes every place with a quote size by its limit price, before anything
+/// else reads the base atoms of the places.
+#[cfg(not(feature = "certora"))]
+fn resolve_quote_sizes(params: &mut BatchUpdateParams, quote_sizes: &[u64]) -> ProgramResult {
+    for (place_order_params, &quote_atoms) in params.orders.iter_mut().zip(quote_sizes) {
+        if quote_atoms == 0 {
+            continue;
+        }
+        require!(
+            place_order_params.base_atoms == 0,
+            ManifestError::InvalidPlaceOrderFromWalletParams,
+            "Quote size {} on an order with {} base atoms",
+            quote_atoms,
+            place_order_params.base_atoms,
+        )?;
+        let base_atoms: u64 = place_order_params.base_atoms_for_quote(quote_atoms)?;
+        require!(
+            base_atoms > 0,
+            ManifestError::InvalidPlaceOrderFromWalletParams,
+            "Quote size {} is less than a base atom",
+            quote_atoms,
+        )?;
+        place_order_params.base_atoms = base_atoms;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "certora")]
+fn resolve_quote_sizes(_params: &mut BatchUpdateParams, _quote_sizes: &[u64]) -> ProgramResult {
+    // Formal verification does not cover quote sizes.
+    Ok(())
 }
 
 #[cfg(not(feature = "certora"))]
@@ -170,12 +557,19 @@ fn batch_cancel_order(
     trader_index: DataIndex,
     order_sequence_number: u64,
     global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+    now_slot: u32,
 ) -> ProgramResult {
-    dynamic_account.cancel_order(
-        trader_index,
-        order_sequence_number,
-        &global_trade_accounts_opts,
-    )
+    if dynamic_account.fixed.get_min_resting_slots() == 0 {
+        return dynamic_account.cancel_order(
+            trader_index,
+            order_sequence_number,
+            global_trade_accounts_opts,
+        );
+    }
+    let order_index: DataIndex =
+        dynamic_account.get_order_index_to_cancel(trader_index, order_sequence_number)?;
+    verify_min_resting_time(dynamic_account, order_index, now_slot)?;
+    dynamic_account.cancel_order_by_index(order_index, global_trade_accounts_opts)
 }
 
 #[cfg(feature = "certora")]
@@ -184,6 +578,7 @@ fn batch_cancel_order(
     trader_index: DataIndex,
     order_sequence_number: u64,
     global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+    _now_slot: u32,
 ) -> ProgramResult {
     mock_cancel_order(
         &dynamic_account,
@@ -193,11 +588,261 @@ fn batch_cancel_order(
     )
 }
 
+/// Cancels every resting order of the trader. On markets with a minimum
+/// resting time, orders that have not rested long enough stay on the book
+/// instead of failing the batch.
+#[cfg(not(feature = "certora"))]
+fn batch_cancel_all(
+    dynamic_account: &mut MarketRefMut,
+    market: &Pubkey,
+    trader: &Pubkey,
+    trader_index: DataIndex,
+    global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+    now_slot: u32,
+) -> ProgramResult {
+    let min_resting_slots: u32 = dynamic_account.fixed.get_min_resting_slots();
+    for order_index in dynamic_account.get_trader_order_indices(trader_index) {
+        let order: &RestingOrder = dynamic_account.get_order_by_index(order_index);
+        if !order.has_rested(now_slot, min_resting_slots) {
+            continue;
+        }
+        let order_sequence_number: u64 = order.get_sequence_number();
+        dynamic_account.cancel_order_by_index(order_index, global_trade_accounts_opts)?;
+        emit_stack(CancelOrderLog {
+            market: *market,
+            trader: *trader,
+            order_sequence_number,
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "certora")]
+fn batch_cancel_all(
+    _dynamic_account: &mut MarketRefMut,
+    _market: &Pubkey,
+    _trader: &Pubkey,
+    _trader_index: DataIndex,
+    _global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+    _now_slot: u32,
+) -> ProgramResult {
+    Ok(())
+}
+
+/// Checks that a hinted index holds the trader's order with
+/// order_sequence_number, since the hint comes from the caller.
+fn verify_order_index_hint(
+    dynamic_account: &MarketRefMut,
+    trader_index: DataIndex,
+    order_sequence_number: u64,
+    hinted_index: DataIndex,
+) -> ProgramResult {
+    // Simple sanity check on the hint given. Make sure that it aligns with
+    // block boundaries before reading it as an order.
+    require!(
+        hinted_index % (MARKET_BLOCK_SIZE as DataIndex) == 0,
+        crate::program::ManifestError::WrongIndexHintParams,
+        "Invalid cancel hint index {}",
+        hinted_index,
+    )?;
+    require!(
+        get_helper::<RBNode<RestingOrder>>(&dynamic_account.dynamic, hinted_index)
+            .get_payload_type()
+            == MarketDataTreeNodeType::RestingOrder as u8,
+        crate::program::ManifestError::WrongIndexHintParams,
+        "Invalid cancel hint index {}",
+        hinted_index,
+    )?;
+    let order: &RestingOrder = dynamic_account.get_order_by_index(hinted_index);
+    require!(
+        trader_index == order.get_trader_index(),
+        crate::program::ManifestError::WrongIndexHintParams,
+        "Invalid cancel hint index {}",
+        hinted_index,
+    )?;
+    require!(
+        order_sequence_number == order.get_sequence_number(),
+        crate::program::ManifestError::WrongIndexHintParams,
+        "Invalid cancel hint sequence number index {}",
+        hinted_index,
+    )?;
+    Ok(())
+}
+
+/// Applies the amends, which only ever reduce or cancel orders.
+#[cfg(not(feature = "certora"))]
+fn batch_amend_orders(
+    dynamic_account: &mut MarketRefMut,
+    market: &Pubkey,
+    trader: &Pubkey,
+    trader_index: DataIndex,
+    amends: &[AmendOrderParams],
+    global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+    now_slot: u32,
+) -> ProgramResult {
+    for amend_order_params in amends {
+        let order_sequence_number: u64 = amend_order_params.order_sequence_number();
+        let order_index: DataIndex = match amend_order_params.order_index_hint() {
+            None => {
+                dynamic_account.get_order_index_to_cancel(trader_index, order_sequence_number)?
+            }
+            Some(hinted_index) => {
+                verify_order_index_hint(
+                    dynamic_account,
+                    trader_index,
+                    order_sequence_number,
+                    hinted_index,
+                )?;
+                hinted_index
+            }
+        };
+        verify_min_resting_time(dynamic_account, order_index, now_slot)?;
+
+        let order: &RestingOrder = dynamic_account.get_order_by_index(order_index);
+        let price: QuoteAtomsPerBaseAtom = order.get_price();
+        let base_atoms: BaseAtoms = BaseAtoms::new(
+            order
+                .get_num_base_atoms()
+                .as_u64()
+                .saturating_sub(amend_order_params.reduce_base_atoms()),
+        );
+        if base_atoms == BaseAtoms::ZERO {
+            dynamic_account.cancel_order_by_index(order_index, global_trade_accounts_opts)?;
+            emit_stack(CancelOrderLog {
+                market: *market,
+                trader: *trader,
+                order_sequence_number,
+            })?;
+            continue;
+        }
+
+        dynamic_account.verify_min_order_size(base_atoms, price)?;
+        // Same price and no more size, so this keeps the sequence number.
+        dynamic_account.replace_order_by_index(order_index, base_atoms, price, now_slot)?;
+        emit_stack(ReplaceOrderLog {
+            market: *market,
+            trader: *trader,
+            price,
+            base_atoms,
+            old_order_sequence_number: order_sequence_number,
+            order_sequence_number,
+            order_index,
+            _padding: [0; 4],
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "certora")]
+fn batch_amend_orders(
+    _dynamic_account: &mut MarketRefMut,
+    _market: &Pubkey,
+    _trader: &Pubkey,
+    _trader_index: DataIndex,
+    _amends: &[AmendOrderParams],
+    _global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+    _now_slot: u32,
+) -> ProgramResult {
+    Ok(())
+}
+
+/// Applies the replaces and pushes the (order_sequence_number, order_index)
+/// of each to results. Returns the quote notional of the replaces that add
+/// size or move the price, which need the market open and count towards the
+/// cosigner threshold like places.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+fn batch_replace_orders(
+    dynamic_account: &mut MarketRefMut,
+    market: &Pubkey,
+    trader: &Pubkey,
+    trader_index: DataIndex,
+    replaces: &[ReplaceOrderParams],
+    global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+    now_slot: u32,
+    results: &mut Vec<(u64, DataIndex)>,
+) -> Result<QuoteAtoms, ProgramError> {
+    let mut notional: QuoteAtoms = QuoteAtoms::ZERO;
+    for replace_order_params in replaces {
+        let old_order_sequence_number: u64 = replace_order_params.order_sequence_number();
+        let order_index: DataIndex = match replace_order_params.order_index_hint() {
+            None => dynamic_account
+                .get_order_index_to_cancel(trader_index, old_order_sequence_number)?,
+            Some(hinted_index) => {
+                verify_order_index_hint(
+                    dynamic_account,
+                    trader_index,
+                    old_order_sequence_number,
+                    hinted_index,
+                )?;
+                hinted_index
+            }
+        };
+        verify_min_resting_time(dynamic_account, order_index, now_slot)?;
+
+        let base_atoms: BaseAtoms = BaseAtoms::new(replace_order_params.base_atoms());
+        if base_atoms == BaseAtoms::ZERO {
+            dynamic_account.cancel_order_by_index(order_index, global_trade_accounts_opts)?;
+            emit_stack(CancelOrderLog {
+                market: *market,
+                trader: *trader,
+                order_sequence_number: old_order_sequence_number,
+            })?;
+            results.push((old_order_sequence_number, NIL));
+            continue;
+        }
+
+        let order: &RestingOrder = dynamic_account.get_order_by_index(order_index);
+        let price: QuoteAtomsPerBaseAtom = replace_order_params
+            .try_price(order.get_order_type())?
+            .unwrap_or(order.get_price());
+        if price != order.get_price() || base_atoms > order.get_num_base_atoms() {
+            assert_market_open(&dynamic_account.fixed.get_trading_hours())?;
+            dynamic_account.verify_not_paused()?;
+            notional = notional.saturating_add(price.checked_quote_for_base(base_atoms, false)?);
+        }
+        dynamic_account.verify_min_order_size(base_atoms, price)?;
+        let order_sequence_number: u64 =
+            dynamic_account.replace_order_by_index(order_index, base_atoms, price, now_slot)?;
+        emit_stack(ReplaceOrderLog {
+            market: *market,
+            trader: *trader,
+            price,
+            base_atoms,
+            old_order_sequence_number,
+            order_sequence_number,
+            order_index,
+            _padding: [0; 4],
+        })?;
+        results.push((order_sequence_number, order_index));
+    }
+    Ok(notional)
+}
+
+#[cfg(feature = "certora")]
+#[allow(clippy::too_many_arguments)]
+fn batch_replace_orders(
+    _dynamic_account: &mut MarketRefMut,
+    _market: &Pubkey,
+    _trader: &Pubkey,
+    _trader_index: DataIndex,
+    _replaces: &[ReplaceOrderParams],
+    _global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+    _now_slot: u32,
+    _results: &mut NoResizableVec<(u64, DataIndex)>,
+) -> Result<QuoteAtoms, ProgramError> {
+    Ok(QuoteAtoms::ZERO)
+}
+
 #[cfg(not(feature = "certora"))]
 fn batch_place_order(
     dynamic_account: &mut MarketRefMut,
     args: AddOrderToMarketArgs,
 ) -> Result<AddOrderToMarketResult, ProgramError> {
+    // Orders that cannot rest cannot leave dust on the book.
+    if order_type_can_rest(args.order_type) {
+        dynamic_account.verify_min_order_size(args.num_base_atoms, args.price)?;
+    }
     dynamic_account.place_order(args)
 }
 
@@ -209,18 +854,312 @@ fn batch_place_order(
     mock_place_order(dynamic_account, args)
 }
 
+/// Global orders would need their gas prepayment refunded by whichever fill
+/// cancels them and reverse orders come back on the other side once filled,
+/// so neither can be in a one cancels other pair.
+fn verify_one_cancels_others(
+    orders: &[PlaceOrderParams],
+    one_cancels_others: &[(u8, u8)],
+) -> ProgramResult {
+    for &(first, second) in one_cancels_others {
+        for place_index in [first, second] {
+            let order_type: OrderType = orders
+                .get(place_index as usize)
+                .ok_or(ProgramError::InvalidInstructionData)?
+                .order_type();
+            require!(
+                order_type != OrderType::Global && !order_type.is_reversible(),
+                ManifestError::InvalidPlaceOrderFromWalletParams,
+                "One cancels other on a {:?} order",
+                order_type,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Links the two places of every one cancels other pair. When one of them
+/// already traded, whether while being placed or against a later place of the
+/// batch, the other is cancelled instead. An order that left the book counts
+/// as traded.
+#[cfg(not(feature = "certora"))]
+fn batch_link_one_cancels_others(
+    dynamic_account: &mut MarketRefMut,
+    market: &Pubkey,
+    payer: &Pubkey,
+    one_cancels_others: &[(u8, u8)],
+    placed: &[(u64, DataIndex, BaseAtoms, BaseAtoms)],
+    global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+) -> ProgramResult {
+    // Whether the place is on the book and whether it traded.
+    let get_state = |dynamic_account: &MarketRefMut, place_index: u8| -> (bool, bool) {
+        let (order_sequence_number, order_index, base_atoms, base_atoms_traded) =
+            placed[place_index as usize];
+        if order_index == NIL {
+            return (false, base_atoms_traded > BaseAtoms::ZERO);
+        }
+        let is_on_book: bool =
+            get_helper::<RBNode<RestingOrder>>(&dynamic_account.dynamic, order_index)
+                .get_payload_type()
+                == MarketDataTreeNodeType::RestingOrder as u8
+                && dynamic_account
+                    .get_order_by_index(order_index)
+                    .get_sequence_number()
+                    == order_sequence_number;
+        let is_untouched: bool = is_on_book
+            && base_atoms_traded == BaseAtoms::ZERO
+            && dynamic_account
+                .get_order_by_index(order_index)
+                .get_num_base_atoms()
+                == base_atoms;
+        (is_on_book, !is_untouched)
+    };
+
+    for &(first, second) in one_cancels_others {
+        let (first_is_on_book, first_traded) = get_state(dynamic_account, first);
+        let (second_is_on_book, second_traded) = get_state(dynamic_account, second);
+        let cancel_index: u8 = if first_traded && second_is_on_book {
+            second
+        } else if second_traded && first_is_on_book {
+            first
+        } else {
+            if first_is_on_book && second_is_on_book {
+                dynamic_account.link_orders(placed[first as usize].1, placed[second as usize].1);
+            }
+            continue;
+        };
+        let (order_sequence_number, order_index, _, _) = placed[cancel_index as usize];
+        dynamic_account.cancel_order_by_index(order_index, global_trade_accounts_opts)?;
+        emit_stack(CancelOrderLog {
+            market: *market,
+            trader: *payer,
+            order_sequence_number,
+        })?;
+    }
+    Ok(())
+}
+
+// Formal verification does not model one cancels other links.
+#[cfg(feature = "certora")]
+fn batch_link_one_cancels_others(
+    _dynamic_account: &mut MarketRefMut,
+    _market: &Pubkey,
+    _payer: &Pubkey,
+    _one_cancels_others: &[(u8, u8)],
+    _placed: &[(u64, DataIndex, BaseAtoms, BaseAtoms)],
+    _global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+) -> ProgramResult {
+    Ok(())
+}
+
+/// Seats with a SeatConfig need the cosigner to sign when the quote notional of
+/// all orders placed in the batch is above the configured threshold. Cancels do
+/// not count since they only reduce exposure, replaces count with
+/// replaced_notional.
+#[cfg(not(feature = "certora"))]
+fn verify_cosigner(
+    market: &ManifestAccountInfo<MarketFixed>,
+    trader_index: DataIndex,
+    orders: &[PlaceOrderParams],
+    replaced_notional: QuoteAtoms,
+    cosigner_opt: &Option<Signer>,
+) -> ProgramResult {
+    let market_data: &Ref<&mut [u8]> = &market.try_borrow_data()?;
+    let dynamic_account: MarketRef = get_dynamic_account(market_data);
+    let seat_config: &SeatConfig = match dynamic_account.get_seat_config(trader_index) {
+        Some(seat_config) => seat_config,
+        None => return Ok(()),
+    };
+
+    let mut notional: QuoteAtoms = replaced_notional;
+    for place_order_params in orders {
+        let price: QuoteAtomsPerBaseAtom = place_order_params.try_price()?;
+        notional = notional.saturating_add(
+            price.checked_quote_for_base(BaseAtoms::new(place_order_params.base_atoms()), false)?,
+        );
+    }
+    if !seat_config.requires_cosigner(notional) {
+        return Ok(());
+    }
+    require!(
+        cosigner_opt
+            .as_ref()
+            .is_some_and(|cosigner| *cosigner.key == seat_config.cosigner),
+        ManifestError::MissingCosigner,
+        "Cosigner {} required for notional {}",
+        seat_config.cosigner,
+        notional,
+    )?;
+    Ok(())
+}
+
+#[cfg(feature = "certora")]
+fn verify_cosigner(
+    _market: &ManifestAccountInfo<MarketFixed>,
+    _trader_index: DataIndex,
+    _orders: &NoResizableVec<PlaceOrderParams>,
+    _replaced_notional: QuoteAtoms,
+    _cosigner_opt: &Option<Signer>,
+) -> ProgramResult {
+    Ok(())
+}
+
+/// Markets with a minimum resting time only let makers cancel orders that
+/// have been on the book that long, so quotes cannot be flashed and pulled.
+fn verify_min_resting_time(
+    dynamic_account: &MarketRefMut,
+    order_index: DataIndex,
+    now_slot: u32,
+) -> ProgramResult {
+    let min_resting_slots: u32 = dynamic_account.fixed.get_min_resting_slots();
+    let order: &RestingOrder = dynamic_account.get_order_by_index(order_index);
+    require!(
+        order.has_rested(now_slot, min_resting_slots),
+        crate::program::ManifestError::InvalidCancel,
+        "Order placed at slot {} cannot be cancelled before resting {} slots",
+        order.get_placed_slot(),
+        min_resting_slots,
+    )?;
+    Ok(())
+}
+
+/// Markets with a taker delay only take through committed swaps, so orders
+/// placed here have to be ones that cannot take.
+#[cfg(not(feature = "certora"))]
+fn verify_taker_delay(
+    market: &ManifestAccountInfo<MarketFixed>,
+    orders: &[PlaceOrderParams],
+) -> ProgramResult {
+    if orders.is_empty() || market.get_fixed()?.get_taker_delay_slots() == 0 {
+        return Ok(());
+    }
+    for order in orders {
+        require!(
+            !order_type_can_take(order.order_type()),
+            crate::program::ManifestError::InvalidSwapCommitment,
+            "Market has a taker delay, only post only and global orders can be placed",
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "certora")]
+fn verify_taker_delay(
+    _market: &ManifestAccountInfo<MarketFixed>,
+    _orders: &NoResizableVec<PlaceOrderParams>,
+) -> ProgramResult {
+    Ok(())
+}
+
+/// Markets with trading hours only take new orders while open, and paused
+/// markets take none. Cancels are always allowed so makers can pull their
+/// quotes when the market closes or is paused.
+#[cfg(not(feature = "certora"))]
+fn verify_trading_hours(
+    market: &ManifestAccountInfo<MarketFixed>,
+    orders: &[PlaceOrderParams],
+) -> ProgramResult {
+    if orders.is_empty() {
+        return Ok(());
+    }
+    assert_market_open(&market.get_fixed()?.get_trading_hours())?;
+    verify_not_paused(market)
+}
+
+#[cfg(feature = "certora")]
+fn verify_trading_hours(
+    _market: &ManifestAccountInfo<MarketFixed>,
+    _orders: &NoResizableVec<PlaceOrderParams>,
+) -> ProgramResult {
+    Ok(())
+}
+
+/// Markets with a CPI allowlist only take new orders and replaces through an
+/// allowed program. Cancels are always allowed so makers are never stuck
+/// with orders on the book.
+#[cfg(not(feature = "certora"))]
+fn verify_batch_cpi_caller(
+    market: &ManifestAccountInfo<MarketFixed>,
+    instructions_sysvar_opt: Option<&AccountInfo>,
+    orders: &[PlaceOrderParams],
+    replaces: &[ReplaceOrderParams],
+) -> ProgramResult {
+    if orders.is_empty() && replaces.is_empty() {
+        return Ok(());
+    }
+    verify_cpi_caller(market, instructions_sysvar_opt)
+}
+
+#[cfg(feature = "certora")]
+fn verify_batch_cpi_caller(
+    _market: &ManifestAccountInfo<MarketFixed>,
+    _instructions_sysvar_opt: Option<&AccountInfo>,
+    _orders: &NoResizableVec<PlaceOrderParams>,
+    _replaces: &[ReplaceOrderParams],
+) -> ProgramResult {
+    Ok(())
+}
+
+/// Make sure there is a block for every place to rest in, plus the spare one
+/// that reverse orders and takers rely on. Done in a single expansion so that
+/// a batch does not stop halfway because of one expansion at a time.
+#[cfg(not(feature = "certora"))]
+fn reserve_blocks_for_places<'a, 'info>(
+    payer: &Signer<'a, 'info>,
+    market: &ManifestAccountInfo<'a, 'info, MarketFixed>,
+    num_places: usize,
+) -> ProgramResult {
+    let blocks_missing: Option<u32> = {
+        let market_data: &Ref<&mut [u8]> = &market.try_borrow_data()?;
+        let dynamic_account: MarketRef = get_dynamic_account(market_data);
+        dynamic_account.free_blocks_short_of_n(num_places as u32 + 1)
+    };
+    match blocks_missing {
+        Some(blocks_missing) if blocks_missing > 0 => {
+            batch_expand_market(payer, market, blocks_missing)
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(feature = "certora")]
+fn reserve_blocks_for_places(
+    _payer: &Signer,
+    _market: &ManifestAccountInfo<MarketFixed>,
+    _num_places: usize,
+) -> ProgramResult {
+    Ok(())
+}
+
 #[cfg_attr(all(feature = "certora", not(feature = "certora-test")), early_panic)]
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn process_batch_update_core(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
     params: BatchUpdateParams,
+    reserve_blocks: bool,
+    relative_expiry: bool,
+    cancel_all: bool,
+    amends: &[AmendOrderParams],
+    replaces: &[ReplaceOrderParams],
+    min_fill_atoms: BaseAtoms,
+    min_taker_atoms: BaseAtoms,
+    tags: &[u32],
+    ioc_min_fills: &[u64],
+    self_trade_preventions: &[SelfTradePrevention],
+    one_cancels_others: &[(u8, u8)],
+    display_sizes: &[u64],
+    last_valid_unix_timestamps: &[u32],
 ) -> ProgramResult {
+    let (accounts, instructions_sysvar_opt) = split_instructions_sysvar(accounts);
     let batch_update_context: BatchUpdateContext = BatchUpdateContext::load(accounts)?;
 
     let BatchUpdateContext {
         market,
         payer,
         global_trade_accounts_opts,
+        cosigner_opt,
+        fill_memo_program_opt,
         ..
     } = batch_update_context;
 
@@ -230,17 +1169,40 @@ pub(crate) fn process_batch_update_core(
         orders,
     } = params;
 
-    let current_slot: Option<u32> = Some(get_now_slot());
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+    verify_batch_cpi_caller(&market, instructions_sysvar_opt, &orders, replaces)?;
+    verify_one_cancels_others(&orders, one_cancels_others)?;
 
-    trace!("batch_update trader_index_hint:{trader_index_hint:?} cancels:{cancels:?} orders:{orders:?}");
+    let now_slot: u32 = get_now_slot();
+    let current_slot: Option<u32> = Some(now_slot);
 
-    let trader_index: DataIndex = {
+    trace!("batch_update trader_index_hint:{trader_index_hint:?} cancels:{cancels:?} amends:{amends:?} replaces:{replaces:?} orders:{orders:?}");
+
+    // Replaces are returned after the places, as (order_sequence_number,
+    // data_index) like them.
+    #[cfg(not(feature = "certora"))]
+    let mut replace_results: Vec<(u64, DataIndex)> = Vec::with_capacity(replaces.len());
+    #[cfg(feature = "certora")]
+    let mut replace_results = NoResizableVec::<(u64, DataIndex)>::new(10);
+
+    let (trader_index, replaced_notional): (DataIndex, QuoteAtoms) = {
         let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
 
         let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
         let trader_index: DataIndex =
             get_trader_index_with_hint(trader_index_hint, &dynamic_account, &payer)?;
 
+        if cancel_all {
+            batch_cancel_all(
+                &mut dynamic_account,
+                market.key,
+                payer.key,
+                trader_index,
+                &global_trade_accounts_opts,
+                now_slot,
+            )?;
+        }
+
         for cancel_order_params in cancels {
             // Hinted is preferred because that is O(1) to find and O(log n) to
             // remove. Without the hint, we lookup by order_sequence_number and
@@ -253,43 +1215,17 @@ pub(crate) fn process_batch_update_core(
                         trader_index,
                         cancel_order_params.order_sequence_number(),
                         &global_trade_accounts_opts,
+                        now_slot,
                     )?;
                 }
                 Some(hinted_cancel_index) => {
-                    // Simple sanity check on the hint given. Make sure that it
-                    // aligns with block boundaries. We do a check that it is an
-                    // order owned by the payer inside the handler.
-                    require!(
-                        hinted_cancel_index % (MARKET_BLOCK_SIZE as DataIndex) == 0,
-                        crate::program::ManifestError::WrongIndexHintParams,
-                        "Invalid cancel hint index {}",
-                        hinted_cancel_index,
-                    )?;
-                    require!(
-                        get_helper::<RBNode<RestingOrder>>(
-                            &dynamic_account.dynamic,
-                            hinted_cancel_index,
-                        )
-                        .get_payload_type()
-                            == MarketDataTreeNodeType::RestingOrder as u8,
-                        crate::program::ManifestError::WrongIndexHintParams,
-                        "Invalid cancel hint index {}",
-                        hinted_cancel_index,
-                    )?;
-                    let order: &RestingOrder =
-                        dynamic_account.get_order_by_index(hinted_cancel_index);
-                    require!(
-                        trader_index == order.get_trader_index(),
-                        crate::program::ManifestError::WrongIndexHintParams,
-                        "Invalid cancel hint index {}",
-                        hinted_cancel_index,
-                    )?;
-                    require!(
-                        cancel_order_params.order_sequence_number() == order.get_sequence_number(),
-                        crate::program::ManifestError::WrongIndexHintParams,
-                        "Invalid cancel hint sequence number index {}",
+                    verify_order_index_hint(
+                        &dynamic_account,
+                        trader_index,
+                        cancel_order_params.order_sequence_number(),
                         hinted_cancel_index,
                     )?;
+                    verify_min_resting_time(&dynamic_account, hinted_cancel_index, now_slot)?;
                     dynamic_account
                         .cancel_order_by_index(hinted_cancel_index, &global_trade_accounts_opts)?;
                 }
@@ -301,22 +1237,123 @@ pub(crate) fn process_batch_update_core(
                 order_sequence_number: cancel_order_params.order_sequence_number(),
             })?;
         }
-        trader_index
+
+        batch_amend_orders(
+            &mut dynamic_account,
+            market.key,
+            payer.key,
+            trader_index,
+            amends,
+            &global_trade_accounts_opts,
+            now_slot,
+        )?;
+
+        let replaced_notional: QuoteAtoms = batch_replace_orders(
+            &mut dynamic_account,
+            market.key,
+            payer.key,
+            trader_index,
+            replaces,
+            &global_trade_accounts_opts,
+            now_slot,
+            &mut replace_results,
+        )?;
+        (trader_index, replaced_notional)
     };
 
+    verify_trading_hours(&market, &orders)?;
+    verify_taker_delay(&market, &orders)?;
+    verify_cosigner(
+        &market,
+        trader_index,
+        &orders,
+        replaced_notional,
+        &cosigner_opt,
+    )?;
+
     try_to_pay_all_global_gas_prepayment(&orders, &global_trade_accounts_opts)?;
 
+    if reserve_blocks {
+        reserve_blocks_for_places(&payer, &market, orders.len())?;
+    }
+
     // Result is a vector of (order_sequence_number, data_index)
     #[cfg(not(feature = "certora"))]
     let mut result: Vec<(u64, DataIndex)> = Vec::with_capacity(orders.len());
     #[cfg(feature = "certora")]
     let mut result = NoResizableVec::<(u64, DataIndex)>::new(10);
-    for place_order_params in orders {
+
+    // Where the previous place rested. Places are usually a ladder on one
+    // side, so each one belongs next to the one before and can be inserted
+    // from there instead of from the root. Only reused for places on the same
+    // side, since matching from the other side can remove it.
+    let mut previous_is_bid: bool = false;
+    let mut previous_order_index: DataIndex = NIL;
+    // (order_sequence_number, order_index, base_atoms, base_atoms_traded) of
+    // every place, kept only to link one cancels other pairs.
+    let mut placed: Vec<(u64, DataIndex, BaseAtoms, BaseAtoms)> =
+        Vec::with_capacity(if one_cancels_others.is_empty() {
+            0
+        } else {
+            orders.len()
+        });
+    for (place_index, place_order_params) in orders.into_iter().enumerate() {
+        // Places without a tag get zero.
+        let tag: u32 = tags.get(place_index).copied().unwrap_or(0);
+        let ioc_min_fill: u64 = ioc_min_fills.get(place_index).copied().unwrap_or(0);
+        let display_size: u64 = display_sizes.get(place_index).copied().unwrap_or(0);
+        let last_valid_unix_timestamp: u32 = last_valid_unix_timestamps
+            .get(place_index)
+            .copied()
+            .unwrap_or(0);
+        let self_trade_prevention: SelfTradePrevention = self_trade_preventions
+            .get(place_index)
+            .copied()
+            .unwrap_or_default();
         {
             let base_atoms: BaseAtoms = BaseAtoms::new(place_order_params.base_atoms());
             let price: QuoteAtomsPerBaseAtom = place_order_params.try_price()?;
             let order_type: OrderType = place_order_params.order_type();
-            let last_valid_slot: u32 = place_order_params.last_valid_slot();
+            require!(
+                ioc_min_fill == 0 || order_type == OrderType::ImmediateOrCancel,
+                ManifestError::InvalidPlaceOrderFromWalletParams,
+                "Minimum fill {} on a {:?} order",
+                ioc_min_fill,
+                order_type,
+            )?;
+            require!(
+                (display_size != 0) == (order_type == OrderType::Iceberg),
+                ManifestError::InvalidPlaceOrderFromWalletParams,
+                "Display size {} on a {:?} order",
+                display_size,
+                order_type,
+            )?;
+            require!(
+                last_valid_unix_timestamp == 0
+                    || (!order_type.is_reversible()
+                        && place_order_params.last_valid_slot() == NO_EXPIRATION_LAST_VALID_SLOT),
+                ManifestError::InvalidPlaceOrderFromWalletParams,
+                "Last valid unix timestamp {} on a {:?} order with last valid slot {}",
+                last_valid_unix_timestamp,
+                order_type,
+                place_order_params.last_valid_slot(),
+            )?;
+            if last_valid_unix_timestamp != 0 {
+                let now_unix_timestamp: i64 = get_now_unix_timestamp();
+                require!(
+                    last_valid_unix_timestamp as i64 > now_unix_timestamp,
+                    ManifestError::AlreadyExpired,
+                    "Placing an already expired order. now: {} last_valid: {}",
+                    now_unix_timestamp,
+                    last_valid_unix_timestamp,
+                )?;
+            }
+            // Reverse orders keep their spread in last_valid_slot.
+            let last_valid_slot: u32 = if relative_expiry && !order_type.is_reversible() {
+                resolve_relative_last_valid_slot(place_order_params.last_valid_slot(), now_slot)?
+            } else {
+                place_order_params.last_valid_slot()
+            };
 
             // Need to reborrow every iteration so we can borrow later for expanding.
             let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
@@ -334,14 +1371,55 @@ pub(crate) fn process_batch_update_core(
                     order_type,
                     global_trade_accounts_opts: &global_trade_accounts_opts,
                     current_slot,
+                    fill_memo: fill_memo_program_opt.is_some(),
+                    insert_hint_index: if previous_is_bid == place_order_params.is_bid() {
+                        previous_order_index
+                    } else {
+                        NIL
+                    },
+                    min_fill_atoms: if order_type_can_rest(order_type) {
+                        BaseAtoms::ZERO
+                    } else {
+                        min_fill_atoms
+                    },
+                    // Icebergs keep their display size in place of a
+                    // minimum taker size.
+                    min_taker_atoms: if order_type == OrderType::Iceberg {
+                        BaseAtoms::new(display_size)
+                    } else {
+                        min_taker_atoms
+                    },
+                    tag,
+                    skip_global_orders: false,
+                    self_trade_prevention,
+                    referred: false,
                 },
             )?;
 
             let AddOrderToMarketResult {
                 order_index,
                 order_sequence_number,
+                base_atoms_traded,
                 ..
             } = add_order_to_market_result;
+            if last_valid_unix_timestamp != 0 && order_index != NIL {
+                dynamic_account
+                    .set_order_last_valid_unix_timestamp(order_index, last_valid_unix_timestamp);
+            }
+            require!(
+                base_atoms_traded.as_u64() >= ioc_min_fill,
+                ManifestError::InsufficientOut,
+                "Insufficient base atoms filled. Minimum: {} Actual: {}",
+                ioc_min_fill,
+                base_atoms_traded,
+            )?;
+            // A post only slide can rest at a price other than the one given.
+            let price: QuoteAtomsPerBaseAtom =
+                if order_type == OrderType::PostOnlySlide && order_index != NIL {
+                    dynamic_account.get_order_by_index(order_index).get_price()
+                } else {
+                    price
+                };
 
             emit_stack(PlaceOrderLog {
                 market: *market.key,
@@ -356,10 +1434,33 @@ pub(crate) fn process_batch_update_core(
                 last_valid_slot,
             })?;
             result.push((order_sequence_number, order_index));
+            if !one_cancels_others.is_empty() {
+                placed.push((
+                    order_sequence_number,
+                    order_index,
+                    base_atoms,
+                    base_atoms_traded,
+                ));
+            }
+            previous_is_bid = place_order_params.is_bid();
+            previous_order_index = order_index;
         }
         expand_market_if_needed(&payer, &market)?;
     }
 
+    if !one_cancels_others.is_empty() {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        batch_link_one_cancels_others(
+            &mut dynamic_account,
+            market.key,
+            payer.key,
+            one_cancels_others,
+            &placed,
+            &global_trade_accounts_opts,
+        )?;
+    }
+
     // Pay out gas prepayment refunds for cancelled global orders. This must
     // happen after the last CPI of this instruction (gas prepayments and
     // market expansions above) because it moves lamports directly.
@@ -370,10 +1471,11 @@ pub(crate) fn process_batch_update_core(
     {
         let mut buffer: Vec<u8> = Vec::with_capacity(
             std::mem::size_of::<BatchUpdateReturn>()
-                + result.len() * 2 * std::mem::size_of::<u64>(),
+                + (result.len() + replace_results.len()) * 2 * std::mem::size_of::<u64>(),
         );
+        result.extend(replace_results);
         let return_data: BatchUpdateReturn = BatchUpdateReturn { orders: result };
-        return_data.serialize(&mut buffer).unwrap();
+        return_data.serialize(&mut buffer)?;
         solana_program::program::set_return_data(&buffer[..]);
     }
 
@@ -0,0 +1,189 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::get_mut_dynamic_account,
+    crate::{
+        logs::{emit_stack, TradeBustLog},
+        program::ManifestError,
+        quantities::{BaseAtoms, QuoteAtoms, WrapperU64},
+        require,
+        state::{
+            utils::{assert_not_in_recovery, get_now_slot},
+            BustTradeArgs, MarketRefMut, TradeBust,
+        },
+        utils::create_account,
+        validation::{get_trade_bust_address, loaders::BustTradeContext},
+    },
+    hypertree::{get_mut_helper, trace, PodBool},
+    solana_program::{rent::Rent, sysvar::Sysvar},
+    std::{cell::RefMut, mem::size_of},
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct BustTradeParams {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub maker: Pubkey,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub taker: Pubkey,
+    pub base_atoms: u64,
+    pub quote_atoms: u64,
+    pub taker_is_buy: bool,
+    pub maker_sequence_number: u64,
+    pub taker_sequence_number: u64,
+    pub taker_fee_bps: u16,
+    pub referred: bool,
+    pub fill_slot: u32,
+}
+
+impl BustTradeParams {
+    /// Fields as in the FillLog of the fill, and the slot of the transaction
+    /// it is in.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        maker: Pubkey,
+        taker: Pubkey,
+        base_atoms: u64,
+        quote_atoms: u64,
+        taker_is_buy: bool,
+        maker_sequence_number: u64,
+        taker_sequence_number: u64,
+        taker_fee_bps: u16,
+        referred: bool,
+        fill_slot: u32,
+    ) -> Self {
+        BustTradeParams {
+            maker,
+            taker,
+            base_atoms,
+            quote_atoms,
+            taker_is_buy,
+            maker_sequence_number,
+            taker_sequence_number,
+            taker_fee_bps,
+            referred,
+            fill_slot,
+        }
+    }
+}
+
+/// Reverse an erroneous fill for operators that have to be able to bust
+/// trades. Signed by the program upgrade authority, who also pays for the
+/// TradeBust record that keeps the fill from being busted again.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_bust_trade(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    trace!("process_bust_trade accs={accounts:?}");
+    let params: BustTradeParams = BustTradeParams::try_from_slice(data)?;
+    let bust_trade_context: BustTradeContext = BustTradeContext::load(accounts)?;
+    let BustTradeContext {
+        authority,
+        market,
+        system_program,
+        trade_bust,
+    } = bust_trade_context;
+    let BustTradeParams {
+        maker,
+        taker,
+        base_atoms,
+        quote_atoms,
+        taker_is_buy,
+        maker_sequence_number,
+        taker_sequence_number,
+        taker_fee_bps,
+        referred,
+        fill_slot,
+    } = params;
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+
+    let (expected_trade_bust_key, trade_bust_bump) =
+        get_trade_bust_address(market.key, maker_sequence_number, taker_sequence_number);
+    require!(
+        expected_trade_bust_key == *trade_bust.info.key,
+        ManifestError::IncorrectAccount,
+        "Incorrect trade bust account",
+    )?;
+
+    let (maker_fee_atoms, taker_fee_atoms): (u64, u64) = {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.bust_trade(BustTradeArgs {
+            maker,
+            taker,
+            base_atoms: BaseAtoms::new(base_atoms),
+            quote_atoms: QuoteAtoms::new(quote_atoms),
+            taker_is_buy,
+            maker_sequence_number,
+            taker_sequence_number,
+            taker_fee_bps,
+            referred,
+            fill_slot,
+        })?
+    };
+
+    create_account(
+        authority.as_ref(),
+        trade_bust.as_ref(),
+        system_program.as_ref(),
+        &crate::id(),
+        &Rent::get()?,
+        size_of::<TradeBust>() as u64,
+        vec![
+            b"trade-bust".to_vec(),
+            market.key.as_ref().to_vec(),
+            maker_sequence_number.to_le_bytes().to_vec(),
+            taker_sequence_number.to_le_bytes().to_vec(),
+            vec![trade_bust_bump],
+        ],
+    )?;
+    let trade_bust_bytes: &mut [u8] = &mut trade_bust.info.try_borrow_mut_data()?[..];
+    *get_mut_helper::<TradeBust>(trade_bust_bytes, 0_u32) = TradeBust::new(
+        market.key,
+        maker_sequence_number,
+        taker_sequence_number,
+        get_now_slot(),
+    );
+
+    emit_stack(TradeBustLog {
+        market: *market.key,
+        authority: *authority.key,
+        maker,
+        taker,
+        base_atoms: BaseAtoms::new(base_atoms),
+        quote_atoms: QuoteAtoms::new(quote_atoms),
+        maker_fee_atoms,
+        taker_fee_atoms,
+        maker_sequence_number,
+        taker_sequence_number,
+        taker_is_buy: PodBool::from(taker_is_buy),
+        _padding: [0; 7],
+    })?;
+
+    Ok(())
+}
+
+// Formal verification does not model trade busts.
+#[cfg(feature = "certora")]
+pub(crate) fn process_bust_trade(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::{
+        get_mut_dynamic_account,
+        withdraw::{
+            spl_token_2022_transfer_from_vault_to_trader_fixed,
+            spl_token_transfer_from_vault_to_trader,
+        },
+    },
+    crate::{
+        logs::{emit_stack, ClaimFeesLog},
+        state::{utils::assert_not_in_recovery, MarketRefMut},
+        validation::loaders::WithdrawContext,
+    },
+    std::cell::RefMut,
+};
+
+/// Pay the fees accrued in the mint of trader_token to it. Anyone can crank
+/// this, the token account has to be owned by the fee recipient.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_claim_fees(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let withdraw_context: WithdrawContext = WithdrawContext::load(accounts)?;
+    let WithdrawContext {
+        market,
+        trader_token,
+        vault,
+        token_program,
+        mint,
+        ..
+    } = withdraw_context;
+    // Fees come after seats in recovery, they are not taken out of the
+    // vaults before traders are paid.
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+
+    // Validation verifies that the mint is either base or quote.
+    let is_base: bool =
+        &trader_token.try_borrow_data()?[0..32] == dynamic_account.get_base_mint().as_ref();
+    let recipient: Pubkey = trader_token.get_owner();
+    let amount_atoms: u64 = dynamic_account.claim_fees(&recipient, is_base)?;
+
+    let mint_key: Pubkey = if is_base {
+        *dynamic_account.get_base_mint()
+    } else {
+        *dynamic_account.get_quote_mint()
+    };
+    let bump: u8 = if is_base {
+        dynamic_account.fixed.get_base_vault_bump()
+    } else {
+        dynamic_account.fixed.get_quote_vault_bump()
+    };
+
+    if *vault.owner == spl_token_2022::id() {
+        spl_token_2022_transfer_from_vault_to_trader_fixed(
+            &token_program,
+            Some(mint),
+            &mint_key,
+            &vault,
+            &trader_token,
+            amount_atoms,
+            if is_base {
+                dynamic_account.fixed.get_base_mint_decimals()
+            } else {
+                dynamic_account.fixed.get_quote_mint_decimals()
+            },
+            market.key,
+            bump,
+        )?;
+    } else {
+        spl_token_transfer_from_vault_to_trader(
+            &token_program,
+            &vault,
+            &trader_token,
+            amount_atoms,
+            market.key,
+            bump,
+            &mint_key,
+        )?;
+    }
+
+    emit_stack(ClaimFeesLog {
+        market: *market.key,
+        recipient,
+        mint: mint_key,
+        amount_atoms,
+    })?;
+
+    Ok(())
+}
+
+// Formal verification does not model fees.
+#[cfg(feature = "certora")]
+pub(crate) fn process_claim_fees(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use std::cell::RefMut;
+
+use crate::{
+    logs::{emit_stack, PointsClaimLog},
+    program::{get_trader_index_with_hint, ManifestError},
+    require,
+    state::{utils::get_now_slot, MarketRefMut},
+    validation::loaders::ClaimPointsContext,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{is_not_nil, DataIndex, NIL};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use super::shared::{expand_market_if_needed, get_mut_dynamic_account};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct ClaimPointsParams {
+    pub trader_index_hint: Option<DataIndex>,
+}
+
+impl ClaimPointsParams {
+    pub fn new(trader_index_hint: Option<DataIndex>) -> Self {
+        ClaimPointsParams { trader_index_hint }
+    }
+}
+
+/// Claims the payer's points of the latest finished epoch as a PointsClaimLog,
+/// so campaigns can pay out from the logs of claims instead of tallying fills
+/// themselves. The first claim on a seat opts it in to earning points.
+pub(crate) fn process_claim_points(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let params: ClaimPointsParams = ClaimPointsParams::try_from_slice(data)?;
+    let claim_points_context: ClaimPointsContext = ClaimPointsContext::load(accounts)?;
+    let ClaimPointsContext { market, payer, .. } = claim_points_context;
+
+    {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        let trader_index: DataIndex =
+            get_trader_index_with_hint(params.trader_index_hint, &dynamic_account, &payer)?;
+        require!(
+            is_not_nil!(trader_index),
+            ManifestError::InvalidSeatConfig,
+            "No seat initialized",
+        )?;
+
+        if let Some((epoch, points)) = dynamic_account.claim_points(trader_index, get_now_slot())? {
+            emit_stack(PointsClaimLog {
+                market: *market.key,
+                trader: *payer.key,
+                points,
+                epoch,
+                _padding: [0; 4],
+            })?;
+        }
+    }
+
+    // Leave a free block on the market
+    expand_market_if_needed(&payer, &market)?;
+
+    Ok(())
+}
@@ -5,6 +5,7 @@ use crate::{
     state::{MarketFixed, MarketRefMut},
     validation::{loaders::ClaimSeatContext, ManifestAccountInfo, Signer},
 };
+use hypertree::{is_not_nil, DataIndex, NIL};
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 
 use super::shared::{expand_market_if_needed, get_mut_dynamic_account};
@@ -34,14 +35,34 @@ pub(crate) fn process_claim_seat_internal<'a, 'info>(
     market: &ManifestAccountInfo<'a, 'info, MarketFixed>,
     payer: &Signer<'a, 'info>,
 ) -> ProgramResult {
-    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
-    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
-    dynamic_account.claim_seat(payer.key)?;
-
-    emit_stack(ClaimSeatLog {
-        market: *market.key,
-        trader: *payer.key,
-    })?;
+    let (trader_index, has_fee_tiers): (DataIndex, bool) = {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+
+        // Claiming is idempotent so that clients can always prepend a claim
+        // without first checking whether the seat exists.
+        let existing_trader_index: DataIndex = dynamic_account.get_trader_index(payer.key);
+        let trader_index: DataIndex = if is_not_nil!(existing_trader_index) {
+            existing_trader_index
+        } else {
+            dynamic_account.claim_seat(payer.key)?;
+            emit_stack(ClaimSeatLog {
+                market: *market.key,
+                trader: *payer.key,
+            })?;
+            dynamic_account.get_trader_index(payer.key)
+        };
+        (trader_index, dynamic_account.has_fee_tiers())
+    };
+
+    // Seats on markets with fee tiers track their taker volume. Seats that
+    // were restored without one get it on the next claim.
+    if has_fee_tiers {
+        expand_market_if_needed(payer, market)?;
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.init_seat_volume(trader_index)?;
+    }
 
     Ok(())
 }
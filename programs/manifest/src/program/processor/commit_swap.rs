@@ -0,0 +1,76 @@
+use std::{cell::RefMut, mem::size_of};
+
+use crate::{
+    state::{utils::get_now_slot, SwapCommitment},
+    utils::create_account,
+    validation::{get_swap_commitment_address, loaders::CommitSwapContext, ManifestAccountInfo},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{get_mut_helper, trace};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey, rent::Rent,
+    sysvar::Sysvar,
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct CommitSwapParams {
+    pub commitment: [u8; 32],
+}
+
+impl CommitSwapParams {
+    pub fn new(commitment: [u8; 32]) -> Self {
+        CommitSwapParams { commitment }
+    }
+}
+
+/// First half of a swap on a market with a taker delay. Records the hash of
+/// the swap and the slot, RevealSwap executes it after the delay. The payer
+/// funds their swap commitment account on the first commit to the market.
+pub(crate) fn process_commit_swap(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    trace!("process_commit_swap accs={accounts:?}");
+    let params: CommitSwapParams = CommitSwapParams::try_from_slice(data)?;
+    let commit_swap_context: CommitSwapContext = CommitSwapContext::load(accounts)?;
+    let CommitSwapContext {
+        payer,
+        market,
+        system_program,
+        swap_commitment,
+    } = commit_swap_context;
+
+    if swap_commitment.data_is_empty() {
+        let (_expected_swap_commitment_key, swap_commitment_bump) =
+            get_swap_commitment_address(market.key, payer.key);
+        create_account(
+            payer.as_ref(),
+            swap_commitment,
+            system_program.as_ref(),
+            &crate::id(),
+            &Rent::get()?,
+            size_of::<SwapCommitment>() as u64,
+            vec![
+                b"swap-commitment".to_vec(),
+                market.key.as_ref().to_vec(),
+                payer.key.as_ref().to_vec(),
+                vec![swap_commitment_bump],
+            ],
+        )?;
+        let swap_commitment_bytes: &mut [u8] = &mut swap_commitment.try_borrow_mut_data()?[..];
+        *get_mut_helper::<SwapCommitment>(swap_commitment_bytes, 0_u32) =
+            SwapCommitment::new_empty(market.key, payer.key);
+    }
+    let swap_commitment: ManifestAccountInfo<SwapCommitment> =
+        ManifestAccountInfo::<SwapCommitment>::new(swap_commitment)?;
+
+    let swap_commitment_data: &mut RefMut<&mut [u8]> =
+        &mut swap_commitment.try_borrow_mut_data()?;
+    get_mut_helper::<SwapCommitment>(swap_commitment_data, 0_u32)
+        .commit(params.commitment, get_now_slot())
+}
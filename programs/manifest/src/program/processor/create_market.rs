@@ -1,17 +1,31 @@
-use std::{cell::Ref, mem::size_of};
+use std::{
+    cell::{Ref, RefMut},
+    mem::size_of,
+};
 
 use crate::{
     logs::{emit_stack, CreateMarketLog},
-    program::{expand_market_if_needed, invoke},
+    program::{
+        batch_expand_market, expand_market, expand_market_if_needed, get_mut_dynamic_account,
+        invoke,
+    },
+    quantities::{BaseAtoms, QuoteAtoms, WrapperU64},
     require,
-    state::MarketFixed,
+    state::{
+        cpi_allowlist::CpiAllowlist, market_extension::MarketExtension, market_fees::MarketFees,
+        min_order_size::MinOrderSize, price_band::PriceBand, MarketFixed, MarketRefMut,
+        TradingHours, FILL_RECORD_BLOCKS, MARKET_EXTENSION_FLAG_FILL_RECORDS,
+        MARKET_FLAG_CPI_ALLOWLIST, MARKET_FLAG_EXTENSION, MARKET_FLAG_FEES,
+        MARKET_FLAG_MIN_ORDER_SIZE, MARKET_FLAG_NO_INTERNAL_CROSS, MARKET_FLAG_PRICE_BAND,
+        MARKET_FLAG_VOLUME_STATS, MAX_CPI_ALLOWLIST_PROGRAMS, MAX_FEE_BPS,
+    },
     utils::create_account,
     validation::{get_vault_address, loaders::CreateMarketContext},
 };
 use hypertree::{get_mut_helper, trace};
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, program_pack::Pack, pubkey::Pubkey,
-    rent::Rent, sysvar::Sysvar,
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    program_pack::Pack, pubkey::Pubkey, rent::Rent, sysvar::Sysvar,
 };
 use spl_token_2022::{
     extension::{
@@ -22,12 +36,283 @@ use spl_token_2022::{
     state::{Account, Mint},
 };
 
+/// Market settings in the CreateMarket data after the flags.
+#[derive(Default)]
+struct CreateMarketOptions {
+    trading_hours: TradingHours,
+    min_resting_slots: u32,
+    taker_delay_slots: u16,
+    maker_points_weight: u16,
+    taker_points_weight: u16,
+    cpi_allowlist_opt: Option<CpiAllowlist>,
+    min_order_size_opt: Option<MinOrderSize>,
+    price_band_opt: Option<PriceBand>,
+    market_fees_opt: Option<MarketFees>,
+    market_extension_opt: Option<MarketExtension>,
+}
+
+/// Data is empty, a single byte of MARKET_FLAG_* bits, or that byte followed
+/// by the trading hours as days u8, open_minute u16 and close_minute u16,
+/// optionally followed by min_resting_slots u32, then taker_delay_slots u16
+/// and then the maker and taker points weights as u16 each. With
+/// MARKET_FLAG_MIN_ORDER_SIZE, MARKET_FLAG_PRICE_BAND, MARKET_FLAG_FEES,
+/// MARKET_FLAG_EXTENSION or MARKET_FLAG_CPI_ALLOWLIST all of those are given.
+/// MARKET_FLAG_MIN_ORDER_SIZE adds the min base atoms and min quote atoms as
+/// u64 each, MARKET_FLAG_PRICE_BAND then adds the band in bps as u16,
+/// MARKET_FLAG_FEES then adds the maker, taker and referral fee bps as u16
+/// each, the fee recipient and the fee tier quote atoms as u64,
+/// MARKET_FLAG_EXTENSION then adds the pauser, default for none, and the
+/// MARKET_EXTENSION_FLAG_* bits set at creation as u8, and
+/// MARKET_FLAG_CPI_ALLOWLIST then adds the pubkeys of up to
+/// MAX_CPI_ALLOWLIST_PROGRAMS programs. MARKET_FLAG_VOLUME_STATS needs no
+/// data.
+fn parse_create_market_data(data: &[u8]) -> Result<(u8, CreateMarketOptions), ProgramError> {
+    let (market_flags, options): (u8, CreateMarketOptions) = match data {
+        [] => (0, CreateMarketOptions::default()),
+        [market_flags] => (*market_flags, CreateMarketOptions::default()),
+        [market_flags, days, open_0, open_1, close_0, close_1, rest @ ..] => {
+            let has_min_order_size: bool = market_flags & MARKET_FLAG_MIN_ORDER_SIZE != 0;
+            let has_price_band: bool = market_flags & MARKET_FLAG_PRICE_BAND != 0;
+            let has_fees: bool = market_flags & MARKET_FLAG_FEES != 0;
+            let has_extension: bool = market_flags & MARKET_FLAG_EXTENSION != 0;
+            // The settings after the trading hours are all given when any of
+            // the blocks follow them.
+            let (rest, mut extra): (&[u8], &[u8]) = if has_min_order_size
+                || has_price_band
+                || has_fees
+                || has_extension
+                || market_flags & MARKET_FLAG_CPI_ALLOWLIST != 0
+            {
+                if rest.len() < 10 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                rest.split_at(10)
+            } else {
+                (rest, &[])
+            };
+            let min_order_size_data: &[u8] =
+                split_block_data(&mut extra, if has_min_order_size { 16 } else { 0 });
+            let price_band_data: &[u8] =
+                split_block_data(&mut extra, if has_price_band { 2 } else { 0 });
+            let market_fees_data: &[u8] =
+                split_block_data(&mut extra, if has_fees { 46 } else { 0 });
+            let market_extension_data: &[u8] =
+                split_block_data(&mut extra, if has_extension { 33 } else { 0 });
+            let programs: &[u8] = extra;
+            let (min_resting_slots, taker_delay_slots, maker_points_weight, taker_points_weight): (
+                u32,
+                u16,
+                u16,
+                u16,
+            ) = match rest {
+                [] => (0, 0, 0, 0),
+                [m_0, m_1, m_2, m_3] => (u32::from_le_bytes([*m_0, *m_1, *m_2, *m_3]), 0, 0, 0),
+                [m_0, m_1, m_2, m_3, d_0, d_1] => (
+                    u32::from_le_bytes([*m_0, *m_1, *m_2, *m_3]),
+                    u16::from_le_bytes([*d_0, *d_1]),
+                    0,
+                    0,
+                ),
+                [m_0, m_1, m_2, m_3, d_0, d_1, p_0, p_1, p_2, p_3] => (
+                    u32::from_le_bytes([*m_0, *m_1, *m_2, *m_3]),
+                    u16::from_le_bytes([*d_0, *d_1]),
+                    u16::from_le_bytes([*p_0, *p_1]),
+                    u16::from_le_bytes([*p_2, *p_3]),
+                ),
+                _ => return Err(ProgramError::InvalidInstructionData),
+            };
+            (
+                *market_flags,
+                CreateMarketOptions {
+                    trading_hours: TradingHours::new(
+                        *days,
+                        u16::from_le_bytes([*open_0, *open_1]),
+                        u16::from_le_bytes([*close_0, *close_1]),
+                    ),
+                    min_resting_slots,
+                    taker_delay_slots,
+                    maker_points_weight,
+                    taker_points_weight,
+                    cpi_allowlist_opt: if programs.is_empty() {
+                        None
+                    } else {
+                        Some(parse_cpi_allowlist(programs)?)
+                    },
+                    min_order_size_opt: if has_min_order_size {
+                        Some(parse_min_order_size(min_order_size_data)?)
+                    } else {
+                        None
+                    },
+                    price_band_opt: if has_price_band {
+                        Some(parse_price_band(price_band_data)?)
+                    } else {
+                        None
+                    },
+                    market_fees_opt: if has_fees {
+                        Some(parse_market_fees(market_fees_data)?)
+                    } else {
+                        None
+                    },
+                    market_extension_opt: if has_extension {
+                        Some(parse_market_extension(market_extension_data)?)
+                    } else {
+                        None
+                    },
+                },
+            )
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+    // Unknown bits are rejected so they can be given a meaning later.
+    if market_flags
+        & !(MARKET_FLAG_NO_INTERNAL_CROSS
+            | MARKET_FLAG_CPI_ALLOWLIST
+            | MARKET_FLAG_MIN_ORDER_SIZE
+            | MARKET_FLAG_PRICE_BAND
+            | MARKET_FLAG_FEES
+            | MARKET_FLAG_VOLUME_STATS
+            | MARKET_FLAG_EXTENSION)
+        != 0
+    {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if (market_flags & MARKET_FLAG_CPI_ALLOWLIST != 0) != options.cpi_allowlist_opt.is_some() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if (market_flags & MARKET_FLAG_MIN_ORDER_SIZE != 0) != options.min_order_size_opt.is_some() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if (market_flags & MARKET_FLAG_PRICE_BAND != 0) != options.price_band_opt.is_some() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if (market_flags & MARKET_FLAG_FEES != 0) != options.market_fees_opt.is_some() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if (market_flags & MARKET_FLAG_EXTENSION != 0) != options.market_extension_opt.is_some() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok((market_flags, options))
+}
+
+/// Splits up to len bytes off the front of data. The parser of the block
+/// rejects them if there were fewer.
+fn split_block_data<'a>(data: &mut &'a [u8], len: usize) -> &'a [u8] {
+    let (block_data, rest): (&[u8], &[u8]) = data.split_at(data.len().min(len));
+    *data = rest;
+    block_data
+}
+
+fn parse_min_order_size(data: &[u8]) -> Result<MinOrderSize, ProgramError> {
+    if data.len() != 16 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let read_u64 = |bytes: &[u8]| -> Result<u64, ProgramError> {
+        Ok(u64::from_le_bytes(
+            bytes
+                .try_into()
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+        ))
+    };
+    Ok(MinOrderSize::new(
+        BaseAtoms::new(read_u64(&data[..8])?),
+        QuoteAtoms::new(read_u64(&data[8..])?),
+    ))
+}
+
+/// The band has to be above 0 and below 100%.
+fn parse_price_band(data: &[u8]) -> Result<PriceBand, ProgramError> {
+    let band_bps: u16 = u16::from_le_bytes(
+        data.try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+    if band_bps == 0 || band_bps >= 10_000 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(PriceBand::new(band_bps))
+}
+
+/// At least one of the fees has to be set, neither above MAX_FEE_BPS, the
+/// referral share cannot be more than the whole taker fee, and the recipient
+/// cannot be the default pubkey. A zero fee tier quote atoms is no fee tiers.
+fn parse_market_fees(data: &[u8]) -> Result<MarketFees, ProgramError> {
+    let [maker_0, maker_1, taker_0, taker_1, referral_0, referral_1, rest @ ..] = data else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+    if rest.len() != 40 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (recipient, fee_tier) = rest.split_at(32);
+    let maker_fee_bps: u16 = u16::from_le_bytes([*maker_0, *maker_1]);
+    let taker_fee_bps: u16 = u16::from_le_bytes([*taker_0, *taker_1]);
+    let referral_fee_bps: u16 = u16::from_le_bytes([*referral_0, *referral_1]);
+    let fee_recipient: Pubkey =
+        Pubkey::try_from(recipient).map_err(|_| ProgramError::InvalidInstructionData)?;
+    let fee_tier_quote_atoms: u64 = u64::from_le_bytes(
+        fee_tier
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+    if (maker_fee_bps == 0 && taker_fee_bps == 0)
+        || maker_fee_bps > MAX_FEE_BPS
+        || taker_fee_bps > MAX_FEE_BPS
+        || referral_fee_bps > 10_000
+        || fee_recipient == Pubkey::default()
+    {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(MarketFees::new(
+        maker_fee_bps,
+        taker_fee_bps,
+        referral_fee_bps,
+        fee_recipient,
+        QuoteAtoms::new(fee_tier_quote_atoms),
+    ))
+}
+
+fn parse_market_extension(data: &[u8]) -> Result<MarketExtension, ProgramError> {
+    let [pauser @ .., extension_flags] = data else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+    let pauser: Pubkey =
+        Pubkey::try_from(pauser).map_err(|_| ProgramError::InvalidInstructionData)?;
+    // Fill records are the only mode chosen at creation. Pausing is up to the
+    // pauser.
+    if extension_flags & !MARKET_EXTENSION_FLAG_FILL_RECORDS != 0 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(MarketExtension::new(&pauser, *extension_flags))
+}
+
+fn parse_cpi_allowlist(programs: &[u8]) -> Result<CpiAllowlist, ProgramError> {
+    if programs.len() % 32 != 0 || programs.len() / 32 > MAX_CPI_ALLOWLIST_PROGRAMS {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let programs: Vec<Pubkey> = programs
+        .chunks_exact(32)
+        .map(|program| Pubkey::try_from(program).map_err(|_| ProgramError::InvalidInstructionData))
+        .collect::<Result<Vec<Pubkey>, ProgramError>>()?;
+    Ok(CpiAllowlist::new(&programs))
+}
+
 pub(crate) fn process_create_market(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
-    _data: &[u8],
+    data: &[u8],
 ) -> ProgramResult {
     trace!("process_create_market accs={accounts:?}");
+    let (market_flags, options) = parse_create_market_data(data)?;
+    let CreateMarketOptions {
+        trading_hours,
+        min_resting_slots,
+        taker_delay_slots,
+        maker_points_weight,
+        taker_points_weight,
+        cpi_allowlist_opt,
+        min_order_size_opt,
+        price_band_opt,
+        market_fees_opt,
+        market_extension_opt,
+    } = options;
     let create_market_context: CreateMarketContext = CreateMarketContext::load(accounts)?;
 
     let CreateMarketContext {
@@ -42,6 +327,12 @@ pub(crate) fn process_create_market(
         token_program_22,
     } = create_market_context;
 
+    require!(
+        trading_hours.is_valid(),
+        crate::program::ManifestError::InvalidMarketParameters,
+        "Invalid trading hours {:?}",
+        trading_hours,
+    )?;
     require!(
         base_mint.info.key != quote_mint.info.key,
         crate::program::ManifestError::InvalidMarketParameters,
@@ -102,7 +393,7 @@ pub(crate) fn process_create_market(
             if is_mint_22 {
                 let mint_data: Ref<'_, &mut [u8]> = mint.data.borrow();
                 let mint_with_extension: PodStateWithExtensions<'_, PodMint> =
-                    PodStateWithExtensions::<PodMint>::unpack(&mint_data).unwrap();
+                    PodStateWithExtensions::<PodMint>::unpack(&mint_data)?;
                 let mint_extensions: Vec<ExtensionType> =
                     mint_with_extension.get_extension_types()?;
                 let required_extensions: Vec<ExtensionType> =
@@ -169,8 +460,12 @@ pub(crate) fn process_create_market(
         // would use an inactive market when multiple exist.
 
         // Setup the empty market
-        let empty_market_fixed: MarketFixed =
-            MarketFixed::new_empty(&base_mint, &quote_mint, market.key);
+        let mut empty_market_fixed: MarketFixed =
+            MarketFixed::new_empty(&base_mint, &quote_mint, market.key, market_flags);
+        empty_market_fixed.set_trading_hours(trading_hours);
+        empty_market_fixed.set_min_resting_slots(min_resting_slots);
+        empty_market_fixed.set_taker_delay_slots(taker_delay_slots);
+        empty_market_fixed.set_points_weights(maker_points_weight, taker_points_weight);
         assert_eq!(market.data_len(), size_of::<MarketFixed>());
 
         let market_bytes: &mut [u8] = &mut market.try_borrow_mut_data()?[..];
@@ -184,6 +479,56 @@ pub(crate) fn process_create_market(
         })?;
     }
 
+    // The allowlist takes the first block.
+    if let Some(cpi_allowlist) = cpi_allowlist_opt {
+        expand_market(&payer, &market)?;
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.init_cpi_allowlist(cpi_allowlist)?;
+    }
+    // The minimum takes the block after it.
+    if let Some(min_order_size) = min_order_size_opt {
+        expand_market(&payer, &market)?;
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.init_min_order_size(min_order_size)?;
+    }
+    // And the band the block after those.
+    if let Some(price_band) = price_band_opt {
+        expand_market(&payer, &market)?;
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.init_price_band(price_band)?;
+    }
+    // Then the fees.
+    if let Some(market_fees) = market_fees_opt {
+        expand_market(&payer, &market)?;
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.init_market_fees(market_fees)?;
+    }
+    // The stats after those.
+    if market_flags & MARKET_FLAG_VOLUME_STATS != 0 {
+        expand_market(&payer, &market)?;
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.init_market_stats()?;
+    }
+    // And the extension after everything else.
+    if let Some(market_extension) = market_extension_opt {
+        expand_market(&payer, &market)?;
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.init_market_extension(market_extension)?;
+    }
+    // With the fill records right behind it.
+    if market_extension_opt.is_some_and(|market_extension| market_extension.has_fill_records()) {
+        batch_expand_market(&payer, &market, FILL_RECORD_BLOCKS)?;
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.init_fill_records()?;
+    }
+
     // Leave a free block on the market so takers can use and leave it.
     expand_market_if_needed(&payer, &market)?;
 
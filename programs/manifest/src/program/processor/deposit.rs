@@ -2,16 +2,22 @@ use std::cell::RefMut;
 
 use crate::{
     logs::{emit_stack, DepositLog},
-    state::MarketRefMut,
+    program::ManifestError,
+    state::{utils::assert_not_in_recovery, MarketRefMut},
     validation::{
-        loaders::DepositContext, MintAccountInfo, Signer, TokenAccountInfo, TokenProgram,
+        loaders::{DepositContext, DepositIdempotentContext},
+        MintAccountInfo, Signer, TokenAccountInfo, TokenProgram,
     },
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use hypertree::DataIndex;
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 
-use super::{get_trader_index_with_hint, shared::get_mut_dynamic_account};
+use super::{
+    claim_seat::process_claim_seat_internal,
+    get_trader_index_with_hint,
+    shared::{expand_market_if_needed, get_mut_dynamic_account},
+};
 
 #[cfg(feature = "certora")]
 use crate::certora::summaries::token::spl_token_2022_transfer_with_fee;
@@ -21,6 +27,10 @@ use early_panic::early_panic;
 use solana_cvt::token::spl_token_transfer;
 
 #[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct DepositParams {
     pub amount_atoms: u64,
     pub trader_index_hint: Option<DataIndex>,
@@ -44,6 +54,28 @@ pub(crate) fn process_deposit(
     process_deposit_core(program_id, accounts, params)
 }
 
+/// Same as deposit, but claims a seat for the payer first if needed, so it can
+/// be sent without knowing whether the seat exists.
+pub(crate) fn process_deposit_idempotent(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let params: DepositParams = DepositParams::try_from_slice(data)?;
+    {
+        let deposit_idempotent_context: DepositIdempotentContext =
+            DepositIdempotentContext::load(accounts)?;
+        let DepositContext { market, payer, .. } = deposit_idempotent_context.deposit;
+
+        // No-op when the seat already exists.
+        process_claim_seat_internal(&market, &payer)?;
+
+        // Leave a free block on the market
+        expand_market_if_needed(&payer, &market)?;
+    }
+    process_deposit_core(program_id, accounts, params)
+}
+
 #[cfg_attr(all(feature = "certora", not(feature = "certora-test")), early_panic)]
 pub(crate) fn process_deposit_core(
     _program_id: &Pubkey,
@@ -69,6 +101,7 @@ pub(crate) fn process_deposit_core(
 
     let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
     let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+    assert_not_in_recovery(dynamic_account.fixed)?;
 
     // Validation already verifies that the mint is either base or quote.
     let is_base: bool =
@@ -98,7 +131,7 @@ pub(crate) fn process_deposit_core(
         let after_vault_balance_atoms: u64 = vault.get_balance_atoms();
         deposited_amount_atoms = after_vault_balance_atoms
             .checked_sub(before_vault_balance_atoms)
-            .unwrap();
+            .ok_or(ManifestError::Overflow)?;
     } else {
         spl_token_transfer_from_trader_to_vault(
             &token_program,
@@ -192,7 +225,7 @@ fn spl_token_2022_transfer_from_trader_to_vault<'a, 'info>(
             token_program.as_ref().clone(),
             trader_account.as_ref().clone(),
             vault.as_ref().clone(),
-            mint.unwrap().as_ref().clone(),
+            mint.ok_or(ManifestError::InvalidMint)?.as_ref().clone(),
             payer.as_ref().clone(),
         ],
     )
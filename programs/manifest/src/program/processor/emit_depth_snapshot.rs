@@ -0,0 +1,71 @@
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::shared::get_dynamic_account,
+    crate::{
+        logs::{emit_stack, DepthLevel, DepthSnapshotLog},
+        quantities::{BaseAtoms, QuoteAtomsPerBaseAtom},
+        state::{constants::DEPTH_SNAPSHOT_MAX_LEVELS, utils::get_now_slot, MarketRef},
+        validation::loaders::EmitDepthSnapshotContext,
+    },
+    bytemuck::Zeroable,
+    solana_program::program_error::ProgramError,
+    std::cell::Ref,
+};
+
+/// Logs the best levels of both sides of the book as a DepthSnapshotLog, so
+/// indexers can keep depth history from transactions instead of polling the
+/// market. Anyone can crank it and nothing is changed. Data is an optional
+/// u8 number of levels per side, DEPTH_SNAPSHOT_MAX_LEVELS when left out.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_emit_depth_snapshot(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let num_levels: usize = match data {
+        [] => DEPTH_SNAPSHOT_MAX_LEVELS,
+        [num_levels] if (1..=DEPTH_SNAPSHOT_MAX_LEVELS).contains(&(*num_levels as usize)) => {
+            *num_levels as usize
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    let emit_depth_snapshot_context: EmitDepthSnapshotContext =
+        EmitDepthSnapshotContext::load(accounts)?;
+    let EmitDepthSnapshotContext { market } = emit_depth_snapshot_context;
+
+    let market_data: &Ref<&mut [u8]> = &market.try_borrow_data()?;
+    let dynamic_account: MarketRef = get_dynamic_account(market_data);
+    let now_slot: u32 = get_now_slot();
+
+    let mut depth_snapshot_log: DepthSnapshotLog = DepthSnapshotLog {
+        market: *market.key,
+        slot: now_slot,
+        ..DepthSnapshotLog::zeroed()
+    };
+    let bids: Vec<(QuoteAtomsPerBaseAtom, BaseAtoms)> =
+        dynamic_account.get_depth_levels(true, num_levels, now_slot)?;
+    let asks: Vec<(QuoteAtomsPerBaseAtom, BaseAtoms)> =
+        dynamic_account.get_depth_levels(false, num_levels, now_slot)?;
+    depth_snapshot_log.num_bids = bids.len() as u8;
+    depth_snapshot_log.num_asks = asks.len() as u8;
+    for (level, (price, base_atoms)) in depth_snapshot_log.bids.iter_mut().zip(bids) {
+        *level = DepthLevel { price, base_atoms };
+    }
+    for (level, (price, base_atoms)) in depth_snapshot_log.asks.iter_mut().zip(asks) {
+        *level = DepthLevel { price, base_atoms };
+    }
+    emit_stack(depth_snapshot_log)
+}
+
+// Logs are not emitted for formal verification.
+#[cfg(feature = "certora")]
+pub(crate) fn process_emit_depth_snapshot(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
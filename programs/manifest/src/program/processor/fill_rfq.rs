@@ -0,0 +1,335 @@
+use std::{cell::RefMut, mem::size_of};
+
+use crate::{
+    logs::{emit_stack, RfqLog},
+    quantities::{BaseAtoms, WrapperU64},
+    state::Rfq,
+    utils::create_account,
+    validation::{get_rfq_address, loaders::PostRfqContext, ManifestAccountInfo},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{get_mut_helper, trace, PodBool};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey, rent::Rent,
+    sysvar::Sysvar,
+};
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::shared::{
+        get_mut_dynamic_account, split_instructions_sysvar, verifies_ed25519_signature,
+        verify_cpi_caller, verify_not_paused,
+    },
+    crate::{
+        program::ManifestError,
+        quantities::QuoteAtomsPerBaseAtom,
+        require,
+        state::{
+            utils::{assert_market_open, assert_not_in_recovery, get_now_slot},
+            MarketRefMut, MAX_RFQ_RESPONSES,
+        },
+        validation::loaders::FillRfqContext,
+    },
+    hypertree::{get_helper, is_nil, DataIndex, NIL},
+    std::cell::Ref,
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct PostRfqParams {
+    /// Size of the request. Zero cancels the open request.
+    pub base_atoms: u64,
+    pub taker_is_buy: bool,
+    /// Last slot the request can be filled in. Cannot be zero, requests
+    /// always expire.
+    pub last_valid_slot: u32,
+}
+
+impl PostRfqParams {
+    pub fn new(base_atoms: u64, taker_is_buy: bool, last_valid_slot: u32) -> Self {
+        PostRfqParams {
+            base_atoms,
+            taker_is_buy,
+            last_valid_slot,
+        }
+    }
+}
+
+/// Posts a request for quote of the payer, replacing their open one on the
+/// market. The payer funds their Rfq account on the first request.
+pub(crate) fn process_post_rfq(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    trace!("process_post_rfq accs={accounts:?}");
+    let params: PostRfqParams = PostRfqParams::try_from_slice(data)?;
+    let post_rfq_context: PostRfqContext = PostRfqContext::load(accounts)?;
+    let PostRfqContext {
+        taker,
+        market,
+        system_program,
+        rfq,
+    } = post_rfq_context;
+
+    if rfq.data_is_empty() {
+        let (_expected_rfq_key, rfq_bump) = get_rfq_address(market.key, taker.key);
+        create_account(
+            taker.as_ref(),
+            rfq,
+            system_program.as_ref(),
+            &crate::id(),
+            &Rent::get()?,
+            size_of::<Rfq>() as u64,
+            vec![
+                b"rfq".to_vec(),
+                market.key.as_ref().to_vec(),
+                taker.key.as_ref().to_vec(),
+                vec![rfq_bump],
+            ],
+        )?;
+        let rfq_bytes: &mut [u8] = &mut rfq.try_borrow_mut_data()?[..];
+        *get_mut_helper::<Rfq>(rfq_bytes, 0_u32) = Rfq::new_empty(market.key, taker.key);
+    }
+    let rfq: ManifestAccountInfo<Rfq> = ManifestAccountInfo::<Rfq>::new(rfq)?;
+
+    let rfq_data: &mut RefMut<&mut [u8]> = &mut rfq.try_borrow_mut_data()?;
+    let rfq_state: &mut Rfq = get_mut_helper::<Rfq>(rfq_data, 0_u32);
+    rfq_state.post(
+        BaseAtoms::new(params.base_atoms),
+        params.taker_is_buy,
+        params.last_valid_slot,
+    )?;
+
+    emit_stack(RfqLog {
+        market: *market.key,
+        taker: *taker.key,
+        rfq: *rfq.key,
+        base_atoms: rfq_state.get_base_atoms(),
+        sequence_number: rfq_state.get_sequence_number(),
+        last_valid_slot: rfq_state.get_last_valid_slot(),
+        taker_is_buy: PodBool::from(rfq_state.get_taker_is_buy()),
+        _padding: [0; 3],
+    })?;
+    Ok(())
+}
+
+/// A maker's answer to an open Rfq, for its whole size and side. The signed
+/// message is the borsh serialization of it, see RfqQuote::message.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct RfqQuote {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub rfq: Pubkey,
+    /// Sequence number of the request the quote answers.
+    pub sequence_number: u64,
+    pub price_mantissa: u32,
+    pub price_exponent: i8,
+    /// Last slot the quote can be filled in.
+    pub last_valid_slot: u32,
+}
+
+impl RfqQuote {
+    pub fn new(
+        rfq: Pubkey,
+        sequence_number: u64,
+        price_mantissa: u32,
+        price_exponent: i8,
+        last_valid_slot: u32,
+    ) -> Self {
+        RfqQuote {
+            rfq,
+            sequence_number,
+            price_mantissa,
+            price_exponent,
+            last_valid_slot,
+        }
+    }
+
+    /// Bytes the maker signs.
+    pub fn message(&self) -> Vec<u8> {
+        borsh::to_vec(self).unwrap_or_default()
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct RfqResponse {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub maker: Pubkey,
+    pub quote: RfqQuote,
+}
+
+impl RfqResponse {
+    pub fn new(maker: Pubkey, quote: RfqQuote) -> Self {
+        RfqResponse { maker, quote }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct FillRfqParams {
+    /// Quotes the taker collected, at most MAX_RFQ_RESPONSES.
+    pub responses: Vec<RfqResponse>,
+}
+
+impl FillRfqParams {
+    pub fn new(responses: Vec<RfqResponse>) -> Self {
+        FillRfqParams { responses }
+    }
+}
+
+/// Fills the open Rfq of the taker against the best of the quotes given,
+/// settling from the seat balances of both sides. The instructions right
+/// before the top level instruction have to be one ed25519 program
+/// instruction per quote, in the same order, each verifying the maker's
+/// signature of RfqQuote::message. Quotes that expired, answer another
+/// request, or whose maker has no seat or not enough balance are passed over.
+/// The request is closed once filled.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_fill_rfq(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    trace!("process_fill_rfq accs={accounts:?}");
+    let params: FillRfqParams = FillRfqParams::try_from_slice(data)?;
+    let (accounts, instructions_sysvar_opt) = split_instructions_sysvar(accounts);
+    let fill_rfq_context: FillRfqContext = FillRfqContext::load(accounts)?;
+    let FillRfqContext { taker, market, rfq } = fill_rfq_context;
+    let FillRfqParams { responses } = params;
+
+    let Some(instructions_sysvar) = instructions_sysvar_opt else {
+        return Err(ManifestError::InvalidRfq.into());
+    };
+    require!(
+        !responses.is_empty() && responses.len() <= MAX_RFQ_RESPONSES,
+        ManifestError::InvalidRfq,
+        "RFQ fill takes 1 to {} quotes, got {}",
+        MAX_RFQ_RESPONSES,
+        responses.len(),
+    )?;
+    for (index, RfqResponse { maker, quote }) in responses.iter().enumerate() {
+        require!(
+            verifies_ed25519_signature(
+                instructions_sysvar,
+                (responses.len() - index) as u16,
+                maker,
+                &quote.message(),
+            )?,
+            ManifestError::InvalidRfq,
+            "No ed25519 instruction verifies the quote of {}",
+            maker,
+        )?;
+    }
+
+    let now_slot: u32 = get_now_slot();
+    let (base_atoms, taker_is_buy, sequence_number) = {
+        let rfq_data: &Ref<&mut [u8]> = &rfq.try_borrow_data()?;
+        let rfq_state: &Rfq = get_helper::<Rfq>(rfq_data, 0_u32);
+        require!(
+            rfq_state.is_open(now_slot),
+            ManifestError::InvalidRfq,
+            "RFQ is filled, cancelled or expired",
+        )?;
+        (
+            rfq_state.get_base_atoms(),
+            rfq_state.get_taker_is_buy(),
+            rfq_state.get_sequence_number(),
+        )
+    };
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+    assert_market_open(&market.get_fixed()?.get_trading_hours())?;
+    verify_not_paused(&market)?;
+    verify_cpi_caller(&market, instructions_sysvar_opt)?;
+
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+    let taker_index: DataIndex = dynamic_account.get_trader_index(taker.key);
+
+    // Best price for the taker, the first quote of it on ties.
+    let mut best_opt: Option<(DataIndex, QuoteAtomsPerBaseAtom)> = None;
+    for RfqResponse { maker, quote } in responses.iter() {
+        if quote.rfq != *rfq.key
+            || quote.sequence_number != sequence_number
+            || quote.last_valid_slot < now_slot
+        {
+            continue;
+        }
+        let Ok(price) = QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
+            quote.price_mantissa,
+            quote.price_exponent,
+        ) else {
+            continue;
+        };
+        let maker_index: DataIndex = dynamic_account.get_trader_index(maker);
+        if is_nil!(maker_index) || maker_index == taker_index {
+            continue;
+        }
+        let (maker_base_atoms, maker_quote_atoms) = dynamic_account.get_trader_balance(maker);
+        let maker_can_cover: bool = if taker_is_buy {
+            maker_base_atoms >= base_atoms
+        } else {
+            price
+                .checked_quote_for_base(base_atoms, false)
+                .is_ok_and(|quote_atoms| maker_quote_atoms >= quote_atoms)
+        };
+        if !maker_can_cover {
+            continue;
+        }
+        let is_better: bool = match best_opt {
+            None => true,
+            Some((_, best_price)) if taker_is_buy => price < best_price,
+            Some((_, best_price)) => price > best_price,
+        };
+        if is_better {
+            best_opt = Some((maker_index, price));
+        }
+    }
+    let Some((maker_index, price)) = best_opt else {
+        return Err(ManifestError::InvalidRfq.into());
+    };
+
+    dynamic_account.fill_rfq(
+        *market.key,
+        maker_index,
+        taker_index,
+        base_atoms,
+        price,
+        taker_is_buy,
+    )?;
+
+    let rfq_data: &mut RefMut<&mut [u8]> = &mut rfq.try_borrow_mut_data()?;
+    get_mut_helper::<Rfq>(rfq_data, 0_u32).fill();
+    Ok(())
+}
+
+// Formal verification does not model RFQs.
+#[cfg(feature = "certora")]
+pub(crate) fn process_fill_rfq(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
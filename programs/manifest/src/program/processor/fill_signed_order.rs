@@ -0,0 +1,273 @@
+use std::{cell::RefMut, mem::size_of};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::get_mut_helper;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey, rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    state::SignedOrderNonces,
+    utils::create_account,
+    validation::{
+        get_signed_order_nonces_address, loaders::CancelSignedOrdersContext, ManifestAccountInfo,
+    },
+};
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::shared::{
+        get_mut_dynamic_account, split_instructions_sysvar, verifies_ed25519_signature,
+        verify_cpi_caller, verify_not_paused,
+    },
+    crate::{
+        program::ManifestError,
+        quantities::{BaseAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
+        require,
+        state::{
+            utils::{
+                assert_market_open, assert_not_already_expired, assert_not_in_recovery,
+                get_now_slot,
+            },
+            MarketRefMut,
+        },
+        validation::loaders::FillSignedOrderContext,
+    },
+    hypertree::{trace, DataIndex},
+};
+
+/// An order a maker signs off chain. The signed message is the borsh
+/// serialization of it, see SignedOrder::message.
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct SignedOrder {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub market: Pubkey,
+    pub base_atoms: u64,
+    pub price_mantissa: u32,
+    pub price_exponent: i8,
+    /// Side of the maker. The taker takes the other side.
+    pub is_bid: bool,
+    /// Last slot the order can be filled in. Cannot be zero, signed orders
+    /// always expire.
+    pub last_valid_slot: u32,
+    /// Chosen by the maker, each can be filled once, see SignedOrderNonces.
+    pub nonce: u64,
+}
+
+impl SignedOrder {
+    pub fn new(
+        market: Pubkey,
+        base_atoms: u64,
+        price_mantissa: u32,
+        price_exponent: i8,
+        is_bid: bool,
+        last_valid_slot: u32,
+        nonce: u64,
+    ) -> Self {
+        SignedOrder {
+            market,
+            base_atoms,
+            price_mantissa,
+            price_exponent,
+            is_bid,
+            last_valid_slot,
+            nonce,
+        }
+    }
+
+    /// Bytes the maker signs.
+    pub fn message(&self) -> Vec<u8> {
+        borsh::to_vec(self).unwrap_or_default()
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct FillSignedOrderParams {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub maker: Pubkey,
+    pub order: SignedOrder,
+}
+
+impl FillSignedOrderParams {
+    pub fn new(maker: Pubkey, order: SignedOrder) -> Self {
+        FillSignedOrderParams { maker, order }
+    }
+}
+
+/// Fill the whole of an order the maker signed off chain, settling from the
+/// seat balances of both sides like an RFQ fill. The instruction right before
+/// the top level instruction has to be an ed25519 program instruction that
+/// verifies the maker's signature of the order. Uses the nonce of the order in
+/// the maker's SignedOrderNonces, so each signed order fills at most once.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_fill_signed_order(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    trace!("process_fill_signed_order accs={accounts:?}");
+    let params: FillSignedOrderParams = FillSignedOrderParams::try_from_slice(data)?;
+    let (accounts, instructions_sysvar_opt) = split_instructions_sysvar(accounts);
+    let fill_signed_order_context: FillSignedOrderContext = FillSignedOrderContext::load(accounts)?;
+    let FillSignedOrderContext {
+        taker,
+        market,
+        signed_order_nonces,
+    } = fill_signed_order_context;
+    let FillSignedOrderParams { maker, order } = params;
+
+    let Some(instructions_sysvar) = instructions_sysvar_opt else {
+        return Err(ManifestError::InvalidSignedOrder.into());
+    };
+    require!(
+        verifies_ed25519_signature(instructions_sysvar, 1, &maker, &order.message())?,
+        ManifestError::InvalidSignedOrder,
+        "Instruction before does not verify a signature of the order by {}",
+        maker,
+    )?;
+
+    let SignedOrder {
+        market: order_market,
+        base_atoms,
+        price_mantissa,
+        price_exponent,
+        is_bid,
+        last_valid_slot,
+        nonce,
+    } = order;
+    require!(
+        order_market == *market.key,
+        ManifestError::InvalidSignedOrder,
+        "Order was signed for market {}",
+        order_market,
+    )?;
+    require!(
+        base_atoms > 0 && last_valid_slot != 0,
+        ManifestError::InvalidSignedOrder,
+        "Signed order needs a positive size and an expiration",
+    )?;
+    assert_not_already_expired(last_valid_slot, get_now_slot())?;
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+    assert_market_open(&market.get_fixed()?.get_trading_hours())?;
+    verify_not_paused(&market)?;
+    verify_cpi_caller(&market, instructions_sysvar_opt)?;
+    {
+        let nonces_data: &mut RefMut<&mut [u8]> = &mut signed_order_nonces.try_borrow_mut_data()?;
+        let nonces: &mut SignedOrderNonces =
+            get_mut_helper::<SignedOrderNonces>(nonces_data, 0_u32);
+        require!(
+            nonces.trader == maker && nonces.market == *market.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect signed order nonces account",
+        )?;
+        nonces.use_nonce(nonce)?;
+    }
+    let price: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(price_mantissa, price_exponent)?;
+
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+    let maker_index: DataIndex = dynamic_account.get_trader_index(&maker);
+    let taker_index: DataIndex = dynamic_account.get_trader_index(taker.key);
+    dynamic_account.fill_rfq(
+        *market.key,
+        maker_index,
+        taker_index,
+        BaseAtoms::new(base_atoms),
+        price,
+        !is_bid,
+    )?;
+
+    Ok(())
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct CancelSignedOrdersParams {
+    /// Signed orders of the payer with a lower nonce can no longer be filled.
+    pub min_nonce: u64,
+}
+
+impl CancelSignedOrdersParams {
+    pub fn new(min_nonce: u64) -> Self {
+        CancelSignedOrdersParams { min_nonce }
+    }
+}
+
+/// Cancels every signed order of the payer on a market with a nonce below
+/// min_nonce. The first call creates the payer's SignedOrderNonces, which
+/// FillSignedOrder needs, so makers send one with a zero min_nonce before
+/// handing out signed orders.
+pub(crate) fn process_cancel_signed_orders(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let params: CancelSignedOrdersParams = CancelSignedOrdersParams::try_from_slice(data)?;
+    let cancel_signed_orders_context: CancelSignedOrdersContext =
+        CancelSignedOrdersContext::load(accounts)?;
+    let CancelSignedOrdersContext {
+        payer,
+        market,
+        system_program,
+        signed_order_nonces,
+    } = cancel_signed_orders_context;
+
+    if signed_order_nonces.data_is_empty() {
+        let (_expected_signed_order_nonces_key, signed_order_nonces_bump) =
+            get_signed_order_nonces_address(market.key, payer.key);
+        create_account(
+            payer.as_ref(),
+            signed_order_nonces,
+            system_program.as_ref(),
+            &crate::id(),
+            &Rent::get()?,
+            size_of::<SignedOrderNonces>() as u64,
+            vec![
+                b"signed-order-nonces".to_vec(),
+                market.key.as_ref().to_vec(),
+                payer.key.as_ref().to_vec(),
+                vec![signed_order_nonces_bump],
+            ],
+        )?;
+        let nonces_bytes: &mut [u8] = &mut signed_order_nonces.try_borrow_mut_data()?[..];
+        *get_mut_helper::<SignedOrderNonces>(nonces_bytes, 0_u32) =
+            SignedOrderNonces::new_empty(market.key, payer.key);
+    }
+    let signed_order_nonces: ManifestAccountInfo<SignedOrderNonces> =
+        ManifestAccountInfo::<SignedOrderNonces>::new(signed_order_nonces)?;
+
+    let nonces_data: &mut RefMut<&mut [u8]> = &mut signed_order_nonces.try_borrow_mut_data()?;
+    get_mut_helper::<SignedOrderNonces>(nonces_data, 0_u32).advance(params.min_nonce);
+    Ok(())
+}
+
+// Formal verification does not model signed orders.
+#[cfg(feature = "certora")]
+pub(crate) fn process_fill_signed_order(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
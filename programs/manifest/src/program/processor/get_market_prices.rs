@@ -0,0 +1,99 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::shared::get_dynamic_account,
+    crate::{
+        quantities::{u64_slice_to_u128, QuoteAtomsPerBaseAtom, WrapperU64},
+        state::{constants::PRICE_DEPTH_BAND_BPS, utils::get_now_slot, MarketFixed, MarketRef},
+        validation::loaders::GetMarketPricesContext,
+    },
+    std::{cell::Ref, mem::size_of},
+};
+
+/// Return data of GetMarketPrices. Prices are quote atoms per base atom
+/// times 10^18, zero when there is none.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct GetMarketPricesReturn {
+    /// Slot the prices were read in.
+    pub slot: u32,
+    /// Best bid and ask that are not expired or global.
+    pub best_bid_price: u128,
+    pub best_ask_price: u128,
+    /// Latest fill against the book and its slot, zero before the first.
+    pub last_trade_price: u128,
+    pub last_trade_slot: u32,
+    /// See MarketFixed::get_twap_price.
+    pub twap_price: u128,
+    /// Size resting within PRICE_DEPTH_BAND_BPS of the best bid and ask,
+    /// counted like the best prices.
+    pub bid_depth_base_atoms: u64,
+    pub bid_depth_quote_atoms: u64,
+    pub ask_depth_base_atoms: u64,
+    pub ask_depth_quote_atoms: u64,
+}
+
+/// Read only view of market prices for programs that price collateral off
+/// Manifest. Writes GetMarketPricesReturn as return data so it can be read
+/// after a CPI. Nothing is changed and no signer is needed.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_get_market_prices(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let get_market_prices_context: GetMarketPricesContext = GetMarketPricesContext::load(accounts)?;
+    let GetMarketPricesContext { market } = get_market_prices_context;
+
+    let market_data: &Ref<&mut [u8]> = &market.try_borrow_data()?;
+    let dynamic_account: MarketRef = get_dynamic_account(market_data);
+    let fixed: &MarketFixed = dynamic_account.fixed;
+    let now_slot: u32 = get_now_slot();
+
+    let mut return_data: GetMarketPricesReturn = GetMarketPricesReturn {
+        slot: now_slot,
+        last_trade_price: price_to_u128(fixed.get_last_trade_price()),
+        last_trade_slot: fixed.get_last_trade_slot(),
+        twap_price: price_to_u128(fixed.get_twap_price(now_slot)),
+        ..GetMarketPricesReturn::default()
+    };
+    if let Some((best_price, base_atoms, quote_atoms)) =
+        dynamic_account.get_price_depth(true, PRICE_DEPTH_BAND_BPS, now_slot)?
+    {
+        return_data.best_bid_price = price_to_u128(best_price);
+        return_data.bid_depth_base_atoms = base_atoms.as_u64();
+        return_data.bid_depth_quote_atoms = quote_atoms.as_u64();
+    }
+    if let Some((best_price, base_atoms, quote_atoms)) =
+        dynamic_account.get_price_depth(false, PRICE_DEPTH_BAND_BPS, now_slot)?
+    {
+        return_data.best_ask_price = price_to_u128(best_price);
+        return_data.ask_depth_base_atoms = base_atoms.as_u64();
+        return_data.ask_depth_quote_atoms = quote_atoms.as_u64();
+    }
+
+    let mut buffer: Vec<u8> = Vec::with_capacity(size_of::<GetMarketPricesReturn>());
+    return_data.serialize(&mut buffer)?;
+    solana_program::program::set_return_data(&buffer[..]);
+    Ok(())
+}
+
+// Formal verification does not cover return values.
+#[cfg(feature = "certora")]
+pub(crate) fn process_get_market_prices(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
+
+#[cfg(not(feature = "certora"))]
+fn price_to_u128(price: QuoteAtomsPerBaseAtom) -> u128 {
+    u64_slice_to_u128(price.inner)
+}
@@ -0,0 +1,74 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::shared::get_dynamic_account,
+    crate::{
+        state::{constants::POINTS_EPOCH_SLOTS, utils::get_now_slot, MarketRef},
+        validation::loaders::GetSeatPointsContext,
+    },
+    hypertree::{is_not_nil, DataIndex, NIL},
+    std::{cell::Ref, mem::size_of},
+};
+
+/// Return data of GetSeatPoints. All zero for seats that do not earn points.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct GetSeatPointsReturn {
+    /// Current epoch and the points earned in it so far.
+    pub epoch: u32,
+    pub points: u64,
+    /// Latest finished epoch with points that have not been claimed.
+    pub claimable_epoch: u32,
+    pub claimable_points: u64,
+}
+
+/// Read only view of the points of a trader's seat. Data is the trader's
+/// pubkey and GetSeatPointsReturn is written as return data.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_get_seat_points(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let trader: Pubkey = Pubkey::try_from_slice(data)?;
+    let get_seat_points_context: GetSeatPointsContext = GetSeatPointsContext::load(accounts)?;
+    let GetSeatPointsContext { market } = get_seat_points_context;
+
+    let market_data: &Ref<&mut [u8]> = &market.try_borrow_data()?;
+    let dynamic_account: MarketRef = get_dynamic_account(market_data);
+    let epoch: u32 = get_now_slot() / POINTS_EPOCH_SLOTS;
+
+    let mut return_data: GetSeatPointsReturn = GetSeatPointsReturn::default();
+    let trader_index: DataIndex = dynamic_account.get_trader_index(&trader);
+    if is_not_nil!(trader_index) {
+        if let Some(seat_config) = dynamic_account.get_seat_config(trader_index) {
+            let (epoch, points, claimable_epoch, claimable_points) = seat_config.get_points(epoch);
+            return_data = GetSeatPointsReturn {
+                epoch,
+                points,
+                claimable_epoch,
+                claimable_points,
+            };
+        }
+    }
+
+    let mut buffer: Vec<u8> = Vec::with_capacity(size_of::<GetSeatPointsReturn>());
+    return_data.serialize(&mut buffer)?;
+    solana_program::program::set_return_data(&buffer[..]);
+    Ok(())
+}
+
+// Formal verification does not cover return values.
+#[cfg(feature = "certora")]
+pub(crate) fn process_get_seat_points(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
@@ -9,13 +9,17 @@ use crate::{
     quantities::{GlobalAtoms, WrapperU64},
     require,
     state::{
-        utils::{get_now_slot, settle_global_gas_refunds},
+        utils::{assert_not_in_recovery, get_now_slot, settle_global_gas_refunds},
         GlobalRefMut, MarketRefMut, RestingOrder, MARKET_BLOCK_SIZE,
     },
     validation::loaders::{GlobalCleanContext, GlobalTradeAccounts},
 };
 
 #[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct GlobalCleanParams {
     pub order_index: DataIndex,
 }
@@ -40,6 +44,7 @@ pub(crate) fn process_global_clean(
         global,
         system_program,
     } = global_clean_context;
+    assert_not_in_recovery(&*market.get_fixed()?)?;
 
     let global_trade_accounts: GlobalTradeAccounts = GlobalTradeAccounts {
         mint_opt: None,
@@ -111,8 +116,7 @@ pub(crate) fn process_global_clean(
     let required_global_atoms: u64 = if resting_order.get_is_bid() {
         resting_order
             .get_num_base_atoms()
-            .checked_mul(resting_order.get_price(), true)
-            .unwrap()
+            .checked_mul(resting_order.get_price(), true)?
             .as_u64()
     } else {
         resting_order.get_num_base_atoms().as_u64()
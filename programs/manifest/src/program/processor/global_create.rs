@@ -120,7 +120,7 @@ pub(crate) fn process_global_create(
             if is_mint_22 {
                 let mint_data: Ref<'_, &mut [u8]> = global_mint.info.data.borrow();
                 let mint_with_extension: PodStateWithExtensions<'_, PodMint> =
-                    PodStateWithExtensions::<PodMint>::unpack(&mint_data).unwrap();
+                    PodStateWithExtensions::<PodMint>::unpack(&mint_data)?;
                 let mint_extensions: Vec<ExtensionType> =
                     mint_with_extension.get_extension_types()?;
                 let required_extensions: Vec<ExtensionType> =
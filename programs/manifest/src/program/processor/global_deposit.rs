@@ -5,7 +5,7 @@ use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubke
 
 use crate::{
     logs::{emit_stack, GlobalDepositLog},
-    program::get_mut_dynamic_account,
+    program::{get_mut_dynamic_account, ManifestError},
     quantities::{GlobalAtoms, WrapperU64},
     state::GlobalRefMut,
     validation::{loaders::GlobalDepositContext, MintAccountInfo, Signer, TokenAccountInfo},
@@ -24,6 +24,10 @@ use {
 };
 
 #[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct GlobalDepositParams {
     pub amount_atoms: u64,
     // No trader index hint because global account is small so there is not much
@@ -83,7 +87,7 @@ pub(crate) fn process_global_deposit_core(
         let after_vault_balance_atoms: u64 = global_vault.get_balance_atoms();
         deposited_amount_atoms = after_vault_balance_atoms
             .checked_sub(before_vault_balance_atoms)
-            .unwrap();
+            .ok_or(ManifestError::Overflow)?;
     } else {
         spl_token_transfer_from_trader_to_global_vault(
             &token_program,
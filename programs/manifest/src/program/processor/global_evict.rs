@@ -13,7 +13,7 @@ use crate::{
 };
 use crate::{
     logs::{emit_stack, GlobalDepositLog, GlobalEvictLog, GlobalWithdrawLog},
-    program::get_mut_dynamic_account,
+    program::{get_mut_dynamic_account, ManifestError},
     quantities::{GlobalAtoms, WrapperU64},
     require,
     state::{GlobalFixed, GlobalRefMut},
@@ -33,6 +33,10 @@ use {
 };
 
 #[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct GlobalEvictParams {
     // Deposit amount that must be greater than the evictee deposit amount
     amount_atoms: u64,
@@ -144,7 +148,7 @@ pub(crate) fn process_global_evict_core(
         let after_vault_balance_atoms: u64 = global_vault.get_balance_atoms();
         let deposited_amount_atoms: u64 = after_vault_balance_atoms
             .checked_sub(before_vault_balance_atoms)
-            .unwrap();
+            .ok_or(ManifestError::Overflow)?;
 
         // Verify that the actual deposited amount is greater than the evictee balance.
         // This check is done after the deposit to account for token22 transfer fees.
@@ -24,6 +24,10 @@ use {
 };
 
 #[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct GlobalWithdrawParams {
     pub amount_atoms: u64,
     // No trader index hint because global account is small so there is not much
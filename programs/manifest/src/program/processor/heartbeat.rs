@@ -0,0 +1,205 @@
+use std::{
+    cell::{Ref, RefMut},
+    cmp::Ordering,
+    mem::size_of,
+};
+
+use crate::{
+    program::{invoke, ManifestError},
+    require,
+    state::{utils::get_now_slot, Heartbeat, MarketRef},
+    utils::create_account,
+    validation::{get_heartbeat_address, loaders::HeartbeatContext, ManifestAccountInfo},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{get_mut_helper, is_not_nil, NIL};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar,
+};
+
+use super::shared::get_dynamic_account;
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::shared::get_mut_dynamic_account,
+    crate::{
+        logs::{emit_stack, CancelOrderLog},
+        state::{utils::assert_not_in_recovery, MarketRefMut},
+        validation::loaders::CancelOnHeartbeatTimeoutContext,
+    },
+    hypertree::DataIndex,
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct HeartbeatParams {
+    /// Slots after this heartbeat until anyone can cancel all the orders of
+    /// the seat. Zero disarms the switch.
+    pub timeout_slots: u32,
+    /// Paid to whoever cancels them. The heartbeat account is topped up or
+    /// refunded to hold this much above rent.
+    pub bounty_lamports: u64,
+}
+
+impl HeartbeatParams {
+    pub fn new(timeout_slots: u32, bounty_lamports: u64) -> Self {
+        HeartbeatParams {
+            timeout_slots,
+            bounty_lamports,
+        }
+    }
+}
+
+/// Restarts the dead man's switch of the payer on a market. The first
+/// heartbeat creates the payer's heartbeat account.
+pub(crate) fn process_heartbeat(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let params: HeartbeatParams = HeartbeatParams::try_from_slice(data)?;
+    let heartbeat_context: HeartbeatContext = HeartbeatContext::load(accounts)?;
+    let HeartbeatContext {
+        payer,
+        market,
+        system_program,
+        heartbeat,
+    } = heartbeat_context;
+
+    {
+        let market_data: &Ref<&mut [u8]> = &market.try_borrow_data()?;
+        let dynamic_account: MarketRef = get_dynamic_account(market_data);
+        require!(
+            is_not_nil!(dynamic_account.get_trader_index(payer.key)),
+            ManifestError::InvalidHeartbeat,
+            "No seat initialized",
+        )?;
+    }
+
+    let rent: Rent = Rent::get()?;
+    if heartbeat.data_is_empty() {
+        let (_expected_heartbeat_key, heartbeat_bump) =
+            get_heartbeat_address(market.key, payer.key);
+        create_account(
+            payer.as_ref(),
+            heartbeat,
+            system_program.as_ref(),
+            &crate::id(),
+            &rent,
+            size_of::<Heartbeat>() as u64,
+            vec![
+                b"heartbeat".to_vec(),
+                market.key.as_ref().to_vec(),
+                payer.key.as_ref().to_vec(),
+                vec![heartbeat_bump],
+            ],
+        )?;
+        let heartbeat_bytes: &mut [u8] = &mut heartbeat.try_borrow_mut_data()?[..];
+        *get_mut_helper::<Heartbeat>(heartbeat_bytes, 0_u32) =
+            Heartbeat::new_empty(market.key, payer.key);
+    }
+    let heartbeat: ManifestAccountInfo<Heartbeat> =
+        ManifestAccountInfo::<Heartbeat>::new(heartbeat)?;
+
+    let HeartbeatParams {
+        timeout_slots,
+        bounty_lamports,
+    } = params;
+    {
+        let heartbeat_data: &mut RefMut<&mut [u8]> = &mut heartbeat.try_borrow_mut_data()?;
+        get_mut_helper::<Heartbeat>(heartbeat_data, 0_u32).beat(
+            timeout_slots,
+            bounty_lamports,
+            get_now_slot(),
+        );
+    }
+
+    let target_lamports: u64 = rent
+        .minimum_balance(size_of::<Heartbeat>())
+        .checked_add(bounty_lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    let lamports: u64 = heartbeat.info.lamports();
+    match lamports.cmp(&target_lamports) {
+        Ordering::Less => invoke(
+            &system_instruction::transfer(payer.key, heartbeat.key, target_lamports - lamports),
+            &[payer.info.clone(), heartbeat.info.clone()],
+        ),
+        Ordering::Greater => {
+            // The account carries data, so the refund cannot be a system
+            // transfer. No CPI follows, so moving the lamports directly is
+            // safe.
+            **heartbeat.info.lamports.borrow_mut() = target_lamports;
+            **payer.info.lamports.borrow_mut() += lamports - target_lamports;
+            Ok(())
+        }
+        Ordering::Equal => Ok(()),
+    }
+}
+
+/// Cancels all the orders of a seat whose heartbeat timed out and pays the
+/// bounty to the payer. Permissionless, so keepers can crank it. Global
+/// orders are cancelled without their global accounts, which forfeits their
+/// gas prepayment.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_cancel_on_heartbeat_timeout(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let cancel_on_heartbeat_timeout_context: CancelOnHeartbeatTimeoutContext =
+        CancelOnHeartbeatTimeoutContext::load(accounts)?;
+    let CancelOnHeartbeatTimeoutContext {
+        payer,
+        market,
+        heartbeat,
+    } = cancel_on_heartbeat_timeout_context;
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+
+    let (trader, bounty_lamports): (Pubkey, u64) = {
+        let heartbeat_data: &mut RefMut<&mut [u8]> = &mut heartbeat.try_borrow_mut_data()?;
+        let heartbeat_state: &mut Heartbeat = get_mut_helper::<Heartbeat>(heartbeat_data, 0_u32);
+        (
+            heartbeat_state.trader,
+            heartbeat_state.fire(get_now_slot())?,
+        )
+    };
+
+    {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        // An archived seat has no orders left to cancel.
+        let trader_index: DataIndex = dynamic_account.get_trader_index(&trader);
+        if is_not_nil!(trader_index) {
+            for order_index in dynamic_account.get_trader_order_indices(trader_index) {
+                let order_sequence_number: u64 = dynamic_account
+                    .get_order_by_index(order_index)
+                    .get_sequence_number();
+                dynamic_account.cancel_order_by_index(order_index, &[None, None])?;
+                emit_stack(CancelOrderLog {
+                    market: *market.key,
+                    trader,
+                    order_sequence_number,
+                })?;
+            }
+        }
+    }
+
+    // Same as the refund on heartbeat, no CPI follows.
+    **heartbeat.info.lamports.borrow_mut() -= bounty_lamports;
+    **payer.info.lamports.borrow_mut() += bounty_lamports;
+    Ok(())
+}
+
+// Formal verification does not model heartbeats.
+#[cfg(feature = "certora")]
+pub(crate) fn process_cancel_on_heartbeat_timeout(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
@@ -1,16 +1,41 @@
+// Processors run on chain, where a panic aborts the transaction without an
+// error code. Fallible steps return a ManifestError instead.
+#![deny(clippy::unwrap_used, clippy::expect_used, clippy::panic)]
+
+pub mod archive_seat;
 pub mod batch_update;
+pub mod bust_trade;
+pub mod claim_fees;
+pub mod claim_points;
 pub mod claim_seat;
+pub mod commit_swap;
 pub mod create_market;
 pub mod deposit;
+pub mod emit_depth_snapshot;
 pub mod expand_market;
+pub mod fill_rfq;
+pub mod fill_signed_order;
+pub mod get_market_prices;
+pub mod get_seat_points;
 pub mod global_add_trader;
 pub mod global_clean;
 pub mod global_create;
 pub mod global_deposit;
 pub mod global_evict;
 pub mod global_withdraw;
+pub mod heartbeat;
+pub mod pegged_order;
+pub mod rebalance;
+pub mod recovery;
+pub mod reset_price_band;
+pub mod restore_seat;
+pub mod set_mmp;
+pub mod set_paused;
+pub mod set_seat_config;
 pub mod shared;
+pub mod stop_order;
 pub mod swap;
+pub mod update_volatility;
 pub mod withdraw;
 
 pub use shared::*;
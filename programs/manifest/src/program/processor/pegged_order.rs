@@ -0,0 +1,496 @@
+use std::{cell::RefMut, mem::size_of};
+
+use crate::{
+    program::{get_trader_index_with_hint, ManifestError},
+    quantities::{BaseAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
+    require,
+    state::{
+        utils::{assert_not_in_recovery, get_now_slot},
+        MarketRefMut, PegReference, PeggedOrders,
+    },
+    utils::create_account,
+    validation::{
+        get_pegged_orders_address, loaders::PlacePeggedOrderContext, ManifestAccountInfo,
+    },
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{get_mut_helper, is_not_nil, trace, DataIndex};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey, rent::Rent,
+    sysvar::Sysvar,
+};
+
+use super::shared::{get_mut_dynamic_account, split_instructions_sysvar, verify_cpi_caller};
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::{
+        batch_update::MarketDataTreeNodeType,
+        shared::{expand_market_if_needed, verify_not_paused},
+    },
+    crate::{
+        logs::{emit_stack, CancelOrderLog, PeggedOrderPlacedLog, PlaceOrderLog},
+        quantities::QuoteAtoms,
+        state::{
+            constants::MAX_PEGGED_ORDERS, utils::assert_market_open, AddOrderToMarketArgs,
+            AddOrderToMarketResult, OrderType, PeggedOrder, RestingOrder,
+        },
+        validation::loaders::{CancelPeggedOrderContext, RepricePeggedOrdersContext},
+    },
+    hypertree::{get_helper, HyperTreeValueIteratorTrait, PodBool, RBNode, NIL},
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct PlacePeggedOrderParams {
+    pub base_atoms: u64,
+    pub is_bid: bool,
+    pub reference: PegReference,
+    /// Signed offset from the reference price, positive is a higher price.
+    pub offset_bps: i16,
+    /// Highest price for a bid and lowest for an ask.
+    pub limit_price_mantissa: u32,
+    pub limit_price_exponent: i8,
+    pub last_valid_slot: u32,
+    pub trader_index_hint: Option<DataIndex>,
+}
+
+impl PlacePeggedOrderParams {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        base_atoms: u64,
+        is_bid: bool,
+        reference: PegReference,
+        offset_bps: i16,
+        limit_price_mantissa: u32,
+        limit_price_exponent: i8,
+        last_valid_slot: u32,
+        trader_index_hint: Option<DataIndex>,
+    ) -> Self {
+        PlacePeggedOrderParams {
+            base_atoms,
+            is_bid,
+            reference,
+            offset_bps,
+            limit_price_mantissa,
+            limit_price_exponent,
+            last_valid_slot,
+            trader_index_hint,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct CancelPeggedOrderParams {
+    pub pegged_index: u8,
+}
+
+impl CancelPeggedOrderParams {
+    pub fn new(pegged_index: u8) -> Self {
+        CancelPeggedOrderParams { pegged_index }
+    }
+}
+
+/// Adds a pegged order for the payer, off the book until the next
+/// RepricePeggedOrders. The checks that need the order's notional are done
+/// at the limit price, the furthest a bid can go. The payer funds their
+/// pegged orders account on the first pegged order on the market.
+pub(crate) fn process_place_pegged_order(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    trace!("process_place_pegged_order accs={accounts:?}");
+    let params: PlacePeggedOrderParams = PlacePeggedOrderParams::try_from_slice(data)?;
+    let (accounts, instructions_sysvar_opt) = split_instructions_sysvar(accounts);
+    let place_pegged_order_context: PlacePeggedOrderContext =
+        PlacePeggedOrderContext::load(accounts)?;
+    let PlacePeggedOrderContext {
+        payer,
+        market,
+        system_program,
+        pegged_orders,
+    } = place_pegged_order_context;
+
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+    verify_cpi_caller(&market, instructions_sysvar_opt)?;
+
+    let PlacePeggedOrderParams {
+        base_atoms,
+        is_bid,
+        reference,
+        offset_bps,
+        limit_price_mantissa,
+        limit_price_exponent,
+        last_valid_slot,
+        trader_index_hint,
+    } = params;
+    let limit_price: QuoteAtomsPerBaseAtom = QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
+        limit_price_mantissa,
+        limit_price_exponent,
+    )?;
+    let base_atoms: BaseAtoms = BaseAtoms::new(base_atoms);
+
+    {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        let trader_index: DataIndex =
+            get_trader_index_with_hint(trader_index_hint, &dynamic_account, &payer)?;
+        require!(
+            is_not_nil!(trader_index),
+            ManifestError::InvalidPeggedOrder,
+            "No seat initialized",
+        )?;
+        // Repricing has no cosigner, so pegged orders cannot be larger than
+        // what the seat can place without one.
+        if let Some(seat_config) = dynamic_account.get_seat_config(trader_index) {
+            require!(
+                !seat_config
+                    .requires_cosigner(limit_price.checked_quote_for_base(base_atoms, false)?),
+                ManifestError::MissingCosigner,
+                "Pegged order needs cosigner {}",
+                seat_config.cosigner,
+            )?;
+        }
+        dynamic_account.verify_min_order_size(base_atoms, limit_price)?;
+    }
+
+    if pegged_orders.data_is_empty() {
+        let (_expected_pegged_orders_key, pegged_orders_bump) =
+            get_pegged_orders_address(market.key, payer.key);
+        create_account(
+            payer.as_ref(),
+            pegged_orders,
+            system_program.as_ref(),
+            &crate::id(),
+            &Rent::get()?,
+            size_of::<PeggedOrders>() as u64,
+            vec![
+                b"pegged-orders".to_vec(),
+                market.key.as_ref().to_vec(),
+                payer.key.as_ref().to_vec(),
+                vec![pegged_orders_bump],
+            ],
+        )?;
+        let pegged_orders_bytes: &mut [u8] = &mut pegged_orders.try_borrow_mut_data()?[..];
+        *get_mut_helper::<PeggedOrders>(pegged_orders_bytes, 0_u32) =
+            PeggedOrders::new_empty(market.key, payer.key);
+    }
+    let pegged_orders: ManifestAccountInfo<PeggedOrders> =
+        ManifestAccountInfo::<PeggedOrders>::new(pegged_orders)?;
+
+    let pegged_orders_data: &mut RefMut<&mut [u8]> = &mut pegged_orders.try_borrow_mut_data()?;
+    get_mut_helper::<PeggedOrders>(pegged_orders_data, 0_u32).add(
+        base_atoms,
+        is_bid,
+        reference,
+        offset_bps,
+        limit_price,
+        last_valid_slot,
+        get_now_slot(),
+    )?;
+    Ok(())
+}
+
+/// Index of the resting order of a pegged order, NIL when it is not on the
+/// book anymore. The block it was at may have been freed and reused, so the
+/// order there has to be the trader's with the same sequence number.
+#[cfg(not(feature = "certora"))]
+fn get_resting_order_index(
+    dynamic_account: &MarketRefMut,
+    trader_index: DataIndex,
+    pegged_order: &PeggedOrder,
+) -> DataIndex {
+    if !pegged_order.is_on_book() {
+        return NIL;
+    }
+    let order_index: DataIndex = pegged_order.get_order_index();
+    if get_helper::<RBNode<RestingOrder>>(dynamic_account.dynamic, order_index).get_payload_type()
+        != MarketDataTreeNodeType::RestingOrder as u8
+    {
+        return NIL;
+    }
+    let order: &RestingOrder = dynamic_account.get_order_by_index(order_index);
+    if order.get_trader_index() == trader_index
+        && order.get_sequence_number() == pegged_order.get_order_sequence_number()
+    {
+        order_index
+    } else {
+        NIL
+    }
+}
+
+/// Removes a pegged order of the payer and cancels its resting order.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_cancel_pegged_order(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let params: CancelPeggedOrderParams = CancelPeggedOrderParams::try_from_slice(data)?;
+    let cancel_pegged_order_context: CancelPeggedOrderContext =
+        CancelPeggedOrderContext::load(accounts)?;
+    let CancelPeggedOrderContext {
+        payer,
+        market,
+        pegged_orders,
+    } = cancel_pegged_order_context;
+
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+    let pegged_order: PeggedOrder = {
+        let pegged_orders_data: &mut RefMut<&mut [u8]> =
+            &mut pegged_orders.try_borrow_mut_data()?;
+        get_mut_helper::<PeggedOrders>(pegged_orders_data, 0_u32).remove(params.pegged_index)?
+    };
+
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+    let trader_index: DataIndex = dynamic_account.get_trader_index(payer.key);
+    if is_not_nil!(trader_index) {
+        let order_index: DataIndex =
+            get_resting_order_index(&dynamic_account, trader_index, &pegged_order);
+        if is_not_nil!(order_index) {
+            dynamic_account.cancel_order_by_index(order_index, &[None, None])?;
+            emit_stack(CancelOrderLog {
+                market: *market.key,
+                trader: *payer.key,
+                order_sequence_number: pegged_order.get_order_sequence_number(),
+            })?;
+        }
+    }
+    Ok(())
+}
+
+// Formal verification does not model pegged orders.
+#[cfg(feature = "certora")]
+pub(crate) fn process_cancel_pegged_order(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
+
+/// Moves the pegged orders of one trader to their prices against the book.
+/// The reference prices are taken once, from the book without the trader's
+/// pegged orders, so they do not peg to themselves or each other. Orders
+/// already at their price are left where they are and keep their priority,
+/// the others are cancelled and placed again as PostOnlySlide, so they never
+/// take and rest one step inside the other side instead of crossing it.
+/// Orders the seat cannot fund or that are under the minimum order size at
+/// their price wait off the book for a later call. Pegged orders whose
+/// resting order was filled or cancelled are cleared, as are expired ones.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_reprice_pegged_orders(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let reprice_pegged_orders_context: RepricePeggedOrdersContext =
+        RepricePeggedOrdersContext::load(accounts)?;
+    let RepricePeggedOrdersContext {
+        payer,
+        market,
+        pegged_orders,
+        ..
+    } = reprice_pegged_orders_context;
+
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+    assert_market_open(&market.get_fixed()?.get_trading_hours())?;
+    verify_not_paused(&market)?;
+    let now_slot: u32 = get_now_slot();
+    let trader: Pubkey = pegged_orders.get_fixed()?.trader;
+
+    // Clear what left the book and find the reference prices without the
+    // orders that are still on it.
+    let (best_bid, best_ask) = {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        let trader_index: DataIndex = dynamic_account.get_trader_index(&trader);
+        require!(
+            is_not_nil!(trader_index),
+            ManifestError::InvalidPeggedOrder,
+            "No seat for {}",
+            trader,
+        )?;
+        let pegged_orders_data: &mut RefMut<&mut [u8]> =
+            &mut pegged_orders.try_borrow_mut_data()?;
+        let pegged_orders: &mut PeggedOrders =
+            get_mut_helper::<PeggedOrders>(pegged_orders_data, 0_u32);
+
+        let mut resting_indices: [DataIndex; MAX_PEGGED_ORDERS] = [NIL; MAX_PEGGED_ORDERS];
+        for (pegged_index, resting_index) in resting_indices.iter_mut().enumerate() {
+            let pegged_order: PeggedOrder = pegged_orders.get_orders()[pegged_index];
+            if pegged_order.is_empty() {
+                continue;
+            }
+            let order_index: DataIndex =
+                get_resting_order_index(&dynamic_account, trader_index, &pegged_order);
+            if pegged_order.is_on_book() && !is_not_nil!(order_index) {
+                pegged_orders.remove(pegged_index as u8)?;
+                continue;
+            }
+            if pegged_order.is_expired(now_slot) {
+                if is_not_nil!(order_index) {
+                    dynamic_account.cancel_order_by_index(order_index, &[None, None])?;
+                    emit_stack(CancelOrderLog {
+                        market: *market.key,
+                        trader,
+                        order_sequence_number: pegged_order.get_order_sequence_number(),
+                    })?;
+                }
+                pegged_orders.remove(pegged_index as u8)?;
+                continue;
+            }
+            if is_not_nil!(order_index) {
+                pegged_orders.set_num_base_atoms(
+                    pegged_index as u8,
+                    dynamic_account
+                        .get_order_by_index(order_index)
+                        .get_num_base_atoms(),
+                )?;
+                *resting_index = order_index;
+            }
+        }
+
+        let best_price = |is_bid: bool| -> Option<QuoteAtomsPerBaseAtom> {
+            if is_bid {
+                dynamic_account.get_bids()
+            } else {
+                dynamic_account.get_asks()
+            }
+            .iter::<RestingOrder>()
+            .find(|(index, order)| !order.is_expired(now_slot) && !resting_indices.contains(index))
+            .map(|(_, order)| order.get_price())
+        };
+        (best_price(true), best_price(false))
+    };
+
+    for pegged_index in 0..MAX_PEGGED_ORDERS {
+        let pegged_order: PeggedOrder = pegged_orders.get_fixed()?.get_orders()[pegged_index];
+        if pegged_order.is_empty() {
+            continue;
+        }
+        let price_opt: Option<QuoteAtomsPerBaseAtom> =
+            pegged_order.get_pegged_price(best_bid, best_ask);
+
+        {
+            let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+            let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+            let trader_index: DataIndex = dynamic_account.get_trader_index(&trader);
+            let pegged_orders_data: &mut RefMut<&mut [u8]> =
+                &mut pegged_orders.try_borrow_mut_data()?;
+            let pegged_orders: &mut PeggedOrders =
+                get_mut_helper::<PeggedOrders>(pegged_orders_data, 0_u32);
+
+            if pegged_order.is_on_book() {
+                let order_index: DataIndex = pegged_order.get_order_index();
+                if price_opt == Some(dynamic_account.get_order_by_index(order_index).get_price()) {
+                    continue;
+                }
+                dynamic_account.cancel_order_by_index(order_index, &[None, None])?;
+                emit_stack(CancelOrderLog {
+                    market: *market.key,
+                    trader,
+                    order_sequence_number: pegged_order.get_order_sequence_number(),
+                })?;
+                pegged_orders.set_off_book(pegged_index as u8)?;
+            }
+            let Some(price) = price_opt else {
+                continue;
+            };
+
+            let num_base_atoms: BaseAtoms = pegged_order.get_num_base_atoms();
+            let is_bid: bool = pegged_order.get_is_bid();
+            let notional: QuoteAtoms = price.checked_quote_for_base(num_base_atoms, is_bid)?;
+            let (base_balance, quote_balance) = dynamic_account.get_trader_balance(&trader);
+            let can_fund: bool = if is_bid {
+                notional <= quote_balance
+            } else {
+                num_base_atoms <= base_balance
+            };
+            let needs_cosigner: bool = dynamic_account
+                .get_seat_config(trader_index)
+                .is_some_and(|seat_config| seat_config.requires_cosigner(notional));
+            if !can_fund
+                || needs_cosigner
+                || dynamic_account
+                    .verify_min_order_size(num_base_atoms, price)
+                    .is_err()
+            {
+                continue;
+            }
+
+            let AddOrderToMarketResult {
+                order_index,
+                order_sequence_number,
+                ..
+            } = dynamic_account.place_order(AddOrderToMarketArgs {
+                market: *market.key,
+                trader_index,
+                num_base_atoms,
+                price,
+                is_bid,
+                last_valid_slot: pegged_order.get_last_valid_slot(),
+                order_type: OrderType::PostOnlySlide,
+                global_trade_accounts_opts: &[None, None],
+                current_slot: Some(now_slot),
+                fill_memo: false,
+                insert_hint_index: NIL,
+                min_fill_atoms: BaseAtoms::ZERO,
+                min_taker_atoms: BaseAtoms::ZERO,
+                tag: 0,
+                skip_global_orders: false,
+                self_trade_prevention: Default::default(),
+                referred: false,
+            })?;
+            if !is_not_nil!(order_index) {
+                continue;
+            }
+            pegged_orders.set_on_book(pegged_index as u8, order_sequence_number, order_index)?;
+
+            emit_stack(PlaceOrderLog {
+                market: *market.key,
+                trader,
+                base_atoms: num_base_atoms,
+                // Slid one step inside the other side when it would cross.
+                price: dynamic_account.get_order_by_index(order_index).get_price(),
+                order_type: OrderType::PostOnlySlide,
+                is_bid: PodBool::from(is_bid),
+                _padding: [0; 6],
+                order_sequence_number,
+                order_index,
+                last_valid_slot: pegged_order.get_last_valid_slot(),
+            })?;
+            emit_stack(PeggedOrderPlacedLog {
+                market: *market.key,
+                trader,
+                order_sequence_number,
+                pegged_index: pegged_index as u8,
+                _padding: [0; 7],
+            })?;
+        }
+
+        // Leave a free block on the market
+        expand_market_if_needed(&payer, &market)?;
+    }
+
+    Ok(())
+}
+
+// Formal verification does not model pegged orders.
+#[cfg(feature = "certora")]
+pub(crate) fn process_reprice_pegged_orders(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::shared::{
+        get_mut_dynamic_account, split_instructions_sysvar, verify_cpi_caller, verify_not_paused,
+    },
+    crate::{
+        program::ManifestError,
+        quantities::{BaseAtoms, QuoteAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
+        require,
+        state::{
+            constants::NO_EXPIRATION_LAST_VALID_SLOT,
+            seat_config::SeatConfig,
+            utils::{assert_market_open, assert_not_in_recovery, get_now_slot},
+            AddOrderToMarketArgs, MarketRefMut, OrderType, RestingOrder,
+        },
+        validation::loaders::RebalanceContext,
+    },
+    hypertree::{is_not_nil, DataIndex, HyperTreeValueIteratorTrait, NIL},
+    std::cell::RefMut,
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct RebalanceParams {
+    /// Withdrawable base the seat should end with. Base above it is sold, and
+    /// base below it is bought with as much of the withdrawable quote as it
+    /// takes.
+    pub target_base_atoms: u64,
+    /// How far through the best opposing price the trade may go, in basis
+    /// points.
+    pub max_slippage_bps: u16,
+}
+
+impl RebalanceParams {
+    pub fn new(target_base_atoms: u64, max_slippage_bps: u16) -> Self {
+        RebalanceParams {
+            target_base_atoms,
+            max_slippage_bps,
+        }
+    }
+}
+
+/// Trades the withdrawable balance of a seat towards target_base_atoms with
+/// an ImmediateOrCancel order priced max_slippage_bps through the best
+/// opposing order, so nothing leaves the market. Signed by the trader. Trades
+/// above the cosign threshold of their SeatConfig also need the cosigner, like
+/// batch updates do.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_rebalance(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let RebalanceParams {
+        target_base_atoms,
+        max_slippage_bps,
+    } = RebalanceParams::try_from_slice(data)?;
+    require!(
+        max_slippage_bps < 10_000,
+        ManifestError::InvalidRebalance,
+        "Max slippage {} bps is not below 10000",
+        max_slippage_bps,
+    )?;
+    let (accounts, instructions_sysvar_opt) = split_instructions_sysvar(accounts);
+    let rebalance_context: RebalanceContext = RebalanceContext::load(accounts)?;
+    let RebalanceContext {
+        payer,
+        market,
+        cosigner_opt,
+    } = rebalance_context;
+    let trader: Pubkey = *payer.key;
+
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+    assert_market_open(&market.get_fixed()?.get_trading_hours())?;
+    verify_not_paused(&market)?;
+    verify_cpi_caller(&market, instructions_sysvar_opt)?;
+    // Markets with a taker delay only take through committed swaps.
+    require!(
+        market.get_fixed()?.get_taker_delay_slots() == 0,
+        ManifestError::InvalidSwapCommitment,
+        "Market has a taker delay, rebalance with a committed swap",
+    )?;
+
+    let now_slot: u32 = get_now_slot();
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+    let trader_index: DataIndex = dynamic_account.get_trader_index(&trader);
+    require!(
+        is_not_nil!(trader_index),
+        ManifestError::InvalidRebalance,
+        "No seat for {}",
+        trader,
+    )?;
+    let seat_config_opt: Option<SeatConfig> =
+        dynamic_account.get_seat_config(trader_index).copied();
+
+    let (base_atoms, quote_atoms): (BaseAtoms, QuoteAtoms) =
+        dynamic_account.get_trader_balance(&trader);
+    let target_base_atoms: BaseAtoms = BaseAtoms::new(target_base_atoms);
+    if base_atoms == target_base_atoms {
+        return Ok(());
+    }
+    let is_bid: bool = base_atoms < target_base_atoms;
+
+    // Global orders cannot be matched without their accounts, so the best
+    // price is the best one the order can take.
+    let best_price_opt: Option<QuoteAtomsPerBaseAtom> = if is_bid {
+        dynamic_account.get_asks()
+    } else {
+        dynamic_account.get_bids()
+    }
+    .iter::<RestingOrder>()
+    .find(|(_, order)| !order.is_expired(now_slot) && !order.is_global())
+    .map(|(_, order)| order.get_price());
+    let Some(best_price) = best_price_opt else {
+        return Ok(());
+    };
+    let slippage_bps: u32 = max_slippage_bps as u32;
+    let (price, num_base_atoms): (QuoteAtomsPerBaseAtom, BaseAtoms) = if is_bid {
+        let price: QuoteAtomsPerBaseAtom =
+            best_price.checked_multiply_rational(10_000 + slippage_bps, 10_000, false)?;
+        let affordable_base_atoms: BaseAtoms = price.checked_base_for_quote(quote_atoms, false)?;
+        (
+            price,
+            BaseAtoms::new(target_base_atoms.as_u64() - base_atoms.as_u64())
+                .min(affordable_base_atoms),
+        )
+    } else {
+        (
+            best_price.checked_multiply_rational(10_000 - slippage_bps, 10_000, true)?,
+            BaseAtoms::new(base_atoms.as_u64() - target_base_atoms.as_u64()),
+        )
+    };
+    if num_base_atoms == BaseAtoms::ZERO {
+        return Ok(());
+    }
+
+    if let Some(seat_config) = seat_config_opt {
+        let signed_by_cosigner: bool = cosigner_opt
+            .as_ref()
+            .is_some_and(|cosigner| *cosigner.key == seat_config.cosigner);
+        require!(
+            signed_by_cosigner
+                || !seat_config
+                    .requires_cosigner(price.checked_quote_for_base(num_base_atoms, false)?),
+            ManifestError::MissingCosigner,
+            "Rebalance needs cosigner {}",
+            seat_config.cosigner,
+        )?;
+    }
+
+    dynamic_account.place_order(AddOrderToMarketArgs {
+        market: *market.key,
+        trader_index,
+        num_base_atoms,
+        price,
+        is_bid,
+        last_valid_slot: NO_EXPIRATION_LAST_VALID_SLOT,
+        order_type: OrderType::ImmediateOrCancel,
+        global_trade_accounts_opts: &[None, None],
+        current_slot: Some(now_slot),
+        fill_memo: false,
+        insert_hint_index: NIL,
+        min_fill_atoms: BaseAtoms::ZERO,
+        min_taker_atoms: BaseAtoms::ZERO,
+        tag: 0,
+        skip_global_orders: true,
+        self_trade_prevention: Default::default(),
+        referred: false,
+    })?;
+    Ok(())
+}
+
+// Formal verification does not model rebalancing.
+#[cfg(feature = "certora")]
+pub(crate) fn process_rebalance(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
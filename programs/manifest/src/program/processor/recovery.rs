@@ -0,0 +1,131 @@
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::{
+        get_mut_dynamic_account,
+        withdraw::{
+            spl_token_2022_transfer_from_vault_to_trader_fixed,
+            spl_token_transfer_from_vault_to_trader,
+        },
+    },
+    crate::{
+        logs::{emit_stack, WithdrawLog},
+        state::{MarketFixed, MarketRefMut},
+        validation::loaders::{EnterRecoveryContext, WithdrawContext},
+    },
+    hypertree::get_mut_helper,
+    std::cell::RefMut,
+};
+
+/// Put a market in recovery. Only RecoveryWithdraw works on it from then on,
+/// so a bug that damaged its state cannot be exploited further while traders
+/// take their share of the vaults out.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_enter_recovery(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let enter_recovery_context: EnterRecoveryContext = EnterRecoveryContext::load(accounts)?;
+    let EnterRecoveryContext { market, .. } = enter_recovery_context;
+
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    get_mut_helper::<MarketFixed>(market_data, 0_u32).set_in_recovery();
+    Ok(())
+}
+
+/// Withdraw the share of the vault owed to the seat of the payer on a market
+/// in recovery. Does not read the trees, see Market::recovery_withdraw.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_recovery_withdraw(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let withdraw_context: WithdrawContext = WithdrawContext::load(accounts)?;
+    let WithdrawContext {
+        market,
+        payer,
+        trader_token,
+        vault,
+        token_program,
+        mint,
+    } = withdraw_context;
+
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+
+    // Validation verifies that the mint is either base or quote.
+    let is_base: bool =
+        &trader_token.try_borrow_data()?[0..32] == dynamic_account.get_base_mint().as_ref();
+    let amount_atoms: u64 =
+        dynamic_account.recovery_withdraw(payer.key, is_base, vault.get_balance_atoms())?;
+
+    let mint_key: Pubkey = if is_base {
+        *dynamic_account.get_base_mint()
+    } else {
+        *dynamic_account.get_quote_mint()
+    };
+    let bump: u8 = if is_base {
+        dynamic_account.fixed.get_base_vault_bump()
+    } else {
+        dynamic_account.fixed.get_quote_vault_bump()
+    };
+
+    if *vault.owner == spl_token_2022::id() {
+        spl_token_2022_transfer_from_vault_to_trader_fixed(
+            &token_program,
+            Some(mint),
+            &mint_key,
+            &vault,
+            &trader_token,
+            amount_atoms,
+            if is_base {
+                dynamic_account.fixed.get_base_mint_decimals()
+            } else {
+                dynamic_account.fixed.get_quote_mint_decimals()
+            },
+            market.key,
+            bump,
+        )?;
+    } else {
+        spl_token_transfer_from_vault_to_trader(
+            &token_program,
+            &vault,
+            &trader_token,
+            amount_atoms,
+            market.key,
+            bump,
+            &mint_key,
+        )?;
+    }
+
+    emit_stack(WithdrawLog {
+        market: *market.key,
+        trader: *payer.key,
+        mint: mint_key,
+        amount_atoms,
+    })?;
+
+    Ok(())
+}
+
+// Formal verification does not model recovery.
+#[cfg(feature = "certora")]
+pub(crate) fn process_enter_recovery(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
+
+#[cfg(feature = "certora")]
+pub(crate) fn process_recovery_withdraw(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
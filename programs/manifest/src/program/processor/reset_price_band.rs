@@ -0,0 +1,42 @@
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::get_mut_dynamic_account,
+    crate::{
+        state::{
+            utils::{assert_not_in_recovery, get_now_slot},
+            MarketRefMut,
+        },
+        validation::loaders::ResetPriceBandContext,
+    },
+    std::cell::RefMut,
+};
+
+/// Moves the reference price of the market price band to the middle of the
+/// book once the market has not traded for PRICE_BAND_RESET_SLOTS. Lets a
+/// market whose price gapped past the band trade again. Permissionless.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_reset_price_band(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let reset_price_band_context: ResetPriceBandContext = ResetPriceBandContext::load(accounts)?;
+    let ResetPriceBandContext { market, .. } = reset_price_band_context;
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+    dynamic_account.reset_price_band(get_now_slot())
+}
+
+// Formal verification does not model the price band.
+#[cfg(feature = "certora")]
+pub(crate) fn process_reset_price_band(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
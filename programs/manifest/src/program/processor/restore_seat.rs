@@ -0,0 +1,112 @@
+use std::cell::RefMut;
+
+use crate::{
+    logs::{emit_stack, RestoreSeatLog},
+    quantities::{BaseAtoms, QuoteAtoms, WrapperU64},
+    state::{seat_archive_leaf, MarketRefMut, SeatArchive, SeatArchiveNode},
+    validation::loaders::RestoreSeatContext,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{get_mut_helper, trace};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use super::shared::{expand_market_if_needed, get_mut_dynamic_account};
+
+/// An archived seat as it was logged in ArchiveSeatLog, with the proof of its
+/// leaf against the current root of the seat archive.
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct RestoreSeatParams {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub trader: Pubkey,
+    pub base_atoms: u64,
+    pub quote_atoms: u64,
+    pub quote_volume: u64,
+    pub leaf_index: u64,
+    pub proof: Vec<SeatArchiveNode>,
+}
+
+impl RestoreSeatParams {
+    pub fn new(
+        trader: Pubkey,
+        base_atoms: u64,
+        quote_atoms: u64,
+        quote_volume: u64,
+        leaf_index: u64,
+        proof: Vec<SeatArchiveNode>,
+    ) -> Self {
+        RestoreSeatParams {
+            trader,
+            base_atoms,
+            quote_atoms,
+            quote_volume,
+            leaf_index,
+            proof,
+        }
+    }
+}
+
+/// Puts an archived seat back on the market. Anyone can restore a seat since
+/// the balances only ever go back to the trader. When the trader claimed a new
+/// seat in the meantime, the archived balances are added to it. Also allowed
+/// on a market in recovery, where archived seats are only owed a share of the
+/// vault once they are restored.
+pub(crate) fn process_restore_seat(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    trace!("process_restore_seat accs={accounts:?}");
+    let params: RestoreSeatParams = RestoreSeatParams::try_from_slice(data)?;
+    let restore_seat_context: RestoreSeatContext = RestoreSeatContext::load(accounts)?;
+    let RestoreSeatContext {
+        payer,
+        market,
+        seat_archive,
+        ..
+    } = restore_seat_context;
+    let RestoreSeatParams {
+        trader,
+        base_atoms,
+        quote_atoms,
+        quote_volume,
+        leaf_index,
+        proof,
+    } = params;
+    let base_atoms: BaseAtoms = BaseAtoms::new(base_atoms);
+    let quote_atoms: QuoteAtoms = QuoteAtoms::new(quote_atoms);
+    let quote_volume: QuoteAtoms = QuoteAtoms::new(quote_volume);
+
+    {
+        let seat_archive_data: &mut RefMut<&mut [u8]> = &mut seat_archive.try_borrow_mut_data()?;
+        let archive: &mut SeatArchive = get_mut_helper::<SeatArchive>(seat_archive_data, 0_u32);
+        archive.remove(
+            &seat_archive_leaf(&trader, base_atoms, quote_atoms, quote_volume),
+            leaf_index,
+            &proof,
+        )?;
+    }
+
+    {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.restore_seat(&trader, base_atoms, quote_atoms, quote_volume)?;
+    }
+    // Leave a free block on the market, like ClaimSeat.
+    expand_market_if_needed(&payer, &market)?;
+
+    emit_stack(RestoreSeatLog {
+        market: *market.key,
+        trader,
+        leaf_index,
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use std::cell::RefMut;
+
+use crate::{
+    program::{get_trader_index_with_hint, ManifestError},
+    quantities::{QuoteAtoms, WrapperU64},
+    require,
+    state::{seat_mmp::SeatMmp, MarketRefMut},
+    validation::loaders::SetMmpContext,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::DataIndex;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use super::shared::{expand_market_if_needed, get_mut_dynamic_account};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct SetMmpParams {
+    /// Slots the fills are counted over.
+    pub window_slots: u32,
+    /// Fills against the seat's orders in a window that pull them. Zero is no
+    /// limit.
+    pub max_fills: u32,
+    /// Quote atoms filled against the seat's orders in a window that pull
+    /// them. Zero is no limit.
+    pub max_quote_atoms: u64,
+    pub trader_index_hint: Option<DataIndex>,
+}
+
+impl SetMmpParams {
+    pub fn new(
+        window_slots: u32,
+        max_fills: u32,
+        max_quote_atoms: u64,
+        trader_index_hint: Option<DataIndex>,
+    ) -> Self {
+        SetMmpParams {
+            window_slots,
+            max_fills,
+            max_quote_atoms,
+            trader_index_hint,
+        }
+    }
+}
+
+/// Sets the market maker protection of the payer's seat, or clears it when
+/// both limits are zero.
+pub(crate) fn process_set_mmp(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let params: SetMmpParams = SetMmpParams::try_from_slice(data)?;
+    let set_mmp_context: SetMmpContext = SetMmpContext::load(accounts)?;
+    let SetMmpContext { market, payer, .. } = set_mmp_context;
+
+    let SetMmpParams {
+        window_slots,
+        max_fills,
+        max_quote_atoms,
+        trader_index_hint,
+    } = params;
+
+    let seat_mmp_opt: Option<SeatMmp> = if max_fills == 0 && max_quote_atoms == 0 {
+        None
+    } else {
+        require!(
+            window_slots > 0,
+            ManifestError::InvalidMmp,
+            "Market maker protection needs a window",
+        )?;
+        Some(SeatMmp::new(
+            window_slots,
+            max_fills,
+            QuoteAtoms::new(max_quote_atoms),
+        ))
+    };
+
+    let trader_index: DataIndex = {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        let trader_index: DataIndex =
+            get_trader_index_with_hint(trader_index_hint, &dynamic_account, &payer)?;
+        // The SeatVolume links the SeatMmp, so the seat needs one first.
+        if seat_mmp_opt.is_some() {
+            dynamic_account.init_seat_volume(trader_index)?;
+        }
+        trader_index
+    };
+
+    // Leave a free block on the market for the SeatMmp.
+    expand_market_if_needed(&payer, &market)?;
+
+    {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.set_seat_mmp(trader_index, seat_mmp_opt)?;
+    }
+
+    // Leave a free block on the market
+    expand_market_if_needed(&payer, &market)?;
+
+    Ok(())
+}
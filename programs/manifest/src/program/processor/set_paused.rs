@@ -0,0 +1,50 @@
+use std::cell::RefMut;
+
+use crate::{
+    logs::{emit_stack, MarketPausedLog},
+    state::MarketRefMut,
+    validation::loaders::SetPausedContext,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::PodBool;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use super::shared::get_mut_dynamic_account;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct SetPausedParams {
+    pub paused: bool,
+}
+
+impl SetPausedParams {
+    pub fn new(paused: bool) -> Self {
+        SetPausedParams { paused }
+    }
+}
+
+/// Pauses or resumes trading on the market. Only its pauser can.
+pub(crate) fn process_set_paused(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let SetPausedParams { paused } = SetPausedParams::try_from_slice(data)?;
+    let set_paused_context: SetPausedContext = SetPausedContext::load(accounts)?;
+    let SetPausedContext { pauser, market } = set_paused_context;
+
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+    dynamic_account.set_paused(pauser.key, paused)?;
+
+    emit_stack(MarketPausedLog {
+        market: *market.key,
+        pauser: *pauser.key,
+        paused: PodBool::from(paused),
+        _padding: [0; 7],
+    })?;
+    Ok(())
+}
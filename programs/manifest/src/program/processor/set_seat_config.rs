@@ -0,0 +1,118 @@
+use std::cell::RefMut;
+
+use crate::{
+    program::{get_trader_index_with_hint, ManifestError},
+    quantities::{QuoteAtoms, WrapperU64},
+    require,
+    state::{seat_config::SeatConfig, MarketRefMut},
+    validation::loaders::SetSeatConfigContext,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{is_not_nil, DataIndex, NIL};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use super::shared::{expand_market_if_needed, get_mut_dynamic_account};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct SetSeatConfigParams {
+    /// Key that has to co-sign large batch updates. The default pubkey removes
+    /// the config from the seat.
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::schema::pubkey"),
+        schemars(with = "String")
+    )]
+    pub cosigner: Pubkey,
+    /// Quote notional placed in one batch_update above which the cosigner has
+    /// to sign.
+    pub cosign_notional_threshold_atoms: u64,
+    pub trader_index_hint: Option<DataIndex>,
+}
+
+impl SetSeatConfigParams {
+    pub fn new(
+        cosigner: Pubkey,
+        cosign_notional_threshold_atoms: u64,
+        trader_index_hint: Option<DataIndex>,
+    ) -> Self {
+        SetSeatConfigParams {
+            cosigner,
+            cosign_notional_threshold_atoms,
+            trader_index_hint,
+        }
+    }
+}
+
+pub(crate) fn process_set_seat_config(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let params: SetSeatConfigParams = SetSeatConfigParams::try_from_slice(data)?;
+    let set_seat_config_context: SetSeatConfigContext = SetSeatConfigContext::load(accounts)?;
+    let SetSeatConfigContext {
+        market,
+        payer,
+        cosigner_opt,
+        ..
+    } = set_seat_config_context;
+
+    let SetSeatConfigParams {
+        cosigner,
+        cosign_notional_threshold_atoms,
+        trader_index_hint,
+    } = params;
+
+    {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        let trader_index: DataIndex =
+            get_trader_index_with_hint(trader_index_hint, &dynamic_account, &payer)?;
+        require!(
+            is_not_nil!(trader_index),
+            ManifestError::InvalidSeatConfig,
+            "No seat initialized",
+        )?;
+
+        // Dual control only means something if the current cosigner also has
+        // to approve loosening or replacing it. Seats that only have a config
+        // for points have no cosigner.
+        if let Some(existing_seat_config) = dynamic_account
+            .get_seat_config(trader_index)
+            .filter(|seat_config| seat_config.cosigner != Pubkey::default())
+        {
+            require!(
+                cosigner_opt
+                    .as_ref()
+                    .is_some_and(|signer| *signer.key == existing_seat_config.cosigner),
+                ManifestError::MissingCosigner,
+                "Existing cosigner {} must sign to change the seat config",
+                existing_seat_config.cosigner,
+            )?;
+        }
+        require!(
+            cosigner != *payer.key,
+            ManifestError::InvalidSeatConfig,
+            "Cosigner cannot be the seat owner",
+        )?;
+
+        let seat_config_opt: Option<SeatConfig> = if cosigner == Pubkey::default() {
+            None
+        } else {
+            Some(SeatConfig::new(
+                cosigner,
+                QuoteAtoms::new(cosign_notional_threshold_atoms),
+            ))
+        };
+        dynamic_account.set_seat_config(trader_index, seat_config_opt)?;
+    }
+
+    // Leave a free block on the market
+    expand_market_if_needed(&payer, &market)?;
+
+    Ok(())
+}
@@ -3,22 +3,34 @@ use std::{
     mem::size_of,
 };
 
+#[cfg(not(feature = "certora"))]
+use crate::state::{cpi_allowlist::CpiAllowlist, MarketRef};
 use crate::{
+    program::ManifestError,
     require,
     state::{
-        claimed_seat::ClaimedSeat, constants::MARKET_BLOCK_SIZE, DynamicAccount, GlobalFixed,
-        MarketFixed, MarketRefMut, GLOBAL_BLOCK_SIZE,
+        claimed_seat::ClaimedSeat,
+        constants::{MARKET_BLOCK_SIZE, MAX_MARKET_BLOCKS_PER_EXPAND, MAX_MARKET_SIZE},
+        DynamicAccount, GlobalFixed, MarketFixed, MarketRefMut, GLOBAL_BLOCK_SIZE,
     },
     validation::{ManifestAccount, ManifestAccountInfo, Signer},
 };
 use bytemuck::Pod;
 use hypertree::{get_helper, get_mut_helper, DataIndex, Get, RBNode};
-#[cfg(not(feature = "certora"))]
-use solana_program::sysvar::Sysvar;
 use solana_program::{
     account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction,
     sysvar::slot_history::ProgramError,
 };
+#[cfg(not(feature = "certora"))]
+use solana_program::{
+    ed25519_program,
+    instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
+    pubkey::Pubkey,
+    sysvar::{
+        instructions::{load_current_index_checked, load_instruction_at_checked},
+        Sysvar,
+    },
+};
 
 use super::batch_update::MarketDataTreeNodeType;
 
@@ -41,20 +53,46 @@ pub(crate) fn expand_market_if_needed<'a, 'info, T: ManifestAccount + Pod + Clon
     expand_market(&Signer::new_payer(payer)?, market_account_info)
 }
 
+/// Number of blocks a market account can still grow by before reaching
+/// MAX_MARKET_SIZE.
+fn get_market_blocks_remaining(market_account: &AccountInfo) -> u32 {
+    (MAX_MARKET_SIZE.saturating_sub(market_account.data_len()) / MARKET_BLOCK_SIZE) as u32
+}
+
 pub(crate) fn expand_market<'a, 'info, T: ManifestAccount + Pod + Clone>(
     payer: &Signer<'a, 'info>,
     manifest_account: &ManifestAccountInfo<'a, 'info, T>,
 ) -> ProgramResult {
+    require!(
+        get_market_blocks_remaining(manifest_account.info) > 0,
+        ManifestError::MarketTooLarge,
+        "Market {} is already {} bytes",
+        manifest_account.key,
+        manifest_account.info.data_len(),
+    )?;
     expand_dynamic(payer, manifest_account, MARKET_BLOCK_SIZE)?;
     expand_market_fixed(manifest_account.info)?;
     Ok(())
 }
 
+/// Adds up to num_blocks. Stops short at the per instruction realloc limit or
+/// MAX_MARKET_SIZE, and only fails if no blocks can be added at all.
 pub(crate) fn batch_expand_market<'a, 'info, T: ManifestAccount + Pod + Clone>(
     payer: &Signer<'a, 'info>,
     manifest_account: &ManifestAccountInfo<'a, 'info, T>,
     num_blocks: u32,
 ) -> ProgramResult {
+    let blocks_remaining: u32 = get_market_blocks_remaining(manifest_account.info);
+    require!(
+        num_blocks == 0 || blocks_remaining > 0,
+        ManifestError::MarketTooLarge,
+        "Market {} is already {} bytes",
+        manifest_account.key,
+        manifest_account.info.data_len(),
+    )?;
+    let num_blocks: u32 = num_blocks
+        .min(blocks_remaining)
+        .min(MAX_MARKET_BLOCKS_PER_EXPAND);
     expand_dynamic(
         payer,
         manifest_account,
@@ -241,6 +279,135 @@ fn verify_trader_index_hint(
     Ok(())
 }
 
+/// Trading instructions on a market with a CPI allowlist take the
+/// instructions sysvar as their last account. It is split off so the loaders
+/// see the same accounts as on any other market.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn split_instructions_sysvar<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+) -> (&'a [AccountInfo<'info>], Option<&'a AccountInfo<'info>>) {
+    match accounts.split_last() {
+        Some((last, rest)) if solana_program::sysvar::instructions::check_id(last.key) => {
+            (rest, Some(last))
+        }
+        _ => (accounts, None),
+    }
+}
+
+#[cfg(feature = "certora")]
+pub(crate) fn split_instructions_sysvar<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+) -> (&'a [AccountInfo<'info>], Option<&'a AccountInfo<'info>>) {
+    (accounts, None)
+}
+
+/// Markets with a CPI allowlist only trade through a direct CPI from a
+/// program on it. The caller is the program of the top level instruction,
+/// which the instructions sysvar tells.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn verify_cpi_caller(
+    market: &ManifestAccountInfo<MarketFixed>,
+    instructions_sysvar_opt: Option<&AccountInfo>,
+) -> ProgramResult {
+    let cpi_allowlist: CpiAllowlist = {
+        let market_data: &Ref<&mut [u8]> = &market.try_borrow_data()?;
+        let dynamic_account: MarketRef = get_dynamic_account(market_data);
+        match dynamic_account.get_cpi_allowlist() {
+            Some(cpi_allowlist) => *cpi_allowlist,
+            None => return Ok(()),
+        }
+    };
+    require!(
+        get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT + 1,
+        ManifestError::CpiCallerNotAllowed,
+        "Market only trades through a CPI from an allowed program",
+    )?;
+    let Some(instructions_sysvar) = instructions_sysvar_opt else {
+        return Err(ManifestError::CpiCallerNotAllowed.into());
+    };
+    let current_index: u16 = load_current_index_checked(instructions_sysvar)?;
+    let caller: Pubkey =
+        load_instruction_at_checked(current_index as usize, instructions_sysvar)?.program_id;
+    require!(
+        cpi_allowlist.is_allowed(&caller),
+        ManifestError::CpiCallerNotAllowed,
+        "Program {} is not on the CPI allowlist",
+        caller,
+    )?;
+    Ok(())
+}
+
+#[cfg(feature = "certora")]
+pub(crate) fn verify_cpi_caller(
+    _market: &ManifestAccountInfo<MarketFixed>,
+    _instructions_sysvar_opt: Option<&AccountInfo>,
+) -> ProgramResult {
+    Ok(())
+}
+
+/// Markets paused by their pauser take no new orders, swaps or fills.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn verify_not_paused(market: &ManifestAccountInfo<MarketFixed>) -> ProgramResult {
+    let market_data: &Ref<&mut [u8]> = &market.try_borrow_data()?;
+    get_dynamic_account(market_data).verify_not_paused()
+}
+
+// Formal verification does not model pausing.
+#[cfg(feature = "certora")]
+pub(crate) fn verify_not_paused(_market: &ManifestAccountInfo<MarketFixed>) -> ProgramResult {
+    Ok(())
+}
+
+/// Whether the instruction instructions_back before the top level one is an
+/// ed25519 program instruction with a single signature by signer of exactly
+/// message, all held in its own data. The ed25519 program fails the
+/// transaction when the signature itself does not verify.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn verifies_ed25519_signature(
+    instructions_sysvar: &AccountInfo,
+    instructions_back: u16,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<bool, ProgramError> {
+    let current_index: u16 = load_current_index_checked(instructions_sysvar)?;
+    if instructions_back == 0 || current_index < instructions_back {
+        return Ok(false);
+    }
+    let ed25519_instruction: Instruction = load_instruction_at_checked(
+        (current_index - instructions_back) as usize,
+        instructions_sysvar,
+    )?;
+    Ok(ed25519_instruction.program_id == ed25519_program::id()
+        && signs_message(&ed25519_instruction.data, signer, message).unwrap_or(false))
+}
+
+/// Data of an ed25519 instruction is the number of signatures, a padding byte
+/// and then the offsets of each as u16s: signature, signature instruction,
+/// public key, public key instruction, message, message size and message
+/// instruction. u16::MAX is the ed25519 instruction itself. None when the data
+/// is too short for the offsets it has.
+#[cfg(not(feature = "certora"))]
+fn signs_message(data: &[u8], signer: &Pubkey, message: &[u8]) -> Option<bool> {
+    let read_u16 = |index: usize| -> Option<usize> {
+        let offset: usize = 2 + 2 * index;
+        Some(u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?) as usize)
+    };
+    if data.first() != Some(&1)
+        || read_u16(1)? != u16::MAX as usize
+        || read_u16(3)? != u16::MAX as usize
+        || read_u16(6)? != u16::MAX as usize
+    {
+        return Some(false);
+    }
+    let public_key_offset: usize = read_u16(2)?;
+    let message_offset: usize = read_u16(4)?;
+    let message_size: usize = read_u16(5)?;
+    Some(
+        data.get(public_key_offset..public_key_offset + 32)? == signer.as_ref()
+            && data.get(message_offset..message_offset + message_size)? == message,
+    )
+}
+
 // TODO: Same for invoke_signed
 
 pub fn invoke(ix: &Instruction, account_infos: &[AccountInfo<'_>]) -> ProgramResult {
@@ -0,0 +1,358 @@
+use std::{cell::RefMut, mem::size_of};
+
+use crate::{
+    program::{get_trader_index_with_hint, ManifestError},
+    quantities::{BaseAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
+    require,
+    state::{
+        order_type_can_rest,
+        utils::{assert_not_in_recovery, get_now_slot},
+        MarketRefMut, StopOrders,
+    },
+    utils::create_account,
+    validation::{
+        get_stop_orders_address,
+        loaders::{CancelStopOrderContext, PlaceStopOrderContext},
+        ManifestAccountInfo,
+    },
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{get_mut_helper, is_not_nil, trace, DataIndex, NIL};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey, rent::Rent, sysvar::Sysvar,
+};
+
+use super::{
+    batch_update::PlaceOrderParams,
+    shared::{
+        get_mut_dynamic_account, split_instructions_sysvar, verify_cpi_caller, verify_not_paused,
+    },
+};
+
+#[cfg(not(feature = "certora"))]
+use {
+    super::shared::expand_market_if_needed,
+    crate::{
+        logs::{emit_stack, PlaceOrderLog, StopOrderTriggeredLog},
+        state::{
+            constants::MAX_STOP_ORDERS, utils::assert_market_open, AddOrderToMarketArgs,
+            AddOrderToMarketResult, MarketFixed, OrderType, StopOrder,
+        },
+        validation::loaders::TriggerStopOrdersContext,
+    },
+    hypertree::PodBool,
+    std::cell::Ref,
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct PlaceStopOrderParams {
+    /// Order placed when triggered, either Limit or ImmediateOrCancel.
+    pub order: PlaceOrderParams,
+    pub trigger_price_mantissa: u32,
+    pub trigger_price_exponent: i8,
+    pub trader_index_hint: Option<DataIndex>,
+}
+
+impl PlaceStopOrderParams {
+    pub fn new(
+        order: PlaceOrderParams,
+        trigger_price_mantissa: u32,
+        trigger_price_exponent: i8,
+        trader_index_hint: Option<DataIndex>,
+    ) -> Self {
+        PlaceStopOrderParams {
+            order,
+            trigger_price_mantissa,
+            trigger_price_exponent,
+            trader_index_hint,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct CancelStopOrderParams {
+    pub stop_index: u8,
+}
+
+impl CancelStopOrderParams {
+    pub fn new(stop_index: u8) -> Self {
+        CancelStopOrderParams { stop_index }
+    }
+}
+
+/// Adds a stop order for the payer. Nothing is locked until it triggers, so
+/// the checks that need the order's notional are done here and again by
+/// place_order when it is triggered. The payer funds their stop orders
+/// account on the first stop order on the market. The params may be followed
+/// by a u16 trailing_bps that makes it a trailing stop.
+pub(crate) fn process_place_stop_order(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    trace!("process_place_stop_order accs={accounts:?}");
+    let mut params_data: &[u8] = data;
+    let params: PlaceStopOrderParams = PlaceStopOrderParams::deserialize(&mut params_data)?;
+    let trailing_bps: u16 = match params_data {
+        [] => 0,
+        [low, high] => u16::from_le_bytes([*low, *high]),
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+    let (accounts, instructions_sysvar_opt) = split_instructions_sysvar(accounts);
+    let place_stop_order_context: PlaceStopOrderContext = PlaceStopOrderContext::load(accounts)?;
+    let PlaceStopOrderContext {
+        payer,
+        market,
+        system_program,
+        stop_orders,
+    } = place_stop_order_context;
+
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+    verify_cpi_caller(&market, instructions_sysvar_opt)?;
+    // Triggered orders can take, which markets with a taker delay only allow
+    // through committed swaps.
+    require!(
+        market.get_fixed()?.get_taker_delay_slots() == 0,
+        ManifestError::InvalidStopOrder,
+        "Market has a taker delay",
+    )?;
+
+    let PlaceStopOrderParams {
+        order,
+        trigger_price_mantissa,
+        trigger_price_exponent,
+        trader_index_hint,
+    } = params;
+    let price: QuoteAtomsPerBaseAtom = order.try_price()?;
+    let trigger_price: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
+            trigger_price_mantissa,
+            trigger_price_exponent,
+        )?;
+    let base_atoms: BaseAtoms = BaseAtoms::new(order.base_atoms());
+
+    {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        let trader_index: DataIndex =
+            get_trader_index_with_hint(trader_index_hint, &dynamic_account, &payer)?;
+        require!(
+            is_not_nil!(trader_index),
+            ManifestError::InvalidStopOrder,
+            "No seat initialized",
+        )?;
+        // The trigger has no cosigner, so stop orders cannot be larger than
+        // what the seat can place without one.
+        if let Some(seat_config) = dynamic_account.get_seat_config(trader_index) {
+            require!(
+                !seat_config.requires_cosigner(price.checked_quote_for_base(base_atoms, false)?),
+                ManifestError::MissingCosigner,
+                "Stop order needs cosigner {}",
+                seat_config.cosigner,
+            )?;
+        }
+        // Checked now since failing at the trigger would hold up the crank.
+        if order_type_can_rest(order.order_type()) {
+            dynamic_account.verify_min_order_size(base_atoms, price)?;
+        }
+    }
+
+    if stop_orders.data_is_empty() {
+        let (_expected_stop_orders_key, stop_orders_bump) =
+            get_stop_orders_address(market.key, payer.key);
+        create_account(
+            payer.as_ref(),
+            stop_orders,
+            system_program.as_ref(),
+            &crate::id(),
+            &Rent::get()?,
+            size_of::<StopOrders>() as u64,
+            vec![
+                b"stop-orders".to_vec(),
+                market.key.as_ref().to_vec(),
+                payer.key.as_ref().to_vec(),
+                vec![stop_orders_bump],
+            ],
+        )?;
+        let stop_orders_bytes: &mut [u8] = &mut stop_orders.try_borrow_mut_data()?[..];
+        *get_mut_helper::<StopOrders>(stop_orders_bytes, 0_u32) =
+            StopOrders::new_empty(market.key, payer.key);
+    }
+    let stop_orders: ManifestAccountInfo<StopOrders> =
+        ManifestAccountInfo::<StopOrders>::new(stop_orders)?;
+
+    let stop_orders_data: &mut RefMut<&mut [u8]> = &mut stop_orders.try_borrow_mut_data()?;
+    get_mut_helper::<StopOrders>(stop_orders_data, 0_u32).add(
+        trigger_price,
+        price,
+        base_atoms,
+        order.is_bid(),
+        order.last_valid_slot(),
+        order.order_type(),
+        trailing_bps,
+        get_now_slot(),
+    )?;
+    Ok(())
+}
+
+pub(crate) fn process_cancel_stop_order(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let params: CancelStopOrderParams = CancelStopOrderParams::try_from_slice(data)?;
+    let cancel_stop_order_context: CancelStopOrderContext = CancelStopOrderContext::load(accounts)?;
+    let CancelStopOrderContext { stop_orders, .. } = cancel_stop_order_context;
+
+    let stop_orders_data: &mut RefMut<&mut [u8]> = &mut stop_orders.try_borrow_mut_data()?;
+    get_mut_helper::<StopOrders>(stop_orders_data, 0_u32).remove(params.stop_index)?;
+    Ok(())
+}
+
+/// Places the triggered stop orders of one trader and clears the expired
+/// ones. The trigger is the market's last trade price, which only trades
+/// against the book move, so a stop order cannot be triggered without a
+/// trade at or through its trigger price. Trailing stops are trailed to the
+/// last trade price first, so they follow the prices this is called at.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_trigger_stop_orders(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let trigger_stop_orders_context: TriggerStopOrdersContext =
+        TriggerStopOrdersContext::load(accounts)?;
+    let TriggerStopOrdersContext {
+        payer,
+        market,
+        stop_orders,
+        ..
+    } = trigger_stop_orders_context;
+
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+    let now_slot: u32 = get_now_slot();
+    let (last_trade_price, last_trade_slot, trader): (QuoteAtomsPerBaseAtom, u32, Pubkey) = {
+        let fixed: Ref<MarketFixed> = market.get_fixed()?;
+        (
+            fixed.get_last_trade_price(),
+            fixed.get_last_trade_slot(),
+            stop_orders.get_fixed()?.trader,
+        )
+    };
+
+    for stop_index in 0..MAX_STOP_ORDERS {
+        let mut stop_order: StopOrder = stop_orders.get_fixed()?.get_orders()[stop_index];
+        if stop_order.is_empty() {
+            continue;
+        }
+        if stop_order.is_expired(now_slot) {
+            let stop_orders_data: &mut RefMut<&mut [u8]> =
+                &mut stop_orders.try_borrow_mut_data()?;
+            get_mut_helper::<StopOrders>(stop_orders_data, 0_u32).remove(stop_index as u8)?;
+            continue;
+        }
+        if stop_order.get_trailing_bps() != 0 {
+            let stop_orders_data: &mut RefMut<&mut [u8]> =
+                &mut stop_orders.try_borrow_mut_data()?;
+            stop_order = get_mut_helper::<StopOrders>(stop_orders_data, 0_u32).trail(
+                stop_index as u8,
+                last_trade_price,
+                last_trade_slot,
+            )?;
+        }
+        if !stop_order.is_triggered(last_trade_price, last_trade_slot) {
+            continue;
+        }
+        assert_market_open(&market.get_fixed()?.get_trading_hours())?;
+        verify_not_paused(&market)?;
+
+        {
+            let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+            let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+            let trader_index: DataIndex = dynamic_account.get_trader_index(&trader);
+            require!(
+                is_not_nil!(trader_index),
+                ManifestError::InvalidStopOrder,
+                "No seat for {}",
+                trader,
+            )?;
+
+            let order_type: OrderType = stop_order.get_order_type();
+            let AddOrderToMarketResult {
+                order_index,
+                order_sequence_number,
+                ..
+            } = dynamic_account.place_order(AddOrderToMarketArgs {
+                market: *market.key,
+                trader_index,
+                num_base_atoms: stop_order.get_num_base_atoms(),
+                price: stop_order.get_price(),
+                is_bid: stop_order.get_is_bid(),
+                last_valid_slot: stop_order.get_last_valid_slot(),
+                order_type,
+                global_trade_accounts_opts: &[None, None],
+                current_slot: Some(now_slot),
+                fill_memo: false,
+                insert_hint_index: NIL,
+                min_fill_atoms: BaseAtoms::ZERO,
+                min_taker_atoms: BaseAtoms::ZERO,
+                tag: 0,
+                // Without the global accounts, global orders cannot be
+                // matched, so pass over them instead of stopping there.
+                skip_global_orders: order_type == OrderType::ImmediateOrCancel,
+                self_trade_prevention: Default::default(),
+                referred: false,
+            })?;
+
+            emit_stack(PlaceOrderLog {
+                market: *market.key,
+                trader,
+                base_atoms: stop_order.get_num_base_atoms(),
+                price: stop_order.get_price(),
+                order_type,
+                is_bid: PodBool::from(stop_order.get_is_bid()),
+                _padding: [0; 6],
+                order_sequence_number,
+                order_index,
+                last_valid_slot: stop_order.get_last_valid_slot(),
+            })?;
+            emit_stack(StopOrderTriggeredLog {
+                market: *market.key,
+                trader,
+                trigger_price: stop_order.get_trigger_price(),
+                order_sequence_number,
+                stop_index: stop_index as u8,
+                _padding: [0; 7],
+            })?;
+        }
+        {
+            let stop_orders_data: &mut RefMut<&mut [u8]> =
+                &mut stop_orders.try_borrow_mut_data()?;
+            get_mut_helper::<StopOrders>(stop_orders_data, 0_u32).remove(stop_index as u8)?;
+        }
+
+        // Leave a free block on the market
+        expand_market_if_needed(&payer, &market)?;
+    }
+
+    Ok(())
+}
+
+// Formal verification does not model the last trade price.
+#[cfg(feature = "certora")]
+pub(crate) fn process_trigger_stop_orders(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
@@ -1,15 +1,18 @@
 use std::cell::RefMut;
 
 use crate::{
-    logs::{emit_stack, PlaceOrderLogV2},
+    logs::{emit_stack, PlaceOrderLogV2, ReferralFeeLog},
     program::expand_market_if_needed,
+    program::ManifestInstruction,
     quantities::{BaseAtoms, QuoteAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
     require,
     state::{
-        AddOrderToMarketArgs, AddOrderToMarketResult, MarketRefMut, OrderType,
-        NO_EXPIRATION_LAST_VALID_SLOT,
+        swap_commitment_hash,
+        utils::{assert_market_open, assert_not_in_recovery, get_now_slot},
+        AddOrderToMarketArgs, AddOrderToMarketResult, MarketFixed, MarketRefMut, OrderType,
+        SelfTradePrevention, SwapCommitment, NO_EXPIRATION_LAST_VALID_SLOT,
     },
-    validation::loaders::SwapContext,
+    validation::{get_swap_commitment_address, loaders::SwapContext, ManifestAccountInfo},
 };
 #[cfg(not(feature = "certora"))]
 use crate::{
@@ -17,10 +20,12 @@ use crate::{
     program::{invoke, ManifestError},
 };
 use borsh::{BorshDeserialize, BorshSerialize};
-use hypertree::{trace, DataIndex, NIL};
+use hypertree::{get_mut_helper, trace, DataIndex, NIL};
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 
-use super::shared::get_mut_dynamic_account;
+use super::shared::{
+    get_mut_dynamic_account, split_instructions_sysvar, verify_cpi_caller, verify_not_paused,
+};
 
 #[cfg(feature = "certora")]
 use {
@@ -43,6 +48,10 @@ use {
 };
 
 #[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct SwapParams {
     pub in_atoms: u64,
     pub out_atoms: u64,
@@ -64,21 +73,200 @@ impl SwapParams {
     }
 }
 
+/// Bits of the optional trailing flags byte after min_fill_atoms.
+///
+/// Pass over global orders when the global accounts are not included, instead
+/// of stopping at the first one. Lets takers that do not need global liquidity
+/// leave the global accounts out and avoid write locking them.
+pub const SWAP_SKIP_GLOBAL_ORDERS: u8 = 1 << 0;
+/// Exact out is the amount the trader receives after transfer fees instead of
+/// the amount taken from the market. The output mint must be included when it
+/// is Token-2022, and the swap fails if less than out_atoms arrives, including
+/// when the book is exhausted. Only valid for exact out.
+pub const SWAP_EXACT_OUT_NET_OF_FEES: u8 = 1 << 1;
+
+/// SwapParams optionally followed by a u64 min_fill_atoms. Fills against a
+/// single maker smaller than it are skipped and that maker stays on the book.
+/// That may be followed by a flags byte.
+fn parse_swap_data(data: &[u8]) -> Result<(SwapParams, BaseAtoms, bool, bool), ProgramError> {
+    let mut params_data: &[u8] = data;
+    let params: SwapParams = SwapParams::deserialize(&mut params_data)?;
+    let (min_fill_atoms, flags): (u64, u8) = match params_data {
+        [] => (0, 0),
+        _ if params_data.len() == 8 => (read_u64_le(params_data)?, 0),
+        [rest @ .., flags] if rest.len() == 8 => (read_u64_le(rest)?, *flags),
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+    // Unknown bits are rejected so they can be given a meaning later.
+    if flags & !(SWAP_SKIP_GLOBAL_ORDERS | SWAP_EXACT_OUT_NET_OF_FEES) != 0 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let exact_out_net_of_fees: bool = flags & SWAP_EXACT_OUT_NET_OF_FEES != 0;
+    if exact_out_net_of_fees && params.is_exact_in {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok((
+        params,
+        BaseAtoms::new(min_fill_atoms),
+        flags & SWAP_SKIP_GLOBAL_ORDERS != 0,
+        exact_out_net_of_fees,
+    ))
+}
+
+fn read_u64_le(data: &[u8]) -> Result<u64, ProgramError> {
+    Ok(u64::from_le_bytes(
+        data.try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?,
+    ))
+}
+
 pub(crate) fn process_swap(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
-    let params = SwapParams::try_from_slice(data)?;
-    process_swap_core(program_id, accounts, params)
+    let (params, min_fill_atoms, skip_global_orders, exact_out_net_of_fees) =
+        parse_swap_data(data)?;
+    process_swap_core(
+        program_id,
+        accounts,
+        params,
+        min_fill_atoms,
+        skip_global_orders,
+        exact_out_net_of_fees,
+    )
+}
+
+/// Same as swap, but the output stays on the owner's seat as withdrawable
+/// balance instead of being sent to their wallet. Falls back to a normal swap
+/// when the owner does not have a seat.
+pub(crate) fn process_swap_to_seat(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let (params, min_fill_atoms, skip_global_orders, exact_out_net_of_fees) =
+        parse_swap_data(data)?;
+    process_swap_internal(
+        program_id,
+        accounts,
+        params,
+        true,
+        min_fill_atoms,
+        skip_global_orders,
+        exact_out_net_of_fees,
+        None,
+    )
+}
+
+/// Second half of a swap on a market with a taker delay. Data is the salt
+/// followed by the instruction data of the swap that was committed to, and
+/// the accounts are the swap commitment followed by the accounts of the swap.
+pub(crate) fn process_reveal_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let (salt, swap_data): (&[u8], &[u8]) = data
+        .split_at_checked(32)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let salt: &[u8; 32] = salt
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    let (tag, swap_params_data): (&u8, &[u8]) = swap_data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let output_to_seat: bool = match ManifestInstruction::try_from(*tag) {
+        Ok(ManifestInstruction::Swap) | Ok(ManifestInstruction::SwapV2) => false,
+        Ok(ManifestInstruction::SwapToSeat) => true,
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+    let (swap_commitment, swap_accounts): (&AccountInfo, &[AccountInfo]) =
+        accounts
+            .split_first()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let (params, min_fill_atoms, skip_global_orders, exact_out_net_of_fees) =
+        parse_swap_data(swap_params_data)?;
+    process_swap_internal(
+        program_id,
+        swap_accounts,
+        params,
+        output_to_seat,
+        min_fill_atoms,
+        skip_global_orders,
+        exact_out_net_of_fees,
+        Some((swap_commitment, swap_commitment_hash(salt, swap_data))),
+    )
+}
+
+/// Markets with a taker delay only swap through RevealSwap, once the swap that
+/// was committed to has waited out the delay.
+fn verify_taker_delay(
+    market: &ManifestAccountInfo<MarketFixed>,
+    owner: &Signer,
+    swap_commitment_opt: Option<(&AccountInfo, [u8; 32])>,
+) -> ProgramResult {
+    let taker_delay_slots: u16 = market.get_fixed()?.get_taker_delay_slots();
+    let Some((swap_commitment, commitment)) = swap_commitment_opt else {
+        require!(
+            taker_delay_slots == 0,
+            crate::program::ManifestError::InvalidSwapCommitment,
+            "Market has a taker delay, swaps have to be committed first",
+        )?;
+        return Ok(());
+    };
+    let swap_commitment: ManifestAccountInfo<SwapCommitment> =
+        ManifestAccountInfo::<SwapCommitment>::new(swap_commitment)?;
+    let (expected_swap_commitment_key, _swap_commitment_bump) =
+        get_swap_commitment_address(market.key, owner.key);
+    require!(
+        expected_swap_commitment_key == *swap_commitment.key,
+        crate::program::ManifestError::IncorrectAccount,
+        "Incorrect swap commitment account",
+    )?;
+    let swap_commitment_data: &mut RefMut<&mut [u8]> =
+        &mut swap_commitment.try_borrow_mut_data()?;
+    get_mut_helper::<SwapCommitment>(swap_commitment_data, 0_u32).reveal(
+        &commitment,
+        get_now_slot(),
+        u32::from(taker_delay_slots),
+    )
 }
 
 #[cfg_attr(all(feature = "certora", not(feature = "certora-test")), early_panic)]
 pub(crate) fn process_swap_core(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: SwapParams,
+    min_fill_atoms: BaseAtoms,
+    skip_global_orders: bool,
+    exact_out_net_of_fees: bool,
+) -> ProgramResult {
+    process_swap_internal(
+        program_id,
+        accounts,
+        params,
+        false,
+        min_fill_atoms,
+        skip_global_orders,
+        exact_out_net_of_fees,
+        None,
+    )
+}
+
+#[cfg_attr(all(feature = "certora", not(feature = "certora-test")), early_panic)]
+#[allow(clippy::too_many_arguments)]
+fn process_swap_internal(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
     params: SwapParams,
+    output_to_seat: bool,
+    min_fill_atoms: BaseAtoms,
+    skip_global_orders: bool,
+    exact_out_net_of_fees: bool,
+    swap_commitment_opt: Option<(&AccountInfo, [u8; 32])>,
 ) -> ProgramResult {
+    let (accounts, instructions_sysvar_opt) = split_instructions_sysvar(accounts);
     let swap_context: SwapContext = SwapContext::load(accounts)?;
 
     let SwapContext {
@@ -94,8 +282,16 @@ pub(crate) fn process_swap_core(
         base_mint,
         quote_mint,
         global_trade_accounts_opts,
+        fill_memo_program_opt,
+        referrer_token_info_opt,
     } = swap_context;
 
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+    assert_market_open(&market.get_fixed()?.get_trading_hours())?;
+    verify_not_paused(&market)?;
+    verify_cpi_caller(&market, instructions_sysvar_opt)?;
+    verify_taker_delay(&market, &owner, swap_commitment_opt)?;
+
     let (existing_seat_index, trader_index, initial_base_atoms, initial_quote_atoms) = {
         let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
         let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
@@ -118,6 +314,10 @@ pub(crate) fn process_swap_core(
         )
     };
 
+    // Only an existing seat can keep the output. A temporary seat is released
+    // at the end of the swap.
+    let output_to_seat: bool = output_to_seat && existing_seat_index != NIL;
+
     // Might need a free list spot for both the temporary claimed seat as well
     // as for a partially filled reverse order. The temporary claimed seat has
     // already been taken, so this is just checking if there is an additional
@@ -145,9 +345,26 @@ pub(crate) fn process_swap_core(
         &quote_mint,
     )?;
 
+    if exact_out_net_of_fees {
+        // Without the mint the fee is unknown and would be assumed to be zero.
+        let (token_program_out, mint_out) = if is_base_in {
+            (&token_program_quote, &quote_mint)
+        } else {
+            (&token_program_base, &base_mint)
+        };
+        require!(
+            *token_program_out.key != spl_token_2022::id() || mint_out.is_some(),
+            ManifestError::IncorrectAccount,
+            "Output mint is required for exact out net of transfer fees",
+        )?;
+    }
+
     // For exact_out (!is_exact_in), calculate how much the market needs to give
     // so that after transfer fees, the trader receives their desired out_atoms.
-    let out_atoms_after_transfer_fees: u64 = if !is_exact_in {
+    // Output kept on the seat is not transferred so there is no fee to cover.
+    let out_atoms_after_transfer_fees: u64 = if exact_out_net_of_fees && output_to_seat {
+        out_atoms
+    } else if !is_exact_in {
         calculate_pre_fee_amount(
             out_atoms,
             is_base_in, // Output is opposite of input: if base_in, output is quote; if quote_in, output is base
@@ -160,6 +377,16 @@ pub(crate) fn process_swap_core(
         out_atoms
     };
 
+    // The taker fee comes out of the output, so exact out matches enough for
+    // the trader to still get out_atoms after it.
+    let out_atoms_to_match: u64 =
+        match dynamic_account.get_taker_fill_fees(trader_index, get_now_slot()) {
+            Some(market_fees) if !is_exact_in => {
+                market_fees.get_taker_gross_atoms(out_atoms_after_transfer_fees)
+            }
+            _ => out_atoms_after_transfer_fees,
+        };
+
     trace!("swap in_atoms:{in_atoms} in_atoms_after_transfer_fees:{in_atoms_after_transfer_fees} out_atoms:{out_atoms} out_atoms_after_transfer_fees:{out_atoms_after_transfer_fees} is_base_in:{is_base_in} is_exact_in:{is_exact_in}");
 
     // This check is redundant with the check that will be done within token
@@ -219,13 +446,13 @@ pub(crate) fn process_swap_core(
             // Use out_atoms_after_transfer_fees to account for transfer fees on output
             dynamic_account.impact_base_atoms(
                 false,
-                QuoteAtoms::new(out_atoms_after_transfer_fees),
+                QuoteAtoms::new(out_atoms_to_match),
                 &global_trade_accounts_opts,
             )?
         } else {
             // input=max(quote) output=desired(base)
             // Use out_atoms_after_transfer_fees to account for transfer fees on output
-            BaseAtoms::new(out_atoms_after_transfer_fees)
+            BaseAtoms::new(out_atoms_to_match)
         }
     };
 
@@ -258,7 +485,7 @@ pub(crate) fn process_swap_core(
         quote_atoms_traded,
         order_sequence_number,
         order_index,
-        ..
+        referral_fee_atoms,
     } = place_order(
         &mut dynamic_account,
         AddOrderToMarketArgs {
@@ -271,18 +498,32 @@ pub(crate) fn process_swap_core(
             order_type,
             global_trade_accounts_opts: &global_trade_accounts_opts,
             current_slot: None,
+            fill_memo: fill_memo_program_opt.is_some(),
+            insert_hint_index: NIL,
+            min_fill_atoms,
+            min_taker_atoms: BaseAtoms::ZERO,
+            tag: 0,
+            skip_global_orders,
+            self_trade_prevention: SelfTradePrevention::None,
+            referred: referrer_token_info_opt.is_some(),
         },
     )?;
 
+    let (end_base_atoms, end_quote_atoms) = dynamic_account.get_trader_balance(owner.key);
+
+    let extra_base_atoms: BaseAtoms = end_base_atoms.checked_sub(initial_base_atoms)?;
+    let extra_quote_atoms: QuoteAtoms = end_quote_atoms.checked_sub(initial_quote_atoms)?;
+
     if is_exact_in {
         let out_atoms_traded: u64 = if is_base_in {
-            quote_atoms_traded.as_u64()
+            extra_quote_atoms.as_u64()
         } else {
-            base_atoms_traded.as_u64()
+            extra_base_atoms.as_u64()
         };
         // Note that we define the spec as the out amount verified against is
-        // the amount taken from the market, not the amount actually received.
-        // These are the same except when there are transfer fees.
+        // the amount taken from the market after the taker fee, not the amount
+        // actually received. These are the same except when there are
+        // transfer fees.
         require!(
             out_atoms <= out_atoms_traded,
             ManifestError::InsufficientOut,
@@ -305,10 +546,32 @@ pub(crate) fn process_swap_core(
         )?;
     }
 
-    let (end_base_atoms, end_quote_atoms) = dynamic_account.get_trader_balance(owner.key);
-
-    let extra_base_atoms: BaseAtoms = end_base_atoms.checked_sub(initial_base_atoms)?;
-    let extra_quote_atoms: QuoteAtoms = end_quote_atoms.checked_sub(initial_quote_atoms)?;
+    if exact_out_net_of_fees {
+        let out_atoms_traded: u64 = if is_base_in {
+            extra_quote_atoms.as_u64()
+        } else {
+            extra_base_atoms.as_u64()
+        };
+        let out_atoms_received: u64 = if output_to_seat {
+            out_atoms_traded
+        } else {
+            calculate_post_fee_amount(
+                out_atoms_traded,
+                !is_base_in,
+                &token_program_base,
+                &token_program_quote,
+                &base_mint,
+                &quote_mint,
+            )?
+        };
+        require!(
+            out_atoms <= out_atoms_received,
+            ManifestError::InsufficientOut,
+            "Insufficient out atoms received after transfer fees. Desired: {} Actual: {}",
+            out_atoms,
+            out_atoms_received
+        )?;
+    }
 
     // Transfer tokens
     if is_base_in {
@@ -330,7 +593,7 @@ pub(crate) fn process_swap_core(
             spl_token_2022_transfer_from_trader_to_vault(
                 &token_program_base,
                 &trader_base_account,
-                base_mint,
+                base_mint.clone(),
                 dynamic_account.fixed.get_base_mint(),
                 &base_vault,
                 &owner,
@@ -349,10 +612,12 @@ pub(crate) fn process_swap_core(
 
         // Give all but what started there.
         let quote_vault_bump: u8 = dynamic_account.fixed.get_quote_vault_bump();
-        if *token_program_quote.key == spl_token_2022::id() {
+        if output_to_seat {
+            // Output stays on the seat.
+        } else if *token_program_quote.key == spl_token_2022::id() {
             spl_token_2022_transfer_from_vault_to_trader(
                 &token_program_quote,
-                quote_mint,
+                quote_mint.clone(),
                 dynamic_account.fixed.get_quote_mint(),
                 &quote_vault,
                 &trader_quote_account,
@@ -383,7 +648,7 @@ pub(crate) fn process_swap_core(
             spl_token_2022_transfer_from_trader_to_vault(
                 &token_program_quote,
                 &trader_quote_account,
-                quote_mint,
+                quote_mint.clone(),
                 dynamic_account.fixed.get_quote_mint(),
                 &quote_vault,
                 &owner,
@@ -402,10 +667,12 @@ pub(crate) fn process_swap_core(
 
         // Give all but what started there.
         let base_vault_bump: u8 = dynamic_account.fixed.get_base_vault_bump();
-        if *token_program_base.key == spl_token_2022::id() {
+        if output_to_seat {
+            // Output stays on the seat.
+        } else if *token_program_base.key == spl_token_2022::id() {
             spl_token_2022_transfer_from_vault_to_trader(
                 &token_program_base,
-                base_mint,
+                base_mint.clone(),
                 dynamic_account.get_base_mint(),
                 &base_vault,
                 &trader_base_account,
@@ -427,14 +694,79 @@ pub(crate) fn process_swap_core(
         }
     }
 
+    // The taker fee is charged in the output mint, so the referrer is paid
+    // from the output vault.
+    if let Some(referrer_token_info) = referrer_token_info_opt {
+        let (out_mint_key, out_vault, token_program_out, out_mint, out_decimals, out_vault_bump) =
+            if is_base_in {
+                (
+                    *dynamic_account.fixed.get_quote_mint(),
+                    &quote_vault,
+                    &token_program_quote,
+                    quote_mint,
+                    dynamic_account.fixed.get_quote_mint_decimals(),
+                    dynamic_account.fixed.get_quote_vault_bump(),
+                )
+            } else {
+                (
+                    *dynamic_account.fixed.get_base_mint(),
+                    &base_vault,
+                    &token_program_base,
+                    base_mint,
+                    dynamic_account.fixed.get_base_mint_decimals(),
+                    dynamic_account.fixed.get_base_vault_bump(),
+                )
+            };
+        let referrer_token: TokenAccountInfo =
+            TokenAccountInfo::new(referrer_token_info, &out_mint_key)?;
+        if referral_fee_atoms > 0 {
+            dynamic_account.pay_referral_fee(!is_base_in, referral_fee_atoms)?;
+            if *token_program_out.key == spl_token_2022::id() {
+                spl_token_2022_transfer_from_vault_to_trader(
+                    token_program_out,
+                    out_mint,
+                    &out_mint_key,
+                    out_vault,
+                    &referrer_token,
+                    referral_fee_atoms,
+                    out_decimals,
+                    market.key,
+                    out_vault_bump,
+                )?;
+            } else {
+                spl_token_transfer_from_vault_to_trader(
+                    token_program_out,
+                    out_vault,
+                    &referrer_token,
+                    referral_fee_atoms,
+                    market.key,
+                    out_vault_bump,
+                    &out_mint_key,
+                )?;
+            }
+            emit_stack(ReferralFeeLog {
+                market: *market.key,
+                referrer: referrer_token.get_owner(),
+                mint: out_mint_key,
+                amount_atoms: referral_fee_atoms,
+            })?;
+        }
+    }
+
     if existing_seat_index == NIL {
         dynamic_account.release_seat(owner.key)?;
     } else {
         // Withdraw in case there already was a seat so it doesnt mess with their
         // balances. Need to withdraw base and quote in case the order wasnt fully
-        // filled.
-        dynamic_account.withdraw(trader_index, extra_base_atoms.as_u64(), true)?;
-        dynamic_account.withdraw(trader_index, extra_quote_atoms.as_u64(), false)?;
+        // filled. Output that was not transferred out stays on the seat.
+        let base_out_to_seat: bool = output_to_seat && !is_base_in;
+        let quote_out_to_seat: bool = output_to_seat && is_base_in;
+        if !base_out_to_seat {
+            dynamic_account.withdraw(trader_index, extra_base_atoms.as_u64(), true)?;
+        }
+        if !quote_out_to_seat {
+            dynamic_account.withdraw(trader_index, extra_quote_atoms.as_u64(), false)?;
+        }
     }
     // Verify that there wasnt a reverse order that took the only spare block.
     require!(
@@ -541,7 +873,7 @@ fn spl_token_2022_transfer_from_trader_to_vault<'a, 'info>(
             token_program.as_ref().clone(),
             trader_account.as_ref().clone(),
             vault.as_ref().clone(),
-            mint.unwrap().as_ref().clone(),
+            mint.ok_or(ManifestError::InvalidMint)?.as_ref().clone(),
             owner.as_ref().clone(),
         ],
     )
@@ -632,7 +964,7 @@ fn spl_token_2022_transfer_from_vault_to_trader<'a, 'info>(
         &[
             token_program.as_ref().clone(),
             vault.as_ref().clone(),
-            mint.unwrap().as_ref().clone(),
+            mint.ok_or(ManifestError::InvalidMint)?.as_ref().clone(),
             trader_account.as_ref().clone(),
         ],
         market_vault_seeds_with_bump!(market_key, mint_pubkey, vault_bump),
@@ -0,0 +1,95 @@
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+#[cfg(not(feature = "certora"))]
+use {
+    crate::{
+        program::ManifestError,
+        quantities::QuoteAtomsPerBaseAtom,
+        require,
+        state::{utils::get_now_slot, MarketFixed, MarketVolatility},
+        utils::create_account,
+        validation::{
+            get_market_volatility_address, loaders::UpdateVolatilityContext, ManifestAccountInfo,
+        },
+    },
+    hypertree::get_mut_helper,
+    solana_program::{rent::Rent, sysvar::Sysvar},
+    std::{
+        cell::{Ref, RefMut},
+        mem::size_of,
+    },
+};
+
+/// Samples the last trade price into the volatility estimate of a market and
+/// copies the estimate to the market for reverse orders with
+/// DYNAMIC_REVERSE_SPREAD. Permissionless, at most once per
+/// VOLATILITY_SAMPLE_SLOTS. The first update creates the market volatility
+/// account.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_update_volatility(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let update_volatility_context: UpdateVolatilityContext =
+        UpdateVolatilityContext::load(accounts)?;
+    let UpdateVolatilityContext {
+        payer,
+        market,
+        system_program,
+        market_volatility,
+    } = update_volatility_context;
+
+    let last_trade_price: QuoteAtomsPerBaseAtom = {
+        let fixed: Ref<MarketFixed> = market.get_fixed()?;
+        require!(
+            fixed.get_last_trade_price() != QuoteAtomsPerBaseAtom::ZERO,
+            ManifestError::InvalidVolatilityUpdate,
+            "No trades to sample",
+        )?;
+        fixed.get_last_trade_price()
+    };
+
+    if market_volatility.data_is_empty() {
+        let (_expected_market_volatility_key, market_volatility_bump) =
+            get_market_volatility_address(market.key);
+        create_account(
+            payer.as_ref(),
+            market_volatility,
+            system_program.as_ref(),
+            &crate::id(),
+            &Rent::get()?,
+            size_of::<MarketVolatility>() as u64,
+            vec![
+                b"volatility".to_vec(),
+                market.key.as_ref().to_vec(),
+                vec![market_volatility_bump],
+            ],
+        )?;
+        let market_volatility_bytes: &mut [u8] = &mut market_volatility.try_borrow_mut_data()?[..];
+        *get_mut_helper::<MarketVolatility>(market_volatility_bytes, 0_u32) =
+            MarketVolatility::new_empty(market.key);
+    }
+    let market_volatility: ManifestAccountInfo<MarketVolatility> =
+        ManifestAccountInfo::<MarketVolatility>::new(market_volatility)?;
+
+    let volatility_bps: u16 = {
+        let market_volatility_data: &mut RefMut<&mut [u8]> =
+            &mut market_volatility.try_borrow_mut_data()?;
+        get_mut_helper::<MarketVolatility>(market_volatility_data, 0_u32)
+            .sample(last_trade_price, get_now_slot())?
+    };
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    get_mut_helper::<MarketFixed>(market_data, 0_u32).set_volatility_bps(volatility_bps);
+    Ok(())
+}
+
+// Formal verification does not model the volatility estimate.
+#[cfg(feature = "certora")]
+pub(crate) fn process_update_volatility(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
@@ -3,8 +3,8 @@ use std::cell::RefMut;
 use super::get_trader_index_with_hint;
 use crate::{
     logs::{emit_stack, WithdrawLog},
-    program::get_mut_dynamic_account,
-    state::MarketRefMut,
+    program::{get_mut_dynamic_account, ManifestError},
+    state::{utils::assert_not_in_recovery, MarketRefMut},
     validation::{loaders::WithdrawContext, MintAccountInfo, TokenAccountInfo, TokenProgram},
 };
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -12,7 +12,21 @@ use hypertree::DataIndex;
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 
 #[cfg(not(feature = "certora"))]
-use {crate::market_vault_seeds_with_bump, solana_program::program::invoke_signed};
+use {
+    super::shared::{split_instructions_sysvar, verify_cpi_caller, verify_not_paused},
+    crate::{
+        market_vault_seeds_with_bump,
+        quantities::{BaseAtoms, QuoteAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
+        require,
+        state::{
+            constants::NO_EXPIRATION_LAST_VALID_SLOT,
+            utils::{assert_market_open, get_now_slot},
+            AddOrderToMarketArgs, OrderType, RestingOrder,
+        },
+    },
+    hypertree::{HyperTreeValueIteratorTrait, NIL},
+    solana_program::{program::invoke_signed, program_error::ProgramError},
+};
 
 #[cfg(feature = "certora")]
 use {
@@ -21,6 +35,10 @@ use {
 };
 
 #[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 pub struct WithdrawParams {
     pub amount_atoms: u64,
     pub trader_index_hint: Option<DataIndex>,
@@ -35,6 +53,26 @@ impl WithdrawParams {
     }
 }
 
+#[derive(BorshDeserialize, BorshSerialize)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+pub struct WithdrawAsQuoteParams {
+    /// How far below the best bid the base may sell, in basis points.
+    pub max_price_impact_bps: u16,
+    pub trader_index_hint: Option<DataIndex>,
+}
+
+impl WithdrawAsQuoteParams {
+    pub fn new(max_price_impact_bps: u16, trader_index_hint: Option<DataIndex>) -> Self {
+        WithdrawAsQuoteParams {
+            max_price_impact_bps,
+            trader_index_hint,
+        }
+    }
+}
+
 pub(crate) fn process_withdraw(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -67,6 +105,7 @@ pub(crate) fn process_withdraw_core(
 
     let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
     let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+    assert_not_in_recovery(dynamic_account.fixed)?;
 
     // Validation verifies that the mint is either base or quote.
     let is_base: bool =
@@ -130,9 +169,129 @@ pub(crate) fn process_withdraw_core(
     Ok(())
 }
 
+/// Sell the withdrawable base of the payer with an ImmediateOrCancel ask
+/// max_price_impact_bps below the best bid, then withdraw the whole quote
+/// balance. Base the bids do not take stays on the seat.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn process_withdraw_as_quote(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let WithdrawAsQuoteParams {
+        max_price_impact_bps,
+        trader_index_hint,
+    } = WithdrawAsQuoteParams::try_from_slice(data)?;
+    require!(
+        max_price_impact_bps < 10_000,
+        ProgramError::InvalidInstructionData,
+        "Max price impact {} bps is not below 10000",
+        max_price_impact_bps,
+    )?;
+    let (accounts, instructions_sysvar_opt) = split_instructions_sysvar(accounts);
+    let WithdrawContext {
+        market,
+        payer,
+        trader_token,
+        ..
+    } = WithdrawContext::load(accounts)?;
+    assert_not_in_recovery(&*market.get_fixed()?)?;
+    let now_slot: u32 = get_now_slot();
+
+    let (trader_index, sell_opt): (DataIndex, Option<(BaseAtoms, QuoteAtomsPerBaseAtom)>) = {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        require!(
+            &trader_token.try_borrow_data()?[0..32] == dynamic_account.get_quote_mint().as_ref(),
+            ManifestError::InvalidWithdrawAccounts,
+            "Withdraw as quote needs a quote token account",
+        )?;
+        let trader_index: DataIndex =
+            get_trader_index_with_hint(trader_index_hint, &dynamic_account, &payer)?;
+        let (base_atoms, _) = dynamic_account.get_trader_balance(payer.key);
+        // Global orders cannot be matched without their accounts, so the
+        // best bid is the best one the ask can take.
+        let best_bid_price_opt: Option<QuoteAtomsPerBaseAtom> = dynamic_account
+            .get_bids()
+            .iter::<RestingOrder>()
+            .find(|(_, order)| !order.is_expired(now_slot) && !order.is_global())
+            .map(|(_, order)| order.get_price());
+        let sell_opt = match best_bid_price_opt {
+            Some(best_bid_price) if base_atoms > BaseAtoms::ZERO => Some((
+                base_atoms,
+                best_bid_price.checked_multiply_rational(
+                    10_000 - max_price_impact_bps as u32,
+                    10_000,
+                    true,
+                )?,
+            )),
+            _ => None,
+        };
+        (trader_index, sell_opt)
+    };
+
+    if let Some((base_atoms, price)) = sell_opt {
+        assert_market_open(&market.get_fixed()?.get_trading_hours())?;
+        verify_not_paused(&market)?;
+        verify_cpi_caller(&market, instructions_sysvar_opt)?;
+        // Markets with a taker delay only take through committed swaps.
+        require!(
+            market.get_fixed()?.get_taker_delay_slots() == 0,
+            ManifestError::InvalidSwapCommitment,
+            "Market has a taker delay, sell the base with a committed swap",
+        )?;
+
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.place_order(AddOrderToMarketArgs {
+            market: *market.key,
+            trader_index,
+            num_base_atoms: base_atoms,
+            price,
+            is_bid: false,
+            last_valid_slot: NO_EXPIRATION_LAST_VALID_SLOT,
+            order_type: OrderType::ImmediateOrCancel,
+            global_trade_accounts_opts: &[None, None],
+            current_slot: Some(now_slot),
+            fill_memo: false,
+            insert_hint_index: NIL,
+            min_fill_atoms: BaseAtoms::ZERO,
+            min_taker_atoms: BaseAtoms::ZERO,
+            tag: 0,
+            skip_global_orders: true,
+            self_trade_prevention: Default::default(),
+            referred: false,
+        })?;
+    }
+
+    let quote_atoms: QuoteAtoms = {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.get_trader_balance(payer.key).1
+    };
+    if quote_atoms == QuoteAtoms::ZERO {
+        return Ok(());
+    }
+    process_withdraw_core(
+        program_id,
+        accounts,
+        WithdrawParams::new(quote_atoms.as_u64(), Some(trader_index)),
+    )
+}
+
+// Formal verification does not model selling into the book on withdraw.
+#[cfg(feature = "certora")]
+pub(crate) fn process_withdraw_as_quote(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
+
 /** Transfer from base (quote) vault to base (quote) trader using SPL Token **/
 #[cfg(not(feature = "certora"))]
-fn spl_token_transfer_from_vault_to_trader<'a, 'info>(
+pub(crate) fn spl_token_transfer_from_vault_to_trader<'a, 'info>(
     token_program: &TokenProgram<'a, 'info>,
     vault: &TokenAccountInfo<'a, 'info>,
     trader_account: &TokenAccountInfo<'a, 'info>,
@@ -161,7 +320,7 @@ fn spl_token_transfer_from_vault_to_trader<'a, 'info>(
 
 #[cfg(feature = "certora")]
 /** (Summary) Transfer from base (quote) vault to base (quote) trader using SPL Token **/
-fn spl_token_transfer_from_vault_to_trader<'a, 'info>(
+pub(crate) fn spl_token_transfer_from_vault_to_trader<'a, 'info>(
     _token_program: &TokenProgram<'a, 'info>,
     vault: &TokenAccountInfo<'a, 'info>,
     trader_account: &TokenAccountInfo<'a, 'info>,
@@ -175,7 +334,7 @@ fn spl_token_transfer_from_vault_to_trader<'a, 'info>(
 
 /** Transfer from base (quote) vault to base (quote) trader using SPL Token 2022 **/
 #[cfg(not(feature = "certora"))]
-fn spl_token_2022_transfer_from_vault_to_trader_fixed<'a, 'info>(
+pub(crate) fn spl_token_2022_transfer_from_vault_to_trader_fixed<'a, 'info>(
     token_program: &TokenProgram<'a, 'info>,
     mint: Option<MintAccountInfo<'a, 'info>>,
     mint_key: &Pubkey,
@@ -200,7 +359,7 @@ fn spl_token_2022_transfer_from_vault_to_trader_fixed<'a, 'info>(
         &[
             token_program.as_ref().clone(),
             vault.as_ref().clone(),
-            mint.unwrap().as_ref().clone(),
+            mint.ok_or(ManifestError::InvalidMint)?.as_ref().clone(),
             trader_token.as_ref().clone(),
         ],
         market_vault_seeds_with_bump!(market_key, mint_key, bump),
@@ -210,7 +369,7 @@ fn spl_token_2022_transfer_from_vault_to_trader_fixed<'a, 'info>(
 // TODO: Share these with swap and deposit.
 #[cfg(feature = "certora")]
 /** (Summary) Transfer from base (quote) vault to base (quote) trader using SPL Token 2022 **/
-fn spl_token_2022_transfer_from_vault_to_trader_fixed<'a, 'info>(
+pub(crate) fn spl_token_2022_transfer_from_vault_to_trader_fixed<'a, 'info>(
     _token_program: &TokenProgram<'a, 'info>,
     _mint: Option<MintAccountInfo<'a, 'info>>,
     _mint_key: &Pubkey,
@@ -199,6 +199,11 @@ macro_rules! basic_u64 {
 #[derive(
     Debug, Clone, Copy, PartialOrd, Ord, Zeroable, Pod, Deserialize, Serialize, ShankAccount,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct QuoteAtoms {
     inner: u64,
@@ -208,6 +213,11 @@ basic_u64!(QuoteAtoms);
 #[derive(
     Debug, Clone, Copy, PartialOrd, Ord, Zeroable, Pod, Deserialize, Serialize, ShankAccount,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct BaseAtoms {
     inner: u64,
@@ -217,6 +227,11 @@ basic_u64!(BaseAtoms);
 #[derive(
     Debug, Clone, Copy, PartialOrd, Ord, Zeroable, Pod, Deserialize, Serialize, ShankAccount,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema),
+    serde(transparent)
+)]
 #[repr(transparent)]
 pub struct GlobalAtoms {
     inner: u64,
@@ -294,6 +309,15 @@ static_assertions::const_assert_eq!(
 #[cfg(not(feature = "certora"))]
 const_assert!(DECIMAL_CONSTANTS[0] * (u32::MAX as u128) < u128::MAX);
 
+// ensures that move_toward cannot overflow for the market TWAP
+#[cfg(not(feature = "certora"))]
+const_assert!(
+    DECIMAL_CONSTANTS[0]
+        * (u32::MAX as u128)
+        * (crate::state::constants::TWAP_WINDOW_SLOTS as u128)
+        < u128::MAX
+);
+
 const_assert!(D18 * (u64::MAX as u128) < u128::MAX);
 
 #[cfg(feature = "certora")]
@@ -325,6 +349,51 @@ impl QuoteAtomsPerBaseAtom {
         }
     }
 
+    /// Moves self toward target by weight / total of the distance between
+    /// them. Total is at most TWAP_WINDOW_SLOTS, see the assert above.
+    pub(crate) fn move_toward(self, target: Self, weight: u32, total: u32) -> Self {
+        let value: u128 = u64_slice_to_u128(self.inner);
+        let target: u128 = u64_slice_to_u128(target.inner);
+        let inner: u128 = if target >= value {
+            value + (target - value) * weight as u128 / total as u128
+        } else {
+            value - (value - target) * weight as u128 / total as u128
+        };
+        QuoteAtomsPerBaseAtom {
+            inner: u128_to_u64_slice(inner),
+        }
+    }
+
+    /// Distance to other in basis points of other, saturating. Zero when
+    /// other is zero.
+    pub(crate) fn abs_diff_bps(self, other: Self) -> u64 {
+        let value: u128 = u64_slice_to_u128(self.inner);
+        let other: u128 = u64_slice_to_u128(other.inner);
+        value
+            .abs_diff(other)
+            .saturating_mul(10_000)
+            .checked_div(other)
+            .unwrap_or(0)
+            .min(u64::MAX as u128) as u64
+    }
+
+    /// The next price up or down by MIN, the smallest step a price can be
+    /// given in. None below zero or above MAX.
+    pub fn checked_step(self, up: bool) -> Option<Self> {
+        let value: u128 = u64_slice_to_u128(self.inner);
+        let step: u128 = u64_slice_to_u128(Self::MIN.inner);
+        let inner: u128 = if up {
+            value
+                .checked_add(step)
+                .filter(|inner| *inner <= u64_slice_to_u128(Self::MAX.inner))?
+        } else {
+            value.checked_sub(step)?
+        };
+        Some(QuoteAtomsPerBaseAtom {
+            inner: u128_to_u64_slice(inner),
+        })
+    }
+
     #[inline(always)]
     pub fn checked_multiply_rational(
         self,
@@ -456,6 +525,47 @@ impl std::fmt::Display for QuoteAtomsPerBaseAtom {
     }
 }
 
+/// Serialized as a decimal string of the u128 fixed point value, quote atoms
+/// per base atom times 10^18, so prices round trip exactly and fit in JSON
+/// numbers that only go up to u64 or f64.
+#[cfg(feature = "serde")]
+impl serde::Serialize for QuoteAtomsPerBaseAtom {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&u64_slice_to_u128(self.inner))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for QuoteAtomsPerBaseAtom {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let inner: u128 = <String as serde::Deserialize>::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)?;
+        Ok(QuoteAtomsPerBaseAtom {
+            inner: u128_to_u64_slice(inner),
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl schemars::JsonSchema for QuoteAtomsPerBaseAtom {
+    fn schema_name() -> String {
+        "QuoteAtomsPerBaseAtom".to_string()
+    }
+
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        schemars::schema::SchemaObject {
+            instance_type: Some(schemars::schema::InstanceType::String.into()),
+            string: Some(Box::new(schemars::schema::StringValidation {
+                pattern: Some("^[0-9]+$".to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 impl std::fmt::Debug for QuoteAtomsPerBaseAtom {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("QuoteAtomsPerBaseAtom")
@@ -662,6 +772,59 @@ fn test_multiply_macro() {
     );
 }
 
+#[test]
+fn test_move_toward() {
+    let one: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(1, 0).unwrap();
+    let three: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(3, 0).unwrap();
+    let two: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(2, 0).unwrap();
+    assert_eq!(one.move_toward(three, 1, 2), two);
+    assert_eq!(three.move_toward(one, 1, 2), two);
+    assert_eq!(one.move_toward(three, 0, 2), one);
+    assert_eq!(one.move_toward(three, 2, 2), three);
+    assert_eq!(
+        QuoteAtomsPerBaseAtom::MAX.move_toward(QuoteAtomsPerBaseAtom::ZERO, 599, 600),
+        QuoteAtomsPerBaseAtom::MAX
+            .checked_multiply_rational(1, 600, true)
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_abs_diff_bps() {
+    let one: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(100, -2).unwrap();
+    let up: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(101, -2).unwrap();
+    assert_eq!(up.abs_diff_bps(one), 100);
+    assert_eq!(one.abs_diff_bps(up), 99);
+    assert_eq!(one.abs_diff_bps(one), 0);
+    assert_eq!(one.abs_diff_bps(QuoteAtomsPerBaseAtom::ZERO), 0);
+    assert_eq!(
+        QuoteAtomsPerBaseAtom::MAX.abs_diff_bps(QuoteAtomsPerBaseAtom::MIN),
+        u64::MAX
+    );
+}
+
+#[test]
+fn test_checked_step() {
+    let one: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(1, 0).unwrap();
+    assert_eq!(
+        one.checked_step(true).unwrap().checked_step(false).unwrap(),
+        one
+    );
+    assert!(one.checked_step(false).unwrap() < one);
+    assert_eq!(
+        QuoteAtomsPerBaseAtom::MIN.checked_step(false),
+        Some(QuoteAtomsPerBaseAtom::ZERO)
+    );
+    assert_eq!(QuoteAtomsPerBaseAtom::ZERO.checked_step(false), None);
+    assert_eq!(QuoteAtomsPerBaseAtom::MAX.checked_step(true), None);
+}
+
 #[test]
 fn test_price_limits() {
     assert!(QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
@@ -111,6 +111,17 @@ impl QuoteAtomsPerBaseAtom {
         unreachable!();
     }
 
+    pub fn checked_step(self, up: bool) -> Option<Self> {
+        let inner: u64 = if up {
+            self.inner[0]
+                .checked_add(Self::MIN.inner[0])
+                .filter(|inner| *inner <= Self::MAX.inner[0])?
+        } else {
+            self.inner[0].checked_sub(Self::MIN.inner[0])?
+        };
+        Some(Self { inner: [inner, 0] })
+    }
+
     pub fn nondet_price_u32() -> Self {
         let x = ::nondet::nondet();
         cvt::cvt_assume!(x <= u32::MAX as u64);
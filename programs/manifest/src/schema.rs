@@ -0,0 +1,266 @@
+//! Serde support and JSON schemas for the types off-chain services exchange
+//! with the program: quantities, instruction params, return data and logs.
+//! Pubkeys are base58 strings, prices decimal strings of the fixed point
+//! u128 and PodBools plain bools. Padding is left out.
+//!
+//! [`log_schemas`] gives the discriminant, version and size of every log, to
+//! tell which fields a logged event has.
+
+use std::collections::BTreeMap;
+
+use schemars::{schema::RootSchema, schema_for};
+use serde::Serialize;
+
+use crate::{
+    logs::*,
+    program::{
+        archive_seat::ArchiveSeatParams,
+        batch_update::{
+            AmendOrderParams, BatchUpdateParams, BatchUpdateReturn, CancelOrderParams,
+            PlaceOrderParams, ReplaceOrderParams,
+        },
+        bust_trade::BustTradeParams,
+        claim_points::ClaimPointsParams,
+        commit_swap::CommitSwapParams,
+        deposit::DepositParams,
+        fill_rfq::{FillRfqParams, PostRfqParams},
+        fill_signed_order::{CancelSignedOrdersParams, FillSignedOrderParams},
+        get_market_prices::GetMarketPricesReturn,
+        get_seat_points::GetSeatPointsReturn,
+        global_clean::GlobalCleanParams,
+        global_deposit::GlobalDepositParams,
+        global_evict::GlobalEvictParams,
+        global_withdraw::GlobalWithdrawParams,
+        heartbeat::HeartbeatParams,
+        pegged_order::{CancelPeggedOrderParams, PlacePeggedOrderParams},
+        rebalance::RebalanceParams,
+        restore_seat::RestoreSeatParams,
+        set_mmp::SetMmpParams,
+        set_paused::SetPausedParams,
+        set_seat_config::SetSeatConfigParams,
+        stop_order::{CancelStopOrderParams, PlaceStopOrderParams},
+        withdraw::{WithdrawAsQuoteParams, WithdrawParams},
+        SwapParams,
+    },
+    quantities::{BaseAtoms, GlobalAtoms, QuoteAtoms, QuoteAtomsPerBaseAtom},
+};
+
+/// Pubkey fields as base58 strings, for serde(with).
+pub(crate) mod pubkey {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use solana_program::pubkey::Pubkey;
+
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+macro_rules! json_schemas {
+    ($($type_name:ident),* $(,)?) => {
+        BTreeMap::from([$((stringify!($type_name), schema_for!($type_name))),*])
+    };
+}
+
+/// JSON schema of every serializable type by name.
+pub fn json_schemas() -> BTreeMap<&'static str, RootSchema> {
+    json_schemas!(
+        // Quantities
+        BaseAtoms,
+        QuoteAtoms,
+        GlobalAtoms,
+        QuoteAtomsPerBaseAtom,
+        // Params and return data
+        ArchiveSeatParams,
+        BustTradeParams,
+        AmendOrderParams,
+        BatchUpdateParams,
+        BatchUpdateReturn,
+        CancelOrderParams,
+        CancelPeggedOrderParams,
+        CancelSignedOrdersParams,
+        CancelStopOrderParams,
+        ClaimPointsParams,
+        CommitSwapParams,
+        DepositParams,
+        FillRfqParams,
+        FillSignedOrderParams,
+        GetMarketPricesReturn,
+        GetSeatPointsReturn,
+        GlobalCleanParams,
+        GlobalDepositParams,
+        GlobalEvictParams,
+        GlobalWithdrawParams,
+        HeartbeatParams,
+        PlaceOrderParams,
+        PlacePeggedOrderParams,
+        PlaceStopOrderParams,
+        PostRfqParams,
+        RebalanceParams,
+        ReplaceOrderParams,
+        RestoreSeatParams,
+        SetMmpParams,
+        SetPausedParams,
+        SetSeatConfigParams,
+        SwapParams,
+        WithdrawAsQuoteParams,
+        WithdrawParams,
+        // Logs
+        ArchiveSeatLog,
+        CancelOrderLog,
+        ClaimFeesLog,
+        ClaimSeatLog,
+        CreateMarketLog,
+        DepositLog,
+        DepthSnapshotLog,
+        FeeLog,
+        FillLog,
+        GlobalAddTraderLog,
+        GlobalClaimSeatLog,
+        GlobalCleanupLog,
+        GlobalCreateLog,
+        GlobalDepositLog,
+        GlobalEvictLog,
+        GlobalWithdrawLog,
+        MarketPausedLog,
+        MmpTriggeredLog,
+        PeggedOrderPlacedLog,
+        PlaceOrderLog,
+        PlaceOrderLogV2,
+        PointsClaimLog,
+        ReferralFeeLog,
+        ReplaceOrderLog,
+        RestoreSeatLog,
+        RfqLog,
+        StopOrderTriggeredLog,
+        TradeBustLog,
+        WithdrawLog,
+    )
+}
+
+/// How a log is framed in the program data: the discriminant, the struct of
+/// `size` bytes and the version byte.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogSchema {
+    pub name: &'static str,
+    pub discriminant: [u8; 8],
+    pub version: u8,
+    pub size: usize,
+}
+
+macro_rules! log_schemas {
+    ($($type_name:ident),* $(,)?) => {
+        vec![$(LogSchema {
+            name: stringify!($type_name),
+            discriminant: $type_name::discriminant(),
+            version: $type_name::version(),
+            size: std::mem::size_of::<$type_name>(),
+        }),*]
+    };
+}
+
+/// Framing of every log, for indexers matching discriminants and versions.
+pub fn log_schemas() -> Vec<LogSchema> {
+    log_schemas!(
+        ArchiveSeatLog,
+        CancelOrderLog,
+        ClaimFeesLog,
+        ClaimSeatLog,
+        CreateMarketLog,
+        DepositLog,
+        DepthSnapshotLog,
+        FeeLog,
+        FillLog,
+        GlobalAddTraderLog,
+        GlobalClaimSeatLog,
+        GlobalCleanupLog,
+        GlobalCreateLog,
+        GlobalDepositLog,
+        GlobalEvictLog,
+        GlobalWithdrawLog,
+        MarketPausedLog,
+        MmpTriggeredLog,
+        PeggedOrderPlacedLog,
+        PlaceOrderLog,
+        PlaceOrderLogV2,
+        PointsClaimLog,
+        ReferralFeeLog,
+        ReplaceOrderLog,
+        RestoreSeatLog,
+        RfqLog,
+        StopOrderTriggeredLog,
+        TradeBustLog,
+        WithdrawLog,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::quantities::WrapperU64;
+    use hypertree::PodBool;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_json_round_trip() {
+        let price: QuoteAtomsPerBaseAtom =
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(15, -1).unwrap();
+        let fill: FillLog = FillLog {
+            market: Pubkey::new_unique(),
+            maker: Pubkey::new_unique(),
+            taker: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            price,
+            base_atoms: BaseAtoms::new(10),
+            quote_atoms: QuoteAtoms::new(15),
+            maker_sequence_number: 1,
+            taker_sequence_number: 2,
+            taker_is_buy: PodBool::from(true),
+            is_maker_global: PodBool::from(false),
+            self_trade: PodBool::from(false),
+            referred: PodBool::from(true),
+            maker_tag: 3,
+            taker_tag: 4,
+            taker_fee_bps: 5,
+            _padding2: [0; 2],
+        };
+        let json: serde_json::Value = serde_json::to_value(fill).unwrap();
+        assert_eq!(json["market"], fill.market.to_string());
+        assert_eq!(json["price"], "1500000000000000000");
+        assert_eq!(json["base_atoms"], 10);
+        assert_eq!(json["taker_is_buy"], true);
+        assert_eq!(json["referred"], true);
+        assert!(json.get("_padding").is_none());
+
+        let decoded: FillLog = serde_json::from_value(json).unwrap();
+        assert_eq!(bytemuck::bytes_of(&decoded), bytemuck::bytes_of(&fill));
+    }
+
+    #[test]
+    fn test_json_schemas() {
+        let schemas: BTreeMap<&'static str, RootSchema> = json_schemas();
+        let fill_schema: serde_json::Value = serde_json::to_value(&schemas["FillLog"]).unwrap();
+        assert_eq!(
+            fill_schema["properties"]["market"]["type"],
+            serde_json::json!("string")
+        );
+        assert!(fill_schema["properties"].get("_padding").is_none());
+        assert!(schemas.contains_key("BatchUpdateParams"));
+    }
+
+    #[test]
+    fn test_log_schemas() {
+        let logs: Vec<LogSchema> = log_schemas();
+        assert_eq!(logs.len(), 29);
+        let fill: &LogSchema = logs.iter().find(|log| log.name == "FillLog").unwrap();
+        assert_eq!(fill.discriminant, FillLog::discriminant());
+        assert_eq!(fill.version, 3);
+        assert_eq!(fill.size, std::mem::size_of::<FillLog>());
+    }
+}
@@ -1,15 +1,16 @@
-use std::mem::size_of;
+use std::mem::{offset_of, size_of};
 
 #[cfg(feature = "certora")]
 use crate::quantities::WrapperU64;
 use crate::quantities::{BaseAtoms, QuoteAtoms};
 use bytemuck::{Pod, Zeroable};
+use hypertree::{DataIndex, PodBool};
 use shank::ShankType;
 use solana_program::pubkey::Pubkey;
-use static_assertions::const_assert_eq;
+use static_assertions::{const_assert, const_assert_eq};
 use std::cmp::Ordering;
 
-use super::constants::CLAIMED_SEAT_SIZE;
+use super::constants::{CLAIMED_SEAT_SIZE, MARKET_BLOCK_SIZE, MAX_MARKET_SIZE};
 
 #[repr(C)]
 #[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
@@ -25,16 +26,33 @@ pub struct ClaimedSeat {
     /// not guaranteed to be maintained. It does not secure any value in
     /// manifest. Use at your own risk.
     pub quote_volume: QuoteAtoms,
-    _padding: [u8; 8],
+    /// Index of the SeatConfig block for this seat. Only valid when
+    /// has_seat_config is set, since zero is also a valid index.
+    seat_config_index: DataIndex,
+    has_seat_config: PodBool,
+    /// Block number plus one of the SeatVolume block for this seat, zero when
+    /// it has none. Little endian. Three bytes is all that was left, and
+    /// block numbers fit since markets are capped at MAX_MARKET_SIZE.
+    seat_volume_block: [u8; 3],
 }
 // 32 + // trader
 //  8 + // base_balance
 //  8 + // quote_balance
 //  8 + // quote_volume
-//  8   // padding
+//  4 + // seat_config_index
+//  1 + // has_seat_config
+//  3   // seat_volume_block
 // = 64
 const_assert_eq!(size_of::<ClaimedSeat>(), CLAIMED_SEAT_SIZE);
 const_assert_eq!(size_of::<ClaimedSeat>() % 8, 0);
+const_assert_eq!(offset_of!(ClaimedSeat, trader), 0);
+const_assert_eq!(offset_of!(ClaimedSeat, base_withdrawable_balance), 32);
+const_assert_eq!(offset_of!(ClaimedSeat, quote_withdrawable_balance), 40);
+const_assert_eq!(offset_of!(ClaimedSeat, quote_volume), 48);
+const_assert_eq!(offset_of!(ClaimedSeat, seat_config_index), 56);
+const_assert_eq!(offset_of!(ClaimedSeat, has_seat_config), 60);
+const_assert_eq!(offset_of!(ClaimedSeat, seat_volume_block), 61);
+const_assert!(MAX_MARKET_SIZE / MARKET_BLOCK_SIZE < 1 << 24);
 
 impl ClaimedSeat {
     pub fn new_empty(trader: Pubkey) -> Self {
@@ -43,6 +61,45 @@ impl ClaimedSeat {
             ..Default::default()
         }
     }
+
+    pub fn get_seat_config_index(&self) -> Option<DataIndex> {
+        if self.has_seat_config.0 == 1 {
+            Some(self.seat_config_index)
+        } else {
+            None
+        }
+    }
+
+    pub fn set_seat_config_index(&mut self, seat_config_index: Option<DataIndex>) {
+        self.seat_config_index = seat_config_index.unwrap_or_default();
+        self.has_seat_config = PodBool::from(seat_config_index.is_some());
+    }
+
+    pub fn get_seat_volume_index(&self) -> Option<DataIndex> {
+        get_block_link_index(self.seat_volume_block)
+    }
+
+    pub fn set_seat_volume_index(&mut self, seat_volume_index: Option<DataIndex>) {
+        self.seat_volume_block = to_block_link(seat_volume_index);
+    }
+}
+
+/// Index of the block a three byte link on a full block points at. Links are
+/// the block number plus one in little endian, zero for none.
+pub(crate) fn get_block_link_index(block_link: [u8; 3]) -> Option<DataIndex> {
+    let [b0, b1, b2] = block_link;
+    match u32::from_le_bytes([b0, b1, b2, 0]) {
+        0 => None,
+        block => Some((block - 1) * MARKET_BLOCK_SIZE as DataIndex),
+    }
+}
+
+pub(crate) fn to_block_link(index_opt: Option<DataIndex>) -> [u8; 3] {
+    let block: u32 = index_opt.map_or(0, |index: DataIndex| {
+        index / MARKET_BLOCK_SIZE as DataIndex + 1
+    });
+    let [b0, b1, b2, _] = block.to_le_bytes();
+    [b0, b1, b2]
 }
 
 #[cfg(feature = "certora")]
@@ -53,7 +110,9 @@ impl nondet::Nondet for ClaimedSeat {
             base_withdrawable_balance: BaseAtoms::new(nondet::nondet()),
             quote_withdrawable_balance: QuoteAtoms::new(nondet::nondet()),
             quote_volume: QuoteAtoms::new(nondet::nondet()),
-            _padding: [0; 8],
+            seat_config_index: 0,
+            has_seat_config: PodBool::from(false),
+            seat_volume_block: [0; 3],
         }
     }
 }
@@ -89,3 +148,19 @@ fn test_display() {
     let claimed_seat: ClaimedSeat = ClaimedSeat::new_empty(Pubkey::default());
     let _ = format!("{}", claimed_seat);
 }
+
+#[test]
+fn test_seat_volume_index() {
+    let mut claimed_seat: ClaimedSeat = ClaimedSeat::new_empty(Pubkey::default());
+    assert_eq!(claimed_seat.get_seat_volume_index(), None);
+    for index in [
+        0,
+        MARKET_BLOCK_SIZE as DataIndex,
+        130_000 * MARKET_BLOCK_SIZE as DataIndex,
+    ] {
+        claimed_seat.set_seat_volume_index(Some(index));
+        assert_eq!(claimed_seat.get_seat_volume_index(), Some(index));
+    }
+    claimed_seat.set_seat_volume_index(None);
+    assert_eq!(claimed_seat.get_seat_volume_index(), None);
+}
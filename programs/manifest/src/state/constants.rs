@@ -1,4 +1,5 @@
 use hypertree::RBTREE_OVERHEAD_BYTES;
+use static_assertions::const_assert;
 
 pub const MARKET_FIXED_SIZE: usize = 256;
 pub const GLOBAL_FIXED_SIZE: usize = 96;
@@ -10,6 +11,16 @@ pub const MARKET_BLOCK_SIZE: usize = 80;
 const MARKET_BLOCK_PAYLOAD_SIZE: usize = MARKET_BLOCK_SIZE - RBTREE_OVERHEAD_BYTES;
 pub const RESTING_ORDER_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
 pub const CLAIMED_SEAT_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const SEAT_CONFIG_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const CPI_ALLOWLIST_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const MIN_ORDER_SIZE_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const PRICE_BAND_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const MARKET_FEES_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const SEAT_VOLUME_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const SEAT_MMP_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const MARKET_STATS_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const MARKET_EXTENSION_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const FILL_RECORDS_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
 const GLOBAL_BLOCK_PAYLOAD_SIZE: usize = GLOBAL_BLOCK_SIZE - RBTREE_OVERHEAD_BYTES;
 pub const GLOBAL_TRADER_SIZE: usize = GLOBAL_BLOCK_PAYLOAD_SIZE;
 pub const GLOBAL_DEPOSIT_SIZE: usize = GLOBAL_BLOCK_PAYLOAD_SIZE;
@@ -19,8 +30,138 @@ pub const GLOBAL_FREE_LIST_BLOCK_SIZE: usize = GLOBAL_BLOCK_SIZE - FREE_LIST_OVE
 
 pub const NO_EXPIRATION_LAST_VALID_SLOT: u32 = 0;
 
+/// Largest size a market account may grow to. Solana caps accounts at 10MiB,
+/// so this is the last whole block under that. Expansions past it fail with
+/// MarketTooLarge rather than a realloc error from the runtime.
+#[cfg(feature = "test")]
+pub const MAX_MARKET_SIZE: usize = MARKET_FIXED_SIZE + 1_024 * MARKET_BLOCK_SIZE;
+#[cfg(not(feature = "test"))]
+pub const MAX_MARKET_SIZE: usize = MARKET_FIXED_SIZE
+    + (10 * 1024 * 1024 - MARKET_FIXED_SIZE) / MARKET_BLOCK_SIZE * MARKET_BLOCK_SIZE;
+
+const_assert!(MAX_MARKET_SIZE <= 10 * 1024 * 1024);
+const_assert!((MAX_MARKET_SIZE - MARKET_FIXED_SIZE) % MARKET_BLOCK_SIZE == 0);
+
+/// Most blocks added by one expansion. The runtime only lets an account grow
+/// by 10KiB per instruction.
+pub const MAX_MARKET_BLOCKS_PER_EXPAND: u32 = (10 * 1024 / MARKET_BLOCK_SIZE) as u32;
+
 pub const MARKET_FIXED_DISCRIMINANT: u64 = 4859840929024028656;
+
+/// Market flag set at creation. Orders that would cross a resting order from
+/// the same seat fail instead of trading against it, so a seat cannot wash
+/// trade with itself, including through its own reverse orders.
+pub const MARKET_FLAG_NO_INTERNAL_CROSS: u8 = 1 << 0;
+/// Market flag set at creation. Swaps, RFQ fills and batch updates that place
+/// or grow orders have to come through a CPI from a program on the market's
+/// CpiAllowlist, so a wrapper or vault can be the only entry point.
+pub const MARKET_FLAG_CPI_ALLOWLIST: u8 = 1 << 1;
+/// Most programs on a CpiAllowlist, as many as fit in a block.
+pub const MAX_CPI_ALLOWLIST_PROGRAMS: usize = 2;
+/// Market flag set at creation. Orders that would rest below the market's
+/// MinOrderSize fail, so the book cannot fill up with dust.
+pub const MARKET_FLAG_MIN_ORDER_SIZE: u8 = 1 << 2;
+/// Market flag set at creation. Takers cannot fill further than the market's
+/// PriceBand from its reference price, so a fat finger cannot walk the book.
+pub const MARKET_FLAG_PRICE_BAND: u8 = 1 << 3;
+/// Market flag set at creation. Makers and takers pay the bps of the market's
+/// MarketFees on what they receive from fills.
+pub const MARKET_FLAG_FEES: u8 = 1 << 4;
+/// Highest maker or taker fee a market can be created with, 10%.
+pub const MAX_FEE_BPS: u16 = 1_000;
+/// Market flag set at creation. The market keeps MarketStats of its fills in
+/// the block after the fees, so readers do not have to replay FillLogs.
+pub const MARKET_FLAG_VOLUME_STATS: u8 = 1 << 5;
+/// Market flag set at creation. The market has a MarketExtension block after
+/// all the other blocks, which reserves space for market fields since
+/// MarketFixed is full. Also the last flag bit, so new modes are set in the
+/// extension.
+pub const MARKET_FLAG_EXTENSION: u8 = 1 << 6;
+/// Version of the MarketExtension of markets created now. Version 2 added the
+/// pauser, version 3 the position of the next fill record, version 4 the fees
+/// owed by the fee recipient.
+pub const MARKET_EXTENSION_VERSION: u8 = 4;
+/// MarketExtension flag set by the market's pauser with SetPaused. New orders,
+/// swaps and fills fail while it is set. Cancels, deposits and withdraws
+/// still work.
+pub const MARKET_EXTENSION_FLAG_PAUSED: u8 = 1 << 0;
+/// MarketExtension flag set at creation. The market keeps FillRecords of its
+/// latest fills in the FILL_RECORD_BLOCKS blocks after the extension, and
+/// only fills found there can be busted with BustTrade.
+pub const MARKET_EXTENSION_FLAG_FILL_RECORDS: u8 = 1 << 1;
+/// Blocks of FillRecords of markets that keep them.
+pub const FILL_RECORD_BLOCKS: u32 = 32;
+/// Fills per block of FillRecords. A market keeps the latest
+/// FILL_RECORD_BLOCKS * FILL_RECORDS_PER_BLOCK fills.
+pub const FILL_RECORDS_PER_BLOCK: usize = 4;
+/// Market flag never set at creation. The program upgrade authority sets it
+/// with EnterRecovery on a market whose state may be damaged. Everything but
+/// RecoveryWithdraw fails from then on.
+pub const MARKET_FLAG_RECOVERY: u8 = 1 << 7;
+/// BustTrade only reverses fills among the last this many order sequence
+/// numbers of a market, on top of having to be in its FillRecords.
+pub const TRADE_BUST_MAX_SEQUENCE_NUMBERS: u64 = 10_000;
+/// Signed order nonces a maker can use out of order, see SignedOrderNonces.
+pub const SIGNED_ORDER_NONCE_WINDOW: u64 = 1_024;
+/// Maker quotes a FillRfq can pick the best of.
+pub const MAX_RFQ_RESPONSES: usize = 4;
+
+/// Trading hours, see TradingHours. Bit 0 is Monday.
+pub const TRADING_DAYS_ALL: u8 = 0b0111_1111;
+pub const MINUTES_PER_DAY: u16 = 1_440;
+
 pub const GLOBAL_FIXED_DISCRIMINANT: u64 = 10787423733276977665;
+pub const SEAT_ARCHIVE_DISCRIMINANT: u64 = 7405986865278176051;
+pub const SWAP_COMMITMENT_DISCRIMINANT: u64 = 11450261195504845139;
+pub const STOP_ORDERS_DISCRIMINANT: u64 = 16662432830009622193;
+pub const HEARTBEAT_DISCRIMINANT: u64 = 3021474203852288509;
+pub const MARKET_VOLATILITY_DISCRIMINANT: u64 = 4889987288196111218;
+pub const TRADE_BUST_DISCRIMINANT: u64 = 5626391606671886983;
+pub const SIGNED_ORDER_NONCES_DISCRIMINANT: u64 = 8149790891111600843;
+pub const RFQ_DISCRIMINANT: u64 = 3578967816379351578;
+pub const PEGGED_ORDERS_DISCRIMINANT: u64 = 5536978491081170263;
+
+/// Levels in the merkle tree of archived seats, enough for about a million.
+pub const SEAT_ARCHIVE_DEPTH: usize = 20;
+/// Anyone can archive a seat without resting orders once both its balances
+/// are at most this many atoms. Traders can archive their own seat at any
+/// balance.
+pub const SEAT_ARCHIVE_MAX_DUST_ATOMS: u64 = 1_000;
+
+/// Window of the market TWAP, about four minutes.
+pub const TWAP_WINDOW_SLOTS: u32 = 600;
+/// Shortest time between two samples of the market volatility, about a
+/// minute, so the estimate cannot be cranked faster than prices move.
+pub const VOLATILITY_SAMPLE_SLOTS: u32 = 150;
+/// Samples the market volatility is averaged over. Each sample moves the
+/// estimate by this fraction of the difference.
+pub const VOLATILITY_AVERAGE_SAMPLES: u32 = 8;
+/// Cap on a volatility sample, and so on the estimate, in basis points.
+pub const MAX_VOLATILITY_BPS: u16 = 5_000;
+/// Spread of a reverse order that uses the market volatility estimate at the
+/// time it flips instead of a fixed spread.
+pub const DYNAMIC_REVERSE_SPREAD: u16 = u16::MAX;
+/// Time without trades after which anyone can reset the reference price of a
+/// market PriceBand to the middle of the book, about a minute.
+pub const PRICE_BAND_RESET_SLOTS: u32 = 150;
+/// Bands the depth of GetMarketPrices is counted in, 2% of the best price.
+pub const PRICE_DEPTH_BAND_BPS: u32 = 200;
+/// Most price levels per side in a DepthSnapshotLog.
+pub const DEPTH_SNAPSHOT_MAX_LEVELS: usize = 10;
+/// Length of a points epoch, about two days like a mainnet epoch.
+pub const POINTS_EPOCH_SLOTS: u32 = 432_000;
+/// Seat taker volume is kept in buckets of about five days, and the rolling
+/// volume is the latest SEAT_VOLUME_BUCKETS of them, about 30 days.
+pub const SEAT_VOLUME_BUCKET_SLOTS: u32 = 1_080_000;
+pub const SEAT_VOLUME_BUCKETS: usize = 6;
+/// Taker fee discounts by rolling taker volume, as the multiple of the
+/// market's fee tier volume a seat needs and the discount in bps of the taker
+/// fee. See MarketFees.
+pub const FEE_TIERS: [(u64, u16); 3] = [(1, 1_000), (10, 2_500), (100, 5_000)];
+/// Stop orders a trader can have waiting on a market.
+pub const MAX_STOP_ORDERS: usize = 8;
+/// Pegged orders a trader can have on a market.
+pub const MAX_PEGGED_ORDERS: usize = 8;
 
 // Amount of gas deposited for every global order. This is done to as an
 // economic disincentive to spam.
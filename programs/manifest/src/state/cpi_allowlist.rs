@@ -0,0 +1,74 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use shank::ShankType;
+use solana_program::pubkey::Pubkey;
+use static_assertions::const_assert_eq;
+use std::cmp::Ordering;
+
+use super::constants::{CPI_ALLOWLIST_SIZE, MAX_CPI_ALLOWLIST_PROGRAMS};
+
+/// Programs allowed to call trading instructions on a market created with
+/// MARKET_FLAG_CPI_ALLOWLIST. Lives in the first block of the market, which
+/// is not part of any tree, and cannot be changed after creation.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct CpiAllowlist {
+    /// Unused entries are the default pubkey.
+    programs: [Pubkey; MAX_CPI_ALLOWLIST_PROGRAMS],
+}
+// 2 * 32 // programs
+// = 64
+const_assert_eq!(size_of::<CpiAllowlist>(), CPI_ALLOWLIST_SIZE);
+const_assert_eq!(size_of::<CpiAllowlist>() % 8, 0);
+
+impl CpiAllowlist {
+    pub fn new(programs: &[Pubkey]) -> Self {
+        let mut cpi_allowlist: CpiAllowlist = CpiAllowlist::default();
+        for (entry, program) in cpi_allowlist.programs.iter_mut().zip(programs) {
+            *entry = *program;
+        }
+        cpi_allowlist
+    }
+
+    pub fn is_allowed(&self, program: &Pubkey) -> bool {
+        *program != Pubkey::default() && self.programs.contains(program)
+    }
+}
+
+impl Ord for CpiAllowlist {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.programs).cmp(&(other.programs))
+    }
+}
+
+impl PartialOrd for CpiAllowlist {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for CpiAllowlist {
+    fn eq(&self, other: &Self) -> bool {
+        (self.programs) == (other.programs)
+    }
+}
+
+impl Eq for CpiAllowlist {}
+
+impl std::fmt::Display for CpiAllowlist {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self.programs)
+    }
+}
+
+#[test]
+fn test_cpi_allowlist_is_allowed() {
+    let program: Pubkey = Pubkey::new_unique();
+    let cpi_allowlist: CpiAllowlist = CpiAllowlist::new(&[program]);
+    assert!(cpi_allowlist.is_allowed(&program));
+    assert!(!cpi_allowlist.is_allowed(&Pubkey::new_unique()));
+    // Unused entries do not allow the default pubkey.
+    assert!(!cpi_allowlist.is_allowed(&Pubkey::default()));
+    let _ = format!("{}", cpi_allowlist);
+}
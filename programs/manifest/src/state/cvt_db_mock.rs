@@ -507,6 +507,10 @@ impl<'a> CvtBookside<'a> {
         }
     }
 
+    pub fn insert_with_hint(&mut self, index: DataIndex, order: RestingOrder, _hint: DataIndex) {
+        self.insert(index, order)
+    }
+
     pub fn insert(&mut self, index: DataIndex, order: RestingOrder) {
         let new_order_node: RBNode<RestingOrder> = RBNode {
             left: nondet(),
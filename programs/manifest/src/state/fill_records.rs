@@ -0,0 +1,168 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use shank::ShankType;
+use solana_program::{hash::hashv, pubkey::Pubkey};
+use static_assertions::const_assert_eq;
+use std::cmp::Ordering;
+
+use crate::quantities::{BaseAtoms, QuoteAtoms, WrapperU64};
+
+use super::constants::{FILL_RECORDS_PER_BLOCK, FILL_RECORDS_SIZE};
+
+/// Truncated hash of a fill, see get_fill_record_digest.
+pub type FillRecordDigest = [u8; 16];
+
+/// Digests of the latest fills of a market whose extension has
+/// MARKET_EXTENSION_FLAG_FILL_RECORDS, so BustTrade can check that a fill it
+/// is given happened as given. FILL_RECORD_BLOCKS of these follow the
+/// extension. Fills are written across them in turn and overwrite the oldest.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct FillRecords {
+    digests: [FillRecordDigest; FILL_RECORDS_PER_BLOCK],
+}
+// 4 * 16 // digests
+// = 64
+const_assert_eq!(size_of::<FillRecords>(), FILL_RECORDS_SIZE);
+const_assert_eq!(size_of::<FillRecords>() % 8, 0);
+
+impl FillRecords {
+    pub fn contains(&self, digest: &FillRecordDigest) -> bool {
+        self.digests.contains(digest)
+    }
+
+    pub(crate) fn set(&mut self, position: usize, digest: FillRecordDigest) {
+        self.digests[position] = digest;
+    }
+}
+
+/// Hash of the fields of a FillLog that a bust reverses, and the slot of the
+/// fill. Maker and taker sequence numbers tell fills apart, so no two fills
+/// of a market have the same fields.
+#[allow(clippy::too_many_arguments)]
+pub fn get_fill_record_digest(
+    maker: &Pubkey,
+    taker: &Pubkey,
+    base_atoms: BaseAtoms,
+    quote_atoms: QuoteAtoms,
+    taker_is_buy: bool,
+    maker_sequence_number: u64,
+    taker_sequence_number: u64,
+    taker_fee_bps: u16,
+    referred: bool,
+    fill_slot: u32,
+) -> FillRecordDigest {
+    let hash: [u8; 32] = hashv(&[
+        maker.as_ref(),
+        taker.as_ref(),
+        &base_atoms.as_u64().to_le_bytes(),
+        &quote_atoms.as_u64().to_le_bytes(),
+        &[taker_is_buy as u8],
+        &maker_sequence_number.to_le_bytes(),
+        &taker_sequence_number.to_le_bytes(),
+        &taker_fee_bps.to_le_bytes(),
+        &[referred as u8],
+        &fill_slot.to_le_bytes(),
+    ])
+    .to_bytes();
+    hash[..16].try_into().unwrap()
+}
+
+// Blocks need an ordering to be RBNode payloads, but fill records are never
+// in a tree.
+impl Ord for FillRecords {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.digests.cmp(&other.digests)
+    }
+}
+
+impl PartialOrd for FillRecords {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for FillRecords {
+    fn eq(&self, other: &Self) -> bool {
+        self.digests == other.digests
+    }
+}
+
+impl Eq for FillRecords {}
+
+impl std::fmt::Display for FillRecords {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} fill records", FILL_RECORDS_PER_BLOCK)
+    }
+}
+
+#[test]
+fn test_fill_records() {
+    let maker: Pubkey = Pubkey::new_unique();
+    let taker: Pubkey = Pubkey::new_unique();
+    let digest: FillRecordDigest = get_fill_record_digest(
+        &maker,
+        &taker,
+        BaseAtoms::new(10),
+        QuoteAtoms::new(25),
+        true,
+        0,
+        1,
+        30,
+        false,
+        100,
+    );
+    // Any other field is another fill.
+    assert_ne!(
+        digest,
+        get_fill_record_digest(
+            &maker,
+            &taker,
+            BaseAtoms::new(10),
+            QuoteAtoms::new(26),
+            true,
+            0,
+            1,
+            30,
+            false,
+            100,
+        )
+    );
+    assert_ne!(
+        digest,
+        get_fill_record_digest(
+            &maker,
+            &taker,
+            BaseAtoms::new(10),
+            QuoteAtoms::new(25),
+            true,
+            0,
+            1,
+            30,
+            true,
+            100,
+        )
+    );
+    assert_ne!(
+        digest,
+        get_fill_record_digest(
+            &maker,
+            &taker,
+            BaseAtoms::new(10),
+            QuoteAtoms::new(25),
+            true,
+            0,
+            1,
+            30,
+            false,
+            101,
+        )
+    );
+
+    let mut fill_records: FillRecords = FillRecords::default();
+    assert!(!fill_records.contains(&digest));
+    fill_records.set(3, digest);
+    assert!(fill_records.contains(&digest));
+    let _ = format!("{}", fill_records);
+}
@@ -0,0 +1,135 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::Get;
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use static_assertions::const_assert_eq;
+
+use crate::{program::ManifestError, require, validation::ManifestAccount};
+
+use super::constants::HEARTBEAT_DISCRIMINANT;
+
+/// Dead man's switch of a trader on a market. Once timeout_slots pass without
+/// a Heartbeat from the trader, anyone can cancel all of the seat's orders
+/// with CancelOnHeartbeatTimeout and take bounty_lamports from this account.
+/// That disarms the switch until the next Heartbeat.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Zeroable, Pod)]
+pub struct Heartbeat {
+    pub discriminant: u64,
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    /// Lamports above rent paid to whoever cancels after the timeout.
+    bounty_lamports: u64,
+    last_heartbeat_slot: u32,
+    /// Zero when disarmed.
+    timeout_slots: u32,
+}
+const_assert_eq!(
+    size_of::<Heartbeat>(),
+    8 +   // discriminant
+    32 +  // market
+    32 +  // trader
+    8 +   // bounty_lamports
+    4 +   // last_heartbeat_slot
+    4 // timeout_slots
+);
+const_assert_eq!(size_of::<Heartbeat>() % 8, 0);
+impl Get for Heartbeat {}
+
+impl ManifestAccount for Heartbeat {
+    fn verify_discriminant(&self) -> ProgramResult {
+        require!(
+            self.discriminant == HEARTBEAT_DISCRIMINANT,
+            ProgramError::InvalidAccountData,
+            "Invalid heartbeat discriminant actual: {} expected: {}",
+            self.discriminant,
+            HEARTBEAT_DISCRIMINANT
+        )?;
+        Ok(())
+    }
+}
+
+impl Heartbeat {
+    pub fn new_empty(market: &Pubkey, trader: &Pubkey) -> Self {
+        Heartbeat {
+            discriminant: HEARTBEAT_DISCRIMINANT,
+            market: *market,
+            trader: *trader,
+            bounty_lamports: 0,
+            last_heartbeat_slot: 0,
+            timeout_slots: 0,
+        }
+    }
+
+    pub fn get_bounty_lamports(&self) -> u64 {
+        self.bounty_lamports
+    }
+
+    pub fn get_last_heartbeat_slot(&self) -> u32 {
+        self.last_heartbeat_slot
+    }
+
+    pub fn get_timeout_slots(&self) -> u32 {
+        self.timeout_slots
+    }
+
+    /// Restarts the timeout from now_slot. A zero timeout disarms the switch.
+    pub fn beat(&mut self, timeout_slots: u32, bounty_lamports: u64, now_slot: u32) {
+        self.timeout_slots = timeout_slots;
+        self.bounty_lamports = bounty_lamports;
+        self.last_heartbeat_slot = now_slot;
+    }
+
+    pub fn is_timed_out(&self, now_slot: u32) -> bool {
+        self.timeout_slots != 0
+            && now_slot > self.last_heartbeat_slot.saturating_add(self.timeout_slots)
+    }
+
+    /// Disarms a timed out switch and returns the bounty.
+    pub fn fire(&mut self, now_slot: u32) -> Result<u64, ProgramError> {
+        require!(
+            self.is_timed_out(now_slot),
+            ManifestError::InvalidHeartbeat,
+            "Heartbeat at slot {} times out after {} slots",
+            self.last_heartbeat_slot,
+            self.timeout_slots,
+        )?;
+        let bounty_lamports: u64 = self.bounty_lamports;
+        self.timeout_slots = 0;
+        self.bounty_lamports = 0;
+        Ok(bounty_lamports)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat() {
+        let mut heartbeat: Heartbeat = Heartbeat::new_empty(&Pubkey::default(), &Pubkey::default());
+        assert!(!heartbeat.is_timed_out(u32::MAX));
+        assert!(heartbeat.fire(u32::MAX).is_err());
+
+        heartbeat.beat(10, 5_000, 100);
+        assert!(!heartbeat.is_timed_out(110));
+        assert!(heartbeat.fire(110).is_err());
+        assert!(heartbeat.is_timed_out(111));
+
+        // A later heartbeat restarts the timeout.
+        heartbeat.beat(10, 5_000, 105);
+        assert!(!heartbeat.is_timed_out(111));
+        assert_eq!(heartbeat.fire(116).unwrap(), 5_000);
+
+        // Disarmed until the next heartbeat.
+        assert!(!heartbeat.is_timed_out(u32::MAX));
+        assert!(heartbeat.fire(u32::MAX).is_err());
+        assert_eq!(heartbeat.get_bounty_lamports(), 0);
+        assert_eq!(heartbeat.get_last_heartbeat_slot(), 105);
+        assert_eq!(heartbeat.get_timeout_slots(), 0);
+
+        heartbeat.beat(u32::MAX, 0, 200);
+        assert!(!heartbeat.is_timed_out(u32::MAX));
+    }
+}
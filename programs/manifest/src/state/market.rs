@@ -3,8 +3,8 @@ use {crate::certora::hooks::*, hook_macro::cvt_hook_end, nondet::nondet};
 
 use bytemuck::{Pod, Zeroable};
 use hypertree::{
-    get_helper, get_mut_helper, is_not_nil, trace, DataIndex, FreeList, FreeListNode, Get, PodBool,
-    RBNode, NIL,
+    get_free_list_stats, get_helper, get_mut_helper, is_not_nil, trace, DataIndex, FreeList,
+    FreeListNode, FreeListStats, Get, PodBool, RBNode, NIL,
 };
 #[cfg(not(feature = "certora"))]
 use hypertree::{
@@ -14,10 +14,10 @@ use hypertree::{
 use shank::ShankType;
 use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
 use static_assertions::const_assert_eq;
-use std::mem::size_of;
+use std::mem::{offset_of, size_of};
 
 use crate::{
-    logs::{emit_stack, FillLog},
+    logs::{emit_fill_memo, emit_stack, CancelOrderLog, FeeLog, FillLog, MmpTriggeredLog},
     program::{batch_update::MarketDataTreeNodeType, ManifestError},
     quantities::{BaseAtoms, GlobalAtoms, QuoteAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
     require,
@@ -26,7 +26,7 @@ use crate::{
             assert_can_take, remove_from_global, transfer_global_tokens,
             try_to_reduce_global_tokens,
         },
-        OrderType,
+        OrderType, SelfTradePrevention,
     },
     validation::{
         get_vault_address, loaders::GlobalTradeAccounts, ManifestAccount, MintAccountInfo,
@@ -36,19 +36,43 @@ use crate::{
 use super::{
     claimed_seat::ClaimedSeat,
     constants::{MARKET_BLOCK_SIZE, MARKET_FIXED_SIZE},
+    cpi_allowlist::CpiAllowlist,
+    market_extension::MarketExtension,
+    market_fees::MarketFees,
+    market_stats::MarketStats,
+    min_order_size::MinOrderSize,
     order_type_can_rest,
+    price_band::PriceBand,
+    seat_config::SeatConfig,
+    seat_mmp::SeatMmp,
+    seat_volume::SeatVolume,
+    trading_hours::TradingHours,
     utils::{
         assert_already_has_seat, assert_not_already_expired, can_back_order, get_now_slot,
         try_to_add_to_global,
     },
     DerefOrBorrow, DerefOrBorrowMut, DynamicAccount, RestingOrder, MARKET_FIXED_DISCRIMINANT,
-    MARKET_FREE_LIST_BLOCK_SIZE, NO_EXPIRATION_LAST_VALID_SLOT,
+    MARKET_FLAG_CPI_ALLOWLIST, MARKET_FLAG_EXTENSION, MARKET_FLAG_FEES, MARKET_FLAG_MIN_ORDER_SIZE,
+    MARKET_FLAG_NO_INTERNAL_CROSS, MARKET_FLAG_PRICE_BAND, MARKET_FLAG_RECOVERY,
+    MARKET_FLAG_VOLUME_STATS, MARKET_FREE_LIST_BLOCK_SIZE, NO_EXPIRATION_LAST_VALID_SLOT,
+};
+#[cfg(not(feature = "certora"))]
+use super::{
+    constants::{
+        FILL_RECORDS_PER_BLOCK, FILL_RECORD_BLOCKS, POINTS_EPOCH_SLOTS,
+        TRADE_BUST_MAX_SEQUENCE_NUMBERS, TWAP_WINDOW_SLOTS,
+    },
+    fill_records::{get_fill_record_digest, FillRecordDigest, FillRecords},
 };
 
 #[path = "market_helpers.rs"]
 pub mod market_helpers;
 use market_helpers::*;
 
+#[cfg(all(test, not(feature = "certora")))]
+#[path = "market_reference_tests.rs"]
+mod market_reference_tests;
+
 #[path = "cvt_munge.rs"]
 #[cfg(feature = "certora")]
 mod cvt_munge;
@@ -109,6 +133,29 @@ pub struct AddOrderToMarketArgs<'a, 'info> {
     pub order_type: OrderType,
     pub global_trade_accounts_opts: &'a [Option<GlobalTradeAccounts<'a, 'info>>; 2],
     pub current_slot: Option<u32>,
+    /// Also send every fill to the memo program. Only set when the memo
+    /// program was passed to the instruction.
+    pub fill_memo: bool,
+    /// Index of an order on the same side that is expected to be next to
+    /// this one in the book, like the previous place in a batch. Saves the
+    /// walk from the root when resting. NIL when there is none.
+    pub insert_hint_index: DataIndex,
+    /// Makers that would fill less than this are skipped and stay on the
+    /// book. Only for orders that do not rest, since resting after skipping
+    /// would cross the book. Zero for no minimum.
+    pub min_fill_atoms: BaseAtoms,
+    /// Minimum taker fill for the remainder if it rests. Zero for no minimum.
+    pub min_taker_atoms: BaseAtoms,
+    /// Opaque tag stored on the order if it rests and echoed in its fills.
+    pub tag: u32,
+    /// Pass over global orders instead of stopping at the first one when the
+    /// global accounts are not included. Only for orders that do not rest.
+    pub skip_global_orders: bool,
+    /// What to do when matching a resting order from the same seat.
+    pub self_trade_prevention: SelfTradePrevention,
+    /// The order is a swap that pays the referral shares of its taker fees to
+    /// a referrer. Recorded with its fills, which are busted net of them.
+    pub referred: bool,
 }
 
 pub struct AddOrderToMarketResult {
@@ -116,6 +163,24 @@ pub struct AddOrderToMarketResult {
     pub order_index: DataIndex,
     pub base_atoms_traded: BaseAtoms,
     pub quote_atoms_traded: QuoteAtoms,
+    /// Referral shares of the taker fees charged on the fills, in the mint
+    /// the taker received. Paid out by swaps that include a referrer.
+    pub referral_fee_atoms: u64,
+}
+
+/// A fill to reverse, as in its FillLog.
+pub struct BustTradeArgs {
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub base_atoms: BaseAtoms,
+    pub quote_atoms: QuoteAtoms,
+    pub taker_is_buy: bool,
+    pub maker_sequence_number: u64,
+    pub taker_sequence_number: u64,
+    pub taker_fee_bps: u16,
+    pub referred: bool,
+    /// Slot of the transaction with the FillLog.
+    pub fill_slot: u32,
 }
 
 #[repr(C, packed)]
@@ -143,7 +208,10 @@ pub struct MarketFixed {
     quote_mint_decimals: u8,
     base_vault_bump: u8,
     quote_vault_bump: u8,
-    _padding1: [u8; 3],
+    _padding1: [u8; 1],
+    /// Volatility estimate in basis points, kept by the UpdateVolatility
+    /// crank. Reverse orders with DYNAMIC_REVERSE_SPREAD flip at this spread.
+    volatility_bps: u16,
 
     /// Base mint
     base_mint: Pubkey,
@@ -176,7 +244,9 @@ pub struct MarketFixed {
     /// LinkedList representing all free blocks that could be used for ClaimedSeats or RestingOrders
     free_list_head_index: DataIndex,
 
-    _padding2: [u32; 1],
+    /// Slots an order has to rest before its maker can cancel it, set by the
+    /// market creator to discourage quote fading. Zero is off.
+    min_resting_slots: u32,
 
     /// Quote volume traded over lifetime, can overflow. This is for
     /// informational and monitoring purposes only. This is not guaranteed to
@@ -184,6 +254,26 @@ pub struct MarketFixed {
     /// Use at your own risk.
     quote_volume: QuoteAtoms,
 
+    /// Modes chosen by the market creator and MARKET_FLAG_RECOVERY, see
+    /// MARKET_FLAG_*.
+    market_flags: u8,
+    /// Trading hours set by the market creator, see TradingHours.
+    trading_days: u8,
+    trading_open_minute: u16,
+    trading_close_minute: u16,
+    /// Slots between committing to a swap and executing it, for markets that
+    /// want takers to be unable to race makers. Zero is off. See
+    /// SwapCommitment.
+    taker_delay_slots: u16,
+
+    /// Quote atoms fills rounded in favor of the taker and of the maker over
+    /// the lifetime of the market. A fill whose quote is not a whole number of
+    /// atoms gives the remainder atom to one side. It settles with the rest
+    /// of the fill, so no dust is left over for the market. Informational like
+    /// quote_volume, can overflow.
+    taker_rounding_quote_atoms: QuoteAtoms,
+    maker_rounding_quote_atoms: QuoteAtoms,
+
     // These are not included in the normal usage because they are informational
     // only and not worth the CU.
     #[cfg(feature = "certora")]
@@ -199,13 +289,27 @@ pub struct MarketFixed {
     /// Quote tokens reserved for non-global orders
     pub orderbook_quote_atoms: QuoteAtoms,
     #[cfg(feature = "certora")]
-    _padding3: [u64; 4],
+    _padding3: [u64; 1],
 
-    // Unused padding. Saved in case a later version wants to be backwards
-    // compatible. Also, it is nice to have the fixed size be a round number,
-    // 256 bytes.
+    /// Price of the latest fill against the book and the slot it was in. RFQ
+    /// fills are not included since their price is not from the book. Not
+    /// kept for formal verification, where this space holds the fields above.
     #[cfg(not(feature = "certora"))]
-    _padding3: [u64; 8],
+    last_trade_price: QuoteAtomsPerBaseAtom,
+    /// Time weighted average of last_trade_price as of last_trade_slot, see
+    /// get_twap_price.
+    #[cfg(not(feature = "certora"))]
+    twap_price: QuoteAtomsPerBaseAtom,
+    #[cfg(not(feature = "certora"))]
+    last_trade_slot: u32,
+
+    /// Points per quote atom filled that a seat with a SeatConfig earns as
+    /// maker and as taker. Both zero means the market does not award points.
+    /// Set at creation and not kept for formal verification.
+    #[cfg(not(feature = "certora"))]
+    maker_points_weight: u16,
+    #[cfg(not(feature = "certora"))]
+    taker_points_weight: u16,
 }
 const_assert_eq!(
     size_of::<MarketFixed>(),
@@ -215,7 +319,8 @@ const_assert_eq!(
     1 +   // quote_mint_decimals
     1 +   // base_vault_bump
     1 +   // quote_vault_bump
-    3 +   // padding
+    1 +   // padding
+    2 +   // volatility_bps
     32 +  // base_mint
     32 +  // quote_mint
     32 +  // base_vault
@@ -227,13 +332,61 @@ const_assert_eq!(
     4 +   // asks_root_index
     4 +   // asks_best_index
     4 +   // claimed_seats_root_index
-    4 +   // claimed_seats_best_index
     4 +   // free_list_head_index
-    8 +   // padding2
-    64 // padding4
+    4 +   // min_resting_slots
+    8 +   // quote_volume
+    8 +   // market_flags, trading hours and taker_delay_slots
+    8 +   // taker_rounding_quote_atoms
+    8 +   // maker_rounding_quote_atoms
+    16 +  // last_trade_price
+    16 +  // twap_price
+    4 +   // last_trade_slot
+    2 +   // maker_points_weight
+    2 // taker_points_weight
 );
 const_assert_eq!(size_of::<MarketFixed>(), MARKET_FIXED_SIZE);
 const_assert_eq!(size_of::<MarketFixed>() % 8, 0);
+// Deployed markets and every client read these fields at fixed offsets, so a
+// field that moves is a breaking change. There are no free bytes left, so new
+// market fields go in the MarketExtension block instead.
+const_assert_eq!(offset_of!(MarketFixed, discriminant), 0);
+const_assert_eq!(offset_of!(MarketFixed, version), 8);
+const_assert_eq!(offset_of!(MarketFixed, base_mint_decimals), 9);
+const_assert_eq!(offset_of!(MarketFixed, quote_mint_decimals), 10);
+const_assert_eq!(offset_of!(MarketFixed, base_vault_bump), 11);
+const_assert_eq!(offset_of!(MarketFixed, quote_vault_bump), 12);
+const_assert_eq!(offset_of!(MarketFixed, volatility_bps), 14);
+const_assert_eq!(offset_of!(MarketFixed, base_mint), 16);
+const_assert_eq!(offset_of!(MarketFixed, quote_mint), 48);
+const_assert_eq!(offset_of!(MarketFixed, base_vault), 80);
+const_assert_eq!(offset_of!(MarketFixed, quote_vault), 112);
+const_assert_eq!(offset_of!(MarketFixed, order_sequence_number), 144);
+const_assert_eq!(offset_of!(MarketFixed, num_bytes_allocated), 152);
+const_assert_eq!(offset_of!(MarketFixed, bids_root_index), 156);
+const_assert_eq!(offset_of!(MarketFixed, bids_best_index), 160);
+const_assert_eq!(offset_of!(MarketFixed, asks_root_index), 164);
+const_assert_eq!(offset_of!(MarketFixed, asks_best_index), 168);
+const_assert_eq!(offset_of!(MarketFixed, claimed_seats_root_index), 172);
+const_assert_eq!(offset_of!(MarketFixed, free_list_head_index), 176);
+const_assert_eq!(offset_of!(MarketFixed, min_resting_slots), 180);
+const_assert_eq!(offset_of!(MarketFixed, quote_volume), 184);
+const_assert_eq!(offset_of!(MarketFixed, market_flags), 192);
+const_assert_eq!(offset_of!(MarketFixed, trading_days), 193);
+const_assert_eq!(offset_of!(MarketFixed, trading_open_minute), 194);
+const_assert_eq!(offset_of!(MarketFixed, trading_close_minute), 196);
+const_assert_eq!(offset_of!(MarketFixed, taker_delay_slots), 198);
+const_assert_eq!(offset_of!(MarketFixed, taker_rounding_quote_atoms), 200);
+const_assert_eq!(offset_of!(MarketFixed, maker_rounding_quote_atoms), 208);
+#[cfg(not(feature = "certora"))]
+const_assert_eq!(offset_of!(MarketFixed, last_trade_price), 216);
+#[cfg(not(feature = "certora"))]
+const_assert_eq!(offset_of!(MarketFixed, twap_price), 232);
+#[cfg(not(feature = "certora"))]
+const_assert_eq!(offset_of!(MarketFixed, last_trade_slot), 248);
+#[cfg(not(feature = "certora"))]
+const_assert_eq!(offset_of!(MarketFixed, maker_points_weight), 252);
+#[cfg(not(feature = "certora"))]
+const_assert_eq!(offset_of!(MarketFixed, taker_points_weight), 254);
 impl Get for MarketFixed {}
 
 impl MarketFixed {
@@ -241,6 +394,7 @@ impl MarketFixed {
         base_mint: &MintAccountInfo,
         quote_mint: &MintAccountInfo,
         market_key: &Pubkey,
+        market_flags: u8,
     ) -> Self {
         let (base_vault, base_vault_bump) = get_vault_address(market_key, base_mint.info.key);
         let (quote_vault, quote_vault_bump) = get_vault_address(market_key, quote_mint.info.key);
@@ -251,7 +405,8 @@ impl MarketFixed {
             quote_mint_decimals: quote_mint.mint.decimals,
             base_vault_bump,
             quote_vault_bump,
-            _padding1: [0; 3],
+            _padding1: [0; 1],
+            volatility_bps: 0,
             base_mint: *base_mint.info.key,
             quote_mint: *quote_mint.info.key,
             base_vault,
@@ -268,10 +423,25 @@ impl MarketFixed {
             #[cfg(feature = "certora")]
             // non NIL
             free_list_head_index: 0,
-            _padding2: [0; 1],
+            min_resting_slots: 0,
             quote_volume: QuoteAtoms::ZERO,
+            market_flags,
+            trading_days: 0,
+            trading_open_minute: 0,
+            trading_close_minute: 0,
+            taker_delay_slots: 0,
+            taker_rounding_quote_atoms: QuoteAtoms::ZERO,
+            maker_rounding_quote_atoms: QuoteAtoms::ZERO,
+            #[cfg(not(feature = "certora"))]
+            last_trade_price: QuoteAtomsPerBaseAtom::ZERO,
+            #[cfg(not(feature = "certora"))]
+            twap_price: QuoteAtomsPerBaseAtom::ZERO,
             #[cfg(not(feature = "certora"))]
-            _padding3: [0; 8],
+            last_trade_slot: 0,
+            #[cfg(not(feature = "certora"))]
+            maker_points_weight: 0,
+            #[cfg(not(feature = "certora"))]
+            taker_points_weight: 0,
             #[cfg(feature = "certora")]
             withdrawable_base_atoms: BaseAtoms::new(0),
             #[cfg(feature = "certora")]
@@ -281,7 +451,7 @@ impl MarketFixed {
             #[cfg(feature = "certora")]
             orderbook_quote_atoms: QuoteAtoms::new(0),
             #[cfg(feature = "certora")]
-            _padding3: [0; 4],
+            _padding3: [0; 1],
         }
     }
 
@@ -297,7 +467,8 @@ impl MarketFixed {
             quote_mint_decimals: nondet(),
             base_vault_bump: nondet(),
             quote_vault_bump: nondet(),
-            _padding1: [0; 3],
+            _padding1: [0; 1],
+            volatility_bps: 0,
             base_mint: nondet(),
             quote_mint: nondet(),
             base_vault: nondet(),
@@ -310,13 +481,20 @@ impl MarketFixed {
             asks_best_index: NIL,
             claimed_seats_root_index,
             free_list_head_index: 0,
-            _padding2: [0; 1],
+            min_resting_slots: 0,
             quote_volume: QuoteAtoms::ZERO,
+            market_flags: 0,
+            trading_days: 0,
+            trading_open_minute: 0,
+            trading_close_minute: 0,
+            taker_delay_slots: 0,
+            taker_rounding_quote_atoms: QuoteAtoms::ZERO,
+            maker_rounding_quote_atoms: QuoteAtoms::ZERO,
             withdrawable_base_atoms: BaseAtoms::new(nondet()),
             withdrawable_quote_atoms: QuoteAtoms::new(nondet()),
             orderbook_base_atoms: BaseAtoms::new(nondet()),
             orderbook_quote_atoms: QuoteAtoms::new(nondet()),
-            _padding3: [0; 4],
+            _padding3: [0; 1],
         }
     }
 
@@ -347,6 +525,173 @@ impl MarketFixed {
     pub fn get_quote_volume(&self) -> QuoteAtoms {
         self.quote_volume
     }
+    pub fn get_taker_rounding_quote_atoms(&self) -> QuoteAtoms {
+        self.taker_rounding_quote_atoms
+    }
+    pub fn get_maker_rounding_quote_atoms(&self) -> QuoteAtoms {
+        self.maker_rounding_quote_atoms
+    }
+    pub fn get_market_flags(&self) -> u8 {
+        self.market_flags
+    }
+    pub fn is_no_internal_cross(&self) -> bool {
+        self.market_flags & MARKET_FLAG_NO_INTERNAL_CROSS != 0
+    }
+    pub fn has_cpi_allowlist(&self) -> bool {
+        self.market_flags & MARKET_FLAG_CPI_ALLOWLIST != 0
+    }
+    pub fn has_min_order_size(&self) -> bool {
+        self.market_flags & MARKET_FLAG_MIN_ORDER_SIZE != 0
+    }
+    pub fn has_price_band(&self) -> bool {
+        self.market_flags & MARKET_FLAG_PRICE_BAND != 0
+    }
+    pub fn has_fees(&self) -> bool {
+        self.market_flags & MARKET_FLAG_FEES != 0
+    }
+    pub fn has_volume_stats(&self) -> bool {
+        self.market_flags & MARKET_FLAG_VOLUME_STATS != 0
+    }
+    pub fn has_extension(&self) -> bool {
+        self.market_flags & MARKET_FLAG_EXTENSION != 0
+    }
+    pub fn is_in_recovery(&self) -> bool {
+        self.market_flags & MARKET_FLAG_RECOVERY != 0
+    }
+    pub(crate) fn set_in_recovery(&mut self) {
+        self.market_flags |= MARKET_FLAG_RECOVERY;
+    }
+    /// Block of the MinOrderSize, after the CpiAllowlist if there is one.
+    fn get_min_order_size_index(&self) -> DataIndex {
+        if self.has_cpi_allowlist() {
+            MARKET_BLOCK_SIZE as DataIndex
+        } else {
+            0
+        }
+    }
+    /// Block of the PriceBand, after the CpiAllowlist and MinOrderSize.
+    fn get_price_band_index(&self) -> DataIndex {
+        (self.has_cpi_allowlist() as DataIndex + self.has_min_order_size() as DataIndex)
+            * MARKET_BLOCK_SIZE as DataIndex
+    }
+    /// Block of the MarketFees, after the CpiAllowlist, MinOrderSize and
+    /// PriceBand.
+    fn get_market_fees_index(&self) -> DataIndex {
+        (self.has_cpi_allowlist() as DataIndex
+            + self.has_min_order_size() as DataIndex
+            + self.has_price_band() as DataIndex)
+            * MARKET_BLOCK_SIZE as DataIndex
+    }
+    /// Block of the MarketStats, after the CpiAllowlist, MinOrderSize,
+    /// PriceBand and MarketFees.
+    fn get_market_stats_index(&self) -> DataIndex {
+        self.get_market_fees_index() + self.has_fees() as DataIndex * MARKET_BLOCK_SIZE as DataIndex
+    }
+    /// Block of the MarketExtension, after every other block set at creation
+    /// but the FillRecords.
+    fn get_market_extension_index(&self) -> DataIndex {
+        self.get_market_stats_index()
+            + self.has_volume_stats() as DataIndex * MARKET_BLOCK_SIZE as DataIndex
+    }
+    /// First block of the FillRecords, right after the MarketExtension.
+    #[cfg(not(feature = "certora"))]
+    fn get_fill_records_index(&self) -> DataIndex {
+        self.get_market_extension_index() + MARKET_BLOCK_SIZE as DataIndex
+    }
+    pub fn get_trading_hours(&self) -> TradingHours {
+        TradingHours::new(
+            self.trading_days,
+            self.trading_open_minute,
+            self.trading_close_minute,
+        )
+    }
+    pub(crate) fn set_trading_hours(&mut self, trading_hours: TradingHours) {
+        self.trading_days = trading_hours.days;
+        self.trading_open_minute = trading_hours.open_minute;
+        self.trading_close_minute = trading_hours.close_minute;
+    }
+    pub fn get_min_resting_slots(&self) -> u32 {
+        self.min_resting_slots
+    }
+    pub(crate) fn set_min_resting_slots(&mut self, min_resting_slots: u32) {
+        self.min_resting_slots = min_resting_slots;
+    }
+    pub fn get_taker_delay_slots(&self) -> u16 {
+        self.taker_delay_slots
+    }
+    pub(crate) fn set_taker_delay_slots(&mut self, taker_delay_slots: u16) {
+        self.taker_delay_slots = taker_delay_slots;
+    }
+    pub fn get_volatility_bps(&self) -> u16 {
+        self.volatility_bps
+    }
+    pub(crate) fn set_volatility_bps(&mut self, volatility_bps: u16) {
+        self.volatility_bps = volatility_bps;
+    }
+    #[cfg(not(feature = "certora"))]
+    pub fn get_last_trade_price(&self) -> QuoteAtomsPerBaseAtom {
+        self.last_trade_price
+    }
+    #[cfg(not(feature = "certora"))]
+    pub fn get_last_trade_slot(&self) -> u32 {
+        self.last_trade_slot
+    }
+    /// Average of the last trade price over about the last TWAP_WINDOW_SLOTS,
+    /// each price weighted by the slots it was the latest. Older prices fade
+    /// out instead of dropping off, so one trade moves it by at most the
+    /// share of the window since the previous trade. Zero before the first
+    /// trade.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_twap_price(&self, now_slot: u32) -> QuoteAtomsPerBaseAtom {
+        if self.twap_price == QuoteAtomsPerBaseAtom::ZERO {
+            return self.last_trade_price;
+        }
+        let elapsed_slots: u32 = now_slot
+            .saturating_sub(self.last_trade_slot)
+            .min(TWAP_WINDOW_SLOTS);
+        self.twap_price
+            .move_toward(self.last_trade_price, elapsed_slots, TWAP_WINDOW_SLOTS)
+    }
+    #[cfg(not(feature = "certora"))]
+    pub(crate) fn record_trade(&mut self, price: QuoteAtomsPerBaseAtom, now_slot: u32) {
+        self.twap_price = self.get_twap_price(now_slot);
+        self.last_trade_price = price;
+        self.last_trade_slot = now_slot;
+    }
+    #[cfg(feature = "certora")]
+    pub(crate) fn record_trade(&mut self, _price: QuoteAtomsPerBaseAtom, _now_slot: u32) {}
+    /// Maker and taker points per quote atom filled.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_points_weights(&self) -> (u16, u16) {
+        (self.maker_points_weight, self.taker_points_weight)
+    }
+    #[cfg(feature = "certora")]
+    pub fn get_points_weights(&self) -> (u16, u16) {
+        (0, 0)
+    }
+    pub fn awards_points(&self) -> bool {
+        self.get_points_weights() != (0, 0)
+    }
+    #[cfg(not(feature = "certora"))]
+    pub(crate) fn set_points_weights(
+        &mut self,
+        maker_points_weight: u16,
+        taker_points_weight: u16,
+    ) {
+        self.maker_points_weight = maker_points_weight;
+        self.taker_points_weight = taker_points_weight;
+    }
+    #[cfg(feature = "certora")]
+    pub(crate) fn set_points_weights(
+        &mut self,
+        _maker_points_weight: u16,
+        _taker_points_weight: u16,
+    ) {
+    }
+    /// Number of blocks in the dynamic part of the account, used or free.
+    pub fn get_num_allocated_blocks(&self) -> u32 {
+        self.num_bytes_allocated / MARKET_BLOCK_SIZE as u32
+    }
 
     // Used only in this file to construct iterator
     pub(crate) fn get_bids_root_index(&self) -> DataIndex {
@@ -577,6 +922,92 @@ impl<Fixed: DerefOrBorrow<MarketFixed>, Dynamic: DerefOrBorrow<[u8]>>
         return Ok(total_matched_quote_atoms);
     }
 
+    /// Best price on one side of the book and the size resting within
+    /// band_bps of it. Expired orders and global orders are skipped, since
+    /// global orders might not be backed. None if no orders are left.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_price_depth(
+        &self,
+        is_bids: bool,
+        band_bps: u32,
+        now_slot: u32,
+    ) -> Result<Option<(QuoteAtomsPerBaseAtom, BaseAtoms, QuoteAtoms)>, ProgramError> {
+        let book: BooksideReadOnly = if is_bids {
+            self.get_bids()
+        } else {
+            self.get_asks()
+        };
+
+        let mut best_price: Option<QuoteAtomsPerBaseAtom> = None;
+        let mut limit_price: QuoteAtomsPerBaseAtom = QuoteAtomsPerBaseAtom::ZERO;
+        let mut base_atoms: BaseAtoms = BaseAtoms::ZERO;
+        let mut quote_atoms: QuoteAtoms = QuoteAtoms::ZERO;
+        for (_, resting_order) in book.iter::<RestingOrder>() {
+            if resting_order.is_expired(now_slot) || resting_order.is_global() {
+                continue;
+            }
+            let price: QuoteAtomsPerBaseAtom = resting_order.get_price();
+            if best_price.is_none() {
+                best_price = Some(price);
+                limit_price = if is_bids {
+                    price.checked_multiply_rational(
+                        10_000_u32.saturating_sub(band_bps),
+                        10_000,
+                        true,
+                    )?
+                } else {
+                    price
+                        .checked_multiply_rational(10_000 + band_bps, 10_000, false)
+                        .unwrap_or(QuoteAtomsPerBaseAtom::MAX)
+                };
+            }
+            if (is_bids && price < limit_price) || (!is_bids && price > limit_price) {
+                break;
+            }
+            base_atoms = base_atoms.checked_add(resting_order.get_num_base_atoms())?;
+            quote_atoms = quote_atoms.saturating_add(
+                price.checked_quote_for_base(resting_order.get_num_base_atoms(), false)?,
+            );
+        }
+        Ok(best_price.map(|best_price| (best_price, base_atoms, quote_atoms)))
+    }
+
+    /// Best max_levels prices on one side of the book and the size resting at
+    /// each, best first. Expired orders are skipped.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_depth_levels(
+        &self,
+        is_bids: bool,
+        max_levels: usize,
+        now_slot: u32,
+    ) -> Result<Vec<(QuoteAtomsPerBaseAtom, BaseAtoms)>, ProgramError> {
+        let book: BooksideReadOnly = if is_bids {
+            self.get_bids()
+        } else {
+            self.get_asks()
+        };
+
+        let mut levels: Vec<(QuoteAtomsPerBaseAtom, BaseAtoms)> = Vec::with_capacity(max_levels);
+        for (_, resting_order) in book.iter::<RestingOrder>() {
+            if resting_order.is_expired(now_slot) {
+                continue;
+            }
+            let price: QuoteAtomsPerBaseAtom = resting_order.get_price();
+            match levels.last_mut() {
+                Some((level_price, base_atoms)) if *level_price == price => {
+                    *base_atoms = base_atoms.checked_add(resting_order.get_num_base_atoms())?;
+                }
+                _ => {
+                    if levels.len() == max_levels {
+                        break;
+                    }
+                    levels.push((price, resting_order.get_num_base_atoms()));
+                }
+            }
+        }
+        Ok(levels)
+    }
+
     // Simplified version for certora. Those checks are actually stronger than
     // needed since it shows invariants hold on swap even when impact_base_atoms
     // returns a wrong value.
@@ -708,6 +1139,86 @@ impl<Fixed: DerefOrBorrow<MarketFixed>, Dynamic: DerefOrBorrow<[u8]>>
         &get_helper_order(dynamic, index).get_value()
     }
 
+    /// Index of the order with order_sequence_number, which has to belong to
+    /// trader_index. Does a linear scan over the orderbook.
+    pub fn get_order_index_to_cancel(
+        &self,
+        trader_index: DataIndex,
+        order_sequence_number: u64,
+    ) -> Result<DataIndex, ProgramError> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+
+        let mut index_to_remove: DataIndex = NIL;
+
+        // One iteration to find the index to cancel in the ask side.
+        let tree: BooksideReadOnly =
+            BooksideReadOnly::new(dynamic, fixed.asks_root_index, fixed.asks_best_index);
+        for (index, resting_order) in tree.iter::<RestingOrder>() {
+            if resting_order.get_sequence_number() == order_sequence_number {
+                require!(
+                    resting_order.get_trader_index() == trader_index,
+                    ManifestError::InvalidCancel,
+                    "Cannot cancel for another trader",
+                )?;
+                require!(
+                    index_to_remove == NIL,
+                    ManifestError::InvalidCancel,
+                    "Book is broken, matched multiple orders",
+                )?;
+                index_to_remove = index;
+            }
+        }
+
+        // Second iteration to find the index to cancel in the bid side.
+        let tree: BooksideReadOnly =
+            BooksideReadOnly::new(dynamic, fixed.bids_root_index, fixed.bids_best_index);
+        for (index, resting_order) in tree.iter::<RestingOrder>() {
+            if resting_order.get_sequence_number() == order_sequence_number {
+                require!(
+                    resting_order.get_trader_index() == trader_index,
+                    ManifestError::InvalidCancel,
+                    "Cannot cancel for another trader",
+                )?;
+                require!(
+                    index_to_remove == NIL,
+                    ManifestError::InvalidCancel,
+                    "Book is broken, matched multiple orders",
+                )?;
+                index_to_remove = index;
+            }
+        }
+
+        // Do not fail silently.
+        require!(
+            is_not_nil!(index_to_remove),
+            ManifestError::InvalidCancel,
+            "Unable to find the order for cancel. Sequence number: {}",
+            order_sequence_number
+        )?;
+        Ok(index_to_remove)
+    }
+
+    /// Indices of all resting orders of trader_index, bids first. Does a
+    /// linear scan over the orderbook.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_trader_order_indices(&self, trader_index: DataIndex) -> Vec<DataIndex> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+
+        let mut order_indices: Vec<DataIndex> = Vec::new();
+        for (root_index, best_index) in [
+            (fixed.bids_root_index, fixed.bids_best_index),
+            (fixed.asks_root_index, fixed.asks_best_index),
+        ] {
+            let tree: BooksideReadOnly = BooksideReadOnly::new(dynamic, root_index, best_index);
+            order_indices.extend(
+                tree.iter::<RestingOrder>()
+                    .filter(|(_, resting_order)| resting_order.get_trader_index() == trader_index)
+                    .map(|(index, _)| index),
+            );
+        }
+        order_indices
+    }
+
     pub fn get_trader_balance(&self, trader: &Pubkey) -> (BaseAtoms, QuoteAtoms) {
         let DynamicAccount { fixed, dynamic } = self.borrow_market();
 
@@ -817,100 +1328,919 @@ impl<Fixed: DerefOrBorrow<MarketFixed>, Dynamic: DerefOrBorrow<[u8]>>
         return false;
     }
 
-    pub fn get_trader_index(&self, trader: &Pubkey) -> DataIndex {
+    /// Free blocks and where they sit in the account. Walks the whole free
+    /// list, so this is for clients and monitoring.
+    pub fn get_free_list_stats(&self) -> FreeListStats {
         let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        get_free_list_stats::<MarketUnusedFreeListPadding>(dynamic, fixed.free_list_head_index)
+    }
 
-        let claimed_seats_tree: ClaimedSeatTreeReadOnly =
-            ClaimedSeatTreeReadOnly::new(dynamic, fixed.claimed_seats_root_index, NIL);
-        let trader_index: DataIndex =
-            claimed_seats_tree.lookup_index(&ClaimedSeat::new_empty(*trader));
-        trader_index
+    /// Blocks currently holding a seat, seat config, resting order or the CPI
+    /// allowlist.
+    pub fn get_num_used_blocks(&self) -> u32 {
+        let DynamicAccount { fixed, .. } = self.borrow_market();
+        fixed
+            .get_num_allocated_blocks()
+            .saturating_sub(self.get_free_list_stats().num_free)
     }
-}
 
-// This generic impl covers MarketRef, MarketRefMut and other
-// DynamicAccount variants that allow write access.
-impl<
-        Fixed: DerefOrBorrowMut<MarketFixed> + DerefOrBorrow<MarketFixed>,
-        Dynamic: DerefOrBorrowMut<[u8]> + DerefOrBorrow<[u8]>,
-    > DynamicAccount<Fixed, Dynamic>
-{
-    fn borrow_mut(&mut self) -> MarketRefMut {
-        MarketRefMut {
-            fixed: self.fixed.deref_or_borrow_mut(),
-            dynamic: self.dynamic.deref_or_borrow_mut(),
-        }
+    pub fn get_seat_config(&self, trader_index: DataIndex) -> Option<&SeatConfig> {
+        let DynamicAccount { dynamic, .. } = self.borrow_market();
+
+        let claimed_seat: &ClaimedSeat = get_helper_seat(dynamic, trader_index).get_value();
+        claimed_seat
+            .get_seat_config_index()
+            .map(|seat_config_index: DataIndex| {
+                get_helper::<RBNode<SeatConfig>>(dynamic, seat_config_index).get_value()
+            })
     }
 
-    pub fn market_expand(&mut self) -> ProgramResult {
-        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
-        let mut free_list: FreeList<MarketUnusedFreeListPadding> =
-            FreeList::new(dynamic, fixed.free_list_head_index);
+    pub fn get_seat_volume(&self, trader_index: DataIndex) -> Option<&SeatVolume> {
+        let DynamicAccount { dynamic, .. } = self.borrow_market();
 
-        free_list.add(fixed.num_bytes_allocated);
-        fixed.num_bytes_allocated += MARKET_BLOCK_SIZE as u32;
-        fixed.free_list_head_index = free_list.get_head();
-        Ok(())
+        let claimed_seat: &ClaimedSeat = get_helper_seat(dynamic, trader_index).get_value();
+        claimed_seat
+            .get_seat_volume_index()
+            .map(|seat_volume_index: DataIndex| {
+                get_helper::<RBNode<SeatVolume>>(dynamic, seat_volume_index).get_value()
+            })
     }
 
-    pub fn market_expand_n(&mut self, mut n: u32) -> ProgramResult {
-        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
-        let mut free_list: FreeList<MarketUnusedFreeListPadding> =
-            FreeList::new(dynamic, fixed.free_list_head_index);
-        while n > 0 {
-            free_list.add(fixed.num_bytes_allocated);
-            fixed.num_bytes_allocated += MARKET_BLOCK_SIZE as u32;
-            fixed.free_list_head_index = free_list.get_head();
-            n -= 1;
-        }
-        Ok(())
+    pub fn get_seat_mmp(&self, trader_index: DataIndex) -> Option<&SeatMmp> {
+        let DynamicAccount { dynamic, .. } = self.borrow_market();
+        get_seat_mmp_index(dynamic, trader_index).map(|seat_mmp_index: DataIndex| {
+            get_helper::<RBNode<SeatMmp>>(dynamic, seat_mmp_index).get_value()
+        })
     }
 
-    pub fn claim_seat(&mut self, trader: &Pubkey) -> ProgramResult {
-        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
-        let free_address: DataIndex = get_free_address_on_market_fixed_for_seat(fixed, dynamic);
+    /// Rolling taker volume of a seat as of now_slot. Zero for seats without
+    /// a SeatVolume.
+    pub fn get_seat_rolling_quote_atoms(
+        &self,
+        trader_index: DataIndex,
+        now_slot: u32,
+    ) -> QuoteAtoms {
+        self.get_seat_volume(trader_index)
+            .map_or(QuoteAtoms::ZERO, |seat_volume: &SeatVolume| {
+                seat_volume.get_rolling_quote_atoms(now_slot)
+            })
+    }
 
-        let mut claimed_seats_tree: ClaimedSeatTree =
-            ClaimedSeatTree::new(dynamic, fixed.claimed_seats_root_index, NIL);
+    /// The allowlist is the first block of markets created with
+    /// MARKET_FLAG_CPI_ALLOWLIST.
+    pub fn get_cpi_allowlist(&self) -> Option<&CpiAllowlist> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        if !fixed.has_cpi_allowlist() {
+            return None;
+        }
+        Some(get_helper::<RBNode<CpiAllowlist>>(dynamic, 0).get_value())
+    }
 
-        let claimed_seat: ClaimedSeat = ClaimedSeat::new_empty(*trader);
-        require!(
-            claimed_seats_tree.lookup_index(&claimed_seat) == NIL,
-            ManifestError::AlreadyClaimedSeat,
-            "Already claimed seat",
-        )?;
-        claimed_seats_tree.insert(free_address, claimed_seat);
-        // theoretically we need to update the total withdrawable amount, but it's always 0 here.
-        // but let's check here that the assumption holds.
-        #[cfg(feature = "certora")]
-        {
-            cvt::cvt_assert!(claimed_seat.base_withdrawable_balance == BaseAtoms::new(0));
-            cvt::cvt_assert!(claimed_seat.quote_withdrawable_balance == QuoteAtoms::new(0));
+    /// The minimum is the first block after the allowlist, if any, of markets
+    /// created with MARKET_FLAG_MIN_ORDER_SIZE.
+    pub fn get_min_order_size(&self) -> Option<&MinOrderSize> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        if !fixed.has_min_order_size() {
+            return None;
         }
-        fixed.claimed_seats_root_index = claimed_seats_tree.get_root_index();
+        Some(
+            get_helper::<RBNode<MinOrderSize>>(dynamic, fixed.get_min_order_size_index())
+                .get_value(),
+        )
+    }
 
-        get_mut_helper::<RBNode<ClaimedSeat>>(dynamic, free_address)
-            .set_payload_type(MarketDataTreeNodeType::ClaimedSeat as u8);
-        Ok(())
+    /// Fails for an order that could rest below the market minimum. Markets
+    /// without one take any size.
+    pub fn verify_min_order_size(
+        &self,
+        num_base_atoms: BaseAtoms,
+        price: QuoteAtomsPerBaseAtom,
+    ) -> ProgramResult {
+        match self.get_min_order_size() {
+            Some(min_order_size) => min_order_size.verify(num_base_atoms, price),
+            None => Ok(()),
+        }
     }
 
-    // Only used when temporarily claiming for swap and we dont have the system
-    // program to expand. Otherwise, there is no reason to ever give up your
-    // seat.
+    /// The band is the first block after the allowlist and minimum, if any,
+    /// of markets created with MARKET_FLAG_PRICE_BAND.
+    pub fn get_price_band(&self) -> Option<&PriceBand> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        if !fixed.has_price_band() {
+            return None;
+        }
+        Some(get_helper::<RBNode<PriceBand>>(dynamic, fixed.get_price_band_index()).get_value())
+    }
+
+    /// Band and current reference price fills are checked against. None for
+    /// markets without a band.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_price_band_reference(&self) -> Option<(PriceBand, QuoteAtomsPerBaseAtom)> {
+        let price_band: PriceBand = *self.get_price_band()?;
+        let DynamicAccount { fixed, .. } = self.borrow_market();
+        let (reference, _reference_slot) =
+            price_band.get_reference(fixed.get_last_trade_price(), fixed.get_last_trade_slot());
+        Some((price_band, reference))
+    }
+    // Formal verification does not model the price band.
+    #[cfg(feature = "certora")]
+    pub fn get_price_band_reference(&self) -> Option<(PriceBand, QuoteAtomsPerBaseAtom)> {
+        None
+    }
+
+    /// The fees are the first block after the allowlist, minimum and band, if
+    /// any, of markets created with MARKET_FLAG_FEES.
+    pub fn get_market_fees(&self) -> Option<&MarketFees> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        if !fixed.has_fees() {
+            return None;
+        }
+        Some(get_helper::<RBNode<MarketFees>>(dynamic, fixed.get_market_fees_index()).get_value())
+    }
+
+    /// The stats are the block after the fees, if any, of markets created
+    /// with MARKET_FLAG_VOLUME_STATS.
+    pub fn get_market_stats(&self) -> Option<&MarketStats> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        if !fixed.has_volume_stats() {
+            return None;
+        }
+        Some(get_helper::<RBNode<MarketStats>>(dynamic, fixed.get_market_stats_index()).get_value())
+    }
+
+    /// The extension is the last block set at creation before the
+    /// FillRecords, of markets created with MARKET_FLAG_EXTENSION.
+    pub fn get_market_extension(&self) -> Option<&MarketExtension> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        if !fixed.has_extension() {
+            return None;
+        }
+        Some(
+            get_helper::<RBNode<MarketExtension>>(dynamic, fixed.get_market_extension_index())
+                .get_value(),
+        )
+    }
+
+    /// Fails while the market's pauser has it paused. Markets without an
+    /// extension cannot be paused.
+    pub fn verify_not_paused(&self) -> ProgramResult {
+        require!(
+            !self
+                .get_market_extension()
+                .is_some_and(MarketExtension::is_paused),
+            ManifestError::MarketPaused,
+            "Market is paused",
+        )?;
+        Ok(())
+    }
+
+    /// Whether a fill with this digest is among the latest fills kept in the
+    /// FillRecords. Always false for markets that keep none.
+    #[cfg(not(feature = "certora"))]
+    pub fn has_fill_record(&self, digest: &FillRecordDigest) -> bool {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        if !has_fill_records(fixed, dynamic) {
+            return false;
+        }
+        (0..FILL_RECORD_BLOCKS).any(|block: u32| {
+            get_helper::<RBNode<FillRecords>>(
+                dynamic,
+                fixed.get_fill_records_index() + block * MARKET_BLOCK_SIZE as DataIndex,
+            )
+            .get_value()
+            .contains(digest)
+        })
+    }
+
+    /// Fees charged on fills. None for markets without fees.
+    #[cfg(not(feature = "certora"))]
+    pub(crate) fn get_fill_fees(&self) -> Option<MarketFees> {
+        self.get_market_fees().copied()
+    }
+    // Formal verification does not model fees.
+    #[cfg(feature = "certora")]
+    pub(crate) fn get_fill_fees(&self) -> Option<MarketFees> {
+        None
+    }
+
+    /// Fees charged on fills taken by the seat at taker_index, with the taker
+    /// fee discounted by the seat's fee tier.
+    pub(crate) fn get_taker_fill_fees(
+        &self,
+        taker_index: DataIndex,
+        now_slot: u32,
+    ) -> Option<MarketFees> {
+        let market_fees: MarketFees = self.get_fill_fees()?;
+        let discount_bps: u16 = market_fees
+            .get_taker_fee_discount_bps(self.get_seat_rolling_quote_atoms(taker_index, now_slot));
+        Some(market_fees.with_taker_fee_bps(market_fees.get_discounted_taker_fee_bps(discount_bps)))
+    }
+
+    pub fn has_fee_tiers(&self) -> bool {
+        self.get_fill_fees()
+            .is_some_and(|market_fees: MarketFees| market_fees.has_fee_tiers())
+    }
+
+    pub fn get_trader_index(&self, trader: &Pubkey) -> DataIndex {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+
+        let claimed_seats_tree: ClaimedSeatTreeReadOnly =
+            ClaimedSeatTreeReadOnly::new(dynamic, fixed.claimed_seats_root_index, NIL);
+        let trader_index: DataIndex =
+            claimed_seats_tree.lookup_index(&ClaimedSeat::new_empty(*trader));
+        trader_index
+    }
+}
+
+// This generic impl covers MarketRef, MarketRefMut and other
+// DynamicAccount variants that allow write access.
+impl<
+        Fixed: DerefOrBorrowMut<MarketFixed> + DerefOrBorrow<MarketFixed>,
+        Dynamic: DerefOrBorrowMut<[u8]> + DerefOrBorrow<[u8]>,
+    > DynamicAccount<Fixed, Dynamic>
+{
+    fn borrow_mut(&mut self) -> MarketRefMut {
+        MarketRefMut {
+            fixed: self.fixed.deref_or_borrow_mut(),
+            dynamic: self.dynamic.deref_or_borrow_mut(),
+        }
+    }
+
+    pub fn market_expand(&mut self) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let mut free_list: FreeList<MarketUnusedFreeListPadding> =
+            FreeList::new(dynamic, fixed.free_list_head_index);
+
+        free_list.add(fixed.num_bytes_allocated);
+        fixed.num_bytes_allocated += MARKET_BLOCK_SIZE as u32;
+        fixed.free_list_head_index = free_list.get_head();
+        Ok(())
+    }
+
+    pub fn market_expand_n(&mut self, n: u32) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let mut free_list: FreeList<MarketUnusedFreeListPadding> =
+            FreeList::new(dynamic, fixed.free_list_head_index);
+        // Lowest new block goes on top so blocks get used from the front.
+        free_list.add_run(fixed.num_bytes_allocated, MARKET_BLOCK_SIZE as u32, n);
+        fixed.num_bytes_allocated += n * MARKET_BLOCK_SIZE as u32;
+        fixed.free_list_head_index = free_list.get_head();
+        Ok(())
+    }
+
+    /// Puts the allowlist in the first block. Only done at creation, when
+    /// that block is the only one.
+    pub fn init_cpi_allowlist(&mut self, cpi_allowlist: CpiAllowlist) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            fixed.has_cpi_allowlist() && fixed.num_bytes_allocated == MARKET_BLOCK_SIZE as u32,
+            ManifestError::InvalidMarketParameters,
+            "CPI allowlist can only be set at creation",
+        )?;
+        let free_address: DataIndex = get_free_address_on_market_fixed_for_seat(fixed, dynamic);
+        let start: usize = free_address as usize;
+        dynamic[start..start + MARKET_BLOCK_SIZE].fill(0);
+        let node: &mut RBNode<CpiAllowlist> =
+            get_mut_helper::<RBNode<CpiAllowlist>>(dynamic, free_address);
+        *node.get_mut_value() = cpi_allowlist;
+        node.set_payload_type(MarketDataTreeNodeType::CpiAllowlist as u8);
+        Ok(())
+    }
+
+    /// Puts the minimum in the block after the allowlist, if any. Only done at
+    /// creation, right after that block is added.
+    pub fn init_min_order_size(&mut self, min_order_size: MinOrderSize) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            fixed.has_min_order_size()
+                && fixed.num_bytes_allocated
+                    == fixed.get_min_order_size_index() + MARKET_BLOCK_SIZE as u32,
+            ManifestError::InvalidMarketParameters,
+            "Min order size can only be set at creation",
+        )?;
+        let free_address: DataIndex = get_free_address_on_market_fixed_for_seat(fixed, dynamic);
+        let start: usize = free_address as usize;
+        dynamic[start..start + MARKET_BLOCK_SIZE].fill(0);
+        let node: &mut RBNode<MinOrderSize> =
+            get_mut_helper::<RBNode<MinOrderSize>>(dynamic, free_address);
+        *node.get_mut_value() = min_order_size;
+        node.set_payload_type(MarketDataTreeNodeType::MinOrderSize as u8);
+        Ok(())
+    }
+
+    /// Puts the band in the block after the allowlist and minimum, if any.
+    /// Only done at creation, right after that block is added.
+    pub fn init_price_band(&mut self, price_band: PriceBand) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            fixed.has_price_band()
+                && fixed.num_bytes_allocated
+                    == fixed.get_price_band_index() + MARKET_BLOCK_SIZE as u32,
+            ManifestError::InvalidMarketParameters,
+            "Price band can only be set at creation",
+        )?;
+        let free_address: DataIndex = get_free_address_on_market_fixed_for_seat(fixed, dynamic);
+        let start: usize = free_address as usize;
+        dynamic[start..start + MARKET_BLOCK_SIZE].fill(0);
+        let node: &mut RBNode<PriceBand> =
+            get_mut_helper::<RBNode<PriceBand>>(dynamic, free_address);
+        *node.get_mut_value() = price_band;
+        node.set_payload_type(MarketDataTreeNodeType::PriceBand as u8);
+        Ok(())
+    }
+
+    /// Puts the fees in the block after the allowlist, minimum and band, if
+    /// any. Only done at creation, right after that block is added.
+    pub fn init_market_fees(&mut self, market_fees: MarketFees) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            fixed.has_fees()
+                && fixed.num_bytes_allocated
+                    == fixed.get_market_fees_index() + MARKET_BLOCK_SIZE as u32,
+            ManifestError::InvalidMarketParameters,
+            "Fees can only be set at creation",
+        )?;
+        let free_address: DataIndex = get_free_address_on_market_fixed_for_seat(fixed, dynamic);
+        let start: usize = free_address as usize;
+        dynamic[start..start + MARKET_BLOCK_SIZE].fill(0);
+        let node: &mut RBNode<MarketFees> =
+            get_mut_helper::<RBNode<MarketFees>>(dynamic, free_address);
+        *node.get_mut_value() = market_fees;
+        node.set_payload_type(MarketDataTreeNodeType::MarketFees as u8);
+        Ok(())
+    }
+
+    /// Puts empty stats in the block after the fees, if any. Only done at
+    /// creation, right after that block is added.
+    pub fn init_market_stats(&mut self) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            fixed.has_volume_stats()
+                && fixed.num_bytes_allocated
+                    == fixed.get_market_stats_index() + MARKET_BLOCK_SIZE as u32,
+            ManifestError::InvalidMarketParameters,
+            "Volume stats can only be set up at creation",
+        )?;
+        let free_address: DataIndex = get_free_address_on_market_fixed_for_seat(fixed, dynamic);
+        let start: usize = free_address as usize;
+        dynamic[start..start + MARKET_BLOCK_SIZE].fill(0);
+        get_mut_helper::<RBNode<MarketStats>>(dynamic, free_address)
+            .set_payload_type(MarketDataTreeNodeType::MarketStats as u8);
+        Ok(())
+    }
+
+    /// Puts the extension of the current version in the block after every
+    /// other block. Only done at creation, right after that block is added.
+    pub fn init_market_extension(&mut self, market_extension: MarketExtension) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            fixed.has_extension()
+                && fixed.num_bytes_allocated
+                    == fixed.get_market_extension_index() + MARKET_BLOCK_SIZE as u32,
+            ManifestError::InvalidMarketParameters,
+            "The extension can only be set up at creation",
+        )?;
+        let free_address: DataIndex = get_free_address_on_market_fixed_for_seat(fixed, dynamic);
+        let start: usize = free_address as usize;
+        dynamic[start..start + MARKET_BLOCK_SIZE].fill(0);
+        let node: &mut RBNode<MarketExtension> =
+            get_mut_helper::<RBNode<MarketExtension>>(dynamic, free_address);
+        *node.get_mut_value() = market_extension;
+        node.set_payload_type(MarketDataTreeNodeType::MarketExtension as u8);
+        Ok(())
+    }
+
+    /// Sets up the FILL_RECORD_BLOCKS blocks after the extension as empty
+    /// FillRecords. Only done at creation, right after those blocks are added.
+    #[cfg(not(feature = "certora"))]
+    pub fn init_fill_records(&mut self) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            has_fill_records(fixed, dynamic)
+                && fixed.num_bytes_allocated
+                    == fixed.get_fill_records_index()
+                        + FILL_RECORD_BLOCKS * MARKET_BLOCK_SIZE as u32,
+            ManifestError::InvalidMarketParameters,
+            "Fill records can only be set up at creation",
+        )?;
+        // The blocks were added in one expansion, so they come off the free
+        // list in order.
+        for _ in 0..FILL_RECORD_BLOCKS {
+            let free_address: DataIndex = get_free_address_on_market_fixed_for_seat(fixed, dynamic);
+            let start: usize = free_address as usize;
+            dynamic[start..start + MARKET_BLOCK_SIZE].fill(0);
+            get_mut_helper::<RBNode<FillRecords>>(dynamic, free_address)
+                .set_payload_type(MarketDataTreeNodeType::FillRecords as u8);
+        }
+        Ok(())
+    }
+    // Formal verification does not model fill records.
+    #[cfg(feature = "certora")]
+    pub fn init_fill_records(&mut self) -> ProgramResult {
+        Ok(())
+    }
+
+    /// Pauses or resumes trading. Only the pauser set at creation can, and it
+    /// has no other power over the market.
+    pub fn set_paused(&mut self, pauser: &Pubkey, paused: bool) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            fixed.has_extension(),
+            ManifestError::InvalidPauser,
+            "Market has no pauser",
+        )?;
+        let market_extension: &mut MarketExtension =
+            get_mut_helper::<RBNode<MarketExtension>>(dynamic, fixed.get_market_extension_index())
+                .get_mut_value();
+        require!(
+            market_extension.get_pauser() == Some(pauser),
+            ManifestError::InvalidPauser,
+            "{} is not the pauser of the market",
+            pauser,
+        )?;
+        market_extension.set_paused(paused);
+        Ok(())
+    }
+
+    /// Zeroes the fees accrued in one mint and returns how many atoms the
+    /// fee recipient is paid, after paying back fees that busts refunded once
+    /// they were claimed. recipient is the owner of the token account they go
+    /// to.
+    pub fn claim_fees(&mut self, recipient: &Pubkey, is_base: bool) -> Result<u64, ProgramError> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            fixed.has_fees(),
+            ManifestError::InvalidFeeClaim,
+            "Market has no fees",
+        )?;
+        let market_fees: &mut MarketFees =
+            get_mut_helper::<RBNode<MarketFees>>(dynamic, fixed.get_market_fees_index())
+                .get_mut_value();
+        require!(
+            market_fees.get_fee_recipient() == recipient,
+            ManifestError::InvalidFeeClaim,
+            "Fees go to {}, not {}",
+            market_fees.get_fee_recipient(),
+            recipient,
+        )?;
+        let claimed_atoms: u64 = market_fees.claim(is_base);
+        if !fixed.has_extension() {
+            return Ok(claimed_atoms);
+        }
+        Ok(
+            get_mut_helper::<RBNode<MarketExtension>>(dynamic, fixed.get_market_extension_index())
+                .get_mut_value()
+                .net_fee_claim(is_base, claimed_atoms),
+        )
+    }
+
+    /// Takes referral_fee_atoms, the referral shares of the fills of a swap
+    /// charged in base when is_base, out of the accrued fees to pay the
+    /// referrer. Does nothing on markets without fees.
+    pub fn pay_referral_fee(&mut self, is_base: bool, referral_fee_atoms: u64) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        if !fixed.has_fees() {
+            return Ok(());
+        }
+        get_mut_helper::<RBNode<MarketFees>>(dynamic, fixed.get_market_fees_index())
+            .get_mut_value()
+            .pay_referral(is_base, referral_fee_atoms)
+    }
+
+    /// Moves the seat balances of a fill back and refunds its fees, returning
+    /// the maker and taker fee atoms refunded. The fill has to be among the
+    /// latest ones in the FillRecords of the market with exactly the given
+    /// fields and slot, and within TRADE_BUST_MAX_SEQUENCE_NUMBERS. Markets
+    /// without fill records cannot bust fills. The volume, stats, points and
+    /// market maker protection counts of the fill are taken back as well,
+    /// see remove_fill. Referral shares already paid to a referrer cannot be
+    /// taken back from it, so only the rest of the taker fee is refunded and
+    /// the taker, whose swap named the referrer, gives the share back. Fees claimed since the fill
+    /// are owed back by the fee recipient, see MarketExtension. The orders
+    /// and last trade price are left as they are.
+    #[cfg(not(feature = "certora"))]
+    pub fn bust_trade(&mut self, args: BustTradeArgs) -> Result<(u64, u64), ProgramError> {
+        let BustTradeArgs {
+            maker,
+            taker,
+            base_atoms,
+            quote_atoms,
+            taker_is_buy,
+            maker_sequence_number,
+            taker_sequence_number,
+            taker_fee_bps,
+            referred,
+            fill_slot,
+        } = args;
+        require!(
+            self.get_market_extension()
+                .is_some_and(MarketExtension::has_fill_records),
+            ManifestError::InvalidTradeBust,
+            "Market keeps no fill records",
+        )?;
+        require!(
+            self.has_fill_record(&get_fill_record_digest(
+                &maker,
+                &taker,
+                base_atoms,
+                quote_atoms,
+                taker_is_buy,
+                maker_sequence_number,
+                taker_sequence_number,
+                taker_fee_bps,
+                referred,
+                fill_slot,
+            )),
+            ManifestError::InvalidTradeBust,
+            "Fill {} of {} is not among the recorded fills",
+            taker_sequence_number,
+            taker,
+        )?;
+        let maker_index: DataIndex = self.get_trader_index(&maker);
+        let taker_index: DataIndex = self.get_trader_index(&taker);
+        require!(
+            is_not_nil!(maker_index) && is_not_nil!(taker_index),
+            ManifestError::InvalidTradeBust,
+            "Maker and taker need seats",
+        )?;
+        // RFQ fills share one sequence number and are not charged fees. Others
+        // refund the taker fee at the rate in their FillLog, which is lower for
+        // seats with a fee tier discount.
+        let market_fees_opt: Option<MarketFees> = if maker_sequence_number == taker_sequence_number
+        {
+            None
+        } else {
+            self.get_fill_fees()
+        };
+        if let Some(market_fees) = market_fees_opt {
+            require!(
+                taker_fee_bps <= market_fees.get_taker_fee_bps(),
+                ManifestError::InvalidTradeBust,
+                "Taker fee {} bps above the market's {} bps",
+                taker_fee_bps,
+                market_fees.get_taker_fee_bps(),
+            )?;
+        }
+        let market_fees_opt: Option<MarketFees> = market_fees_opt
+            .map(|market_fees: MarketFees| market_fees.with_taker_fee_bps(taker_fee_bps));
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            maker_sequence_number <= taker_sequence_number
+                && taker_sequence_number < fixed.order_sequence_number
+                && fixed.order_sequence_number - taker_sequence_number
+                    <= TRADE_BUST_MAX_SEQUENCE_NUMBERS,
+            ManifestError::InvalidTradeBust,
+            "Fill {} is not among the last {} orders",
+            taker_sequence_number,
+            TRADE_BUST_MAX_SEQUENCE_NUMBERS,
+        )?;
+
+        let (maker_gross_atoms, taker_gross_atoms): (u64, u64) = if taker_is_buy {
+            (quote_atoms.as_u64(), base_atoms.as_u64())
+        } else {
+            (base_atoms.as_u64(), quote_atoms.as_u64())
+        };
+        let (maker_fee_atoms, taker_fee_atoms): (u64, u64) = match market_fees_opt {
+            Some(market_fees) => {
+                let taker_fee_atoms: u64 = market_fees.get_taker_fee_atoms(taker_gross_atoms);
+                let referral_fee_atoms: u64 = if referred {
+                    market_fees.get_referral_fee_atoms(taker_fee_atoms)
+                } else {
+                    0
+                };
+                (
+                    market_fees.get_maker_fee_atoms(maker_gross_atoms),
+                    taker_fee_atoms - referral_fee_atoms,
+                )
+            }
+            None => (0, 0),
+        };
+        let (buyer_index, seller_index, buyer_fee_atoms, seller_fee_atoms) = if taker_is_buy {
+            (taker_index, maker_index, taker_fee_atoms, maker_fee_atoms)
+        } else {
+            (maker_index, taker_index, maker_fee_atoms, taker_fee_atoms)
+        };
+
+        // Credit first so a self trade does not need the balance twice.
+        update_balance(
+            fixed,
+            dynamic,
+            buyer_index,
+            false,
+            true,
+            quote_atoms.as_u64(),
+        )?;
+        update_balance(
+            fixed,
+            dynamic,
+            seller_index,
+            true,
+            true,
+            base_atoms.as_u64(),
+        )?;
+        update_balance(
+            fixed,
+            dynamic,
+            buyer_index,
+            true,
+            false,
+            base_atoms.as_u64() - buyer_fee_atoms,
+        )?;
+        update_balance(
+            fixed,
+            dynamic,
+            seller_index,
+            false,
+            false,
+            quote_atoms.as_u64() - seller_fee_atoms,
+        )?;
+        if market_fees_opt.is_some() {
+            let (base_owed_atoms, quote_owed_atoms): (BaseAtoms, QuoteAtoms) =
+                get_mut_helper::<RBNode<MarketFees>>(dynamic, fixed.get_market_fees_index())
+                    .get_mut_value()
+                    .refund(
+                        BaseAtoms::new(buyer_fee_atoms),
+                        QuoteAtoms::new(seller_fee_atoms),
+                    );
+            // Markets with fill records have an extension.
+            get_mut_helper::<RBNode<MarketExtension>>(dynamic, fixed.get_market_extension_index())
+                .get_mut_value()
+                .owe_fees(base_owed_atoms, quote_owed_atoms)?;
+        }
+        remove_fill(
+            fixed,
+            dynamic,
+            maker_index,
+            taker_index,
+            base_atoms,
+            quote_atoms,
+            maker_sequence_number == taker_sequence_number,
+            fill_slot,
+        );
+        Ok((maker_fee_atoms, taker_fee_atoms))
+    }
+
+    /// Resets the reference price of the band to the middle of the best bid
+    /// and ask that have not expired, see PriceBand::reset.
+    #[cfg(not(feature = "certora"))]
+    pub fn reset_price_band(&mut self, now_slot: u32) -> ProgramResult {
+        let best_price = |bookside: BooksideReadOnly| {
+            bookside
+                .iter::<RestingOrder>()
+                .find(|(_, order)| !order.is_expired(now_slot))
+                .map(|(_, order)| order.get_price())
+        };
+        let best_bid_price_opt: Option<QuoteAtomsPerBaseAtom> = best_price(self.get_bids());
+        let best_ask_price_opt: Option<QuoteAtomsPerBaseAtom> = best_price(self.get_asks());
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            fixed.has_price_band(),
+            ManifestError::InvalidPriceBandReset,
+            "Market has no price band",
+        )?;
+        let (Some(best_bid_price), Some(best_ask_price)) = (best_bid_price_opt, best_ask_price_opt)
+        else {
+            return Err(ManifestError::InvalidPriceBandReset.into());
+        };
+        let mid_price: QuoteAtomsPerBaseAtom = best_bid_price.move_toward(best_ask_price, 1, 2);
+        let last_trade_price: QuoteAtomsPerBaseAtom = fixed.get_last_trade_price();
+        let last_trade_slot: u32 = fixed.get_last_trade_slot();
+        get_mut_helper::<RBNode<PriceBand>>(dynamic, fixed.get_price_band_index())
+            .get_mut_value()
+            .reset(mid_price, last_trade_price, last_trade_slot, now_slot)
+    }
+
+    pub fn claim_seat(&mut self, trader: &Pubkey) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let free_address: DataIndex = get_free_address_on_market_fixed_for_seat(fixed, dynamic);
+
+        let mut claimed_seats_tree: ClaimedSeatTree =
+            ClaimedSeatTree::new(dynamic, fixed.claimed_seats_root_index, NIL);
+
+        let claimed_seat: ClaimedSeat = ClaimedSeat::new_empty(*trader);
+        require!(
+            claimed_seats_tree.lookup_index(&claimed_seat) == NIL,
+            ManifestError::AlreadyClaimedSeat,
+            "Already claimed seat",
+        )?;
+        claimed_seats_tree.insert(free_address, claimed_seat);
+        // theoretically we need to update the total withdrawable amount, but it's always 0 here.
+        // but let's check here that the assumption holds.
+        #[cfg(feature = "certora")]
+        {
+            cvt::cvt_assert!(claimed_seat.base_withdrawable_balance == BaseAtoms::new(0));
+            cvt::cvt_assert!(claimed_seat.quote_withdrawable_balance == QuoteAtoms::new(0));
+        }
+        fixed.claimed_seats_root_index = claimed_seats_tree.get_root_index();
+
+        get_mut_helper::<RBNode<ClaimedSeat>>(dynamic, free_address)
+            .set_payload_type(MarketDataTreeNodeType::ClaimedSeat as u8);
+        Ok(())
+    }
+
+    // Only used when temporarily claiming for swap and we dont have the system
+    // program to expand. Otherwise, there is no reason to ever give up your
+    // seat.
     pub fn release_seat(&mut self, trader: &Pubkey) -> ProgramResult {
         let trader_seat_index: DataIndex = self.get_trader_index(trader);
         let DynamicAccount { fixed, dynamic } = self.borrow_mut();
 
-        let mut claimed_seats_tree: ClaimedSeatTree =
-            ClaimedSeatTree::new(dynamic, fixed.claimed_seats_root_index, NIL);
-        claimed_seats_tree.remove_by_index(trader_seat_index);
-        fixed.claimed_seats_root_index = claimed_seats_tree.get_root_index();
+        let mut claimed_seats_tree: ClaimedSeatTree =
+            ClaimedSeatTree::new(dynamic, fixed.claimed_seats_root_index, NIL);
+        claimed_seats_tree.remove_by_index(trader_seat_index);
+        fixed.claimed_seats_root_index = claimed_seats_tree.get_root_index();
+
+        // Seats that are temporarily claimed never have a config, but do not
+        // leak the block if that ever changes.
+        let seat_config_index_opt: Option<DataIndex> = get_helper_seat(dynamic, trader_seat_index)
+            .get_value()
+            .get_seat_config_index();
+        if let Some(seat_config_index) = seat_config_index_opt {
+            release_address_on_market_fixed_for_seat(fixed, dynamic, seat_config_index);
+        }
+        let seat_volume_index_opt: Option<DataIndex> = get_helper_seat(dynamic, trader_seat_index)
+            .get_value()
+            .get_seat_volume_index();
+        if let Some(seat_mmp_index) = get_seat_mmp_index(dynamic, trader_seat_index) {
+            release_address_on_market_fixed_for_seat(fixed, dynamic, seat_mmp_index);
+        }
+        if let Some(seat_volume_index) = seat_volume_index_opt {
+            release_address_on_market_fixed_for_seat(fixed, dynamic, seat_volume_index);
+        }
+
+        // Put back seat on free list.
+        release_address_on_market_fixed_for_seat(fixed, dynamic, trader_seat_index);
+        Ok(())
+    }
+
+    /// Sets or clears the cosigner in the SeatConfig of a seat. Setting a
+    /// config on a seat that does not have one yet uses a free block, so the
+    /// caller is responsible for making sure one is available. Points are
+    /// kept, and on markets that award points clearing keeps the block so the
+    /// seat goes on earning them.
+    pub fn set_seat_config(
+        &mut self,
+        trader_index: DataIndex,
+        seat_config_opt: Option<SeatConfig>,
+    ) -> ProgramResult {
+        require!(
+            is_not_nil!(trader_index),
+            ManifestError::InvalidSeatConfig,
+            "No seat initialized",
+        )?;
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let existing_index_opt: Option<DataIndex> = get_helper_seat(dynamic, trader_index)
+            .get_value()
+            .get_seat_config_index();
+
+        match (seat_config_opt, existing_index_opt) {
+            (Some(seat_config), Some(existing_index)) => {
+                let existing: &mut SeatConfig =
+                    get_mut_helper::<RBNode<SeatConfig>>(dynamic, existing_index).get_mut_value();
+                existing.cosigner = seat_config.cosigner;
+                existing.cosign_notional_threshold = seat_config.cosign_notional_threshold;
+            }
+            (None, Some(existing_index)) if fixed.awards_points() => {
+                let existing: &mut SeatConfig =
+                    get_mut_helper::<RBNode<SeatConfig>>(dynamic, existing_index).get_mut_value();
+                existing.cosigner = Pubkey::default();
+                existing.cosign_notional_threshold = QuoteAtoms::ZERO;
+            }
+            (Some(seat_config), None) => {
+                let free_address: DataIndex =
+                    get_free_address_on_market_fixed_for_seat(fixed, dynamic);
+                // The block is not part of any tree, so clear the free list
+                // pointer it still carries.
+                let start: usize = free_address as usize;
+                dynamic[start..start + MARKET_BLOCK_SIZE].fill(0);
+                let node: &mut RBNode<SeatConfig> =
+                    get_mut_helper::<RBNode<SeatConfig>>(dynamic, free_address);
+                *node.get_mut_value() = seat_config;
+                node.set_payload_type(MarketDataTreeNodeType::SeatConfig as u8);
+                get_mut_helper_seat(dynamic, trader_index)
+                    .get_mut_value()
+                    .set_seat_config_index(Some(free_address));
+            }
+            (None, Some(existing_index)) => {
+                get_mut_helper_seat(dynamic, trader_index)
+                    .get_mut_value()
+                    .set_seat_config_index(None);
+                release_address_on_market_fixed_for_seat(fixed, dynamic, existing_index);
+            }
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
+    /// Gives a seat a SeatVolume so that its taker volume counts toward fee
+    /// tiers. Uses a free block, so the caller is responsible for making sure
+    /// one is available. Does nothing for seats that already have one.
+    pub fn init_seat_volume(&mut self, trader_index: DataIndex) -> ProgramResult {
+        assert_already_has_seat(trader_index)?;
+        if self.get_seat_volume(trader_index).is_some() {
+            return Ok(());
+        }
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let free_address: DataIndex = get_free_address_on_market_fixed_for_seat(fixed, dynamic);
+        // The block is not part of any tree, so clear the free list pointer it
+        // still carries.
+        let start: usize = free_address as usize;
+        dynamic[start..start + MARKET_BLOCK_SIZE].fill(0);
+        get_mut_helper::<RBNode<SeatVolume>>(dynamic, free_address)
+            .set_payload_type(MarketDataTreeNodeType::SeatVolume as u8);
+        get_mut_helper_seat(dynamic, trader_index)
+            .get_mut_value()
+            .set_seat_volume_index(Some(free_address));
+        Ok(())
+    }
+
+    /// Sets or clears the market maker protection of a seat, which needs a
+    /// SeatVolume to link it. Setting it on a seat that does not have one yet
+    /// uses a free block, so the caller is responsible for making sure one is
+    /// available. Updating the limits restarts the window.
+    pub fn set_seat_mmp(
+        &mut self,
+        trader_index: DataIndex,
+        seat_mmp_opt: Option<SeatMmp>,
+    ) -> ProgramResult {
+        assert_already_has_seat(trader_index)?;
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let Some(seat_volume_index) = get_helper_seat(dynamic, trader_index)
+            .get_value()
+            .get_seat_volume_index()
+        else {
+            require!(
+                seat_mmp_opt.is_none(),
+                ManifestError::InvalidMmp,
+                "Seat has no volume block to link the MMP",
+            )?;
+            return Ok(());
+        };
+        let existing_index_opt: Option<DataIndex> =
+            get_helper::<RBNode<SeatVolume>>(dynamic, seat_volume_index)
+                .get_value()
+                .get_seat_mmp_index();
 
-        // Put back seat on free list.
-        release_address_on_market_fixed_for_seat(fixed, dynamic, trader_seat_index);
+        match (seat_mmp_opt, existing_index_opt) {
+            (Some(seat_mmp), Some(existing_index)) => {
+                get_mut_helper::<RBNode<SeatMmp>>(dynamic, existing_index)
+                    .get_mut_value()
+                    .update_limits(&seat_mmp);
+            }
+            (Some(seat_mmp), None) => {
+                let free_address: DataIndex =
+                    get_free_address_on_market_fixed_for_seat(fixed, dynamic);
+                // The block is not part of any tree, so clear the free list
+                // pointer it still carries.
+                let start: usize = free_address as usize;
+                dynamic[start..start + MARKET_BLOCK_SIZE].fill(0);
+                let node: &mut RBNode<SeatMmp> =
+                    get_mut_helper::<RBNode<SeatMmp>>(dynamic, free_address);
+                *node.get_mut_value() = seat_mmp;
+                node.set_payload_type(MarketDataTreeNodeType::SeatMmp as u8);
+                get_mut_helper::<RBNode<SeatVolume>>(dynamic, seat_volume_index)
+                    .get_mut_value()
+                    .set_seat_mmp_index(Some(free_address));
+            }
+            (None, Some(existing_index)) => {
+                get_mut_helper::<RBNode<SeatVolume>>(dynamic, seat_volume_index)
+                    .get_mut_value()
+                    .set_seat_mmp_index(None);
+                release_address_on_market_fixed_for_seat(fixed, dynamic, existing_index);
+            }
+            (None, None) => {}
+        }
         Ok(())
     }
 
+    /// Takes the seat's points of the latest finished epoch that were not
+    /// claimed yet, as (epoch, points). Seats without a SeatConfig get one so
+    /// they start earning points, which uses a free block.
+    pub fn claim_points(
+        &mut self,
+        trader_index: DataIndex,
+        now_slot: u32,
+    ) -> Result<Option<(u32, u64)>, ProgramError> {
+        require!(
+            self.borrow_market().fixed.awards_points(),
+            ManifestError::InvalidSeatConfig,
+            "Market does not award points",
+        )?;
+        if self.get_seat_config(trader_index).is_none() {
+            self.set_seat_config(trader_index, Some(SeatConfig::default()))?;
+        }
+        let DynamicAccount { dynamic, .. } = self.borrow_mut();
+        let seat_config_index: DataIndex = get_helper_seat(dynamic, trader_index)
+            .get_value()
+            .get_seat_config_index()
+            .unwrap();
+        Ok(
+            get_mut_helper::<RBNode<SeatConfig>>(dynamic, seat_config_index)
+                .get_mut_value()
+                .claim_points(now_slot / POINTS_EPOCH_SLOTS),
+        )
+    }
+
     pub fn deposit(
         &mut self,
         trader_index: DataIndex,
@@ -938,6 +2268,339 @@ impl<
         Ok(())
     }
 
+    /// Pays out the share of the vault the trader is owed on a market in
+    /// recovery and zeroes what that share was for. A seat is owed its
+    /// withdrawable balance of the token plus what its non global orders lock
+    /// of it, and gets vault_atoms times that over what all seats are owed,
+    /// never more than it is owed. Blocks are scanned by payload type instead
+    /// of walking the trees, which may be damaged. Later withdraws get the
+    /// same share since both the vault and the total shrink by the payout.
+    #[cfg(not(feature = "certora"))]
+    pub fn recovery_withdraw(
+        &mut self,
+        trader: &Pubkey,
+        is_base: bool,
+        vault_atoms: u64,
+    ) -> Result<u64, ProgramError> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            fixed.is_in_recovery(),
+            ManifestError::InvalidRecovery,
+            "Market is not in recovery",
+        )?;
+        let block_indices = || (0..fixed.num_bytes_allocated).step_by(MARKET_BLOCK_SIZE);
+        let is_payload =
+            |dynamic: &[u8], index: DataIndex, payload_type: MarketDataTreeNodeType| {
+                get_helper::<RBNode<ClaimedSeat>>(dynamic, index).get_payload_type()
+                    == payload_type as u8
+            };
+
+        let trader_index: DataIndex = block_indices()
+            .filter(|index| is_payload(dynamic, *index, MarketDataTreeNodeType::ClaimedSeat))
+            .find(|index| get_helper_seat(dynamic, *index).get_value().trader == *trader)
+            .unwrap_or(NIL);
+        require!(
+            is_not_nil!(trader_index),
+            ManifestError::InvalidRecovery,
+            "No seat for {}",
+            trader,
+        )?;
+
+        // Running sums over a single scan. The trader's orders are zeroed as
+        // they are counted since the payout only depends on the sums.
+        let mut total_owed: u128 = 0;
+        let mut trader_owed: u128 = 0;
+        for index in block_indices() {
+            if is_payload(dynamic, index, MarketDataTreeNodeType::ClaimedSeat) {
+                let seat: &ClaimedSeat = get_helper_seat(dynamic, index).get_value();
+                let atoms: u64 = if is_base {
+                    seat.base_withdrawable_balance.as_u64()
+                } else {
+                    seat.quote_withdrawable_balance.as_u64()
+                };
+                total_owed += atoms as u128;
+                if index == trader_index {
+                    trader_owed += atoms as u128;
+                }
+            } else if is_payload(dynamic, index, MarketDataTreeNodeType::RestingOrder) {
+                let order: &mut RestingOrder = get_mut_helper_order(dynamic, index).get_mut_value();
+                // Bids lock quote and asks lock base. Global orders lock
+                // nothing in the vault.
+                if order.is_global() || order.get_is_bid() == is_base {
+                    continue;
+                }
+                let atoms: u64 = if is_base {
+                    order.get_num_base_atoms().as_u64()
+                } else {
+                    order
+                        .get_num_base_atoms()
+                        .checked_mul(order.get_price(), true)?
+                        .as_u64()
+                };
+                total_owed += atoms as u128;
+                if order.get_trader_index() == trader_index {
+                    trader_owed += atoms as u128;
+                    order.reduce(order.get_num_base_atoms())?;
+                }
+            }
+        }
+        let payout: u64 = (trader_owed * vault_atoms as u128)
+            .checked_div(total_owed)
+            .unwrap_or(0)
+            .min(trader_owed) as u64;
+
+        let seat: &mut ClaimedSeat = get_mut_helper_seat(dynamic, trader_index).get_mut_value();
+        if is_base {
+            seat.base_withdrawable_balance = BaseAtoms::ZERO;
+        } else {
+            seat.quote_withdrawable_balance = QuoteAtoms::ZERO;
+        }
+        Ok(payout)
+    }
+
+    /// Settle a block trade at a price quoted off the book. Both sides trade
+    /// from their seat balances and the book is not touched. Quote atoms are
+    /// rounded in favor of the maker. The fill goes through the same price
+    /// band, minimum order size and market maker protection as book fills.
+    /// It is not charged fees, so markets with MarketFees or a taker delay
+    /// only fill on the book.
+    pub fn fill_rfq(
+        &mut self,
+        market: Pubkey,
+        maker_index: DataIndex,
+        taker_index: DataIndex,
+        base_atoms: BaseAtoms,
+        price: QuoteAtomsPerBaseAtom,
+        taker_is_buy: bool,
+    ) -> Result<QuoteAtoms, ProgramError> {
+        require!(
+            is_not_nil!(maker_index) && is_not_nil!(taker_index),
+            ManifestError::InvalidRfq,
+            "Maker and taker need seats",
+        )?;
+        require!(
+            maker_index != taker_index,
+            ManifestError::InvalidRfq,
+            "Maker and taker cannot be the same seat",
+        )?;
+        require!(
+            self.get_market_fees().is_none(),
+            ManifestError::InvalidRfq,
+            "Market charges fees, fill on the book",
+        )?;
+        require!(
+            self.borrow_market().fixed.get_taker_delay_slots() == 0,
+            ManifestError::InvalidSwapCommitment,
+            "Market has a taker delay, fill on the book",
+        )?;
+        self.verify_min_order_size(base_atoms, price)?;
+        if let Some((price_band, reference)) = self.get_price_band_reference() {
+            price_band.verify(price, reference)?;
+        }
+        let now_slot: u32 = get_now_slot();
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+
+        let quote_atoms: QuoteAtoms = price.checked_quote_for_base(base_atoms, taker_is_buy)?;
+        record_rounding(fixed, price, base_atoms, quote_atoms, taker_is_buy, false)?;
+        let (buyer_index, seller_index) = if taker_is_buy {
+            (taker_index, maker_index)
+        } else {
+            (maker_index, taker_index)
+        };
+        update_balance(
+            fixed,
+            dynamic,
+            buyer_index,
+            false,
+            false,
+            quote_atoms.into(),
+        )?;
+        update_balance(fixed, dynamic, seller_index, true, false, base_atoms.into())?;
+        update_balance(fixed, dynamic, buyer_index, true, true, base_atoms.into())?;
+        update_balance(
+            fixed,
+            dynamic,
+            seller_index,
+            false,
+            true,
+            quote_atoms.into(),
+        )?;
+
+        record_volume_by_trader_index(dynamic, maker_index, quote_atoms);
+        record_volume_by_trader_index(dynamic, taker_index, quote_atoms);
+        fixed.quote_volume = fixed.quote_volume.wrapping_add(quote_atoms);
+        record_market_stats(fixed, dynamic, base_atoms, quote_atoms);
+        if fixed.awards_points() {
+            record_fill_points(
+                fixed,
+                dynamic,
+                maker_index,
+                taker_index,
+                quote_atoms,
+                now_slot,
+            );
+        }
+
+        // There is no resting order, so maker and taker share one sequence
+        // number.
+        let sequence_number: u64 = fixed.order_sequence_number;
+        fixed.order_sequence_number = sequence_number.wrapping_add(1);
+
+        let maker: Pubkey = get_helper_seat(dynamic, maker_index).get_value().trader;
+        let taker: Pubkey = get_helper_seat(dynamic, taker_index).get_value().trader;
+        #[cfg(not(feature = "certora"))]
+        if has_fill_records(fixed, dynamic) {
+            record_fill(
+                fixed,
+                dynamic,
+                get_fill_record_digest(
+                    &maker,
+                    &taker,
+                    base_atoms,
+                    quote_atoms,
+                    taker_is_buy,
+                    sequence_number,
+                    sequence_number,
+                    0,
+                    false,
+                    now_slot,
+                ),
+            );
+        }
+
+        emit_stack(FillLog {
+            market,
+            maker,
+            taker,
+            base_mint: fixed.base_mint,
+            quote_mint: fixed.quote_mint,
+            base_atoms,
+            quote_atoms,
+            price,
+            maker_sequence_number: sequence_number,
+            taker_sequence_number: sequence_number,
+            taker_is_buy: PodBool::from(taker_is_buy),
+            is_maker_global: PodBool::from(false),
+            self_trade: PodBool::from(false),
+            referred: PodBool::from(false),
+            maker_tag: 0,
+            taker_tag: 0,
+            taker_fee_bps: 0,
+            _padding2: [0; 2],
+        })?;
+
+        // The quote was the maker's, so it counts toward its protection.
+        // Global orders pulled on a trip strand their gas prepayment since
+        // the global accounts are not passed.
+        #[cfg(not(feature = "certora"))]
+        if let Some(window) = record_maker_fill_for_mmp(dynamic, maker_index, quote_atoms, now_slot)
+        {
+            pull_orders_on_mmp_trip(fixed, dynamic, market, maker_index, window, &[None, None])?;
+        }
+        Ok(quote_atoms)
+    }
+
+    /// Removes the seat of a trader with no resting orders, no seat config, no
+    /// market maker protection and no seat volume and returns what it held,
+    /// so it can be kept in the SeatArchive instead.
+    /// Balances are zeroed before the block goes back on the free list.
+    pub fn archive_seat(&mut self, trader: &Pubkey) -> Result<ClaimedSeat, ProgramError> {
+        let trader_index: DataIndex = self.get_trader_index(trader);
+        require!(
+            is_not_nil!(trader_index),
+            ManifestError::InvalidSeatArchive,
+            "No seat initialized",
+        )?;
+        require!(
+            self.get_seat_config(trader_index).is_none(),
+            ManifestError::InvalidSeatArchive,
+            "Cannot archive a seat with a seat config",
+        )?;
+        require!(
+            self.get_seat_mmp(trader_index).is_none(),
+            ManifestError::InvalidSeatArchive,
+            "Cannot archive a seat with market maker protection",
+        )?;
+        // The rolling volume does not fit in the SeatArchive and would be lost
+        // when the block is freed.
+        require!(
+            self.get_seat_volume(trader_index).is_none(),
+            ManifestError::InvalidSeatArchive,
+            "Cannot archive a seat with a seat volume",
+        )?;
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+
+        for (root_index, best_index) in [
+            (fixed.bids_root_index, fixed.bids_best_index),
+            (fixed.asks_root_index, fixed.asks_best_index),
+        ] {
+            let tree: BooksideReadOnly = BooksideReadOnly::new(dynamic, root_index, best_index);
+            for (_, resting_order) in tree.iter::<RestingOrder>() {
+                require!(
+                    resting_order.get_trader_index() != trader_index,
+                    ManifestError::InvalidSeatArchive,
+                    "Cannot archive a seat with resting orders",
+                )?;
+            }
+        }
+
+        let claimed_seat: ClaimedSeat = *get_helper_seat(dynamic, trader_index).get_value();
+        update_balance(
+            fixed,
+            dynamic,
+            trader_index,
+            true,
+            false,
+            claimed_seat.base_withdrawable_balance.as_u64(),
+        )?;
+        update_balance(
+            fixed,
+            dynamic,
+            trader_index,
+            false,
+            false,
+            claimed_seat.quote_withdrawable_balance.as_u64(),
+        )?;
+        self.release_seat(trader)?;
+        Ok(claimed_seat)
+    }
+
+    /// Gives back balances and volume of an archived seat, claiming a seat
+    /// first if the trader does not have one. The caller is responsible for
+    /// making sure a free block is available.
+    pub fn restore_seat(
+        &mut self,
+        trader: &Pubkey,
+        base_atoms: BaseAtoms,
+        quote_atoms: QuoteAtoms,
+        quote_volume: QuoteAtoms,
+    ) -> ProgramResult {
+        let mut trader_index: DataIndex = self.get_trader_index(trader);
+        if trader_index == NIL {
+            self.claim_seat(trader)?;
+            trader_index = self.get_trader_index(trader);
+        }
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        update_balance(
+            fixed,
+            dynamic,
+            trader_index,
+            true,
+            true,
+            base_atoms.as_u64(),
+        )?;
+        update_balance(
+            fixed,
+            dynamic,
+            trader_index,
+            false,
+            true,
+            quote_atoms.as_u64(),
+        )?;
+        record_volume_by_trader_index(dynamic, trader_index, quote_volume);
+        Ok(())
+    }
+
     pub fn place_order_(
         &mut self,
         args: AddOrderToMarketArgs,
@@ -951,18 +2614,26 @@ impl<
     /// 2. Rest any amount of the order leftover on the book
     pub fn place_order(
         &mut self,
-        args: AddOrderToMarketArgs,
+        mut args: AddOrderToMarketArgs,
     ) -> Result<AddOrderToMarketResult, ProgramError> {
         let AddOrderToMarketArgs {
             market,
             trader_index,
             num_base_atoms,
-            price,
+            mut price,
             is_bid,
             last_valid_slot,
             order_type,
             global_trade_accounts_opts,
             current_slot,
+            fill_memo,
+            insert_hint_index: _,
+            min_fill_atoms,
+            min_taker_atoms: _,
+            tag,
+            skip_global_orders,
+            self_trade_prevention,
+            referred,
         } = args;
         assert_already_has_seat(trader_index)?;
         let now_slot: u32 = current_slot.unwrap_or_else(|| get_now_slot());
@@ -972,6 +2643,15 @@ impl<
             assert_not_already_expired(last_valid_slot, now_slot)?;
         }
 
+        // The reference is fixed for the whole order, so one order cannot walk
+        // it along as it fills.
+        let price_band_reference_opt: Option<(PriceBand, QuoteAtomsPerBaseAtom)> =
+            self.get_price_band_reference();
+        // The fee tier is fixed for the whole order too.
+        let market_fees_opt: Option<MarketFees> = self.get_taker_fill_fees(trader_index, now_slot);
+        let taker_fee_bps: u16 =
+            market_fees_opt.map_or(0, |market_fees: MarketFees| market_fees.get_taker_fee_bps());
+
         let DynamicAccount { fixed, dynamic } = self.borrow_mut();
 
         let mut current_maker_order_index: DataIndex = if is_bid {
@@ -989,10 +2669,17 @@ impl<
 
         let mut total_base_atoms_traded: BaseAtoms = BaseAtoms::ZERO;
         let mut total_quote_atoms_traded: QuoteAtoms = QuoteAtoms::ZERO;
+        let mut total_base_fee_atoms: BaseAtoms = BaseAtoms::ZERO;
+        let mut total_quote_fee_atoms: QuoteAtoms = QuoteAtoms::ZERO;
+        let mut total_referral_fee_atoms: u64 = 0;
 
         // Accumulator for batched global token transfers (only one side per order)
         let mut global_atoms_to_transfer: GlobalAtoms = GlobalAtoms::ZERO;
 
+        // Set when a maker was passed over for its minimum taker size. The
+        // remainder cannot rest then, since it would cross that maker.
+        let mut skipped_maker: bool = false;
+
         let mut remaining_base_atoms: BaseAtoms = num_base_atoms;
         while remaining_base_atoms > BaseAtoms::ZERO && is_not_nil!(current_maker_order_index) {
             let maker_order: &RestingOrder =
@@ -1023,31 +2710,144 @@ impl<
                 break;
             }
 
+            // Post only slide rests one step inside the best instead of
+            // failing. The hint was for the original price, so drop it.
+            if order_type == OrderType::PostOnlySlide {
+                let slid_price_opt: Option<QuoteAtomsPerBaseAtom> =
+                    maker_order.get_price().checked_step(!is_bid);
+                require!(
+                    slid_price_opt.is_some(),
+                    ManifestError::PostOnlyCrosses,
+                    "No price to slide to from {}",
+                    maker_order.get_price(),
+                )?;
+                price = slid_price_opt.unwrap();
+                args.price = price;
+                args.insert_hint_index = NIL;
+                break;
+            }
+
             // Got a match. First make sure we are allowed to match. We check
             // inside the matching rather than skipping the matching altogether
             // because post only orders should fail, not produce a crossed book.
             assert_can_take(order_type)?;
+            if let Some((price_band, reference)) = price_band_reference_opt {
+                price_band.verify(maker_order.get_price(), reference)?;
+            }
 
             let maker_sequence_number = maker_order.get_sequence_number();
             let maker_trader_index: DataIndex = maker_order.get_trader_index();
+            if maker_trader_index == trader_index
+                && self_trade_prevention != SelfTradePrevention::None
+            {
+                require!(
+                    self_trade_prevention != SelfTradePrevention::Reject,
+                    ManifestError::InternalCross,
+                    "Order would cross an order from the same seat",
+                )?;
+                if self_trade_prevention == SelfTradePrevention::CancelTake {
+                    remaining_base_atoms = BaseAtoms::ZERO;
+                    break;
+                }
+                let maker_base_atoms: BaseAtoms = maker_order.get_num_base_atoms();
+                if self_trade_prevention == SelfTradePrevention::CancelProvide
+                    || remaining_base_atoms >= maker_base_atoms
+                {
+                    if self_trade_prevention == SelfTradePrevention::DecrementBoth {
+                        remaining_base_atoms =
+                            remaining_base_atoms.checked_sub(maker_base_atoms)?;
+                    }
+                    let next_maker_order_index: DataIndex = get_next_candidate_match_index(
+                        fixed,
+                        dynamic,
+                        current_maker_order_index,
+                        is_bid,
+                    );
+                    remove_and_update_balances(
+                        fixed,
+                        dynamic,
+                        current_maker_order_index,
+                        global_trade_accounts_opts,
+                    )?;
+                    current_maker_order_index = next_maker_order_index;
+                    continue;
+                }
+
+                // Decrement the larger resting order by the whole remainder
+                // and return what it no longer needs locked.
+                if !maker_order.is_global() {
+                    let maker_price: QuoteAtomsPerBaseAtom = maker_order.get_price();
+                    let released_atoms: u64 = if is_bid {
+                        remaining_base_atoms.as_u64()
+                    } else {
+                        maker_price
+                            .checked_quote_for_base(maker_base_atoms, true)?
+                            .checked_sub(maker_price.checked_quote_for_base(
+                                maker_base_atoms.checked_sub(remaining_base_atoms)?,
+                                true,
+                            )?)?
+                            .as_u64()
+                    };
+                    update_balance(fixed, dynamic, trader_index, is_bid, true, released_atoms)?;
+                }
+                get_mut_helper_order(dynamic, current_maker_order_index)
+                    .get_mut_value()
+                    .reduce(remaining_base_atoms)?;
+                remaining_base_atoms = BaseAtoms::ZERO;
+                break;
+            }
+            require!(
+                !fixed.is_no_internal_cross() || maker_trader_index != trader_index,
+                ManifestError::InternalCross,
+                "Order would cross an order from the same seat",
+            )?;
+            // Icebergs only trade the tranche they show at a time.
+            let base_atoms_traded: BaseAtoms =
+                remaining_base_atoms.min(maker_order.get_visible_base_atoms());
             let did_fully_match_resting_order: bool =
-                remaining_base_atoms >= maker_order.get_num_base_atoms();
-            let base_atoms_traded: BaseAtoms = if did_fully_match_resting_order {
-                maker_order.get_num_base_atoms()
-            } else {
-                remaining_base_atoms
-            };
+                base_atoms_traded == maker_order.get_num_base_atoms();
+
+            // Skip makers that would fill less than the taker's minimum. Once
+            // the taker's remainder is itself below the minimum, no maker can
+            // fill it.
+            if base_atoms_traded < min_fill_atoms {
+                if remaining_base_atoms < min_fill_atoms {
+                    break;
+                }
+                current_maker_order_index = get_next_candidate_match_index(
+                    fixed,
+                    dynamic,
+                    current_maker_order_index,
+                    is_bid,
+                );
+                continue;
+            }
+
+            // Skip makers that do not accept a fill this small. Taking the
+            // rest of the maker is always allowed.
+            if !did_fully_match_resting_order
+                && base_atoms_traded < maker_order.get_min_taker_atoms()
+            {
+                skipped_maker = true;
+                current_maker_order_index = get_next_candidate_match_index(
+                    fixed,
+                    dynamic,
+                    current_maker_order_index,
+                    is_bid,
+                );
+                continue;
+            }
 
             let matched_price: QuoteAtomsPerBaseAtom = maker_order.get_price();
             let maker_order_type: OrderType = maker_order.get_order_type();
-            let maker_price_reverse: Result<QuoteAtomsPerBaseAtom, _> = maker_order.reverse_price();
+            let maker_price_reverse: Result<QuoteAtomsPerBaseAtom, _> =
+                maker_order.reverse_price(fixed.get_volatility_bps());
 
             // on full fill: round in favor of the taker
             // on partial fill: round in favor of the maker
-            let quote_atoms_traded: QuoteAtoms = matched_price.checked_quote_for_base(
-                base_atoms_traded,
-                is_bid != did_fully_match_resting_order,
-            )?;
+            let round_up: bool = is_bid != did_fully_match_resting_order;
+            let quote_atoms_traded: QuoteAtoms =
+                matched_price.checked_quote_for_base(base_atoms_traded, round_up)?;
 
             // If it is a global order, just in time bring the funds over, or
             // remove from the tree and continue on to the next order.
@@ -1059,6 +2859,9 @@ impl<
             let is_maker_reverse: bool = maker_order.is_reversible();
 
             let maker_reverse_spread: u16 = maker_order.get_reverse_spread();
+            let maker_min_taker_atoms: BaseAtoms = maker_order.get_min_taker_atoms();
+            let maker_visible_base_atoms: BaseAtoms = maker_order.get_visible_base_atoms();
+            let maker_tag: u32 = maker_order.get_tag();
 
             if is_global {
                 let global_trade_accounts_opt: &Option<GlobalTradeAccounts> = if is_bid {
@@ -1067,11 +2870,20 @@ impl<
                     &global_trade_accounts_opts[1]
                 };
                 // When the global account is not included, a taker order can
-                // halt here, but a possible maker order will need to crash
-                // since that would result in a crossed book.
+                // halt or pass over it here, but a possible maker order will
+                // need to crash since that would result in a crossed book.
                 if global_trade_accounts_opt.is_none() {
                     if order_type_can_rest(order_type) {
                         return Err(ManifestError::MissingGlobal.into());
+                    } else if skip_global_orders {
+                        skipped_maker = true;
+                        current_maker_order_index = get_next_candidate_match_index(
+                            fixed,
+                            dynamic,
+                            current_maker_order_index,
+                            is_bid,
+                        );
+                        continue;
                     } else {
                         break;
                     }
@@ -1111,6 +2923,15 @@ impl<
 
             total_base_atoms_traded = total_base_atoms_traded.checked_add(base_atoms_traded)?;
             total_quote_atoms_traded = total_quote_atoms_traded.checked_add(quote_atoms_traded)?;
+            record_rounding(
+                fixed,
+                matched_price,
+                base_atoms_traded,
+                quote_atoms_traded,
+                round_up,
+                did_fully_match_resting_order,
+            )?;
+            fixed.record_trade(matched_price, now_slot);
 
             // Possibly increase bonus atom maker gets from the rounding the
             // quote in their favor. They will get one less than expected when
@@ -1165,6 +2986,37 @@ impl<
                 )?;
             }
 
+            // Each side pays its fee out of what it receives, so a fill never
+            // needs more than the orders already have.
+            let (maker_gross_atoms, taker_gross_atoms): (u64, u64) = if is_bid {
+                (quote_atoms_traded.as_u64(), base_atoms_traded.as_u64())
+            } else {
+                (base_atoms_traded.as_u64(), quote_atoms_traded.as_u64())
+            };
+            let (maker_fee_atoms, taker_fee_atoms): (u64, u64) = match market_fees_opt {
+                Some(market_fees) => (
+                    market_fees.get_maker_fee_atoms(maker_gross_atoms),
+                    market_fees.get_taker_fee_atoms(taker_gross_atoms),
+                ),
+                None => (0, 0),
+            };
+            let referral_fee_atoms: u64 = market_fees_opt.map_or(0, |market_fees: MarketFees| {
+                market_fees.get_referral_fee_atoms(taker_fee_atoms)
+            });
+            total_referral_fee_atoms = total_referral_fee_atoms.saturating_add(referral_fee_atoms);
+            let maker_atoms_received: u64 = maker_gross_atoms - maker_fee_atoms;
+            if is_bid {
+                total_base_fee_atoms =
+                    total_base_fee_atoms.checked_add(BaseAtoms::new(taker_fee_atoms))?;
+                total_quote_fee_atoms =
+                    total_quote_fee_atoms.checked_add(QuoteAtoms::new(maker_fee_atoms))?;
+            } else {
+                total_base_fee_atoms =
+                    total_base_fee_atoms.checked_add(BaseAtoms::new(maker_fee_atoms))?;
+                total_quote_fee_atoms =
+                    total_quote_fee_atoms.checked_add(QuoteAtoms::new(taker_fee_atoms))?;
+            }
+
             // Increase maker from the matched amount in the trade.
             update_balance(
                 fixed,
@@ -1172,11 +3024,7 @@ impl<
                 maker_trader_index,
                 !is_bid,
                 true,
-                if is_bid {
-                    quote_atoms_traded.into()
-                } else {
-                    base_atoms_traded.into()
-                },
+                maker_atoms_received,
             )?;
             // Decrease taker
             update_balance(
@@ -1198,18 +3046,47 @@ impl<
                 trader_index,
                 is_bid,
                 true,
-                if is_bid {
-                    base_atoms_traded.into()
-                } else {
-                    quote_atoms_traded.into()
-                },
+                taker_gross_atoms - taker_fee_atoms,
             )?;
 
             // record maker & taker volume
             record_volume_by_trader_index(dynamic, maker_trader_index, quote_atoms_traded);
             record_volume_by_trader_index(dynamic, trader_index, quote_atoms_traded);
+            if maker_trader_index != trader_index {
+                record_seat_taker_volume(dynamic, trader_index, quote_atoms_traded, now_slot);
+            }
+            record_market_stats(fixed, dynamic, base_atoms_traded, quote_atoms_traded);
+            if fixed.awards_points() {
+                record_fill_points(
+                    fixed,
+                    dynamic,
+                    maker_trader_index,
+                    trader_index,
+                    quote_atoms_traded,
+                    now_slot,
+                );
+            }
+            #[cfg(not(feature = "certora"))]
+            if has_fill_records(fixed, dynamic) {
+                record_fill(
+                    fixed,
+                    dynamic,
+                    get_fill_record_digest(
+                        &maker,
+                        &taker,
+                        base_atoms_traded,
+                        quote_atoms_traded,
+                        is_bid,
+                        maker_sequence_number,
+                        this_order_sequence_number,
+                        taker_fee_bps,
+                        referred,
+                        now_slot,
+                    ),
+                );
+            }
 
-            emit_stack(FillLog {
+            let fill_log: FillLog = FillLog {
                 market,
                 maker,
                 taker,
@@ -1222,8 +3099,41 @@ impl<
                 taker_sequence_number: this_order_sequence_number,
                 taker_is_buy: PodBool::from(is_bid),
                 is_maker_global: PodBool::from(is_global),
-                _padding: [0; 14],
-            })?;
+                self_trade: PodBool::from(maker_trader_index == trader_index),
+                referred: PodBool::from(referred),
+                maker_tag,
+                taker_tag: tag,
+                taker_fee_bps,
+                _padding2: [0; 2],
+            };
+            emit_stack(fill_log)?;
+            if market_fees_opt.is_some() {
+                emit_stack(FeeLog {
+                    market,
+                    maker,
+                    taker,
+                    maker_sequence_number,
+                    taker_sequence_number: this_order_sequence_number,
+                    maker_fee_atoms,
+                    taker_fee_atoms,
+                    referral_fee_atoms,
+                    protocol_fee_atoms: taker_fee_atoms - referral_fee_atoms,
+                    taker_is_buy: PodBool::from(is_bid),
+                    _padding: [0; 7],
+                })?;
+            }
+            if fill_memo {
+                emit_fill_memo(&fill_log)?;
+            }
+
+            // A fill cancels the other order of a one cancels other pair.
+            cancel_linked_order(
+                fixed,
+                dynamic,
+                market,
+                current_maker_order_index,
+                global_trade_accounts_opts,
+            )?;
 
             if did_fully_match_resting_order {
                 // Get paid for removing a global order.
@@ -1258,10 +3168,43 @@ impl<
                 let maker_order: &mut RestingOrder =
                     get_mut_helper::<RBNode<RestingOrder>>(dynamic, current_maker_order_index)
                         .get_mut_value();
+                let is_tranche_filled: bool =
+                    maker_order.is_iceberg() && base_atoms_traded == maker_visible_base_atoms;
                 maker_order.reduce(base_atoms_traded)?;
                 #[cfg(feature = "certora")]
                 add_to_orderbook_balance(fixed, dynamic, current_maker_order_index);
-                remaining_base_atoms = BaseAtoms::ZERO;
+                if is_tranche_filled {
+                    // Show the next tranche behind the orders at its price.
+                    // Those come first unless there are none, then the
+                    // iceberg is still the best.
+                    let next_maker_order_index: DataIndex = get_next_candidate_match_index(
+                        fixed,
+                        dynamic,
+                        current_maker_order_index,
+                        is_bid,
+                    );
+                    let iceberg: RestingOrder =
+                        *get_helper_order(dynamic, current_maker_order_index).get_value();
+                    requeue_order(
+                        fixed,
+                        dynamic,
+                        current_maker_order_index,
+                        iceberg.get_num_base_atoms(),
+                        matched_price,
+                        now_slot,
+                    )?;
+                    remaining_base_atoms = remaining_base_atoms.checked_sub(base_atoms_traded)?;
+                    if is_not_nil!(next_maker_order_index)
+                        && get_helper_order(dynamic, next_maker_order_index)
+                            .get_value()
+                            .get_price()
+                            == matched_price
+                    {
+                        current_maker_order_index = next_maker_order_index;
+                    }
+                } else {
+                    remaining_base_atoms = BaseAtoms::ZERO;
+                }
             }
 
             // Place the reverse order if the maker was a reverse order type.
@@ -1269,13 +3212,16 @@ impl<
             // filling the books on partial fills, we coalesce on top of book.
             if is_maker_reverse {
                 if let Ok(price_reverse) = maker_price_reverse {
+                    // The reverse order uses what the maker received after
+                    // the maker fee.
                     let num_base_atoms_reverse: BaseAtoms = if is_bid {
                         // Maker is now buying with the exact number of quote atoms.
                         // Do not round_up because there might not be enough atoms
                         // for that.
-                        price_reverse.checked_base_for_quote(quote_atoms_traded, false)?
+                        price_reverse
+                            .checked_base_for_quote(QuoteAtoms::new(maker_atoms_received), false)?
                     } else {
-                        base_atoms_traded
+                        BaseAtoms::new(maker_atoms_received)
                     };
 
                     let mut coalesced: bool = false;
@@ -1349,7 +3295,7 @@ impl<
                                         order_to_coalesce_into.get_price(),
                                         order_to_coalesce_into.get_num_base_atoms(),
                                         num_base_atoms_reverse,
-                                        quote_atoms_traded,
+                                        QuoteAtoms::new(maker_atoms_received),
                                     )?;
                                 order_to_coalesce_into.increase(base_atoms_to_add)?;
                                 reverse_quote_atoms_debited = quote_atoms_to_debit;
@@ -1399,12 +3345,16 @@ impl<
                             maker_order_type,
                         )?;
                         new_reverse_resting_order.set_reverse_spread(maker_reverse_spread);
+                        new_reverse_resting_order.set_min_taker_atoms(maker_min_taker_atoms);
+                        new_reverse_resting_order.set_tag(maker_tag);
+                        new_reverse_resting_order.set_placed_slot(now_slot);
                         insert_order_into_tree(
                             is_bid,
                             fixed,
                             dynamic,
                             free_address,
                             &new_reverse_resting_order,
+                            NIL,
                         );
                         set_payload_order(dynamic, free_address);
                     }
@@ -1424,9 +3374,37 @@ impl<
                 }
             }
 
+            // Pull the maker's orders once its fills trip its market maker
+            // protection. The next candidate may have been one of them, so
+            // start over from the best of the book.
+            #[cfg(not(feature = "certora"))]
+            if maker_trader_index != trader_index {
+                if let Some(window) = record_maker_fill_for_mmp(
+                    dynamic,
+                    maker_trader_index,
+                    quote_atoms_traded,
+                    now_slot,
+                ) {
+                    pull_orders_on_mmp_trip(
+                        fixed,
+                        dynamic,
+                        market,
+                        maker_trader_index,
+                        window,
+                        global_trade_accounts_opts,
+                    )?;
+                    current_maker_order_index = if is_bid {
+                        fixed.asks_best_index
+                    } else {
+                        fixed.bids_best_index
+                    };
+                }
+            }
+
             // Stop if the last resting order did not fully match since that
-            // means the taker was exhausted.
-            if !did_fully_match_resting_order {
+            // means the taker was exhausted, unless it was an iceberg that
+            // only filled the tranche it showed.
+            if !did_fully_match_resting_order && remaining_base_atoms == BaseAtoms::ZERO {
                 break;
             }
         }
@@ -1447,26 +3425,39 @@ impl<
         // Record volume on market
         fixed.quote_volume = fixed.quote_volume.wrapping_add(total_quote_atoms_traded);
 
+        if market_fees_opt.is_some()
+            && (total_base_fee_atoms > BaseAtoms::ZERO || total_quote_fee_atoms > QuoteAtoms::ZERO)
+        {
+            get_mut_helper::<RBNode<MarketFees>>(dynamic, fixed.get_market_fees_index())
+                .get_mut_value()
+                .accrue(total_base_fee_atoms, total_quote_fee_atoms)?;
+        }
+
         // If there is nothing left to rest, then return before resting.
         if !order_type_can_rest(order_type)
             || remaining_base_atoms == BaseAtoms::ZERO
             || price == QuoteAtomsPerBaseAtom::ZERO
+            || skipped_maker
         {
             return Ok(AddOrderToMarketResult {
                 order_sequence_number: this_order_sequence_number,
                 order_index: NIL,
                 base_atoms_traded: total_base_atoms_traded,
                 quote_atoms_traded: total_quote_atoms_traded,
+                referral_fee_atoms: total_referral_fee_atoms,
             });
         }
 
-        self.rest_remaining(
-            args,
-            remaining_base_atoms,
-            this_order_sequence_number,
-            total_base_atoms_traded,
-            total_quote_atoms_traded,
-        )
+        Ok(AddOrderToMarketResult {
+            referral_fee_atoms: total_referral_fee_atoms,
+            ..self.rest_remaining(
+                args,
+                remaining_base_atoms,
+                this_order_sequence_number,
+                total_base_atoms_traded,
+                total_quote_atoms_traded,
+            )?
+        })
     }
 
     /// Rest the remaining order onto the market in a RestingOrder.
@@ -1503,6 +3494,10 @@ impl<
             last_valid_slot,
             order_type,
             global_trade_accounts_opts,
+            current_slot,
+            insert_hint_index,
+            min_taker_atoms,
+            tag,
             ..
         } = args;
         let DynamicAccount { fixed, dynamic } = self.borrow_mut();
@@ -1531,6 +3526,9 @@ impl<
         if order_type.is_reversible() {
             resting_order.set_reverse_spread(last_valid_slot as u16);
         }
+        resting_order.set_min_taker_atoms(min_taker_atoms);
+        resting_order.set_tag(tag);
+        resting_order.set_placed_slot(current_slot.unwrap_or_else(get_now_slot));
 
         if resting_order.is_global() {
             let global_trade_account_opt = &global_trade_accounts_opts[if is_bid { 1 } else { 0 }];
@@ -1556,7 +3554,14 @@ impl<
                 },
             )?;
         }
-        insert_order_into_tree(is_bid, fixed, dynamic, free_address, &resting_order);
+        insert_order_into_tree(
+            is_bid,
+            fixed,
+            dynamic,
+            free_address,
+            &resting_order,
+            insert_hint_index,
+        );
 
         set_payload_order(dynamic, free_address);
 
@@ -1565,6 +3570,7 @@ impl<
             order_index: free_address,
             base_atoms_traded: total_base_atoms_traded,
             quote_atoms_traded: total_quote_atoms_traded,
+            referral_fee_atoms: 0,
         })
     }
 
@@ -1575,62 +3581,10 @@ impl<
         order_sequence_number: u64,
         global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
     ) -> ProgramResult {
-        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
-
-        let mut index_to_remove: DataIndex = NIL;
-
-        // One iteration to find the index to cancel in the ask side.
-        let tree: BooksideReadOnly =
-            BooksideReadOnly::new(dynamic, fixed.asks_root_index, fixed.asks_best_index);
-        for (index, resting_order) in tree.iter::<RestingOrder>() {
-            if resting_order.get_sequence_number() == order_sequence_number {
-                require!(
-                    resting_order.get_trader_index() == trader_index,
-                    ManifestError::InvalidCancel,
-                    "Cannot cancel for another trader",
-                )?;
-                require!(
-                    index_to_remove == NIL,
-                    ManifestError::InvalidCancel,
-                    "Book is broken, matched multiple orders",
-                )?;
-                index_to_remove = index;
-            }
-        }
-
-        // Second iteration to find the index to cancel in the bid side.
-        let tree: BooksideReadOnly =
-            BooksideReadOnly::new(dynamic, fixed.bids_root_index, fixed.bids_best_index);
-        for (index, resting_order) in tree.iter::<RestingOrder>() {
-            if resting_order.get_sequence_number() == order_sequence_number {
-                require!(
-                    resting_order.get_trader_index() == trader_index,
-                    ManifestError::InvalidCancel,
-                    "Cannot cancel for another trader",
-                )?;
-                require!(
-                    index_to_remove == NIL,
-                    ManifestError::InvalidCancel,
-                    "Book is broken, matched multiple orders",
-                )?;
-                index_to_remove = index;
-            }
-        }
-
-        if is_not_nil!(index_to_remove) {
-            // Cancel order by index will update balances.
-            self.cancel_order_by_index(index_to_remove, global_trade_accounts_opts)?;
-            return Ok(());
-        }
-
-        // Do not fail silently.
-        require!(
-            false,
-            ManifestError::InvalidCancel,
-            "Unable to find the order for cancel. Sequence number: {}",
-            order_sequence_number
-        )?;
-        Ok(())
+        let index_to_remove: DataIndex =
+            self.get_order_index_to_cancel(trader_index, order_sequence_number)?;
+        // Cancel order by index will update balances.
+        self.cancel_order_by_index(index_to_remove, global_trade_accounts_opts)
     }
 
     #[cfg_attr(feature = "certora", cvt_hook_end(cancel_order_by_index_was_called()))]
@@ -1682,6 +3636,152 @@ impl<
 
         Ok(())
     }
+
+    /// Links two resting orders so that a fill on either cancels the other.
+    pub fn link_orders(&mut self, first_index: DataIndex, second_index: DataIndex) {
+        let DynamicAccount { dynamic, .. } = self.borrow_mut();
+        get_mut_helper_order(dynamic, first_index)
+            .get_mut_value()
+            .set_linked_index(second_index);
+        get_mut_helper_order(dynamic, second_index)
+            .get_mut_value()
+            .set_linked_index(first_index);
+    }
+
+    /// Expires a resting order after a unix timestamp instead of a slot.
+    pub fn set_order_last_valid_unix_timestamp(
+        &mut self,
+        order_index: DataIndex,
+        last_valid_unix_timestamp: u32,
+    ) {
+        let DynamicAccount { dynamic, .. } = self.borrow_mut();
+        get_mut_helper_order(dynamic, order_index)
+            .get_mut_value()
+            .set_last_valid_unix_timestamp(last_valid_unix_timestamp);
+    }
+
+    /// Changes the size and price of a resting order without taking its block
+    /// off the book. Reducing the size at the same price keeps the order's
+    /// place in the queue. A new price or a larger size puts it behind the
+    /// orders already at that price with a new sequence number, and the new
+    /// price cannot cross the book. Global orders can only be reduced. Returns
+    /// the sequence number of the order afterwards.
+    #[cfg(not(feature = "certora"))]
+    pub fn replace_order_by_index(
+        &mut self,
+        order_index: DataIndex,
+        num_base_atoms: BaseAtoms,
+        price: QuoteAtomsPerBaseAtom,
+        now_slot: u32,
+    ) -> Result<u64, ProgramError> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let resting_order: RestingOrder = *get_helper_order(dynamic, order_index).get_value();
+        let trader_index: DataIndex = resting_order.get_trader_index();
+        let is_bid: bool = resting_order.get_is_bid();
+        let locked_atoms = |num_base_atoms: BaseAtoms,
+                            price: QuoteAtomsPerBaseAtom|
+         -> Result<u64, ProgramError> {
+            Ok(if is_bid {
+                num_base_atoms.checked_mul(price, true)?.as_u64()
+            } else {
+                num_base_atoms.as_u64()
+            })
+        };
+
+        if price == resting_order.get_price()
+            && num_base_atoms <= resting_order.get_num_base_atoms()
+        {
+            if !resting_order.is_global() {
+                let released_atoms: u64 = locked_atoms(
+                    resting_order.get_num_base_atoms(),
+                    resting_order.get_price(),
+                )? - locked_atoms(num_base_atoms, price)?;
+                update_balance(fixed, dynamic, trader_index, !is_bid, true, released_atoms)?;
+            }
+            get_mut_helper_order(dynamic, order_index)
+                .get_mut_value()
+                .reduce(
+                    resting_order
+                        .get_num_base_atoms()
+                        .checked_sub(num_base_atoms)?,
+                )?;
+            return Ok(resting_order.get_sequence_number());
+        }
+
+        require!(
+            !resting_order.is_global(),
+            ManifestError::InvalidCancel,
+            "Global orders can only be reduced",
+        )?;
+        let best_other_index: DataIndex = if is_bid {
+            fixed.asks_best_index
+        } else {
+            fixed.bids_best_index
+        };
+        if is_not_nil!(best_other_index) {
+            let best_other_price: QuoteAtomsPerBaseAtom =
+                get_helper_order(dynamic, best_other_index)
+                    .get_value()
+                    .get_price();
+            require!(
+                if is_bid {
+                    price < best_other_price
+                } else {
+                    price > best_other_price
+                },
+                ManifestError::PostOnlyCrosses,
+                "Replaced order at {} would cross {}",
+                price,
+                best_other_price,
+            )?;
+        }
+
+        update_balance(
+            fixed,
+            dynamic,
+            trader_index,
+            !is_bid,
+            true,
+            locked_atoms(
+                resting_order.get_num_base_atoms(),
+                resting_order.get_price(),
+            )?,
+        )?;
+        update_balance(
+            fixed,
+            dynamic,
+            trader_index,
+            !is_bid,
+            false,
+            locked_atoms(num_base_atoms, price)?,
+        )?;
+
+        requeue_order(fixed, dynamic, order_index, num_base_atoms, price, now_slot)
+    }
+}
+
+/// Puts the order at order_index back on the book with a new size and price
+/// behind the orders already at that price, under a new sequence number that
+/// is returned. The order keeps its block, so links to it stay valid.
+fn requeue_order(
+    fixed: &mut MarketFixed,
+    dynamic: &mut [u8],
+    order_index: DataIndex,
+    num_base_atoms: BaseAtoms,
+    price: QuoteAtomsPerBaseAtom,
+    now_slot: u32,
+) -> Result<u64, ProgramError> {
+    let resting_order: RestingOrder = *get_helper_order(dynamic, order_index).get_value();
+    let is_bid: bool = resting_order.get_is_bid();
+    let order_sequence_number: u64 = fixed.order_sequence_number;
+    fixed.order_sequence_number = order_sequence_number.wrapping_add(1);
+    let requeued_order: RestingOrder =
+        resting_order.requeued(num_base_atoms, price, order_sequence_number, now_slot);
+
+    remove_order_from_tree(fixed, dynamic, order_index, is_bid)?;
+    insert_order_into_tree(is_bid, fixed, dynamic, order_index, &requeued_order, NIL);
+    set_payload_order(dynamic, order_index);
+    Ok(order_sequence_number)
 }
 
 fn set_payload_order(dynamic: &mut [u8], free_address: DataIndex) {
@@ -1770,6 +3870,16 @@ fn remove_order_from_tree_and_free(
     order_index: DataIndex,
     is_bids: bool,
 ) -> ProgramResult {
+    // Unlink the other order of a one cancels other pair, so no link points
+    // at a free block.
+    let linked_index: DataIndex = get_helper_order(dynamic, order_index)
+        .get_value()
+        .get_linked_index();
+    if is_not_nil!(linked_index) {
+        get_mut_helper_order(dynamic, linked_index)
+            .get_mut_value()
+            .set_linked_index(NIL);
+    }
     remove_order_from_tree(fixed, dynamic, order_index, is_bids)?;
     // Separate release functions because certora needs that.
     if is_bids {
@@ -1854,6 +3964,34 @@ pub fn update_balance(
     Ok(())
 }
 
+/// Adds the atom a fill rounded to one side, if its quote was not a whole
+/// number of atoms. quote_atoms is the fill's quote rounded up when round_up.
+fn record_rounding(
+    fixed: &mut MarketFixed,
+    price: QuoteAtomsPerBaseAtom,
+    base_atoms: BaseAtoms,
+    quote_atoms: QuoteAtoms,
+    round_up: bool,
+    in_favor_of_taker: bool,
+) -> ProgramResult {
+    let other_quote_atoms: QuoteAtoms = price.checked_quote_for_base(base_atoms, !round_up)?;
+    let rounding_atoms: QuoteAtoms = if round_up {
+        quote_atoms.checked_sub(other_quote_atoms)?
+    } else {
+        other_quote_atoms.checked_sub(quote_atoms)?
+    };
+    if in_favor_of_taker {
+        fixed.taker_rounding_quote_atoms = fixed
+            .taker_rounding_quote_atoms
+            .wrapping_add(rounding_atoms);
+    } else {
+        fixed.maker_rounding_quote_atoms = fixed
+            .maker_rounding_quote_atoms
+            .wrapping_add(rounding_atoms);
+    }
+    Ok(())
+}
+
 fn record_volume_by_trader_index(
     dynamic: &mut [u8],
     trader_index: DataIndex,
@@ -1863,6 +4001,244 @@ fn record_volume_by_trader_index(
     claimed_seat.quote_volume = claimed_seat.quote_volume.wrapping_add(amount_atoms);
 }
 
+/// Adds to the rolling taker volume of a seat, if it has a SeatVolume. Self
+/// trades are left out by the caller so fee tiers cannot be farmed.
+fn record_seat_taker_volume(
+    dynamic: &mut [u8],
+    trader_index: DataIndex,
+    quote_atoms: QuoteAtoms,
+    now_slot: u32,
+) {
+    let Some(seat_volume_index) = get_helper_seat(dynamic, trader_index)
+        .get_value()
+        .get_seat_volume_index()
+    else {
+        return;
+    };
+    get_mut_helper::<RBNode<SeatVolume>>(dynamic, seat_volume_index)
+        .get_mut_value()
+        .add(quote_atoms, now_slot);
+}
+
+fn get_seat_mmp_index(dynamic: &[u8], trader_index: DataIndex) -> Option<DataIndex> {
+    let seat_volume_index: DataIndex = get_helper_seat(dynamic, trader_index)
+        .get_value()
+        .get_seat_volume_index()?;
+    get_helper::<RBNode<SeatVolume>>(dynamic, seat_volume_index)
+        .get_value()
+        .get_seat_mmp_index()
+}
+
+/// Counts a fill against the market maker protection of the maker, if it has
+/// one. Returns the window when the fill trips it. Self trades are left out
+/// by the caller.
+#[cfg(not(feature = "certora"))]
+fn record_maker_fill_for_mmp(
+    dynamic: &mut [u8],
+    maker_index: DataIndex,
+    quote_atoms: QuoteAtoms,
+    now_slot: u32,
+) -> Option<(u32, QuoteAtoms)> {
+    let seat_mmp_index: DataIndex = get_seat_mmp_index(dynamic, maker_index)?;
+    get_mut_helper::<RBNode<SeatMmp>>(dynamic, seat_mmp_index)
+        .get_mut_value()
+        .record_fill(quote_atoms, now_slot)
+}
+
+/// Cancels every order of a maker whose market maker protection tripped and
+/// logs each like a cancel by the maker, then the trip itself.
+#[cfg(not(feature = "certora"))]
+fn pull_orders_on_mmp_trip(
+    fixed: &mut MarketFixed,
+    dynamic: &mut [u8],
+    market: Pubkey,
+    maker_index: DataIndex,
+    (window_fills, window_quote_atoms): (u32, QuoteAtoms),
+    global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+) -> ProgramResult {
+    let mut order_indices: Vec<DataIndex> = Vec::new();
+    for (root_index, best_index) in [
+        (fixed.bids_root_index, fixed.bids_best_index),
+        (fixed.asks_root_index, fixed.asks_best_index),
+    ] {
+        let tree: BooksideReadOnly = BooksideReadOnly::new(dynamic, root_index, best_index);
+        for (index, resting_order) in tree.iter::<RestingOrder>() {
+            if resting_order.get_trader_index() == maker_index {
+                order_indices.push(index);
+            }
+        }
+    }
+
+    let trader: Pubkey = get_helper_seat(dynamic, maker_index).get_value().trader;
+    for order_index in order_indices.iter() {
+        let order_sequence_number: u64 = get_helper_order(dynamic, *order_index)
+            .get_value()
+            .get_sequence_number();
+        remove_and_update_balances(fixed, dynamic, *order_index, global_trade_accounts_opts)?;
+        emit_stack(CancelOrderLog {
+            market,
+            trader,
+            order_sequence_number,
+        })?;
+    }
+    emit_stack(MmpTriggeredLog {
+        market,
+        trader,
+        window_fills,
+        num_orders_pulled: order_indices.len() as u32,
+        window_quote_atoms,
+    })?;
+    Ok(())
+}
+
+/// Adds a fill to the MarketStats of markets that keep them.
+#[cfg(not(feature = "certora"))]
+fn record_market_stats(
+    fixed: &MarketFixed,
+    dynamic: &mut [u8],
+    base_atoms: BaseAtoms,
+    quote_atoms: QuoteAtoms,
+) {
+    if !fixed.has_volume_stats() {
+        return;
+    }
+    get_mut_helper::<RBNode<MarketStats>>(dynamic, fixed.get_market_stats_index())
+        .get_mut_value()
+        .record_fill(base_atoms, quote_atoms);
+}
+// Formal verification does not model the volume stats.
+#[cfg(feature = "certora")]
+fn record_market_stats(
+    _fixed: &MarketFixed,
+    _dynamic: &mut [u8],
+    _base_atoms: BaseAtoms,
+    _quote_atoms: QuoteAtoms,
+) {
+}
+
+/// Whether the extension of the market has MARKET_EXTENSION_FLAG_FILL_RECORDS.
+#[cfg(not(feature = "certora"))]
+fn has_fill_records(fixed: &MarketFixed, dynamic: &[u8]) -> bool {
+    fixed.has_extension()
+        && get_helper::<RBNode<MarketExtension>>(dynamic, fixed.get_market_extension_index())
+            .get_value()
+            .has_fill_records()
+}
+
+/// Writes the digest of a fill over the oldest one in the FillRecords. The
+/// caller checks that the market keeps them.
+#[cfg(not(feature = "certora"))]
+fn record_fill(fixed: &MarketFixed, dynamic: &mut [u8], digest: FillRecordDigest) {
+    let position: u32 =
+        get_mut_helper::<RBNode<MarketExtension>>(dynamic, fixed.get_market_extension_index())
+            .get_mut_value()
+            .next_fill_record();
+    let block_index: DataIndex = fixed.get_fill_records_index()
+        + position / FILL_RECORDS_PER_BLOCK as u32 * MARKET_BLOCK_SIZE as DataIndex;
+    get_mut_helper::<RBNode<FillRecords>>(dynamic, block_index)
+        .get_mut_value()
+        .set(position as usize % FILL_RECORDS_PER_BLOCK, digest);
+}
+
+/// Takes a busted fill at fill_slot back out of everything it was counted in:
+/// the seat and market volumes, the rolling taker volume, the MarketStats,
+/// the points and the market maker protection of the maker. Self trades and
+/// RFQ fills were left out of the same ones when they were recorded.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+fn remove_fill(
+    fixed: &mut MarketFixed,
+    dynamic: &mut [u8],
+    maker_index: DataIndex,
+    taker_index: DataIndex,
+    base_atoms: BaseAtoms,
+    quote_atoms: QuoteAtoms,
+    is_rfq: bool,
+    fill_slot: u32,
+) {
+    for trader_index in [maker_index, taker_index] {
+        let claimed_seat: &mut ClaimedSeat =
+            get_mut_helper_seat(dynamic, trader_index).get_mut_value();
+        claimed_seat.quote_volume = claimed_seat.quote_volume.wrapping_sub(quote_atoms);
+    }
+    fixed.quote_volume = fixed.quote_volume.wrapping_sub(quote_atoms);
+    if fixed.has_volume_stats() {
+        get_mut_helper::<RBNode<MarketStats>>(dynamic, fixed.get_market_stats_index())
+            .get_mut_value()
+            .remove_fill(base_atoms, quote_atoms);
+    }
+    if maker_index == taker_index {
+        return;
+    }
+    if !is_rfq {
+        if let Some(seat_volume_index) = get_helper_seat(dynamic, taker_index)
+            .get_value()
+            .get_seat_volume_index()
+        {
+            get_mut_helper::<RBNode<SeatVolume>>(dynamic, seat_volume_index)
+                .get_mut_value()
+                .remove(quote_atoms, fill_slot);
+        }
+    }
+    if fixed.awards_points() {
+        let (maker_points_weight, taker_points_weight) = fixed.get_points_weights();
+        for (trader_index, weight) in [
+            (maker_index, maker_points_weight),
+            (taker_index, taker_points_weight),
+        ] {
+            if let Some(seat_config_index) = get_helper_seat(dynamic, trader_index)
+                .get_value()
+                .get_seat_config_index()
+            {
+                get_mut_helper::<RBNode<SeatConfig>>(dynamic, seat_config_index)
+                    .get_mut_value()
+                    .remove_points(
+                        quote_atoms.as_u64().saturating_mul(weight as u64),
+                        fill_slot / POINTS_EPOCH_SLOTS,
+                    );
+            }
+        }
+    }
+    if let Some(seat_mmp_index) = get_seat_mmp_index(dynamic, maker_index) {
+        get_mut_helper::<RBNode<SeatMmp>>(dynamic, seat_mmp_index)
+            .get_mut_value()
+            .remove_fill(quote_atoms, fill_slot);
+    }
+}
+
+/// Gives maker and taker the market's points for a fill, if they have a
+/// SeatConfig. Self trades earn nothing, so points cannot be farmed by
+/// trading with oneself.
+fn record_fill_points(
+    fixed: &MarketFixed,
+    dynamic: &mut [u8],
+    maker_index: DataIndex,
+    taker_index: DataIndex,
+    quote_atoms: QuoteAtoms,
+    now_slot: u32,
+) {
+    if maker_index == taker_index {
+        return;
+    }
+    let (maker_points_weight, taker_points_weight) = fixed.get_points_weights();
+    let epoch: u32 = now_slot / POINTS_EPOCH_SLOTS;
+    for (trader_index, weight) in [
+        (maker_index, maker_points_weight),
+        (taker_index, taker_points_weight),
+    ] {
+        let seat_config_index: DataIndex = match get_helper_seat(dynamic, trader_index)
+            .get_value()
+            .get_seat_config_index()
+        {
+            Some(seat_config_index) if weight != 0 => seat_config_index,
+            _ => continue,
+        };
+        get_mut_helper::<RBNode<SeatConfig>>(dynamic, seat_config_index)
+            .get_mut_value()
+            .add_points(quote_atoms.as_u64().saturating_mul(weight as u64), epoch);
+    }
+}
+
 #[inline(always)]
 fn insert_order_into_tree(
     is_bid: bool,
@@ -1870,13 +4246,14 @@ fn insert_order_into_tree(
     dynamic: &mut [u8],
     free_address: DataIndex,
     resting_order: &RestingOrder,
+    insert_hint_index: DataIndex,
 ) {
     let mut tree: Bookside = if is_bid {
         Bookside::new(dynamic, fixed.bids_root_index, fixed.bids_best_index)
     } else {
         Bookside::new(dynamic, fixed.asks_root_index, fixed.asks_best_index)
     };
-    tree.insert(free_address, *resting_order);
+    tree.insert_with_hint(free_address, *resting_order, insert_hint_index);
 
     if is_bid {
         trace!(
@@ -1926,6 +4303,35 @@ fn get_next_candidate_match_index(
     }
 }
 
+/// Cancels the order linked to order_index, if any, and logs it like a cancel
+/// by its trader.
+fn cancel_linked_order(
+    fixed: &mut MarketFixed,
+    dynamic: &mut [u8],
+    market: Pubkey,
+    order_index: DataIndex,
+    global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+) -> ProgramResult {
+    let linked_index: DataIndex = get_helper_order(dynamic, order_index)
+        .get_value()
+        .get_linked_index();
+    if !is_not_nil!(linked_index) {
+        return Ok(());
+    }
+    let linked_order: &RestingOrder = get_helper_order(dynamic, linked_index).get_value();
+    let order_sequence_number: u64 = linked_order.get_sequence_number();
+    let trader: Pubkey = get_helper_seat(dynamic, linked_order.get_trader_index())
+        .get_value()
+        .trader;
+    remove_and_update_balances(fixed, dynamic, linked_index, global_trade_accounts_opts)?;
+    emit_stack(CancelOrderLog {
+        market,
+        trader,
+        order_sequence_number,
+    })?;
+    Ok(())
+}
+
 fn remove_and_update_balances(
     fixed: &mut MarketFixed,
     dynamic: &mut [u8],
@@ -2028,6 +4434,6 @@ pub fn create_empty_market(
             executable: false,
         },
     };
-    let market_fixed: MarketFixed = MarketFixed::new_empty(&base_mint, &quote_mint, market_key);
+    let market_fixed: MarketFixed = MarketFixed::new_empty(&base_mint, &quote_mint, market_key, 0);
     market_fixed
 }
@@ -0,0 +1,214 @@
+use std::mem::{offset_of, size_of};
+
+use bytemuck::{Pod, Zeroable};
+use shank::ShankType;
+use static_assertions::const_assert_eq;
+use std::cmp::Ordering;
+
+use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::quantities::{BaseAtoms, QuoteAtoms, WrapperU64};
+
+use super::constants::{
+    FILL_RECORDS_PER_BLOCK, FILL_RECORD_BLOCKS, MARKET_EXTENSION_FLAG_FILL_RECORDS,
+    MARKET_EXTENSION_FLAG_PAUSED, MARKET_EXTENSION_SIZE, MARKET_EXTENSION_VERSION,
+};
+
+/// Reserved space for market fields that do not fit in MarketFixed, which has
+/// no bytes left. Markets created with MARKET_FLAG_EXTENSION have it in the
+/// block after all the other blocks set at creation but their FillRecords,
+/// which follow it. New fields bump MARKET_EXTENSION_VERSION. Extensions of an
+/// older version have zeroes where the newer fields are, so those fields have
+/// to read zero as unset. The reserved space is used up.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct MarketExtension {
+    /// MARKET_EXTENSION_VERSION when the market was created.
+    version: u8,
+    /// MARKET_EXTENSION_FLAG_* bits. Since version 2.
+    extension_flags: u8,
+    _padding: [u8; 6],
+    /// Key that can only pause and resume trading on the market, see
+    /// SetPaused. Default for none. Since version 2.
+    pauser: Pubkey,
+    /// Fills recorded so far in the FillRecords, wrapping. The next goes in
+    /// this modulo the number kept. Since version 3.
+    num_fill_records: u32,
+    _padding2: [u8; 4],
+    /// Fees that busts refunded after they had been claimed, see
+    /// MarketFees::refund. Claims pay them back before paying out. Since
+    /// version 4.
+    base_fees_owed_atoms: BaseAtoms,
+    quote_fees_owed_atoms: QuoteAtoms,
+}
+//  1 + // version
+//  1 + // extension_flags
+//  6 + // padding
+// 32 + // pauser
+//  4 + // num_fill_records
+//  4 + // padding2
+//  8 + // base_fees_owed_atoms
+//  8   // quote_fees_owed_atoms
+// = 64
+const_assert_eq!(size_of::<MarketExtension>(), MARKET_EXTENSION_SIZE);
+const_assert_eq!(size_of::<MarketExtension>() % 8, 0);
+// Fields keep their offsets across versions.
+const_assert_eq!(offset_of!(MarketExtension, version), 0);
+const_assert_eq!(offset_of!(MarketExtension, extension_flags), 1);
+const_assert_eq!(offset_of!(MarketExtension, pauser), 8);
+const_assert_eq!(offset_of!(MarketExtension, num_fill_records), 40);
+const_assert_eq!(offset_of!(MarketExtension, base_fees_owed_atoms), 48);
+const_assert_eq!(offset_of!(MarketExtension, quote_fees_owed_atoms), 56);
+
+impl MarketExtension {
+    pub fn new(pauser: &Pubkey, extension_flags: u8) -> Self {
+        MarketExtension {
+            version: MARKET_EXTENSION_VERSION,
+            extension_flags,
+            pauser: *pauser,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn get_pauser(&self) -> Option<&Pubkey> {
+        (self.pauser != Pubkey::default()).then_some(&self.pauser)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.extension_flags & MARKET_EXTENSION_FLAG_PAUSED != 0
+    }
+
+    pub fn has_fill_records(&self) -> bool {
+        self.extension_flags & MARKET_EXTENSION_FLAG_FILL_RECORDS != 0
+    }
+
+    /// Position in the FillRecords of the next fill, which overwrites the
+    /// oldest one once they are full.
+    pub(crate) fn next_fill_record(&mut self) -> u32 {
+        let position: u32 =
+            self.num_fill_records % (FILL_RECORD_BLOCKS * FILL_RECORDS_PER_BLOCK as u32);
+        self.num_fill_records = self.num_fill_records.wrapping_add(1);
+        position
+    }
+
+    pub fn get_base_fees_owed_atoms(&self) -> BaseAtoms {
+        self.base_fees_owed_atoms
+    }
+
+    pub fn get_quote_fees_owed_atoms(&self) -> QuoteAtoms {
+        self.quote_fees_owed_atoms
+    }
+
+    /// Adds fees a bust refunded that were no longer accrued.
+    pub(crate) fn owe_fees(
+        &mut self,
+        base_atoms: BaseAtoms,
+        quote_atoms: QuoteAtoms,
+    ) -> ProgramResult {
+        self.base_fees_owed_atoms = self.base_fees_owed_atoms.checked_add(base_atoms)?;
+        self.quote_fees_owed_atoms = self.quote_fees_owed_atoms.checked_add(quote_atoms)?;
+        Ok(())
+    }
+
+    /// Pays the fees owed in one mint out of claimed_atoms and returns what is
+    /// left for the fee recipient.
+    pub(crate) fn net_fee_claim(&mut self, is_base: bool, claimed_atoms: u64) -> u64 {
+        let owed_atoms: u64 = if is_base {
+            self.base_fees_owed_atoms.as_u64()
+        } else {
+            self.quote_fees_owed_atoms.as_u64()
+        };
+        let repaid_atoms: u64 = owed_atoms.min(claimed_atoms);
+        if is_base {
+            self.base_fees_owed_atoms = BaseAtoms::new(owed_atoms - repaid_atoms);
+        } else {
+            self.quote_fees_owed_atoms = QuoteAtoms::new(owed_atoms - repaid_atoms);
+        }
+        claimed_atoms - repaid_atoms
+    }
+
+    pub(crate) fn set_paused(&mut self, paused: bool) {
+        if paused {
+            self.extension_flags |= MARKET_EXTENSION_FLAG_PAUSED;
+        } else {
+            self.extension_flags &= !MARKET_EXTENSION_FLAG_PAUSED;
+        }
+    }
+}
+
+// Blocks need an ordering to be RBNode payloads, but the extension is never
+// in a tree.
+impl Ord for MarketExtension {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.version.cmp(&other.version)
+    }
+}
+
+impl PartialOrd for MarketExtension {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MarketExtension {
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version
+    }
+}
+
+impl Eq for MarketExtension {}
+
+impl std::fmt::Display for MarketExtension {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "version {}", self.version)
+    }
+}
+
+#[test]
+fn test_market_extension() {
+    let mut market_extension: MarketExtension = MarketExtension::new(&Pubkey::default(), 0);
+    assert_eq!(market_extension.get_version(), MARKET_EXTENSION_VERSION);
+    assert_eq!(market_extension.get_pauser(), None);
+    assert!(!market_extension.has_fill_records());
+    assert_eq!(market_extension.get_base_fees_owed_atoms(), BaseAtoms::ZERO);
+    assert_eq!(
+        market_extension.get_quote_fees_owed_atoms(),
+        QuoteAtoms::ZERO
+    );
+
+    let pauser: Pubkey = Pubkey::new_unique();
+    market_extension = MarketExtension::new(&pauser, MARKET_EXTENSION_FLAG_FILL_RECORDS);
+    assert!(market_extension.has_fill_records());
+    assert_eq!(market_extension.next_fill_record(), 0);
+    assert_eq!(market_extension.next_fill_record(), 1);
+    market_extension.num_fill_records = FILL_RECORD_BLOCKS * FILL_RECORDS_PER_BLOCK as u32;
+    assert_eq!(market_extension.next_fill_record(), 0);
+    assert_eq!(market_extension.get_pauser(), Some(&pauser));
+    assert!(!market_extension.is_paused());
+    market_extension.set_paused(true);
+    assert!(market_extension.is_paused());
+    market_extension.set_paused(false);
+    assert!(!market_extension.is_paused());
+
+    // Claims pay back what busts refunded before anything goes out.
+    market_extension
+        .owe_fees(BaseAtoms::new(10), QuoteAtoms::new(3))
+        .unwrap();
+    assert_eq!(market_extension.net_fee_claim(true, 4), 0);
+    assert_eq!(
+        market_extension.get_base_fees_owed_atoms(),
+        BaseAtoms::new(6)
+    );
+    assert_eq!(market_extension.net_fee_claim(true, 10), 4);
+    assert_eq!(market_extension.get_base_fees_owed_atoms(), BaseAtoms::ZERO);
+    assert_eq!(market_extension.net_fee_claim(false, 5), 2);
+    assert_eq!(
+        market_extension.get_quote_fees_owed_atoms(),
+        QuoteAtoms::ZERO
+    );
+    let _ = format!("{}", market_extension);
+}
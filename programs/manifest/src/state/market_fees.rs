@@ -0,0 +1,345 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use shank::ShankType;
+use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+use static_assertions::const_assert_eq;
+use std::cmp::Ordering;
+
+use crate::quantities::{BaseAtoms, QuoteAtoms, WrapperU64};
+
+use super::constants::{FEE_TIERS, MARKET_FEES_SIZE};
+
+/// Fee schedule of a market created with MARKET_FLAG_FEES and the fees it has
+/// charged so far. Makers and takers each pay their bps out of what they
+/// receive from a fill, so buyers pay in base and sellers in quote. Lives in
+/// the block after the CpiAllowlist, MinOrderSize and PriceBand the market
+/// has. The rates and recipient cannot be changed after creation. Swaps that
+/// include a referrer token account pay it referral_fee_bps of the taker fee
+/// out of the accrued fees. With a fee_tier_quote_atoms, seats get a taker fee
+/// discount from FEE_TIERS by their rolling taker volume, see SeatVolume.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct MarketFees {
+    maker_fee_bps: u16,
+    taker_fee_bps: u16,
+    /// Share of the taker fee paid to the referrer of a swap, in bps of the
+    /// fee.
+    referral_fee_bps: u16,
+    _padding: [u8; 2],
+    /// Fees charged and not yet claimed. They stay in the vaults but belong
+    /// to no seat.
+    base_fee_atoms: BaseAtoms,
+    quote_fee_atoms: QuoteAtoms,
+    /// Owner of the token accounts ClaimFees pays out to.
+    fee_recipient: Pubkey,
+    /// Rolling taker volume of the first fee tier, the others are multiples
+    /// of it. Zero is no tiers.
+    fee_tier_quote_atoms: QuoteAtoms,
+}
+// 2 + // maker_fee_bps
+// 2 + // taker_fee_bps
+// 2 + // referral_fee_bps
+// 2 + // padding
+// 8 + // base_fee_atoms
+// 8 + // quote_fee_atoms
+// 32 + // fee_recipient
+// 8 // fee_tier_quote_atoms
+// = 64
+const_assert_eq!(size_of::<MarketFees>(), MARKET_FEES_SIZE);
+const_assert_eq!(size_of::<MarketFees>() % 8, 0);
+
+impl MarketFees {
+    pub fn new(
+        maker_fee_bps: u16,
+        taker_fee_bps: u16,
+        referral_fee_bps: u16,
+        fee_recipient: Pubkey,
+        fee_tier_quote_atoms: QuoteAtoms,
+    ) -> Self {
+        MarketFees {
+            maker_fee_bps,
+            taker_fee_bps,
+            referral_fee_bps,
+            fee_recipient,
+            fee_tier_quote_atoms,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_maker_fee_bps(&self) -> u16 {
+        self.maker_fee_bps
+    }
+
+    pub fn get_taker_fee_bps(&self) -> u16 {
+        self.taker_fee_bps
+    }
+
+    pub fn get_referral_fee_bps(&self) -> u16 {
+        self.referral_fee_bps
+    }
+
+    pub fn get_base_fee_atoms(&self) -> BaseAtoms {
+        self.base_fee_atoms
+    }
+
+    pub fn get_quote_fee_atoms(&self) -> QuoteAtoms {
+        self.quote_fee_atoms
+    }
+
+    pub fn get_fee_recipient(&self) -> &Pubkey {
+        &self.fee_recipient
+    }
+
+    pub fn get_fee_tier_quote_atoms(&self) -> QuoteAtoms {
+        self.fee_tier_quote_atoms
+    }
+
+    pub fn has_fee_tiers(&self) -> bool {
+        self.fee_tier_quote_atoms > QuoteAtoms::ZERO
+    }
+
+    /// Discount in bps of the taker fee for a seat with rolling_quote_atoms of
+    /// taker volume.
+    pub fn get_taker_fee_discount_bps(&self, rolling_quote_atoms: QuoteAtoms) -> u16 {
+        if !self.has_fee_tiers() {
+            return 0;
+        }
+        FEE_TIERS
+            .iter()
+            .take_while(|(multiple, _)| {
+                rolling_quote_atoms.as_u64()
+                    >= self.fee_tier_quote_atoms.as_u64().saturating_mul(*multiple)
+            })
+            .last()
+            .map_or(0, |(_, discount_bps)| *discount_bps)
+    }
+
+    /// Same fees with taker_fee_bps charged to the taker instead, for a seat
+    /// with a discount or a fill being busted.
+    pub fn with_taker_fee_bps(&self, taker_fee_bps: u16) -> MarketFees {
+        MarketFees {
+            taker_fee_bps,
+            ..*self
+        }
+    }
+
+    /// Taker fee bps after the discount, rounded down in favor of the taker.
+    pub fn get_discounted_taker_fee_bps(&self, discount_bps: u16) -> u16 {
+        (self.taker_fee_bps as u32 * (10_000 - discount_bps.min(10_000) as u32) / 10_000) as u16
+    }
+
+    /// Fee on atoms a maker receives, rounded down in favor of the maker.
+    pub fn get_maker_fee_atoms(&self, atoms_received: u64) -> u64 {
+        (atoms_received as u128 * self.maker_fee_bps as u128 / 10_000) as u64
+    }
+
+    /// Fee on atoms a taker receives, rounded up in favor of the market.
+    pub fn get_taker_fee_atoms(&self, atoms_received: u64) -> u64 {
+        (atoms_received as u128 * self.taker_fee_bps as u128).div_ceil(10_000) as u64
+    }
+
+    /// Referrer's share of a taker fee, rounded down in favor of the market.
+    pub fn get_referral_fee_atoms(&self, taker_fee_atoms: u64) -> u64 {
+        (taker_fee_atoms as u128 * self.referral_fee_bps as u128 / 10_000) as u64
+    }
+
+    /// Atoms a taker has to receive before the taker fee to keep at least
+    /// net_atoms. Used by exact out swaps.
+    pub fn get_taker_gross_atoms(&self, net_atoms: u64) -> u64 {
+        (net_atoms as u128 * 10_000)
+            .div_ceil(10_000 - self.taker_fee_bps as u128)
+            .min(u64::MAX as u128) as u64
+    }
+
+    pub(crate) fn accrue(
+        &mut self,
+        base_atoms: BaseAtoms,
+        quote_atoms: QuoteAtoms,
+    ) -> ProgramResult {
+        self.base_fee_atoms = self.base_fee_atoms.checked_add(base_atoms)?;
+        self.quote_fee_atoms = self.quote_fee_atoms.checked_add(quote_atoms)?;
+        Ok(())
+    }
+
+    /// Gives back fees of a busted fill out of the accrued fees, as far as
+    /// they go. Returns the part that was claimed already, which the fee
+    /// recipient owes back, see MarketExtension::owe_fees.
+    pub(crate) fn refund(
+        &mut self,
+        base_atoms: BaseAtoms,
+        quote_atoms: QuoteAtoms,
+    ) -> (BaseAtoms, QuoteAtoms) {
+        let base_refunded_atoms: BaseAtoms = base_atoms.min(self.base_fee_atoms);
+        let quote_refunded_atoms: QuoteAtoms = quote_atoms.min(self.quote_fee_atoms);
+        self.base_fee_atoms -= base_refunded_atoms;
+        self.quote_fee_atoms -= quote_refunded_atoms;
+        (
+            base_atoms - base_refunded_atoms,
+            quote_atoms - quote_refunded_atoms,
+        )
+    }
+
+    /// Takes referral_atoms, the referral shares of the fills of a swap, out
+    /// of the accrued fees of one side.
+    pub(crate) fn pay_referral(&mut self, is_base: bool, referral_atoms: u64) -> ProgramResult {
+        if is_base {
+            self.base_fee_atoms = self
+                .base_fee_atoms
+                .checked_sub(BaseAtoms::new(referral_atoms))?;
+        } else {
+            self.quote_fee_atoms = self
+                .quote_fee_atoms
+                .checked_sub(QuoteAtoms::new(referral_atoms))?;
+        }
+        Ok(())
+    }
+
+    /// Zeroes the accrued fees of one side and returns how many atoms they
+    /// were.
+    pub(crate) fn claim(&mut self, is_base: bool) -> u64 {
+        if is_base {
+            std::mem::take(&mut self.base_fee_atoms).as_u64()
+        } else {
+            std::mem::take(&mut self.quote_fee_atoms).as_u64()
+        }
+    }
+}
+
+impl Ord for MarketFees {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.maker_fee_bps, self.taker_fee_bps).cmp(&(other.maker_fee_bps, other.taker_fee_bps))
+    }
+}
+
+impl PartialOrd for MarketFees {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MarketFees {
+    fn eq(&self, other: &Self) -> bool {
+        (self.maker_fee_bps, self.taker_fee_bps) == (other.maker_fee_bps, other.taker_fee_bps)
+    }
+}
+
+impl Eq for MarketFees {}
+
+impl std::fmt::Display for MarketFees {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "maker {} bps, taker {} bps, referral {} bps, accrued {} base atoms {} quote atoms",
+            self.maker_fee_bps,
+            self.taker_fee_bps,
+            self.referral_fee_bps,
+            self.base_fee_atoms.as_u64(),
+            self.quote_fee_atoms.as_u64()
+        )
+    }
+}
+
+#[test]
+fn test_market_fees() {
+    let mut market_fees: MarketFees =
+        MarketFees::new(5, 30, 2_500, Pubkey::new_unique(), QuoteAtoms::ZERO);
+    assert_eq!(market_fees.get_maker_fee_bps(), 5);
+    assert_eq!(market_fees.get_taker_fee_bps(), 30);
+    assert_eq!(market_fees.get_referral_fee_bps(), 2_500);
+
+    // Maker rounds down, taker rounds up.
+    assert_eq!(market_fees.get_maker_fee_atoms(10_000), 5);
+    assert_eq!(market_fees.get_maker_fee_atoms(1_999), 0);
+    assert_eq!(market_fees.get_taker_fee_atoms(10_000), 30);
+    assert_eq!(market_fees.get_taker_fee_atoms(1), 1);
+    assert_eq!(market_fees.get_taker_fee_atoms(0), 0);
+
+    // Grossing up leaves at least the net after the fee.
+    for net_atoms in [0, 1, 997, 1_000, 123_456_789] {
+        let gross_atoms: u64 = market_fees.get_taker_gross_atoms(net_atoms);
+        assert!(gross_atoms - market_fees.get_taker_fee_atoms(gross_atoms) >= net_atoms);
+        assert!(
+            gross_atoms == 0
+                || gross_atoms - 1 - market_fees.get_taker_fee_atoms(gross_atoms - 1) < net_atoms
+        );
+    }
+
+    market_fees
+        .accrue(BaseAtoms::new(1), QuoteAtoms::new(2))
+        .unwrap();
+    market_fees
+        .accrue(BaseAtoms::new(3), QuoteAtoms::new(4))
+        .unwrap();
+    assert_eq!(market_fees.get_base_fee_atoms(), BaseAtoms::new(4));
+    assert_eq!(market_fees.get_quote_fee_atoms(), QuoteAtoms::new(6));
+    let _ = format!("{}", market_fees);
+
+    assert_eq!(
+        market_fees.refund(BaseAtoms::new(3), QuoteAtoms::new(4)),
+        (BaseAtoms::ZERO, QuoteAtoms::ZERO)
+    );
+    // Only one base atom is left, the other was claimed.
+    assert_eq!(
+        market_fees.refund(BaseAtoms::new(2), QuoteAtoms::ZERO),
+        (BaseAtoms::new(1), QuoteAtoms::ZERO)
+    );
+    assert_eq!(market_fees.get_base_fee_atoms(), BaseAtoms::ZERO);
+    market_fees
+        .accrue(BaseAtoms::new(4), QuoteAtoms::new(4))
+        .unwrap();
+
+    // The referrer gets a quarter of the taker fee, rounded down.
+    assert_eq!(market_fees.get_referral_fee_atoms(3), 0);
+    assert_eq!(market_fees.get_referral_fee_atoms(4), 1);
+    market_fees.pay_referral(false, 1).unwrap();
+    assert_eq!(market_fees.get_quote_fee_atoms(), QuoteAtoms::new(5));
+    assert!(market_fees.pay_referral(false, 10).is_err());
+    market_fees
+        .accrue(BaseAtoms::ZERO, QuoteAtoms::new(1))
+        .unwrap();
+
+    assert_eq!(market_fees.claim(true), 4);
+    assert_eq!(market_fees.claim(true), 0);
+    assert_eq!(market_fees.get_quote_fee_atoms(), QuoteAtoms::new(6));
+    assert_eq!(market_fees.claim(false), 6);
+    assert_eq!(market_fees.get_quote_fee_atoms(), QuoteAtoms::ZERO);
+    assert_eq!(
+        market_fees.get_taker_fee_discount_bps(QuoteAtoms::new(u64::MAX)),
+        0
+    );
+}
+
+#[test]
+fn test_fee_tiers() {
+    let market_fees: MarketFees =
+        MarketFees::new(0, 30, 0, Pubkey::new_unique(), QuoteAtoms::new(1_000));
+    assert!(market_fees.has_fee_tiers());
+    assert_eq!(
+        market_fees.get_taker_fee_discount_bps(QuoteAtoms::new(999)),
+        0
+    );
+    assert_eq!(
+        market_fees.get_taker_fee_discount_bps(QuoteAtoms::new(1_000)),
+        1_000
+    );
+    assert_eq!(
+        market_fees.get_taker_fee_discount_bps(QuoteAtoms::new(99_999)),
+        2_500
+    );
+    assert_eq!(
+        market_fees.get_taker_fee_discount_bps(QuoteAtoms::new(u64::MAX)),
+        5_000
+    );
+
+    // The discounted rate rounds down.
+    assert_eq!(market_fees.get_discounted_taker_fee_bps(0), 30);
+    assert_eq!(market_fees.get_discounted_taker_fee_bps(1_000), 27);
+    assert_eq!(market_fees.get_discounted_taker_fee_bps(2_500), 22);
+    let discounted: MarketFees = market_fees.with_taker_fee_bps(15);
+    assert_eq!(discounted.get_taker_fee_bps(), 15);
+    assert_eq!(
+        discounted.get_fee_tier_quote_atoms(),
+        QuoteAtoms::new(1_000)
+    );
+}
@@ -198,6 +198,15 @@ pub enum AddOrderStatus {
     /// The maker was a global order but the global accounts were not passed in,
     /// so matching stops here.
     GlobalMissing,
+    /// The maker was a global order, the global accounts were not passed in
+    /// and the taker skips global orders, so the maker was left on the book.
+    GlobalMissingSkip,
+    /// The fill would have been below the taker's minimum, so the maker was
+    /// left on the book.
+    MinFillSkip,
+    /// The fill would have been below the maker's minimum taker size, so the
+    /// maker was left on the book.
+    MinTakerSkip,
 }
 
 #[derive(Default)]
@@ -219,6 +228,9 @@ pub struct AddSingleOrderCtx<'a, 'b, 'info> {
     /// logs are correct even if sequence numbers are consumed by reverse
     /// orders during matching. Mirrors `Market::place_order`.
     pub this_order_sequence_number: u64,
+    /// Set once a maker is skipped for its minimum taker size. Mirrors
+    /// `skipped_maker` in `Market::place_order`.
+    pub skipped_maker: bool,
 }
 
 impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
@@ -243,6 +255,7 @@ impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
             total_quote_atoms_traded: QuoteAtoms::ZERO,
             global_atoms_to_transfer: GlobalAtoms::ZERO,
             this_order_sequence_number,
+            skipped_maker: false,
         }
     }
     /// One iteration of the matching loop in `Market::place_order`. Kept
@@ -269,6 +282,14 @@ impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
             order_type,
             global_trade_accounts_opts,
             current_slot: _,
+            fill_memo,
+            insert_hint_index: _,
+            min_fill_atoms,
+            min_taker_atoms: _,
+            tag,
+            skip_global_orders,
+            self_trade_prevention: _,
+            referred: _,
         } = self.args;
 
         let next_order_index: DataIndex =
@@ -311,6 +332,11 @@ impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
 
         let maker_sequence_number: u64 = other_order.get_sequence_number();
         let maker_trader_index: DataIndex = other_order.get_trader_index();
+        require!(
+            !fixed.is_no_internal_cross() || maker_trader_index != trader_index,
+            ManifestError::InternalCross,
+            "Order would cross an order from the same seat",
+        )?;
         let did_fully_match_resting_order: bool =
             remaining_base_atoms >= other_order.get_num_base_atoms();
         let base_atoms_traded: BaseAtoms = if did_fully_match_resting_order {
@@ -319,17 +345,42 @@ impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
             remaining_base_atoms
         };
 
+        // Mirrors the minimum fill skip in Market::place_order.
+        if base_atoms_traded < min_fill_atoms {
+            return Ok(AddOrderToMarketInnerResult {
+                next_order_index: if remaining_base_atoms < min_fill_atoms {
+                    NIL
+                } else {
+                    next_order_index
+                },
+                status: AddOrderStatus::MinFillSkip,
+            });
+        }
+
+        // Mirrors the minimum taker size skip in Market::place_order.
+        if !did_fully_match_resting_order && base_atoms_traded < other_order.get_min_taker_atoms() {
+            self.skipped_maker = true;
+            return Ok(AddOrderToMarketInnerResult {
+                next_order_index,
+                status: AddOrderStatus::MinTakerSkip,
+            });
+        }
+
         let matched_price: QuoteAtomsPerBaseAtom = other_order.get_price();
         let maker_order_type: OrderType = other_order.get_order_type();
-        let maker_price_reverse: Result<QuoteAtomsPerBaseAtom, _> = other_order.reverse_price();
+        let maker_price_reverse: Result<QuoteAtomsPerBaseAtom, _> =
+            other_order.reverse_price(fixed.get_volatility_bps());
         let is_global: bool = other_order.is_global();
         let is_maker_reverse: bool = other_order.is_reversible();
         let maker_reverse_spread: u16 = other_order.get_reverse_spread();
+        let maker_min_taker_atoms: BaseAtoms = other_order.get_min_taker_atoms();
+        let maker_tag: u32 = other_order.get_tag();
 
         // on full fill: round in favor of the taker
         // on partial fill: round in favor of the maker
-        let quote_atoms_traded: QuoteAtoms = matched_price
-            .checked_quote_for_base(base_atoms_traded, is_bid != did_fully_match_resting_order)?;
+        let round_up: bool = is_bid != did_fully_match_resting_order;
+        let quote_atoms_traded: QuoteAtoms =
+            matched_price.checked_quote_for_base(base_atoms_traded, round_up)?;
 
         // If it is a global order, just in time bring the funds over, or
         // remove from the tree and continue on to the next order.
@@ -345,12 +396,19 @@ impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
                 &global_trade_accounts_opts[1]
             };
             // When the global account is not included, a taker order can halt
-            // here, but a possible maker order will need to crash since that
-            // would result in a crossed book.
+            // or pass over it here, but a possible maker order will need to
+            // crash since that would result in a crossed book.
             if global_trade_accounts_opt.is_none() {
                 if order_type_can_rest(order_type) {
                     return Err(ManifestError::MissingGlobal.into());
                 }
+                if skip_global_orders {
+                    self.skipped_maker = true;
+                    return Ok(AddOrderToMarketInnerResult {
+                        next_order_index,
+                        status: AddOrderStatus::GlobalMissingSkip,
+                    });
+                }
                 return Ok(AddOrderToMarketInnerResult {
                     next_order_index: NIL,
                     status: AddOrderStatus::GlobalMissing,
@@ -392,6 +450,15 @@ impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
         self.total_quote_atoms_traded = self
             .total_quote_atoms_traded
             .checked_add(quote_atoms_traded)?;
+        record_rounding(
+            fixed,
+            matched_price,
+            base_atoms_traded,
+            quote_atoms_traded,
+            round_up,
+            did_fully_match_resting_order,
+        )?;
+        fixed.record_trade(matched_price, now_slot);
 
         // Possibly increase bonus atom maker gets from the rounding the
         // quote in their favor. They will get one less than expected when
@@ -490,7 +557,7 @@ impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
         record_volume_by_trader_index(dynamic, maker_trader_index, quote_atoms_traded);
         record_volume_by_trader_index(dynamic, trader_index, quote_atoms_traded);
 
-        emit_stack(FillLog {
+        let fill_log: FillLog = FillLog {
             market,
             maker,
             taker,
@@ -503,8 +570,17 @@ impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
             base_mint: *fixed.get_base_mint(),
             quote_mint: *fixed.get_quote_mint(),
             is_maker_global: PodBool::from(is_global),
-            _padding: [0; 14],
-        })?;
+            self_trade: PodBool::from(maker_trader_index == trader_index),
+            referred: PodBool::from(false),
+            maker_tag,
+            taker_tag: tag,
+            taker_fee_bps: 0,
+            _padding2: [0; 2],
+        };
+        emit_stack(fill_log)?;
+        if fill_memo {
+            emit_fill_memo(&fill_log)?;
+        }
 
         let status: AddOrderStatus = if did_fully_match_resting_order {
             // Get paid for removing a global order.
@@ -542,10 +618,13 @@ impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
                     maker_trader_index,
                     maker_order_type,
                     maker_reverse_spread,
+                    maker_min_taker_atoms,
+                    maker_tag,
                     price_reverse,
                     base_atoms_traded,
                     quote_atoms_traded,
                     is_bid,
+                    now_slot,
                 )?;
             }
         }
@@ -571,10 +650,13 @@ fn place_reverse_order(
     maker_trader_index: DataIndex,
     maker_order_type: OrderType,
     maker_reverse_spread: u16,
+    maker_min_taker_atoms: BaseAtoms,
+    maker_tag: u32,
     price_reverse: QuoteAtomsPerBaseAtom,
     base_atoms_traded: BaseAtoms,
     quote_atoms_traded: QuoteAtoms,
     is_bid: bool,
+    now_slot: u32,
 ) -> ProgramResult {
     let num_base_atoms_reverse: BaseAtoms = if is_bid {
         // Maker is now buying with the exact number of quote atoms. Do not
@@ -656,12 +738,16 @@ fn place_reverse_order(
             maker_order_type,
         )?;
         new_reverse_resting_order.set_reverse_spread(maker_reverse_spread);
+        new_reverse_resting_order.set_min_taker_atoms(maker_min_taker_atoms);
+        new_reverse_resting_order.set_tag(maker_tag);
+        new_reverse_resting_order.set_placed_slot(now_slot);
         insert_order_into_tree(
             is_bid,
             fixed,
             dynamic,
             free_address,
             &new_reverse_resting_order,
+            NIL,
         );
         set_payload_order(dynamic, free_address);
     }
@@ -699,6 +785,14 @@ pub fn place_order_helper<
         order_type,
         global_trade_accounts_opts: _,
         current_slot,
+        fill_memo: _,
+        insert_hint_index: _,
+        min_fill_atoms: _,
+        min_taker_atoms: _,
+        tag: _,
+        skip_global_orders: _,
+        self_trade_prevention: _,
+        referred: _,
     } = args;
     assert_already_has_seat(trader_index)?;
     let now_slot: u32 = current_slot.unwrap_or_else(|| get_now_slot());
@@ -760,6 +854,7 @@ pub fn place_order_helper<
     // move out args so that they can be used later. The taker's sequence
     // number was claimed by AddSingleOrderCtx::new before matching started.
     let this_order_sequence_number: u64 = ctx.this_order_sequence_number;
+    let skipped_maker: bool = ctx.skipped_maker;
     let args: AddOrderToMarketArgs = ctx.args;
     // ctx is dead from this point onward
 
@@ -770,12 +865,14 @@ pub fn place_order_helper<
     if !order_type_can_rest(order_type)
         || remaining_base_atoms == BaseAtoms::ZERO
         || price == QuoteAtomsPerBaseAtom::ZERO
+        || skipped_maker
     {
         return Ok(AddOrderToMarketResult {
             order_sequence_number: this_order_sequence_number,
             order_index: NIL,
             base_atoms_traded: total_base_atoms_traded,
             quote_atoms_traded: total_quote_atoms_traded,
+            referral_fee_atoms: 0,
         });
     }
 
@@ -800,7 +897,8 @@ mod place_order_equivalence_tests {
     use super::*;
     use crate::state::{
         market::{create_empty_market, MarketValue},
-        OrderType, MARKET_BLOCK_SIZE, NO_EXPIRATION_LAST_VALID_SLOT,
+        OrderType, SelfTradePrevention, MARKET_BLOCK_SIZE, MARKET_FLAG_NO_INTERNAL_CROSS,
+        NO_EXPIRATION_LAST_VALID_SLOT,
     };
     use solana_program::pubkey::Pubkey;
 
@@ -851,6 +949,31 @@ mod place_order_equivalence_tests {
         order_type: OrderType,
         last_valid_slot: u32,
         current_slot: u32,
+    ) -> Result<AddOrderToMarketResult, ProgramError> {
+        place_with_min_taker(
+            market,
+            trader_index,
+            num_base_atoms,
+            price,
+            is_bid,
+            order_type,
+            last_valid_slot,
+            current_slot,
+            0,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn place_with_min_taker(
+        market: &mut MarketValue,
+        trader_index: DataIndex,
+        num_base_atoms: u64,
+        price: f64,
+        is_bid: bool,
+        order_type: OrderType,
+        last_valid_slot: u32,
+        current_slot: u32,
+        min_taker_atoms: u64,
     ) -> Result<AddOrderToMarketResult, ProgramError> {
         market.place_order(AddOrderToMarketArgs {
             market: Pubkey::new_unique(),
@@ -862,6 +985,14 @@ mod place_order_equivalence_tests {
             order_type,
             global_trade_accounts_opts: &[None, None],
             current_slot: Some(current_slot),
+            fill_memo: false,
+            insert_hint_index: NIL,
+            min_fill_atoms: BaseAtoms::ZERO,
+            min_taker_atoms: BaseAtoms::new(min_taker_atoms),
+            tag: 0,
+            skip_global_orders: false,
+            self_trade_prevention: SelfTradePrevention::None,
+            referred: false,
         })
     }
 
@@ -877,31 +1008,33 @@ mod place_order_equivalence_tests {
         order_type: OrderType,
         last_valid_slot: u32,
     ) {
-        let market_key: Pubkey = Pubkey::new_unique();
-        let mut production: MarketValue = MarketValue {
-            fixed: market.fixed,
-            dynamic: market.dynamic.clone(),
-        };
-        let mut model: MarketValue = MarketValue {
-            fixed: market.fixed,
-            dynamic: market.dynamic.clone(),
-        };
+        assert_equivalent_taker_with_min_fill(
+            market,
+            trader_index,
+            num_base_atoms,
+            price,
+            is_bid,
+            order_type,
+            last_valid_slot,
+            0,
+        );
+    }
 
-        let production_result: Result<AddOrderToMarketResult, ProgramError> = production
-            .place_order(AddOrderToMarketArgs {
-                market: market_key,
-                trader_index,
-                num_base_atoms: BaseAtoms::new(num_base_atoms),
-                price: price.try_into().unwrap(),
-                is_bid,
-                last_valid_slot,
-                order_type,
-                global_trade_accounts_opts: &[None, None],
-                current_slot: Some(NOW_SLOT),
-            });
-        let model_result: Result<AddOrderToMarketResult, ProgramError> =
-            model.place_order_(AddOrderToMarketArgs {
-                market: market_key,
+    #[allow(clippy::too_many_arguments)]
+    fn assert_equivalent_taker_with_min_fill(
+        market: &MarketValue,
+        trader_index: DataIndex,
+        num_base_atoms: u64,
+        price: f64,
+        is_bid: bool,
+        order_type: OrderType,
+        last_valid_slot: u32,
+        min_fill_atoms: u64,
+    ) -> MarketValue {
+        assert_equivalent_args(
+            market,
+            AddOrderToMarketArgs {
+                market: Pubkey::new_unique(),
                 trader_index,
                 num_base_atoms: BaseAtoms::new(num_base_atoms),
                 price: price.try_into().unwrap(),
@@ -910,7 +1043,33 @@ mod place_order_equivalence_tests {
                 order_type,
                 global_trade_accounts_opts: &[None, None],
                 current_slot: Some(NOW_SLOT),
-            });
+                fill_memo: false,
+                insert_hint_index: NIL,
+                min_fill_atoms: BaseAtoms::new(min_fill_atoms),
+                min_taker_atoms: BaseAtoms::ZERO,
+                tag: 0,
+                skip_global_orders: false,
+                self_trade_prevention: SelfTradePrevention::None,
+                referred: false,
+            },
+        )
+    }
+
+    /// Runs the same args through both implementations, see
+    /// `assert_equivalent_taker`, and returns the production market.
+    fn assert_equivalent_args(market: &MarketValue, args: AddOrderToMarketArgs) -> MarketValue {
+        let mut production: MarketValue = MarketValue {
+            fixed: market.fixed,
+            dynamic: market.dynamic.clone(),
+        };
+        let mut model: MarketValue = MarketValue {
+            fixed: market.fixed,
+            dynamic: market.dynamic.clone(),
+        };
+
+        let production_result: Result<AddOrderToMarketResult, ProgramError> =
+            production.place_order(args.clone());
+        let model_result: Result<AddOrderToMarketResult, ProgramError> = model.place_order_(args);
 
         match (production_result, model_result) {
             (Ok(production_result), Ok(model_result)) => {
@@ -952,6 +1111,7 @@ mod place_order_equivalence_tests {
             production.dynamic, model.dynamic,
             "market dynamic state diverged"
         );
+        production
     }
 
     /// Two ask levels, both consumed, the remainder rests.
@@ -1181,6 +1341,51 @@ mod place_order_equivalence_tests {
         );
     }
 
+    /// The come-back order of a tagged reverse maker keeps the maker's tag,
+    /// and the taker's remainder rests with its own.
+    #[test]
+    fn test_equivalence_reverse_maker_keeps_tag() {
+        let (mut market, maker_index, taker_index, _, _) = new_market_with_seats();
+        market
+            .place_order(AddOrderToMarketArgs {
+                market: Pubkey::new_unique(),
+                trader_index: maker_index,
+                num_base_atoms: BaseAtoms::new(100),
+                price: 0.150.try_into().unwrap(),
+                is_bid: false,
+                last_valid_slot: NO_EXPIRATION_LAST_VALID_SLOT,
+                order_type: OrderType::Reverse,
+                global_trade_accounts_opts: &[None, None],
+                current_slot: Some(NOW_SLOT),
+                fill_memo: false,
+                insert_hint_index: NIL,
+                min_fill_atoms: BaseAtoms::ZERO,
+                min_taker_atoms: BaseAtoms::ZERO,
+                tag: 9,
+                skip_global_orders: false,
+                self_trade_prevention: SelfTradePrevention::None,
+                referred: false,
+            })
+            .unwrap();
+
+        let production: MarketValue = assert_equivalent_taker_with_min_fill(
+            &market,
+            taker_index,
+            150,
+            0.150,
+            true,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            0,
+        );
+        let bids: Vec<(DataIndex, u32)> = production
+            .get_bids()
+            .iter::<RestingOrder>()
+            .map(|(_, order)| (order.get_trader_index(), order.get_tag()))
+            .collect();
+        assert_eq!(bids, vec![(maker_index, 9), (taker_index, 0)]);
+    }
+
     /// A zero-price order matches nothing and does not rest.
     #[test]
     fn test_equivalence_zero_price_returns_early() {
@@ -1213,4 +1418,348 @@ mod place_order_equivalence_tests {
             10,
         );
     }
+
+    /// Without the global accounts, a taker that skips global orders passes
+    /// over a global maker and fills the one behind it, where it would
+    /// otherwise stop.
+    #[test]
+    fn test_equivalence_skip_global_orders() {
+        let (mut market, maker_index, taker_index, _, _) = new_market_with_seats();
+        let global_order_index: DataIndex = place(
+            &mut market,
+            maker_index,
+            100,
+            0.150,
+            false,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            NOW_SLOT,
+        )
+        .unwrap()
+        .order_index;
+        place(
+            &mut market,
+            maker_index,
+            100,
+            0.160,
+            false,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            NOW_SLOT,
+        )
+        .unwrap();
+        // Placing a global needs the global accounts, so turn the best ask
+        // into one in place. Price and sequence number, the tree key, stay.
+        let order: &mut RestingOrder =
+            get_mut_helper_order(&mut market.dynamic, global_order_index).get_mut_value();
+        *order = RestingOrder::new(
+            maker_index,
+            order.get_num_base_atoms(),
+            order.get_price(),
+            order.get_sequence_number(),
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            false,
+            OrderType::Global,
+        )
+        .unwrap();
+
+        for skip_global_orders in [false, true] {
+            let production: MarketValue = assert_equivalent_args(
+                &market,
+                AddOrderToMarketArgs {
+                    market: Pubkey::new_unique(),
+                    trader_index: taker_index,
+                    num_base_atoms: BaseAtoms::new(100),
+                    price: 0.200.try_into().unwrap(),
+                    is_bid: true,
+                    last_valid_slot: NO_EXPIRATION_LAST_VALID_SLOT,
+                    order_type: OrderType::ImmediateOrCancel,
+                    global_trade_accounts_opts: &[None, None],
+                    current_slot: Some(NOW_SLOT),
+                    fill_memo: false,
+                    insert_hint_index: NIL,
+                    min_fill_atoms: BaseAtoms::ZERO,
+                    min_taker_atoms: BaseAtoms::ZERO,
+                    tag: 0,
+                    skip_global_orders,
+                    self_trade_prevention: SelfTradePrevention::None,
+                    referred: false,
+                },
+            );
+            let asks: Vec<(u64, bool)> = production
+                .get_asks()
+                .iter::<RestingOrder>()
+                .map(|(_, order)| (order.get_num_base_atoms().as_u64(), order.is_global()))
+                .collect();
+            if skip_global_orders {
+                assert_eq!(asks, vec![(100, true)]);
+            } else {
+                assert_eq!(asks, vec![(100, true), (100, false)]);
+            }
+        }
+    }
+
+    /// With the market flag set, crossing a resting order from the same seat
+    /// fails instead of self matching. Other seats still match it.
+    #[test]
+    fn test_equivalence_no_internal_cross() {
+        let (mut market, maker_index, taker_index, _, _) = new_market_with_seats();
+        place(
+            &mut market,
+            maker_index,
+            100,
+            0.150,
+            false,
+            OrderType::Reverse,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            NOW_SLOT,
+        )
+        .unwrap();
+
+        // Self matching is allowed by default.
+        assert_equivalent_taker(
+            &market,
+            maker_index,
+            100,
+            0.200,
+            true,
+            OrderType::ImmediateOrCancel,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+        );
+
+        market.fixed.market_flags = MARKET_FLAG_NO_INTERNAL_CROSS;
+        for order_type in [OrderType::ImmediateOrCancel, OrderType::Limit] {
+            let mut production: MarketValue = MarketValue {
+                fixed: market.fixed,
+                dynamic: market.dynamic.clone(),
+            };
+            assert_eq!(
+                place(
+                    &mut production,
+                    maker_index,
+                    100,
+                    0.200,
+                    true,
+                    order_type,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                    NOW_SLOT,
+                )
+                .err(),
+                Some(ManifestError::InternalCross.into())
+            );
+            assert_equivalent_taker(
+                &market,
+                maker_index,
+                100,
+                0.200,
+                true,
+                order_type,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            );
+        }
+        // Not crossing is fine.
+        assert_equivalent_taker(
+            &market,
+            maker_index,
+            100,
+            0.100,
+            true,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+        );
+        let production: MarketValue = assert_equivalent_taker_with_min_fill(
+            &market,
+            taker_index,
+            100,
+            0.200,
+            true,
+            OrderType::ImmediateOrCancel,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            0,
+        );
+        // The maker's reverse order flipped to a bid.
+        assert_eq!(production.get_asks().iter::<RestingOrder>().count(), 0);
+        assert_eq!(production.get_bids().iter::<RestingOrder>().count(), 1);
+    }
+
+    /// Makers below the minimum fill are skipped and stay on the book, and
+    /// matching stops once the remainder is below the minimum.
+    #[test]
+    fn test_equivalence_min_fill_skips_small_makers() {
+        let (mut market, maker_index, taker_index, _, _) = new_market_with_seats();
+        for (num_base_atoms, price) in [(20, 0.150), (100, 0.160), (30, 0.170)] {
+            place(
+                &mut market,
+                maker_index,
+                num_base_atoms,
+                price,
+                false,
+                OrderType::Limit,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                NOW_SLOT,
+            )
+            .unwrap();
+        }
+
+        let production: MarketValue = assert_equivalent_taker_with_min_fill(
+            &market,
+            taker_index,
+            140,
+            0.200,
+            true,
+            OrderType::ImmediateOrCancel,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            50,
+        );
+        let asks: Vec<u64> = production
+            .get_asks()
+            .iter::<RestingOrder>()
+            .map(|(_, order)| order.get_num_base_atoms().as_u64())
+            .collect();
+        assert_eq!(asks, vec![20, 30]);
+    }
+
+    /// A taker too small for a maker's minimum passes over it, and its
+    /// remainder does not rest since that would cross the skipped maker.
+    #[test]
+    fn test_equivalence_min_taker_skips_maker_and_does_not_rest() {
+        let (mut market, maker_index, taker_index, _, _) = new_market_with_seats();
+        place_with_min_taker(
+            &mut market,
+            maker_index,
+            100,
+            0.150,
+            false,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            NOW_SLOT,
+            50,
+        )
+        .unwrap();
+        place(
+            &mut market,
+            maker_index,
+            20,
+            0.160,
+            false,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            NOW_SLOT,
+        )
+        .unwrap();
+
+        let production: MarketValue = assert_equivalent_taker_with_min_fill(
+            &market,
+            taker_index,
+            40,
+            0.200,
+            true,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            0,
+        );
+        let asks: Vec<u64> = production
+            .get_asks()
+            .iter::<RestingOrder>()
+            .map(|(_, order)| order.get_num_base_atoms().as_u64())
+            .collect();
+        assert_eq!(asks, vec![100]);
+        assert_eq!(production.get_bids().iter::<RestingOrder>().count(), 0);
+    }
+
+    /// Self trade prevention only exists in production, so each policy is
+    /// checked on its own copy of a book with an own ask in front of another
+    /// seat's ask.
+    #[test]
+    fn test_self_trade_prevention() {
+        let (mut market, maker_index, taker_index, maker_pk, _) = new_market_with_seats();
+        place(
+            &mut market,
+            maker_index,
+            100,
+            0.150,
+            false,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            NOW_SLOT,
+        )
+        .unwrap();
+        place(
+            &mut market,
+            taker_index,
+            100,
+            0.160,
+            false,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            NOW_SLOT,
+        )
+        .unwrap();
+        let (base_before, _) = market.get_trader_balance(&maker_pk);
+
+        let place_with_policy =
+            |self_trade_prevention: SelfTradePrevention,
+             num_base_atoms: u64|
+             -> (Result<AddOrderToMarketResult, ProgramError>, MarketValue) {
+                let mut production: MarketValue = MarketValue {
+                    fixed: market.fixed,
+                    dynamic: market.dynamic.clone(),
+                };
+                let result = production.place_order(AddOrderToMarketArgs {
+                    market: Pubkey::new_unique(),
+                    trader_index: maker_index,
+                    num_base_atoms: BaseAtoms::new(num_base_atoms),
+                    price: 0.200.try_into().unwrap(),
+                    is_bid: true,
+                    last_valid_slot: NO_EXPIRATION_LAST_VALID_SLOT,
+                    order_type: OrderType::Limit,
+                    global_trade_accounts_opts: &[None, None],
+                    current_slot: Some(NOW_SLOT),
+                    fill_memo: false,
+                    insert_hint_index: NIL,
+                    min_fill_atoms: BaseAtoms::ZERO,
+                    min_taker_atoms: BaseAtoms::ZERO,
+                    tag: 0,
+                    skip_global_orders: false,
+                    self_trade_prevention,
+                    referred: false,
+                });
+                (result, production)
+            };
+        let asks = |market: &MarketValue| -> Vec<u64> {
+            market
+                .get_asks()
+                .iter::<RestingOrder>()
+                .map(|(_, order)| order.get_num_base_atoms().as_u64())
+                .collect()
+        };
+
+        let (result, _) = place_with_policy(SelfTradePrevention::Reject, 50);
+        assert_eq!(result.err(), Some(ManifestError::InternalCross.into()));
+
+        // Nothing trades and nothing rests.
+        let (result, production) = place_with_policy(SelfTradePrevention::CancelTake, 50);
+        assert_eq!(result.unwrap().base_atoms_traded, BaseAtoms::ZERO);
+        assert_eq!(asks(&production), vec![100, 100]);
+        assert_eq!(production.get_bids().iter::<RestingOrder>().count(), 0);
+
+        // The own ask is cancelled and the other seat's ask fills.
+        let (result, production) = place_with_policy(SelfTradePrevention::CancelProvide, 50);
+        assert_eq!(result.unwrap().base_atoms_traded, BaseAtoms::new(50));
+        assert_eq!(asks(&production), vec![50]);
+
+        // Smaller than the own ask, which gives back what it decremented.
+        let (result, production) = place_with_policy(SelfTradePrevention::DecrementBoth, 30);
+        assert_eq!(result.unwrap().base_atoms_traded, BaseAtoms::ZERO);
+        assert_eq!(asks(&production), vec![70, 100]);
+        assert_eq!(
+            production.get_trader_balance(&maker_pk).0,
+            base_before.checked_add(BaseAtoms::new(30)).unwrap()
+        );
+
+        // Larger than the own ask, the rest fills against the other seat.
+        let (result, production) = place_with_policy(SelfTradePrevention::DecrementBoth, 130);
+        assert_eq!(result.unwrap().base_atoms_traded, BaseAtoms::new(30));
+        assert_eq!(asks(&production), vec![70]);
+    }
 }
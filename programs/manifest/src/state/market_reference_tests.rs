@@ -0,0 +1,563 @@
+//! Differential testing of the zero-copy matching engine against a plain
+//! reference engine. The reference keeps each side of the book in a `Vec` in
+//! priority order and settles balances the way the program documents it, so
+//! it is easy to read and hard to get subtly wrong. Random streams of places
+//! and cancels run through both, and after every operation the results, both
+//! books, every trader balance and the market volume have to agree.
+//!
+//! Only limit, immediate-or-cancel, post-only and iceberg orders are covered.
+//! Global and reverse orders need accounts or coalescing rules that the reference
+//! would have to copy from the program rather than restate.
+use super::*;
+use crate::{
+    program::ManifestError,
+    quantities::WrapperU64,
+    state::{order_type_can_take, MARKET_BLOCK_SIZE},
+};
+use std::collections::HashMap;
+
+const NUM_TRADERS: usize = 3;
+const INITIAL_DEPOSIT_ATOMS: u64 = 1_000_000_000_000;
+const NUM_OPERATIONS: usize = 400;
+const NUM_SEEDS: u64 = 16;
+
+/// xorshift64*, enough to make streams reproducible from a seed without a
+/// dependency.
+struct TestRng(u64);
+
+impl TestRng {
+    fn new(seed: u64) -> Self {
+        TestRng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ReferenceOrder {
+    trader_index: DataIndex,
+    price: QuoteAtomsPerBaseAtom,
+    num_base_atoms: u64,
+    sequence_number: u64,
+    last_valid_slot: u32,
+    /// Zero unless an iceberg.
+    display_base_atoms: u64,
+}
+
+impl ReferenceOrder {
+    fn is_expired(&self, now_slot: u32) -> bool {
+        self.last_valid_slot != NO_EXPIRATION_LAST_VALID_SLOT && self.last_valid_slot < now_slot
+    }
+
+    /// Atoms locked by the order, rounded up on bids like the program.
+    fn locked_atoms(&self, is_bid: bool) -> u64 {
+        if is_bid {
+            quote_for_base(self.price, self.num_base_atoms, true)
+        } else {
+            self.num_base_atoms
+        }
+    }
+
+    /// Icebergs show what is left over whole display sizes, odd lot first.
+    fn visible_base_atoms(&self) -> u64 {
+        if self.display_base_atoms == 0 || self.num_base_atoms == 0 {
+            self.num_base_atoms
+        } else {
+            (self.num_base_atoms - 1) % self.display_base_atoms + 1
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct ReferenceMarket {
+    /// Best first, ties in arrival order.
+    bids: Vec<ReferenceOrder>,
+    asks: Vec<ReferenceOrder>,
+    /// Withdrawable (base, quote) per trader.
+    balances: HashMap<DataIndex, (u64, u64)>,
+    order_sequence_number: u64,
+    quote_volume: u64,
+    taker_rounding_quote_atoms: u64,
+    maker_rounding_quote_atoms: u64,
+}
+
+#[derive(Debug, PartialEq)]
+struct ReferenceResult {
+    order_sequence_number: u64,
+    rested: bool,
+    base_atoms_traded: u64,
+    quote_atoms_traded: u64,
+}
+
+fn quote_for_base(price: QuoteAtomsPerBaseAtom, base_atoms: u64, round_up: bool) -> u64 {
+    price
+        .checked_quote_for_base(BaseAtoms::new(base_atoms), round_up)
+        .unwrap()
+        .as_u64()
+}
+
+impl ReferenceMarket {
+    fn credit(&mut self, trader_index: DataIndex, is_base: bool, amount_atoms: u64) {
+        let balance: &mut (u64, u64) = self.balances.entry(trader_index).or_default();
+        if is_base {
+            balance.0 += amount_atoms;
+        } else {
+            balance.1 += amount_atoms;
+        }
+    }
+
+    fn debit(
+        &mut self,
+        trader_index: DataIndex,
+        is_base: bool,
+        amount_atoms: u64,
+    ) -> Result<(), ProgramError> {
+        let balance: &mut (u64, u64) = self.balances.entry(trader_index).or_default();
+        let available: &mut u64 = if is_base {
+            &mut balance.0
+        } else {
+            &mut balance.1
+        };
+        if *available < amount_atoms {
+            return Err(ProgramError::InsufficientFunds);
+        }
+        *available -= amount_atoms;
+        Ok(())
+    }
+
+    fn refund(&mut self, order: &ReferenceOrder, is_bid: bool) {
+        self.credit(order.trader_index, !is_bid, order.locked_atoms(is_bid));
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn place(
+        &mut self,
+        trader_index: DataIndex,
+        num_base_atoms: u64,
+        price: QuoteAtomsPerBaseAtom,
+        is_bid: bool,
+        order_type: OrderType,
+        last_valid_slot: u32,
+        display_base_atoms: u64,
+        now_slot: u32,
+    ) -> Result<ReferenceResult, ProgramError> {
+        let order_sequence_number: u64 = self.order_sequence_number;
+        self.order_sequence_number += 1;
+
+        let mut remaining: u64 = num_base_atoms;
+        let mut base_atoms_traded: u64 = 0;
+        let mut quote_atoms_traded: u64 = 0;
+        loop {
+            let makers: &mut Vec<ReferenceOrder> = if is_bid {
+                &mut self.asks
+            } else {
+                &mut self.bids
+            };
+            if remaining == 0 || makers.is_empty() {
+                break;
+            }
+            let maker: ReferenceOrder = makers[0];
+            if maker.is_expired(now_slot) || maker.num_base_atoms == 0 {
+                makers.remove(0);
+                self.refund(&maker, !is_bid);
+                continue;
+            }
+            if (is_bid && maker.price > price) || (!is_bid && maker.price < price) {
+                break;
+            }
+            if !order_type_can_take(order_type) {
+                return Err(ManifestError::PostOnlyCrosses.into());
+            }
+
+            let traded: u64 = remaining.min(maker.visible_base_atoms());
+            let fully_matched: bool = traded == maker.num_base_atoms;
+            // Full fills round for the taker, partial fills for the maker.
+            let quote: u64 = quote_for_base(maker.price, traded, is_bid != fully_matched);
+            let rounding: u64 = quote_for_base(maker.price, traded, true)
+                - quote_for_base(maker.price, traded, false);
+            if fully_matched {
+                self.taker_rounding_quote_atoms += rounding;
+            } else {
+                self.maker_rounding_quote_atoms += rounding;
+            }
+
+            if is_bid {
+                self.credit(maker.trader_index, false, quote);
+                self.debit(trader_index, false, quote)?;
+                self.credit(trader_index, true, traded);
+            } else {
+                // The maker's bid locked its quote rounded up as a whole, so
+                // whatever that lock shrinks by beyond the quote paid out
+                // goes back to the maker.
+                let lock_before: u64 = quote_for_base(maker.price, maker.num_base_atoms, true);
+                let lock_after: u64 =
+                    quote_for_base(maker.price, maker.num_base_atoms - traded, true);
+                self.credit(maker.trader_index, false, lock_before - lock_after - quote);
+                self.credit(maker.trader_index, true, traded);
+                self.debit(trader_index, true, traded)?;
+                self.credit(trader_index, false, quote);
+            }
+            base_atoms_traded += traded;
+            quote_atoms_traded += quote;
+
+            let makers: &mut Vec<ReferenceOrder> = if is_bid {
+                &mut self.asks
+            } else {
+                &mut self.bids
+            };
+            if fully_matched {
+                makers.remove(0);
+                remaining -= traded;
+            } else if maker.display_base_atoms != 0 && traded == maker.visible_base_atoms() {
+                // The next tranche goes behind the orders at its price.
+                let mut iceberg: ReferenceOrder = makers.remove(0);
+                iceberg.num_base_atoms -= traded;
+                iceberg.sequence_number = self.order_sequence_number;
+                self.order_sequence_number += 1;
+                let position: usize = makers
+                    .iter()
+                    .position(|resting| resting.price != iceberg.price)
+                    .unwrap_or(makers.len());
+                makers.insert(position, iceberg);
+                remaining -= traded;
+            } else {
+                makers[0].num_base_atoms -= traded;
+                remaining = 0;
+            }
+        }
+        self.quote_volume = self.quote_volume.wrapping_add(quote_atoms_traded);
+
+        let rested: bool = order_type_can_rest(order_type)
+            && remaining > 0
+            && price != QuoteAtomsPerBaseAtom::ZERO;
+        if rested {
+            let order: ReferenceOrder = ReferenceOrder {
+                trader_index,
+                price,
+                num_base_atoms: remaining,
+                sequence_number: order_sequence_number,
+                last_valid_slot,
+                display_base_atoms,
+            };
+            self.debit(trader_index, !is_bid, order.locked_atoms(is_bid))?;
+            let side: &mut Vec<ReferenceOrder> = if is_bid {
+                &mut self.bids
+            } else {
+                &mut self.asks
+            };
+            let position: usize = side
+                .iter()
+                .position(|resting| {
+                    if is_bid {
+                        resting.price < price
+                    } else {
+                        resting.price > price
+                    }
+                })
+                .unwrap_or(side.len());
+            side.insert(position, order);
+        }
+
+        Ok(ReferenceResult {
+            order_sequence_number,
+            rested,
+            base_atoms_traded,
+            quote_atoms_traded,
+        })
+    }
+
+    fn cancel(&mut self, trader_index: DataIndex, sequence_number: u64) {
+        for is_bid in [true, false] {
+            let side: &mut Vec<ReferenceOrder> = if is_bid {
+                &mut self.bids
+            } else {
+                &mut self.asks
+            };
+            if let Some(position) = side
+                .iter()
+                .position(|order| order.sequence_number == sequence_number)
+            {
+                let order: ReferenceOrder = side.remove(position);
+                assert_eq!(order.trader_index, trader_index);
+                self.refund(&order, is_bid);
+                return;
+            }
+        }
+        panic!("no order {sequence_number} to cancel");
+    }
+}
+
+fn new_market() -> (MarketValue, Vec<Pubkey>, Vec<DataIndex>) {
+    let market_key: Pubkey = Pubkey::new_unique();
+    let mint_authority: Pubkey = Pubkey::new_unique();
+    let fixed: MarketFixed = create_empty_market(
+        "So11111111111111111111111111111111111111112",
+        "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+        9,
+        6,
+        &mint_authority,
+        &market_key,
+    );
+    let mut market: MarketValue = MarketValue {
+        fixed,
+        dynamic: vec![0; MARKET_BLOCK_SIZE * (NUM_OPERATIONS + 2 * NUM_TRADERS + 2)],
+    };
+    market.market_expand_n(NUM_TRADERS as u32).unwrap();
+
+    let mut traders: Vec<Pubkey> = Vec::new();
+    let mut trader_indices: Vec<DataIndex> = Vec::new();
+    for _ in 0..NUM_TRADERS {
+        let trader: Pubkey = Pubkey::new_unique();
+        market.claim_seat(&trader).unwrap();
+        let trader_index: DataIndex = market.get_trader_index(&trader);
+        market
+            .deposit(trader_index, INITIAL_DEPOSIT_ATOMS, true)
+            .unwrap();
+        market
+            .deposit(trader_index, INITIAL_DEPOSIT_ATOMS, false)
+            .unwrap();
+        traders.push(trader);
+        trader_indices.push(trader_index);
+    }
+    (market, traders, trader_indices)
+}
+
+/// (trader index, price, base atoms, sequence number) of every order on one
+/// side, best first. Expiry is left out since the program only exposes it
+/// through matching.
+type BookEntries = Vec<(DataIndex, QuoteAtomsPerBaseAtom, u64, u64)>;
+
+fn book_side(market: &MarketValue, is_bid: bool) -> BookEntries {
+    let side: BooksideReadOnly = if is_bid {
+        market.get_bids()
+    } else {
+        market.get_asks()
+    };
+    side.iter::<RestingOrder>()
+        .map(|(_, order)| {
+            (
+                order.get_trader_index(),
+                order.get_price(),
+                order.get_num_base_atoms().as_u64(),
+                order.get_sequence_number(),
+            )
+        })
+        .collect()
+}
+
+fn reference_side(orders: &[ReferenceOrder]) -> BookEntries {
+    orders
+        .iter()
+        .map(|order| {
+            (
+                order.trader_index,
+                order.price,
+                order.num_base_atoms,
+                order.sequence_number,
+            )
+        })
+        .collect()
+}
+
+fn assert_same_state(
+    market: &MarketValue,
+    reference: &ReferenceMarket,
+    traders: &[Pubkey],
+    trader_indices: &[DataIndex],
+    context: &str,
+) {
+    assert_eq!(
+        book_side(market, true),
+        reference_side(&reference.bids),
+        "bids, {context}"
+    );
+    assert_eq!(
+        book_side(market, false),
+        reference_side(&reference.asks),
+        "asks, {context}"
+    );
+    for (trader, trader_index) in traders.iter().zip(trader_indices) {
+        let (base, quote) = market.get_trader_balance(trader);
+        assert_eq!(
+            (base.as_u64(), quote.as_u64()),
+            reference.balances[trader_index],
+            "balance of {trader_index}, {context}"
+        );
+    }
+    assert_eq!(
+        market.fixed.get_quote_volume().as_u64(),
+        reference.quote_volume,
+        "quote volume, {context}"
+    );
+    assert_eq!(
+        (
+            market.fixed.get_taker_rounding_quote_atoms().as_u64(),
+            market.fixed.get_maker_rounding_quote_atoms().as_u64(),
+        ),
+        (
+            reference.taker_rounding_quote_atoms,
+            reference.maker_rounding_quote_atoms,
+        ),
+        "rounding, {context}"
+    );
+}
+
+fn run_stream(seed: u64) {
+    let mut rng: TestRng = TestRng::new(seed);
+    let (mut market, traders, trader_indices) = new_market();
+    let mut reference: ReferenceMarket = ReferenceMarket {
+        order_sequence_number: market.fixed.order_sequence_number,
+        ..Default::default()
+    };
+    for trader_index in &trader_indices {
+        reference.balances.insert(
+            *trader_index,
+            (INITIAL_DEPOSIT_ATOMS, INITIAL_DEPOSIT_ATOMS),
+        );
+    }
+    let mut now_slot: u32 = 1;
+
+    for step in 0..NUM_OPERATIONS {
+        now_slot += rng.below(3) as u32;
+        let resting: Vec<ReferenceOrder> = reference
+            .bids
+            .iter()
+            .chain(reference.asks.iter())
+            .copied()
+            .collect();
+
+        if !resting.is_empty() && rng.below(5) == 0 {
+            let order: ReferenceOrder = resting[rng.below(resting.len() as u64) as usize];
+            let context: String = format!(
+                "seed {seed} step {step} cancel {} by {}",
+                order.sequence_number, order.trader_index
+            );
+            market
+                .cancel_order(order.trader_index, order.sequence_number, &[None, None])
+                .unwrap();
+            reference.cancel(order.trader_index, order.sequence_number);
+            assert_same_state(&market, &reference, &traders, &trader_indices, &context);
+            continue;
+        }
+
+        let trader_index: DataIndex = trader_indices[rng.below(NUM_TRADERS as u64) as usize];
+        // Small sizes exercise rounding, a narrow price range makes ties and
+        // crosses common.
+        let num_base_atoms: u64 = if rng.below(4) == 0 {
+            1 + rng.below(10)
+        } else {
+            1 + rng.below(1_000_000)
+        };
+        let price: QuoteAtomsPerBaseAtom =
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(95 + rng.below(11) as u32, -2)
+                .unwrap();
+        let is_bid: bool = rng.below(2) == 0;
+        let order_type: OrderType = match rng.below(7) {
+            0 => OrderType::ImmediateOrCancel,
+            1 => OrderType::PostOnly,
+            2 => OrderType::Iceberg,
+            _ => OrderType::Limit,
+        };
+        let display_base_atoms: u64 = if order_type == OrderType::Iceberg {
+            1 + rng.below(num_base_atoms)
+        } else {
+            0
+        };
+        let last_valid_slot: u32 = if rng.below(2) == 0 {
+            NO_EXPIRATION_LAST_VALID_SLOT
+        } else {
+            now_slot + 1 + rng.below(20) as u32
+        };
+        let context: String = format!(
+            "seed {seed} step {step} slot {now_slot} {:?} {} {num_base_atoms}@{price} by {trader_index}",
+            order_type,
+            if is_bid { "bid" } else { "ask" },
+        );
+
+        if !market.has_free_block() {
+            market.market_expand().unwrap();
+        }
+        let market_before: MarketValue = MarketValue {
+            fixed: market.fixed,
+            dynamic: market.dynamic.clone(),
+        };
+        let reference_before: ReferenceMarket = reference.clone();
+
+        let result: Result<AddOrderToMarketResult, ProgramError> =
+            market.place_order(AddOrderToMarketArgs {
+                market: Pubkey::default(),
+                trader_index,
+                num_base_atoms: BaseAtoms::new(num_base_atoms),
+                price,
+                is_bid,
+                last_valid_slot,
+                order_type,
+                global_trade_accounts_opts: &[None, None],
+                current_slot: Some(now_slot),
+                fill_memo: false,
+                insert_hint_index: NIL,
+                min_fill_atoms: BaseAtoms::ZERO,
+                // Icebergs take their display size in place of a minimum.
+                min_taker_atoms: BaseAtoms::new(display_base_atoms),
+                tag: 0,
+                skip_global_orders: false,
+                self_trade_prevention: SelfTradePrevention::None,
+                referred: false,
+            });
+        let reference_result: Result<ReferenceResult, ProgramError> = reference.place(
+            trader_index,
+            num_base_atoms,
+            price,
+            is_bid,
+            order_type,
+            last_valid_slot,
+            display_base_atoms,
+            now_slot,
+        );
+
+        match (result, reference_result) {
+            (Ok(result), Ok(reference_result)) => {
+                assert_eq!(
+                    ReferenceResult {
+                        order_sequence_number: result.order_sequence_number,
+                        rested: result.order_index != NIL,
+                        base_atoms_traded: result.base_atoms_traded.as_u64(),
+                        quote_atoms_traded: result.quote_atoms_traded.as_u64(),
+                    },
+                    reference_result,
+                    "{context}"
+                );
+            }
+            (Err(error), Err(reference_error)) => {
+                assert_eq!(error, reference_error, "{context}");
+                // A failed instruction leaves no trace on chain.
+                market = market_before;
+                reference = reference_before;
+            }
+            (result, reference_result) => {
+                panic!(
+                    "{context}: engine ok={} reference ok={}",
+                    result.is_ok(),
+                    reference_result.is_ok()
+                );
+            }
+        }
+        assert_same_state(&market, &reference, &traders, &trader_indices, &context);
+    }
+}
+
+#[test]
+fn test_differential_against_reference_engine() {
+    for seed in 0..NUM_SEEDS {
+        run_stream(seed);
+    }
+}
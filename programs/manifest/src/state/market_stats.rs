@@ -0,0 +1,107 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use shank::ShankType;
+use static_assertions::const_assert_eq;
+use std::cmp::Ordering;
+
+use crate::quantities::{BaseAtoms, QuoteAtoms, WrapperU64};
+
+use super::constants::MARKET_STATS_SIZE;
+
+/// Lifetime fill statistics of a market created with
+/// MARKET_FLAG_VOLUME_STATS, for protocols that read them instead of
+/// replaying FillLogs. The price and slot of the latest fill are
+/// last_trade_price and last_trade_slot of MarketFixed. Lives in the block
+/// after the MarketFees, or where they would be. Volumes wrap on overflow.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct MarketStats {
+    base_volume: BaseAtoms,
+    quote_volume: QuoteAtoms,
+    num_fills: u64,
+    _padding: [u64; 5],
+}
+// 8 + // base_volume
+// 8 + // quote_volume
+// 8 + // num_fills
+// 5 * 8 // padding
+// = 64
+const_assert_eq!(size_of::<MarketStats>(), MARKET_STATS_SIZE);
+const_assert_eq!(size_of::<MarketStats>() % 8, 0);
+
+impl MarketStats {
+    pub fn get_base_volume(&self) -> BaseAtoms {
+        self.base_volume
+    }
+
+    pub fn get_quote_volume(&self) -> QuoteAtoms {
+        self.quote_volume
+    }
+
+    pub fn get_num_fills(&self) -> u64 {
+        self.num_fills
+    }
+
+    pub fn record_fill(&mut self, base_atoms: BaseAtoms, quote_atoms: QuoteAtoms) {
+        self.base_volume = self.base_volume.wrapping_add(base_atoms);
+        self.quote_volume = self.quote_volume.wrapping_add(quote_atoms);
+        self.num_fills = self.num_fills.wrapping_add(1);
+    }
+
+    /// Takes a busted fill back out of the stats.
+    pub fn remove_fill(&mut self, base_atoms: BaseAtoms, quote_atoms: QuoteAtoms) {
+        self.base_volume = self.base_volume.wrapping_sub(base_atoms);
+        self.quote_volume = self.quote_volume.wrapping_sub(quote_atoms);
+        self.num_fills = self.num_fills.wrapping_sub(1);
+    }
+}
+
+// Blocks need an ordering to be RBNode payloads, but the stats are never in a
+// tree.
+impl Ord for MarketStats {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.num_fills.cmp(&other.num_fills)
+    }
+}
+
+impl PartialOrd for MarketStats {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MarketStats {
+    fn eq(&self, other: &Self) -> bool {
+        self.num_fills == other.num_fills
+    }
+}
+
+impl Eq for MarketStats {}
+
+impl std::fmt::Display for MarketStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} fills, {} base atoms, {} quote atoms",
+            self.num_fills,
+            self.base_volume.as_u64(),
+            self.quote_volume.as_u64()
+        )
+    }
+}
+
+#[test]
+fn test_market_stats() {
+    let mut market_stats: MarketStats = MarketStats::default();
+    market_stats.record_fill(BaseAtoms::new(10), QuoteAtoms::new(25));
+    market_stats.record_fill(BaseAtoms::new(5), QuoteAtoms::new(u64::MAX));
+    assert_eq!(market_stats.get_base_volume(), BaseAtoms::new(15));
+    assert_eq!(market_stats.get_quote_volume(), QuoteAtoms::new(24));
+    assert_eq!(market_stats.get_num_fills(), 2);
+    market_stats.remove_fill(BaseAtoms::new(5), QuoteAtoms::new(u64::MAX));
+    assert_eq!(market_stats.get_base_volume(), BaseAtoms::new(10));
+    assert_eq!(market_stats.get_quote_volume(), QuoteAtoms::new(25));
+    assert_eq!(market_stats.get_num_fills(), 1);
+    let _ = format!("{}", market_stats);
+}
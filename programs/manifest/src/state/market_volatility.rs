@@ -0,0 +1,142 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::Get;
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use static_assertions::const_assert_eq;
+
+use crate::{
+    program::ManifestError, quantities::QuoteAtomsPerBaseAtom, require, validation::ManifestAccount,
+};
+
+use super::constants::{
+    MARKET_VOLATILITY_DISCRIMINANT, MAX_VOLATILITY_BPS, VOLATILITY_AVERAGE_SAMPLES,
+    VOLATILITY_SAMPLE_SLOTS,
+};
+
+/// State of the volatility estimate of a market. Each UpdateVolatility
+/// samples how far the last trade price moved since the previous sample and
+/// averages that into volatility_bps, which it copies to the market for
+/// reverse orders with DYNAMIC_REVERSE_SPREAD.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Zeroable, Pod)]
+pub struct MarketVolatility {
+    pub discriminant: u64,
+    pub market: Pubkey,
+    /// Last trade price at the previous sample, zero before the first.
+    sample_price: QuoteAtomsPerBaseAtom,
+    sample_slot: u32,
+    volatility_bps: u16,
+    _padding: [u8; 2],
+}
+const_assert_eq!(
+    size_of::<MarketVolatility>(),
+    8 +   // discriminant
+    32 +  // market
+    16 +  // sample_price
+    4 +   // sample_slot
+    2 +   // volatility_bps
+    2 // padding
+);
+const_assert_eq!(size_of::<MarketVolatility>() % 8, 0);
+impl Get for MarketVolatility {}
+
+impl ManifestAccount for MarketVolatility {
+    fn verify_discriminant(&self) -> ProgramResult {
+        require!(
+            self.discriminant == MARKET_VOLATILITY_DISCRIMINANT,
+            ProgramError::InvalidAccountData,
+            "Invalid market volatility discriminant actual: {} expected: {}",
+            self.discriminant,
+            MARKET_VOLATILITY_DISCRIMINANT
+        )?;
+        Ok(())
+    }
+}
+
+impl MarketVolatility {
+    pub fn new_empty(market: &Pubkey) -> Self {
+        MarketVolatility {
+            discriminant: MARKET_VOLATILITY_DISCRIMINANT,
+            market: *market,
+            sample_price: QuoteAtomsPerBaseAtom::ZERO,
+            sample_slot: 0,
+            volatility_bps: 0,
+            _padding: [0; 2],
+        }
+    }
+
+    pub fn get_sample_slot(&self) -> u32 {
+        self.sample_slot
+    }
+
+    pub fn get_volatility_bps(&self) -> u16 {
+        self.volatility_bps
+    }
+
+    /// Samples the move from the previous sample to price and returns the
+    /// new estimate. The first sample only records the price.
+    #[cfg(not(feature = "certora"))]
+    pub fn sample(
+        &mut self,
+        price: QuoteAtomsPerBaseAtom,
+        now_slot: u32,
+    ) -> Result<u16, ProgramError> {
+        if self.sample_price != QuoteAtomsPerBaseAtom::ZERO {
+            require!(
+                now_slot >= self.sample_slot.saturating_add(VOLATILITY_SAMPLE_SLOTS),
+                ManifestError::InvalidVolatilityUpdate,
+                "Sampled at slot {}, next sample after {} slots",
+                self.sample_slot,
+                VOLATILITY_SAMPLE_SLOTS,
+            )?;
+            let move_bps: u32 = price
+                .abs_diff_bps(self.sample_price)
+                .min(MAX_VOLATILITY_BPS as u64) as u32;
+            self.volatility_bps = ((self.volatility_bps as u32 * (VOLATILITY_AVERAGE_SAMPLES - 1)
+                + move_bps)
+                / VOLATILITY_AVERAGE_SAMPLES) as u16;
+        }
+        self.sample_price = price;
+        self.sample_slot = now_slot;
+        Ok(self.volatility_bps)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sample() {
+        let price = |mantissa: u32| {
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(mantissa, -2).unwrap()
+        };
+        let mut volatility: MarketVolatility = MarketVolatility::new_empty(&Pubkey::default());
+        assert_eq!(volatility.sample(price(100), 10).unwrap(), 0);
+        assert_eq!(volatility.get_sample_slot(), 10);
+
+        // Too soon after the previous sample.
+        assert!(volatility
+            .sample(price(110), 10 + VOLATILITY_SAMPLE_SLOTS - 1)
+            .is_err());
+
+        // A 10% move averages in as an eighth.
+        let slot: u32 = 10 + VOLATILITY_SAMPLE_SLOTS;
+        assert_eq!(volatility.sample(price(110), slot).unwrap(), 125);
+        // No move decays it.
+        let slot: u32 = slot + VOLATILITY_SAMPLE_SLOTS;
+        assert_eq!(volatility.sample(price(110), slot).unwrap(), 109);
+
+        // Moves are capped at MAX_VOLATILITY_BPS.
+        let slot: u32 = slot + VOLATILITY_SAMPLE_SLOTS;
+        assert_eq!(
+            volatility.sample(price(1_000), slot).unwrap(),
+            (109 * 7 + MAX_VOLATILITY_BPS) / 8
+        );
+        assert_eq!(
+            volatility.get_volatility_bps(),
+            (109 * 7 + MAX_VOLATILITY_BPS) / 8
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use shank::ShankType;
+use solana_program::entrypoint::ProgramResult;
+use static_assertions::const_assert_eq;
+use std::cmp::Ordering;
+
+use crate::{
+    program::ManifestError,
+    quantities::{BaseAtoms, QuoteAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
+    require,
+};
+
+use super::constants::MIN_ORDER_SIZE_SIZE;
+
+/// Smallest order that can rest on a market created with
+/// MARKET_FLAG_MIN_ORDER_SIZE, in base atoms and in quote atoms at the order
+/// price. Lives in the block after the CpiAllowlist if the market has one and
+/// in the first block otherwise, and cannot be changed after creation.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct MinOrderSize {
+    min_base_atoms: BaseAtoms,
+    min_quote_atoms: QuoteAtoms,
+    _padding: [u64; 6],
+}
+// 8 + // min_base_atoms
+// 8 + // min_quote_atoms
+// 6 * 8 // padding
+// = 64
+const_assert_eq!(size_of::<MinOrderSize>(), MIN_ORDER_SIZE_SIZE);
+const_assert_eq!(size_of::<MinOrderSize>() % 8, 0);
+
+impl MinOrderSize {
+    pub fn new(min_base_atoms: BaseAtoms, min_quote_atoms: QuoteAtoms) -> Self {
+        MinOrderSize {
+            min_base_atoms,
+            min_quote_atoms,
+            _padding: [0; 6],
+        }
+    }
+
+    pub fn get_min_base_atoms(&self) -> BaseAtoms {
+        self.min_base_atoms
+    }
+
+    pub fn get_min_quote_atoms(&self) -> QuoteAtoms {
+        self.min_quote_atoms
+    }
+
+    /// Fails for orders smaller than either minimum. The quote value rounds
+    /// down so an order exactly at the minimum passes.
+    pub fn verify(&self, num_base_atoms: BaseAtoms, price: QuoteAtomsPerBaseAtom) -> ProgramResult {
+        require!(
+            num_base_atoms >= self.min_base_atoms,
+            ManifestError::OrderTooSmall,
+            "Order of {} base atoms is below the market minimum of {}",
+            num_base_atoms.as_u64(),
+            self.min_base_atoms.as_u64(),
+        )?;
+        let quote_atoms: QuoteAtoms = price.checked_quote_for_base(num_base_atoms, false)?;
+        require!(
+            quote_atoms >= self.min_quote_atoms,
+            ManifestError::OrderTooSmall,
+            "Order worth {} quote atoms is below the market minimum of {}",
+            quote_atoms.as_u64(),
+            self.min_quote_atoms.as_u64(),
+        )?;
+        Ok(())
+    }
+}
+
+impl Ord for MinOrderSize {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.min_base_atoms, self.min_quote_atoms)
+            .cmp(&(other.min_base_atoms, other.min_quote_atoms))
+    }
+}
+
+impl PartialOrd for MinOrderSize {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MinOrderSize {
+    fn eq(&self, other: &Self) -> bool {
+        (self.min_base_atoms, self.min_quote_atoms) == (other.min_base_atoms, other.min_quote_atoms)
+    }
+}
+
+impl Eq for MinOrderSize {}
+
+impl std::fmt::Display for MinOrderSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} base atoms, {} quote atoms",
+            self.min_base_atoms.as_u64(),
+            self.min_quote_atoms.as_u64()
+        )
+    }
+}
+
+#[test]
+fn test_min_order_size_verify() {
+    let min_order_size: MinOrderSize = MinOrderSize::new(BaseAtoms::new(10), QuoteAtoms::new(50));
+    let price =
+        |mantissa: u32| QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(mantissa, 0).unwrap();
+    assert!(min_order_size.verify(BaseAtoms::new(10), price(5)).is_ok());
+    // Below the base minimum at any price.
+    assert!(min_order_size
+        .verify(BaseAtoms::new(9), price(100))
+        .is_err());
+    // Enough base but not enough quote.
+    assert!(min_order_size.verify(BaseAtoms::new(12), price(4)).is_err());
+    assert!(min_order_size.verify(BaseAtoms::new(13), price(4)).is_ok());
+    let _ = format!("{}", min_order_size);
+}
@@ -1,13 +1,51 @@
 pub mod claimed_seat;
 pub mod constants;
+pub mod cpi_allowlist;
 pub mod dynamic_account;
+pub mod fill_records;
 pub mod global;
+pub mod heartbeat;
 pub mod market;
+pub mod market_extension;
+pub mod market_fees;
+pub mod market_stats;
+pub mod market_volatility;
+pub mod min_order_size;
+pub mod pegged_orders;
+pub mod price_band;
 pub mod resting_order;
+pub mod rfq;
+pub mod seat_archive;
+pub mod seat_config;
+pub mod seat_mmp;
+pub mod seat_volume;
+pub mod signed_order_nonces;
+pub mod stop_orders;
+pub mod swap_commitment;
+pub mod trade_bust;
+pub mod trading_hours;
 pub mod utils;
 
 pub use constants::*;
 pub use dynamic_account::*;
+pub use fill_records::*;
 pub use global::*;
+pub use heartbeat::*;
 pub use market::*;
+pub use market_extension::*;
+pub use market_fees::*;
+pub use market_stats::*;
+pub use market_volatility::*;
+pub use min_order_size::*;
+pub use pegged_orders::*;
+pub use price_band::*;
 pub use resting_order::*;
+pub use rfq::*;
+pub use seat_archive::*;
+pub use seat_mmp::*;
+pub use seat_volume::*;
+pub use signed_order_nonces::*;
+pub use stop_orders::*;
+pub use swap_commitment::*;
+pub use trade_bust::*;
+pub use trading_hours::*;
@@ -0,0 +1,499 @@
+use std::mem::size_of;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
+use hypertree::{DataIndex, Get, PodBool, NIL};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use shank::ShankType;
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use static_assertions::const_assert_eq;
+
+use crate::{
+    program::ManifestError,
+    quantities::{BaseAtoms, QuoteAtomsPerBaseAtom},
+    require,
+    validation::ManifestAccount,
+};
+
+use super::constants::{
+    MAX_PEGGED_ORDERS, NO_EXPIRATION_LAST_VALID_SLOT, PEGGED_ORDERS_DISCRIMINANT,
+};
+
+#[derive(
+    Debug,
+    BorshDeserialize,
+    BorshSerialize,
+    PartialEq,
+    Clone,
+    Copy,
+    Default,
+    ShankType,
+    IntoPrimitive,
+    TryFromPrimitive,
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+#[repr(u8)]
+pub enum PegReference {
+    // Best bid on the book.
+    #[default]
+    BestBid = 0,
+
+    // Best ask on the book.
+    BestAsk = 1,
+
+    // Halfway between the best bid and the best ask. Needs both sides.
+    Mid = 2,
+}
+unsafe impl bytemuck::Zeroable for PegReference {}
+unsafe impl bytemuck::Pod for PegReference {}
+
+/// Order whose price is offset_bps away from a reference price of the book,
+/// never worse for the trader than limit_price. RepricePeggedOrders places it
+/// on the book at that price and moves it when the reference moves.
+/// order_index is NIL while it is not on the book. Empty when num_base_atoms
+/// is zero.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod)]
+pub struct PeggedOrder {
+    limit_price: QuoteAtomsPerBaseAtom,
+    /// Left to fill, the size of the resting order while on the book.
+    num_base_atoms: BaseAtoms,
+    /// Sequence number of the resting order while on the book.
+    order_sequence_number: u64,
+    last_valid_slot: u32,
+    order_index: DataIndex,
+    /// Signed, positive is a higher price on either side.
+    offset_bps: i16,
+    is_bid: PodBool,
+    reference: PegReference,
+    _padding: [u8; 4],
+}
+// 16 + // limit_price
+//  8 + // num_base_atoms
+//  8 + // order_sequence_number
+//  4 + // last_valid_slot
+//  4 + // order_index
+//  2 + // offset_bps
+//  1 + // is_bid
+//  1 + // reference
+//  4   // padding
+// = 48
+const_assert_eq!(size_of::<PeggedOrder>(), 48);
+const_assert_eq!(size_of::<PeggedOrder>() % 8, 0);
+
+impl PeggedOrder {
+    pub fn get_limit_price(&self) -> QuoteAtomsPerBaseAtom {
+        self.limit_price
+    }
+
+    pub fn get_num_base_atoms(&self) -> BaseAtoms {
+        self.num_base_atoms
+    }
+
+    pub fn get_order_sequence_number(&self) -> u64 {
+        self.order_sequence_number
+    }
+
+    pub fn get_last_valid_slot(&self) -> u32 {
+        self.last_valid_slot
+    }
+
+    pub fn get_order_index(&self) -> DataIndex {
+        self.order_index
+    }
+
+    pub fn get_offset_bps(&self) -> i16 {
+        self.offset_bps
+    }
+
+    pub fn get_is_bid(&self) -> bool {
+        self.is_bid.0 == 1
+    }
+
+    pub fn get_reference(&self) -> PegReference {
+        self.reference
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_base_atoms == BaseAtoms::ZERO
+    }
+
+    pub fn is_on_book(&self) -> bool {
+        !self.is_empty() && self.order_index != NIL
+    }
+
+    pub fn is_expired(&self, current_slot: u32) -> bool {
+        self.last_valid_slot != NO_EXPIRATION_LAST_VALID_SLOT && self.last_valid_slot < current_slot
+    }
+
+    /// Price of the order given the best prices of the book without it. None
+    /// when the reference side is empty or the price is out of range. Bids
+    /// round down and asks round up, then both are capped at limit_price.
+    pub fn get_pegged_price(
+        &self,
+        best_bid: Option<QuoteAtomsPerBaseAtom>,
+        best_ask: Option<QuoteAtomsPerBaseAtom>,
+    ) -> Option<QuoteAtomsPerBaseAtom> {
+        let is_bid: bool = self.get_is_bid();
+        let reference: QuoteAtomsPerBaseAtom = match self.reference {
+            PegReference::BestBid => best_bid?,
+            PegReference::BestAsk => best_ask?,
+            PegReference::Mid => {
+                let (bid, ask) = (best_bid?, best_ask?);
+                if is_bid {
+                    bid.move_toward(ask, 1, 2)
+                } else {
+                    ask.move_toward(bid, 1, 2)
+                }
+            }
+        };
+        let numerator: u32 = (10_000 + self.offset_bps as i32) as u32;
+        let price: Option<QuoteAtomsPerBaseAtom> = reference
+            .checked_multiply_rational(numerator, 10_000, !is_bid)
+            .ok()
+            .filter(|price| *price <= QuoteAtomsPerBaseAtom::MAX);
+        if is_bid {
+            let price: QuoteAtomsPerBaseAtom = price.map_or(self.limit_price, |price| {
+                std::cmp::min(price, self.limit_price)
+            });
+            Some(price).filter(|price| *price > QuoteAtomsPerBaseAtom::ZERO)
+        } else {
+            price.map(|price| std::cmp::max(price, self.limit_price))
+        }
+    }
+}
+
+/// Pegged orders of a trader on a market. Their funds are only locked while
+/// RepricePeggedOrders has them on the book, where they rest as normal orders
+/// of the seat that match at their current price. Orders that leave the book
+/// other than through RepricePeggedOrders, filled or cancelled, end their
+/// pegged order. The account is kept when empty and reused.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Zeroable, Pod)]
+pub struct PeggedOrders {
+    pub discriminant: u64,
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    orders: [PeggedOrder; MAX_PEGGED_ORDERS],
+}
+const_assert_eq!(
+    size_of::<PeggedOrders>(),
+    8 +   // discriminant
+    32 +  // market
+    32 +  // trader
+    48 * MAX_PEGGED_ORDERS // orders
+);
+const_assert_eq!(size_of::<PeggedOrders>() % 8, 0);
+impl Get for PeggedOrders {}
+
+impl ManifestAccount for PeggedOrders {
+    fn verify_discriminant(&self) -> ProgramResult {
+        require!(
+            self.discriminant == PEGGED_ORDERS_DISCRIMINANT,
+            ProgramError::InvalidAccountData,
+            "Invalid pegged orders discriminant actual: {} expected: {}",
+            self.discriminant,
+            PEGGED_ORDERS_DISCRIMINANT
+        )?;
+        Ok(())
+    }
+}
+
+impl PeggedOrders {
+    pub fn new_empty(market: &Pubkey, trader: &Pubkey) -> Self {
+        PeggedOrders {
+            discriminant: PEGGED_ORDERS_DISCRIMINANT,
+            market: *market,
+            trader: *trader,
+            orders: [PeggedOrder::default(); MAX_PEGGED_ORDERS],
+        }
+    }
+
+    pub fn get_orders(&self) -> &[PeggedOrder; MAX_PEGGED_ORDERS] {
+        &self.orders
+    }
+
+    /// Adds a pegged order off the book in the first empty slot and returns
+    /// the slot.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add(
+        &mut self,
+        num_base_atoms: BaseAtoms,
+        is_bid: bool,
+        reference: PegReference,
+        offset_bps: i16,
+        limit_price: QuoteAtomsPerBaseAtom,
+        last_valid_slot: u32,
+        now_slot: u32,
+    ) -> Result<u8, ProgramError> {
+        require!(
+            num_base_atoms > BaseAtoms::ZERO,
+            ManifestError::InvalidPeggedOrder,
+            "Pegged order needs a size",
+        )?;
+        require!(
+            offset_bps > -10_000,
+            ManifestError::InvalidPeggedOrder,
+            "Pegged order offset by {} bps",
+            offset_bps,
+        )?;
+        require!(
+            last_valid_slot == NO_EXPIRATION_LAST_VALID_SLOT || last_valid_slot > now_slot,
+            ManifestError::InvalidPeggedOrder,
+            "Pegged order expired at slot {}",
+            last_valid_slot,
+        )?;
+        let index: usize = self
+            .orders
+            .iter()
+            .position(|order| order.is_empty())
+            .ok_or(ManifestError::InvalidPeggedOrder)?;
+        self.orders[index] = PeggedOrder {
+            limit_price,
+            num_base_atoms,
+            order_sequence_number: 0,
+            last_valid_slot,
+            order_index: NIL,
+            offset_bps,
+            is_bid: PodBool::from_bool(is_bid),
+            reference,
+            _padding: [0; 4],
+        };
+        Ok(index as u8)
+    }
+
+    fn get_order_mut(&mut self, index: u8) -> Result<&mut PeggedOrder, ProgramError> {
+        Ok(self
+            .orders
+            .get_mut(index as usize)
+            .filter(|order| !order.is_empty())
+            .ok_or(ManifestError::InvalidPeggedOrder)?)
+    }
+
+    /// Records the resting order the pegged order in slot index was placed as.
+    pub fn set_on_book(
+        &mut self,
+        index: u8,
+        order_sequence_number: u64,
+        order_index: DataIndex,
+    ) -> ProgramResult {
+        let order: &mut PeggedOrder = self.get_order_mut(index)?;
+        order.order_sequence_number = order_sequence_number;
+        order.order_index = order_index;
+        Ok(())
+    }
+
+    /// Takes the pegged order in slot index off the book with the size left.
+    pub fn set_off_book(&mut self, index: u8) -> ProgramResult {
+        self.get_order_mut(index)?.order_index = NIL;
+        Ok(())
+    }
+
+    /// Size left of the pegged order in slot index after its resting order
+    /// was partially filled.
+    pub fn set_num_base_atoms(&mut self, index: u8, num_base_atoms: BaseAtoms) -> ProgramResult {
+        require!(
+            num_base_atoms > BaseAtoms::ZERO,
+            ManifestError::InvalidPeggedOrder,
+            "Pegged order needs a size",
+        )?;
+        self.get_order_mut(index)?.num_base_atoms = num_base_atoms;
+        Ok(())
+    }
+
+    /// Removes and returns the pegged order in slot index.
+    pub fn remove(&mut self, index: u8) -> Result<PeggedOrder, ProgramError> {
+        let order: &mut PeggedOrder = self.get_order_mut(index)?;
+        let removed: PeggedOrder = *order;
+        *order = PeggedOrder::default();
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::quantities::WrapperU64;
+
+    fn price(mantissa: u32) -> QuoteAtomsPerBaseAtom {
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(mantissa, 0).unwrap()
+    }
+
+    #[test]
+    fn test_pegged_orders() {
+        let mut pegged_orders: PeggedOrders =
+            PeggedOrders::new_empty(&Pubkey::default(), &Pubkey::default());
+
+        assert!(pegged_orders
+            .add(
+                BaseAtoms::ZERO,
+                true,
+                PegReference::BestBid,
+                0,
+                price(10),
+                0,
+                10
+            )
+            .is_err());
+        assert!(pegged_orders
+            .add(
+                BaseAtoms::ONE,
+                true,
+                PegReference::BestBid,
+                -10_000,
+                price(10),
+                0,
+                10
+            )
+            .is_err());
+        assert!(pegged_orders
+            .add(
+                BaseAtoms::ONE,
+                true,
+                PegReference::BestBid,
+                0,
+                price(10),
+                5,
+                10
+            )
+            .is_err());
+
+        let bid_index: u8 = pegged_orders
+            .add(
+                BaseAtoms::new(10),
+                true,
+                PegReference::BestBid,
+                0,
+                price(10),
+                0,
+                10,
+            )
+            .unwrap();
+        let ask_index: u8 = pegged_orders
+            .add(
+                BaseAtoms::ONE,
+                false,
+                PegReference::BestAsk,
+                0,
+                price(10),
+                20,
+                10,
+            )
+            .unwrap();
+        assert_eq!((bid_index, ask_index), (0, 1));
+        assert!(!pegged_orders.get_orders()[0].is_on_book());
+        assert!(!pegged_orders.get_orders()[0].is_expired(u32::MAX));
+        assert!(!pegged_orders.get_orders()[1].is_expired(20));
+        assert!(pegged_orders.get_orders()[1].is_expired(21));
+
+        pegged_orders.set_on_book(bid_index, 7, 3).unwrap();
+        let bid: PeggedOrder = pegged_orders.get_orders()[0];
+        assert!(bid.is_on_book());
+        assert_eq!(
+            (bid.get_order_sequence_number(), bid.get_order_index()),
+            (7, 3)
+        );
+        assert!(pegged_orders
+            .set_num_base_atoms(bid_index, BaseAtoms::ZERO)
+            .is_err());
+        pegged_orders
+            .set_num_base_atoms(bid_index, BaseAtoms::new(4))
+            .unwrap();
+        pegged_orders.set_off_book(bid_index).unwrap();
+        let bid: PeggedOrder = pegged_orders.get_orders()[0];
+        assert!(!bid.is_on_book());
+        assert_eq!(bid.get_num_base_atoms(), BaseAtoms::new(4));
+
+        pegged_orders.remove(bid_index).unwrap();
+        assert!(pegged_orders.remove(bid_index).is_err());
+        assert!(pegged_orders.set_on_book(bid_index, 7, 3).is_err());
+        assert!(pegged_orders.remove(MAX_PEGGED_ORDERS as u8).is_err());
+
+        for _ in 0..MAX_PEGGED_ORDERS - 1 {
+            pegged_orders
+                .add(BaseAtoms::ONE, true, PegReference::Mid, 0, price(10), 0, 10)
+                .unwrap();
+        }
+        assert!(pegged_orders
+            .add(BaseAtoms::ONE, true, PegReference::Mid, 0, price(10), 0, 10)
+            .is_err());
+    }
+
+    #[test]
+    fn test_pegged_price() {
+        let mut pegged_orders: PeggedOrders =
+            PeggedOrders::new_empty(&Pubkey::default(), &Pubkey::default());
+        // Bid 1% under the best bid up to 100.
+        pegged_orders
+            .add(
+                BaseAtoms::ONE,
+                true,
+                PegReference::BestBid,
+                -100,
+                price(100),
+                0,
+                10,
+            )
+            .unwrap();
+        // Ask 1% over the best bid down to 100.
+        pegged_orders
+            .add(
+                BaseAtoms::ONE,
+                false,
+                PegReference::BestBid,
+                100,
+                price(100),
+                0,
+                10,
+            )
+            .unwrap();
+        // Both at the mid.
+        pegged_orders
+            .add(
+                BaseAtoms::ONE,
+                true,
+                PegReference::Mid,
+                0,
+                price(1_000),
+                0,
+                10,
+            )
+            .unwrap();
+        pegged_orders
+            .add(BaseAtoms::ONE, false, PegReference::Mid, 0, price(1), 0, 10)
+            .unwrap();
+        let [bid, ask, mid_bid, mid_ask, ..] = *pegged_orders.get_orders();
+
+        assert_eq!(bid.get_pegged_price(None, Some(price(50))), None);
+        assert_eq!(
+            bid.get_pegged_price(Some(price(50)), None),
+            Some(QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(495, -1).unwrap())
+        );
+        // Capped at the limit price.
+        assert_eq!(
+            bid.get_pegged_price(Some(price(200)), None),
+            Some(price(100))
+        );
+        assert_eq!(
+            ask.get_pegged_price(Some(price(50)), None),
+            Some(price(100))
+        );
+        assert_eq!(
+            ask.get_pegged_price(Some(price(200)), None),
+            Some(price(202))
+        );
+
+        assert_eq!(mid_bid.get_pegged_price(Some(price(100)), None), None);
+        assert_eq!(
+            mid_bid.get_pegged_price(Some(price(100)), Some(price(120))),
+            Some(price(110))
+        );
+        assert_eq!(
+            mid_ask.get_pegged_price(Some(price(100)), Some(price(120))),
+            Some(price(110))
+        );
+    }
+}
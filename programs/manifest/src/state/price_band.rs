@@ -0,0 +1,168 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use shank::ShankType;
+use solana_program::entrypoint::ProgramResult;
+use static_assertions::const_assert_eq;
+use std::cmp::Ordering;
+
+use crate::{program::ManifestError, quantities::QuoteAtomsPerBaseAtom, require};
+
+use super::constants::{PRICE_BAND_RESET_SLOTS, PRICE_BAND_SIZE};
+
+/// Circuit breaker of a market created with MARKET_FLAG_PRICE_BAND. Takers
+/// cannot fill further than band_bps from the reference price, which is the
+/// last trade price unless a ResetPriceBand came after the last trade. Lives
+/// in the block after the CpiAllowlist and MinOrderSize the market has, and
+/// only the reference changes after creation.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct PriceBand {
+    band_bps: u16,
+    _padding: [u8; 2],
+    reference_slot: u32,
+    reference_price: QuoteAtomsPerBaseAtom,
+    _padding2: [u64; 5],
+}
+// 2 + // band_bps
+// 2 + // padding
+// 4 + // reference_slot
+// 16 + // reference_price
+// 5 * 8 // padding2
+// = 64
+const_assert_eq!(size_of::<PriceBand>(), PRICE_BAND_SIZE);
+const_assert_eq!(size_of::<PriceBand>() % 8, 0);
+
+impl PriceBand {
+    pub fn new(band_bps: u16) -> Self {
+        PriceBand {
+            band_bps,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_band_bps(&self) -> u16 {
+        self.band_bps
+    }
+
+    /// Reference price and the slot it is from, whichever of the last trade
+    /// and the last reset is newer. Zero before either.
+    pub fn get_reference(
+        &self,
+        last_trade_price: QuoteAtomsPerBaseAtom,
+        last_trade_slot: u32,
+    ) -> (QuoteAtomsPerBaseAtom, u32) {
+        if self.reference_price != QuoteAtomsPerBaseAtom::ZERO
+            && self.reference_slot >= last_trade_slot
+        {
+            (self.reference_price, self.reference_slot)
+        } else {
+            (last_trade_price, last_trade_slot)
+        }
+    }
+
+    /// Fails for a fill more than band_bps away from reference. Anything goes
+    /// before the first reference.
+    pub fn verify(
+        &self,
+        price: QuoteAtomsPerBaseAtom,
+        reference: QuoteAtomsPerBaseAtom,
+    ) -> ProgramResult {
+        if reference == QuoteAtomsPerBaseAtom::ZERO {
+            return Ok(());
+        }
+        require!(
+            price.abs_diff_bps(reference) <= self.band_bps as u64,
+            ManifestError::PriceBandExceeded,
+            "Fill at {} is more than {} bps from the reference {}",
+            price,
+            self.band_bps,
+            reference,
+        )?;
+        Ok(())
+    }
+
+    /// Moves the reference to price once there has been no trade or reset for
+    /// PRICE_BAND_RESET_SLOTS, so a market whose price gapped past the band
+    /// can trade again.
+    pub fn reset(
+        &mut self,
+        price: QuoteAtomsPerBaseAtom,
+        last_trade_price: QuoteAtomsPerBaseAtom,
+        last_trade_slot: u32,
+        now_slot: u32,
+    ) -> ProgramResult {
+        let (_reference, reference_slot) = self.get_reference(last_trade_price, last_trade_slot);
+        require!(
+            now_slot >= reference_slot.saturating_add(PRICE_BAND_RESET_SLOTS),
+            ManifestError::InvalidPriceBandReset,
+            "Reference is from slot {}, reset after {} slots",
+            reference_slot,
+            PRICE_BAND_RESET_SLOTS,
+        )?;
+        self.reference_price = price;
+        self.reference_slot = now_slot;
+        Ok(())
+    }
+}
+
+impl Ord for PriceBand {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.band_bps.cmp(&other.band_bps)
+    }
+}
+
+impl PartialOrd for PriceBand {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for PriceBand {
+    fn eq(&self, other: &Self) -> bool {
+        self.band_bps == other.band_bps
+    }
+}
+
+impl Eq for PriceBand {}
+
+impl std::fmt::Display for PriceBand {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} bps around {}", self.band_bps, self.reference_price)
+    }
+}
+
+#[test]
+fn test_price_band() {
+    let price = |mantissa: u32| {
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(mantissa, -2).unwrap()
+    };
+    let mut price_band: PriceBand = PriceBand::new(500);
+    assert_eq!(price_band.get_band_bps(), 500);
+
+    // No reference before the first trade.
+    let (reference, _) = price_band.get_reference(QuoteAtomsPerBaseAtom::ZERO, 0);
+    assert!(price_band.verify(price(1_000), reference).is_ok());
+
+    let (reference, reference_slot) = price_band.get_reference(price(100), 10);
+    assert_eq!(reference_slot, 10);
+    assert!(price_band.verify(price(105), reference).is_ok());
+    assert!(price_band.verify(price(95), reference).is_ok());
+    assert!(price_band.verify(price(106), reference).is_err());
+    assert!(price_band.verify(price(94), reference).is_err());
+
+    // Too soon after the last trade.
+    assert!(price_band
+        .reset(price(200), price(100), 10, 10 + PRICE_BAND_RESET_SLOTS - 1)
+        .is_err());
+    let slot: u32 = 10 + PRICE_BAND_RESET_SLOTS;
+    price_band.reset(price(200), price(100), 10, slot).unwrap();
+    let (reference, _) = price_band.get_reference(price(100), 10);
+    assert!(price_band.verify(price(205), reference).is_ok());
+    assert!(price_band.verify(price(105), reference).is_err());
+
+    // A later trade takes over.
+    let (reference, _) = price_band.get_reference(price(210), slot + 1);
+    assert_eq!(reference, price(210));
+    let _ = format!("{}", price_band);
+}
@@ -1,10 +1,10 @@
-use std::mem::size_of;
+use std::mem::{offset_of, size_of};
 
+#[cfg(feature = "certora")]
+use crate::quantities::QuoteAtoms;
 use crate::quantities::{
-    u64_slice_to_u128, BaseAtoms, PriceConversionError, QuoteAtomsPerBaseAtom,
+    u64_slice_to_u128, BaseAtoms, PriceConversionError, QuoteAtomsPerBaseAtom, WrapperU64,
 };
-#[cfg(feature = "certora")]
-use crate::quantities::{QuoteAtoms, WrapperU64};
 use borsh::{BorshDeserialize, BorshSerialize};
 use bytemuck::{Pod, Zeroable};
 use hypertree::{DataIndex, PodBool};
@@ -14,7 +14,15 @@ use solana_program::{entrypoint::ProgramResult, program_error::ProgramError};
 use static_assertions::const_assert_eq;
 use std::cmp::Ordering;
 
-use super::{constants::NO_EXPIRATION_LAST_VALID_SLOT, RESTING_ORDER_SIZE};
+use super::{
+    constants::{DYNAMIC_REVERSE_SPREAD, MAX_VOLATILITY_BPS, NO_EXPIRATION_LAST_VALID_SLOT},
+    utils::get_now_unix_timestamp,
+    RESTING_ORDER_SIZE,
+};
+
+// Set in place of the spread of orders that cannot reverse when their
+// last_valid_slot is a unix timestamp.
+const EXPIRES_BY_UNIX_TIMESTAMP: u16 = 1;
 
 #[derive(
     Debug,
@@ -27,6 +35,10 @@ use super::{constants::NO_EXPIRATION_LAST_VALID_SLOT, RESTING_ORDER_SIZE};
     IntoPrimitive,
     TryFromPrimitive,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
 #[repr(u8)]
 pub enum OrderType {
     // Normal limit order.
@@ -49,6 +61,15 @@ pub enum OrderType {
     // Same as a reverse order except that it much tighter, allowing for stables
     // to have even smaller spreads.
     ReverseTight = 5,
+
+    // Post only that does not fail when it would cross. It rests one price
+    // step inside the opposing best instead, see checked_step.
+    PostOnlySlide = 6,
+
+    // Limit order that shows display size at a time. Once the shown tranche
+    // fills, the next goes to the back of the price level with a new sequence
+    // number, see get_visible_base_atoms.
+    Iceberg = 7,
 }
 unsafe impl bytemuck::Zeroable for OrderType {}
 unsafe impl bytemuck::Pod for OrderType {}
@@ -71,12 +92,52 @@ impl OrderType {
     }
 }
 
+/// What happens when an order would match a resting order from its own
+/// seat. Only applies to the order being placed, not to the resting order.
+#[derive(
+    Debug,
+    BorshDeserialize,
+    BorshSerialize,
+    PartialEq,
+    Clone,
+    Copy,
+    Default,
+    ShankType,
+    IntoPrimitive,
+    TryFromPrimitive,
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)
+)]
+#[repr(u8)]
+pub enum SelfTradePrevention {
+    // Match like any other order. Fails on markets with
+    // MARKET_FLAG_NO_INTERNAL_CROSS.
+    #[default]
+    None = 0,
+
+    // Cancel the resting order and keep matching.
+    CancelProvide = 1,
+
+    // Cancel the rest of the order being placed. Earlier fills stay.
+    CancelTake = 2,
+
+    // Reduce both orders by the smaller size without a trade, then keep
+    // matching with whatever is left.
+    DecrementBoth = 3,
+
+    // Fail the place.
+    Reject = 4,
+}
 pub fn order_type_can_rest(order_type: OrderType) -> bool {
     order_type != OrderType::ImmediateOrCancel
 }
 
 pub fn order_type_can_take(order_type: OrderType) -> bool {
-    order_type != OrderType::PostOnly && order_type != OrderType::Global
+    order_type != OrderType::PostOnly
+        && order_type != OrderType::Global
+        && order_type != OrderType::PostOnlySlide
 }
 
 #[repr(C)]
@@ -89,9 +150,22 @@ pub struct RestingOrder {
     last_valid_slot: u32,
     is_bid: PodBool,
     order_type: OrderType,
-    // Spread for reverse orders. Defaults to zero.
+    // Spread for reverse orders. Defaults to zero. DYNAMIC_REVERSE_SPREAD
+    // follows the market volatility estimate instead. Other orders have
+    // EXPIRES_BY_UNIX_TIMESTAMP here when last_valid_slot is a unix timestamp.
     reverse_spread: u16,
-    _padding: [u8; 20],
+    // Opaque tag set by the trader, echoed in fills. Defaults to zero.
+    tag: u32,
+    // Takers that would fill less than this skip the order, unless they take
+    // all of it. Defaults to zero. Iceberg orders keep their display size here
+    // instead and have no minimum.
+    min_taker_atoms: BaseAtoms,
+    // Slot the order was put on the book, for the market's minimum resting
+    // time. Zero for orders placed before it was tracked.
+    placed_slot: u32,
+    // Other order of a one cancels other pair, see get_linked_index. Stored
+    // plus one so that zero, the padding of older orders, is no link.
+    linked_index_plus_one: u32,
 }
 
 // 16 +  // price
@@ -102,10 +176,27 @@ pub struct RestingOrder {
 //  1 +  // is_bid
 //  1 +  // order_type
 //  2 +  // spread
-// 20    // padding 2
+//  4 +  // tag
+//  8 +  // min_taker_atoms
+//  4 +  // placed_slot
+//  4    // linked_index_plus_one
 // = 64
 const_assert_eq!(size_of::<RestingOrder>(), RESTING_ORDER_SIZE);
 const_assert_eq!(size_of::<RestingOrder>() % 8, 0);
+// Orders already on the book keep their bytes across upgrades, so offsets are
+// fixed. New fields take from the padding.
+const_assert_eq!(offset_of!(RestingOrder, price), 0);
+const_assert_eq!(offset_of!(RestingOrder, num_base_atoms), 16);
+const_assert_eq!(offset_of!(RestingOrder, sequence_number), 24);
+const_assert_eq!(offset_of!(RestingOrder, trader_index), 32);
+const_assert_eq!(offset_of!(RestingOrder, last_valid_slot), 36);
+const_assert_eq!(offset_of!(RestingOrder, is_bid), 40);
+const_assert_eq!(offset_of!(RestingOrder, order_type), 41);
+const_assert_eq!(offset_of!(RestingOrder, reverse_spread), 42);
+const_assert_eq!(offset_of!(RestingOrder, tag), 44);
+const_assert_eq!(offset_of!(RestingOrder, min_taker_atoms), 48);
+const_assert_eq!(offset_of!(RestingOrder, placed_slot), 56);
+const_assert_eq!(offset_of!(RestingOrder, linked_index_plus_one), 60);
 
 impl RestingOrder {
     pub fn new(
@@ -130,7 +221,10 @@ impl RestingOrder {
             is_bid: PodBool::from_bool(is_bid),
             order_type,
             reverse_spread: 0,
-            _padding: Default::default(),
+            tag: 0,
+            min_taker_atoms: BaseAtoms::ZERO,
+            placed_slot: 0,
+            linked_index_plus_one: 0,
         })
     }
 
@@ -167,23 +261,77 @@ impl RestingOrder {
         self.order_type.is_reversible()
     }
 
-    pub fn reverse_price(&self) -> Result<QuoteAtomsPerBaseAtom, PriceConversionError> {
+    pub fn is_iceberg(&self) -> bool {
+        self.order_type == OrderType::Iceberg
+    }
+
+    /// Size an iceberg order shows at a time, zero for other orders.
+    pub fn get_display_base_atoms(&self) -> BaseAtoms {
+        if self.is_iceberg() {
+            self.min_taker_atoms
+        } else {
+            BaseAtoms::ZERO
+        }
+    }
+
+    /// Size that trades before the order goes to the back of its price level.
+    /// Icebergs show what is left over a whole number of display sizes, so
+    /// an odd lot shows first and every later tranche is the display size.
+    pub fn get_visible_base_atoms(&self) -> BaseAtoms {
+        let display_base_atoms: u64 = self.get_display_base_atoms().as_u64();
+        if display_base_atoms == 0 || self.num_base_atoms == BaseAtoms::ZERO {
+            return self.num_base_atoms;
+        }
+        BaseAtoms::new((self.num_base_atoms.as_u64() - 1) % display_base_atoms + 1)
+    }
+
+    /// Copy of the order with a new size, price and sequence number, to put
+    /// back on the book behind the orders already at its price.
+    pub fn requeued(
+        &self,
+        num_base_atoms: BaseAtoms,
+        price: QuoteAtomsPerBaseAtom,
+        sequence_number: u64,
+        placed_slot: u32,
+    ) -> Self {
+        RestingOrder {
+            num_base_atoms,
+            price,
+            sequence_number,
+            placed_slot,
+            ..*self
+        }
+    }
+
+    /// Price the order comes back at once filled. Orders with
+    /// DYNAMIC_REVERSE_SPREAD use volatility_bps, the market estimate, as
+    /// their spread.
+    pub fn reverse_price(
+        &self,
+        volatility_bps: u16,
+    ) -> Result<QuoteAtomsPerBaseAtom, PriceConversionError> {
         let base = match self.order_type {
             OrderType::Reverse => 100_000_u32,
             OrderType::ReverseTight => 100_000_000_u32,
             _ => return Ok(self.price),
         };
+        let spread: u32 = if self.reverse_spread == DYNAMIC_REVERSE_SPREAD {
+            // A basis point is base / 10_000 in units of the spread.
+            volatility_bps.min(MAX_VOLATILITY_BPS) as u32 * (base / 10_000)
+        } else {
+            self.reverse_spread as u32
+        };
 
         if self.get_is_bid() {
             // Bid @P * (1 - spread) --> Ask @P
             // equivalent to
             // Bid @P --> Ask @P / (1 - spread)
             self.price
-                .checked_multiply_rational(base, base - self.reverse_spread as u32, false)
+                .checked_multiply_rational(base, base - spread, false)
         } else {
             // Ask @P --> Bid @P * (1 - spread)
             self.price
-                .checked_multiply_rational(base - self.reverse_spread as u32, base, true)
+                .checked_multiply_rational(base - spread, base, true)
         }
     }
 
@@ -195,12 +343,98 @@ impl RestingOrder {
         self.reverse_spread = spread;
     }
 
+    pub fn get_min_taker_atoms(&self) -> BaseAtoms {
+        if self.is_iceberg() {
+            BaseAtoms::ZERO
+        } else {
+            self.min_taker_atoms
+        }
+    }
+
+    pub fn set_min_taker_atoms(&mut self, min_taker_atoms: BaseAtoms) {
+        self.min_taker_atoms = min_taker_atoms;
+    }
+
+    pub fn get_tag(&self) -> u32 {
+        self.tag
+    }
+
+    pub fn set_tag(&mut self, tag: u32) {
+        self.tag = tag;
+    }
+
+    pub fn get_placed_slot(&self) -> u32 {
+        self.placed_slot
+    }
+
+    pub fn set_placed_slot(&mut self, placed_slot: u32) {
+        self.placed_slot = placed_slot;
+    }
+
+    /// Order cancelled when this one fills, NIL when not linked. Links are
+    /// mutual and removed with either order, so this is always on the book.
+    pub fn get_linked_index(&self) -> DataIndex {
+        self.linked_index_plus_one.wrapping_sub(1)
+    }
+
+    pub fn set_linked_index(&mut self, linked_index: DataIndex) {
+        self.linked_index_plus_one = linked_index.wrapping_add(1);
+    }
+
+    /// Whether the order has been on the book for min_resting_slots, so that
+    /// its maker may cancel it.
+    pub fn has_rested(&self, now_slot: u32, min_resting_slots: u32) -> bool {
+        min_resting_slots == 0 || now_slot >= self.placed_slot.saturating_add(min_resting_slots)
+    }
+
     pub fn get_sequence_number(&self) -> u64 {
         self.sequence_number
     }
 
+    /// NO_EXPIRATION_LAST_VALID_SLOT for orders that expire by unix timestamp.
+    pub fn get_last_valid_slot(&self) -> u32 {
+        if self.expires_by_unix_timestamp() {
+            NO_EXPIRATION_LAST_VALID_SLOT
+        } else {
+            self.last_valid_slot
+        }
+    }
+
+    pub fn get_last_valid_unix_timestamp(&self) -> Option<u32> {
+        if self.expires_by_unix_timestamp() {
+            Some(self.last_valid_slot)
+        } else {
+            None
+        }
+    }
+
+    /// Expires the order after a unix timestamp instead of a slot. Only for
+    /// orders that cannot reverse.
+    pub fn set_last_valid_unix_timestamp(&mut self, last_valid_unix_timestamp: u32) {
+        debug_assert!(!self.is_reversible());
+        self.last_valid_slot = last_valid_unix_timestamp;
+        self.reverse_spread = EXPIRES_BY_UNIX_TIMESTAMP;
+    }
+
+    fn expires_by_unix_timestamp(&self) -> bool {
+        !self.is_reversible()
+            && self.reverse_spread == EXPIRES_BY_UNIX_TIMESTAMP
+            && self.last_valid_slot != NO_EXPIRATION_LAST_VALID_SLOT
+    }
+
     pub fn is_expired(&self, current_slot: u32) -> bool {
-        self.last_valid_slot != NO_EXPIRATION_LAST_VALID_SLOT && self.last_valid_slot < current_slot
+        self.is_expired_at(current_slot, get_now_unix_timestamp)
+    }
+
+    // The clock is only read for orders that expire by unix timestamp.
+    fn is_expired_at(&self, current_slot: u32, now_unix_timestamp: impl FnOnce() -> i64) -> bool {
+        if self.last_valid_slot == NO_EXPIRATION_LAST_VALID_SLOT {
+            false
+        } else if self.expires_by_unix_timestamp() {
+            (self.last_valid_slot as i64) < now_unix_timestamp()
+        } else {
+            self.last_valid_slot < current_slot
+        }
     }
 
     pub fn get_is_bid(&self) -> bool {
@@ -366,5 +600,113 @@ mod test {
         .unwrap();
         resting_order.set_last_valid_slot(1);
         resting_order.set_sequence_number(1);
+        resting_order.set_min_taker_atoms(BaseAtoms::new(5));
+        assert_eq!(resting_order.get_min_taker_atoms(), BaseAtoms::new(5));
+        resting_order.set_tag(7);
+        assert_eq!(resting_order.get_tag(), 7);
+        resting_order.set_placed_slot(10);
+        assert_eq!(resting_order.get_placed_slot(), 10);
+        assert!(resting_order.has_rested(10, 0));
+        assert!(!resting_order.has_rested(14, 5));
+        assert!(resting_order.has_rested(15, 5));
+    }
+
+    #[test]
+    fn test_unix_timestamp_expiry() {
+        let mut resting_order: RestingOrder = RestingOrder::new(
+            0,
+            BaseAtoms::ONE,
+            QuoteAtomsPerBaseAtom::MIN,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            true,
+            OrderType::Limit,
+        )
+        .unwrap();
+        assert_eq!(resting_order.get_last_valid_unix_timestamp(), None);
+        resting_order.set_last_valid_unix_timestamp(1_000);
+        assert_eq!(resting_order.get_last_valid_unix_timestamp(), Some(1_000));
+        assert_eq!(
+            resting_order.get_last_valid_slot(),
+            NO_EXPIRATION_LAST_VALID_SLOT
+        );
+
+        // Slots do not matter, only the clock.
+        assert!(!resting_order.is_expired_at(u32::MAX, || 1_000));
+        assert!(resting_order.is_expired_at(0, || 1_001));
+        assert_eq!(
+            resting_order.reverse_price(0).unwrap(),
+            QuoteAtomsPerBaseAtom::MIN
+        );
+
+        // Slot expiry does not read the clock.
+        resting_order.set_reverse_spread(0);
+        assert!(!resting_order.is_expired_at(1_000, || panic!()));
+        assert!(resting_order.is_expired_at(1_001, || panic!()));
+    }
+
+    #[test]
+    fn test_dynamic_reverse_price() {
+        let price: QuoteAtomsPerBaseAtom =
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(10, 0).unwrap();
+        let mut ask: RestingOrder = RestingOrder::new(
+            0,
+            BaseAtoms::ONE,
+            price,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            false,
+            OrderType::Reverse,
+        )
+        .unwrap();
+        ask.set_reverse_spread(DYNAMIC_REVERSE_SPREAD);
+        assert_eq!(ask.reverse_price(0).unwrap(), price);
+        // 1% comes back at 9.9.
+        assert_eq!(
+            ask.reverse_price(100).unwrap(),
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(99, -1).unwrap()
+        );
+        // Capped at MAX_VOLATILITY_BPS.
+        assert_eq!(
+            ask.reverse_price(u16::MAX - 1).unwrap(),
+            ask.reverse_price(MAX_VOLATILITY_BPS).unwrap()
+        );
+
+        // A fixed spread ignores the estimate.
+        ask.set_reverse_spread(1_000);
+        assert_eq!(
+            ask.reverse_price(0).unwrap(),
+            ask.reverse_price(MAX_VOLATILITY_BPS).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_iceberg_visible_base_atoms() {
+        let mut iceberg: RestingOrder = RestingOrder::new(
+            0,
+            BaseAtoms::new(10),
+            QuoteAtomsPerBaseAtom::MIN,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            true,
+            OrderType::Iceberg,
+        )
+        .unwrap();
+        iceberg.set_min_taker_atoms(BaseAtoms::new(4));
+        assert_eq!(iceberg.get_display_base_atoms(), BaseAtoms::new(4));
+        assert_eq!(iceberg.get_min_taker_atoms(), BaseAtoms::ZERO);
+        // The odd lot shows first.
+        assert_eq!(iceberg.get_visible_base_atoms(), BaseAtoms::new(2));
+        iceberg.reduce(BaseAtoms::new(2)).unwrap();
+        assert_eq!(iceberg.get_visible_base_atoms(), BaseAtoms::new(4));
+        iceberg.reduce(BaseAtoms::new(1)).unwrap();
+        assert_eq!(iceberg.get_visible_base_atoms(), BaseAtoms::new(3));
+
+        let requeued: RestingOrder =
+            iceberg.requeued(BaseAtoms::new(4), QuoteAtomsPerBaseAtom::MIN, 5, 20);
+        assert_eq!(requeued.get_sequence_number(), 5);
+        assert_eq!(requeued.get_placed_slot(), 20);
+        assert_eq!(requeued.get_display_base_atoms(), BaseAtoms::new(4));
+        assert_eq!(requeued.get_visible_base_atoms(), BaseAtoms::new(4));
     }
 }
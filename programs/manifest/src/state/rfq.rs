@@ -0,0 +1,151 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::{Get, PodBool};
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use static_assertions::const_assert_eq;
+
+use crate::{
+    program::ManifestError,
+    quantities::{BaseAtoms, WrapperU64},
+    require,
+    validation::ManifestAccount,
+};
+
+use super::constants::RFQ_DISCRIMINANT;
+
+/// Request for quote of a taker on a market. The taker posts the size, side
+/// and expiration, makers answer off chain with an RfqQuote signed for the
+/// sequence number of the request, and FillRfq settles the best of them. A
+/// taker has one request per market at a time. The account is kept once the
+/// request is filled or cancelled and reused by the next post.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Zeroable, Pod)]
+pub struct Rfq {
+    pub discriminant: u64,
+    pub market: Pubkey,
+    pub taker: Pubkey,
+    /// Zero when there is no open request.
+    base_atoms: BaseAtoms,
+    /// Number of requests posted so far. Quotes name the request they answer
+    /// by it, so they cannot fill a later request of the taker.
+    sequence_number: u64,
+    last_valid_slot: u32,
+    taker_is_buy: PodBool,
+    _padding: [u8; 3],
+}
+const_assert_eq!(
+    size_of::<Rfq>(),
+    8 +   // discriminant
+    32 +  // market
+    32 +  // taker
+    8 +   // base_atoms
+    8 +   // sequence_number
+    4 +   // last_valid_slot
+    1 +   // taker_is_buy
+    3 // padding
+);
+const_assert_eq!(size_of::<Rfq>() % 8, 0);
+impl Get for Rfq {}
+
+impl ManifestAccount for Rfq {
+    fn verify_discriminant(&self) -> ProgramResult {
+        require!(
+            self.discriminant == RFQ_DISCRIMINANT,
+            ProgramError::InvalidAccountData,
+            "Invalid rfq discriminant actual: {} expected: {}",
+            self.discriminant,
+            RFQ_DISCRIMINANT
+        )?;
+        Ok(())
+    }
+}
+
+impl Rfq {
+    pub fn new_empty(market: &Pubkey, taker: &Pubkey) -> Self {
+        Rfq {
+            discriminant: RFQ_DISCRIMINANT,
+            market: *market,
+            taker: *taker,
+            base_atoms: BaseAtoms::ZERO,
+            sequence_number: 0,
+            last_valid_slot: 0,
+            taker_is_buy: PodBool::from(false),
+            _padding: [0; 3],
+        }
+    }
+
+    pub fn get_base_atoms(&self) -> BaseAtoms {
+        self.base_atoms
+    }
+
+    pub fn get_sequence_number(&self) -> u64 {
+        self.sequence_number
+    }
+
+    pub fn get_last_valid_slot(&self) -> u32 {
+        self.last_valid_slot
+    }
+
+    pub fn get_taker_is_buy(&self) -> bool {
+        self.taker_is_buy.0 == 1
+    }
+
+    /// Replaces any open request with a new one, which quotes for the old one
+    /// cannot fill. Zero base_atoms only cancels the open request.
+    pub fn post(
+        &mut self,
+        base_atoms: BaseAtoms,
+        taker_is_buy: bool,
+        last_valid_slot: u32,
+    ) -> ProgramResult {
+        require!(
+            base_atoms == BaseAtoms::ZERO || last_valid_slot != 0,
+            ManifestError::InvalidRfq,
+            "RFQ needs an expiration",
+        )?;
+        self.sequence_number = self.sequence_number.wrapping_add(1);
+        self.base_atoms = base_atoms;
+        self.taker_is_buy = PodBool::from(taker_is_buy);
+        self.last_valid_slot = last_valid_slot;
+        Ok(())
+    }
+
+    pub fn is_open(&self, now_slot: u32) -> bool {
+        self.base_atoms.as_u64() > 0 && now_slot <= self.last_valid_slot
+    }
+
+    /// Closes the request once it is filled.
+    pub fn fill(&mut self) {
+        self.base_atoms = BaseAtoms::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_post_and_fill() {
+        let mut rfq: Rfq = Rfq::new_empty(&Pubkey::default(), &Pubkey::default());
+        assert!(!rfq.is_open(0));
+        assert!(rfq.post(BaseAtoms::new(10), true, 0).is_err());
+
+        rfq.post(BaseAtoms::new(10), true, 100).unwrap();
+        assert_eq!(rfq.get_sequence_number(), 1);
+        assert_eq!(rfq.get_base_atoms(), BaseAtoms::new(10));
+        assert!(rfq.get_taker_is_buy());
+        assert!(rfq.is_open(100));
+        assert!(!rfq.is_open(101));
+
+        // A cancel still moves to the next request.
+        rfq.post(BaseAtoms::ZERO, false, 0).unwrap();
+        assert_eq!(rfq.get_sequence_number(), 2);
+        assert!(!rfq.is_open(0));
+
+        rfq.post(BaseAtoms::new(5), false, 100).unwrap();
+        rfq.fill();
+        assert!(!rfq.is_open(0));
+        assert_eq!(rfq.get_sequence_number(), 3);
+    }
+}
@@ -0,0 +1,264 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::Get;
+use solana_program::{
+    entrypoint::ProgramResult, hash::hashv, program_error::ProgramError, pubkey::Pubkey,
+};
+use static_assertions::const_assert_eq;
+
+use crate::{
+    program::ManifestError,
+    quantities::{BaseAtoms, QuoteAtoms, WrapperU64},
+    require,
+    validation::ManifestAccount,
+};
+
+use super::constants::{SEAT_ARCHIVE_DEPTH, SEAT_ARCHIVE_DISCRIMINANT};
+
+pub type SeatArchiveNode = [u8; 32];
+
+/// Leaf of a seat that was restored, or never archived.
+pub const EMPTY_SEAT_ARCHIVE_LEAF: SeatArchiveNode = [0; 32];
+
+/// Companion account of a market that holds a merkle root over seats that
+/// were archived to free their blocks. Only the root and the right edge of the
+/// tree are stored. The leaves are in the ArchiveSeatLog events, which is
+/// where clients get them to build proofs for RestoreSeat.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Zeroable, Pod)]
+pub struct SeatArchive {
+    pub discriminant: u64,
+    pub market: Pubkey,
+    root: SeatArchiveNode,
+    num_leaves: u64,
+    /// Latest left node at every level, needed to append without the leaves.
+    filled_subtrees: [SeatArchiveNode; SEAT_ARCHIVE_DEPTH],
+}
+const_assert_eq!(
+    size_of::<SeatArchive>(),
+    8 +   // discriminant
+    32 +  // market
+    32 +  // root
+    8 +   // num_leaves
+    32 * SEAT_ARCHIVE_DEPTH // filled_subtrees
+);
+const_assert_eq!(size_of::<SeatArchive>() % 8, 0);
+impl Get for SeatArchive {}
+
+impl ManifestAccount for SeatArchive {
+    fn verify_discriminant(&self) -> ProgramResult {
+        require!(
+            self.discriminant == SEAT_ARCHIVE_DISCRIMINANT,
+            ProgramError::InvalidAccountData,
+            "Invalid seat archive discriminant actual: {} expected: {}",
+            self.discriminant,
+            SEAT_ARCHIVE_DISCRIMINANT
+        )?;
+        Ok(())
+    }
+}
+
+/// Leaves and inner nodes are hashed with different prefixes so a node can
+/// never be passed off as a seat.
+pub fn seat_archive_leaf(
+    trader: &Pubkey,
+    base_atoms: BaseAtoms,
+    quote_atoms: QuoteAtoms,
+    quote_volume: QuoteAtoms,
+) -> SeatArchiveNode {
+    hashv(&[
+        &[0],
+        trader.as_ref(),
+        &base_atoms.as_u64().to_le_bytes(),
+        &quote_atoms.as_u64().to_le_bytes(),
+        &quote_volume.as_u64().to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+fn hash_nodes(left: &SeatArchiveNode, right: &SeatArchiveNode) -> SeatArchiveNode {
+    hashv(&[&[1], left, right]).to_bytes()
+}
+
+/// Root of an empty subtree at every level, zero_hashes[0] is an empty leaf.
+fn zero_hashes() -> [SeatArchiveNode; SEAT_ARCHIVE_DEPTH + 1] {
+    let mut zero_hashes: [SeatArchiveNode; SEAT_ARCHIVE_DEPTH + 1] =
+        [EMPTY_SEAT_ARCHIVE_LEAF; SEAT_ARCHIVE_DEPTH + 1];
+    let mut node: SeatArchiveNode = EMPTY_SEAT_ARCHIVE_LEAF;
+    for zero_hash in zero_hashes.iter_mut() {
+        *zero_hash = node;
+        node = hash_nodes(&node, &node);
+    }
+    zero_hashes
+}
+
+impl SeatArchive {
+    pub fn new_empty(market: &Pubkey) -> Self {
+        let zero_hashes: [SeatArchiveNode; SEAT_ARCHIVE_DEPTH + 1] = zero_hashes();
+        let mut filled_subtrees: [SeatArchiveNode; SEAT_ARCHIVE_DEPTH] =
+            [EMPTY_SEAT_ARCHIVE_LEAF; SEAT_ARCHIVE_DEPTH];
+        filled_subtrees.copy_from_slice(&zero_hashes[..SEAT_ARCHIVE_DEPTH]);
+        SeatArchive {
+            discriminant: SEAT_ARCHIVE_DISCRIMINANT,
+            market: *market,
+            root: zero_hashes[SEAT_ARCHIVE_DEPTH],
+            num_leaves: 0,
+            filled_subtrees,
+        }
+    }
+
+    pub fn get_root(&self) -> &SeatArchiveNode {
+        &self.root
+    }
+
+    pub fn get_num_leaves(&self) -> u64 {
+        self.num_leaves
+    }
+
+    /// Adds a leaf and returns its index.
+    pub fn append(&mut self, leaf: SeatArchiveNode) -> Result<u64, ProgramError> {
+        require!(
+            self.num_leaves < 1 << SEAT_ARCHIVE_DEPTH,
+            ManifestError::InvalidSeatArchive,
+            "Seat archive is full",
+        )?;
+        let zero_hashes: [SeatArchiveNode; SEAT_ARCHIVE_DEPTH + 1] = zero_hashes();
+        let leaf_index: u64 = self.num_leaves;
+        let mut index: u64 = leaf_index;
+        let mut node: SeatArchiveNode = leaf;
+        for (level, zero_hash) in zero_hashes.iter().enumerate().take(SEAT_ARCHIVE_DEPTH) {
+            if index & 1 == 0 {
+                self.filled_subtrees[level] = node;
+                node = hash_nodes(&node, zero_hash);
+            } else {
+                node = hash_nodes(&self.filled_subtrees[level], &node);
+            }
+            index >>= 1;
+        }
+        self.root = node;
+        self.num_leaves += 1;
+        Ok(leaf_index)
+    }
+
+    /// Checks that leaf is at leaf_index and replaces it with the empty leaf,
+    /// so the same seat cannot be restored twice.
+    pub fn remove(
+        &mut self,
+        leaf: &SeatArchiveNode,
+        leaf_index: u64,
+        proof: &[SeatArchiveNode],
+    ) -> ProgramResult {
+        require!(
+            leaf_index < self.num_leaves
+                && proof.len() == SEAT_ARCHIVE_DEPTH
+                && *leaf != EMPTY_SEAT_ARCHIVE_LEAF,
+            ManifestError::InvalidSeatArchive,
+            "Invalid seat archive leaf {} of {}",
+            leaf_index,
+            self.num_leaves,
+        )?;
+        let mut old_node: SeatArchiveNode = *leaf;
+        let mut new_node: SeatArchiveNode = EMPTY_SEAT_ARCHIVE_LEAF;
+        // Leftmost node at each level on the path of the newest leaf, which is
+        // what filled_subtrees holds.
+        let last_index: u64 = self.num_leaves - 1;
+        let mut new_filled_subtrees: [SeatArchiveNode; SEAT_ARCHIVE_DEPTH] = self.filled_subtrees;
+        for (level, sibling) in proof.iter().enumerate() {
+            let index: u64 = leaf_index >> level;
+            if index == (last_index >> level) & !1 {
+                new_filled_subtrees[level] = new_node;
+            }
+            if index & 1 == 0 {
+                old_node = hash_nodes(&old_node, sibling);
+                new_node = hash_nodes(&new_node, sibling);
+            } else {
+                old_node = hash_nodes(sibling, &old_node);
+                new_node = hash_nodes(sibling, &new_node);
+            }
+        }
+        require!(
+            old_node == self.root,
+            ManifestError::InvalidSeatArchive,
+            "Seat archive proof does not match the root",
+        )?;
+        self.root = new_node;
+        self.filled_subtrees = new_filled_subtrees;
+        Ok(())
+    }
+}
+
+/// Proof for RestoreSeat of the leaf at leaf_index, given every leaf in
+/// order from the ArchiveSeatLog events with restored ones set to
+/// EMPTY_SEAT_ARCHIVE_LEAF. For use off chain.
+pub fn seat_archive_proof(leaves: &[SeatArchiveNode], leaf_index: usize) -> Vec<SeatArchiveNode> {
+    let zero_hashes: [SeatArchiveNode; SEAT_ARCHIVE_DEPTH + 1] = zero_hashes();
+    let mut proof: Vec<SeatArchiveNode> = Vec::with_capacity(SEAT_ARCHIVE_DEPTH);
+    let mut level_nodes: Vec<SeatArchiveNode> = leaves.to_vec();
+    let mut index: usize = leaf_index;
+    for zero_hash in zero_hashes.iter().take(SEAT_ARCHIVE_DEPTH) {
+        proof.push(*level_nodes.get(index ^ 1).unwrap_or(zero_hash));
+        level_nodes = level_nodes
+            .chunks(2)
+            .map(|pair| hash_nodes(&pair[0], pair.get(1).unwrap_or(zero_hash)))
+            .collect();
+        index >>= 1;
+    }
+    proof
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn leaf(n: u64) -> SeatArchiveNode {
+        seat_archive_leaf(
+            &Pubkey::new_from_array([n as u8; 32]),
+            BaseAtoms::new(n),
+            QuoteAtoms::new(n * 2),
+            QuoteAtoms::new(n * 3),
+        )
+    }
+
+    #[test]
+    fn test_append_and_remove() {
+        let mut archive: SeatArchive = SeatArchive::new_empty(&Pubkey::default());
+        let mut leaves: Vec<SeatArchiveNode> = Vec::new();
+        for n in 0..5 {
+            assert_eq!(archive.append(leaf(n)).unwrap(), n);
+            leaves.push(leaf(n));
+        }
+
+        // Wrong leaf or stale proof is rejected.
+        assert!(archive
+            .remove(&leaf(9), 2, &seat_archive_proof(&leaves, 2))
+            .is_err());
+        assert!(archive
+            .remove(&leaf(2), 3, &seat_archive_proof(&leaves, 3))
+            .is_err());
+
+        archive
+            .remove(&leaf(2), 2, &seat_archive_proof(&leaves, 2))
+            .unwrap();
+        leaves[2] = EMPTY_SEAT_ARCHIVE_LEAF;
+        // Cannot be restored twice.
+        assert!(archive
+            .remove(&leaf(2), 2, &seat_archive_proof(&leaves, 2))
+            .is_err());
+
+        // Appends after a removal on the right edge still prove.
+        archive
+            .remove(&leaf(4), 4, &seat_archive_proof(&leaves, 4))
+            .unwrap();
+        leaves[4] = EMPTY_SEAT_ARCHIVE_LEAF;
+        for n in 5..9 {
+            archive.append(leaf(n)).unwrap();
+            leaves.push(leaf(n));
+        }
+        for n in [0, 1, 3, 5, 6, 7, 8] {
+            let mut copy: SeatArchive = archive;
+            copy.remove(&leaf(n), n, &seat_archive_proof(&leaves, n as usize))
+                .unwrap();
+        }
+    }
+}
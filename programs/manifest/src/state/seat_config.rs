@@ -0,0 +1,197 @@
+use std::mem::size_of;
+
+use crate::quantities::QuoteAtoms;
+#[cfg(feature = "certora")]
+use crate::quantities::WrapperU64;
+use bytemuck::{Pod, Zeroable};
+use shank::ShankType;
+use solana_program::pubkey::Pubkey;
+use static_assertions::const_assert_eq;
+use std::cmp::Ordering;
+
+use super::constants::SEAT_CONFIG_SIZE;
+
+/// Optional per seat configuration. Lives in its own market block that is not
+/// part of any tree and is only reachable through the index stored on the
+/// ClaimedSeat that owns it.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct SeatConfig {
+    /// Second key that has to sign a batch_update when the quote notional of
+    /// the orders placed in it exceeds the threshold.
+    pub cosigner: Pubkey,
+    /// Total quote notional of placed orders in a single batch_update above
+    /// which the cosigner is required. Zero means every batch_update that
+    /// places an order requires the cosigner.
+    pub cosign_notional_threshold: QuoteAtoms,
+    /// Points earned in points_epoch on markets that award points.
+    points: u64,
+    /// Points of an earlier finished epoch that have not been claimed yet.
+    previous_points: u64,
+    points_epoch: u32,
+    previous_points_epoch: u32,
+}
+// 32 + // cosigner
+//  8 + // cosign_notional_threshold
+//  8 + // points
+//  8 + // previous_points
+//  4 + // points_epoch
+//  4   // previous_points_epoch
+// = 64
+const_assert_eq!(size_of::<SeatConfig>(), SEAT_CONFIG_SIZE);
+const_assert_eq!(size_of::<SeatConfig>() % 8, 0);
+
+impl SeatConfig {
+    pub fn new(cosigner: Pubkey, cosign_notional_threshold: QuoteAtoms) -> Self {
+        SeatConfig {
+            cosigner,
+            cosign_notional_threshold,
+            ..Default::default()
+        }
+    }
+
+    /// Whether placing orders with a total quote notional of notional requires
+    /// the cosigner.
+    pub fn requires_cosigner(&self, notional: QuoteAtoms) -> bool {
+        self.cosigner != Pubkey::default() && notional > self.cosign_notional_threshold
+    }
+
+    /// Points as of epoch as (epoch, points, previous epoch, previous points).
+    /// Only epochs before epoch show up as previous, and those can be claimed.
+    pub fn get_points(&self, epoch: u32) -> (u32, u64, u32, u64) {
+        let mut seat_config: SeatConfig = *self;
+        seat_config.roll_points_epoch(epoch);
+        (
+            seat_config.points_epoch,
+            seat_config.points,
+            seat_config.previous_points_epoch,
+            seat_config.previous_points,
+        )
+    }
+
+    pub fn add_points(&mut self, points: u64, epoch: u32) {
+        self.roll_points_epoch(epoch);
+        self.points = self.points.saturating_add(points);
+    }
+
+    /// Takes back points added in epoch, for a busted fill. Points that were
+    /// claimed or replaced by a later epoch are gone already.
+    pub fn remove_points(&mut self, points: u64, epoch: u32) {
+        if epoch == self.points_epoch {
+            self.points = self.points.saturating_sub(points);
+        } else if epoch == self.previous_points_epoch {
+            self.previous_points = self.previous_points.saturating_sub(points);
+        }
+    }
+
+    /// Takes the points of the latest finished epoch that were not claimed
+    /// yet, as (epoch, points).
+    pub fn claim_points(&mut self, epoch: u32) -> Option<(u32, u64)> {
+        self.roll_points_epoch(epoch);
+        if self.previous_points == 0 {
+            return None;
+        }
+        let claimed: (u32, u64) = (self.previous_points_epoch, self.previous_points);
+        self.previous_points = 0;
+        Some(claimed)
+    }
+
+    /// Once epoch has started, the points of the epoch before become
+    /// claimable. They replace older unclaimed points, so each finished epoch
+    /// has to be claimed before a later one with points finishes.
+    fn roll_points_epoch(&mut self, epoch: u32) {
+        if epoch <= self.points_epoch {
+            return;
+        }
+        if self.points != 0 {
+            self.previous_points = self.points;
+            self.previous_points_epoch = self.points_epoch;
+        }
+        self.points = 0;
+        self.points_epoch = epoch;
+    }
+}
+
+#[cfg(feature = "certora")]
+impl nondet::Nondet for SeatConfig {
+    fn nondet() -> Self {
+        SeatConfig {
+            cosigner: nondet::nondet(),
+            cosign_notional_threshold: QuoteAtoms::new(nondet::nondet()),
+            points: nondet::nondet(),
+            previous_points: nondet::nondet(),
+            points_epoch: nondet::nondet(),
+            previous_points_epoch: nondet::nondet(),
+        }
+    }
+}
+
+impl Ord for SeatConfig {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.cosigner).cmp(&(other.cosigner))
+    }
+}
+
+impl PartialOrd for SeatConfig {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SeatConfig {
+    fn eq(&self, other: &Self) -> bool {
+        (self.cosigner) == (other.cosigner)
+    }
+}
+
+impl Eq for SeatConfig {}
+
+impl std::fmt::Display for SeatConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.cosigner)
+    }
+}
+
+#[test]
+fn test_requires_cosigner() {
+    #[cfg(not(feature = "certora"))]
+    use crate::quantities::WrapperU64;
+
+    let seat_config: SeatConfig = SeatConfig::new(Pubkey::new_unique(), QuoteAtoms::new(1_000));
+    assert!(!seat_config.requires_cosigner(QuoteAtoms::new(1_000)));
+    assert!(seat_config.requires_cosigner(QuoteAtoms::new(1_001)));
+
+    let disabled: SeatConfig = SeatConfig::new(Pubkey::default(), QuoteAtoms::new(0));
+    assert!(!disabled.requires_cosigner(QuoteAtoms::new(u64::MAX)));
+    let _ = format!("{}", disabled);
+}
+
+#[test]
+fn test_points_epochs() {
+    let mut seat_config: SeatConfig = SeatConfig::default();
+    seat_config.add_points(100, 5);
+    seat_config.add_points(50, 5);
+    assert_eq!(seat_config.claim_points(5), None);
+    assert_eq!(seat_config.get_points(5), (5, 150, 0, 0));
+
+    // Epoch 5 becomes claimable once epoch 6 starts.
+    assert_eq!(seat_config.get_points(6), (6, 0, 5, 150));
+    seat_config.add_points(10, 6);
+
+    // Busted fills take their points back from the epoch they were in.
+    seat_config.add_points(20, 6);
+    seat_config.remove_points(20, 6);
+    seat_config.add_points(30, 6);
+    seat_config.remove_points(30, 5);
+    seat_config.remove_points(30, 4);
+    assert_eq!(seat_config.get_points(6), (6, 40, 5, 120));
+    seat_config.remove_points(30, 6);
+    assert_eq!(seat_config.claim_points(6), Some((5, 120)));
+    assert_eq!(seat_config.claim_points(6), None);
+
+    // Epochs without points do not replace unclaimed ones, later ones with
+    // points do.
+    assert_eq!(seat_config.get_points(9), (9, 0, 6, 10));
+    seat_config.add_points(1, 9);
+    assert_eq!(seat_config.claim_points(10), Some((9, 1)));
+}
@@ -0,0 +1,209 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use shank::ShankType;
+use static_assertions::const_assert_eq;
+use std::cmp::Ordering;
+
+use crate::quantities::{QuoteAtoms, WrapperU64};
+
+use super::constants::SEAT_MMP_SIZE;
+
+/// Market maker protection of a seat. Counts the fills against the seat's
+/// resting orders in windows of window_slots, and once a window reaches
+/// max_fills or max_quote_atoms the matching engine pulls all the seat's
+/// orders. Lives in its own market block that is only reachable through the
+/// SeatVolume of the seat.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct SeatMmp {
+    window_slots: u32,
+    /// Zero is no limit on fills.
+    max_fills: u32,
+    /// Zero is no limit on quote atoms.
+    max_quote_atoms: QuoteAtoms,
+    window_start_slot: u32,
+    window_fills: u32,
+    window_quote_atoms: QuoteAtoms,
+    /// Times the protection pulled the orders of the seat.
+    num_trips: u64,
+    _padding: [u64; 3],
+}
+//  4 + // window_slots
+//  4 + // max_fills
+//  8 + // max_quote_atoms
+//  4 + // window_start_slot
+//  4 + // window_fills
+//  8 + // window_quote_atoms
+//  8 + // num_trips
+// 24   // padding
+// = 64
+const_assert_eq!(size_of::<SeatMmp>(), SEAT_MMP_SIZE);
+const_assert_eq!(size_of::<SeatMmp>() % 8, 0);
+
+impl SeatMmp {
+    pub fn new(window_slots: u32, max_fills: u32, max_quote_atoms: QuoteAtoms) -> Self {
+        SeatMmp {
+            window_slots,
+            max_fills,
+            max_quote_atoms,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_window_slots(&self) -> u32 {
+        self.window_slots
+    }
+
+    pub fn get_max_fills(&self) -> u32 {
+        self.max_fills
+    }
+
+    pub fn get_max_quote_atoms(&self) -> QuoteAtoms {
+        self.max_quote_atoms
+    }
+
+    pub fn get_num_trips(&self) -> u64 {
+        self.num_trips
+    }
+
+    /// Fills and quote atoms counted in the window that is open at now_slot.
+    pub fn get_window(&self, now_slot: u32) -> (u32, QuoteAtoms) {
+        if self.is_window_over(now_slot) {
+            (0, QuoteAtoms::ZERO)
+        } else {
+            (self.window_fills, self.window_quote_atoms)
+        }
+    }
+
+    fn is_window_over(&self, now_slot: u32) -> bool {
+        now_slot >= self.window_start_slot.saturating_add(self.window_slots)
+    }
+
+    /// Takes a fill at fill_slot back out of the window, for a busted fill.
+    /// Fills of an earlier window, or counted before a trip, are not in it.
+    pub fn remove_fill(&mut self, quote_atoms: QuoteAtoms, fill_slot: u32) {
+        if fill_slot < self.window_start_slot || self.is_window_over(fill_slot) {
+            return;
+        }
+        self.window_fills = self.window_fills.saturating_sub(1);
+        self.window_quote_atoms = self.window_quote_atoms.saturating_sub(quote_atoms);
+    }
+
+    /// Keeps the new limits and restarts the window.
+    pub fn update_limits(&mut self, new: &SeatMmp) {
+        self.window_slots = new.window_slots;
+        self.max_fills = new.max_fills;
+        self.max_quote_atoms = new.max_quote_atoms;
+        self.window_fills = 0;
+        self.window_quote_atoms = QuoteAtoms::ZERO;
+    }
+
+    /// Counts a fill of quote_atoms against the seat. Returns the fills and
+    /// quote atoms of the window when the fill trips the protection, which
+    /// also starts a new window for the orders the maker places next.
+    pub fn record_fill(
+        &mut self,
+        quote_atoms: QuoteAtoms,
+        now_slot: u32,
+    ) -> Option<(u32, QuoteAtoms)> {
+        if self.is_window_over(now_slot) {
+            self.window_start_slot = now_slot;
+            self.window_fills = 0;
+            self.window_quote_atoms = QuoteAtoms::ZERO;
+        }
+        self.window_fills = self.window_fills.saturating_add(1);
+        self.window_quote_atoms = self.window_quote_atoms.saturating_add(quote_atoms);
+
+        let tripped: bool = (self.max_fills != 0 && self.window_fills >= self.max_fills)
+            || (self.max_quote_atoms != QuoteAtoms::ZERO
+                && self.window_quote_atoms >= self.max_quote_atoms);
+        if !tripped {
+            return None;
+        }
+        let window: (u32, QuoteAtoms) = (self.window_fills, self.window_quote_atoms);
+        self.num_trips = self.num_trips.wrapping_add(1);
+        self.window_fills = 0;
+        self.window_quote_atoms = QuoteAtoms::ZERO;
+        Some(window)
+    }
+}
+
+// Blocks need an ordering to be RBNode payloads, but seat MMPs are never in a
+// tree.
+impl Ord for SeatMmp {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.window_start_slot.cmp(&other.window_start_slot)
+    }
+}
+
+impl PartialOrd for SeatMmp {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SeatMmp {
+    fn eq(&self, other: &Self) -> bool {
+        self.window_start_slot == other.window_start_slot
+    }
+}
+
+impl Eq for SeatMmp {}
+
+impl std::fmt::Display for SeatMmp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} fills or {} quote atoms in {} slots",
+            self.max_fills,
+            self.max_quote_atoms.as_u64(),
+            self.window_slots
+        )
+    }
+}
+
+#[test]
+fn test_seat_mmp() {
+    let mut seat_mmp: SeatMmp = SeatMmp::new(10, 3, QuoteAtoms::new(1_000));
+
+    // Trips on the third fill of a window.
+    assert_eq!(seat_mmp.record_fill(QuoteAtoms::new(1), 100), None);
+    assert_eq!(seat_mmp.record_fill(QuoteAtoms::new(1), 105), None);
+    assert_eq!(seat_mmp.get_window(109), (2, QuoteAtoms::new(2)));
+    assert_eq!(
+        seat_mmp.record_fill(QuoteAtoms::new(1), 109),
+        Some((3, QuoteAtoms::new(3)))
+    );
+    assert_eq!(seat_mmp.get_num_trips(), 1);
+    assert_eq!(seat_mmp.get_window(109), (0, QuoteAtoms::ZERO));
+
+    // Fills in a later window start over.
+    assert_eq!(seat_mmp.record_fill(QuoteAtoms::new(1), 110), None);
+    assert_eq!(seat_mmp.record_fill(QuoteAtoms::new(1), 120), None);
+    assert_eq!(seat_mmp.get_window(120), (1, QuoteAtoms::new(1)));
+
+    // A busted fill only leaves the window it was counted in.
+    seat_mmp.remove_fill(QuoteAtoms::new(1), 110);
+    assert_eq!(seat_mmp.get_window(120), (1, QuoteAtoms::new(1)));
+    assert_eq!(seat_mmp.record_fill(QuoteAtoms::new(1), 121), None);
+    seat_mmp.remove_fill(QuoteAtoms::new(1), 121);
+    assert_eq!(seat_mmp.get_window(121), (1, QuoteAtoms::new(1)));
+    assert_eq!(seat_mmp.get_window(130), (0, QuoteAtoms::ZERO));
+
+    // Or on the quote atoms.
+    assert_eq!(
+        seat_mmp.record_fill(QuoteAtoms::new(999), 121),
+        Some((2, QuoteAtoms::new(1_000)))
+    );
+
+    // Zero limits never trip.
+    seat_mmp.update_limits(&SeatMmp::new(10, 0, QuoteAtoms::ZERO));
+    for _ in 0..10 {
+        assert_eq!(seat_mmp.record_fill(QuoteAtoms::new(u64::MAX), 125), None);
+    }
+    assert_eq!(seat_mmp.get_max_fills(), 0);
+    assert_eq!(seat_mmp.get_max_quote_atoms(), QuoteAtoms::ZERO);
+    assert_eq!(seat_mmp.get_window_slots(), 10);
+    let _ = format!("{}", seat_mmp);
+}
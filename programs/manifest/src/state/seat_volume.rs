@@ -0,0 +1,185 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use shank::ShankType;
+use static_assertions::const_assert_eq;
+use std::cmp::Ordering;
+
+use crate::quantities::QuoteAtoms;
+use hypertree::DataIndex;
+
+use super::{
+    claimed_seat::{get_block_link_index, to_block_link},
+    constants::{SEAT_VOLUME_BUCKETS, SEAT_VOLUME_BUCKET_SLOTS, SEAT_VOLUME_SIZE},
+};
+
+/// Rolling taker volume of a seat on a market with fee tiers, which sets its
+/// taker fee discount. Lives in its own market block that is not part of any
+/// tree and is only reachable through the ClaimedSeat that owns it. Volume is
+/// kept in buckets of SEAT_VOLUME_BUCKET_SLOTS, and each bucket is reused once
+/// it falls out of the window. Also links the SeatMmp of the seat, since the
+/// ClaimedSeat has no room left for another link.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct SeatVolume {
+    /// Quote atoms taken in bucket now_slot / SEAT_VOLUME_BUCKET_SLOTS, at
+    /// that bucket modulo SEAT_VOLUME_BUCKETS.
+    bucket_quote_atoms: [QuoteAtoms; SEAT_VOLUME_BUCKETS],
+    /// Latest bucket volume was added in.
+    latest_bucket: u32,
+    /// Link to the SeatMmp block, see get_block_link_index.
+    seat_mmp_block: [u8; 3],
+    _padding: [u8; 1],
+    _padding2: [u64; 1],
+}
+// 48 + // bucket_quote_atoms
+//  4 + // latest_bucket
+//  3 + // seat_mmp_block
+//  1 + // padding
+//  8   // padding2
+// = 64
+const_assert_eq!(size_of::<SeatVolume>(), SEAT_VOLUME_SIZE);
+const_assert_eq!(size_of::<SeatVolume>() % 8, 0);
+
+impl SeatVolume {
+    /// Taker volume over the latest SEAT_VOLUME_BUCKETS buckets as of
+    /// now_slot, including the current one.
+    pub fn get_rolling_quote_atoms(&self, now_slot: u32) -> QuoteAtoms {
+        let now_bucket: u32 = now_slot / SEAT_VOLUME_BUCKET_SLOTS;
+        let mut total: QuoteAtoms = QuoteAtoms::ZERO;
+        for age in 0..SEAT_VOLUME_BUCKETS as u32 {
+            let Some(bucket) = now_bucket.checked_sub(age) else {
+                break;
+            };
+            if bucket <= self.latest_bucket
+                && self.latest_bucket - bucket < SEAT_VOLUME_BUCKETS as u32
+            {
+                total = total
+                    .saturating_add(self.bucket_quote_atoms[bucket as usize % SEAT_VOLUME_BUCKETS]);
+            }
+        }
+        total
+    }
+
+    pub fn add(&mut self, quote_atoms: QuoteAtoms, now_slot: u32) {
+        let now_bucket: u32 = now_slot / SEAT_VOLUME_BUCKET_SLOTS;
+        if now_bucket > self.latest_bucket {
+            // Clear the buckets that are reused for the buckets since.
+            let cleared: u32 = (now_bucket - self.latest_bucket).min(SEAT_VOLUME_BUCKETS as u32);
+            for bucket in now_bucket + 1 - cleared..=now_bucket {
+                self.bucket_quote_atoms[bucket as usize % SEAT_VOLUME_BUCKETS] = QuoteAtoms::ZERO;
+            }
+            self.latest_bucket = now_bucket;
+        }
+        let bucket_quote_atoms: &mut QuoteAtoms =
+            &mut self.bucket_quote_atoms[self.latest_bucket as usize % SEAT_VOLUME_BUCKETS];
+        *bucket_quote_atoms = bucket_quote_atoms.saturating_add(quote_atoms);
+    }
+
+    /// Takes back quote_atoms added at fill_slot, for a busted fill. Nothing
+    /// to take once its bucket has left the window.
+    pub fn remove(&mut self, quote_atoms: QuoteAtoms, fill_slot: u32) {
+        let fill_bucket: u32 = fill_slot / SEAT_VOLUME_BUCKET_SLOTS;
+        if fill_bucket > self.latest_bucket
+            || self.latest_bucket - fill_bucket >= SEAT_VOLUME_BUCKETS as u32
+        {
+            return;
+        }
+        let bucket_quote_atoms: &mut QuoteAtoms =
+            &mut self.bucket_quote_atoms[fill_bucket as usize % SEAT_VOLUME_BUCKETS];
+        *bucket_quote_atoms = bucket_quote_atoms.saturating_sub(quote_atoms);
+    }
+
+    pub fn get_seat_mmp_index(&self) -> Option<DataIndex> {
+        get_block_link_index(self.seat_mmp_block)
+    }
+
+    pub fn set_seat_mmp_index(&mut self, seat_mmp_index: Option<DataIndex>) {
+        self.seat_mmp_block = to_block_link(seat_mmp_index);
+    }
+}
+
+// Blocks need an ordering to be RBNode payloads, but seat volumes are never
+// in a tree.
+impl Ord for SeatVolume {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.latest_bucket.cmp(&other.latest_bucket)
+    }
+}
+
+impl PartialOrd for SeatVolume {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SeatVolume {
+    fn eq(&self, other: &Self) -> bool {
+        self.latest_bucket == other.latest_bucket
+    }
+}
+
+impl Eq for SeatVolume {}
+
+impl std::fmt::Display for SeatVolume {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "bucket {}", self.latest_bucket)
+    }
+}
+
+#[test]
+fn test_seat_volume() {
+    use crate::quantities::WrapperU64;
+
+    let mut seat_volume: SeatVolume = SeatVolume::default();
+    assert_eq!(seat_volume.get_rolling_quote_atoms(0), QuoteAtoms::ZERO);
+
+    seat_volume.add(QuoteAtoms::new(100), 0);
+    seat_volume.add(QuoteAtoms::new(50), SEAT_VOLUME_BUCKET_SLOTS);
+    assert_eq!(
+        seat_volume.get_rolling_quote_atoms(SEAT_VOLUME_BUCKET_SLOTS),
+        QuoteAtoms::new(150)
+    );
+
+    // The first bucket falls out of the window.
+    let last_slot_with_first: u32 = SEAT_VOLUME_BUCKETS as u32 * SEAT_VOLUME_BUCKET_SLOTS - 1;
+    assert_eq!(
+        seat_volume.get_rolling_quote_atoms(last_slot_with_first),
+        QuoteAtoms::new(150)
+    );
+    assert_eq!(
+        seat_volume.get_rolling_quote_atoms(last_slot_with_first + 1),
+        QuoteAtoms::new(50)
+    );
+
+    // Adding clears the reused bucket first.
+    seat_volume.add(QuoteAtoms::new(7), last_slot_with_first + 1);
+    assert_eq!(
+        seat_volume.get_rolling_quote_atoms(last_slot_with_first + 1),
+        QuoteAtoms::new(57)
+    );
+
+    // Removing only takes from the bucket of the fill while it is kept.
+    seat_volume.remove(QuoteAtoms::new(100), 0);
+    seat_volume.remove(QuoteAtoms::new(20), SEAT_VOLUME_BUCKET_SLOTS);
+    assert_eq!(
+        seat_volume.get_rolling_quote_atoms(last_slot_with_first + 1),
+        QuoteAtoms::new(37)
+    );
+
+    // Long after, everything is gone.
+    assert_eq!(
+        seat_volume.get_rolling_quote_atoms(u32::MAX),
+        QuoteAtoms::ZERO
+    );
+    seat_volume.add(QuoteAtoms::new(1), u32::MAX);
+    assert_eq!(
+        seat_volume.get_rolling_quote_atoms(u32::MAX),
+        QuoteAtoms::new(1)
+    );
+
+    assert_eq!(seat_volume.get_seat_mmp_index(), None);
+    seat_volume.set_seat_mmp_index(Some(160));
+    assert_eq!(seat_volume.get_seat_mmp_index(), Some(160));
+    let _ = format!("{}", seat_volume);
+}
@@ -0,0 +1,157 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::Get;
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use static_assertions::const_assert_eq;
+
+use crate::{program::ManifestError, require, validation::ManifestAccount};
+
+use super::constants::{SIGNED_ORDER_NONCES_DISCRIMINANT, SIGNED_ORDER_NONCE_WINDOW};
+
+const NONCE_WORDS: usize = SIGNED_ORDER_NONCE_WINDOW as usize / 64;
+
+/// Nonces of the signed orders of a maker on a market that can no longer be
+/// filled. Every nonce below min_nonce is used. The window of
+/// SIGNED_ORDER_NONCE_WINDOW nonces from min_nonce on is a bitmap, so makers
+/// can fill orders out of order within it. Using a nonce past the window
+/// moves the window up to it, which uses every nonce that falls out of it.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Zeroable, Pod)]
+pub struct SignedOrderNonces {
+    pub discriminant: u64,
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    min_nonce: u64,
+    /// Bit nonce % SIGNED_ORDER_NONCE_WINDOW is set once nonce is used.
+    used_bits: [u64; NONCE_WORDS],
+}
+const_assert_eq!(
+    size_of::<SignedOrderNonces>(),
+    8 +   // discriminant
+    32 +  // market
+    32 +  // trader
+    8 +   // min_nonce
+    128 // used_bits
+);
+const_assert_eq!(size_of::<SignedOrderNonces>() % 8, 0);
+impl Get for SignedOrderNonces {}
+
+impl ManifestAccount for SignedOrderNonces {
+    fn verify_discriminant(&self) -> ProgramResult {
+        require!(
+            self.discriminant == SIGNED_ORDER_NONCES_DISCRIMINANT,
+            ProgramError::InvalidAccountData,
+            "Invalid signed order nonces discriminant actual: {} expected: {}",
+            self.discriminant,
+            SIGNED_ORDER_NONCES_DISCRIMINANT
+        )?;
+        Ok(())
+    }
+}
+
+impl SignedOrderNonces {
+    pub fn new_empty(market: &Pubkey, trader: &Pubkey) -> Self {
+        SignedOrderNonces {
+            discriminant: SIGNED_ORDER_NONCES_DISCRIMINANT,
+            market: *market,
+            trader: *trader,
+            min_nonce: 0,
+            used_bits: [0; NONCE_WORDS],
+        }
+    }
+
+    pub fn get_min_nonce(&self) -> u64 {
+        self.min_nonce
+    }
+
+    fn bit(nonce: u64) -> (usize, u64) {
+        let position: u64 = nonce % SIGNED_ORDER_NONCE_WINDOW;
+        ((position / 64) as usize, 1 << (position % 64))
+    }
+
+    pub fn is_used(&self, nonce: u64) -> bool {
+        if nonce < self.min_nonce {
+            return true;
+        }
+        if nonce - self.min_nonce >= SIGNED_ORDER_NONCE_WINDOW {
+            return false;
+        }
+        let (word, mask) = Self::bit(nonce);
+        self.used_bits[word] & mask != 0
+    }
+
+    /// Uses every nonce below min_nonce. Never moves the window down.
+    pub fn advance(&mut self, min_nonce: u64) {
+        if min_nonce <= self.min_nonce {
+            return;
+        }
+        if min_nonce - self.min_nonce >= SIGNED_ORDER_NONCE_WINDOW {
+            self.used_bits = [0; NONCE_WORDS];
+        } else {
+            // The bits of the nonces leaving the window are the ones of the
+            // nonces entering it.
+            for nonce in self.min_nonce..min_nonce {
+                let (word, mask) = Self::bit(nonce);
+                self.used_bits[word] &= !mask;
+            }
+        }
+        self.min_nonce = min_nonce;
+    }
+
+    pub fn use_nonce(&mut self, nonce: u64) -> ProgramResult {
+        require!(
+            !self.is_used(nonce),
+            ManifestError::InvalidSignedOrder,
+            "Signed order nonce {} already used",
+            nonce,
+        )?;
+        if nonce - self.min_nonce >= SIGNED_ORDER_NONCE_WINDOW {
+            self.advance(nonce - SIGNED_ORDER_NONCE_WINDOW + 1);
+        }
+        let (word, mask) = Self::bit(nonce);
+        self.used_bits[word] |= mask;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_signed_order_nonces() {
+        let mut nonces: SignedOrderNonces =
+            SignedOrderNonces::new_empty(&Pubkey::default(), &Pubkey::default());
+        assert!(!nonces.is_used(0));
+
+        // Out of order within the window.
+        nonces.use_nonce(5).unwrap();
+        nonces.use_nonce(2).unwrap();
+        assert!(nonces.use_nonce(5).is_err());
+        assert!(nonces.is_used(2));
+        assert!(!nonces.is_used(3));
+
+        // Past the window moves it up and uses what falls out.
+        nonces.use_nonce(SIGNED_ORDER_NONCE_WINDOW + 3).unwrap();
+        assert_eq!(nonces.get_min_nonce(), 4);
+        assert!(nonces.is_used(3));
+        assert!(nonces.is_used(5));
+        assert!(!nonces.is_used(6));
+        assert!(!nonces.is_used(SIGNED_ORDER_NONCE_WINDOW + 2));
+        assert!(nonces.is_used(SIGNED_ORDER_NONCE_WINDOW + 3));
+
+        // Advancing clears the bits reused by the nonces entering the window.
+        nonces.advance(6);
+        assert!(!nonces.is_used(SIGNED_ORDER_NONCE_WINDOW + 5));
+        assert!(nonces.is_used(SIGNED_ORDER_NONCE_WINDOW + 3));
+        nonces.advance(3);
+        assert_eq!(nonces.get_min_nonce(), 6);
+
+        nonces.advance(u64::MAX - 1);
+        assert!(nonces.is_used(u64::MAX - 2));
+        nonces.use_nonce(u64::MAX).unwrap();
+        assert!(!nonces.is_used(u64::MAX - 1));
+        assert!(nonces.is_used(u64::MAX));
+    }
+}
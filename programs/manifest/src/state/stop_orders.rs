@@ -0,0 +1,462 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::{Get, PodBool};
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use static_assertions::const_assert_eq;
+
+use crate::{
+    program::ManifestError,
+    quantities::{BaseAtoms, PriceConversionError, QuoteAtomsPerBaseAtom},
+    require,
+    validation::ManifestAccount,
+};
+
+use super::{
+    constants::{MAX_STOP_ORDERS, NO_EXPIRATION_LAST_VALID_SLOT, STOP_ORDERS_DISCRIMINANT},
+    OrderType,
+};
+
+/// Order that is placed on the book once the last trade price of the market
+/// reaches trigger_price. Bids trigger when the price rises to it and asks
+/// when it falls to it, so a stop loss on a long is an ask below the market.
+/// Trailing stops move trigger_price to trailing_bps away from later trades,
+/// only ever towards the market. Empty when num_base_atoms is zero.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod)]
+pub struct StopOrder {
+    trigger_price: QuoteAtomsPerBaseAtom,
+    /// Limit price of the order placed when triggered.
+    price: QuoteAtomsPerBaseAtom,
+    num_base_atoms: BaseAtoms,
+    last_valid_slot: u32,
+    /// Only trades after this slot trigger the order.
+    placed_slot: u32,
+    is_bid: PodBool,
+    order_type: OrderType,
+    /// Zero for a fixed trigger price.
+    trailing_bps: u16,
+    _padding: [u8; 4],
+}
+// 16 + // trigger_price
+// 16 + // price
+//  8 + // num_base_atoms
+//  4 + // last_valid_slot
+//  4 + // placed_slot
+//  1 + // is_bid
+//  1 + // order_type
+//  2 + // trailing_bps
+//  4   // padding
+// = 56
+const_assert_eq!(size_of::<StopOrder>(), 56);
+const_assert_eq!(size_of::<StopOrder>() % 8, 0);
+
+impl StopOrder {
+    pub fn get_trigger_price(&self) -> QuoteAtomsPerBaseAtom {
+        self.trigger_price
+    }
+
+    pub fn get_price(&self) -> QuoteAtomsPerBaseAtom {
+        self.price
+    }
+
+    pub fn get_num_base_atoms(&self) -> BaseAtoms {
+        self.num_base_atoms
+    }
+
+    pub fn get_last_valid_slot(&self) -> u32 {
+        self.last_valid_slot
+    }
+
+    pub fn get_is_bid(&self) -> bool {
+        self.is_bid.0 == 1
+    }
+
+    pub fn get_order_type(&self) -> OrderType {
+        self.order_type
+    }
+
+    pub fn get_trailing_bps(&self) -> u16 {
+        self.trailing_bps
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_base_atoms == BaseAtoms::ZERO
+    }
+
+    pub fn is_expired(&self, current_slot: u32) -> bool {
+        self.last_valid_slot != NO_EXPIRATION_LAST_VALID_SLOT && self.last_valid_slot < current_slot
+    }
+
+    /// Whether a trade at last_trade_price in last_trade_slot crossed the
+    /// trigger. Trades from before the order was placed do not count, so an
+    /// order is not triggered by a price the market already moved past.
+    pub fn is_triggered(
+        &self,
+        last_trade_price: QuoteAtomsPerBaseAtom,
+        last_trade_slot: u32,
+    ) -> bool {
+        if self.is_empty() || last_trade_slot <= self.placed_slot {
+            return false;
+        }
+        if self.get_is_bid() {
+            last_trade_price >= self.trigger_price
+        } else {
+            last_trade_price <= self.trigger_price
+        }
+    }
+
+    /// Moves the trigger of a trailing stop to trailing_bps below the last
+    /// trade price for asks and above it for bids, when that is closer to the
+    /// market. The limit price stays where it was placed. Like triggering,
+    /// only trades after the order was placed count.
+    pub fn trail(
+        &mut self,
+        last_trade_price: QuoteAtomsPerBaseAtom,
+        last_trade_slot: u32,
+    ) -> Result<(), PriceConversionError> {
+        if self.is_empty() || self.trailing_bps == 0 || last_trade_slot <= self.placed_slot {
+            return Ok(());
+        }
+        let trailing_bps: u32 = self.trailing_bps as u32;
+        if self.get_is_bid() {
+            let trigger_price: QuoteAtomsPerBaseAtom = last_trade_price
+                .checked_multiply_rational(10_000 + trailing_bps, 10_000, true)
+                .unwrap_or(QuoteAtomsPerBaseAtom::MAX);
+            if trigger_price < self.trigger_price {
+                self.trigger_price = trigger_price;
+            }
+        } else {
+            let trigger_price: QuoteAtomsPerBaseAtom =
+                last_trade_price.checked_multiply_rational(10_000 - trailing_bps, 10_000, false)?;
+            if trigger_price > self.trigger_price {
+                self.trigger_price = trigger_price;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Stop orders of a trader on a market. They are not on the book and do not
+/// lock funds, the seat needs the funds when TriggerStopOrders places them.
+/// The account is kept when empty and reused by the next stop order.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Zeroable, Pod)]
+pub struct StopOrders {
+    pub discriminant: u64,
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    orders: [StopOrder; MAX_STOP_ORDERS],
+}
+const_assert_eq!(
+    size_of::<StopOrders>(),
+    8 +   // discriminant
+    32 +  // market
+    32 +  // trader
+    56 * MAX_STOP_ORDERS // orders
+);
+const_assert_eq!(size_of::<StopOrders>() % 8, 0);
+impl Get for StopOrders {}
+
+impl ManifestAccount for StopOrders {
+    fn verify_discriminant(&self) -> ProgramResult {
+        require!(
+            self.discriminant == STOP_ORDERS_DISCRIMINANT,
+            ProgramError::InvalidAccountData,
+            "Invalid stop orders discriminant actual: {} expected: {}",
+            self.discriminant,
+            STOP_ORDERS_DISCRIMINANT
+        )?;
+        Ok(())
+    }
+}
+
+impl StopOrders {
+    pub fn new_empty(market: &Pubkey, trader: &Pubkey) -> Self {
+        StopOrders {
+            discriminant: STOP_ORDERS_DISCRIMINANT,
+            market: *market,
+            trader: *trader,
+            orders: [StopOrder::default(); MAX_STOP_ORDERS],
+        }
+    }
+
+    pub fn get_orders(&self) -> &[StopOrder; MAX_STOP_ORDERS] {
+        &self.orders
+    }
+
+    /// Adds a stop order in the first empty slot and returns the slot.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add(
+        &mut self,
+        trigger_price: QuoteAtomsPerBaseAtom,
+        price: QuoteAtomsPerBaseAtom,
+        num_base_atoms: BaseAtoms,
+        is_bid: bool,
+        last_valid_slot: u32,
+        order_type: OrderType,
+        trailing_bps: u16,
+        now_slot: u32,
+    ) -> Result<u8, ProgramError> {
+        require!(
+            num_base_atoms > BaseAtoms::ZERO,
+            ManifestError::InvalidStopOrder,
+            "Stop order needs a size",
+        )?;
+        // Orders that rest or take like a normal order. Post only would fail
+        // on the move that triggers it and global funds are not checked.
+        require!(
+            order_type == OrderType::Limit || order_type == OrderType::ImmediateOrCancel,
+            ManifestError::InvalidStopOrder,
+            "Stop order cannot be {:?}",
+            order_type,
+        )?;
+        require!(
+            trailing_bps < 10_000,
+            ManifestError::InvalidStopOrder,
+            "Trailing stop by {} bps",
+            trailing_bps,
+        )?;
+        require!(
+            last_valid_slot == NO_EXPIRATION_LAST_VALID_SLOT || last_valid_slot > now_slot,
+            ManifestError::InvalidStopOrder,
+            "Stop order expired at slot {}",
+            last_valid_slot,
+        )?;
+        let index: usize = self
+            .orders
+            .iter()
+            .position(|order| order.is_empty())
+            .ok_or(ManifestError::InvalidStopOrder)?;
+        self.orders[index] = StopOrder {
+            trigger_price,
+            price,
+            num_base_atoms,
+            last_valid_slot,
+            placed_slot: now_slot,
+            is_bid: PodBool::from_bool(is_bid),
+            order_type,
+            trailing_bps,
+            _padding: [0; 4],
+        };
+        Ok(index as u8)
+    }
+
+    /// Trails the stop order in slot index and returns it.
+    pub fn trail(
+        &mut self,
+        index: u8,
+        last_trade_price: QuoteAtomsPerBaseAtom,
+        last_trade_slot: u32,
+    ) -> Result<StopOrder, ProgramError> {
+        let order: &mut StopOrder = self
+            .orders
+            .get_mut(index as usize)
+            .ok_or(ManifestError::InvalidStopOrder)?;
+        order.trail(last_trade_price, last_trade_slot)?;
+        Ok(*order)
+    }
+
+    /// Removes and returns the stop order in slot index.
+    pub fn remove(&mut self, index: u8) -> Result<StopOrder, ProgramError> {
+        let order: &mut StopOrder = self
+            .orders
+            .get_mut(index as usize)
+            .filter(|order| !order.is_empty())
+            .ok_or(ManifestError::InvalidStopOrder)?;
+        let removed: StopOrder = *order;
+        *order = StopOrder::default();
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stop_orders() {
+        let mut stop_orders: StopOrders =
+            StopOrders::new_empty(&Pubkey::default(), &Pubkey::default());
+        let trigger_price: QuoteAtomsPerBaseAtom =
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(10, 0).unwrap();
+        let above: QuoteAtomsPerBaseAtom =
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(11, 0).unwrap();
+        let below: QuoteAtomsPerBaseAtom =
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(9, 0).unwrap();
+
+        assert!(stop_orders
+            .add(
+                trigger_price,
+                above,
+                BaseAtoms::ZERO,
+                true,
+                0,
+                OrderType::Limit,
+                0,
+                10
+            )
+            .is_err());
+        assert!(stop_orders
+            .add(
+                trigger_price,
+                above,
+                BaseAtoms::ONE,
+                true,
+                0,
+                OrderType::PostOnly,
+                0,
+                10
+            )
+            .is_err());
+        assert!(stop_orders
+            .add(
+                trigger_price,
+                above,
+                BaseAtoms::ONE,
+                true,
+                5,
+                OrderType::Limit,
+                0,
+                10
+            )
+            .is_err());
+
+        let bid_index: u8 = stop_orders
+            .add(
+                trigger_price,
+                above,
+                BaseAtoms::ONE,
+                true,
+                0,
+                OrderType::Limit,
+                0,
+                10,
+            )
+            .unwrap();
+        let ask_index: u8 = stop_orders
+            .add(
+                trigger_price,
+                below,
+                BaseAtoms::ONE,
+                false,
+                20,
+                OrderType::ImmediateOrCancel,
+                0,
+                10,
+            )
+            .unwrap();
+        assert_eq!((bid_index, ask_index), (0, 1));
+
+        let bid: StopOrder = stop_orders.get_orders()[0];
+        let ask: StopOrder = stop_orders.get_orders()[1];
+        // Trades up to the slot it was placed in do not count.
+        assert!(!bid.is_triggered(above, 10));
+        assert!(bid.is_triggered(trigger_price, 11));
+        assert!(!bid.is_triggered(below, 11));
+        assert!(ask.is_triggered(below, 11));
+        assert!(!ask.is_triggered(above, 11));
+        assert!(!bid.is_expired(u32::MAX));
+        assert!(!ask.is_expired(20));
+        assert!(ask.is_expired(21));
+
+        stop_orders.remove(bid_index).unwrap();
+        assert!(stop_orders.remove(bid_index).is_err());
+        assert!(stop_orders.remove(MAX_STOP_ORDERS as u8).is_err());
+        assert!(!stop_orders.get_orders()[0].is_triggered(above, 11));
+
+        for _ in 0..MAX_STOP_ORDERS - 1 {
+            stop_orders
+                .add(
+                    trigger_price,
+                    above,
+                    BaseAtoms::ONE,
+                    true,
+                    0,
+                    OrderType::Limit,
+                    0,
+                    10,
+                )
+                .unwrap();
+        }
+        assert!(stop_orders
+            .add(
+                trigger_price,
+                above,
+                BaseAtoms::ONE,
+                true,
+                0,
+                OrderType::Limit,
+                0,
+                10
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_trailing_stop_order() {
+        let mut stop_orders: StopOrders =
+            StopOrders::new_empty(&Pubkey::default(), &Pubkey::default());
+        let price = |mantissa: u32| {
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(mantissa, 0).unwrap()
+        };
+
+        assert!(stop_orders
+            .add(
+                price(90),
+                price(80),
+                BaseAtoms::ONE,
+                false,
+                0,
+                OrderType::Limit,
+                10_000,
+                10
+            )
+            .is_err());
+        // Sell once the price falls 10% from its high.
+        let ask_index: u8 = stop_orders
+            .add(
+                price(90),
+                price(80),
+                BaseAtoms::ONE,
+                false,
+                0,
+                OrderType::Limit,
+                1_000,
+                10,
+            )
+            .unwrap();
+        // Buy once the price rises 10% from its low.
+        let bid_index: u8 = stop_orders
+            .add(
+                price(110),
+                price(120),
+                BaseAtoms::ONE,
+                true,
+                0,
+                OrderType::Limit,
+                1_000,
+                10,
+            )
+            .unwrap();
+
+        // Trades up to the slot it was placed in do not move it.
+        let ask: StopOrder = stop_orders.trail(ask_index, price(200), 10).unwrap();
+        assert_eq!(ask.get_trigger_price(), price(90));
+
+        let ask: StopOrder = stop_orders.trail(ask_index, price(200), 11).unwrap();
+        assert_eq!(ask.get_trigger_price(), price(180));
+        assert_eq!(ask.get_price(), price(80));
+        let bid: StopOrder = stop_orders.trail(bid_index, price(200), 11).unwrap();
+        assert_eq!(bid.get_trigger_price(), price(110));
+
+        // Only moves towards the market.
+        let ask: StopOrder = stop_orders.trail(ask_index, price(190), 12).unwrap();
+        assert_eq!(ask.get_trigger_price(), price(180));
+        assert!(!ask.is_triggered(price(190), 12));
+        assert!(ask.is_triggered(price(180), 12));
+        let bid: StopOrder = stop_orders.trail(bid_index, price(50), 12).unwrap();
+        assert_eq!(bid.get_trigger_price(), price(55));
+        assert!(bid.is_triggered(price(55), 12));
+    }
+}
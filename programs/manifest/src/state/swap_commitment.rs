@@ -0,0 +1,145 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::Get;
+use solana_program::{
+    entrypoint::ProgramResult, hash::hashv, program_error::ProgramError, pubkey::Pubkey,
+};
+use static_assertions::const_assert_eq;
+
+use crate::{program::ManifestError, require, validation::ManifestAccount};
+
+use super::constants::SWAP_COMMITMENT_DISCRIMINANT;
+
+/// Commitment that is not set, either never committed or already revealed.
+pub const EMPTY_SWAP_COMMITMENT: [u8; 32] = [0; 32];
+
+/// Pending swap of a trader on a market with a taker delay. The trader commits
+/// to the hash of a swap, then reveals and executes it once the delay has
+/// passed. The account is kept after the reveal and reused by the next commit.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Zeroable, Pod)]
+pub struct SwapCommitment {
+    pub discriminant: u64,
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    commitment: [u8; 32],
+    commit_slot: u32,
+    _padding: [u8; 4],
+}
+const_assert_eq!(
+    size_of::<SwapCommitment>(),
+    8 +   // discriminant
+    32 +  // market
+    32 +  // trader
+    32 +  // commitment
+    4 +   // commit_slot
+    4 // padding
+);
+const_assert_eq!(size_of::<SwapCommitment>() % 8, 0);
+impl Get for SwapCommitment {}
+
+impl ManifestAccount for SwapCommitment {
+    fn verify_discriminant(&self) -> ProgramResult {
+        require!(
+            self.discriminant == SWAP_COMMITMENT_DISCRIMINANT,
+            ProgramError::InvalidAccountData,
+            "Invalid swap commitment discriminant actual: {} expected: {}",
+            self.discriminant,
+            SWAP_COMMITMENT_DISCRIMINANT
+        )?;
+        Ok(())
+    }
+}
+
+/// Hash a trader commits to. swap_data is the whole Swap, SwapV2 or
+/// SwapToSeat instruction data, starting with the instruction byte. The salt
+/// keeps others from guessing the swap from the hash.
+pub fn swap_commitment_hash(salt: &[u8; 32], swap_data: &[u8]) -> [u8; 32] {
+    hashv(&[salt, swap_data]).to_bytes()
+}
+
+impl SwapCommitment {
+    pub fn new_empty(market: &Pubkey, trader: &Pubkey) -> Self {
+        SwapCommitment {
+            discriminant: SWAP_COMMITMENT_DISCRIMINANT,
+            market: *market,
+            trader: *trader,
+            commitment: EMPTY_SWAP_COMMITMENT,
+            commit_slot: 0,
+            _padding: [0; 4],
+        }
+    }
+
+    pub fn get_commitment(&self) -> &[u8; 32] {
+        &self.commitment
+    }
+
+    pub fn get_commit_slot(&self) -> u32 {
+        self.commit_slot
+    }
+
+    /// Replaces any pending commitment, which restarts the delay.
+    pub fn commit(&mut self, commitment: [u8; 32], now_slot: u32) -> ProgramResult {
+        require!(
+            commitment != EMPTY_SWAP_COMMITMENT,
+            ManifestError::InvalidSwapCommitment,
+            "Swap commitment cannot be empty",
+        )?;
+        self.commitment = commitment;
+        self.commit_slot = now_slot;
+        Ok(())
+    }
+
+    /// Checks that commitment is the pending one and was made at least
+    /// delay_slots ago, then clears it so it is only executed once.
+    pub fn reveal(
+        &mut self,
+        commitment: &[u8; 32],
+        now_slot: u32,
+        delay_slots: u32,
+    ) -> ProgramResult {
+        require!(
+            self.commitment != EMPTY_SWAP_COMMITMENT && self.commitment == *commitment,
+            ManifestError::InvalidSwapCommitment,
+            "Swap does not match the commitment",
+        )?;
+        require!(
+            now_slot >= self.commit_slot.saturating_add(delay_slots),
+            ManifestError::InvalidSwapCommitment,
+            "Swap committed at slot {} cannot execute before {} slots",
+            self.commit_slot,
+            delay_slots,
+        )?;
+        self.commitment = EMPTY_SWAP_COMMITMENT;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_commit_and_reveal() {
+        let mut swap_commitment: SwapCommitment =
+            SwapCommitment::new_empty(&Pubkey::default(), &Pubkey::default());
+        let commitment: [u8; 32] = swap_commitment_hash(&[1; 32], &[4, 5, 6]);
+        assert!(swap_commitment.commit(EMPTY_SWAP_COMMITMENT, 10).is_err());
+        assert!(swap_commitment
+            .reveal(&EMPTY_SWAP_COMMITMENT, 100, 0)
+            .is_err());
+
+        swap_commitment.commit(commitment, 10).unwrap();
+        assert_eq!(swap_commitment.get_commit_slot(), 10);
+        // Too early, or a different swap or salt.
+        assert!(swap_commitment.reveal(&commitment, 14, 5).is_err());
+        assert!(swap_commitment
+            .reveal(&swap_commitment_hash(&[2; 32], &[4, 5, 6]), 15, 5)
+            .is_err());
+
+        swap_commitment.reveal(&commitment, 15, 5).unwrap();
+        // Only once.
+        assert!(swap_commitment.reveal(&commitment, 16, 5).is_err());
+    }
+}
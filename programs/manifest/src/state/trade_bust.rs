@@ -0,0 +1,66 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::Get;
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use static_assertions::const_assert_eq;
+
+use crate::{require, validation::ManifestAccount};
+
+use super::constants::TRADE_BUST_DISCRIMINANT;
+
+/// Record of a fill reversed by BustTrade. The account is created by the bust
+/// and its address is derived from the sequence numbers of the fill, so the
+/// same fill cannot be busted twice.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Zeroable, Pod)]
+pub struct TradeBust {
+    pub discriminant: u64,
+    pub market: Pubkey,
+    pub maker_sequence_number: u64,
+    pub taker_sequence_number: u64,
+    pub bust_slot: u32,
+    _padding: [u8; 4],
+}
+const_assert_eq!(
+    size_of::<TradeBust>(),
+    8 +   // discriminant
+    32 +  // market
+    8 +   // maker_sequence_number
+    8 +   // taker_sequence_number
+    4 +   // bust_slot
+    4 // padding
+);
+const_assert_eq!(size_of::<TradeBust>() % 8, 0);
+impl Get for TradeBust {}
+
+impl ManifestAccount for TradeBust {
+    fn verify_discriminant(&self) -> ProgramResult {
+        require!(
+            self.discriminant == TRADE_BUST_DISCRIMINANT,
+            ProgramError::InvalidAccountData,
+            "Invalid trade bust discriminant actual: {} expected: {}",
+            self.discriminant,
+            TRADE_BUST_DISCRIMINANT
+        )?;
+        Ok(())
+    }
+}
+
+impl TradeBust {
+    pub fn new(
+        market: &Pubkey,
+        maker_sequence_number: u64,
+        taker_sequence_number: u64,
+        bust_slot: u32,
+    ) -> Self {
+        TradeBust {
+            discriminant: TRADE_BUST_DISCRIMINANT,
+            market: *market,
+            maker_sequence_number,
+            taker_sequence_number,
+            bust_slot,
+            _padding: [0; 4],
+        }
+    }
+}
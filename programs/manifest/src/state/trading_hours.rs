@@ -0,0 +1,92 @@
+use super::constants::{MINUTES_PER_DAY, TRADING_DAYS_ALL};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+/// 1970-01-01 was a Thursday, so day zero is weekday 3 counting from Monday.
+const EPOCH_WEEKDAY: i64 = 3;
+
+/// Weekly window a market accepts new orders and swaps in, for markets whose
+/// underlying only trades at set hours. Times are minutes into the UTC day.
+/// Cancels and withdraws are always allowed.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TradingHours {
+    /// Bit 0 is Monday through bit 6 is Sunday. Zero means always open.
+    pub days: u8,
+    /// First minute of the day the market is open.
+    pub open_minute: u16,
+    /// First minute of the day the market is closed again.
+    pub close_minute: u16,
+}
+
+impl TradingHours {
+    pub fn new(days: u8, open_minute: u16, close_minute: u16) -> Self {
+        TradingHours {
+            days,
+            open_minute,
+            close_minute,
+        }
+    }
+
+    pub fn is_always_open(&self) -> bool {
+        self.days == 0
+    }
+
+    /// Windows do not wrap past midnight. A session over midnight is two
+    /// windows, which a single market cannot express.
+    pub fn is_valid(&self) -> bool {
+        if self.is_always_open() {
+            return self.open_minute == 0 && self.close_minute == 0;
+        }
+        self.days & !TRADING_DAYS_ALL == 0
+            && self.open_minute < self.close_minute
+            && self.close_minute <= MINUTES_PER_DAY
+    }
+
+    pub fn is_open(&self, unix_timestamp: i64) -> bool {
+        if self.is_always_open() {
+            return true;
+        }
+        let days_since_epoch: i64 = unix_timestamp.div_euclid(SECONDS_PER_DAY);
+        let weekday: i64 = (days_since_epoch + EPOCH_WEEKDAY).rem_euclid(7);
+        let minute: i64 = unix_timestamp.rem_euclid(SECONDS_PER_DAY) / 60;
+        self.days & (1 << weekday) != 0
+            && minute >= self.open_minute as i64
+            && minute < self.close_minute as i64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Monday 2024-01-01 00:00 UTC
+    const MONDAY: i64 = 1_704_067_200;
+
+    #[test]
+    fn test_weekday_hours() {
+        // Monday to Friday, 14:30 to 21:00 UTC
+        let hours: TradingHours = TradingHours::new(0b0011111, 870, 1_260);
+        assert!(hours.is_valid());
+        assert!(!hours.is_open(MONDAY));
+        assert!(!hours.is_open(MONDAY + 870 * 60 - 1));
+        assert!(hours.is_open(MONDAY + 870 * 60));
+        assert!(hours.is_open(MONDAY + 1_260 * 60 - 1));
+        assert!(!hours.is_open(MONDAY + 1_260 * 60));
+        // Friday is open, Saturday and Sunday are not.
+        assert!(hours.is_open(MONDAY + 4 * SECONDS_PER_DAY + 900 * 60));
+        assert!(!hours.is_open(MONDAY + 5 * SECONDS_PER_DAY + 900 * 60));
+        assert!(!hours.is_open(MONDAY + 6 * SECONDS_PER_DAY + 900 * 60));
+        assert!(hours.is_open(MONDAY + 7 * SECONDS_PER_DAY + 900 * 60));
+    }
+
+    #[test]
+    fn test_always_open_and_invalid() {
+        assert!(TradingHours::default().is_valid());
+        assert!(TradingHours::default().is_open(0));
+        assert!(TradingHours::default().is_open(i64::MAX));
+        assert!(!TradingHours::new(0, 1, 2).is_valid());
+        assert!(!TradingHours::new(1 << 7, 1, 2).is_valid());
+        assert!(!TradingHours::new(1, 2, 2).is_valid());
+        assert!(!TradingHours::new(1, 0, MINUTES_PER_DAY + 1).is_valid());
+        assert!(TradingHours::new(TRADING_DAYS_ALL, 0, MINUTES_PER_DAY).is_valid());
+    }
+}
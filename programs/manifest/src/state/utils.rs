@@ -29,8 +29,8 @@ use spl_token_2022::{
 };
 
 use super::{
-    order_type_can_take, GlobalRefMut, OrderType, RestingOrder, GAS_DEPOSIT_LAMPORTS,
-    NO_EXPIRATION_LAST_VALID_SLOT,
+    order_type_can_take, GlobalRefMut, MarketFixed, OrderType, RestingOrder, TradingHours,
+    GAS_DEPOSIT_LAMPORTS, NO_EXPIRATION_LAST_VALID_SLOT,
 };
 
 pub fn get_now_slot() -> u32 {
@@ -70,6 +70,22 @@ pub(crate) fn get_now_epoch() -> u64 {
     now_epoch
 }
 
+pub(crate) fn get_now_unix_timestamp() -> i64 {
+    #[cfg(feature = "no-clock")]
+    let now_unix_timestamp: i64 = 0;
+    #[cfg(not(feature = "no-clock"))]
+    let now_unix_timestamp: i64 = solana_program::clock::Clock::get()
+        .unwrap_or(solana_program::clock::Clock {
+            slot: u64::MAX,
+            epoch_start_timestamp: i64::MAX,
+            epoch: u64::MAX,
+            leader_schedule_epoch: u64::MAX,
+            unix_timestamp: i64::MAX,
+        })
+        .unix_timestamp;
+    now_unix_timestamp
+}
+
 #[inline(always)]
 pub(crate) fn remove_from_global(
     global_trade_accounts_opt: &Option<GlobalTradeAccounts>,
@@ -285,6 +301,48 @@ pub(crate) fn assert_not_already_expired(last_valid_slot: u32, now_slot: u32) ->
     Ok(())
 }
 
+/// New orders and swaps are only allowed inside the market's trading hours.
+/// The clock is only read for markets that have them.
+pub(crate) fn assert_market_open(trading_hours: &TradingHours) -> ProgramResult {
+    if trading_hours.is_always_open() {
+        return Ok(());
+    }
+    let now_unix_timestamp: i64 = get_now_unix_timestamp();
+    require!(
+        trading_hours.is_open(now_unix_timestamp),
+        crate::program::ManifestError::MarketClosed,
+        "Market is closed at {}, trading hours {:?}",
+        now_unix_timestamp,
+        trading_hours
+    )?;
+    Ok(())
+}
+
+/// Markets in recovery only pay out with RecoveryWithdraw.
+pub(crate) fn assert_not_in_recovery(fixed: &MarketFixed) -> ProgramResult {
+    require!(
+        !fixed.is_in_recovery(),
+        crate::program::ManifestError::InvalidRecovery,
+        "Market is in recovery",
+    )?;
+    Ok(())
+}
+
+/// Turn a number of slots in force into an absolute last valid slot. Zero
+/// stays no expiration.
+pub(crate) fn resolve_relative_last_valid_slot(
+    slots_in_force: u32,
+    now_slot: u32,
+) -> Result<u32, ProgramError> {
+    if slots_in_force == NO_EXPIRATION_LAST_VALID_SLOT {
+        return Ok(NO_EXPIRATION_LAST_VALID_SLOT);
+    }
+    match now_slot.checked_add(slots_in_force) {
+        Some(last_valid_slot) => Ok(last_valid_slot),
+        None => Err(crate::program::ManifestError::Overflow.into()),
+    }
+}
+
 pub(crate) fn assert_already_has_seat(trader_index: DataIndex) -> ProgramResult {
     require!(
         trader_index != NIL,
@@ -1,6 +1,13 @@
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, keccak, program::invoke_signed,
-    program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction,
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    keccak,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
 };
 
 /// Canonical discriminant of the given struct. It is the hash of program ID and
@@ -42,6 +49,43 @@ pub fn create_account<'a, 'info>(
     )
 }
 
+/// Send CPI for creating the associated token account of wallet for mint. Does
+/// nothing if it already exists.
+pub fn create_associated_token_account_idempotent<'a, 'info>(
+    payer: &'a AccountInfo<'info>,
+    associated_token_account: &'a AccountInfo<'info>,
+    wallet: &'a AccountInfo<'info>,
+    mint: &'a AccountInfo<'info>,
+    system_program: &'a AccountInfo<'info>,
+    token_program: &'a AccountInfo<'info>,
+    associated_token_program: &'a AccountInfo<'info>,
+) -> ProgramResult {
+    crate::program::invoke(
+        &Instruction {
+            program_id: *associated_token_program.key,
+            accounts: vec![
+                AccountMeta::new(*payer.key, true),
+                AccountMeta::new(*associated_token_account.key, false),
+                AccountMeta::new_readonly(*wallet.key, false),
+                AccountMeta::new_readonly(*mint.key, false),
+                AccountMeta::new_readonly(*system_program.key, false),
+                AccountMeta::new_readonly(*token_program.key, false),
+            ],
+            // AssociatedTokenAccountInstruction::CreateIdempotent
+            data: vec![1],
+        },
+        &[
+            payer.clone(),
+            associated_token_account.clone(),
+            wallet.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+    )
+}
+
 #[test]
 fn test_get_discriminant() {
     // Update this when updating program id.
@@ -6,6 +6,8 @@ use std::{
 use hypertree::{get_helper, trace};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable,
+    entrypoint::ProgramResult,
     program_error::ProgramError,
     pubkey::Pubkey,
     system_program,
@@ -14,97 +16,920 @@ use solana_program::{
 use crate::{
     program::ManifestError,
     require,
-    state::{GlobalFixed, MarketFixed},
+    state::{
+        GlobalFixed, Heartbeat, MarketFixed, MarketVolatility, PeggedOrders, Rfq, SeatArchive,
+        SignedOrderNonces, StopOrders,
+    },
+    utils::create_associated_token_account_idempotent,
     validation::{
-        get_global_address, EmptyAccount, MintAccountInfo, Program, Signer, TokenAccountInfo,
+        get_global_address, get_heartbeat_address, get_market_volatility_address,
+        get_pegged_orders_address, get_rfq_address, get_seat_archive_address,
+        get_signed_order_nonces_address, get_stop_orders_address, get_swap_commitment_address,
+        is_mint_account, EmptyAccount, MintAccountInfo, Program, Signer, TokenAccountInfo,
+        SPL_ASSOCIATED_TOKEN_ACCOUNT_PROGRAM_ID, SPL_MEMO_PROGRAM_ID,
     },
 };
 
-use super::{get_vault_address, ManifestAccountInfo, TokenProgram};
+use super::{get_vault_address, ManifestAccountInfo, TokenProgram};
+
+#[cfg(feature = "certora")]
+use early_panic::early_panic;
+
+/// CreateMarket account infos
+pub(crate) struct CreateMarketContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub base_mint: MintAccountInfo<'a, 'info>,
+    pub quote_mint: MintAccountInfo<'a, 'info>,
+    pub base_vault: EmptyAccount<'a, 'info>,
+    pub quote_vault: EmptyAccount<'a, 'info>,
+    pub system_program: Program<'a, 'info>,
+    pub token_program: TokenProgram<'a, 'info>,
+    pub token_program_22: TokenProgram<'a, 'info>,
+}
+
+impl<'a, 'info> CreateMarketContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new_init(next_account_info(account_iter)?)?;
+        let system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let base_mint: MintAccountInfo = MintAccountInfo::new(next_account_info(account_iter)?)?;
+        let quote_mint: MintAccountInfo = MintAccountInfo::new(next_account_info(account_iter)?)?;
+        let base_vault: EmptyAccount = EmptyAccount::new(next_account_info(account_iter)?)?;
+        let quote_vault: EmptyAccount = EmptyAccount::new(next_account_info(account_iter)?)?;
+
+        let (expected_base_vault, _base_vault_bump) =
+            get_vault_address(market.key, base_mint.info.key);
+        let (expected_quote_vault, _quote_vault_bump) =
+            get_vault_address(market.key, quote_mint.info.key);
+
+        require!(
+            expected_base_vault == *base_vault.info.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect base vault account",
+        )?;
+        require!(
+            expected_quote_vault == *quote_vault.info.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect quote vault account",
+        )?;
+        let token_program: TokenProgram = TokenProgram::new(next_account_info(account_iter)?)?;
+        let token_program_22: TokenProgram = TokenProgram::new(next_account_info(account_iter)?)?;
+
+        Ok(Self {
+            payer,
+            market,
+            base_vault,
+            quote_vault,
+            base_mint,
+            quote_mint,
+            token_program,
+            token_program_22,
+            system_program,
+        })
+    }
+}
+
+/// ClaimSeat account infos
+pub(crate) struct ClaimSeatContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub _system_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> ClaimSeatContext<'a, 'info> {
+    #[cfg_attr(all(feature = "certora", not(feature = "certora-test")), early_panic)]
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let _system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        Ok(Self {
+            payer,
+            market,
+            _system_program,
+        })
+    }
+}
+
+/// SetSeatConfig account infos
+pub(crate) struct SetSeatConfigContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub _system_program: Program<'a, 'info>,
+    // Required when the seat already has a cosigner.
+    pub cosigner_opt: Option<Signer<'a, 'info>>,
+}
+
+impl<'a, 'info> SetSeatConfigContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let _system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let cosigner_opt: Option<Signer> = match next_account_info(account_iter) {
+            Ok(cosigner) => Some(Signer::new(cosigner)?),
+            Err(_) => None,
+        };
+        Ok(Self {
+            payer,
+            market,
+            _system_program,
+            cosigner_opt,
+        })
+    }
+}
+
+/// Rebalance account infos
+pub(crate) struct RebalanceContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    // Required when the trade is above the cosign threshold of the seat.
+    pub cosigner_opt: Option<Signer<'a, 'info>>,
+}
+
+impl<'a, 'info> RebalanceContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let cosigner_opt: Option<Signer> = match next_account_info(account_iter) {
+            Ok(cosigner) => Some(Signer::new(cosigner)?),
+            Err(_) => None,
+        };
+        Ok(Self {
+            payer,
+            market,
+            cosigner_opt,
+        })
+    }
+}
+
+/// EnterRecovery account infos
+pub(crate) struct EnterRecoveryContext<'a, 'info> {
+    // Upgrade authority of the program.
+    pub _authority: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+}
+
+impl<'a, 'info> EnterRecoveryContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let authority: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        verify_upgrade_authority(
+            next_account_info(account_iter)?,
+            authority.key,
+            ManifestError::InvalidRecovery,
+        )?;
+
+        Ok(Self {
+            _authority: authority,
+            market,
+        })
+    }
+}
+
+/// Checks that authority is the upgrade authority in the program data account
+/// of this program, failing with error otherwise.
+fn verify_upgrade_authority(
+    program_data: &AccountInfo,
+    authority: &Pubkey,
+    error: ManifestError,
+) -> ProgramResult {
+    let (expected_program_data_key, _program_data_bump) =
+        Pubkey::find_program_address(&[crate::id().as_ref()], &bpf_loader_upgradeable::id());
+    require!(
+        expected_program_data_key == *program_data.key
+            && *program_data.owner == bpf_loader_upgradeable::id(),
+        ManifestError::IncorrectAccount,
+        "Incorrect program data account",
+    )?;
+
+    // UpgradeableLoaderState::ProgramData is a u32 tag of 3, the deploy
+    // slot and an optional upgrade authority.
+    let program_data_bytes: Ref<&mut [u8]> = program_data.try_borrow_data()?;
+    require!(
+        program_data_bytes.len() >= 45
+            && program_data_bytes[0..4] == 3_u32.to_le_bytes()
+            && program_data_bytes[12] == 1
+            && program_data_bytes[13..45] == authority.to_bytes(),
+        error,
+        "Signer is not the program upgrade authority",
+    )?;
+    Ok(())
+}
+
+/// BustTrade account infos
+pub(crate) struct BustTradeContext<'a, 'info> {
+    // Upgrade authority of the program, pays for the trade bust record.
+    pub authority: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub system_program: Program<'a, 'info>,
+    // Created by the bust, so a fill that was busted before fails here. Its
+    // address depends on the fill and is checked by the processor.
+    pub trade_bust: EmptyAccount<'a, 'info>,
+}
+
+impl<'a, 'info> BustTradeContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let authority: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        verify_upgrade_authority(
+            next_account_info(account_iter)?,
+            authority.key,
+            ManifestError::InvalidTradeBust,
+        )?;
+        let system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let trade_bust: EmptyAccount = EmptyAccount::new(next_account_info(account_iter)?)?;
+        Ok(Self {
+            authority,
+            market,
+            system_program,
+            trade_bust,
+        })
+    }
+}
+
+/// ResetPriceBand account infos
+pub(crate) struct ResetPriceBandContext<'a, 'info> {
+    pub _payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+}
+
+impl<'a, 'info> ResetPriceBandContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let _payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        Ok(Self { _payer, market })
+    }
+}
+
+/// ClaimPoints account infos
+pub(crate) struct ClaimPointsContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub _system_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> ClaimPointsContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let _system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        Ok(Self {
+            payer,
+            market,
+            _system_program,
+        })
+    }
+}
+
+/// SetMmp account infos
+pub(crate) struct SetMmpContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub _system_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> SetMmpContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let _system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        Ok(Self {
+            payer,
+            market,
+            _system_program,
+        })
+    }
+}
+
+/// SetPaused account infos
+pub(crate) struct SetPausedContext<'a, 'info> {
+    pub pauser: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+}
+
+impl<'a, 'info> SetPausedContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let pauser: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        Ok(Self { pauser, market })
+    }
+}
+
+/// PostRfq account infos
+pub(crate) struct PostRfqContext<'a, 'info> {
+    pub taker: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub system_program: Program<'a, 'info>,
+    // Created by the first request of the taker on a market, so it is not
+    // loaded as a ManifestAccountInfo here.
+    pub rfq: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> PostRfqContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let taker: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let rfq: &AccountInfo = next_account_info(account_iter)?;
+        let (expected_rfq_key, _rfq_bump) = get_rfq_address(market.key, taker.key);
+        require!(
+            expected_rfq_key == *rfq.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect rfq account",
+        )?;
+        Ok(Self {
+            taker,
+            market,
+            system_program,
+            rfq,
+        })
+    }
+}
+
+/// FillRfqContext account infos
+pub(crate) struct FillRfqContext<'a, 'info> {
+    pub taker: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub rfq: ManifestAccountInfo<'a, 'info, Rfq>,
+}
+
+impl<'a, 'info> FillRfqContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let taker: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let rfq: ManifestAccountInfo<Rfq> =
+            ManifestAccountInfo::<Rfq>::new(next_account_info(account_iter)?)?;
+        let (expected_rfq_key, _rfq_bump) = get_rfq_address(market.key, taker.key);
+        require!(
+            expected_rfq_key == *rfq.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect rfq account",
+        )?;
+        Ok(Self { taker, market, rfq })
+    }
+}
+
+/// FillSignedOrder account infos
+pub(crate) struct FillSignedOrderContext<'a, 'info> {
+    pub taker: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub signed_order_nonces: ManifestAccountInfo<'a, 'info, SignedOrderNonces>,
+}
+
+impl<'a, 'info> FillSignedOrderContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let taker: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let signed_order_nonces: ManifestAccountInfo<SignedOrderNonces> =
+            ManifestAccountInfo::<SignedOrderNonces>::new(next_account_info(account_iter)?)?;
+        let (expected_signed_order_nonces_key, _signed_order_nonces_bump) =
+            get_signed_order_nonces_address(market.key, &signed_order_nonces.get_fixed()?.trader);
+        require!(
+            expected_signed_order_nonces_key == *signed_order_nonces.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect signed order nonces account",
+        )?;
+        Ok(Self {
+            taker,
+            market,
+            signed_order_nonces,
+        })
+    }
+}
+
+/// CancelSignedOrders account infos
+pub(crate) struct CancelSignedOrdersContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub system_program: Program<'a, 'info>,
+    // Created by the first call of the payer on a market, so it is not loaded
+    // as a ManifestAccountInfo here.
+    pub signed_order_nonces: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> CancelSignedOrdersContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let signed_order_nonces: &AccountInfo = next_account_info(account_iter)?;
+        let (expected_signed_order_nonces_key, _signed_order_nonces_bump) =
+            get_signed_order_nonces_address(market.key, payer.key);
+        require!(
+            expected_signed_order_nonces_key == *signed_order_nonces.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect signed order nonces account",
+        )?;
+        Ok(Self {
+            payer,
+            market,
+            system_program,
+            signed_order_nonces,
+        })
+    }
+}
+
+/// GetMarketPrices account infos
+pub(crate) struct GetMarketPricesContext<'a, 'info> {
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+}
+
+impl<'a, 'info> GetMarketPricesContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        Ok(Self { market })
+    }
+}
+
+/// EmitDepthSnapshot account infos
+pub(crate) struct EmitDepthSnapshotContext<'a, 'info> {
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+}
+
+impl<'a, 'info> EmitDepthSnapshotContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        Ok(Self { market })
+    }
+}
+
+/// GetSeatPoints account infos
+pub(crate) struct GetSeatPointsContext<'a, 'info> {
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+}
+
+impl<'a, 'info> GetSeatPointsContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        Ok(Self { market })
+    }
+}
+
+/// ArchiveSeat account infos
+pub(crate) struct ArchiveSeatContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub system_program: Program<'a, 'info>,
+    // Created by the first archive on a market, so it is not loaded as a
+    // ManifestAccountInfo here.
+    pub seat_archive: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> ArchiveSeatContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let seat_archive: &AccountInfo = next_account_info(account_iter)?;
+        let (expected_seat_archive_key, _seat_archive_bump) = get_seat_archive_address(market.key);
+        require!(
+            expected_seat_archive_key == *seat_archive.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect seat archive account",
+        )?;
+        Ok(Self {
+            payer,
+            market,
+            system_program,
+            seat_archive,
+        })
+    }
+}
+
+/// CommitSwap account infos
+pub(crate) struct CommitSwapContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub system_program: Program<'a, 'info>,
+    // Created by the first commit of the payer on a market, so it is not
+    // loaded as a ManifestAccountInfo here.
+    pub swap_commitment: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> CommitSwapContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let swap_commitment: &AccountInfo = next_account_info(account_iter)?;
+        let (expected_swap_commitment_key, _swap_commitment_bump) =
+            get_swap_commitment_address(market.key, payer.key);
+        require!(
+            expected_swap_commitment_key == *swap_commitment.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect swap commitment account",
+        )?;
+        Ok(Self {
+            payer,
+            market,
+            system_program,
+            swap_commitment,
+        })
+    }
+}
+
+/// PlaceStopOrder account infos
+pub(crate) struct PlaceStopOrderContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub system_program: Program<'a, 'info>,
+    // Created by the first stop order of the payer on a market, so it is not
+    // loaded as a ManifestAccountInfo here.
+    pub stop_orders: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> PlaceStopOrderContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let stop_orders: &AccountInfo = next_account_info(account_iter)?;
+        let (expected_stop_orders_key, _stop_orders_bump) =
+            get_stop_orders_address(market.key, payer.key);
+        require!(
+            expected_stop_orders_key == *stop_orders.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect stop orders account",
+        )?;
+        Ok(Self {
+            payer,
+            market,
+            system_program,
+            stop_orders,
+        })
+    }
+}
+
+/// CancelStopOrder account infos
+pub(crate) struct CancelStopOrderContext<'a, 'info> {
+    pub _payer: Signer<'a, 'info>,
+    pub stop_orders: ManifestAccountInfo<'a, 'info, StopOrders>,
+}
 
-#[cfg(feature = "certora")]
-use early_panic::early_panic;
+impl<'a, 'info> CancelStopOrderContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
 
-/// CreateMarket account infos
-pub(crate) struct CreateMarketContext<'a, 'info> {
+        let payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let stop_orders: ManifestAccountInfo<StopOrders> =
+            ManifestAccountInfo::<StopOrders>::new(next_account_info(account_iter)?)?;
+        require!(
+            stop_orders.get_fixed()?.trader == *payer.key,
+            ManifestError::IncorrectAccount,
+            "Stop orders belong to {}",
+            stop_orders.get_fixed()?.trader,
+        )?;
+        Ok(Self {
+            _payer: payer,
+            stop_orders,
+        })
+    }
+}
+
+/// TriggerStopOrders account infos
+pub(crate) struct TriggerStopOrdersContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub _system_program: Program<'a, 'info>,
+    pub stop_orders: ManifestAccountInfo<'a, 'info, StopOrders>,
+}
+
+impl<'a, 'info> TriggerStopOrdersContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let _system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let stop_orders: ManifestAccountInfo<StopOrders> =
+            ManifestAccountInfo::<StopOrders>::new(next_account_info(account_iter)?)?;
+        let (expected_stop_orders_key, _stop_orders_bump) =
+            get_stop_orders_address(market.key, &stop_orders.get_fixed()?.trader);
+        require!(
+            expected_stop_orders_key == *stop_orders.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect stop orders account",
+        )?;
+        Ok(Self {
+            payer,
+            market,
+            _system_program,
+            stop_orders,
+        })
+    }
+}
+
+/// PlacePeggedOrder account infos
+pub(crate) struct PlacePeggedOrderContext<'a, 'info> {
     pub payer: Signer<'a, 'info>,
     pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
-    pub base_mint: MintAccountInfo<'a, 'info>,
-    pub quote_mint: MintAccountInfo<'a, 'info>,
-    pub base_vault: EmptyAccount<'a, 'info>,
-    pub quote_vault: EmptyAccount<'a, 'info>,
     pub system_program: Program<'a, 'info>,
-    pub token_program: TokenProgram<'a, 'info>,
-    pub token_program_22: TokenProgram<'a, 'info>,
+    // Created by the first pegged order of the payer on a market, so it is not
+    // loaded as a ManifestAccountInfo here.
+    pub pegged_orders: &'a AccountInfo<'info>,
 }
 
-impl<'a, 'info> CreateMarketContext<'a, 'info> {
+impl<'a, 'info> PlacePeggedOrderContext<'a, 'info> {
     pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
         let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
 
         let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
         let market: ManifestAccountInfo<MarketFixed> =
-            ManifestAccountInfo::<MarketFixed>::new_init(next_account_info(account_iter)?)?;
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
         let system_program: Program =
             Program::new(next_account_info(account_iter)?, &system_program::id())?;
-        let base_mint: MintAccountInfo = MintAccountInfo::new(next_account_info(account_iter)?)?;
-        let quote_mint: MintAccountInfo = MintAccountInfo::new(next_account_info(account_iter)?)?;
-        let base_vault: EmptyAccount = EmptyAccount::new(next_account_info(account_iter)?)?;
-        let quote_vault: EmptyAccount = EmptyAccount::new(next_account_info(account_iter)?)?;
+        let pegged_orders: &AccountInfo = next_account_info(account_iter)?;
+        let (expected_pegged_orders_key, _pegged_orders_bump) =
+            get_pegged_orders_address(market.key, payer.key);
+        require!(
+            expected_pegged_orders_key == *pegged_orders.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect pegged orders account",
+        )?;
+        Ok(Self {
+            payer,
+            market,
+            system_program,
+            pegged_orders,
+        })
+    }
+}
 
-        let (expected_base_vault, _base_vault_bump) =
-            get_vault_address(market.key, base_mint.info.key);
-        let (expected_quote_vault, _quote_vault_bump) =
-            get_vault_address(market.key, quote_mint.info.key);
+/// CancelPeggedOrder account infos
+pub(crate) struct CancelPeggedOrderContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub pegged_orders: ManifestAccountInfo<'a, 'info, PeggedOrders>,
+}
 
+impl<'a, 'info> CancelPeggedOrderContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let pegged_orders: ManifestAccountInfo<PeggedOrders> =
+            ManifestAccountInfo::<PeggedOrders>::new(next_account_info(account_iter)?)?;
+        let (expected_pegged_orders_key, _pegged_orders_bump) =
+            get_pegged_orders_address(market.key, payer.key);
         require!(
-            expected_base_vault == *base_vault.info.key,
+            expected_pegged_orders_key == *pegged_orders.key,
             ManifestError::IncorrectAccount,
-            "Incorrect base vault account",
+            "Incorrect pegged orders account",
         )?;
+        Ok(Self {
+            payer,
+            market,
+            pegged_orders,
+        })
+    }
+}
+
+/// RepricePeggedOrders account infos
+pub(crate) struct RepricePeggedOrdersContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub _system_program: Program<'a, 'info>,
+    pub pegged_orders: ManifestAccountInfo<'a, 'info, PeggedOrders>,
+}
+
+impl<'a, 'info> RepricePeggedOrdersContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let _system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let pegged_orders: ManifestAccountInfo<PeggedOrders> =
+            ManifestAccountInfo::<PeggedOrders>::new(next_account_info(account_iter)?)?;
+        let (expected_pegged_orders_key, _pegged_orders_bump) =
+            get_pegged_orders_address(market.key, &pegged_orders.get_fixed()?.trader);
         require!(
-            expected_quote_vault == *quote_vault.info.key,
+            expected_pegged_orders_key == *pegged_orders.key,
             ManifestError::IncorrectAccount,
-            "Incorrect quote vault account",
+            "Incorrect pegged orders account",
         )?;
-        let token_program: TokenProgram = TokenProgram::new(next_account_info(account_iter)?)?;
-        let token_program_22: TokenProgram = TokenProgram::new(next_account_info(account_iter)?)?;
+        Ok(Self {
+            payer,
+            market,
+            _system_program,
+            pegged_orders,
+        })
+    }
+}
+
+/// Heartbeat account infos
+pub(crate) struct HeartbeatContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub system_program: Program<'a, 'info>,
+    // Created by the first heartbeat of the payer on a market, so it is not
+    // loaded as a ManifestAccountInfo here.
+    pub heartbeat: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> HeartbeatContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let heartbeat: &AccountInfo = next_account_info(account_iter)?;
+        let (expected_heartbeat_key, _heartbeat_bump) =
+            get_heartbeat_address(market.key, payer.key);
+        require!(
+            expected_heartbeat_key == *heartbeat.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect heartbeat account",
+        )?;
+        Ok(Self {
+            payer,
+            market,
+            system_program,
+            heartbeat,
+        })
+    }
+}
+
+/// CancelOnHeartbeatTimeout account infos
+pub(crate) struct CancelOnHeartbeatTimeoutContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub heartbeat: ManifestAccountInfo<'a, 'info, Heartbeat>,
+}
+
+impl<'a, 'info> CancelOnHeartbeatTimeoutContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
 
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let heartbeat: ManifestAccountInfo<Heartbeat> =
+            ManifestAccountInfo::<Heartbeat>::new(next_account_info(account_iter)?)?;
+        let (expected_heartbeat_key, _heartbeat_bump) =
+            get_heartbeat_address(market.key, &heartbeat.get_fixed()?.trader);
+        require!(
+            expected_heartbeat_key == *heartbeat.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect heartbeat account",
+        )?;
+        Ok(Self {
+            payer,
+            market,
+            heartbeat,
+        })
+    }
+}
+
+/// UpdateVolatility account infos
+pub(crate) struct UpdateVolatilityContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub system_program: Program<'a, 'info>,
+    // Created by the first update on a market, so it is not loaded as a
+    // ManifestAccountInfo here.
+    pub market_volatility: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> UpdateVolatilityContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let market_volatility: &AccountInfo = next_account_info(account_iter)?;
+        let (expected_market_volatility_key, _market_volatility_bump) =
+            get_market_volatility_address(market.key);
+        require!(
+            expected_market_volatility_key == *market_volatility.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect market volatility account",
+        )?;
+        if !market_volatility.data_is_empty() {
+            ManifestAccountInfo::<MarketVolatility>::new(market_volatility)?;
+        }
         Ok(Self {
             payer,
             market,
-            base_vault,
-            quote_vault,
-            base_mint,
-            quote_mint,
-            token_program,
-            token_program_22,
             system_program,
+            market_volatility,
         })
     }
 }
 
-/// ClaimSeat account infos
-pub(crate) struct ClaimSeatContext<'a, 'info> {
+/// RestoreSeat account infos
+pub(crate) struct RestoreSeatContext<'a, 'info> {
     pub payer: Signer<'a, 'info>,
     pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
     pub _system_program: Program<'a, 'info>,
+    pub seat_archive: ManifestAccountInfo<'a, 'info, SeatArchive>,
 }
 
-impl<'a, 'info> ClaimSeatContext<'a, 'info> {
-    #[cfg_attr(all(feature = "certora", not(feature = "certora-test")), early_panic)]
+impl<'a, 'info> RestoreSeatContext<'a, 'info> {
     pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
         let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
 
-        let payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
         let market: ManifestAccountInfo<MarketFixed> =
             ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
         let _system_program: Program =
             Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let seat_archive: ManifestAccountInfo<SeatArchive> =
+            ManifestAccountInfo::<SeatArchive>::new(next_account_info(account_iter)?)?;
+        let (expected_seat_archive_key, _seat_archive_bump) = get_seat_archive_address(market.key);
+        require!(
+            expected_seat_archive_key == *seat_archive.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect seat archive account",
+        )?;
         Ok(Self {
             payer,
             market,
             _system_program,
+            seat_archive,
         })
     }
 }
@@ -196,10 +1021,31 @@ impl<'a, 'info> DepositContext<'a, 'info> {
     }
 }
 
+/// DepositIdempotent account infos
+pub(crate) struct DepositIdempotentContext<'a, 'info> {
+    pub deposit: DepositContext<'a, 'info>,
+    pub _system_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> DepositIdempotentContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let deposit: DepositContext = DepositContext::load(accounts)?;
+
+        // System program follows the deposit accounts.
+        let system_program_info: &AccountInfo<'info> =
+            accounts.get(6).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let _system_program: Program = Program::new(system_program_info, &system_program::id())?;
+        Ok(Self {
+            deposit,
+            _system_program,
+        })
+    }
+}
+
 /// Withdraw account infos
 pub(crate) struct WithdrawContext<'a, 'info> {
     // TODO: Separate owner and payer so you can crank a withdraw on behalf of
-    // someone else to their token account.
+    // someone else.
     pub payer: Signer<'a, 'info>,
     pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
     pub trader_token: TokenAccountInfo<'a, 'info>,
@@ -231,8 +1077,9 @@ impl<'a, 'info> WithdrawContext<'a, 'info> {
                 return Err(ManifestError::InvalidWithdrawAccounts.into());
             };
 
-        let trader_token: TokenAccountInfo =
-            TokenAccountInfo::new_with_owner(token_account_info, mint, payer.key)?;
+        // Destination does not need to be owned by the payer. The payer signs
+        // for their own seat balance, so they can send it to any wallet.
+        let trader_token: TokenAccountInfo = TokenAccountInfo::new(token_account_info, mint)?;
         let vault: TokenAccountInfo = TokenAccountInfo::new_with_owner_and_key(
             next_account_info(account_iter)?,
             mint,
@@ -272,6 +1119,12 @@ pub(crate) struct SwapContext<'a, 'info> {
 
     // One for each side. First is base, then is quote.
     pub global_trade_accounts_opts: [Option<GlobalTradeAccounts<'a, 'info>>; 2],
+
+    // When included, fills are also sent to the memo program.
+    pub fill_memo_program_opt: Option<Program<'a, 'info>>,
+
+    // When included, paid the referral share of the taker fee.
+    pub referrer_token_info_opt: Option<&'a AccountInfo<'info>>,
 }
 
 impl<'a, 'info> SwapContext<'a, 'info> {
@@ -302,18 +1155,19 @@ impl<'a, 'info> SwapContext<'a, 'info> {
                 )
             };
 
-        // Included in case we need to expand for a reverse order.
-        let _system_program: Program =
+        // Included in case we need to expand for a reverse order or create a
+        // trader token account.
+        let system_program: Program =
             Program::new(next_account_info(account_iter)?, &system_program::id())?;
 
         let market_fixed: Ref<MarketFixed> = market.get_fixed()?;
         let base_mint_key: Pubkey = *market_fixed.get_base_mint();
         let quote_mint_key: Pubkey = *market_fixed.get_quote_mint();
 
-        let trader_base: TokenAccountInfo =
-            TokenAccountInfo::new(next_account_info(account_iter)?, &base_mint_key)?;
-        let trader_quote: TokenAccountInfo =
-            TokenAccountInfo::new(next_account_info(account_iter)?, &quote_mint_key)?;
+        // Validated once the optional accounts are known, since either may
+        // still have to be created.
+        let trader_base_info: &'a AccountInfo<'info> = next_account_info(account_iter)?;
+        let trader_quote_info: &'a AccountInfo<'info> = next_account_info(account_iter)?;
         let base_vault_address: &Pubkey = market_fixed.get_base_vault();
         let quote_vault_address: &Pubkey = market_fixed.get_quote_vault();
 
@@ -337,11 +1191,10 @@ impl<'a, 'info> SwapContext<'a, 'info> {
         let mut current_account_info_or: Result<&AccountInfo<'info>, ProgramError> =
             next_account_info(account_iter);
 
-        // Possibly includes base mint.
-        if current_account_info_or
-            .as_ref()
-            .is_ok_and(|f| *f.owner == spl_token::id() || *f.owner == spl_token_2022::id())
-        {
+        // Possibly includes base mint. A token account here is the referrer.
+        if current_account_info_or.as_ref().is_ok_and(|f| {
+            (*f.owner == spl_token::id() || *f.owner == spl_token_2022::id()) && is_mint_account(f)
+        }) {
             let current_account_info: &AccountInfo<'info> = current_account_info_or?;
             base_mint = Some(MintAccountInfo::new(current_account_info)?);
             current_account_info_or = next_account_info(account_iter);
@@ -364,12 +1217,95 @@ impl<'a, 'info> SwapContext<'a, 'info> {
             current_account_info_or = next_account_info(account_iter);
         }
         // Possibly includes quote mint if the quote token program was token22.
+        if current_account_info_or.as_ref().is_ok_and(|f| {
+            (*f.owner == spl_token::id() || *f.owner == spl_token_2022::id()) && is_mint_account(f)
+        }) {
+            let current_account_info: &AccountInfo<'info> = current_account_info_or?;
+            quote_mint = Some(MintAccountInfo::new(current_account_info)?);
+            current_account_info_or = next_account_info(account_iter);
+        }
+
+        // Possibly includes the associated token account program, which
+        // creates the trader token accounts that do not exist yet. Their mints
+        // have to be included for that.
+        let mut associated_token_program_opt: Option<Program<'a, 'info>> = None;
         if current_account_info_or
             .as_ref()
-            .is_ok_and(|f| *f.owner == spl_token::id() || *f.owner == spl_token_2022::id())
+            .is_ok_and(|f| *f.key == SPL_ASSOCIATED_TOKEN_ACCOUNT_PROGRAM_ID)
         {
             let current_account_info: &AccountInfo<'info> = current_account_info_or?;
-            quote_mint = Some(MintAccountInfo::new(current_account_info)?);
+            associated_token_program_opt = Some(Program::new(
+                current_account_info,
+                &SPL_ASSOCIATED_TOKEN_ACCOUNT_PROGRAM_ID,
+            )?);
+            current_account_info_or = next_account_info(account_iter);
+        }
+        if let Some(associated_token_program) = &associated_token_program_opt {
+            for (trader_token_info, mint_key, mint_opt, token_program) in [
+                (
+                    trader_base_info,
+                    &base_mint_key,
+                    &base_mint,
+                    &token_program_base,
+                ),
+                (
+                    trader_quote_info,
+                    &quote_mint_key,
+                    &quote_mint,
+                    &token_program_quote,
+                ),
+            ] {
+                if !trader_token_info.data_is_empty() {
+                    continue;
+                }
+                require!(
+                    mint_opt
+                        .as_ref()
+                        .is_some_and(|mint| mint.info.key == mint_key),
+                    ManifestError::IncorrectAccount,
+                    "Mint {} is needed to create token account {}",
+                    mint_key,
+                    trader_token_info.key,
+                )?;
+                if let Some(mint) = mint_opt {
+                    create_associated_token_account_idempotent(
+                        payer,
+                        trader_token_info,
+                        owner.info,
+                        mint.info,
+                        system_program.info,
+                        token_program.info,
+                        associated_token_program.info,
+                    )?;
+                }
+            }
+        }
+        let trader_base: TokenAccountInfo =
+            TokenAccountInfo::new(trader_base_info, &base_mint_key)?;
+        let trader_quote: TokenAccountInfo =
+            TokenAccountInfo::new(trader_quote_info, &quote_mint_key)?;
+
+        // Memo program is the last account. It is here when there are no
+        // global accounts.
+        let mut fill_memo_program_opt: Option<Program<'a, 'info>> = None;
+        if current_account_info_or
+            .as_ref()
+            .is_ok_and(|f| *f.key == SPL_MEMO_PROGRAM_ID)
+        {
+            let current_account_info: &AccountInfo<'info> = current_account_info_or?;
+            fill_memo_program_opt = Some(Program::new(current_account_info, &SPL_MEMO_PROGRAM_ID)?);
+            current_account_info_or = next_account_info(account_iter);
+        }
+
+        // Possibly includes the token account of a referrer before the global
+        // accounts. Its mint is checked against the output once the direction
+        // of the swap is known.
+        let mut referrer_token_info_opt: Option<&'a AccountInfo<'info>> = None;
+        if current_account_info_or
+            .as_ref()
+            .is_ok_and(|f| *f.owner == spl_token::id() || *f.owner == spl_token_2022::id())
+        {
+            referrer_token_info_opt = Some(current_account_info_or?);
             current_account_info_or = next_account_info(account_iter);
         }
 
@@ -451,6 +1387,13 @@ impl<'a, 'info> SwapContext<'a, 'info> {
             }
         }
 
+        // Or after the global accounts.
+        if let Ok(next_account) = next_account_info(account_iter) {
+            if *next_account.key == SPL_MEMO_PROGRAM_ID {
+                fill_memo_program_opt = Some(Program::new(next_account, &SPL_MEMO_PROGRAM_ID)?);
+            }
+        }
+
         Ok(Self {
             payer: payer.clone(),
             owner,
@@ -464,6 +1407,8 @@ impl<'a, 'info> SwapContext<'a, 'info> {
             base_mint,
             quote_mint,
             global_trade_accounts_opts,
+            fill_memo_program_opt,
+            referrer_token_info_opt,
         })
     }
 }
@@ -507,6 +1452,13 @@ pub(crate) struct BatchUpdateContext<'a, 'info> {
 
     // One for each side. First is base, then is quote.
     pub global_trade_accounts_opts: [Option<GlobalTradeAccounts<'a, 'info>>; 2],
+
+    // Second signer for seats that have a cosigner configured. Comes after
+    // the global accounts, if any.
+    pub cosigner_opt: Option<Signer<'a, 'info>>,
+
+    // When included, fills are also sent to the memo program.
+    pub fill_memo_program_opt: Option<Program<'a, 'info>>,
 }
 
 impl<'a, 'info> BatchUpdateContext<'a, 'info> {
@@ -526,6 +1478,14 @@ impl<'a, 'info> BatchUpdateContext<'a, 'info> {
         #[cfg(not(feature = "certora"))]
         let mut global_trade_accounts_opts: [Option<GlobalTradeAccounts<'a, 'info>>; 2] =
             [None, None];
+        #[cfg(feature = "certora")]
+        let cosigner_opt: Option<Signer<'a, 'info>> = None;
+        #[cfg(not(feature = "certora"))]
+        let mut cosigner_opt: Option<Signer<'a, 'info>> = None;
+        #[cfg(feature = "certora")]
+        let fill_memo_program_opt: Option<Program<'a, 'info>> = None;
+        #[cfg(not(feature = "certora"))]
+        let mut fill_memo_program_opt: Option<Program<'a, 'info>> = None;
 
         #[cfg(not(feature = "certora"))]
         {
@@ -536,12 +1496,19 @@ impl<'a, 'info> BatchUpdateContext<'a, 'info> {
             let quote_vault: Pubkey = *market_fixed.get_quote_vault();
             drop(market_fixed);
 
+            let mut trailing_account_opt: Option<&'a AccountInfo<'info>> = None;
             for _ in 0..2 {
                 let next_account_info_or: Result<&AccountInfo<'info>, ProgramError> =
                     next_account_info(account_iter);
                 if next_account_info_or.is_ok() {
-                    let mint: MintAccountInfo<'a, 'info> =
-                        MintAccountInfo::new(next_account_info_or?)?;
+                    let next_account: &'a AccountInfo<'info> = next_account_info_or?;
+                    // Mints never sign and are not the memo program, so either
+                    // of those means there are no more global accounts.
+                    if next_account.is_signer || *next_account.key == SPL_MEMO_PROGRAM_ID {
+                        trailing_account_opt = Some(next_account);
+                        break;
+                    }
+                    let mint: MintAccountInfo<'a, 'info> = MintAccountInfo::new(next_account)?;
                     let (index, expected_market_vault_address) = if base_mint == *mint.info.key {
                         (0, &base_vault)
                     } else {
@@ -616,6 +1583,17 @@ impl<'a, 'info> BatchUpdateContext<'a, 'info> {
                     })
                 };
             }
+
+            // Cosigner and memo program can follow in either order. Ignore
+            // anything else to stay compatible with clients that pass extra
+            // accounts.
+            for next_account in trailing_account_opt.into_iter().chain(account_iter) {
+                if next_account.is_signer && cosigner_opt.is_none() {
+                    cosigner_opt = Some(Signer::new(next_account)?);
+                } else if *next_account.key == SPL_MEMO_PROGRAM_ID {
+                    fill_memo_program_opt = Some(Program::new(next_account, &SPL_MEMO_PROGRAM_ID)?);
+                }
+            }
         }
 
         Ok(Self {
@@ -623,6 +1601,8 @@ impl<'a, 'info> BatchUpdateContext<'a, 'info> {
             market,
             _system_program: system_program,
             global_trade_accounts_opts,
+            cosigner_opt,
+            fill_memo_program_opt,
         })
     }
 }
@@ -105,7 +105,12 @@ fn verify_uninitialized<T: Pod + ManifestAccount>(info: &AccountInfo) -> Program
 #[cfg(test)]
 mod test {
     use crate::state::{
-        GlobalFixed, MarketFixed, GLOBAL_FIXED_DISCRIMINANT, MARKET_FIXED_DISCRIMINANT,
+        GlobalFixed, Heartbeat, MarketFixed, MarketVolatility, PeggedOrders, Rfq, SeatArchive,
+        SignedOrderNonces, StopOrders, SwapCommitment, TradeBust, GLOBAL_FIXED_DISCRIMINANT,
+        HEARTBEAT_DISCRIMINANT, MARKET_FIXED_DISCRIMINANT, MARKET_VOLATILITY_DISCRIMINANT,
+        PEGGED_ORDERS_DISCRIMINANT, RFQ_DISCRIMINANT, SEAT_ARCHIVE_DISCRIMINANT,
+        SIGNED_ORDER_NONCES_DISCRIMINANT, STOP_ORDERS_DISCRIMINANT, SWAP_COMMITMENT_DISCRIMINANT,
+        TRADE_BUST_DISCRIMINANT,
     };
 
     #[test]
@@ -119,6 +124,60 @@ mod test {
         let discriminant: u64 = crate::utils::get_discriminant::<GlobalFixed>().unwrap();
         assert_eq!(discriminant, GLOBAL_FIXED_DISCRIMINANT);
     }
+
+    #[test]
+    fn test_seat_archive_discriminant() {
+        let discriminant: u64 = crate::utils::get_discriminant::<SeatArchive>().unwrap();
+        assert_eq!(discriminant, SEAT_ARCHIVE_DISCRIMINANT);
+    }
+
+    #[test]
+    fn test_swap_commitment_discriminant() {
+        let discriminant: u64 = crate::utils::get_discriminant::<SwapCommitment>().unwrap();
+        assert_eq!(discriminant, SWAP_COMMITMENT_DISCRIMINANT);
+    }
+
+    #[test]
+    fn test_stop_orders_discriminant() {
+        let discriminant: u64 = crate::utils::get_discriminant::<StopOrders>().unwrap();
+        assert_eq!(discriminant, STOP_ORDERS_DISCRIMINANT);
+    }
+
+    #[test]
+    fn test_heartbeat_discriminant() {
+        let discriminant: u64 = crate::utils::get_discriminant::<Heartbeat>().unwrap();
+        assert_eq!(discriminant, HEARTBEAT_DISCRIMINANT);
+    }
+
+    #[test]
+    fn test_market_volatility_discriminant() {
+        let discriminant: u64 = crate::utils::get_discriminant::<MarketVolatility>().unwrap();
+        assert_eq!(discriminant, MARKET_VOLATILITY_DISCRIMINANT);
+    }
+
+    #[test]
+    fn test_trade_bust_discriminant() {
+        let discriminant: u64 = crate::utils::get_discriminant::<TradeBust>().unwrap();
+        assert_eq!(discriminant, TRADE_BUST_DISCRIMINANT);
+    }
+
+    #[test]
+    fn test_signed_order_nonces_discriminant() {
+        let discriminant: u64 = crate::utils::get_discriminant::<SignedOrderNonces>().unwrap();
+        assert_eq!(discriminant, SIGNED_ORDER_NONCES_DISCRIMINANT);
+    }
+
+    #[test]
+    fn test_rfq_discriminant() {
+        let discriminant: u64 = crate::utils::get_discriminant::<Rfq>().unwrap();
+        assert_eq!(discriminant, RFQ_DISCRIMINANT);
+    }
+
+    #[test]
+    fn test_pegged_orders_discriminant() {
+        let discriminant: u64 = crate::utils::get_discriminant::<PeggedOrders>().unwrap();
+        assert_eq!(discriminant, PEGGED_ORDERS_DISCRIMINANT);
+    }
 }
 
 macro_rules! global_seeds {
@@ -137,3 +196,176 @@ macro_rules! global_seeds_with_bump {
 pub fn get_global_address(mint: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(global_seeds!(mint), &crate::ID)
 }
+
+macro_rules! seat_archive_seeds {
+    ( $market:expr ) => {
+        &[b"seat-archive", $market.as_ref()]
+    };
+}
+
+#[macro_export]
+macro_rules! seat_archive_seeds_with_bump {
+    ( $market:expr, $bump:expr ) => {
+        &[&[b"seat-archive", $market.as_ref(), &[$bump]]]
+    };
+}
+
+pub fn get_seat_archive_address(market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seat_archive_seeds!(market), &crate::ID)
+}
+
+macro_rules! swap_commitment_seeds {
+    ( $market:expr, $trader:expr ) => {
+        &[b"swap-commitment", $market.as_ref(), $trader.as_ref()]
+    };
+}
+
+#[macro_export]
+macro_rules! swap_commitment_seeds_with_bump {
+    ( $market:expr, $trader:expr, $bump:expr ) => {
+        &[&[
+            b"swap-commitment",
+            $market.as_ref(),
+            $trader.as_ref(),
+            &[$bump],
+        ]]
+    };
+}
+
+pub fn get_swap_commitment_address(market: &Pubkey, trader: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(swap_commitment_seeds!(market, trader), &crate::ID)
+}
+
+macro_rules! stop_orders_seeds {
+    ( $market:expr, $trader:expr ) => {
+        &[b"stop-orders", $market.as_ref(), $trader.as_ref()]
+    };
+}
+
+#[macro_export]
+macro_rules! stop_orders_seeds_with_bump {
+    ( $market:expr, $trader:expr, $bump:expr ) => {
+        &[&[b"stop-orders", $market.as_ref(), $trader.as_ref(), &[$bump]]]
+    };
+}
+
+pub fn get_stop_orders_address(market: &Pubkey, trader: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(stop_orders_seeds!(market, trader), &crate::ID)
+}
+
+macro_rules! heartbeat_seeds {
+    ( $market:expr, $trader:expr ) => {
+        &[b"heartbeat", $market.as_ref(), $trader.as_ref()]
+    };
+}
+
+#[macro_export]
+macro_rules! heartbeat_seeds_with_bump {
+    ( $market:expr, $trader:expr, $bump:expr ) => {
+        &[&[b"heartbeat", $market.as_ref(), $trader.as_ref(), &[$bump]]]
+    };
+}
+
+pub fn get_heartbeat_address(market: &Pubkey, trader: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(heartbeat_seeds!(market, trader), &crate::ID)
+}
+
+macro_rules! market_volatility_seeds {
+    ( $market:expr ) => {
+        &[b"volatility", $market.as_ref()]
+    };
+}
+
+#[macro_export]
+macro_rules! market_volatility_seeds_with_bump {
+    ( $market:expr, $bump:expr ) => {
+        &[&[b"volatility", $market.as_ref(), &[$bump]]]
+    };
+}
+
+pub fn get_market_volatility_address(market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(market_volatility_seeds!(market), &crate::ID)
+}
+
+macro_rules! trade_bust_seeds {
+    ( $market:expr, $maker_sequence_number:expr, $taker_sequence_number:expr ) => {
+        &[
+            b"trade-bust",
+            $market.as_ref(),
+            &$maker_sequence_number.to_le_bytes(),
+            &$taker_sequence_number.to_le_bytes(),
+        ]
+    };
+}
+
+pub fn get_trade_bust_address(
+    market: &Pubkey,
+    maker_sequence_number: u64,
+    taker_sequence_number: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        trade_bust_seeds!(market, maker_sequence_number, taker_sequence_number),
+        &crate::ID,
+    )
+}
+
+macro_rules! signed_order_nonces_seeds {
+    ( $market:expr, $trader:expr ) => {
+        &[b"signed-order-nonces", $market.as_ref(), $trader.as_ref()]
+    };
+}
+
+#[macro_export]
+macro_rules! signed_order_nonces_seeds_with_bump {
+    ( $market:expr, $trader:expr, $bump:expr ) => {
+        &[&[
+            b"signed-order-nonces",
+            $market.as_ref(),
+            $trader.as_ref(),
+            &[$bump],
+        ]]
+    };
+}
+
+pub fn get_signed_order_nonces_address(market: &Pubkey, trader: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(signed_order_nonces_seeds!(market, trader), &crate::ID)
+}
+
+macro_rules! rfq_seeds {
+    ( $market:expr, $taker:expr ) => {
+        &[b"rfq", $market.as_ref(), $taker.as_ref()]
+    };
+}
+
+#[macro_export]
+macro_rules! rfq_seeds_with_bump {
+    ( $market:expr, $taker:expr, $bump:expr ) => {
+        &[&[b"rfq", $market.as_ref(), $taker.as_ref(), &[$bump]]]
+    };
+}
+
+pub fn get_rfq_address(market: &Pubkey, taker: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(rfq_seeds!(market, taker), &crate::ID)
+}
+
+macro_rules! pegged_orders_seeds {
+    ( $market:expr, $trader:expr ) => {
+        &[b"pegged-orders", $market.as_ref(), $trader.as_ref()]
+    };
+}
+
+#[macro_export]
+macro_rules! pegged_orders_seeds_with_bump {
+    ( $market:expr, $trader:expr, $bump:expr ) => {
+        &[&[
+            b"pegged-orders",
+            $market.as_ref(),
+            $trader.as_ref(),
+            &[$bump],
+        ]]
+    };
+}
+
+pub fn get_pegged_orders_address(market: &Pubkey, trader: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(pegged_orders_seeds!(market, trader), &crate::ID)
+}
@@ -1,9 +1,18 @@
 use crate::require;
 use solana_program::{
-    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, system_program,
+    account_info::AccountInfo, program_error::ProgramError, pubkey, pubkey::Pubkey, system_program,
 };
 use std::ops::Deref;
 
+/// SPL memo program v2. Optional trailing account on instructions that match
+/// orders, see FillLog.
+pub const SPL_MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// SPL associated token account program. Optional account on swaps, see
+/// SwapContext.
+pub const SPL_ASSOCIATED_TOKEN_ACCOUNT_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
 #[derive(Clone)]
 pub struct Program<'a, 'info> {
     pub info: &'a AccountInfo<'info>,
@@ -1,7 +1,10 @@
 use crate::require;
+use solana_program::program_pack::Pack;
 use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
 use spl_token_2022::{
-    check_spl_token_program_account, extension::StateWithExtensions, state::Mint,
+    check_spl_token_program_account,
+    extension::{AccountType, StateWithExtensions},
+    state::{Account, Mint},
 };
 use std::ops::Deref;
 
@@ -21,6 +24,16 @@ impl<'a, 'info> MintAccountInfo<'a, 'info> {
     }
 }
 
+/// Tells a mint from a token account of either token program without
+/// unpacking it. Token22 mints with extensions are padded to the size of a
+/// token account and then marked with their account type.
+pub fn is_mint_account(info: &AccountInfo) -> bool {
+    info.try_borrow_data().is_ok_and(|data| {
+        data.len() == Mint::LEN
+            || (data.len() > Account::LEN && data[Account::LEN] == AccountType::Mint as u8)
+    })
+}
+
 impl<'a, 'info> AsRef<AccountInfo<'info>> for MintAccountInfo<'a, 'info> {
     fn as_ref(&self) -> &AccountInfo<'info> {
         self.info
@@ -147,3 +160,38 @@ pub fn get_vault_address(market: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
 pub fn get_global_vault_address(mint: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(global_vault_seeds!(mint), &crate::ID)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn is_mint_data(data: &mut [u8]) -> bool {
+        let key: Pubkey = Pubkey::new_unique();
+        let mut lamports: u64 = 0;
+        let info: AccountInfo = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            data,
+            &spl_token_2022::ID,
+            false,
+            0,
+        );
+        is_mint_account(&info)
+    }
+
+    #[test]
+    fn test_is_mint_account() {
+        assert!(is_mint_data(&mut [0; Mint::LEN]));
+        assert!(!is_mint_data(&mut [0; Account::LEN]));
+
+        let mut mint_with_extensions: [u8; Account::LEN + 5] = [0; Account::LEN + 5];
+        mint_with_extensions[Account::LEN] = AccountType::Mint as u8;
+        assert!(is_mint_data(&mut mint_with_extensions));
+
+        let mut account_with_extensions: [u8; Account::LEN + 5] = [0; Account::LEN + 5];
+        account_with_extensions[Account::LEN] = AccountType::Account as u8;
+        assert!(!is_mint_data(&mut account_with_extensions));
+    }
+}
@@ -1,11 +1,32 @@
+use std::rc::Rc;
+
 use hypertree::DataIndex;
 use manifest::{
-    program::batch_update::{CancelOrderParams, PlaceOrderParams},
-    state::{OrderType, MARKET_BLOCK_SIZE, NO_EXPIRATION_LAST_VALID_SLOT},
+    program::{
+        batch_update::{
+            AmendOrderParams, CancelOrderParams, PlaceOrderParams, ReplaceOrderParams,
+            BATCH_UPDATE_RELATIVE_EXPIRY,
+        },
+        batch_update_cancel_all_instruction, batch_update_reserve_blocks_instruction,
+        batch_update_with_amends_instruction, batch_update_with_display_sizes_instruction,
+        batch_update_with_flags_instruction, batch_update_with_ioc_min_fills_instruction,
+        batch_update_with_last_valid_unix_timestamps_instruction,
+        batch_update_with_min_taker_instruction, batch_update_with_one_cancels_other_instruction,
+        batch_update_with_replaces_instruction,
+        batch_update_with_self_trade_prevention_instruction, batch_update_with_tags_instruction,
+    },
+    quantities::{BaseAtoms, WrapperU64},
+    state::{
+        OrderType, RestingOrder, SelfTradePrevention, MARKET_BLOCK_SIZE,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    },
 };
+use solana_clock::Clock;
+use solana_keypair::Keypair;
 use solana_program_test::tokio;
+use solana_signer::Signer;
 
-use crate::{TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+use crate::{send_tx_with_retry, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
 
 #[tokio::test]
 async fn batch_update_test() -> anyhow::Result<()> {
@@ -198,3 +219,1280 @@ async fn batch_update_partial_fill_then_cancel_test() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn batch_update_reserve_blocks_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 10 * SOL_UNIT_SIZE).await?;
+
+    let orders: Vec<PlaceOrderParams> = (1..=5)
+        .map(|price_mantissa| {
+            PlaceOrderParams::new(
+                1 * SOL_UNIT_SIZE,
+                price_mantissa,
+                0,
+                false,
+                OrderType::Limit,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )
+        })
+        .collect();
+    let payer = test_fixture.payer();
+    let payer_keypair = test_fixture.payer_keypair();
+    let batch_update_ix = batch_update_reserve_blocks_instruction(
+        &test_fixture.market_fixture.key,
+        &payer,
+        None,
+        vec![],
+        orders,
+        None,
+        None,
+        None,
+        None,
+    );
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[batch_update_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    // All five rest and the spare block is still there.
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        5
+    );
+    assert!(
+        test_fixture
+            .market_fixture
+            .market
+            .get_free_list_stats()
+            .num_free
+            >= 1
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_relative_expiry_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+
+    // Far enough along that 100 as an absolute slot is already expired.
+    test_fixture.advance_time_seconds(2_000).await;
+    let now_slot: u32 = test_fixture
+        .context
+        .borrow_mut()
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await?
+        .slot as u32;
+
+    let payer = test_fixture.payer();
+    let payer_keypair = test_fixture.payer_keypair();
+    let batch_update_ix = batch_update_with_flags_instruction(
+        &test_fixture.market_fixture.key,
+        &payer,
+        None,
+        vec![],
+        vec![
+            PlaceOrderParams::new(1 * SOL_UNIT_SIZE, 1, 0, false, OrderType::Limit, 100),
+            PlaceOrderParams::new(
+                1 * SOL_UNIT_SIZE,
+                2,
+                0,
+                false,
+                OrderType::Limit,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            ),
+        ],
+        None,
+        None,
+        None,
+        None,
+        BATCH_UPDATE_RELATIVE_EXPIRY,
+    );
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[batch_update_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 2);
+    // Zero is still no expiration, so only one order ever expires.
+    let expiring: Vec<&RestingOrder> = orders
+        .iter()
+        .filter(|order| order.is_expired(u32::MAX))
+        .collect();
+    assert_eq!(expiring.len(), 1);
+    // Valid for 100 slots from landing. Leaves some room for slots passing
+    // while the transaction is processed.
+    assert!(!expiring[0].is_expired(now_slot + 100));
+    assert!(expiring[0].is_expired(now_slot + 200));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_min_taker_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1 * SOL_UNIT_SIZE).await?;
+
+    let payer = test_fixture.payer();
+    let payer_keypair = test_fixture.payer_keypair();
+    let batch_update_ix = batch_update_with_min_taker_instruction(
+        &test_fixture.market_fixture.key,
+        &payer,
+        None,
+        vec![],
+        vec![PlaceOrderParams::new(
+            1 * SOL_UNIT_SIZE,
+            1,
+            0,
+            false,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+        )],
+        None,
+        None,
+        None,
+        None,
+        0,
+        0,
+        SOL_UNIT_SIZE / 2,
+    );
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[batch_update_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(
+        orders[0].get_min_taker_atoms(),
+        BaseAtoms::new(SOL_UNIT_SIZE / 2)
+    );
+
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 1_000 * USDC_UNIT_SIZE)
+        .await;
+
+    // Too small for the order, so nothing fills and the minimum out fails.
+    assert!(test_fixture
+        .swap(100 * USDC_UNIT_SIZE, SOL_UNIT_SIZE / 10, false, true)
+        .await
+        .is_err());
+
+    // Taking all of it is always allowed.
+    test_fixture
+        .swap(1_000 * USDC_UNIT_SIZE, 1 * SOL_UNIT_SIZE, false, false)
+        .await?;
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        1 * SOL_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_tags_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+
+    let payer = test_fixture.payer();
+    let payer_keypair = test_fixture.payer_keypair();
+    let place = |price_mantissa: u32| {
+        PlaceOrderParams::new(
+            1 * SOL_UNIT_SIZE,
+            price_mantissa,
+            0,
+            false,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+        )
+    };
+
+    // Trailing data without a flag bit for it is rejected.
+    let mut batch_update_ix = batch_update_with_flags_instruction(
+        &test_fixture.market_fixture.key,
+        &payer,
+        None,
+        vec![],
+        vec![place(1), place(2)],
+        None,
+        None,
+        None,
+        None,
+        0,
+    );
+    batch_update_ix.data.extend_from_slice(&7u32.to_le_bytes());
+    batch_update_ix.data.extend_from_slice(&8u32.to_le_bytes());
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[batch_update_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    // A tag for only one of the two places is rejected.
+    let batch_update_ix = batch_update_with_tags_instruction(
+        &test_fixture.market_fixture.key,
+        &payer,
+        None,
+        vec![],
+        vec![place(1), place(2)],
+        None,
+        None,
+        None,
+        None,
+        0,
+        0,
+        0,
+        &[7],
+    );
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[batch_update_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    let batch_update_ix = batch_update_with_tags_instruction(
+        &test_fixture.market_fixture.key,
+        &payer,
+        None,
+        vec![],
+        vec![place(1), place(2)],
+        None,
+        None,
+        None,
+        None,
+        0,
+        0,
+        0,
+        &[7, 8],
+    );
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[batch_update_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    let mut tags: Vec<u32> = test_fixture
+        .market_fixture
+        .get_resting_orders()
+        .await
+        .iter()
+        .map(|order| order.get_tag())
+        .collect();
+    tags.sort();
+    assert_eq!(tags, vec![7, 8]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_cancel_all_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 3 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .deposit(Token::USDC, 10 * USDC_UNIT_SIZE)
+        .await?;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 1 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            1 * SOL_UNIT_SIZE,
+            10,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    2,
+                    0,
+                    false,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    3,
+                    0,
+                    false,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    1,
+                    -3,
+                    true,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+            ],
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        4
+    );
+
+    // Cancels all three orders of the payer without listing them and places
+    // a new one in the same instruction.
+    let payer = test_fixture.payer();
+    let payer_keypair = test_fixture.payer_keypair();
+    let batch_update_ix = batch_update_cancel_all_instruction(
+        &test_fixture.market_fixture.key,
+        &payer,
+        None,
+        vec![PlaceOrderParams::new(
+            1 * SOL_UNIT_SIZE,
+            4,
+            0,
+            false,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+        )],
+        None,
+        None,
+        None,
+        None,
+    );
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[batch_update_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    // The other trader's order stays, next to the new one.
+    let mut sequence_numbers: Vec<u64> = test_fixture
+        .market_fixture
+        .get_resting_orders()
+        .await
+        .iter()
+        .map(|order| order.get_sequence_number())
+        .collect();
+    sequence_numbers.sort();
+    assert_eq!(sequence_numbers, vec![0, 4]);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        2 * SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer)
+            .await,
+        10 * USDC_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&second_keypair.pubkey())
+            .await,
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_replace_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 3 * SOL_UNIT_SIZE).await?;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 1 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            1 * SOL_UNIT_SIZE,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    10,
+                    0,
+                    false,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    10,
+                    0,
+                    false,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    11,
+                    0,
+                    false,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+            ],
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+
+    let payer = test_fixture.payer();
+    let payer_keypair = test_fixture.payer_keypair();
+
+    // Moving an ask onto the bid would cross.
+    let crossing_ix = batch_update_with_replaces_instruction(
+        &test_fixture.market_fixture.key,
+        &payer,
+        None,
+        vec![],
+        vec![ReplaceOrderParams::new(
+            3,
+            None,
+            1 * SOL_UNIT_SIZE,
+            Some((1, -3)),
+        )],
+        vec![],
+        None,
+        None,
+        None,
+        None,
+        0,
+    );
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[crossing_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    // Halves the first ask in place, moves the last one up a tick and cancels
+    // the middle one.
+    let replace_ix = batch_update_with_replaces_instruction(
+        &test_fixture.market_fixture.key,
+        &payer,
+        None,
+        vec![],
+        vec![
+            ReplaceOrderParams::new(1, None, SOL_UNIT_SIZE / 2, None),
+            ReplaceOrderParams::new(3, None, 1 * SOL_UNIT_SIZE, Some((12, 0))),
+            ReplaceOrderParams::new(2, None, 0, None),
+        ],
+        vec![],
+        None,
+        None,
+        None,
+        None,
+        0,
+    );
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[replace_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    let mut asks: Vec<(u64, u64, u32)> = test_fixture
+        .market_fixture
+        .get_resting_orders()
+        .await
+        .iter()
+        .filter(|order| !order.get_is_bid())
+        .map(|order| {
+            (
+                order.get_sequence_number(),
+                order.get_num_base_atoms().as_u64(),
+                order
+                    .get_price()
+                    .checked_quote_for_base(BaseAtoms::new(1), false)
+                    .unwrap()
+                    .as_u64() as u32,
+            )
+        })
+        .collect();
+    asks.sort();
+    // The reduced ask kept its sequence number, the repriced one got a new one.
+    assert_eq!(
+        asks,
+        vec![(1, SOL_UNIT_SIZE / 2, 10), (4, 1 * SOL_UNIT_SIZE, 12)]
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        3 * SOL_UNIT_SIZE / 2
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_ioc_min_fill_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            1 * SOL_UNIT_SIZE,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 2 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    let ioc_min_fill_ix = |order_type: OrderType, min_fill_base_atoms: u64| {
+        batch_update_with_ioc_min_fills_instruction(
+            &test_fixture.market_fixture.key,
+            &second_keypair.pubkey(),
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                2 * SOL_UNIT_SIZE,
+                1,
+                -3,
+                true,
+                order_type,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            None,
+            None,
+            None,
+            None,
+            0,
+            vec![min_fill_base_atoms],
+        )
+    };
+
+    // Only 1 SOL is on the book.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[ioc_min_fill_ix(
+            OrderType::ImmediateOrCancel,
+            3 * SOL_UNIT_SIZE / 2
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+    // Orders that can rest cannot have a minimum.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[ioc_min_fill_ix(OrderType::Limit, 1)],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[ioc_min_fill_ix(
+            OrderType::ImmediateOrCancel,
+            1 * SOL_UNIT_SIZE,
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&second_keypair.pubkey())
+            .await,
+        1 * SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_self_trade_prevention_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .deposit(Token::USDC, 1 * USDC_UNIT_SIZE)
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            1 * SOL_UNIT_SIZE,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let self_trade_prevention_ix = |self_trade_prevention: SelfTradePrevention| {
+        batch_update_with_self_trade_prevention_instruction(
+            &test_fixture.market_fixture.key,
+            &payer_keypair.pubkey(),
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                1 * SOL_UNIT_SIZE,
+                1,
+                -3,
+                true,
+                OrderType::Limit,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            None,
+            None,
+            None,
+            None,
+            0,
+            vec![self_trade_prevention],
+        )
+    };
+
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[self_trade_prevention_ix(SelfTradePrevention::Reject)],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    // The own ask is cancelled and the bid rests in its place.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[self_trade_prevention_ix(SelfTradePrevention::CancelProvide)],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+    let resting_orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(resting_orders.len(), 1);
+    assert!(resting_orders[0].get_is_bid());
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer_keypair.pubkey())
+            .await,
+        1 * SOL_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_one_cancels_other_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .deposit(Token::USDC, 1 * USDC_UNIT_SIZE)
+        .await?;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 1 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let one_cancels_other_ix = |one_cancels_others: Vec<(u8, u8)>| {
+        batch_update_with_one_cancels_other_instruction(
+            &test_fixture.market_fixture.key,
+            &payer_keypair.pubkey(),
+            None,
+            vec![],
+            vec![
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    1,
+                    -3,
+                    true,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    2,
+                    -3,
+                    false,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+            ],
+            None,
+            None,
+            None,
+            None,
+            0,
+            one_cancels_others,
+        )
+    };
+
+    // An order cannot be linked to itself.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[one_cancels_other_ix(vec![(0, 0)])],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[one_cancels_other_ix(vec![(0, 1)])],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        2
+    );
+
+    // A partial fill on the bid cancels the ask.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            SOL_UNIT_SIZE / 2,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+    let resting_orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(resting_orders.len(), 1);
+    assert!(resting_orders[0].get_is_bid());
+    assert_eq!(
+        resting_orders[0].get_num_base_atoms(),
+        BaseAtoms::new(SOL_UNIT_SIZE / 2)
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer_keypair.pubkey())
+            .await,
+        SOL_UNIT_SIZE + SOL_UNIT_SIZE / 2
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_iceberg_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 3 * SOL_UNIT_SIZE).await?;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 1 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 10 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let iceberg_ix = |order_type: OrderType| {
+        batch_update_with_display_sizes_instruction(
+            &test_fixture.market_fixture.key,
+            &payer_keypair.pubkey(),
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                3 * SOL_UNIT_SIZE,
+                1,
+                -3,
+                false,
+                order_type,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            None,
+            None,
+            None,
+            None,
+            0,
+            vec![1 * SOL_UNIT_SIZE],
+        )
+    };
+
+    // Only icebergs take a display size.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[iceberg_ix(OrderType::Limit)],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[iceberg_ix(OrderType::Iceberg)],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            1 * SOL_UNIT_SIZE,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    // Filling the shown tranche puts the rest behind the later ask.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            1 * SOL_UNIT_SIZE,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+    let resting_orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(resting_orders.len(), 2);
+    let iceberg: &RestingOrder = resting_orders
+        .iter()
+        .find(|order| order.get_order_type() == OrderType::Iceberg)
+        .unwrap();
+    let limit: &RestingOrder = resting_orders
+        .iter()
+        .find(|order| order.get_order_type() == OrderType::Limit)
+        .unwrap();
+    assert_eq!(
+        iceberg.get_num_base_atoms(),
+        BaseAtoms::new(2 * SOL_UNIT_SIZE)
+    );
+    assert_eq!(
+        iceberg.get_visible_base_atoms(),
+        BaseAtoms::new(1 * SOL_UNIT_SIZE)
+    );
+    assert!(iceberg.get_sequence_number() > limit.get_sequence_number());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_amend_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1 * SOL_UNIT_SIZE).await?;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 1 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 10 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    test_fixture
+        .place_order(
+            Side::Ask,
+            1 * SOL_UNIT_SIZE,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            1 * SOL_UNIT_SIZE,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    let order_sequence_number: u64 = test_fixture
+        .market_fixture
+        .get_resting_orders()
+        .await
+        .iter()
+        .map(|order| order.get_sequence_number())
+        .min()
+        .unwrap();
+
+    // A partial fill before the amend lands is not added back.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE / 2,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let amend_ix = |reduce_base_atoms: u64| {
+        batch_update_with_amends_instruction(
+            &test_fixture.market_fixture.key,
+            &payer_keypair.pubkey(),
+            None,
+            vec![],
+            vec![AmendOrderParams::new(
+                order_sequence_number,
+                None,
+                reduce_base_atoms,
+            )],
+            vec![],
+            None,
+            None,
+            None,
+            None,
+            0,
+        )
+    };
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[amend_ix(SOL_UNIT_SIZE / 5)],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+    let resting_orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    let amended: &RestingOrder = resting_orders
+        .iter()
+        .find(|order| order.get_sequence_number() == order_sequence_number)
+        .unwrap();
+    assert_eq!(
+        amended.get_num_base_atoms(),
+        BaseAtoms::new(SOL_UNIT_SIZE / 2 - SOL_UNIT_SIZE / 5)
+    );
+
+    // Still ahead of the other ask.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE / 10,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_resting_orders()
+            .await
+            .iter()
+            .find(|order| order.get_sequence_number() == order_sequence_number)
+            .unwrap()
+            .get_num_base_atoms(),
+        BaseAtoms::new(SOL_UNIT_SIZE / 5)
+    );
+
+    // Reducing by more than is left cancels it.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[amend_ix(1 * SOL_UNIT_SIZE)],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+    let resting_orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(resting_orders.len(), 1);
+    assert!(resting_orders[0].get_sequence_number() != order_sequence_number);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer_keypair.pubkey())
+            .await,
+        SOL_UNIT_SIZE - SOL_UNIT_SIZE / 2 - SOL_UNIT_SIZE / 10
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_quote_sizes_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 10 * USDC_UNIT_SIZE)
+        .await?;
+
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let quote_sizes_ix = |base_atoms: u64, quote_atoms: u64| {
+        batch_update_with_quote_sizes_instruction(
+            &test_fixture.market_fixture.key,
+            &payer_keypair.pubkey(),
+            None,
+            vec![],
+            vec![
+                PlaceOrderParams::new(
+                    base_atoms,
+                    3,
+                    -3,
+                    true,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+                PlaceOrderParams::new(
+                    SOL_UNIT_SIZE,
+                    1,
+                    -3,
+                    true,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+            ],
+            None,
+            None,
+            None,
+            None,
+            0,
+            vec![quote_atoms, 0],
+        )
+    };
+
+    // Places with a quote size cannot also have base atoms.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[quote_sizes_ix(SOL_UNIT_SIZE, USDC_UNIT_SIZE)],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    // 1 USDC and an atom at 3 USDC per SOL, rounded down.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[quote_sizes_ix(0, USDC_UNIT_SIZE + 1)],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+    let mut resting_base_atoms: Vec<u64> = test_fixture
+        .market_fixture
+        .get_resting_orders()
+        .await
+        .iter()
+        .map(|order| order.get_num_base_atoms().as_u64())
+        .collect();
+    resting_base_atoms.sort();
+    assert_eq!(resting_base_atoms, vec![333_333_666, SOL_UNIT_SIZE]);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer_keypair.pubkey())
+            .await,
+        10 * USDC_UNIT_SIZE - (USDC_UNIT_SIZE + 1) - USDC_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_last_valid_unix_timestamps_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1 * SOL_UNIT_SIZE).await?;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 10 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture.set_unix_timestamp(1_000_000).await;
+
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let ask_ix = |last_valid_slot: u32, last_valid_unix_timestamp: u32| {
+        batch_update_with_last_valid_unix_timestamps_instruction(
+            &test_fixture.market_fixture.key,
+            &payer_keypair.pubkey(),
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                1 * SOL_UNIT_SIZE,
+                1,
+                -3,
+                false,
+                OrderType::Limit,
+                last_valid_slot,
+            )],
+            None,
+            None,
+            None,
+            None,
+            0,
+            vec![last_valid_unix_timestamp],
+        )
+    };
+
+    // Already expired, or with a slot expiry as well.
+    for ix in [
+        ask_ix(NO_EXPIRATION_LAST_VALID_SLOT, 1_000_000),
+        ask_ix(u32::MAX, 1_000_100),
+    ] {
+        assert!(send_tx_with_retry(
+            Rc::clone(&test_fixture.context),
+            &[ix],
+            Some(&payer_keypair.pubkey()),
+            &[&payer_keypair],
+        )
+        .await
+        .is_err());
+    }
+
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[ask_ix(NO_EXPIRATION_LAST_VALID_SLOT, 1_000_100)],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+    let resting_orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(resting_orders.len(), 1);
+    assert_eq!(
+        resting_orders[0].get_last_valid_unix_timestamp(),
+        Some(1_000_100)
+    );
+
+    // Matches until the timestamp.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE / 10,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&second_keypair.pubkey())
+            .await,
+        SOL_UNIT_SIZE / 10
+    );
+
+    // Then takers remove it instead.
+    test_fixture.set_unix_timestamp(1_000_101).await;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE / 10,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&second_keypair.pubkey())
+            .await,
+        SOL_UNIT_SIZE / 10
+    );
+    assert!(test_fixture
+        .market_fixture
+        .get_resting_orders()
+        .await
+        .is_empty());
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer_keypair.pubkey())
+            .await,
+        SOL_UNIT_SIZE - SOL_UNIT_SIZE / 10
+    );
+
+    Ok(())
+}
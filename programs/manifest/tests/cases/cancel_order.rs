@@ -3,7 +3,7 @@ use std::rc::Rc;
 use hypertree::DataIndex;
 use manifest::{
     program::{batch_update::CancelOrderParams, batch_update_instruction},
-    state::{OrderType, MARKET_BLOCK_SIZE},
+    state::{OrderType, TradingHours, MARKET_BLOCK_SIZE},
 };
 use solana_instruction::Instruction;
 use solana_keypair::Keypair;
@@ -11,7 +11,9 @@ use solana_program::pubkey::Pubkey;
 use solana_program_test::tokio;
 use solana_signer::Signer;
 
-use crate::{send_tx_with_retry, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+use crate::{
+    send_tx_with_retry, MarketFixture, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE,
+};
 
 #[tokio::test]
 async fn cancel_order_test() -> anyhow::Result<()> {
@@ -183,3 +185,44 @@ async fn cancel_order_with_hint_test() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn cancel_order_min_resting_slots_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.market_fixture = MarketFixture::new_with_min_resting_slots(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        10,
+    )
+    .await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+
+    test_fixture
+        .place_order(Side::Ask, 1, 1, 0, u32::MAX, OrderType::Limit)
+        .await?;
+
+    // Too soon, with or without the hint.
+    assert!(test_fixture.cancel_order(0).await.is_err());
+    assert!(test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![CancelOrderParams::new_with_hint(
+                0,
+                Some(MARKET_BLOCK_SIZE as DataIndex)
+            )],
+            vec![],
+            &test_fixture.payer_keypair()
+        )
+        .await
+        .is_err());
+
+    // 20 seconds is 10 slots.
+    test_fixture.advance_time_seconds(20).await;
+    test_fixture.cancel_order(0).await?;
+
+    Ok(())
+}
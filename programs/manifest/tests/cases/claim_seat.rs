@@ -1,6 +1,21 @@
+use std::rc::Rc;
+
+use hypertree::NIL;
+use manifest::{
+    program::{archive_seat_instruction, restore_seat_instruction, set_mmp_instruction},
+    quantities::{BaseAtoms, QuoteAtoms},
+    state::{
+        seat_archive_leaf, seat_archive_proof, OrderType, SeatArchiveNode,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    },
+};
+use solana_keypair::Keypair;
 use solana_program_test::tokio;
 
-use crate::TestFixture;
+use solana_program::pubkey::Pubkey;
+use solana_signer::Signer;
+
+use crate::{send_tx_with_retry, Side, TestFixture, Token, SOL_UNIT_SIZE};
 
 #[tokio::test]
 async fn claim_seat() -> anyhow::Result<()> {
@@ -12,12 +27,21 @@ async fn claim_seat() -> anyhow::Result<()> {
 }
 
 #[tokio::test]
-async fn claim_seat_again_fail() -> anyhow::Result<()> {
-    let test_fixture: TestFixture = TestFixture::new().await;
+async fn claim_seat_again_noop() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
     test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
 
-    // Should not be able to claim a second seat
-    assert!(test_fixture.claim_seat().await.is_err());
+    // Claiming again succeeds and leaves the existing seat untouched.
+    test_fixture.claim_seat().await?;
+    let payer: Pubkey = test_fixture.payer();
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        SOL_UNIT_SIZE
+    );
 
     Ok(())
 }
@@ -34,3 +58,136 @@ async fn claim_seat_different_user() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn archive_and_restore_seat() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let payer: Pubkey = payer_keypair.pubkey();
+    let other_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let other: Pubkey = other_keypair.pubkey();
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+
+    // Seats with resting orders cannot be archived.
+    test_fixture
+        .place_order(
+            Side::Ask,
+            1,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[archive_seat_instruction(&market, &payer, &payer)],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+    test_fixture.cancel_order(0).await?;
+
+    // Only the trader can archive a seat holding more than dust.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[archive_seat_instruction(&market, &other, &payer)],
+        Some(&other),
+        &[&other_keypair],
+    )
+    .await
+    .is_err());
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[archive_seat_instruction(&market, &payer, &payer)],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture.market_fixture.market.get_trader_index(&payer),
+        NIL
+    );
+
+    // Anyone can restore, the balance goes back to the trader.
+    let leaves: Vec<SeatArchiveNode> = vec![seat_archive_leaf(
+        &payer,
+        BaseAtoms::new(SOL_UNIT_SIZE),
+        QuoteAtoms::new(0),
+        QuoteAtoms::new(0),
+    )];
+    let restore_ix = || {
+        restore_seat_instruction(
+            &market,
+            &other,
+            &payer,
+            SOL_UNIT_SIZE,
+            0,
+            0,
+            0,
+            seat_archive_proof(&leaves, 0),
+        )
+    };
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[restore_ix()],
+        Some(&other),
+        &[&other_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        SOL_UNIT_SIZE
+    );
+
+    // The leaf is cleared, so the seat cannot be restored twice.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[restore_ix()],
+        Some(&other),
+        &[&other_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn archive_seat_with_volume() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let other_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let other: Pubkey = other_keypair.pubkey();
+    test_fixture.claim_seat_for_keypair(&other_keypair).await?;
+
+    // Setting and clearing market maker protection leaves a SeatVolume on
+    // the seat, and its rolling volume would be lost with the seat.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[
+            set_mmp_instruction(&market, &other, 100, 2, 0, None),
+            set_mmp_instruction(&market, &other, 0, 0, 0, None),
+        ],
+        Some(&other),
+        &[&other_keypair],
+    )
+    .await?;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[archive_seat_instruction(&market, &other, &other)],
+        Some(&other),
+        &[&other_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use std::rc::Rc;
+
+use manifest::{
+    program::{
+        batch_update::PlaceOrderParams, batch_update_instruction, create_market_instructions,
+        create_market_with_flags_instruction,
+    },
+    state::{OrderType, TradingHours, MARKET_FLAG_CPI_ALLOWLIST, NO_EXPIRATION_LAST_VALID_SLOT},
+};
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey, sysvar};
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{
+    send_tx_with_retry, MarketFixture, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE,
+};
+
+#[tokio::test]
+async fn cpi_allowlist_rejects_direct_calls_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.market_fixture = MarketFixture::new_with_cpi_allowlist(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        0,
+        0,
+        0,
+        0,
+        &[Pubkey::new_unique()],
+    )
+    .await;
+
+    // Deposits do not need the allowed program.
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+    test_fixture.deposit(Token::USDC, USDC_UNIT_SIZE).await?;
+
+    assert!(test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await
+        .is_err());
+    assert!(test_fixture
+        .swap(USDC_UNIT_SIZE, 0, false, true)
+        .await
+        .is_err());
+
+    // Passing the instructions sysvar from the top level is not a CPI.
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let mut place_order_ix: Instruction = batch_update_instruction(
+        &test_fixture.market_fixture.key,
+        &payer_keypair.pubkey(),
+        None,
+        vec![],
+        vec![PlaceOrderParams::new(
+            SOL_UNIT_SIZE,
+            1,
+            -3,
+            false,
+            OrderType::Limit,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+        )],
+        None,
+        None,
+        None,
+        None,
+    );
+    place_order_ix
+        .accounts
+        .push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[place_order_ix],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    // Withdraws are never blocked.
+    test_fixture.withdraw(Token::SOL, SOL_UNIT_SIZE).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cpi_allowlist_flag_without_programs_fails_test() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let market_keypair: Keypair = Keypair::new();
+
+    let mut create_market_ixs: Vec<Instruction> = create_market_instructions(
+        &market_keypair.pubkey(),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        &payer_keypair.pubkey(),
+    )
+    .unwrap();
+    create_market_ixs[1] = create_market_with_flags_instruction(
+        &market_keypair.pubkey(),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        &payer_keypair.pubkey(),
+        MARKET_FLAG_CPI_ALLOWLIST,
+    );
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &create_market_ixs[..],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair, &market_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
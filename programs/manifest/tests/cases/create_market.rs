@@ -1,6 +1,9 @@
-use std::cell::RefMut;
+use std::{cell::RefMut, rc::Rc};
 
-use manifest::program::create_market_instructions;
+use manifest::{
+    program::create_market_instructions,
+    state::{MARKET_EXTENSION_VERSION, MARKET_FLAG_EXTENSION, MARKET_FLAG_VOLUME_STATS},
+};
 use solana_instruction::Instruction;
 use solana_keypair::Keypair;
 use solana_program::pubkey::Pubkey;
@@ -8,7 +11,7 @@ use solana_program_test::{tokio, ProgramTestContext};
 use solana_signer::Signer;
 use solana_transaction::Transaction;
 
-use crate::TestFixture;
+use crate::{MarketFixture, TestFixture};
 
 #[tokio::test]
 async fn create_market() -> anyhow::Result<()> {
@@ -17,6 +20,47 @@ async fn create_market() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn create_market_with_extension() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.market_fixture.reload().await;
+    assert!(test_fixture
+        .market_fixture
+        .market
+        .get_market_extension()
+        .is_none());
+
+    // The extension goes after every other block, here the stats.
+    test_fixture.market_fixture = MarketFixture::new_with_flags(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        MARKET_FLAG_VOLUME_STATS | MARKET_FLAG_EXTENSION,
+    )
+    .await;
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .get_market_extension()
+            .unwrap()
+            .get_version(),
+        MARKET_EXTENSION_VERSION
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .get_market_stats()
+            .unwrap()
+            .get_num_fills(),
+        0
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn create_market_fail_same_base_and_quote() -> anyhow::Result<()> {
     let test_fixture: TestFixture = TestFixture::new().await;
@@ -1,7 +1,10 @@
 use std::rc::Rc;
 
 use borsh::ser::BorshSerialize;
-use manifest::program::{deposit::DepositParams, deposit_instruction, ManifestInstruction};
+use manifest::program::{
+    deposit::DepositParams, deposit_idempotent_instruction, deposit_instruction,
+    ManifestInstruction,
+};
 use solana_instruction::Instruction;
 use solana_keypair::Keypair;
 use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
@@ -35,6 +38,48 @@ async fn deposit_fail_no_seat_yet_test() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn deposit_idempotent_claims_seat_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+
+    let payer: &Pubkey = &test_fixture.payer().clone();
+    let payer_keypair: &Keypair = &test_fixture.payer_keypair().insecure_clone();
+    let user_token_account: Pubkey = test_fixture.payer_sol_fixture.key;
+    test_fixture
+        .sol_mint_fixture
+        .mint_to(&user_token_account, 2 * SOL_UNIT_SIZE)
+        .await;
+
+    // First one claims the seat, second one finds it already there.
+    for _ in 0..2 {
+        send_tx_with_retry(
+            Rc::clone(&test_fixture.context),
+            &[deposit_idempotent_instruction(
+                &test_fixture.market_fixture.key,
+                payer,
+                &test_fixture.sol_mint_fixture.key,
+                SOL_UNIT_SIZE,
+                &user_token_account,
+                spl_token::id(),
+                None,
+            )],
+            Some(payer),
+            &[payer_keypair],
+        )
+        .await?;
+    }
+
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(payer)
+            .await,
+        2 * SOL_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn deposit_fail_insufficient_funds_test() -> anyhow::Result<()> {
     let test_fixture: TestFixture = TestFixture::new().await;
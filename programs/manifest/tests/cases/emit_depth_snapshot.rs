@@ -0,0 +1,100 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use manifest::{
+    logs::{DepthSnapshotLog, Discriminant},
+    program::emit_depth_snapshot_instruction,
+    quantities::{BaseAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
+    state::{constants::NO_EXPIRATION_LAST_VALID_SLOT, OrderType},
+};
+use solana_keypair::Keypair;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::{Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+#[tokio::test]
+async fn emit_depth_snapshot_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 3 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .deposit(Token::USDC, 1_000 * USDC_UNIT_SIZE)
+        .await?;
+
+    // Two asks at 0.1 make one level, the ask at 0.2 is past the levels asked
+    // for.
+    for price_mantissa in [1, 1, 2] {
+        test_fixture
+            .place_order(
+                Side::Ask,
+                SOL_UNIT_SIZE,
+                price_mantissa,
+                -1,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                OrderType::Limit,
+            )
+            .await?;
+    }
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            9,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let mut context = test_fixture.context.borrow_mut();
+    let blockhash = context.get_new_latest_blockhash().await?;
+    let tx: Transaction = Transaction::new_signed_with_payer(
+        &[emit_depth_snapshot_instruction(
+            &test_fixture.market_fixture.key,
+            1,
+        )],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+        blockhash,
+    );
+    let logs: Vec<String> = context
+        .banks_client
+        .simulate_transaction(tx)
+        .await?
+        .simulation_details
+        .unwrap()
+        .logs;
+    let data: Vec<u8> = logs
+        .iter()
+        .find_map(|log| log.strip_prefix("Program data: "))
+        .map(|encoded| STANDARD.decode(encoded))
+        .unwrap()?;
+    assert_eq!(data[..8], DepthSnapshotLog::discriminant());
+    let (body, version) = data[8..].split_at(data.len() - 9);
+    assert_eq!(version, [DepthSnapshotLog::version()]);
+    let depth_snapshot_log: DepthSnapshotLog = bytemuck::pod_read_unaligned(body);
+
+    assert_eq!(depth_snapshot_log.market, test_fixture.market_fixture.key);
+    assert_eq!(depth_snapshot_log.num_bids, 1);
+    assert_eq!(depth_snapshot_log.num_asks, 1);
+    assert_eq!(
+        depth_snapshot_log.bids[0].price,
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(9, -2).unwrap()
+    );
+    assert_eq!(
+        depth_snapshot_log.bids[0].base_atoms,
+        BaseAtoms::new(SOL_UNIT_SIZE)
+    );
+    assert_eq!(
+        depth_snapshot_log.asks[0].price,
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(1, -1).unwrap()
+    );
+    assert_eq!(
+        depth_snapshot_log.asks[0].base_atoms,
+        BaseAtoms::new(2 * SOL_UNIT_SIZE)
+    );
+    assert_eq!(depth_snapshot_log.asks[1].base_atoms, BaseAtoms::new(0));
+
+    Ok(())
+}
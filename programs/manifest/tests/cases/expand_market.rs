@@ -0,0 +1,80 @@
+use std::rc::Rc;
+
+use manifest::{
+    program::expand_market_n_instruction,
+    state::{MARKET_BLOCK_SIZE, MAX_MARKET_BLOCKS_PER_EXPAND, MAX_MARKET_SIZE},
+};
+use solana_program_test::tokio;
+
+use crate::{send_tx_with_retry, TestFixture};
+
+#[tokio::test]
+async fn expand_market_max_size_test() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    let market_key = test_fixture.market_fixture.key;
+    let payer = test_fixture.payer();
+    let payer_keypair = test_fixture.payer_keypair();
+
+    let mut market_len: usize = test_fixture
+        .try_load(&market_key)
+        .await?
+        .unwrap()
+        .data
+        .len();
+    // Ask for far more than fits. Each call grows by at most
+    // MAX_MARKET_BLOCKS_PER_EXPAND blocks and stops at MAX_MARKET_SIZE. The
+    // requested count differs every time so the transactions are not dupes.
+    let mut num_free_blocks: u32 = 100_000;
+    while market_len < MAX_MARKET_SIZE {
+        send_tx_with_retry(
+            Rc::clone(&test_fixture.context),
+            &[expand_market_n_instruction(
+                &market_key,
+                &payer,
+                num_free_blocks,
+            )],
+            Some(&payer),
+            &[&payer_keypair],
+        )
+        .await?;
+        num_free_blocks += 1;
+
+        let new_market_len: usize = test_fixture
+            .try_load(&market_key)
+            .await?
+            .unwrap()
+            .data
+            .len();
+        assert_eq!(
+            new_market_len,
+            MAX_MARKET_SIZE
+                .min(market_len + MAX_MARKET_BLOCKS_PER_EXPAND as usize * MARKET_BLOCK_SIZE)
+        );
+        market_len = new_market_len;
+    }
+
+    // Full market cannot grow any further.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[expand_market_n_instruction(
+            &market_key,
+            &payer,
+            num_free_blocks,
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+    assert_eq!(
+        test_fixture
+            .try_load(&market_key)
+            .await?
+            .unwrap()
+            .data
+            .len(),
+        MAX_MARKET_SIZE
+    );
+
+    Ok(())
+}
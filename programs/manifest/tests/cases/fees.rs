@@ -0,0 +1,598 @@
+use std::rc::Rc;
+
+use hypertree::DataIndex;
+use manifest::{
+    program::{claim_fees_instruction, deposit_instruction, swap_instruction, with_referrer},
+    quantities::{BaseAtoms, QuoteAtoms},
+    state::{MarketFees, OrderType, TradingHours, NO_EXPIRATION_LAST_VALID_SLOT},
+};
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{
+    get_token_account_balance, send_tx_with_retry, MarketFixture, MintFixture, Side, TestFixture,
+    Token, TokenAccountFixture, SOL_UNIT_SIZE, USDC_UNIT_SIZE,
+};
+
+#[tokio::test]
+async fn fees_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let fee_recipient: Pubkey = Pubkey::new_unique();
+    test_fixture.market_fixture = MarketFixture::new_with_fees(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        0,
+        0,
+        0,
+        0,
+        &[],
+        0,
+        0,
+        0,
+        10,
+        30,
+        0,
+        &fee_recipient,
+        0,
+    )
+    .await;
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+
+    // The second keypair asks 2 SOL at 1 quote atom per base atom.
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 2 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            2 * SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    // The taker pays 30 bps of the base it buys, the maker 10 bps of the
+    // quote it sells for.
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 1_000 * USDC_UNIT_SIZE)
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        997_000_000
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&second_keypair.pubkey())
+            .await,
+        999_000_000
+    );
+
+    // An exact out swap matches enough that the output after the fee is what
+    // was asked for.
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 1_000 * USDC_UNIT_SIZE)
+        .await;
+    let payer_sol_atoms: u64 = test_fixture.payer_sol_fixture.balance_atoms().await;
+    test_fixture
+        .swap(1_000 * USDC_UNIT_SIZE, SOL_UNIT_SIZE / 2, false, false)
+        .await?;
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        payer_sol_atoms + SOL_UNIT_SIZE / 2
+    );
+
+    // 501_504_514 base atoms traded in the swap.
+    test_fixture.market_fixture.reload().await;
+    let market_fees: MarketFees = *test_fixture
+        .market_fixture
+        .market
+        .get_market_fees()
+        .unwrap();
+    assert_eq!(market_fees.get_maker_fee_bps(), 10);
+    assert_eq!(market_fees.get_taker_fee_bps(), 30);
+    assert_eq!(
+        market_fees.get_base_fee_atoms(),
+        BaseAtoms::new(3_000_000 + 1_504_514)
+    );
+    assert_eq!(
+        market_fees.get_quote_fee_atoms(),
+        QuoteAtoms::new(1_000_000 + 501_504)
+    );
+    assert_eq!(*market_fees.get_fee_recipient(), fee_recipient);
+    test_fixture
+        .market_fixture
+        .verify_vault_balance(&[payer, second_keypair.pubkey()], true)
+        .await;
+
+    // Anyone can claim, but only to token accounts of the fee recipient.
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let sol_mint: Pubkey = test_fixture.sol_mint_fixture.key;
+    let usdc_mint: Pubkey = test_fixture.usdc_mint_fixture.key;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[claim_fees_instruction(
+            &market,
+            &payer,
+            &sol_mint,
+            &test_fixture.payer_sol_fixture.key,
+            spl_token::id(),
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    let recipient_sol_fixture: TokenAccountFixture =
+        TokenAccountFixture::new(Rc::clone(&test_fixture.context), &sol_mint, &fee_recipient).await;
+    let recipient_usdc_fixture: TokenAccountFixture =
+        TokenAccountFixture::new(Rc::clone(&test_fixture.context), &usdc_mint, &fee_recipient)
+            .await;
+    let claim_fees_ixs: Vec<Instruction> = vec![
+        claim_fees_instruction(
+            &market,
+            &payer,
+            &sol_mint,
+            &recipient_sol_fixture.key,
+            spl_token::id(),
+        ),
+        claim_fees_instruction(
+            &market,
+            &payer,
+            &usdc_mint,
+            &recipient_usdc_fixture.key,
+            spl_token::id(),
+        ),
+    ];
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &claim_fees_ixs,
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert_eq!(
+        recipient_sol_fixture.balance_atoms().await,
+        3_000_000 + 1_504_514
+    );
+    assert_eq!(
+        recipient_usdc_fixture.balance_atoms().await,
+        1_000_000 + 501_504
+    );
+
+    // The counters are zeroed, so the vaults still hold exactly what the seats
+    // and orders are owed.
+    test_fixture.market_fixture.reload().await;
+    let market_fees: MarketFees = *test_fixture
+        .market_fixture
+        .market
+        .get_market_fees()
+        .unwrap();
+    assert_eq!(market_fees.get_base_fee_atoms(), BaseAtoms::ZERO);
+    assert_eq!(market_fees.get_quote_fee_atoms(), QuoteAtoms::ZERO);
+    test_fixture
+        .market_fixture
+        .verify_vault_balance(&[payer, second_keypair.pubkey()], true)
+        .await;
+
+    Ok(())
+}
+
+// Plain SPL market, so the swap has no mints and the referrer token account
+// follows the token program.
+#[tokio::test]
+async fn referral_fee_spl_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.market_fixture = MarketFixture::new_with_fees(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        0,
+        0,
+        0,
+        0,
+        &[],
+        0,
+        0,
+        0,
+        10,
+        30,
+        5_000,
+        &Pubkey::new_unique(),
+        0,
+    )
+    .await;
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 2 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            2 * SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 1_000 * USDC_UNIT_SIZE)
+        .await;
+
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let sol_mint: Pubkey = test_fixture.sol_mint_fixture.key;
+    let usdc_mint: Pubkey = test_fixture.usdc_mint_fixture.key;
+    let referrer: Pubkey = Pubkey::new_unique();
+    let referrer_sol_fixture: TokenAccountFixture =
+        TokenAccountFixture::new(Rc::clone(&test_fixture.context), &sol_mint, &referrer).await;
+    let referrer_usdc_fixture: TokenAccountFixture =
+        TokenAccountFixture::new(Rc::clone(&test_fixture.context), &usdc_mint, &referrer).await;
+    let swap_ix = |referrer_token_account: &Pubkey| -> Instruction {
+        with_referrer(
+            swap_instruction(
+                &market,
+                &payer,
+                &sol_mint,
+                &usdc_mint,
+                &test_fixture.payer_sol_fixture.key,
+                &test_fixture.payer_usdc_fixture.key,
+                1_000 * USDC_UNIT_SIZE,
+                SOL_UNIT_SIZE / 2,
+                false,
+                false,
+                spl_token::id(),
+                spl_token::id(),
+                false,
+            ),
+            &sol_mint,
+            &usdc_mint,
+            referrer_token_account,
+        )
+    };
+
+    // The referrer has to be paid in the output mint.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[swap_ix(&referrer_usdc_fixture.key)],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    // Same swap as in fees_test, so the taker fee is 1_504_514 base atoms and
+    // half of it, rounded down, goes to the referrer.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[swap_ix(&referrer_sol_fixture.key)],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert_eq!(referrer_sol_fixture.balance_atoms().await, 752_257);
+    assert_eq!(referrer_usdc_fixture.balance_atoms().await, 0);
+
+    test_fixture.market_fixture.reload().await;
+    let market_fees: MarketFees = *test_fixture
+        .market_fixture
+        .market
+        .get_market_fees()
+        .unwrap();
+    assert_eq!(market_fees.get_referral_fee_bps(), 5_000);
+    assert_eq!(
+        market_fees.get_base_fee_atoms(),
+        BaseAtoms::new(1_504_514 - 752_257)
+    );
+    assert_eq!(market_fees.get_quote_fee_atoms(), QuoteAtoms::new(501_504));
+    test_fixture
+        .market_fixture
+        .verify_vault_balance(&[payer, second_keypair.pubkey()], true)
+        .await;
+
+    Ok(())
+}
+
+// Token22 base and SPL quote, so the swap has the base mint and quote token
+// program before the referrer token account.
+#[tokio::test]
+async fn referral_fee_token22_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let mut base_mint_fixture: MintFixture =
+        MintFixture::new_with_version(Rc::clone(&test_fixture.context), Some(9), true).await;
+    let base_mint: Pubkey = base_mint_fixture.key;
+    let usdc_mint: Pubkey = test_fixture.usdc_mint_fixture.key;
+    test_fixture.market_fixture = MarketFixture::new_with_fees(
+        Rc::clone(&test_fixture.context),
+        &base_mint,
+        &usdc_mint,
+        0,
+        TradingHours::default(),
+        0,
+        0,
+        0,
+        0,
+        &[],
+        0,
+        0,
+        0,
+        10,
+        30,
+        5_000,
+        &Pubkey::new_unique(),
+        0,
+    )
+    .await;
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    let maker_base_fixture: TokenAccountFixture = TokenAccountFixture::new_with_keypair_2022(
+        Rc::clone(&test_fixture.context),
+        &base_mint,
+        &second_keypair.pubkey(),
+        &Keypair::new(),
+    )
+    .await;
+    base_mint_fixture
+        .mint_to_2022(&maker_base_fixture.key, 2 * SOL_UNIT_SIZE)
+        .await;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[deposit_instruction(
+            &market,
+            &second_keypair.pubkey(),
+            &base_mint,
+            2 * SOL_UNIT_SIZE,
+            &maker_base_fixture.key,
+            spl_token_2022::id(),
+            None,
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            2 * SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    let payer_base_fixture: TokenAccountFixture = TokenAccountFixture::new_with_keypair_2022(
+        Rc::clone(&test_fixture.context),
+        &base_mint,
+        &payer,
+        &Keypair::new(),
+    )
+    .await;
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 1_000 * USDC_UNIT_SIZE)
+        .await;
+    let referrer_base_fixture: TokenAccountFixture = TokenAccountFixture::new_with_keypair_2022(
+        Rc::clone(&test_fixture.context),
+        &base_mint,
+        &Pubkey::new_unique(),
+        &Keypair::new(),
+    )
+    .await;
+
+    // Same swap as in referral_fee_spl_test.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[with_referrer(
+            swap_instruction(
+                &market,
+                &payer,
+                &base_mint,
+                &usdc_mint,
+                &payer_base_fixture.key,
+                &test_fixture.payer_usdc_fixture.key,
+                1_000 * USDC_UNIT_SIZE,
+                SOL_UNIT_SIZE / 2,
+                false,
+                false,
+                spl_token_2022::id(),
+                spl_token::id(),
+                false,
+            ),
+            &base_mint,
+            &usdc_mint,
+            &referrer_base_fixture.key,
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert_eq!(
+        get_token_account_balance(Rc::clone(&test_fixture.context), referrer_base_fixture.key)
+            .await,
+        752_257
+    );
+    assert_eq!(
+        get_token_account_balance(Rc::clone(&test_fixture.context), payer_base_fixture.key).await,
+        SOL_UNIT_SIZE / 2
+    );
+
+    test_fixture.market_fixture.reload().await;
+    let market_fees: MarketFees = *test_fixture
+        .market_fixture
+        .market
+        .get_market_fees()
+        .unwrap();
+    assert_eq!(
+        market_fees.get_base_fee_atoms(),
+        BaseAtoms::new(1_504_514 - 752_257)
+    );
+    assert_eq!(market_fees.get_quote_fee_atoms(), QuoteAtoms::new(501_504));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fee_tier_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    // The first tier is at 1 SOL worth of quote atoms at the price below.
+    test_fixture.market_fixture = MarketFixture::new_with_fees(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        0,
+        0,
+        0,
+        0,
+        &[],
+        0,
+        0,
+        0,
+        0,
+        30,
+        0,
+        &Pubkey::new_unique(),
+        SOL_UNIT_SIZE,
+    )
+    .await;
+    let payer: Pubkey = test_fixture.payer();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+
+    // The second keypair asks 2 SOL at 1 quote atom per base atom.
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 2 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            2 * SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    // The first buy pays the full 30 bps and reaches the first tier.
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 1_000 * USDC_UNIT_SIZE)
+        .await?;
+    test_fixture.market_fixture.reload().await;
+    let trader_index: DataIndex = test_fixture.market_fixture.market.get_trader_index(&payer);
+    assert!(test_fixture
+        .market_fixture
+        .market
+        .get_seat_volume(trader_index)
+        .is_some());
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        997_000_000
+    );
+
+    // The second gets 10% off, so 27 bps.
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        997_000_000 + 997_300_000
+    );
+
+    // Only the taker volume counts toward tiers.
+    test_fixture.market_fixture.reload().await;
+    let market_fees: MarketFees = *test_fixture
+        .market_fixture
+        .market
+        .get_market_fees()
+        .unwrap();
+    assert_eq!(
+        market_fees.get_base_fee_atoms(),
+        BaseAtoms::new(3_000_000 + 2_700_000)
+    );
+    let maker_index: DataIndex = test_fixture
+        .market_fixture
+        .market
+        .get_trader_index(&second_keypair.pubkey());
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .get_seat_rolling_quote_atoms(maker_index, 0),
+        QuoteAtoms::ZERO
+    );
+    test_fixture
+        .market_fixture
+        .verify_vault_balance(&[payer, second_keypair.pubkey()], true)
+        .await;
+
+    Ok(())
+}
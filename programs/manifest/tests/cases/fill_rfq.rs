@@ -0,0 +1,442 @@
+use std::rc::Rc;
+
+use manifest::{
+    program::{
+        ed25519_verify_instruction,
+        fill_rfq::{RfqQuote, RfqResponse},
+        fill_rfq_instruction, post_rfq_instruction, set_mmp_instruction,
+    },
+    state::{OrderType, TradingHours, NO_EXPIRATION_LAST_VALID_SLOT},
+    validation::get_rfq_address,
+};
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{
+    send_tx_with_retry, MarketFixture, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE,
+};
+
+/// Quote for the RFQ of taker, price_mantissa in hundredths of a quote atom
+/// per base atom.
+fn quote(
+    market: &Pubkey,
+    taker: &Pubkey,
+    sequence_number: u64,
+    price_mantissa: u32,
+    last_valid_slot: u32,
+) -> RfqQuote {
+    RfqQuote::new(
+        get_rfq_address(market, taker).0,
+        sequence_number,
+        price_mantissa,
+        -2,
+        last_valid_slot,
+    )
+}
+
+/// One ed25519 instruction per response for the signature of its maker,
+/// then the FillRfq.
+fn fill_ixs(
+    market: &Pubkey,
+    taker: &Pubkey,
+    responses: &[(&Keypair, RfqQuote)],
+) -> Vec<Instruction> {
+    let mut ixs: Vec<Instruction> = responses
+        .iter()
+        .map(|(maker_keypair, quote)| {
+            let signature: [u8; 64] = maker_keypair.sign_message(&quote.message()).into();
+            ed25519_verify_instruction(&maker_keypair.pubkey(), &signature, &quote.message())
+        })
+        .collect();
+    ixs.push(fill_rfq_instruction(
+        market,
+        taker,
+        responses
+            .iter()
+            .map(|(maker_keypair, quote)| RfqResponse::new(maker_keypair.pubkey(), quote.clone()))
+            .collect(),
+    ));
+    ixs
+}
+
+#[tokio::test]
+async fn fill_rfq_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let (maker_keypair, taker_keypair) = fund_maker_and_taker(&mut test_fixture).await?;
+    let maker: Pubkey = maker_keypair.pubkey();
+    let taker: Pubkey = taker_keypair.pubkey();
+    // Quotes without a seat on the market are passed over.
+    let seatless_keypair: Keypair = Keypair::new();
+    let post = |base_atoms: u64, last_valid_slot: u32| {
+        post_rfq_instruction(&market, &taker, base_atoms, true, last_valid_slot)
+    };
+
+    // Taker buys 5 SOL. The best quote the maker can cover is 100 USDC per
+    // SOL, 0.1 quote atoms per base atom.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[post(5 * SOL_UNIT_SIZE, 1_000)],
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await?;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &fill_ixs(
+            &market,
+            &taker,
+            &[
+                (&maker_keypair, quote(&market, &taker, 1, 20, 1_000)),
+                (&seatless_keypair, quote(&market, &taker, 1, 5, 1_000)),
+                (&maker_keypair, quote(&market, &taker, 1, 10, 1_000)),
+            ],
+        ),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await?;
+
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&maker)
+            .await,
+        5 * SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&maker)
+            .await,
+        500 * USDC_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&taker)
+            .await,
+        5 * SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&taker)
+            .await,
+        500 * USDC_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture.market_fixture.get_quote_volume(&maker).await,
+        500 * USDC_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture.market_fixture.get_quote_volume(&taker).await,
+        500 * USDC_UNIT_SIZE
+    );
+    assert!(test_fixture
+        .market_fixture
+        .get_resting_orders()
+        .await
+        .is_empty());
+
+    // A filled request is closed.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &fill_ixs(
+            &market,
+            &taker,
+            &[(&maker_keypair, quote(&market, &taker, 1, 11, 1_000))],
+        ),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+
+    // Quotes for an earlier request do not fill the next one.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[post(SOL_UNIT_SIZE, 1_000)],
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await?;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &fill_ixs(
+            &market,
+            &taker,
+            &[(&maker_keypair, quote(&market, &taker, 1, 12, 1_000))],
+        ),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+
+    // Fails when signed by someone else than the maker.
+    let forged: RfqQuote = quote(&market, &taker, 2, 13, 1_000);
+    let forged_signature: [u8; 64] = taker_keypair.sign_message(&forged.message()).into();
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[
+            ed25519_verify_instruction(&taker, &forged_signature, &forged.message()),
+            fill_rfq_instruction(
+                &market,
+                &taker,
+                vec![RfqResponse::new(maker, forged.clone())]
+            ),
+        ],
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+
+    // Fails without the ed25519 instruction.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[fill_rfq_instruction(
+            &market,
+            &taker,
+            vec![RfqResponse::new(maker, forged)]
+        )],
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+
+    // Fails when no maker can cover the request.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[post(6 * SOL_UNIT_SIZE, 1_000)],
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await?;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &fill_ixs(
+            &market,
+            &taker,
+            &[(&maker_keypair, quote(&market, &taker, 3, 10, 1_000))],
+        ),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+
+    // Fails once cancelled.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[post(0, 0)],
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await?;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &fill_ixs(
+            &market,
+            &taker,
+            &[(&maker_keypair, quote(&market, &taker, 4, 10, 1_000))],
+        ),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+
+    // Fails once the request expired.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[post(SOL_UNIT_SIZE, 100)],
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await?;
+    test_fixture.advance_time_seconds(2_000).await;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &fill_ixs(
+            &market,
+            &taker,
+            &[(&maker_keypair, quote(&market, &taker, 5, 10, 5_000))],
+        ),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
+
+/// Maker with 10 SOL and taker with 1000 USDC on the market of test_fixture.
+async fn fund_maker_and_taker(
+    test_fixture: &mut TestFixture,
+) -> anyhow::Result<(Keypair, Keypair)> {
+    let maker_keypair: Keypair = test_fixture.payer_keypair();
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 10 * SOL_UNIT_SIZE).await?;
+    let taker_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&taker_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 1_000 * USDC_UNIT_SIZE, &taker_keypair)
+        .await?;
+    Ok((maker_keypair, taker_keypair))
+}
+
+/// Posts a request of the taker to buy base_atoms, then fills it with a
+/// quote of the maker at 100 USDC per SOL.
+fn post_and_fill_ixs(
+    market: &Pubkey,
+    maker_keypair: &Keypair,
+    taker: &Pubkey,
+    base_atoms: u64,
+) -> Vec<Instruction> {
+    [
+        vec![post_rfq_instruction(market, taker, base_atoms, true, 1_000)],
+        fill_ixs(
+            market,
+            taker,
+            &[(maker_keypair, quote(market, taker, 1, 10, 1_000))],
+        ),
+    ]
+    .concat()
+}
+
+#[tokio::test]
+async fn fill_rfq_fees_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.market_fixture = MarketFixture::new_with_fees(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        0,
+        0,
+        0,
+        0,
+        &[],
+        0,
+        0,
+        0,
+        0,
+        10,
+        0,
+        &Pubkey::new_unique(),
+        0,
+    )
+    .await;
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let (maker_keypair, taker_keypair) = fund_maker_and_taker(&mut test_fixture).await?;
+
+    // RFQ fills are not charged fees, so markets with fees only fill on the
+    // book.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &post_and_fill_ixs(
+            &market,
+            &maker_keypair,
+            &taker_keypair.pubkey(),
+            SOL_UNIT_SIZE
+        ),
+        Some(&taker_keypair.pubkey()),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fill_rfq_min_order_size_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.market_fixture = MarketFixture::new_with_min_order_size(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        0,
+        0,
+        0,
+        0,
+        &[],
+        SOL_UNIT_SIZE,
+        0,
+    )
+    .await;
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let (maker_keypair, taker_keypair) = fund_maker_and_taker(&mut test_fixture).await?;
+    let taker: Pubkey = taker_keypair.pubkey();
+
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &post_and_fill_ixs(&market, &maker_keypair, &taker, SOL_UNIT_SIZE - 1),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &post_and_fill_ixs(&market, &maker_keypair, &taker, SOL_UNIT_SIZE),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fill_rfq_mmp_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let (maker_keypair, taker_keypair) = fund_maker_and_taker(&mut test_fixture).await?;
+    let maker: Pubkey = maker_keypair.pubkey();
+    let taker: Pubkey = taker_keypair.pubkey();
+
+    // One fill within 100 slots trips the maker's protection, which pulls
+    // its ask on the book.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_mmp_instruction(&market, &maker, 100, 1, 0, None)],
+        Some(&maker),
+        &[&maker_keypair],
+    )
+    .await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            2,
+            -1,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &post_and_fill_ixs(&market, &maker_keypair, &taker, SOL_UNIT_SIZE),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await?;
+    assert!(test_fixture
+        .market_fixture
+        .get_resting_orders()
+        .await
+        .is_empty());
+
+    Ok(())
+}
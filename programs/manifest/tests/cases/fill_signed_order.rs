@@ -0,0 +1,189 @@
+use std::rc::Rc;
+
+use manifest::program::{
+    cancel_signed_orders_instruction, ed25519_verify_instruction, fill_signed_order::SignedOrder,
+    fill_signed_order_instruction,
+};
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{send_tx_with_retry, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+#[tokio::test]
+async fn fill_signed_order_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let market: Pubkey = test_fixture.market_fixture.key;
+
+    // Maker sells SOL off chain, taker buys with USDC.
+    let maker_keypair: Keypair = test_fixture.payer_keypair();
+    let maker: Pubkey = maker_keypair.pubkey();
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 20 * SOL_UNIT_SIZE).await?;
+
+    let taker_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let taker: Pubkey = taker_keypair.pubkey();
+    test_fixture.claim_seat_for_keypair(&taker_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 2_000 * USDC_UNIT_SIZE, &taker_keypair)
+        .await?;
+
+    // Fills need the nonces account of the maker.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cancel_signed_orders_instruction(&market, &maker, 0)],
+        Some(&maker),
+        &[&maker_keypair],
+    )
+    .await?;
+
+    // 100 USDC per SOL is 0.1 quote atoms per base atom.
+    let order = |market: Pubkey, last_valid_slot: u32, nonce: u64| {
+        SignedOrder::new(
+            market,
+            5 * SOL_UNIT_SIZE,
+            1,
+            -1,
+            false,
+            last_valid_slot,
+            nonce,
+        )
+    };
+    let fill_ixs = |signer: &Keypair, order: SignedOrder| -> Vec<Instruction> {
+        let signature: [u8; 64] = signer.sign_message(&order.message()).into();
+        vec![
+            ed25519_verify_instruction(&signer.pubkey(), &signature, &order.message()),
+            fill_signed_order_instruction(&market, &taker, &maker, order),
+        ]
+    };
+
+    // Only the taker signs the transaction.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &fill_ixs(&maker_keypair, order(market, 1_000, 0)),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&maker)
+            .await,
+        15 * SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&maker)
+            .await,
+        500 * USDC_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&taker)
+            .await,
+        5 * SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&taker)
+            .await,
+        1_500 * USDC_UNIT_SIZE
+    );
+
+    // The same order cannot be filled twice.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &fill_ixs(&maker_keypair, order(market, 1_000, 0)),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+
+    // Cancelled orders cannot be filled, later nonces still can.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cancel_signed_orders_instruction(&market, &maker, 10)],
+        Some(&maker),
+        &[&maker_keypair],
+    )
+    .await?;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &fill_ixs(&maker_keypair, order(market, 1_000, 7)),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &fill_ixs(&maker_keypair, order(market, 1_000, 10)),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&taker)
+            .await,
+        10 * SOL_UNIT_SIZE
+    );
+
+    // Fails when signed by someone else than the maker.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &fill_ixs(&taker_keypair, order(market, 1_000, 11)),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+
+    // Fails without the ed25519 instruction.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[fill_signed_order_instruction(
+            &market,
+            &taker,
+            &maker,
+            order(market, 1_000, 12)
+        )],
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+
+    // Fails for an order signed for another market or without an expiration.
+    for order in [order(Pubkey::new_unique(), 1_000, 13), order(market, 0, 14)] {
+        assert!(send_tx_with_retry(
+            Rc::clone(&test_fixture.context),
+            &fill_ixs(&maker_keypair, order),
+            Some(&taker),
+            &[&taker_keypair],
+        )
+        .await
+        .is_err());
+    }
+
+    // Fails once the order expired.
+    test_fixture.advance_time_seconds(2_000).await;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &fill_ixs(&maker_keypair, order(market, 100, 15)),
+        Some(&taker),
+        &[&taker_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use std::rc::Rc;
+
+use borsh::BorshDeserialize;
+use manifest::{
+    program::{get_market_prices::GetMarketPricesReturn, get_market_prices_instruction},
+    state::{constants::NO_EXPIRATION_LAST_VALID_SLOT, OrderType},
+};
+use solana_keypair::Keypair;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::{Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+#[tokio::test]
+async fn get_market_prices_test() -> anyhow::Result<()> {
+    const D17: u128 = 100_000_000_000_000_000;
+
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 3 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .deposit(Token::USDC, 1_000 * USDC_UNIT_SIZE)
+        .await?;
+
+    // Asks at 0.1, 0.101 and 0.105 quote atoms per base atom, the last one
+    // outside of 2% of the best ask.
+    for (price_mantissa, price_exponent) in [(1, -1), (101, -3), (105, -3)] {
+        test_fixture
+            .place_order(
+                Side::Ask,
+                SOL_UNIT_SIZE,
+                price_mantissa,
+                price_exponent,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                OrderType::Limit,
+            )
+            .await?;
+    }
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            9,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    // Buys 10^8 base atoms at 0.1.
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 10 * USDC_UNIT_SIZE)
+        .await;
+    test_fixture
+        .swap(10 * USDC_UNIT_SIZE, 0, false, true)
+        .await?;
+
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let mut context = test_fixture.context.borrow_mut();
+    let blockhash = context.get_new_latest_blockhash().await?;
+    let tx: Transaction = Transaction::new_signed_with_payer(
+        &[get_market_prices_instruction(
+            &test_fixture.market_fixture.key,
+        )],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+        blockhash,
+    );
+    let return_data: Vec<u8> = context
+        .banks_client
+        .simulate_transaction(tx)
+        .await?
+        .simulation_details
+        .unwrap()
+        .return_data
+        .unwrap()
+        .data;
+    let prices: GetMarketPricesReturn = GetMarketPricesReturn::try_from_slice(&return_data)?;
+
+    assert_eq!(prices.best_bid_price, 9 * D17 / 10);
+    assert_eq!(prices.best_ask_price, D17);
+    assert_eq!(prices.last_trade_price, D17);
+    assert_eq!(prices.twap_price, D17);
+    assert!(prices.last_trade_slot <= prices.slot);
+    assert_eq!(prices.bid_depth_base_atoms, SOL_UNIT_SIZE);
+    assert_eq!(prices.ask_depth_base_atoms, 2 * SOL_UNIT_SIZE - 100_000_000);
+    assert_eq!(prices.ask_depth_quote_atoms, 90_000_000 + 101_000_000);
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use std::rc::Rc;
+
+use manifest::{
+    program::{cancel_on_heartbeat_timeout_instruction, heartbeat_instruction},
+    state::{OrderType, NO_EXPIRATION_LAST_VALID_SLOT},
+    validation::get_heartbeat_address,
+};
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{send_tx_with_retry, Side, TestFixture, Token, SOL_UNIT_SIZE};
+
+#[tokio::test]
+async fn heartbeat_timeout_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let (heartbeat, _) = get_heartbeat_address(&market, &payer_keypair.pubkey());
+
+    // Needs a seat.
+    let heartbeat_ix = heartbeat_instruction(&market, &payer_keypair.pubkey(), 10, 1_000_000);
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[heartbeat_ix.clone()],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            10,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[heartbeat_ix],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+    let armed_lamports: u64 = test_fixture.try_load(&heartbeat).await?.unwrap().lamports;
+
+    let cancel_ix = cancel_on_heartbeat_timeout_instruction(
+        &market,
+        &second_keypair.pubkey(),
+        &payer_keypair.pubkey(),
+    );
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cancel_ix.clone()],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+
+    // Anyone can cancel once the timeout passes, and takes the bounty.
+    test_fixture.advance_time_seconds(40).await;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cancel_ix.clone()],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        0
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer_keypair.pubkey())
+            .await,
+        SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture.try_load(&heartbeat).await?.unwrap().lamports,
+        armed_lamports - 1_000_000
+    );
+
+    // Disarmed until the next heartbeat.
+    test_fixture.advance_time_seconds(40).await;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cancel_ix],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
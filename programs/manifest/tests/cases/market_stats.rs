@@ -0,0 +1,133 @@
+use std::rc::Rc;
+
+use manifest::{
+    quantities::{BaseAtoms, QuoteAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
+    state::{
+        MarketStats, OrderType, TradingHours, MARKET_FLAG_VOLUME_STATS,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    },
+};
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+
+use crate::{MarketFixture, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+#[tokio::test]
+async fn market_stats_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+
+    // Markets without the flag keep no stats.
+    test_fixture.market_fixture.reload().await;
+    assert!(test_fixture
+        .market_fixture
+        .market
+        .get_market_stats()
+        .is_none());
+
+    // The stats go after the fees.
+    test_fixture.market_fixture = MarketFixture::new_with_fees(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        MARKET_FLAG_VOLUME_STATS,
+        TradingHours::default(),
+        0,
+        0,
+        0,
+        0,
+        &[],
+        0,
+        0,
+        0,
+        10,
+        30,
+        0,
+        &Pubkey::new_unique(),
+        0,
+    )
+    .await;
+    test_fixture.market_fixture.reload().await;
+    let market_stats: MarketStats = *test_fixture
+        .market_fixture
+        .market
+        .get_market_stats()
+        .unwrap();
+    assert_eq!(market_stats.get_num_fills(), 0);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .get_market_fees()
+            .unwrap()
+            .get_taker_fee_bps(),
+        30
+    );
+
+    // The second keypair asks 1 SOL at 1 and 1 SOL at 2 quote atoms per base
+    // atom.
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 2 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    for price_mantissa in [1, 2] {
+        test_fixture
+            .place_order_for_keypair(
+                Side::Ask,
+                SOL_UNIT_SIZE,
+                price_mantissa,
+                0,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                OrderType::Limit,
+                &second_keypair,
+            )
+            .await?;
+    }
+
+    // Buying 1.5 SOL fills both.
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 3_000 * USDC_UNIT_SIZE)
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            3 * SOL_UNIT_SIZE / 2,
+            2,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+
+    test_fixture.market_fixture.reload().await;
+    let market_stats: MarketStats = *test_fixture
+        .market_fixture
+        .market
+        .get_market_stats()
+        .unwrap();
+    assert_eq!(
+        market_stats.get_base_volume(),
+        BaseAtoms::new(3 * SOL_UNIT_SIZE / 2)
+    );
+    assert_eq!(
+        market_stats.get_quote_volume(),
+        QuoteAtoms::new(2 * SOL_UNIT_SIZE)
+    );
+    assert_eq!(market_stats.get_num_fills(), 2);
+    assert_eq!(
+        test_fixture.market_fixture.market.fixed.get_quote_volume(),
+        market_stats.get_quote_volume()
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .fixed
+            .get_last_trade_price(),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(2, 0).unwrap()
+    );
+
+    Ok(())
+}
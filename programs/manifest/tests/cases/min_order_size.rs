@@ -0,0 +1,136 @@
+use std::rc::Rc;
+
+use manifest::{
+    program::{create_market_instructions, create_market_with_flags_instruction},
+    quantities::{BaseAtoms, QuoteAtoms, WrapperU64},
+    state::{
+        min_order_size::MinOrderSize, OrderType, TradingHours, MARKET_FLAG_MIN_ORDER_SIZE,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    },
+};
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{
+    send_tx_with_retry, MarketFixture, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE,
+};
+
+#[tokio::test]
+async fn min_order_size_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.market_fixture = MarketFixture::new_with_min_order_size(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        0,
+        0,
+        0,
+        0,
+        &[],
+        SOL_UNIT_SIZE / 100,
+        USDC_UNIT_SIZE,
+    )
+    .await;
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture.market_fixture.market.get_min_order_size(),
+        Some(&MinOrderSize::new(
+            BaseAtoms::new(SOL_UNIT_SIZE / 100),
+            QuoteAtoms::new(USDC_UNIT_SIZE)
+        ))
+    );
+
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+
+    // Dust is below the base minimum.
+    assert!(test_fixture
+        .place_order(
+            Side::Ask,
+            1,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await
+        .is_err());
+    // 0.1 SOL at 1 USDC is enough base but only worth 0.1 USDC.
+    assert!(test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE / 10,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await
+        .is_err());
+    test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    // Orders that cannot rest can be any size.
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            1_000,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn min_order_size_flag_without_sizes_fails_test() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let market_keypair: Keypair = Keypair::new();
+
+    let mut create_market_ixs: Vec<Instruction> = create_market_instructions(
+        &market_keypair.pubkey(),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        &payer_keypair.pubkey(),
+    )
+    .unwrap();
+    create_market_ixs[1] = create_market_with_flags_instruction(
+        &market_keypair.pubkey(),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        &payer_keypair.pubkey(),
+        MARKET_FLAG_MIN_ORDER_SIZE,
+    );
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &create_market_ixs[..],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair, &market_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use std::rc::Rc;
+
+use hypertree::DataIndex;
+use manifest::{
+    program::{archive_seat_instruction, set_mmp_instruction},
+    state::{OrderType, NO_EXPIRATION_LAST_VALID_SLOT},
+};
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{send_tx_with_retry, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+#[tokio::test]
+async fn mmp_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let market: Pubkey = test_fixture.market_fixture.key;
+
+    // The second keypair quotes three 1 SOL asks at 1 quote atom per base
+    // atom and a bid below them.
+    let maker_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let maker: Pubkey = maker_keypair.pubkey();
+    test_fixture.claim_seat_for_keypair(&maker_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 3 * SOL_UNIT_SIZE, &maker_keypair)
+        .await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 100 * USDC_UNIT_SIZE, &maker_keypair)
+        .await?;
+
+    // Needs a window.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_mmp_instruction(&market, &maker, 0, 2, 0, None)],
+        Some(&maker),
+        &[&maker_keypair],
+    )
+    .await
+    .is_err());
+
+    // Two fills within 100 slots pull the quotes.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_mmp_instruction(&market, &maker, 100, 2, 0, None)],
+        Some(&maker),
+        &[&maker_keypair],
+    )
+    .await?;
+    test_fixture.market_fixture.reload().await;
+    let maker_index: DataIndex = test_fixture.market_fixture.market.get_trader_index(&maker);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .get_seat_mmp(maker_index)
+            .unwrap()
+            .get_max_fills(),
+        2
+    );
+
+    // The SeatMmp would be freed with the seat, so it cannot be archived.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[archive_seat_instruction(&market, &maker, &maker)],
+        Some(&maker),
+        &[&maker_keypair],
+    )
+    .await
+    .is_err());
+
+    for _ in 0..3 {
+        test_fixture
+            .place_order_for_keypair(
+                Side::Ask,
+                SOL_UNIT_SIZE,
+                1,
+                0,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                OrderType::Limit,
+                &maker_keypair,
+            )
+            .await?;
+    }
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            1,
+            -1,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &maker_keypair,
+        )
+        .await?;
+
+    // The taker only gets the first two asks.
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 3_000 * USDC_UNIT_SIZE)
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            3 * SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+    let payer: Pubkey = test_fixture.payer();
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        2 * SOL_UNIT_SIZE
+    );
+
+    // Everything the maker had on the book is back in its balances.
+    assert!(test_fixture
+        .market_fixture
+        .get_resting_orders()
+        .await
+        .is_empty());
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&maker)
+            .await,
+        SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&maker)
+            .await,
+        2_100 * USDC_UNIT_SIZE
+    );
+
+    // Cleared, the maker's quotes are not pulled anymore.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_mmp_instruction(&market, &maker, 0, 0, 0, None)],
+        Some(&maker),
+        &[&maker_keypair],
+    )
+    .await?;
+    test_fixture.market_fixture.reload().await;
+    assert!(test_fixture
+        .market_fixture
+        .market
+        .get_seat_mmp(maker_index)
+        .is_none());
+    for _ in 0..2 {
+        test_fixture
+            .place_order_for_keypair(
+                Side::Ask,
+                SOL_UNIT_SIZE / 2,
+                1,
+                0,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                OrderType::Limit,
+                &maker_keypair,
+            )
+            .await?;
+    }
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE / 2,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        1
+    );
+
+    Ok(())
+}
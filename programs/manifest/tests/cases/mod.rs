@@ -1,15 +1,37 @@
 pub mod batch_update;
 pub mod cancel_order;
 pub mod claim_seat;
+pub mod cpi_allowlist;
 pub mod create_market;
 pub mod deposit;
+pub mod emit_depth_snapshot;
+pub mod expand_market;
 pub mod exploit_global_clean;
 pub mod exploit_global_reduce;
+pub mod fees;
+pub mod fill_rfq;
+pub mod fill_signed_order;
+pub mod get_market_prices;
 pub mod global;
+pub mod heartbeat;
 pub mod loaders;
+pub mod market_stats;
 pub mod matching;
+pub mod min_order_size;
+pub mod mmp;
+pub mod pause;
+pub mod pegged_order;
 pub mod place_order;
+pub mod points;
+pub mod price_band;
+pub mod rebalance;
+pub mod recovery;
+pub mod replay;
 pub mod reverse;
+pub mod seat_config;
+pub mod stop_order;
 pub mod swap;
 pub mod token22;
+pub mod trade_bust;
+pub mod volatility;
 pub mod withdraw;
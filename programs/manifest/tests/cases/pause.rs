@@ -0,0 +1,157 @@
+use std::rc::Rc;
+
+use manifest::{
+    program::set_paused_instruction,
+    state::{OrderType, TradingHours, NO_EXPIRATION_LAST_VALID_SLOT},
+};
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{
+    send_tx_with_retry, MarketFixture, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE,
+};
+
+#[tokio::test]
+async fn pause_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+
+    // The default market has no pauser.
+    let pauser_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let pauser: Pubkey = pauser_keypair.pubkey();
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_paused_instruction(
+            &test_fixture.market_fixture.key,
+            &pauser,
+            true
+        )],
+        Some(&pauser),
+        &[&pauser_keypair],
+    )
+    .await
+    .is_err());
+
+    test_fixture.market_fixture = MarketFixture::new_with_pauser(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        0,
+        0,
+        0,
+        0,
+        &[],
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        &Pubkey::default(),
+        0,
+        &pauser,
+    )
+    .await;
+    let market: Pubkey = test_fixture.market_fixture.key;
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .get_market_extension()
+            .unwrap()
+            .get_pauser(),
+        Some(&pauser)
+    );
+
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .deposit(Token::USDC, 1_000 * USDC_UNIT_SIZE)
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    // Only the pauser can pause.
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_paused_instruction(&market, &payer, true)],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_paused_instruction(&market, &pauser, true)],
+        Some(&pauser),
+        &[&pauser_keypair],
+    )
+    .await?;
+    test_fixture.market_fixture.reload().await;
+    assert!(test_fixture
+        .market_fixture
+        .market
+        .get_market_extension()
+        .unwrap()
+        .is_paused());
+
+    // Paused, there are no new orders or swaps.
+    assert!(test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await
+        .is_err());
+    assert!(test_fixture
+        .swap(SOL_UNIT_SIZE, 0, true, true)
+        .await
+        .is_err());
+
+    // Cancels and withdraws still work.
+    test_fixture.cancel_order(0).await?;
+    test_fixture.withdraw(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+
+    // Resumed, orders go on the book again.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_paused_instruction(&market, &pauser, false)],
+        Some(&pauser),
+        &[&pauser_keypair],
+    )
+    .await?;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        1
+    );
+
+    Ok(())
+}
@@ -0,0 +1,303 @@
+use std::rc::Rc;
+
+use hypertree::DataIndex;
+use manifest::{
+    program::{
+        cancel_pegged_order_instruction, place_pegged_order_instruction,
+        reprice_pegged_orders_instruction,
+    },
+    quantities::QuoteAtomsPerBaseAtom,
+    state::{constants::NO_EXPIRATION_LAST_VALID_SLOT, OrderType, PegReference, RestingOrder},
+};
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{send_tx_with_retry, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+fn price(mantissa: u32) -> QuoteAtomsPerBaseAtom {
+    QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(mantissa, -2).unwrap()
+}
+
+/// Resting orders of trader, best first.
+async fn get_orders_of(test_fixture: &mut TestFixture, trader: &Pubkey) -> Vec<RestingOrder> {
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    let trader_index: DataIndex = test_fixture.market_fixture.market.get_trader_index(trader);
+    orders
+        .into_iter()
+        .filter(|order| order.get_trader_index() == trader_index)
+        .collect()
+}
+
+async fn reprice(
+    test_fixture: &TestFixture,
+    trader: &Pubkey,
+    payer_keypair: &Keypair,
+) -> anyhow::Result<()> {
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[reprice_pegged_orders_instruction(
+            &test_fixture.market_fixture.key,
+            trader,
+            &payer_keypair.pubkey(),
+        )],
+        Some(&payer_keypair.pubkey()),
+        &[payer_keypair],
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn pegged_order_reprice_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let payer: Pubkey = payer_keypair.pubkey();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let market: Pubkey = test_fixture.market_fixture.key;
+
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 100 * USDC_UNIT_SIZE)
+        .await?;
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 1_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 10 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            8,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            12,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    // Join the best bid with one SOL, up to 0.10, placed in the same
+    // transaction.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[
+            place_pegged_order_instruction(
+                &market,
+                &payer,
+                SOL_UNIT_SIZE,
+                true,
+                PegReference::BestBid,
+                0,
+                10,
+                -2,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                None,
+            ),
+            reprice_pegged_orders_instruction(&market, &payer, &payer),
+        ],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    let orders: Vec<RestingOrder> = get_orders_of(&mut test_fixture, &payer).await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].get_price(), price(8));
+
+    // Follows the best bid up.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            9,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    reprice(&test_fixture, &payer, &second_keypair).await?;
+    let orders: Vec<RestingOrder> = get_orders_of(&mut test_fixture, &payer).await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].get_price(), price(9));
+    let order_sequence_number: u64 = orders[0].get_sequence_number();
+
+    // Left in place, keeping its priority, while the reference holds.
+    reprice(&test_fixture, &payer, &payer_keypair).await?;
+    let orders: Vec<RestingOrder> = get_orders_of(&mut test_fixture, &payer).await;
+    assert_eq!(orders[0].get_sequence_number(), order_sequence_number);
+
+    // Capped at the limit price.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            11,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    reprice(&test_fixture, &payer, &second_keypair).await?;
+    let orders: Vec<RestingOrder> = get_orders_of(&mut test_fixture, &payer).await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].get_price(), price(10));
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer)
+            .await,
+        0
+    );
+
+    // Only the trader can cancel it, which takes it off the book.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cancel_pegged_order_instruction(
+            &market,
+            &second_keypair.pubkey(),
+            0,
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cancel_pegged_order_instruction(&market, &payer, 0)],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert!(get_orders_of(&mut test_fixture, &payer).await.is_empty());
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer)
+            .await,
+        100 * USDC_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn pegged_order_fill_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let payer: Pubkey = payer_keypair.pubkey();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let market: Pubkey = test_fixture.market_fixture.key;
+
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 1_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 10 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    // Ask 10% over the mid, no lower than 0.05. Waits off the book while
+    // there is no mid.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[
+            place_pegged_order_instruction(
+                &market,
+                &payer,
+                SOL_UNIT_SIZE,
+                false,
+                PegReference::Mid,
+                1_000,
+                5,
+                -2,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                None,
+            ),
+            reprice_pegged_orders_instruction(&market, &payer, &payer),
+        ],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert!(get_orders_of(&mut test_fixture, &payer).await.is_empty());
+
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            8,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            12,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    reprice(&test_fixture, &payer, &second_keypair).await?;
+    let orders: Vec<RestingOrder> = get_orders_of(&mut test_fixture, &payer).await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].get_price(), price(11));
+    assert!(!orders[0].get_is_bid());
+
+    // Filled by a taker, after which the next reprice clears it.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            11,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+    assert!(get_orders_of(&mut test_fixture, &payer).await.is_empty());
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        0
+    );
+    reprice(&test_fixture, &payer, &payer_keypair).await?;
+    assert!(get_orders_of(&mut test_fixture, &payer).await.is_empty());
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cancel_pegged_order_instruction(&market, &payer, 0)],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
@@ -712,6 +712,63 @@ async fn post_only_fail_test() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn post_only_slide_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+
+    // Ask for 2@10
+    test_fixture.deposit(Token::SOL, 20 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            2 * SOL_UNIT_SIZE,
+            10,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 20_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    // Would match at 10, so it rests one step below instead.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            1 * SOL_UNIT_SIZE,
+            11,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::PostOnlySlide,
+            &second_keypair,
+        )
+        .await?;
+
+    let resting_orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(resting_orders.len(), 2);
+    let ten: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(10, 0).unwrap();
+    assert!(resting_orders[0].get_is_bid());
+    assert_eq!(
+        resting_orders[0].get_price(),
+        ten.checked_step(false).unwrap()
+    );
+    assert_eq!(
+        resting_orders[0].get_num_base_atoms().as_u64(),
+        SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        resting_orders[1].get_num_base_atoms().as_u64(),
+        2 * SOL_UNIT_SIZE
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn place_order_already_expired_test() -> anyhow::Result<()> {
     let mut test_fixture: TestFixture = TestFixture::new().await;
@@ -0,0 +1,192 @@
+use std::rc::Rc;
+
+use borsh::BorshDeserialize;
+use manifest::{
+    program::{
+        claim_points_instruction, get_seat_points::GetSeatPointsReturn, get_seat_points_instruction,
+    },
+    state::{
+        constants::POINTS_EPOCH_SLOTS, OrderType, TradingHours, NO_EXPIRATION_LAST_VALID_SLOT,
+    },
+};
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::{
+    send_tx_with_retry, MarketFixture, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE,
+};
+
+async fn get_seat_points(
+    test_fixture: &TestFixture,
+    trader: &Pubkey,
+) -> anyhow::Result<GetSeatPointsReturn> {
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let mut context = test_fixture.context.borrow_mut();
+    let blockhash = context.get_new_latest_blockhash().await?;
+    let tx: Transaction = Transaction::new_signed_with_payer(
+        &[get_seat_points_instruction(
+            &test_fixture.market_fixture.key,
+            trader,
+        )],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+        blockhash,
+    );
+    let return_data: Vec<u8> = context
+        .banks_client
+        .simulate_transaction(tx)
+        .await?
+        .simulation_details
+        .unwrap()
+        .return_data
+        .unwrap()
+        .data;
+    Ok(GetSeatPointsReturn::try_from_slice(&return_data)?)
+}
+
+async fn claim_points(test_fixture: &TestFixture, keypair: &Keypair) -> anyhow::Result<()> {
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[claim_points_instruction(
+            &test_fixture.market_fixture.key,
+            &keypair.pubkey(),
+            None,
+        )],
+        Some(&keypair.pubkey()),
+        &[keypair],
+    )
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn points_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.market_fixture = MarketFixture::new_with_points(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        0,
+        0,
+        2,
+        1,
+    )
+    .await;
+
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat().await?;
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture.deposit(Token::USDC, USDC_UNIT_SIZE).await?;
+
+    // Only seats that claimed once earn points.
+    claim_points(&test_fixture, &second_keypair).await?;
+
+    // 10^9 base atoms at 0.001 is 10^6 quote atoms.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE / 2,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    assert_eq!(
+        get_seat_points(&test_fixture, &second_keypair.pubkey()).await?,
+        GetSeatPointsReturn {
+            epoch: 0,
+            points: USDC_UNIT_SIZE,
+            claimable_epoch: 0,
+            claimable_points: 0,
+        }
+    );
+    assert_eq!(
+        get_seat_points(&test_fixture, &payer_keypair.pubkey()).await?,
+        GetSeatPointsReturn::default()
+    );
+
+    claim_points(&test_fixture, &payer_keypair).await?;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE / 2,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    assert_eq!(
+        get_seat_points(&test_fixture, &second_keypair.pubkey())
+            .await?
+            .points,
+        2 * USDC_UNIT_SIZE
+    );
+    assert_eq!(
+        get_seat_points(&test_fixture, &payer_keypair.pubkey())
+            .await?
+            .points,
+        USDC_UNIT_SIZE / 2
+    );
+
+    // Points of the epoch become claimable once it is over.
+    test_fixture
+        .context
+        .borrow_mut()
+        .warp_to_slot(POINTS_EPOCH_SLOTS as u64 + 1)?;
+    assert_eq!(
+        get_seat_points(&test_fixture, &second_keypair.pubkey()).await?,
+        GetSeatPointsReturn {
+            epoch: 1,
+            points: 0,
+            claimable_epoch: 0,
+            claimable_points: 2 * USDC_UNIT_SIZE,
+        }
+    );
+    claim_points(&test_fixture, &second_keypair).await?;
+    assert_eq!(
+        get_seat_points(&test_fixture, &second_keypair.pubkey()).await?,
+        GetSeatPointsReturn {
+            epoch: 1,
+            points: 0,
+            claimable_epoch: 0,
+            claimable_points: 0,
+        }
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn claim_points_without_points_test() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+
+    assert!(claim_points(&test_fixture, &test_fixture.payer_keypair())
+        .await
+        .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use std::rc::Rc;
+
+use manifest::{
+    program::reset_price_band_instruction,
+    state::{OrderType, TradingHours, NO_EXPIRATION_LAST_VALID_SLOT, PRICE_BAND_RESET_SLOTS},
+};
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_program_test::{tokio, BanksClientError};
+use solana_signer::Signer;
+
+use crate::{
+    send_tx_with_retry, MarketFixture, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE,
+};
+
+async fn buy(
+    test_fixture: &mut TestFixture,
+    keypair: &Keypair,
+    num_sol: u64,
+    price_mantissa: u32,
+) -> anyhow::Result<(), BanksClientError> {
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            num_sol * SOL_UNIT_SIZE,
+            price_mantissa,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            keypair,
+        )
+        .await
+}
+
+#[tokio::test]
+async fn price_band_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.market_fixture = MarketFixture::new_with_price_band(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        0,
+        0,
+        0,
+        0,
+        &[],
+        0,
+        0,
+        500,
+    )
+    .await;
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .get_price_band()
+            .unwrap()
+            .get_band_bps(),
+        500
+    );
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 3 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .deposit(Token::USDC, 10_000 * USDC_UNIT_SIZE)
+        .await?;
+    for price_mantissa in [100, 104, 110] {
+        test_fixture
+            .place_order(
+                Side::Ask,
+                SOL_UNIT_SIZE,
+                price_mantissa,
+                -2,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                OrderType::Limit,
+            )
+            .await?;
+    }
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 10_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    // Anything fills before the first trade.
+    buy(&mut test_fixture, &second_keypair, 1, 100).await?;
+
+    // 1.10 is more than 5% from the last trade at 1.00, so sweeping both asks
+    // fails as a whole.
+    assert!(buy(&mut test_fixture, &second_keypair, 2, 110)
+        .await
+        .is_err());
+    buy(&mut test_fixture, &second_keypair, 1, 104).await?;
+    // Still more than 5% from 1.04.
+    assert!(buy(&mut test_fixture, &second_keypair, 1, 110)
+        .await
+        .is_err());
+
+    // The reference can only be reset after the market has been quiet.
+    let reset_price_band_ix: Instruction =
+        reset_price_band_instruction(&test_fixture.market_fixture.key, &second_keypair.pubkey());
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[reset_price_band_ix.clone()],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+    test_fixture
+        .advance_time_seconds(2 * PRICE_BAND_RESET_SLOTS as i64)
+        .await;
+    // It needs both sides of the book.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[reset_price_band_ix.clone()],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            106,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    // Resets to 1.08, the middle of 1.06 and 1.10.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[reset_price_band_ix],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    buy(&mut test_fixture, &second_keypair, 1, 110).await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&second_keypair.pubkey())
+            .await,
+        3 * SOL_UNIT_SIZE
+    );
+
+    Ok(())
+}
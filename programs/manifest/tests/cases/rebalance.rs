@@ -0,0 +1,177 @@
+use std::rc::Rc;
+
+use manifest::{
+    program::{rebalance_instruction, set_seat_config_instruction},
+    state::{OrderType, NO_EXPIRATION_LAST_VALID_SLOT},
+};
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{send_tx_with_retry, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+#[tokio::test]
+async fn rebalance_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let delegate_keypair: Keypair = Keypair::new();
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let payer: Pubkey = payer_keypair.pubkey();
+
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 1_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    // Bid at 0.10 and ask at 0.11.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            10,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            11,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    // Trades above 1 quote atom need the cosigner next to the trader.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_seat_config_instruction(
+            &market,
+            &payer,
+            &delegate_keypair.pubkey(),
+            1,
+            None,
+            None,
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    // The cosigner cannot rebalance the seat on its own.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[rebalance_instruction(
+            &market,
+            &delegate_keypair.pubkey(),
+            SOL_UNIT_SIZE,
+            100,
+            Some(&payer),
+        )],
+        Some(&payer),
+        &[&payer_keypair, &delegate_keypair],
+    )
+    .await
+    .is_err());
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[rebalance_instruction(
+            &market,
+            &payer,
+            SOL_UNIT_SIZE,
+            100,
+            None,
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    // Sells the base over the target into the bid.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[rebalance_instruction(
+            &market,
+            &payer,
+            SOL_UNIT_SIZE,
+            100,
+            Some(&delegate_keypair.pubkey()),
+        )],
+        Some(&payer),
+        &[&payer_keypair, &delegate_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer)
+            .await,
+        100 * USDC_UNIT_SIZE
+    );
+
+    // Below the threshold the trader rebalances alone. Buying up to 2 SOL at
+    // most 1% above 0.11 is limited to the 900_090_009 base atoms the quote
+    // on the seat pays for at 0.1111, which fill at 0.11.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_seat_config_instruction(
+            &market,
+            &payer,
+            &delegate_keypair.pubkey(),
+            u64::MAX,
+            None,
+            Some(&delegate_keypair.pubkey()),
+        )],
+        Some(&payer),
+        &[&payer_keypair, &delegate_keypair],
+    )
+    .await?;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[rebalance_instruction(
+            &market,
+            &payer,
+            2 * SOL_UNIT_SIZE,
+            100,
+            None,
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        SOL_UNIT_SIZE + 900_090_009
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer)
+            .await,
+        100 * USDC_UNIT_SIZE - 99_009_901
+    );
+
+    Ok(())
+}
@@ -0,0 +1,388 @@
+use std::rc::Rc;
+
+use hypertree::DataIndex;
+use manifest::{
+    program::{
+        archive_seat_instruction, batch_update::PlaceOrderParams,
+        cancel_on_heartbeat_timeout_instruction, enter_recovery_instruction,
+        global_clean_instruction, heartbeat_instruction, recovery_withdraw_instruction,
+        restore_seat_instruction,
+    },
+    quantities::{BaseAtoms, QuoteAtoms},
+    state::{
+        seat_archive_leaf, seat_archive_proof, OrderType, SeatArchiveNode, MARKET_BLOCK_SIZE,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    },
+    validation::get_vault_address,
+};
+use solana_account::{Account, AccountSharedData};
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_program::{bpf_loader_upgradeable, program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{send_tx_with_retry, Side, TestFixture, Token, TokenAccountFixture, SOL_UNIT_SIZE};
+
+fn set_upgrade_authority(test_fixture: &TestFixture, authority: &Pubkey) {
+    let (program_data, _) =
+        Pubkey::find_program_address(&[manifest::id().as_ref()], &bpf_loader_upgradeable::id());
+    let mut program_data_bytes: Vec<u8> = vec![3, 0, 0, 0];
+    program_data_bytes.extend_from_slice(&0_u64.to_le_bytes());
+    program_data_bytes.push(1);
+    program_data_bytes.extend_from_slice(authority.as_ref());
+    test_fixture.context.borrow_mut().set_account(
+        &program_data,
+        &AccountSharedData::from(Account {
+            lamports: 1_000_000_000,
+            data: program_data_bytes,
+            owner: bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        }),
+    );
+}
+
+/// Sets the base vault of the market to hold vault_atoms.
+async fn set_base_vault_atoms(test_fixture: &TestFixture, vault_atoms: u64) -> anyhow::Result<()> {
+    let (base_vault, _) = get_vault_address(
+        &test_fixture.market_fixture.key,
+        &test_fixture.sol_mint_fixture.key,
+    );
+    let mut base_vault_account: Account = test_fixture
+        .context
+        .borrow_mut()
+        .banks_client
+        .get_account(base_vault)
+        .await?
+        .unwrap();
+    let mut vault_token_account: spl_token::state::Account =
+        spl_token::state::Account::unpack(&base_vault_account.data)?;
+    vault_token_account.amount = vault_atoms;
+    vault_token_account.pack_into_slice(&mut base_vault_account.data);
+    test_fixture
+        .context
+        .borrow_mut()
+        .set_account(&base_vault, &AccountSharedData::from(base_vault_account));
+    Ok(())
+}
+
+#[tokio::test]
+async fn recovery_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let sol_mint: Pubkey = test_fixture.sol_mint_fixture.key;
+
+    // The payer has 6 SOL on the seat and 4 SOL in an ask, the second trader
+    // has 10 SOL on the seat.
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 10 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            4 * SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 10 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    // The second keypair is the upgrade authority of the program.
+    set_upgrade_authority(&test_fixture, &second_keypair.pubkey());
+
+    // Withdraws are normal until the market enters recovery.
+    let recovery_withdraw_ix: Instruction = recovery_withdraw_instruction(
+        &market,
+        &payer_keypair.pubkey(),
+        &sol_mint,
+        &test_fixture.payer_sol_fixture.key,
+        spl_token::id(),
+    );
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[recovery_withdraw_ix.clone()],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    // Only the upgrade authority can enter recovery.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[enter_recovery_instruction(&market, &payer_keypair.pubkey())],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[enter_recovery_instruction(
+            &market,
+            &second_keypair.pubkey(),
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    test_fixture.market_fixture.reload().await;
+    assert!(test_fixture.market_fixture.market.fixed.is_in_recovery());
+
+    // Everything else is frozen.
+    assert!(test_fixture
+        .deposit(Token::SOL, SOL_UNIT_SIZE)
+        .await
+        .is_err());
+    assert!(test_fixture
+        .withdraw(Token::SOL, SOL_UNIT_SIZE)
+        .await
+        .is_err());
+    assert!(test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await
+        .is_err());
+
+    // Half of the base vault is lost, so each trader gets half of their 10 SOL.
+    set_base_vault_atoms(&test_fixture, 10 * SOL_UNIT_SIZE).await?;
+
+    let payer_sol_atoms: u64 = test_fixture.payer_sol_fixture.balance_atoms().await;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[recovery_withdraw_ix.clone()],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        payer_sol_atoms + 5 * SOL_UNIT_SIZE
+    );
+    // The seat and the ask are zeroed.
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer_keypair.pubkey())
+            .await,
+        0
+    );
+
+    let second_sol_fixture: TokenAccountFixture = TokenAccountFixture::new(
+        Rc::clone(&test_fixture.context),
+        &sol_mint,
+        &second_keypair.pubkey(),
+    )
+    .await;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[recovery_withdraw_instruction(
+            &market,
+            &second_keypair.pubkey(),
+            &sol_mint,
+            &second_sol_fixture.key,
+            spl_token::id(),
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    assert_eq!(second_sol_fixture.balance_atoms().await, 5 * SOL_UNIT_SIZE);
+
+    // Nothing is owed on a second withdraw.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[recovery_withdraw_ix],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        payer_sol_atoms + 5 * SOL_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn recovery_restore_seat_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let payer: Pubkey = payer_keypair.pubkey();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let sol_mint: Pubkey = test_fixture.sol_mint_fixture.key;
+
+    // The payer archives a seat with 10 SOL, the second trader has 10 SOL on
+    // the seat.
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 10 * SOL_UNIT_SIZE).await?;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[archive_seat_instruction(&market, &payer, &payer)],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 10 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    set_upgrade_authority(&test_fixture, &second_keypair.pubkey());
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[enter_recovery_instruction(
+            &market,
+            &second_keypair.pubkey(),
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    set_base_vault_atoms(&test_fixture, 10 * SOL_UNIT_SIZE).await?;
+
+    // Archived seats are not scanned, so they are restored to be owed their
+    // share.
+    let leaves: Vec<SeatArchiveNode> = vec![seat_archive_leaf(
+        &payer,
+        BaseAtoms::new(10 * SOL_UNIT_SIZE),
+        QuoteAtoms::new(0),
+        QuoteAtoms::new(0),
+    )];
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[restore_seat_instruction(
+            &market,
+            &payer,
+            &payer,
+            10 * SOL_UNIT_SIZE,
+            0,
+            0,
+            0,
+            seat_archive_proof(&leaves, 0),
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    let payer_sol_atoms: u64 = test_fixture.payer_sol_fixture.balance_atoms().await;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[recovery_withdraw_instruction(
+            &market,
+            &payer,
+            &sol_mint,
+            &test_fixture.payer_sol_fixture.key,
+            spl_token::id(),
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        payer_sol_atoms + 5 * SOL_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn recovery_cranks_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let payer: Pubkey = payer_keypair.pubkey();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let market: Pubkey = test_fixture.market_fixture.key;
+
+    // The payer has a global bid whose funds are withdrawn, so anyone could
+    // clean it, and a heartbeat that is about to time out.
+    test_fixture.claim_seat().await?;
+    test_fixture.global_add_trader().await?;
+    test_fixture.global_deposit(1_000_000).await?;
+    test_fixture
+        .batch_update_with_global_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                100,
+                1,
+                0,
+                true,
+                OrderType::Global,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &payer_keypair,
+        )
+        .await?;
+    test_fixture.global_withdraw(1_000_000).await?;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[heartbeat_instruction(&market, &payer, 10, 1_000_000)],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    set_upgrade_authority(&test_fixture, &second_keypair.pubkey());
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[enter_recovery_instruction(
+            &market,
+            &second_keypair.pubkey(),
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    test_fixture.advance_time_seconds(40).await;
+
+    // Permissionless cranks cannot move orders of a market in recovery either.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cancel_on_heartbeat_timeout_instruction(
+            &market,
+            &second_keypair.pubkey(),
+            &payer,
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[global_clean_instruction(
+            &test_fixture.global_fixture.key,
+            &second_keypair.pubkey(),
+            &market,
+            MARKET_BLOCK_SIZE as DataIndex,
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        1
+    );
+
+    Ok(())
+}
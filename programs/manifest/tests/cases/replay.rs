@@ -0,0 +1,355 @@
+//! Replay files let a bug report carry the exact sequence of operations that
+//! led to a market state, instead of a hand written test. They are produced by
+//! `scripts/manifest-cli.ts replay` from mainnet history or written by hand,
+//! and every file in `tests/testdata/replays` runs as part of this suite.
+//!
+//! A replay is a JSON object:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "description": "what the replay reproduces",
+//!   "traders": 2,
+//!   "operations": [
+//!     { "op": "claimSeat", "trader": 0 },
+//!     { "op": "deposit", "trader": 0, "token": "base", "atoms": 1000 },
+//!     { "op": "placeOrder", "trader": 0, "side": "ask", "baseAtoms": 1000,
+//!       "priceMantissa": 10, "priceExponent": 0, "lastValidSlot": 0,
+//!       "orderType": "Limit" },
+//!     { "op": "swap", "trader": 1, "inAtoms": 5000, "outAtoms": 0,
+//!       "isBaseIn": false, "isExactIn": true },
+//!     { "op": "cancelOrder", "trader": 0, "order": 2 },
+//!     { "op": "advanceSlots", "slots": 10 },
+//!     { "op": "withdraw", "trader": 0, "token": "quote", "atoms": 5000 },
+//!     { "op": "expectBalance", "trader": 0, "baseAtoms": 500,
+//!       "quoteAtoms": 0 }
+//!   ]
+//! }
+//! ```
+//!
+//! Traders are numbered from zero. Trader 0 is the test payer and the others
+//! are fresh wallets funded by it. Tokens are minted to the trader right
+//! before a deposit or swap needs them, so a replay only lists what happened
+//! on the market. `cancelOrder` names the order by the index of the
+//! `placeOrder` operation that placed it, since sequence numbers depend on
+//! everything that came before. An order can give `"expiresInSlots"` instead
+//! of `"lastValidSlot"` to expire that many slots after it is placed. Any operation may set `"shouldFail": true`
+//! when the transaction is expected to be rejected. Amounts may be JSON
+//! numbers or decimal strings, for values that do not fit in a double.
+//!
+//! Global orders are not supported, they need a global account per trader.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use hypertree::DataIndex;
+use manifest::{
+    program::{
+        batch_update::{CancelOrderParams, PlaceOrderParams},
+        swap_instruction,
+    },
+    state::OrderType,
+};
+use solana_clock::Clock;
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_program::{pubkey::Pubkey, system_instruction::transfer};
+use solana_program_test::{tokio, BanksClientError, ProgramTestContext};
+use solana_signer::Signer;
+
+use crate::{send_tx_with_retry, TestFixture, Token, TokenAccountFixture};
+
+const REPLAY_VERSION: u64 = 1;
+const REPLAY_DIRECTORY: &str = "tests/testdata/replays";
+const TRADER_LAMPORTS: u64 = 1_000_000_000;
+
+fn get_u64(operation: &serde_json::Value, key: &str) -> u64 {
+    let value: &serde_json::Value = &operation[key];
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+        .unwrap_or_else(|| panic!("{key} should be an unsigned integer in {operation}"))
+}
+
+fn get_bool(operation: &serde_json::Value, key: &str) -> bool {
+    operation[key]
+        .as_bool()
+        .unwrap_or_else(|| panic!("{key} should be a bool in {operation}"))
+}
+
+fn get_str<'a>(operation: &'a serde_json::Value, key: &str) -> &'a str {
+    operation[key]
+        .as_str()
+        .unwrap_or_else(|| panic!("{key} should be a string in {operation}"))
+}
+
+fn get_token(operation: &serde_json::Value) -> Token {
+    match get_str(operation, "token") {
+        "base" => Token::SOL,
+        "quote" => Token::USDC,
+        token => panic!("unknown token {token}"),
+    }
+}
+
+fn get_order_type(operation: &serde_json::Value) -> OrderType {
+    match get_str(operation, "orderType") {
+        "Limit" => OrderType::Limit,
+        "ImmediateOrCancel" => OrderType::ImmediateOrCancel,
+        "PostOnly" => OrderType::PostOnly,
+        "Reverse" => OrderType::Reverse,
+        "ReverseTight" => OrderType::ReverseTight,
+        order_type => panic!("unsupported order type {order_type}"),
+    }
+}
+
+async fn get_slot(context: &Rc<RefCell<ProgramTestContext>>) -> u64 {
+    let clock: Clock = context
+        .borrow_mut()
+        .banks_client
+        .get_sysvar()
+        .await
+        .unwrap();
+    clock.slot
+}
+
+async fn advance_slots(context: &Rc<RefCell<ProgramTestContext>>, slots: u64) {
+    let mut clock: Clock = context
+        .borrow_mut()
+        .banks_client
+        .get_sysvar()
+        .await
+        .unwrap();
+    clock.slot += slots;
+    context.borrow_mut().set_sysvar(&clock);
+}
+
+async fn swap_for_keypair(
+    test_fixture: &mut TestFixture,
+    operation: &serde_json::Value,
+    keypair: &Keypair,
+) -> anyhow::Result<(), BanksClientError> {
+    let in_atoms: u64 = get_u64(operation, "inAtoms");
+    let is_base_in: bool = get_bool(operation, "isBaseIn");
+    let mut token_accounts: Vec<Pubkey> = Vec::new();
+    for mint in [
+        test_fixture.sol_mint_fixture.key,
+        test_fixture.usdc_mint_fixture.key,
+    ] {
+        let token_account_fixture: TokenAccountFixture =
+            TokenAccountFixture::new(Rc::clone(&test_fixture.context), &mint, &keypair.pubkey())
+                .await;
+        token_accounts.push(token_account_fixture.key);
+    }
+    if is_base_in {
+        test_fixture
+            .sol_mint_fixture
+            .mint_to(&token_accounts[0], in_atoms)
+            .await;
+    } else {
+        test_fixture
+            .usdc_mint_fixture
+            .mint_to(&token_accounts[1], in_atoms)
+            .await;
+    }
+
+    let swap_ix: Instruction = swap_instruction(
+        &test_fixture.market_fixture.key,
+        &keypair.pubkey(),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        &token_accounts[0],
+        &token_accounts[1],
+        in_atoms,
+        get_u64(operation, "outAtoms"),
+        is_base_in,
+        get_bool(operation, "isExactIn"),
+        spl_token::id(),
+        spl_token::id(),
+        false,
+    );
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[swap_ix],
+        Some(&keypair.pubkey()),
+        &[keypair],
+    )
+    .await
+}
+
+/// Run a replay against a fresh market and check its expectations.
+pub async fn replay(replay_file: &serde_json::Value) -> anyhow::Result<()> {
+    assert_eq!(
+        get_u64(replay_file, "version"),
+        REPLAY_VERSION,
+        "unsupported replay version"
+    );
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+
+    let mut traders: Vec<Keypair> = vec![payer_keypair.insecure_clone()];
+    for _ in 1..get_u64(replay_file, "traders") {
+        let keypair: Keypair = Keypair::new();
+        send_tx_with_retry(
+            Rc::clone(&test_fixture.context),
+            &[transfer(&payer, &keypair.pubkey(), TRADER_LAMPORTS)],
+            Some(&payer),
+            &[&payer_keypair],
+        )
+        .await?;
+        traders.push(keypair);
+    }
+
+    // Index of the placeOrder operation to the sequence number of the order
+    // it rested.
+    let mut sequence_numbers: HashMap<u64, u64> = HashMap::new();
+    let operations: &Vec<serde_json::Value> = replay_file["operations"]
+        .as_array()
+        .expect("operations should be an array");
+    for (index, operation) in operations.iter().enumerate() {
+        let trader: &Keypair = &traders[operation["trader"].as_u64().unwrap_or(0) as usize];
+        let result: anyhow::Result<(), BanksClientError> = match get_str(operation, "op") {
+            "claimSeat" => test_fixture.claim_seat_for_keypair(trader).await,
+            "deposit" => {
+                test_fixture
+                    .deposit_for_keypair(get_token(operation), get_u64(operation, "atoms"), trader)
+                    .await
+            }
+            "withdraw" => {
+                test_fixture
+                    .withdraw_for_keypair(get_token(operation), get_u64(operation, "atoms"), trader)
+                    .await
+            }
+            "placeOrder" => {
+                // Expirations from mainnet are kept relative to the slot the
+                // order was placed in.
+                let last_valid_slot: u32 = if operation["expiresInSlots"].is_null() {
+                    get_u64(operation, "lastValidSlot") as u32
+                } else {
+                    (get_slot(&test_fixture.context).await + get_u64(operation, "expiresInSlots"))
+                        as u32
+                };
+                let resting_before: HashSet<u64> = test_fixture
+                    .market_fixture
+                    .get_resting_orders()
+                    .await
+                    .iter()
+                    .map(|order| order.get_sequence_number())
+                    .collect();
+                let result: anyhow::Result<(), BanksClientError> = test_fixture
+                    .batch_update_for_keypair(
+                        None,
+                        vec![],
+                        vec![PlaceOrderParams::new(
+                            get_u64(operation, "baseAtoms"),
+                            get_u64(operation, "priceMantissa") as u32,
+                            operation["priceExponent"]
+                                .as_i64()
+                                .expect("priceExponent should be an integer")
+                                as i8,
+                            get_str(operation, "side") == "bid",
+                            get_order_type(operation),
+                            last_valid_slot,
+                        )],
+                        trader,
+                    )
+                    .await;
+                // The order claims its sequence number before any reverse
+                // orders it triggers, so it is the lowest new one of its
+                // trader.
+                let trader_index: DataIndex = test_fixture
+                    .market_fixture
+                    .market
+                    .get_trader_index(&trader.pubkey());
+                if let Some(sequence_number) = test_fixture
+                    .market_fixture
+                    .get_resting_orders()
+                    .await
+                    .iter()
+                    .filter(|order| {
+                        order.get_trader_index() == trader_index
+                            && !resting_before.contains(&order.get_sequence_number())
+                    })
+                    .map(|order| order.get_sequence_number())
+                    .min()
+                {
+                    sequence_numbers.insert(index as u64, sequence_number);
+                }
+                result
+            }
+            "cancelOrder" => {
+                let order: u64 = get_u64(operation, "order");
+                let sequence_number: u64 = *sequence_numbers
+                    .get(&order)
+                    .unwrap_or_else(|| panic!("operation {order} did not rest an order"));
+                test_fixture
+                    .batch_update_for_keypair(
+                        None,
+                        vec![CancelOrderParams::new(sequence_number)],
+                        vec![],
+                        trader,
+                    )
+                    .await
+            }
+            "swap" => swap_for_keypair(&mut test_fixture, operation, trader).await,
+            "advanceSlots" => {
+                advance_slots(&test_fixture.context, get_u64(operation, "slots")).await;
+                Ok(())
+            }
+            "expectBalance" => {
+                let trader: Pubkey = trader.pubkey();
+                assert_eq!(
+                    test_fixture
+                        .market_fixture
+                        .get_base_balance_atoms(&trader)
+                        .await,
+                    get_u64(operation, "baseAtoms"),
+                    "base balance at operation {index}"
+                );
+                assert_eq!(
+                    test_fixture
+                        .market_fixture
+                        .get_quote_balance_atoms(&trader)
+                        .await,
+                    get_u64(operation, "quoteAtoms"),
+                    "quote balance at operation {index}"
+                );
+                Ok(())
+            }
+            op => panic!("unknown operation {op}"),
+        };
+
+        let should_fail: bool = operation["shouldFail"].as_bool().unwrap_or(false);
+        assert_eq!(
+            result.is_err(),
+            should_fail,
+            "operation {index} {operation}: {result:?}"
+        );
+    }
+
+    let trader_keys: Vec<Pubkey> = traders.iter().map(|keypair| keypair.pubkey()).collect();
+    test_fixture
+        .market_fixture
+        .verify_vault_balance(&trader_keys, false)
+        .await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_testdata_test() -> anyhow::Result<()> {
+    let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir(REPLAY_DIRECTORY)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    paths.sort();
+    for path in paths {
+        let file: std::fs::File = std::fs::File::open(&path)?;
+        let json: serde_json::Value =
+            serde_json::from_reader(file).expect("replay should be proper JSON");
+        println!("Replaying {}", path.display());
+        replay(&json).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use std::rc::Rc;
+
+use manifest::{
+    program::{
+        batch_update::PlaceOrderParams, batch_update_instruction, set_seat_config_instruction,
+    },
+    state::{OrderType, NO_EXPIRATION_LAST_VALID_SLOT},
+};
+use solana_keypair::Keypair;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{send_tx_with_retry, TestFixture, Token, SOL_UNIT_SIZE};
+
+fn ask(base_atoms: u64) -> PlaceOrderParams {
+    PlaceOrderParams::new(
+        base_atoms,
+        1,
+        0,
+        false,
+        OrderType::Limit,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    )
+}
+
+#[tokio::test]
+async fn cosigned_batch_update_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 10 * SOL_UNIT_SIZE).await?;
+
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let payer: Pubkey = payer_keypair.pubkey();
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let cosigner_keypair: Keypair = Keypair::new();
+    let cosigner: Pubkey = cosigner_keypair.pubkey();
+
+    // Price is 1 quote atom per base atom, so the threshold is 2 SOL of asks.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_seat_config_instruction(
+            &market,
+            &payer,
+            &cosigner,
+            2 * SOL_UNIT_SIZE,
+            None,
+            None,
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    // Below the threshold does not need the cosigner.
+    test_fixture
+        .batch_update_for_keypair(None, vec![], vec![ask(SOL_UNIT_SIZE)], &payer_keypair)
+        .await?;
+
+    // Above the threshold fails without the cosigner.
+    assert!(test_fixture
+        .batch_update_for_keypair(None, vec![], vec![ask(3 * SOL_UNIT_SIZE)], &payer_keypair)
+        .await
+        .is_err());
+
+    // Notional is summed across all orders in the batch.
+    assert!(test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![ask(SOL_UNIT_SIZE), ask(SOL_UNIT_SIZE), ask(SOL_UNIT_SIZE)],
+            &payer_keypair
+        )
+        .await
+        .is_err());
+
+    // Succeeds with the cosigner appended after the other accounts.
+    let mut cosigned_ix: Instruction = batch_update_instruction(
+        &market,
+        &payer,
+        None,
+        vec![],
+        vec![ask(3 * SOL_UNIT_SIZE)],
+        None,
+        None,
+        None,
+        None,
+    );
+    cosigned_ix
+        .accounts
+        .push(AccountMeta::new_readonly(cosigner, true));
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cosigned_ix],
+        Some(&payer),
+        &[&payer_keypair, &cosigner_keypair],
+    )
+    .await?;
+
+    // The wrong key does not count as the cosigner.
+    let other_keypair: Keypair = Keypair::new();
+    let mut wrong_cosigner_ix: Instruction = batch_update_instruction(
+        &market,
+        &payer,
+        None,
+        vec![],
+        vec![ask(3 * SOL_UNIT_SIZE)],
+        None,
+        None,
+        None,
+        None,
+    );
+    wrong_cosigner_ix
+        .accounts
+        .push(AccountMeta::new_readonly(other_keypair.pubkey(), true));
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[wrong_cosigner_ix],
+        Some(&payer),
+        &[&payer_keypair, &other_keypair],
+    )
+    .await
+    .is_err());
+
+    // The owner alone cannot remove the cosigner.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_seat_config_instruction(
+            &market,
+            &payer,
+            &Pubkey::default(),
+            0,
+            None,
+            None,
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_seat_config_instruction(
+            &market,
+            &payer,
+            &Pubkey::default(),
+            0,
+            None,
+            Some(&cosigner),
+        )],
+        Some(&payer),
+        &[&payer_keypair, &cosigner_keypair],
+    )
+    .await?;
+
+    test_fixture
+        .batch_update_for_keypair(None, vec![], vec![ask(3 * SOL_UNIT_SIZE)], &payer_keypair)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn set_seat_config_requires_seat_test() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let payer: Pubkey = payer_keypair.pubkey();
+
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[set_seat_config_instruction(
+            &test_fixture.market_fixture.key,
+            &payer,
+            &Pubkey::new_unique(),
+            0,
+            None,
+            None,
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,342 @@
+use std::rc::Rc;
+
+use manifest::{
+    program::{
+        batch_update::PlaceOrderParams, cancel_stop_order_instruction,
+        place_stop_order_instruction, place_trailing_stop_order_instruction,
+        trigger_stop_orders_instruction,
+    },
+    state::{constants::NO_EXPIRATION_LAST_VALID_SLOT, OrderType},
+};
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{send_tx_with_retry, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+#[tokio::test]
+async fn stop_order_trigger_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let market: Pubkey = test_fixture.market_fixture.key;
+
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 1_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    // Bid at 0.08 that the trades below hit.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            2 * SOL_UNIT_SIZE,
+            8,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE / 10,
+            8,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+
+    // Stop loss selling one SOL down to 0.05 once the price is at most 0.09.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[place_stop_order_instruction(
+            &market,
+            &payer_keypair.pubkey(),
+            PlaceOrderParams::new(
+                SOL_UNIT_SIZE,
+                5,
+                -2,
+                false,
+                OrderType::ImmediateOrCancel,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            ),
+            9,
+            -2,
+            None,
+        )],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    // The trade before the stop order was placed does not trigger it.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[trigger_stop_orders_instruction(
+            &market,
+            &payer_keypair.pubkey(),
+            &second_keypair.pubkey(),
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer_keypair.pubkey())
+            .await,
+        2 * SOL_UNIT_SIZE - SOL_UNIT_SIZE / 10
+    );
+
+    test_fixture.advance_time_seconds(10).await;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE / 10,
+            8,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+
+    // Anyone can trigger it once a later trade reaches the trigger price.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[trigger_stop_orders_instruction(
+            &market,
+            &payer_keypair.pubkey(),
+            &second_keypair.pubkey(),
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer_keypair.pubkey())
+            .await,
+        2 * SOL_UNIT_SIZE - SOL_UNIT_SIZE / 10 - SOL_UNIT_SIZE / 10 - SOL_UNIT_SIZE
+    );
+
+    // Triggered orders are removed.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cancel_stop_order_instruction(
+            &market,
+            &payer_keypair.pubkey(),
+            0,
+        )],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn stop_order_cancel_test() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let place_stop_order_ix = |order_type: OrderType| {
+        place_stop_order_instruction(
+            &market,
+            &payer_keypair.pubkey(),
+            PlaceOrderParams::new(
+                SOL_UNIT_SIZE,
+                11,
+                -2,
+                true,
+                order_type,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            ),
+            1,
+            -1,
+            None,
+        )
+    };
+
+    // Needs a seat.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[place_stop_order_ix(OrderType::Limit)],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    test_fixture.claim_seat().await?;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[place_stop_order_ix(OrderType::PostOnly)],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[place_stop_order_ix(OrderType::Limit)],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cancel_stop_order_instruction(
+            &market,
+            &payer_keypair.pubkey(),
+            0,
+        )],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[cancel_stop_order_instruction(
+            &market,
+            &payer_keypair.pubkey(),
+            0,
+        )],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn trailing_stop_order_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let market: Pubkey = test_fixture.market_fixture.key;
+
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 3 * SOL_UNIT_SIZE).await?;
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 1_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    // Sell one SOL down to 0.05 once the price falls 10% from its high. The
+    // trigger starts at 0.05, which the trades below never reach.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[place_trailing_stop_order_instruction(
+            &market,
+            &payer_keypair.pubkey(),
+            PlaceOrderParams::new(
+                SOL_UNIT_SIZE,
+                5,
+                -2,
+                false,
+                OrderType::ImmediateOrCancel,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            ),
+            5,
+            -2,
+            1_000,
+            None,
+        )],
+        Some(&payer_keypair.pubkey()),
+        &[&payer_keypair],
+    )
+    .await?;
+    test_fixture.advance_time_seconds(10).await;
+
+    let trigger_ix =
+        trigger_stop_orders_instruction(&market, &payer_keypair.pubkey(), &second_keypair.pubkey());
+    // Trade at 0.10 trails the trigger up to 0.09.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE / 10,
+            10,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE / 10,
+            10,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[trigger_ix.clone()],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer_keypair.pubkey())
+            .await,
+        3 * SOL_UNIT_SIZE - SOL_UNIT_SIZE / 10
+    );
+
+    // Trade at 0.09 reaches the trailed trigger.
+    test_fixture.advance_time_seconds(10).await;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            2 * SOL_UNIT_SIZE,
+            9,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE / 10,
+            9,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[trigger_ix],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer_keypair.pubkey())
+            .await,
+        3 * SOL_UNIT_SIZE - SOL_UNIT_SIZE / 10 - SOL_UNIT_SIZE / 10 - SOL_UNIT_SIZE
+    );
+
+    Ok(())
+}
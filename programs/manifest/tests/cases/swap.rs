@@ -4,24 +4,31 @@ use borsh::BorshSerialize;
 use manifest::{
     program::{
         batch_update::{CancelOrderParams, PlaceOrderParams},
-        batch_update_instruction, expand_market_instruction, global_add_trader_instruction,
-        global_deposit_instruction, global_withdraw_instruction, swap_instruction,
-        ManifestInstruction, SwapParams,
+        batch_update_instruction, commit_swap_instruction, expand_market_n_instruction,
+        global_add_trader_instruction, global_deposit_instruction, global_withdraw_instruction,
+        reveal_swap_instruction,
+        swap::SWAP_SKIP_GLOBAL_ORDERS,
+        swap_instruction, swap_to_seat_instruction, with_create_token_accounts,
+        with_min_fill_atoms, with_swap_flags, ManifestInstruction, SwapParams,
     },
     quantities::{BaseAtoms, WrapperU64},
-    state::{constants::NO_EXPIRATION_LAST_VALID_SLOT, OrderType, RestingOrder},
-    validation::get_vault_address,
+    state::{
+        constants::NO_EXPIRATION_LAST_VALID_SLOT, OrderType, RestingOrder, TradingHours,
+        MARKET_FLAG_NO_INTERNAL_CROSS,
+    },
+    validation::{get_vault_address, SPL_MEMO_PROGRAM_ID},
 };
 use solana_instruction::Instruction;
 use solana_keypair::Keypair;
-use solana_program::instruction::AccountMeta;
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
 use solana_program_test::{tokio, ProgramTestContext};
 use solana_signer::Signer;
 use solana_transaction::Transaction;
+use spl_associated_token_account::get_associated_token_address;
 
 use crate::{
-    send_tx_with_retry, Side, TestFixture, Token, TokenAccountFixture, SOL_UNIT_SIZE,
-    USDC_UNIT_SIZE,
+    get_and_deserialize, send_tx_with_retry, MarketFixture, Side, TestFixture, Token,
+    TokenAccountFixture, SOL_UNIT_SIZE, USDC_UNIT_SIZE,
 };
 
 #[tokio::test]
@@ -529,6 +536,300 @@ async fn swap_already_has_deposits() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn swap_to_seat_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 1 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            1 * SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 1_000 * USDC_UNIT_SIZE)
+        .await;
+
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair().insecure_clone();
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[swap_to_seat_instruction(
+            &test_fixture.market_fixture.key,
+            &payer,
+            &payer,
+            &test_fixture.sol_mint_fixture.key,
+            &test_fixture.usdc_mint_fixture.key,
+            &test_fixture.payer_sol_fixture.key,
+            &test_fixture.payer_usdc_fixture.key,
+            1_000 * USDC_UNIT_SIZE,
+            1 * SOL_UNIT_SIZE,
+            false,
+            false,
+            spl_token::id(),
+            spl_token::id(),
+            false,
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    // Quote came from the wallet, base stayed on the seat.
+    assert_eq!(test_fixture.payer_sol_fixture.balance_atoms().await, 0);
+    assert_eq!(test_fixture.payer_usdc_fixture.balance_atoms().await, 0);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        1 * SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer)
+            .await,
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn swap_min_fill_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 2 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    // Dust ask ahead of the real one at the same price.
+    for num_base_atoms in [SOL_UNIT_SIZE / 10, 1 * SOL_UNIT_SIZE] {
+        test_fixture
+            .place_order_for_keypair(
+                Side::Ask,
+                num_base_atoms,
+                1,
+                0,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                OrderType::Limit,
+                &second_keypair,
+            )
+            .await?;
+    }
+
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 1_000 * USDC_UNIT_SIZE)
+        .await;
+
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair().insecure_clone();
+    let swap_ix: Instruction = with_min_fill_atoms(
+        swap_instruction(
+            &test_fixture.market_fixture.key,
+            &payer,
+            &test_fixture.sol_mint_fixture.key,
+            &test_fixture.usdc_mint_fixture.key,
+            &test_fixture.payer_sol_fixture.key,
+            &test_fixture.payer_usdc_fixture.key,
+            1_000 * USDC_UNIT_SIZE,
+            1 * SOL_UNIT_SIZE,
+            false,
+            false,
+            spl_token::id(),
+            spl_token::id(),
+            false,
+        ),
+        SOL_UNIT_SIZE / 2,
+    );
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[swap_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    // Filled entirely against the larger ask, the dust ask is untouched.
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        1 * SOL_UNIT_SIZE
+    );
+    assert_eq!(test_fixture.payer_usdc_fixture.balance_atoms().await, 0);
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(
+        orders[0].get_num_base_atoms(),
+        BaseAtoms::new(SOL_UNIT_SIZE / 10)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn swap_fill_memo_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 1 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            1 * SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 1_000 * USDC_UNIT_SIZE)
+        .await;
+
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair().insecure_clone();
+    let mut swap_ix: Instruction = swap_instruction(
+        &test_fixture.market_fixture.key,
+        &payer,
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        &test_fixture.payer_sol_fixture.key,
+        &test_fixture.payer_usdc_fixture.key,
+        1_000 * USDC_UNIT_SIZE,
+        1 * SOL_UNIT_SIZE,
+        false,
+        false,
+        spl_token::id(),
+        spl_token::id(),
+        false,
+    );
+    swap_ix
+        .accounts
+        .push(AccountMeta::new_readonly(SPL_MEMO_PROGRAM_ID, false));
+
+    let mut context: RefMut<ProgramTestContext> = test_fixture.context.borrow_mut();
+    let swap_tx: Transaction = Transaction::new_signed_with_payer(
+        &[swap_ix],
+        Some(&payer),
+        &[&payer_keypair],
+        context.get_new_latest_blockhash().await?,
+    );
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(swap_tx)
+        .await?;
+    assert!(result.result.is_ok());
+
+    // Memo names the maker so wallets that index memos can pick it up.
+    let log_messages: Vec<String> = result.metadata.unwrap().log_messages;
+    let expected_maker: String = format!("maker:{}", second_keypair.pubkey());
+    assert!(log_messages
+        .iter()
+        .any(|log| log.contains("Memo") && log.contains(&expected_maker)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn swap_create_output_token_account_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 1 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            1 * SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 1_000 * USDC_UNIT_SIZE)
+        .await;
+
+    // Buy SOL into an associated token account that does not exist yet.
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair().insecure_clone();
+    let payer_sol_ata: Pubkey =
+        get_associated_token_address(&payer, &test_fixture.sol_mint_fixture.key);
+    let swap_ix = || {
+        with_create_token_accounts(
+            swap_instruction(
+                &test_fixture.market_fixture.key,
+                &payer,
+                &test_fixture.sol_mint_fixture.key,
+                &test_fixture.usdc_mint_fixture.key,
+                &payer_sol_ata,
+                &test_fixture.payer_usdc_fixture.key,
+                500 * USDC_UNIT_SIZE,
+                SOL_UNIT_SIZE / 2,
+                false,
+                false,
+                spl_token::id(),
+                spl_token::id(),
+                false,
+            ),
+            &test_fixture.sol_mint_fixture.key,
+            &test_fixture.usdc_mint_fixture.key,
+        )
+    };
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[swap_ix()],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    let payer_sol_ata_account: spl_token::state::Account =
+        get_and_deserialize(Rc::clone(&test_fixture.context), payer_sol_ata).await;
+    assert_eq!(payer_sol_ata_account.amount, SOL_UNIT_SIZE / 2);
+
+    // Creation is idempotent, the same instruction works again.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[swap_ix()],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    let payer_sol_ata_account: spl_token::state::Account =
+        get_and_deserialize(Rc::clone(&test_fixture.context), payer_sol_ata).await;
+    assert_eq!(payer_sol_ata_account.amount, SOL_UNIT_SIZE);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn swap_fail_limit_test() -> anyhow::Result<()> {
     let mut test_fixture: TestFixture = TestFixture::new().await;
@@ -1014,68 +1315,215 @@ async fn swap_global() -> anyhow::Result<()> {
     Ok(())
 }
 
-// This test case illustrates that the exact in is really just a desired in.
+// Global bid ahead of a limit bid. Without the global accounts the taker stops
+// at the global order, unless it skips global orders.
 #[tokio::test]
-async fn swap_full_match_sell_exact_in_exhaust_book() -> anyhow::Result<()> {
+async fn swap_skip_global_orders() -> anyhow::Result<()> {
     let mut test_fixture: TestFixture = TestFixture::new().await;
 
     let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
     test_fixture.claim_seat_for_keypair(&second_keypair).await?;
-    test_fixture
-        .deposit_for_keypair(Token::USDC, 3_000 * USDC_UNIT_SIZE, &second_keypair)
-        .await?;
 
-    // 2 bids for 1@1 and 2@.5
     send_tx_with_retry(
         Rc::clone(&test_fixture.context),
-        &[batch_update_instruction(
-            &test_fixture.market_fixture.key,
+        &[global_add_trader_instruction(
+            &test_fixture.global_fixture.key,
             &second_keypair.pubkey(),
-            None,
-            vec![],
-            vec![
-                PlaceOrderParams::new(
-                    1 * SOL_UNIT_SIZE,
-                    1,
-                    0,
-                    true,
-                    OrderType::Limit,
-                    NO_EXPIRATION_LAST_VALID_SLOT,
-                ),
-                PlaceOrderParams::new(
-                    2 * SOL_UNIT_SIZE,
-                    5,
-                    -1,
-                    true,
-                    OrderType::Limit,
-                    NO_EXPIRATION_LAST_VALID_SLOT,
-                ),
-            ],
-            None,
-            None,
-            Some(*test_fixture.market_fixture.market.get_quote_mint()),
-            None,
         )],
         Some(&second_keypair.pubkey()),
         &[&second_keypair],
     )
     .await?;
-    // Swapper will exact_in of 4, min quote out of 2. Result should be that it
-    // succeeds. It will not be able to fully fill all the exact in of 4 and
-    // there will be 1 leftover and it gets out 1*1 + 2*.5 = 2 quote.
-    test_fixture
-        .sol_mint_fixture
-        .mint_to(&test_fixture.payer_sol_fixture.key, 4 * SOL_UNIT_SIZE)
-        .await;
 
+    let token_account_keypair: Keypair = Keypair::new();
+    let token_account_fixture: TokenAccountFixture = TokenAccountFixture::new_with_keypair(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.global_fixture.mint_key,
+        &second_keypair.pubkey(),
+        &token_account_keypair,
+    )
+    .await;
     test_fixture
-        .swap(4 * SOL_UNIT_SIZE, 2_000 * USDC_UNIT_SIZE, true, true)
-        .await?;
-
-    assert_eq!(
-        test_fixture.payer_sol_fixture.balance_atoms().await,
-        1 * SOL_UNIT_SIZE
-    );
+        .usdc_mint_fixture
+        .mint_to(&token_account_fixture.key, 1 * SOL_UNIT_SIZE)
+        .await;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[global_deposit_instruction(
+            &test_fixture.global_fixture.mint_key,
+            &second_keypair.pubkey(),
+            &token_account_fixture.key,
+            &spl_token::id(),
+            1 * SOL_UNIT_SIZE,
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+
+    let batch_update_ix: Instruction = batch_update_instruction(
+        &test_fixture.market_fixture.key,
+        &second_keypair.pubkey(),
+        None,
+        vec![],
+        vec![PlaceOrderParams::new(
+            1 * SOL_UNIT_SIZE,
+            1,
+            0,
+            true,
+            OrderType::Global,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+        )],
+        None,
+        None,
+        Some(*test_fixture.market_fixture.market.get_quote_mint()),
+        None,
+    );
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[batch_update_ix],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 500 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            1 * SOL_UNIT_SIZE,
+            5,
+            -1,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    test_fixture
+        .sol_mint_fixture
+        .mint_to(&test_fixture.payer_sol_fixture.key, 1 * SOL_UNIT_SIZE)
+        .await;
+
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair().insecure_clone();
+    let swap_ix = |flags: u8| {
+        with_swap_flags(
+            swap_instruction(
+                &test_fixture.market_fixture.key,
+                &payer,
+                &test_fixture.sol_mint_fixture.key,
+                &test_fixture.usdc_mint_fixture.key,
+                &test_fixture.payer_sol_fixture.key,
+                &test_fixture.payer_usdc_fixture.key,
+                1 * SOL_UNIT_SIZE,
+                500 * USDC_UNIT_SIZE,
+                true,
+                true,
+                spl_token::id(),
+                spl_token::id(),
+                false,
+            ),
+            0,
+            flags,
+        )
+    };
+
+    // Stops at the global bid, so nothing comes out.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[swap_ix(0)],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[swap_ix(SWAP_SKIP_GLOBAL_ORDERS)],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    // Filled against the limit bid, the global bid is untouched.
+    assert_eq!(test_fixture.payer_sol_fixture.balance_atoms().await, 0);
+    assert_eq!(
+        test_fixture.payer_usdc_fixture.balance_atoms().await,
+        500 * USDC_UNIT_SIZE
+    );
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert!(orders[0].is_global());
+
+    Ok(())
+}
+
+// This test case illustrates that the exact in is really just a desired in.
+#[tokio::test]
+async fn swap_full_match_sell_exact_in_exhaust_book() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 3_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    // 2 bids for 1@1 and 2@.5
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[batch_update_instruction(
+            &test_fixture.market_fixture.key,
+            &second_keypair.pubkey(),
+            None,
+            vec![],
+            vec![
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    1,
+                    0,
+                    true,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+                PlaceOrderParams::new(
+                    2 * SOL_UNIT_SIZE,
+                    5,
+                    -1,
+                    true,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+            ],
+            None,
+            None,
+            Some(*test_fixture.market_fixture.market.get_quote_mint()),
+            None,
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    // Swapper will exact_in of 4, min quote out of 2. Result should be that it
+    // succeeds. It will not be able to fully fill all the exact in of 4 and
+    // there will be 1 leftover and it gets out 1*1 + 2*.5 = 2 quote.
+    test_fixture
+        .sol_mint_fixture
+        .mint_to(&test_fixture.payer_sol_fixture.key, 4 * SOL_UNIT_SIZE)
+        .await;
+
+    test_fixture
+        .swap(4 * SOL_UNIT_SIZE, 2_000 * USDC_UNIT_SIZE, true, true)
+        .await?;
+
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        1 * SOL_UNIT_SIZE
+    );
     assert_eq!(
         test_fixture.payer_usdc_fixture.balance_atoms().await,
         2_000 * USDC_UNIT_SIZE
@@ -1285,16 +1733,14 @@ async fn swap_wash_reverse_test() -> anyhow::Result<()> {
     // new reversed order.
     let payer = test_fixture.payer();
     let payer_keypair = test_fixture.payer_keypair();
-    for _ in 0..10 {
-        let expand_ix = expand_market_instruction(&test_fixture.market_fixture.key, &payer);
-        send_tx_with_retry(
-            Rc::clone(&test_fixture.context),
-            &[expand_ix],
-            Some(&payer),
-            &[&payer_keypair],
-        )
-        .await?;
-    }
+    let expand_ix = expand_market_n_instruction(&test_fixture.market_fixture.key, &payer, 10);
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[expand_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
 
     // Mint tokens to payer's external wallet for swapping
     test_fixture
@@ -1458,6 +1904,317 @@ async fn swap_wash_reverse_test() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn swap_wash_reverse_no_internal_cross_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.market_fixture = MarketFixture::new_with_flags(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        MARKET_FLAG_NO_INTERNAL_CROSS,
+    )
+    .await;
+
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::SOL, 100 * SOL_UNIT_SIZE)
+        .await?;
+    test_fixture
+        .deposit(Token::USDC, 100_000 * USDC_UNIT_SIZE)
+        .await?;
+
+    // Same reverse quotes as swap_wash_reverse_test, one level per side.
+    test_fixture
+        .place_order(
+            Side::Bid,
+            5 * SOL_UNIT_SIZE,
+            10,
+            0,
+            10_000,
+            OrderType::Reverse,
+        )
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            5 * SOL_UNIT_SIZE,
+            12,
+            0,
+            10_000,
+            OrderType::Reverse,
+        )
+        .await?;
+
+    test_fixture
+        .sol_mint_fixture
+        .mint_to(&test_fixture.payer_sol_fixture.key, 20 * SOL_UNIT_SIZE)
+        .await;
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 200 * USDC_UNIT_SIZE)
+        .await;
+
+    // Swapping against its own reverse orders is rejected on both sides.
+    assert!(test_fixture
+        .swap(60 * USDC_UNIT_SIZE, 0, false, true)
+        .await
+        .is_err());
+    assert!(test_fixture
+        .swap(SOL_UNIT_SIZE, 0, true, true)
+        .await
+        .is_err());
+
+    // So is a crossing limit order from the same seat.
+    assert!(test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            12,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await
+        .is_err());
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        2
+    );
+
+    // Another seat still trades against the same order.
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    // Price is 12 quote atoms per base atom.
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 12 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            12,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&second_keypair.pubkey())
+            .await,
+        SOL_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn trading_hours_test() -> anyhow::Result<()> {
+    // Monday 2024-01-01 00:00 UTC
+    const MONDAY: i64 = 1_704_067_200;
+    const SECONDS_PER_DAY: i64 = 86_400;
+
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    // Weekdays from 14:30 to 21:00 UTC.
+    test_fixture.market_fixture = MarketFixture::new_with_trading_hours(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::new(0b0011111, 870, 1_260),
+    )
+    .await;
+
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 20 * USDC_UNIT_SIZE)
+        .await;
+
+    // Monday before the open.
+    test_fixture.set_unix_timestamp(MONDAY + 800 * 60).await;
+    assert!(test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await
+        .is_err());
+
+    // Monday during trading hours.
+    test_fixture.set_unix_timestamp(MONDAY + 900 * 60).await;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            2 * SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    test_fixture.swap(USDC_UNIT_SIZE, 0, false, true).await?;
+
+    // Saturday, swaps and new orders fail but the maker can still cancel.
+    test_fixture
+        .set_unix_timestamp(MONDAY + 5 * SECONDS_PER_DAY + 900 * 60)
+        .await;
+    assert!(test_fixture
+        .swap(USDC_UNIT_SIZE, 0, false, true)
+        .await
+        .is_err());
+    assert!(test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await
+        .is_err());
+    test_fixture.cancel_order(0).await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn taker_delay_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.market_fixture = MarketFixture::new_with_taker_delay(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        0,
+        4,
+    )
+    .await;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 2 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    // Makers can only place orders that do not take.
+    assert!(test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await
+        .is_err());
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            2 * SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::PostOnly,
+            &second_keypair,
+        )
+        .await?;
+
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 2 * USDC_UNIT_SIZE)
+        .await;
+    assert!(test_fixture
+        .swap(USDC_UNIT_SIZE, 0, false, true)
+        .await
+        .is_err());
+
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let salt: [u8; 32] = [7; 32];
+    let swap_ix: Instruction = swap_instruction(
+        &test_fixture.market_fixture.key,
+        &payer,
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        &test_fixture.payer_sol_fixture.key,
+        &test_fixture.payer_usdc_fixture.key,
+        USDC_UNIT_SIZE,
+        0,
+        false,
+        true,
+        spl_token::id(),
+        spl_token::id(),
+        false,
+    );
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[commit_swap_instruction(
+            &test_fixture.market_fixture.key,
+            &payer,
+            &swap_ix,
+            &salt,
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    let reveal_swap_ix: Instruction =
+        reveal_swap_instruction(&test_fixture.market_fixture.key, &payer, &swap_ix, &salt);
+    // Too early.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[reveal_swap_ix.clone()],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    // 8 seconds is 4 slots.
+    test_fixture.advance_time_seconds(8).await;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[reveal_swap_ix.clone()],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    // One quote atom per base atom.
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        USDC_UNIT_SIZE
+    );
+
+    // The commitment is used up.
+    test_fixture.advance_time_seconds(8).await;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[reveal_swap_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
+
 // This test is no longer valid because of change in how sequence numbers are
 // assigned. When there is a limit taker going through a reverse
 // Previously
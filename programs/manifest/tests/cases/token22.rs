@@ -3,7 +3,8 @@ use std::{cell::RefCell, rc::Rc, u64};
 use manifest::{
     program::{
         batch_update::PlaceOrderParams, batch_update_instruction, claim_seat_instruction,
-        create_market_instructions, deposit_instruction, swap_instruction, withdraw_instruction,
+        create_market_instructions, deposit_instruction, swap::SWAP_EXACT_OUT_NET_OF_FEES,
+        swap_instruction, with_swap_flags, withdraw_instruction,
     },
     quantities::WrapperU64,
     state::{OrderType, NO_EXPIRATION_LAST_VALID_SLOT},
@@ -11,12 +12,19 @@ use manifest::{
 use solana_instruction::Instruction;
 use solana_keypair::Keypair;
 use solana_program::{
-    program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction::create_account,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::{create_account, transfer},
 };
 use solana_program_test::{ProgramTest, ProgramTestContext};
 use solana_signer::Signer;
 
-use crate::{manifest_program_test, send_tx_with_retry, MintFixture, RUST_LOG_DEFAULT};
+use crate::{
+    create_market_with_mints, create_spl_token_account, create_token_2022_account,
+    get_token_account_balance, manifest_program_test, mint_token_2022, send_tx_with_retry,
+    MintFixture, RUST_LOG_DEFAULT,
+};
 
 #[tokio::test]
 async fn token22_base() -> anyhow::Result<()> {
@@ -1484,3 +1492,161 @@ async fn token22_transfer_fee_zero_to_nonzero() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn token22_swap_exact_out_net_of_fees() -> anyhow::Result<()> {
+    let program_test: ProgramTest = manifest_program_test();
+    solana_logger::setup_with_default(RUST_LOG_DEFAULT);
+
+    let context: Rc<RefCell<ProgramTestContext>> =
+        Rc::new(RefCell::new(program_test.start_with_context().await));
+    let payer_keypair: Keypair = context.borrow().payer.insecure_clone();
+    let payer: &Pubkey = &payer_keypair.pubkey();
+    let taker_keypair: Keypair = Keypair::new();
+    let taker: &Pubkey = &taker_keypair.pubkey();
+
+    // Base is Token-2022 with a 10% transfer fee, quote is a normal mint.
+    let mut usdc_mint_f: MintFixture =
+        MintFixture::new_with_version(Rc::clone(&context), Some(6), false).await;
+    let base_mint_f: MintFixture =
+        MintFixture::new_with_transfer_fee(Rc::clone(&context), 9, 1_000).await;
+    let base_mint_key: Pubkey = base_mint_f.key;
+    let market_keypair: Keypair =
+        create_market_with_mints(Rc::clone(&context), &base_mint_key, &usdc_mint_f.key).await?;
+
+    // Maker asks 5_000_000_000 base at 0.001 quote atoms per base atom.
+    let maker_base_keypair: Keypair =
+        create_token_2022_account(Rc::clone(&context), &base_mint_key, payer).await?;
+    mint_token_2022(
+        Rc::clone(&context),
+        &base_mint_key,
+        &maker_base_keypair.pubkey(),
+        10_000_000_000,
+    )
+    .await?;
+    send_tx_with_retry(
+        Rc::clone(&context),
+        &[
+            claim_seat_instruction(&market_keypair.pubkey(), payer),
+            deposit_instruction(
+                &market_keypair.pubkey(),
+                payer,
+                &base_mint_key,
+                10_000_000_000,
+                &maker_base_keypair.pubkey(),
+                spl_token_2022::id(),
+                None,
+            ),
+            batch_update_instruction(
+                &market_keypair.pubkey(),
+                payer,
+                None,
+                vec![],
+                vec![PlaceOrderParams::new(
+                    5_000_000_000,
+                    1,
+                    -3,
+                    false,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                )],
+                None,
+                None,
+                None,
+                None,
+            ),
+        ],
+        Some(payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    let taker_base_keypair: Keypair =
+        create_token_2022_account(Rc::clone(&context), &base_mint_key, taker).await?;
+    let taker_quote_keypair: Keypair =
+        create_spl_token_account(Rc::clone(&context), &usdc_mint_f.key, taker).await?;
+    usdc_mint_f
+        .mint_to(&taker_quote_keypair.pubkey(), 100_000_000)
+        .await;
+    send_tx_with_retry(
+        Rc::clone(&context),
+        &[transfer(payer, taker, 1_000_000_000)],
+        Some(payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    let swap_ix = |in_atoms: u64, out_atoms: u64, is_exact_in: bool| -> Instruction {
+        with_swap_flags(
+            swap_instruction(
+                &market_keypair.pubkey(),
+                taker,
+                &base_mint_key,
+                &usdc_mint_f.key,
+                &taker_base_keypair.pubkey(),
+                &taker_quote_keypair.pubkey(),
+                in_atoms,
+                out_atoms,
+                false,
+                is_exact_in,
+                spl_token_2022::id(),
+                spl_token::id(),
+                false,
+            ),
+            0,
+            SWAP_EXACT_OUT_NET_OF_FEES,
+        )
+    };
+
+    // Fails without the base mint since the fee would be unknown.
+    let mut no_mint_ix: Instruction = swap_ix(2_000_000, 1_000_000_000, false);
+    no_mint_ix
+        .accounts
+        .retain(|meta| meta.pubkey != base_mint_key);
+    assert!(send_tx_with_retry(
+        Rc::clone(&context),
+        &[no_mint_ix],
+        Some(payer),
+        &[&payer_keypair, &taker_keypair],
+    )
+    .await
+    .is_err());
+
+    // Only valid for exact out.
+    assert!(send_tx_with_retry(
+        Rc::clone(&context),
+        &[swap_ix(2_000_000, 1_000_000_000, true)],
+        Some(payer),
+        &[&payer_keypair, &taker_keypair],
+    )
+    .await
+    .is_err());
+
+    // More than the book has after fees fails instead of under delivering.
+    assert!(send_tx_with_retry(
+        Rc::clone(&context),
+        &[swap_ix(20_000_000, 6_000_000_000, false)],
+        Some(payer),
+        &[&payer_keypair, &taker_keypair],
+    )
+    .await
+    .is_err());
+
+    // The market gives enough extra that the fee is covered.
+    send_tx_with_retry(
+        Rc::clone(&context),
+        &[swap_ix(2_000_000, 1_000_000_000, false)],
+        Some(payer),
+        &[&payer_keypair, &taker_keypair],
+    )
+    .await?;
+    let received: u64 =
+        get_token_account_balance(Rc::clone(&context), taker_base_keypair.pubkey()).await;
+    assert!(
+        received >= 1_000_000_000,
+        "Received {} after transfer fees",
+        received
+    );
+
+    Ok(())
+}
@@ -0,0 +1,370 @@
+use std::rc::Rc;
+
+use manifest::{
+    program::{bust_trade::BustTradeParams, bust_trade_instruction, claim_fees_instruction},
+    quantities::{BaseAtoms, QuoteAtoms},
+    state::{
+        MarketExtension, MarketFees, OrderType, TradingHours, MARKET_EXTENSION_FLAG_FILL_RECORDS,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    },
+};
+use solana_account::{Account, AccountSharedData};
+use solana_clock::Clock;
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_program::{bpf_loader_upgradeable, pubkey::Pubkey};
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{
+    send_tx_with_retry, MarketFixture, Side, TestFixture, Token, TokenAccountFixture,
+    SOL_UNIT_SIZE, USDC_UNIT_SIZE,
+};
+
+/// Market with fill records on which the second keypair, the upgrade
+/// authority, sold 1 SOL to the payer. Returns the slot of the fill.
+async fn setup_fill(test_fixture: &mut TestFixture, fee_recipient: &Pubkey) -> anyhow::Result<u32> {
+    test_fixture.market_fixture = MarketFixture::new_with_extension(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        0,
+        TradingHours::default(),
+        0,
+        0,
+        0,
+        0,
+        &[],
+        0,
+        0,
+        0,
+        10,
+        30,
+        0,
+        fee_recipient,
+        0,
+        &Pubkey::default(),
+        MARKET_EXTENSION_FLAG_FILL_RECORDS,
+    )
+    .await;
+    let payer: Pubkey = test_fixture.payer();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+
+    // The second keypair is the upgrade authority of the program.
+    let (program_data, _) =
+        Pubkey::find_program_address(&[manifest::id().as_ref()], &bpf_loader_upgradeable::id());
+    let mut program_data_bytes: Vec<u8> = vec![3, 0, 0, 0];
+    program_data_bytes.extend_from_slice(&0_u64.to_le_bytes());
+    program_data_bytes.push(1);
+    program_data_bytes.extend_from_slice(second_keypair.pubkey().as_ref());
+    test_fixture.context.borrow_mut().set_account(
+        &program_data,
+        &AccountSharedData::from(Account {
+            lamports: 1_000_000_000,
+            data: program_data_bytes,
+            owner: bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        }),
+    );
+
+    // The second keypair asks 1 SOL at 1 quote atom per base atom, order
+    // sequence number 0, and the payer buys it, sequence number 1.
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 1_000 * USDC_UNIT_SIZE)
+        .await?;
+    let fill_slot: u64 = test_fixture
+        .context
+        .borrow_mut()
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await?
+        .slot;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        997_000_000
+    );
+
+    Ok(fill_slot as u32)
+}
+
+#[tokio::test]
+async fn trade_bust_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let fill_slot: u32 = setup_fill(&mut test_fixture, &Pubkey::new_unique()).await?;
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let market: Pubkey = test_fixture.market_fixture.key;
+
+    let bust_trade_params = |quote_atoms: u64| {
+        BustTradeParams::new(
+            second_keypair.pubkey(),
+            payer,
+            SOL_UNIT_SIZE,
+            quote_atoms,
+            true,
+            0,
+            1,
+            30,
+            false,
+            fill_slot,
+        )
+    };
+
+    // Only the upgrade authority can bust.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[bust_trade_instruction(
+            &market,
+            &payer,
+            bust_trade_params(SOL_UNIT_SIZE)
+        )],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await
+    .is_err());
+
+    // Only the fill as it happened can be busted, not one made up from it.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[bust_trade_instruction(
+            &market,
+            &second_keypair.pubkey(),
+            bust_trade_params(2 * SOL_UNIT_SIZE)
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+
+    let bust_trade_ix: Instruction = bust_trade_instruction(
+        &market,
+        &second_keypair.pubkey(),
+        bust_trade_params(SOL_UNIT_SIZE),
+    );
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[bust_trade_ix.clone()],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+
+    // Both seats are back to their deposits and the fees are refunded.
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        0
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer)
+            .await,
+        1_000 * USDC_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&second_keypair.pubkey())
+            .await,
+        SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&second_keypair.pubkey())
+            .await,
+        0
+    );
+    test_fixture.market_fixture.reload().await;
+    let market_fees: MarketFees = *test_fixture
+        .market_fixture
+        .market
+        .get_market_fees()
+        .unwrap();
+    assert_eq!(market_fees.get_base_fee_atoms(), BaseAtoms::ZERO);
+    assert_eq!(market_fees.get_quote_fee_atoms(), QuoteAtoms::ZERO);
+
+    // And the fill no longer counts toward either volume.
+    assert_eq!(
+        test_fixture.market_fixture.get_quote_volume(&payer).await,
+        0
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_volume(&second_keypair.pubkey())
+            .await,
+        0
+    );
+    assert_eq!(
+        test_fixture.market_fixture.market.fixed.get_quote_volume(),
+        0
+    );
+    test_fixture
+        .market_fixture
+        .verify_vault_balance(&[payer, second_keypair.pubkey()], true)
+        .await;
+
+    // A fill can only be busted once.
+    test_fixture.advance_time_seconds(1).await;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[bust_trade_ix],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn trade_bust_after_claim_fees_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let fee_recipient: Pubkey = Pubkey::new_unique();
+    let fill_slot: u32 = setup_fill(&mut test_fixture, &fee_recipient).await?;
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let sol_mint: Pubkey = test_fixture.sol_mint_fixture.key;
+    let usdc_mint: Pubkey = test_fixture.usdc_mint_fixture.key;
+
+    // Anyone can claim the fees of the fill before it is busted.
+    let recipient_sol_fixture: TokenAccountFixture =
+        TokenAccountFixture::new(Rc::clone(&test_fixture.context), &sol_mint, &fee_recipient).await;
+    let recipient_usdc_fixture: TokenAccountFixture =
+        TokenAccountFixture::new(Rc::clone(&test_fixture.context), &usdc_mint, &fee_recipient)
+            .await;
+    let claim_fees_ixs: Vec<Instruction> = vec![
+        claim_fees_instruction(
+            &market,
+            &payer,
+            &sol_mint,
+            &recipient_sol_fixture.key,
+            spl_token::id(),
+        ),
+        claim_fees_instruction(
+            &market,
+            &payer,
+            &usdc_mint,
+            &recipient_usdc_fixture.key,
+            spl_token::id(),
+        ),
+    ];
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &claim_fees_ixs,
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert_eq!(recipient_sol_fixture.balance_atoms().await, 3_000_000);
+    assert_eq!(recipient_usdc_fixture.balance_atoms().await, 1_000_000);
+
+    // The bust still goes through and the claimed fees are owed back.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[bust_trade_instruction(
+            &market,
+            &second_keypair.pubkey(),
+            BustTradeParams::new(
+                second_keypair.pubkey(),
+                payer,
+                SOL_UNIT_SIZE,
+                SOL_UNIT_SIZE,
+                true,
+                0,
+                1,
+                30,
+                false,
+                fill_slot,
+            ),
+        )],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        0
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&second_keypair.pubkey())
+            .await,
+        0
+    );
+    test_fixture.market_fixture.reload().await;
+    let market_extension: MarketExtension = *test_fixture
+        .market_fixture
+        .market
+        .get_market_extension()
+        .unwrap();
+    assert_eq!(
+        market_extension.get_base_fees_owed_atoms(),
+        BaseAtoms::new(3_000_000)
+    );
+    assert_eq!(
+        market_extension.get_quote_fees_owed_atoms(),
+        QuoteAtoms::new(1_000_000)
+    );
+    test_fixture
+        .market_fixture
+        .verify_vault_balance(&[payer, second_keypair.pubkey()], true)
+        .await;
+
+    // Claims pay nothing until fees accrued since cover what is owed.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &claim_fees_ixs,
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+    assert_eq!(recipient_sol_fixture.balance_atoms().await, 3_000_000);
+    assert_eq!(recipient_usdc_fixture.balance_atoms().await, 1_000_000);
+
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use std::rc::Rc;
+
+use manifest::{
+    program::update_volatility_instruction,
+    quantities::{QuoteAtomsPerBaseAtom, WrapperU64},
+    state::{
+        OrderType, RestingOrder, DYNAMIC_REVERSE_SPREAD, NO_EXPIRATION_LAST_VALID_SLOT,
+        VOLATILITY_SAMPLE_SLOTS,
+    },
+    validation::get_market_volatility_address,
+};
+use solana_keypair::Keypair;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_signer::Signer;
+
+use crate::{send_tx_with_retry, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+#[tokio::test]
+async fn dynamic_reverse_spread_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let market: Pubkey = test_fixture.market_fixture.key;
+    let (market_volatility, _) = get_market_volatility_address(&market);
+
+    // A limit ask at 1.00 and reverse asks at 1.10 and 1.20 that flip at the
+    // market volatility.
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 3 * SOL_UNIT_SIZE).await?;
+    for (price_mantissa, order_type) in [
+        (100, OrderType::Limit),
+        (110, OrderType::Reverse),
+        (120, OrderType::Reverse),
+    ] {
+        test_fixture
+            .place_order(
+                Side::Ask,
+                SOL_UNIT_SIZE,
+                price_mantissa,
+                -2,
+                if order_type == OrderType::Reverse {
+                    DYNAMIC_REVERSE_SPREAD as u32
+                } else {
+                    NO_EXPIRATION_LAST_VALID_SLOT
+                },
+                order_type,
+            )
+            .await?;
+    }
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 10_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    // Nothing to sample before the first trade.
+    let update_volatility_ix = update_volatility_instruction(&market, &second_keypair.pubkey());
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[update_volatility_ix.clone()],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            100,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+
+    // The first sample only records the price, and the next has to wait.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[update_volatility_ix.clone()],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    assert!(test_fixture.try_load(&market_volatility).await?.is_some());
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .fixed
+            .get_volatility_bps(),
+        0
+    );
+
+    // Flips at no spread while the estimate is zero.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            110,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[update_volatility_ix.clone()],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await
+    .is_err());
+
+    // A 10% move averages in as an eighth, 125 bps.
+    test_fixture
+        .advance_time_seconds(2 * VOLATILITY_SAMPLE_SLOTS as i64)
+        .await;
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[update_volatility_ix],
+        Some(&second_keypair.pubkey()),
+        &[&second_keypair],
+    )
+    .await?;
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .fixed
+            .get_volatility_bps(),
+        125
+    );
+
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            120,
+            -2,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+
+    // Bids first, best first.
+    let resting_orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(resting_orders.len(), 2);
+    assert!(resting_orders[0].get_is_bid());
+    assert_eq!(
+        resting_orders[0].get_price(),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(1_185, -3).unwrap()
+    );
+    assert_eq!(
+        resting_orders[0].get_reverse_spread(),
+        DYNAMIC_REVERSE_SPREAD
+    );
+    assert_eq!(
+        resting_orders[1].get_price(),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(110, -2).unwrap()
+    );
+    assert!(resting_orders[1].get_num_base_atoms().as_u64() > 0);
+
+    Ok(())
+}
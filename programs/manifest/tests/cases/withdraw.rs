@@ -1,7 +1,13 @@
 use std::rc::Rc;
 
 use borsh::BorshSerialize;
-use manifest::program::{withdraw::WithdrawParams, withdraw_instruction, ManifestInstruction};
+use manifest::{
+    program::{
+        withdraw::WithdrawParams, withdraw_as_quote_instruction, withdraw_instruction,
+        ManifestInstruction,
+    },
+    state::{constants::NO_EXPIRATION_LAST_VALID_SLOT, OrderType},
+};
 use solana_instruction::Instruction;
 use solana_keypair::Keypair;
 use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
@@ -9,7 +15,10 @@ use solana_program_test::{tokio, ProgramTestContext};
 use solana_signer::Signer;
 use solana_transaction::Transaction;
 
-use crate::{MintFixture, TestFixture, Token, TokenAccountFixture, SOL_UNIT_SIZE};
+use crate::{
+    send_tx_with_retry, MintFixture, Side, TestFixture, Token, TokenAccountFixture, SOL_UNIT_SIZE,
+    USDC_UNIT_SIZE,
+};
 
 #[tokio::test]
 async fn withdraw_test() -> anyhow::Result<()> {
@@ -37,6 +46,139 @@ async fn withdraw_quote_test() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn withdraw_to_other_wallet_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+
+    // Destination is owned by a different wallet than the seat.
+    let cold_storage: Pubkey = test_fixture.second_keypair.pubkey();
+    let destination: TokenAccountFixture = TokenAccountFixture::new(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.sol_mint_fixture.key,
+        &cold_storage,
+    )
+    .await;
+
+    let payer: &Pubkey = &test_fixture.payer().clone();
+    let payer_keypair: &Keypair = &test_fixture.payer_keypair().insecure_clone();
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[withdraw_instruction(
+            &test_fixture.market_fixture.key,
+            payer,
+            &test_fixture.sol_mint_fixture.key,
+            SOL_UNIT_SIZE,
+            &destination.key,
+            spl_token::id(),
+            None,
+        )],
+        Some(payer),
+        &[payer_keypair],
+    )
+    .await?;
+
+    assert_eq!(destination.balance_atoms().await, SOL_UNIT_SIZE);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(payer)
+            .await,
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn withdraw_as_quote_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+    test_fixture.deposit(Token::USDC, USDC_UNIT_SIZE).await?;
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 1_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    // Half a SOL bid at 0.10 and half at 0.08.
+    for price_mantissa in [10, 8] {
+        test_fixture
+            .place_order_for_keypair(
+                Side::Bid,
+                SOL_UNIT_SIZE / 2,
+                price_mantissa,
+                -2,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                OrderType::Limit,
+                &second_keypair,
+            )
+            .await?;
+    }
+
+    let destination: TokenAccountFixture = TokenAccountFixture::new(
+        Rc::clone(&test_fixture.context),
+        &test_fixture.usdc_mint_fixture.key,
+        &test_fixture.payer(),
+    )
+    .await;
+    let payer: &Pubkey = &test_fixture.payer().clone();
+    let payer_keypair: &Keypair = &test_fixture.payer_keypair().insecure_clone();
+    let withdraw_as_quote_ix = |trader_token_account: &Pubkey| {
+        withdraw_as_quote_instruction(
+            &test_fixture.market_fixture.key,
+            payer,
+            &test_fixture.usdc_mint_fixture.key,
+            1_000,
+            trader_token_account,
+            spl_token::id(),
+            None,
+        )
+    };
+
+    // Only to a quote token account.
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[withdraw_as_quote_ix(&test_fixture.payer_sol_fixture.key)],
+        Some(payer),
+        &[payer_keypair],
+    )
+    .await
+    .is_err());
+
+    // Selling at most 10% below 0.10 only takes the first bid, and the rest
+    // of the base stays on the seat.
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[withdraw_as_quote_ix(&destination.key)],
+        Some(payer),
+        &[payer_keypair],
+    )
+    .await?;
+    assert_eq!(
+        destination.balance_atoms().await,
+        USDC_UNIT_SIZE + 50 * USDC_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(payer)
+            .await,
+        SOL_UNIT_SIZE / 2
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(payer)
+            .await,
+        0
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn withdraw_insufficient_funds_test() -> anyhow::Result<()> {
     let mut test_fixture: TestFixture = TestFixture::new().await;
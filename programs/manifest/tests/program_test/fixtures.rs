@@ -11,14 +11,16 @@ use manifest::{
         batch_update::{CancelOrderParams, PlaceOrderParams},
         batch_update_instruction,
         claim_seat_instruction::claim_seat_instruction,
-        create_market_instructions, deposit_instruction, get_dynamic_value,
-        global_add_trader_instruction,
+        create_market_instructions, create_market_with_extension_instruction, deposit_instruction,
+        expand_market_n_instruction, get_dynamic_value, global_add_trader_instruction,
         global_create_instruction::create_global_instruction,
         global_deposit_instruction, global_withdraw_instruction, swap_instruction,
         swap_v2_instruction, withdraw_instruction,
     },
     quantities::WrapperU64,
-    state::{GlobalFixed, GlobalValue, MarketFixed, MarketValue, OrderType, RestingOrder},
+    state::{
+        GlobalFixed, GlobalValue, MarketFixed, MarketValue, OrderType, RestingOrder, TradingHours,
+    },
     validation::{get_global_address, get_vault_address, MintAccountInfo},
 };
 use solana_account::Account;
@@ -251,6 +253,18 @@ impl TestFixture {
         self.context.borrow_mut().set_sysvar(&clock);
     }
 
+    pub async fn set_unix_timestamp(&self, unix_timestamp: i64) {
+        let mut clock: Clock = self
+            .context
+            .borrow_mut()
+            .banks_client
+            .get_sysvar()
+            .await
+            .unwrap();
+        clock.unix_timestamp = unix_timestamp;
+        self.context.borrow_mut().set_sysvar(&clock);
+    }
+
     pub async fn create_new_market(
         &self,
         base_mint: &Pubkey,
@@ -800,13 +814,354 @@ impl MarketFixture {
         context: Rc<RefCell<ProgramTestContext>>,
         base_mint: &Pubkey,
         quote_mint: &Pubkey,
+    ) -> Self {
+        Self::new_with_flags(context, base_mint, quote_mint, 0).await
+    }
+
+    pub async fn new_with_flags(
+        context: Rc<RefCell<ProgramTestContext>>,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        market_flags: u8,
+    ) -> Self {
+        Self::new_with_trading_hours(
+            context,
+            base_mint,
+            quote_mint,
+            market_flags,
+            TradingHours::default(),
+        )
+        .await
+    }
+
+    pub async fn new_with_trading_hours(
+        context: Rc<RefCell<ProgramTestContext>>,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        market_flags: u8,
+        trading_hours: TradingHours,
+    ) -> Self {
+        Self::new_with_min_resting_slots(
+            context,
+            base_mint,
+            quote_mint,
+            market_flags,
+            trading_hours,
+            0,
+        )
+        .await
+    }
+
+    pub async fn new_with_min_resting_slots(
+        context: Rc<RefCell<ProgramTestContext>>,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        market_flags: u8,
+        trading_hours: TradingHours,
+        min_resting_slots: u32,
+    ) -> Self {
+        Self::new_with_taker_delay(
+            context,
+            base_mint,
+            quote_mint,
+            market_flags,
+            trading_hours,
+            min_resting_slots,
+            0,
+        )
+        .await
+    }
+
+    pub async fn new_with_taker_delay(
+        context: Rc<RefCell<ProgramTestContext>>,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        market_flags: u8,
+        trading_hours: TradingHours,
+        min_resting_slots: u32,
+        taker_delay_slots: u16,
+    ) -> Self {
+        Self::new_with_points(
+            context,
+            base_mint,
+            quote_mint,
+            market_flags,
+            trading_hours,
+            min_resting_slots,
+            taker_delay_slots,
+            0,
+            0,
+        )
+        .await
+    }
+
+    pub async fn new_with_points(
+        context: Rc<RefCell<ProgramTestContext>>,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        market_flags: u8,
+        trading_hours: TradingHours,
+        min_resting_slots: u32,
+        taker_delay_slots: u16,
+        maker_points_weight: u16,
+        taker_points_weight: u16,
+    ) -> Self {
+        Self::new_with_cpi_allowlist(
+            context,
+            base_mint,
+            quote_mint,
+            market_flags,
+            trading_hours,
+            min_resting_slots,
+            taker_delay_slots,
+            maker_points_weight,
+            taker_points_weight,
+            &[],
+        )
+        .await
+    }
+
+    pub async fn new_with_cpi_allowlist(
+        context: Rc<RefCell<ProgramTestContext>>,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        market_flags: u8,
+        trading_hours: TradingHours,
+        min_resting_slots: u32,
+        taker_delay_slots: u16,
+        maker_points_weight: u16,
+        taker_points_weight: u16,
+        cpi_allowlist: &[Pubkey],
+    ) -> Self {
+        Self::new_with_min_order_size(
+            context,
+            base_mint,
+            quote_mint,
+            market_flags,
+            trading_hours,
+            min_resting_slots,
+            taker_delay_slots,
+            maker_points_weight,
+            taker_points_weight,
+            cpi_allowlist,
+            0,
+            0,
+        )
+        .await
+    }
+
+    pub async fn new_with_min_order_size(
+        context: Rc<RefCell<ProgramTestContext>>,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        market_flags: u8,
+        trading_hours: TradingHours,
+        min_resting_slots: u32,
+        taker_delay_slots: u16,
+        maker_points_weight: u16,
+        taker_points_weight: u16,
+        cpi_allowlist: &[Pubkey],
+        min_base_atoms: u64,
+        min_quote_atoms: u64,
+    ) -> Self {
+        Self::new_with_price_band(
+            context,
+            base_mint,
+            quote_mint,
+            market_flags,
+            trading_hours,
+            min_resting_slots,
+            taker_delay_slots,
+            maker_points_weight,
+            taker_points_weight,
+            cpi_allowlist,
+            min_base_atoms,
+            min_quote_atoms,
+            0,
+        )
+        .await
+    }
+
+    pub async fn new_with_price_band(
+        context: Rc<RefCell<ProgramTestContext>>,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        market_flags: u8,
+        trading_hours: TradingHours,
+        min_resting_slots: u32,
+        taker_delay_slots: u16,
+        maker_points_weight: u16,
+        taker_points_weight: u16,
+        cpi_allowlist: &[Pubkey],
+        min_base_atoms: u64,
+        min_quote_atoms: u64,
+        price_band_bps: u16,
+    ) -> Self {
+        Self::new_with_fees(
+            context,
+            base_mint,
+            quote_mint,
+            market_flags,
+            trading_hours,
+            min_resting_slots,
+            taker_delay_slots,
+            maker_points_weight,
+            taker_points_weight,
+            cpi_allowlist,
+            min_base_atoms,
+            min_quote_atoms,
+            price_band_bps,
+            0,
+            0,
+            0,
+            &Pubkey::default(),
+            0,
+        )
+        .await
+    }
+
+    pub async fn new_with_fees(
+        context: Rc<RefCell<ProgramTestContext>>,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        market_flags: u8,
+        trading_hours: TradingHours,
+        min_resting_slots: u32,
+        taker_delay_slots: u16,
+        maker_points_weight: u16,
+        taker_points_weight: u16,
+        cpi_allowlist: &[Pubkey],
+        min_base_atoms: u64,
+        min_quote_atoms: u64,
+        price_band_bps: u16,
+        maker_fee_bps: u16,
+        taker_fee_bps: u16,
+        referral_fee_bps: u16,
+        fee_recipient: &Pubkey,
+        fee_tier_quote_atoms: u64,
+    ) -> Self {
+        Self::new_with_pauser(
+            context,
+            base_mint,
+            quote_mint,
+            market_flags,
+            trading_hours,
+            min_resting_slots,
+            taker_delay_slots,
+            maker_points_weight,
+            taker_points_weight,
+            cpi_allowlist,
+            min_base_atoms,
+            min_quote_atoms,
+            price_band_bps,
+            maker_fee_bps,
+            taker_fee_bps,
+            referral_fee_bps,
+            fee_recipient,
+            fee_tier_quote_atoms,
+            &Pubkey::default(),
+        )
+        .await
+    }
+
+    pub async fn new_with_pauser(
+        context: Rc<RefCell<ProgramTestContext>>,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        market_flags: u8,
+        trading_hours: TradingHours,
+        min_resting_slots: u32,
+        taker_delay_slots: u16,
+        maker_points_weight: u16,
+        taker_points_weight: u16,
+        cpi_allowlist: &[Pubkey],
+        min_base_atoms: u64,
+        min_quote_atoms: u64,
+        price_band_bps: u16,
+        maker_fee_bps: u16,
+        taker_fee_bps: u16,
+        referral_fee_bps: u16,
+        fee_recipient: &Pubkey,
+        fee_tier_quote_atoms: u64,
+        pauser: &Pubkey,
+    ) -> Self {
+        Self::new_with_extension(
+            context,
+            base_mint,
+            quote_mint,
+            market_flags,
+            trading_hours,
+            min_resting_slots,
+            taker_delay_slots,
+            maker_points_weight,
+            taker_points_weight,
+            cpi_allowlist,
+            min_base_atoms,
+            min_quote_atoms,
+            price_band_bps,
+            maker_fee_bps,
+            taker_fee_bps,
+            referral_fee_bps,
+            fee_recipient,
+            fee_tier_quote_atoms,
+            pauser,
+            0,
+        )
+        .await
+    }
+
+    pub async fn new_with_extension(
+        context: Rc<RefCell<ProgramTestContext>>,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        market_flags: u8,
+        trading_hours: TradingHours,
+        min_resting_slots: u32,
+        taker_delay_slots: u16,
+        maker_points_weight: u16,
+        taker_points_weight: u16,
+        cpi_allowlist: &[Pubkey],
+        min_base_atoms: u64,
+        min_quote_atoms: u64,
+        price_band_bps: u16,
+        maker_fee_bps: u16,
+        taker_fee_bps: u16,
+        referral_fee_bps: u16,
+        fee_recipient: &Pubkey,
+        fee_tier_quote_atoms: u64,
+        pauser: &Pubkey,
+        extension_flags: u8,
     ) -> Self {
         let market_keypair: Keypair = Keypair::new();
         let payer: Pubkey = context.borrow().payer.pubkey();
         let payer_keypair: Keypair = context.borrow().payer.insecure_clone();
-        let create_market_ixs: Vec<Instruction> =
+        let mut create_market_ixs: Vec<Instruction> =
             create_market_instructions(&market_keypair.pubkey(), base_mint, quote_mint, &payer)
                 .unwrap();
+        // The second instruction is the create market itself.
+        create_market_ixs[1] = create_market_with_extension_instruction(
+            &market_keypair.pubkey(),
+            base_mint,
+            quote_mint,
+            &payer,
+            market_flags,
+            trading_hours,
+            min_resting_slots,
+            taker_delay_slots,
+            maker_points_weight,
+            taker_points_weight,
+            cpi_allowlist,
+            min_base_atoms,
+            min_quote_atoms,
+            price_band_bps,
+            maker_fee_bps,
+            taker_fee_bps,
+            referral_fee_bps,
+            fee_recipient,
+            fee_tier_quote_atoms,
+            pauser,
+            extension_flags,
+        );
 
         send_tx_with_retry(
             Rc::clone(&context),
@@ -865,7 +1220,7 @@ impl MarketFixture {
             context: context_ref,
             key: market_keypair.pubkey(),
             market: MarketValue {
-                fixed: MarketFixed::new_empty(&base_mint, &quote_mint, &market_keypair.pubkey()),
+                fixed: MarketFixed::new_empty(&base_mint, &quote_mint, &market_keypair.pubkey(), 0),
                 dynamic: Vec::new(),
             },
         }
@@ -970,7 +1325,7 @@ impl MarketFixture {
         (base_locked, quote_locked)
     }
 
-    /// Verify that vault balances match seats + orders.
+    /// Verify that vault balances match seats + orders + accrued fees.
     /// Takes a slice of trader pubkeys to sum their seat balances.
     /// When exact is true, checks exact equality; when false, checks vault has at least expected.
     pub async fn verify_vault_balance(&mut self, traders: &[Pubkey], exact: bool) {
@@ -990,46 +1345,72 @@ impl MarketFixture {
         // Get vault balances
         let (vault_base, vault_quote) = self.get_vault_balances().await;
 
+        // Accrued fees stay in the vaults until claimed
+        let (fees_base, fees_quote): (u64, u64) = self
+            .market
+            .get_market_fees()
+            .map(|market_fees| {
+                (
+                    market_fees.get_base_fee_atoms().as_u64(),
+                    market_fees.get_quote_fee_atoms().as_u64(),
+                )
+            })
+            .unwrap_or_default();
+
+        // Fees claimed before a bust refunded them are owed back to the vaults
+        let (owed_base, owed_quote): (u64, u64) = self
+            .market
+            .get_market_extension()
+            .map(|market_extension| {
+                (
+                    market_extension.get_base_fees_owed_atoms().as_u64(),
+                    market_extension.get_quote_fees_owed_atoms().as_u64(),
+                )
+            })
+            .unwrap_or_default();
+
         // Total expected in vault
-        let expected_base = seats_base + base_in_asks;
-        let expected_quote = seats_quote + quote_in_bids;
+        let expected_base = seats_base + base_in_asks + fees_base - owed_base;
+        let expected_quote = seats_quote + quote_in_bids + fees_quote - owed_quote;
 
         println!(
-            "Vault verification: base_vault={} expected={} (seats={} + asks={})",
-            vault_base, expected_base, seats_base, base_in_asks
+            "Vault verification: base_vault={} expected={} (seats={} + asks={} + fees={})",
+            vault_base, expected_base, seats_base, base_in_asks, fees_base
         );
         println!(
-            "Vault verification: quote_vault={} expected={} (seats={} + bids={})",
-            vault_quote, expected_quote, seats_quote, quote_in_bids
+            "Vault verification: quote_vault={} expected={} (seats={} + bids={} + fees={})",
+            vault_quote, expected_quote, seats_quote, quote_in_bids, fees_quote
         );
 
         if exact {
             assert_eq!(
                 vault_base, expected_base,
-                "Base vault mismatch: vault={}, expected={} (seats={} + asks={})",
-                vault_base, expected_base, seats_base, base_in_asks
+                "Base vault mismatch: vault={}, expected={} (seats={} + asks={} + fees={})",
+                vault_base, expected_base, seats_base, base_in_asks, fees_base
             );
             assert_eq!(
                 vault_quote, expected_quote,
-                "Quote vault mismatch: vault={}, expected={} (seats={} + bids={})",
-                vault_quote, expected_quote, seats_quote, quote_in_bids
+                "Quote vault mismatch: vault={}, expected={} (seats={} + bids={} + fees={})",
+                vault_quote, expected_quote, seats_quote, quote_in_bids, fees_quote
             );
         } else {
             assert!(
                 vault_base >= expected_base,
-                "Base vault insufficient: vault={}, expected at least {} (seats={} + asks={})",
+                "Base vault insufficient: vault={}, expected at least {} (seats={} + asks={} + fees={})",
                 vault_base,
                 expected_base,
                 seats_base,
-                base_in_asks
+                base_in_asks,
+                fees_base
             );
             assert!(
                 vault_quote >= expected_quote,
-                "Quote vault insufficient: vault={}, expected at least {} (seats={} + bids={})",
+                "Quote vault insufficient: vault={}, expected at least {} (seats={} + bids={} + fees={})",
                 vault_quote,
                 expected_quote,
                 seats_quote,
-                quote_in_bids
+                quote_in_bids,
+                fees_quote
             );
         }
     }
@@ -1624,7 +2005,7 @@ pub async fn send_tx_with_retry(
 }
 
 /// Get the balance of a token account, handling both SPL Token and Token-2022.
-async fn get_token_account_balance(
+pub async fn get_token_account_balance(
     context: Rc<RefCell<ProgramTestContext>>,
     token_account: Pubkey,
 ) -> u64 {
@@ -1710,45 +2091,58 @@ pub async fn verify_vault_balance(
     let vault_base: u64 = get_token_account_balance(Rc::clone(&context), base_vault).await;
     let vault_quote: u64 = get_token_account_balance(Rc::clone(&context), quote_vault).await;
 
-    let expected_base = seats_base + base_in_asks;
-    let expected_quote = seats_quote + quote_in_bids;
+    // Accrued fees stay in the vaults until claimed
+    let (fees_base, fees_quote): (u64, u64) = market
+        .get_market_fees()
+        .map(|market_fees| {
+            (
+                market_fees.get_base_fee_atoms().as_u64(),
+                market_fees.get_quote_fee_atoms().as_u64(),
+            )
+        })
+        .unwrap_or_default();
+
+    let expected_base = seats_base + base_in_asks + fees_base;
+    let expected_quote = seats_quote + quote_in_bids + fees_quote;
 
     println!(
-        "Vault verification: base_vault={} expected={} (seats={} + asks={})",
-        vault_base, expected_base, seats_base, base_in_asks
+        "Vault verification: base_vault={} expected={} (seats={} + asks={} + fees={})",
+        vault_base, expected_base, seats_base, base_in_asks, fees_base
     );
     println!(
-        "Vault verification: quote_vault={} expected={} (seats={} + bids={})",
-        vault_quote, expected_quote, seats_quote, quote_in_bids
+        "Vault verification: quote_vault={} expected={} (seats={} + bids={} + fees={})",
+        vault_quote, expected_quote, seats_quote, quote_in_bids, fees_quote
     );
 
     if exact {
         assert_eq!(
             vault_base, expected_base,
-            "Base vault mismatch: vault={}, expected={} (seats={} + asks={})",
-            vault_base, expected_base, seats_base, base_in_asks
+            "Base vault mismatch: vault={}, expected={} (seats={} + asks={} + fees={})",
+            vault_base, expected_base, seats_base, base_in_asks, fees_base
         );
         assert_eq!(
             vault_quote, expected_quote,
-            "Quote vault mismatch: vault={}, expected={} (seats={} + bids={})",
-            vault_quote, expected_quote, seats_quote, quote_in_bids
+            "Quote vault mismatch: vault={}, expected={} (seats={} + bids={} + fees={})",
+            vault_quote, expected_quote, seats_quote, quote_in_bids, fees_quote
         );
     } else {
         assert!(
             vault_base >= expected_base,
-            "Base vault insufficient: vault={}, expected at least {} (seats={} + asks={})",
+            "Base vault insufficient: vault={}, expected at least {} (seats={} + asks={} + fees={})",
             vault_base,
             expected_base,
             seats_base,
-            base_in_asks
+            base_in_asks,
+            fees_base
         );
         assert!(
             vault_quote >= expected_quote,
-            "Quote vault insufficient: vault={}, expected at least {} (seats={} + bids={})",
+            "Quote vault insufficient: vault={}, expected at least {} (seats={} + bids={} + fees={})",
             vault_quote,
             expected_quote,
             seats_quote,
-            quote_in_bids
+            quote_in_bids,
+            fees_quote
         );
     }
 
@@ -1892,33 +2286,16 @@ pub async fn mint_token_2022(
     .await
 }
 
-/// Expand a market to add more free blocks for orders.
-/// Calls expand_market_instruction `count` times.
+/// Expand a market until it has at least num_free_blocks free blocks.
 pub async fn expand_market(
     context: Rc<RefCell<ProgramTestContext>>,
     market: &Pubkey,
     num_free_blocks: u32,
 ) -> Result<(), BanksClientError> {
-    use manifest::program::ManifestInstruction;
-    use solana_program::system_program;
-
     let payer_keypair = context.borrow().payer.insecure_clone();
     let payer = payer_keypair.pubkey();
 
-    // Create instruction with the number of free blocks required as data
-    let expand_ix = Instruction {
-        program_id: manifest::id(),
-        accounts: vec![
-            AccountMeta::new(payer, true),
-            AccountMeta::new(*market, false),
-            AccountMeta::new_readonly(system_program::id(), false),
-        ],
-        data: [
-            ManifestInstruction::Expand.to_vec(),
-            num_free_blocks.to_le_bytes().to_vec(),
-        ]
-        .concat(),
-    };
+    let expand_ix: Instruction = expand_market_n_instruction(market, &payer, num_free_blocks);
 
     send_tx_with_retry(
         Rc::clone(&context),
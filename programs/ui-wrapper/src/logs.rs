@@ -29,6 +29,9 @@ macro_rules! discriminant {
             fn discriminant() -> [u8; 8] {
                 $value
             }
+            fn version() -> u8 {
+                1
+            }
         }
 
         #[test]
@@ -451,7 +451,7 @@ impl MarketFixture {
             context: context_ref,
             key,
             market: MarketValue {
-                fixed: MarketFixed::new_empty(&base_mint, &quote_mint, &key),
+                fixed: MarketFixed::new_empty(&base_mint, &quote_mint, &key, 0),
                 dynamic: Vec::new(),
             },
         }
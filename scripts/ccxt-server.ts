@@ -0,0 +1,363 @@
+import 'dotenv/config';
+
+import cors from 'cors';
+import express, { RequestHandler } from 'express';
+import promBundle from 'express-prom-bundle';
+import {
+  AccountInfo,
+  Connection,
+  ConfirmedSignatureInfo,
+  PublicKey,
+  RpcResponseAndContext,
+  Transaction,
+  TransactionInstruction,
+  VersionedTransactionResponse,
+} from '@solana/web3.js';
+import BN from 'bn.js';
+import {
+  CcxtMarket,
+  ManifestClient,
+  Market,
+  OrderType,
+  toCcxtMarket,
+  toCcxtOrderBook,
+  toCcxtTrade,
+} from '../client/ts/src';
+import { fillDiscriminant, toFillLogResult } from '../client/ts/src/fillFeed';
+import { FillLog } from '../client/ts/src/manifest/accounts/FillLog';
+import { FillLogResult } from '../client/ts/src/types';
+
+// CCXT style REST layer. Reads are served straight from RPC. Orders are
+// returned as unsigned transactions for the caller to sign and send, so this
+// service never holds keys.
+//
+// MARKETS is a comma separated list of SYMBOL=ADDRESS, for example
+// SOL/USDC=ENhU8LsaR7vDD2G1CsWcsuSGNrih9Cv5WZEk7q9kPapQ
+const { RPC_URL, MARKETS, PORT } = process.env;
+
+if (!RPC_URL) {
+  throw new Error('RPC_URL missing from env');
+}
+if (!MARKETS) {
+  throw new Error('MARKETS missing from env');
+}
+
+const MAX_TRADES_LIMIT: number = 1_000;
+const DEFAULT_TRADES_LIMIT: number = 50;
+// Max quote a market buy may spend when no price limit is given.
+const U64_MAX: string = '18446744073709551615';
+
+const connection: Connection = new Connection(RPC_URL, 'confirmed');
+const symbolToMarket: Map<string, PublicKey> = new Map(
+  MARKETS.split(',').map((entry: string) => {
+    const [symbol, address] = entry.trim().split('=');
+    return [symbol, new PublicKey(address)];
+  }),
+);
+
+class BadRequest extends Error {}
+
+async function loadMarket(
+  symbol: string | undefined,
+): Promise<{ market: Market; slot: number }> {
+  const address: PublicKey | undefined = symbol
+    ? symbolToMarket.get(symbol)
+    : undefined;
+  if (!address) {
+    throw new BadRequest(`Unknown symbol ${symbol}`);
+  }
+  const response: RpcResponseAndContext<AccountInfo<Buffer> | null> =
+    await connection.getAccountInfoAndContext(address);
+  if (!response.value) {
+    throw new Error(`Failed to load ${address.toBase58()}`);
+  }
+  const slot: number = response.context.slot;
+  return {
+    market: Market.loadFromBuffer({
+      address,
+      buffer: response.value.data,
+      slot,
+    }),
+    slot,
+  };
+}
+
+function parseFills(tx: VersionedTransactionResponse): FillLogResult[] {
+  if (!tx.meta?.logMessages || tx.meta.err != null) {
+    return [];
+  }
+  const fills: FillLogResult[] = [];
+  for (const message of tx.meta.logMessages) {
+    if (!message.startsWith('Program data: ')) {
+      continue;
+    }
+    const buffer: Buffer = Buffer.from(message.split(' ')[2], 'base64');
+    if (!buffer.subarray(0, 8).equals(fillDiscriminant)) {
+      continue;
+    }
+    const fillLog: FillLog = FillLog.deserialize(buffer.subarray(8))[0];
+    fills.push(
+      toFillLogResult(
+        fillLog,
+        tx.slot,
+        tx.transaction.signatures[0],
+        undefined,
+        undefined,
+        undefined,
+        undefined,
+        tx.blockTime ?? undefined,
+      ),
+    );
+  }
+  return fills;
+}
+
+/**
+ * Convert tokens to atoms, rejecting values with more precision than the
+ * mint supports.
+ */
+function toAtoms(tokens: number, decimals: number, name: string): string {
+  const atoms: number = Math.round(tokens * 10 ** decimals);
+  if (
+    !Number.isFinite(tokens) ||
+    tokens <= 0 ||
+    Math.abs(atoms - tokens * 10 ** decimals) > 1e-6
+  ) {
+    throw new BadRequest(`Invalid ${name} ${tokens}`);
+  }
+  return atoms.toLocaleString('fullwide', { useGrouping: false });
+}
+
+async function toUnsignedTransaction(
+  payer: PublicKey,
+  instructions: TransactionInstruction[],
+): Promise<Transaction> {
+  const { blockhash, lastValidBlockHeight } =
+    await connection.getLatestBlockhash();
+  return new Transaction({
+    feePayer: payer,
+    blockhash,
+    lastValidBlockHeight,
+  }).add(...instructions);
+}
+
+function serialize(transaction: Transaction): string {
+  return transaction
+    .serialize({ requireAllSignatures: false, verifySignatures: false })
+    .toString('base64');
+}
+
+const withErrors =
+  (handler: RequestHandler): RequestHandler =>
+  async (req, res, next) => {
+    try {
+      await handler(req, res, next);
+    } catch (error) {
+      if (error instanceof BadRequest) {
+        res.status(400).send({ error: error.message });
+        return;
+      }
+      console.error('Error handling', req.path, error);
+      res.status(500).send({ error: 'Internal server error' });
+    }
+  };
+
+const marketsHandler: RequestHandler = async (_req, res) => {
+  const markets: CcxtMarket[] = await Promise.all(
+    [...symbolToMarket.keys()].map(async (symbol: string) =>
+      toCcxtMarket((await loadMarket(symbol)).market, symbol),
+    ),
+  );
+  res.send(markets);
+};
+
+const orderBookHandler: RequestHandler = async (req, res) => {
+  const symbol = req.query.symbol as string;
+  const limit: number | undefined = req.query.limit
+    ? parseInt(req.query.limit as string)
+    : undefined;
+  const { market, slot } = await loadMarket(symbol);
+  res.send(toCcxtOrderBook(market, symbol, limit, slot));
+};
+
+const tradesHandler: RequestHandler = async (req, res) => {
+  const symbol = req.query.symbol as string;
+  const limit: number = Math.min(
+    parseInt(req.query.limit as string) || DEFAULT_TRADES_LIMIT,
+    MAX_TRADES_LIMIT,
+  );
+  const { market } = await loadMarket(symbol);
+  const signatures: ConfirmedSignatureInfo[] =
+    await connection.getSignaturesForAddress(market.address, { limit });
+  const transactions: (VersionedTransactionResponse | null)[] =
+    await connection.getTransactions(
+      signatures
+        .filter((signature) => signature.err == null)
+        .map((signature) => signature.signature),
+      { maxSupportedTransactionVersion: 0 },
+    );
+  // Newest first from RPC, ccxt wants oldest first.
+  const trades = transactions
+    .filter((tx): tx is VersionedTransactionResponse => tx != null)
+    .reverse()
+    .flatMap(parseFills)
+    .filter((fill) => fill.market == market.address.toBase58())
+    .map((fill) =>
+      toCcxtTrade(fill, symbol, market.baseDecimals(), market.quoteDecimals()),
+    );
+  res.send(trades.slice(-limit));
+};
+
+/**
+ * Body: { symbol, type: 'limit' | 'market', side: 'buy' | 'sell', amount,
+ * price?, owner, params?: { postOnly?, timeInForce?, clientOrderId? } }
+ *
+ * Limit orders go through the wrapper. If the owner has no wrapper or seat
+ * yet, the setup transaction is returned instead with setupNeeded set and the
+ * order has to be created again once it lands. Market orders are swaps from
+ * the owner's associated token accounts, with price as the worst acceptable
+ * price.
+ */
+const createOrderHandler: RequestHandler = async (req, res) => {
+  const { symbol, type, side, amount, price, owner, params } = req.body ?? {};
+  if (side != 'buy' && side != 'sell') {
+    throw new BadRequest(`Invalid side ${side}`);
+  }
+  let ownerPk: PublicKey;
+  try {
+    ownerPk = new PublicKey(owner);
+  } catch {
+    throw new BadRequest(`Invalid owner ${owner}`);
+  }
+  const { market } = await loadMarket(symbol);
+  const isBid: boolean = side == 'buy';
+  const baseDecimals: number = market.baseDecimals();
+  const quoteDecimals: number = market.quoteDecimals();
+  const baseAtoms: string = toAtoms(Number(amount), baseDecimals, 'amount');
+
+  if (type == 'market') {
+    let limitQuoteAtoms: string | undefined;
+    if (price != undefined) {
+      const quoteTokens: number = Number(price) * Number(amount);
+      limitQuoteAtoms = (
+        isBid
+          ? Math.ceil(quoteTokens * 10 ** quoteDecimals)
+          : Math.floor(quoteTokens * 10 ** quoteDecimals)
+      ).toLocaleString('fullwide', { useGrouping: false });
+    }
+    const client: ManifestClient = await ManifestClient.getClientReadOnly(
+      connection,
+      market.address,
+    );
+    const swapIx: TransactionInstruction = client.swapIx(ownerPk, {
+      inAtoms: new BN(isBid ? (limitQuoteAtoms ?? U64_MAX) : baseAtoms),
+      outAtoms: new BN(isBid ? baseAtoms : (limitQuoteAtoms ?? 0)),
+      isBaseIn: !isBid,
+      isExactIn: !isBid,
+    });
+    const transaction: Transaction = await toUnsignedTransaction(ownerPk, [
+      swapIx,
+    ]);
+    res.send({
+      symbol,
+      type,
+      side,
+      amount,
+      transaction: serialize(transaction),
+    });
+    return;
+  }
+
+  if (type != 'limit') {
+    throw new BadRequest(`Invalid type ${type}`);
+  }
+  const tokenPrice: number = Number(price);
+  if (!Number.isFinite(tokenPrice) || tokenPrice <= 0) {
+    throw new BadRequest(`Invalid price ${price}`);
+  }
+
+  const setupData = await ManifestClient.getSetupIxs(
+    connection,
+    market.address,
+    ownerPk,
+  );
+  if (setupData.setupNeeded) {
+    const transaction: Transaction = await toUnsignedTransaction(
+      ownerPk,
+      setupData.instructions,
+    );
+    if (setupData.wrapperKeypair) {
+      transaction.partialSign(setupData.wrapperKeypair);
+    }
+    res.send({
+      symbol,
+      setupNeeded: true,
+      transaction: serialize(transaction),
+    });
+    return;
+  }
+
+  const orderType: OrderType = params?.postOnly
+    ? OrderType.PostOnly
+    : params?.timeInForce == 'IOC'
+      ? OrderType.ImmediateOrCancel
+      : OrderType.Limit;
+  const client: ManifestClient =
+    await ManifestClient.getClientForMarketNoPrivateKey(
+      connection,
+      market.address,
+      ownerPk,
+    );
+  const instructions: TransactionInstruction[] =
+    await client.placeOrderWithRequiredDepositIxs(ownerPk, {
+      numBaseTokens: Number(amount),
+      tokenPrice,
+      isBid,
+      lastValidSlot: 0,
+      orderType,
+      clientOrderId: Number(params?.clientOrderId ?? 0),
+    });
+  const transaction: Transaction = await toUnsignedTransaction(
+    ownerPk,
+    instructions,
+  );
+  res.send({
+    symbol,
+    type,
+    side,
+    amount,
+    price: tokenPrice,
+    clientOrderId: String(params?.clientOrderId ?? 0),
+    transaction: serialize(transaction),
+  });
+};
+
+const run = async () => {
+  const app = express();
+  app.use(cors());
+  app.use(express.json());
+  app.use(
+    promBundle({
+      includeMethod: true,
+      includePath: true,
+    }),
+  );
+
+  app.get('/markets', withErrors(marketsHandler));
+  app.get('/orderbook', withErrors(orderBookHandler));
+  app.get('/trades', withErrors(tradesHandler));
+  app.post('/order', withErrors(createOrderHandler));
+  app.get('/health', (_req, res) => {
+    res.status(200).send('OK');
+  });
+
+  const port: number = Number(PORT ?? 3001);
+  app.listen(port, () => {
+    console.log(`ccxt server running on port ${port}`);
+  });
+};
+
+run().catch((e) => {
+  console.error('fatal error', e);
+  throw e;
+});
@@ -0,0 +1,485 @@
+import path from 'path';
+import * as grpc from '@grpc/grpc-js';
+import * as protoLoader from '@grpc/proto-loader';
+import { AccountInfo, Connection, PublicKey } from '@solana/web3.js';
+import * as promClient from 'prom-client';
+import { Market, OrderType, RestingOrder } from '../../client/ts/src';
+import { FillLogResult } from '../../client/ts/src/types';
+
+const PROTO_PATH: string = path.resolve(
+  __dirname,
+  '../../client/proto/feed.proto',
+);
+
+// Messages a subscriber may have queued before it is dropped. A dropped
+// client gets RESOURCE_EXHAUSTED and should resubscribe, which for the book
+// starts again from a snapshot.
+const MAX_QUEUED_MESSAGES: number = 10_000;
+
+const D18: bigint = 10n ** 18n;
+
+const grpcSubscribers = new promClient.Gauge({
+  name: 'grpc_subscribers',
+  help: 'Number of open gRPC feed streams',
+  labelNames: ['stream'] as const,
+});
+const grpcDropped = new promClient.Counter({
+  name: 'grpc_dropped_subscribers',
+  help: 'Number of gRPC feed streams dropped for falling behind',
+  labelNames: ['stream'] as const,
+});
+
+type Level = { price: number; baseAtoms: string };
+
+type BookUpdate = {
+  market: string;
+  slot: number;
+  snapshot: boolean;
+  bids: Level[];
+  asks: Level[];
+};
+
+type Fill = {
+  market: string;
+  maker: string;
+  taker: string;
+  baseAtoms: string;
+  quoteAtoms: string;
+  priceAtoms: number;
+  takerIsBuy: boolean;
+  isMakerGlobal: boolean;
+  makerSequenceNumber: string;
+  takerSequenceNumber: string;
+  slot: number;
+  signature: string;
+  blockTime: number;
+  inferred: boolean;
+};
+
+type Position = {
+  market: string;
+  trader: string;
+  slot: number;
+  baseWithdrawableAtoms: string;
+  quoteWithdrawableAtoms: string;
+  baseOpenOrdersAtoms: string;
+  quoteOpenOrdersAtoms: string;
+};
+
+/**
+ * Server side of one stream. Writes go out as fast as the client reads them
+ * and queue up otherwise, so a slow client only ever holds its own queue.
+ */
+class Subscriber<T> {
+  private queue: T[] = [];
+  private blocked: boolean = false;
+  private closed: boolean = false;
+
+  constructor(
+    private call: grpc.ServerWritableStream<unknown, T>,
+    private stream: string,
+    onClose: () => void,
+  ) {
+    grpcSubscribers.inc({ stream });
+    const close = () => {
+      if (this.closed) {
+        return;
+      }
+      this.closed = true;
+      this.queue = [];
+      grpcSubscribers.dec({ stream });
+      onClose();
+    };
+    call.on('cancelled', close);
+    call.on('close', close);
+    call.on('error', close);
+  }
+
+  public send(message: T): void {
+    if (this.closed) {
+      return;
+    }
+    if (this.queue.length >= MAX_QUEUED_MESSAGES) {
+      grpcDropped.inc({ stream: this.stream });
+      endWithStatus(
+        this.call,
+        grpc.status.RESOURCE_EXHAUSTED,
+        'subscriber fell behind',
+      );
+      return;
+    }
+    this.queue.push(message);
+    this.flush();
+  }
+
+  private flush(): void {
+    while (!this.blocked && !this.closed && this.queue.length > 0) {
+      if (!this.call.write(this.queue.shift()!)) {
+        this.blocked = true;
+        this.call.once('drain', () => {
+          this.blocked = false;
+          this.flush();
+        });
+      }
+    }
+  }
+}
+
+function toLevels(orders: RestingOrder[]): Map<number, bigint> {
+  const levels: Map<number, bigint> = new Map();
+  for (const order of orders) {
+    const baseAtoms: bigint = BigInt(order.numBaseAtoms.toString());
+    levels.set(
+      order.tokenPrice,
+      (levels.get(order.tokenPrice) ?? 0n) + baseAtoms,
+    );
+  }
+  return levels;
+}
+
+function diffLevels(
+  before: Map<number, bigint>,
+  after: Map<number, bigint>,
+): Level[] {
+  const changed: Level[] = [];
+  for (const [price, baseAtoms] of after) {
+    if (before.get(price) !== baseAtoms) {
+      changed.push({ price, baseAtoms: baseAtoms.toString() });
+    }
+  }
+  for (const price of before.keys()) {
+    if (!after.has(price)) {
+      changed.push({ price, baseAtoms: '0' });
+    }
+  }
+  return changed;
+}
+
+function toLevelList(levels: Map<number, bigint>): Level[] {
+  return [...levels].map(([price, baseAtoms]) => {
+    return { price, baseAtoms: baseAtoms.toString() };
+  });
+}
+
+function toPosition(market: Market, trader: PublicKey, slot: number): Position {
+  const isOwn = (order: RestingOrder) =>
+    order.trader.equals(trader) && order.orderType != OrderType.Global;
+  let baseOpenOrdersAtoms: bigint = 0n;
+  for (const ask of market.asks().filter(isOwn)) {
+    baseOpenOrdersAtoms += BigInt(ask.numBaseAtoms.toString());
+  }
+  let quoteOpenOrdersAtoms: bigint = 0n;
+  for (const bid of market.bids().filter(isOwn)) {
+    // Rounded up like the program does when locking quote for a bid.
+    const quoteAtomsD18: bigint =
+      BigInt(bid.numBaseAtoms.toString()) * BigInt(bid.price.toString());
+    quoteOpenOrdersAtoms += (quoteAtomsD18 + D18 - 1n) / D18;
+  }
+  const seat = market
+    .claimedSeats()
+    .find((claimedSeat) => claimedSeat.publicKey.equals(trader));
+  return {
+    market: market.address.toBase58(),
+    trader: trader.toBase58(),
+    slot,
+    baseWithdrawableAtoms: (seat?.baseBalance ?? 0).toString(),
+    quoteWithdrawableAtoms: (seat?.quoteBalance ?? 0).toString(),
+    baseOpenOrdersAtoms: baseOpenOrdersAtoms.toString(),
+    quoteOpenOrdersAtoms: quoteOpenOrdersAtoms.toString(),
+  };
+}
+
+/**
+ * Keeps one account subscription per market for as long as any book or
+ * position stream needs it.
+ */
+class MarketWatcher {
+  public market: Market | undefined;
+  public slot: number = 0;
+  public bids: Map<number, bigint> = new Map();
+  public asks: Map<number, bigint> = new Map();
+  public bookSubscribers: Set<Subscriber<BookUpdate>> = new Set();
+  public positionSubscribers: Map<
+    Subscriber<Position>,
+    { trader: PublicKey; last: string }
+  > = new Map();
+  private subscriptionId: number;
+
+  constructor(
+    private connection: Connection,
+    private address: PublicKey,
+  ) {
+    this.subscriptionId = connection.onAccountChange(
+      address,
+      (accountInfo: AccountInfo<Buffer>, context: { slot: number }) =>
+        this.update(accountInfo.data, context.slot),
+      'confirmed',
+    );
+  }
+
+  public async load(): Promise<void> {
+    if (this.market) {
+      return;
+    }
+    const response = await this.connection.getAccountInfoAndContext(
+      this.address,
+    );
+    if (!response.value) {
+      throw new Error(`Failed to load ${this.address.toBase58()}`);
+    }
+    // A notification may have arrived while loading.
+    if (!this.market) {
+      this.update(response.value.data, response.context.slot);
+    }
+  }
+
+  public isIdle(): boolean {
+    return (
+      this.bookSubscribers.size == 0 && this.positionSubscribers.size == 0
+    );
+  }
+
+  public close(): void {
+    this.connection.removeAccountChangeListener(this.subscriptionId);
+  }
+
+  public snapshot(): BookUpdate {
+    return {
+      market: this.address.toBase58(),
+      slot: this.slot,
+      snapshot: true,
+      bids: toLevelList(this.bids),
+      asks: toLevelList(this.asks),
+    };
+  }
+
+  public sendPosition(subscriber: Subscriber<Position>): void {
+    const entry = this.positionSubscribers.get(subscriber);
+    if (!entry || !this.market) {
+      return;
+    }
+    const position: Position = toPosition(this.market, entry.trader, this.slot);
+    const key: string = JSON.stringify({ ...position, slot: 0 });
+    if (key != entry.last) {
+      entry.last = key;
+      subscriber.send(position);
+    }
+  }
+
+  private update(buffer: Buffer, slot: number): void {
+    if (slot < this.slot) {
+      return;
+    }
+    this.market = Market.loadFromBuffer({
+      address: this.address,
+      buffer,
+      slot,
+    });
+    this.slot = slot;
+
+    const bids: Map<number, bigint> = toLevels(this.market.bids());
+    const asks: Map<number, bigint> = toLevels(this.market.asks());
+    const update: BookUpdate = {
+      market: this.address.toBase58(),
+      slot,
+      snapshot: false,
+      bids: diffLevels(this.bids, bids),
+      asks: diffLevels(this.asks, asks),
+    };
+    this.bids = bids;
+    this.asks = asks;
+    if (update.bids.length > 0 || update.asks.length > 0) {
+      for (const subscriber of this.bookSubscribers) {
+        subscriber.send(update);
+      }
+    }
+    for (const subscriber of this.positionSubscribers.keys()) {
+      this.sendPosition(subscriber);
+    }
+  }
+}
+
+/**
+ * gRPC server for the feed. Fills are pushed in by the fill feed through
+ * publishFill, book and position streams follow the market accounts
+ * directly.
+ */
+export class GrpcFeedServer {
+  private server: grpc.Server = new grpc.Server();
+  private fillSubscribers: Map<Subscriber<Fill>, Set<string>> = new Map();
+  private watchers: Map<string, MarketWatcher> = new Map();
+
+  constructor(private connection: Connection) {
+    const packageDefinition = protoLoader.loadSync(PROTO_PATH, {
+      longs: String,
+      defaults: true,
+    });
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    const proto: any = grpc.loadPackageDefinition(packageDefinition);
+    this.server.addService(proto.manifest.feed.v1.Feed.service, {
+      subscribeBook: this.subscribeBook.bind(this),
+      subscribeFills: this.subscribeFills.bind(this),
+      subscribePositions: this.subscribePositions.bind(this),
+      subscribeFunding: this.subscribeFunding.bind(this),
+    });
+  }
+
+  public start(port: number): Promise<void> {
+    return new Promise((resolve, reject) => {
+      this.server.bindAsync(
+        `0.0.0.0:${port}`,
+        grpc.ServerCredentials.createInsecure(),
+        (error: Error | null) => {
+          if (error) {
+            reject(error);
+            return;
+          }
+          console.log(`gRPC feed listening on port ${port}`);
+          resolve();
+        },
+      );
+    });
+  }
+
+  public publishFill(fill: FillLogResult, inferred: boolean): void {
+    const message: Fill = {
+      market: fill.market,
+      maker: fill.maker,
+      taker: fill.taker,
+      baseAtoms: fill.baseAtoms,
+      quoteAtoms: fill.quoteAtoms,
+      priceAtoms: fill.priceAtoms,
+      takerIsBuy: fill.takerIsBuy,
+      isMakerGlobal: fill.isMakerGlobal,
+      makerSequenceNumber: fill.makerSequenceNumber,
+      takerSequenceNumber: fill.takerSequenceNumber,
+      slot: fill.slot,
+      signature: fill.signature,
+      blockTime: fill.blockTime ?? 0,
+      inferred,
+    };
+    for (const [subscriber, markets] of this.fillSubscribers) {
+      if (markets.size == 0 || markets.has(fill.market)) {
+        subscriber.send(message);
+      }
+    }
+  }
+
+  private subscribeFills(
+    call: grpc.ServerWritableStream<{ markets: string[] }, Fill>,
+  ): void {
+    const subscriber: Subscriber<Fill> = new Subscriber(
+      call,
+      'fills',
+      () => this.fillSubscribers.delete(subscriber),
+    );
+    this.fillSubscribers.set(subscriber, new Set(call.request.markets));
+  }
+
+  private async subscribeBook(
+    call: grpc.ServerWritableStream<{ market: string }, BookUpdate>,
+  ): Promise<void> {
+    const watcher: MarketWatcher | undefined = await this.watch(call);
+    if (!watcher) {
+      return;
+    }
+    const subscriber: Subscriber<BookUpdate> = new Subscriber(
+      call,
+      'book',
+      () => {
+        watcher.bookSubscribers.delete(subscriber);
+        this.unwatch(call.request.market);
+      },
+    );
+    subscriber.send(watcher.snapshot());
+    watcher.bookSubscribers.add(subscriber);
+  }
+
+  private async subscribePositions(
+    call: grpc.ServerWritableStream<
+      { market: string; trader: string },
+      Position
+    >,
+  ): Promise<void> {
+    let trader: PublicKey;
+    try {
+      trader = new PublicKey(call.request.trader);
+    } catch {
+      endWithStatus(
+        call,
+        grpc.status.INVALID_ARGUMENT,
+        `Invalid trader ${call.request.trader}`,
+      );
+      return;
+    }
+    const watcher: MarketWatcher | undefined = await this.watch(call);
+    if (!watcher) {
+      return;
+    }
+    const subscriber: Subscriber<Position> = new Subscriber(
+      call,
+      'positions',
+      () => {
+        watcher.positionSubscribers.delete(subscriber);
+        this.unwatch(call.request.market);
+      },
+    );
+    watcher.positionSubscribers.set(subscriber, { trader, last: '' });
+    watcher.sendPosition(subscriber);
+  }
+
+  private subscribeFunding(call: grpc.ServerWritableStream<unknown, unknown>) {
+    endWithStatus(call, grpc.status.UNIMPLEMENTED, 'markets have no funding');
+  }
+
+  private async watch(
+    call: grpc.ServerWritableStream<{ market: string }, unknown>,
+  ): Promise<MarketWatcher | undefined> {
+    const market: string = call.request.market;
+    let address: PublicKey;
+    try {
+      address = new PublicKey(market);
+    } catch {
+      endWithStatus(
+        call,
+        grpc.status.INVALID_ARGUMENT,
+        `Invalid market ${market}`,
+      );
+      return undefined;
+    }
+    let watcher: MarketWatcher | undefined = this.watchers.get(market);
+    if (!watcher) {
+      watcher = new MarketWatcher(this.connection, address);
+      this.watchers.set(market, watcher);
+    }
+    try {
+      await watcher.load();
+    } catch (error) {
+      console.error('Failed to load market', market, error);
+      this.unwatch(market);
+      endWithStatus(
+        call,
+        grpc.status.NOT_FOUND,
+        `Failed to load market ${market}`,
+      );
+      return undefined;
+    }
+    return watcher;
+  }
+
+  private unwatch(market: string): void {
+    const watcher: MarketWatcher | undefined = this.watchers.get(market);
+    if (watcher && watcher.isIdle()) {
+      watcher.close();
+      this.watchers.delete(market);
+    }
+  }
+}
+
+function endWithStatus(
+  call: grpc.ServerWritableStream<unknown, unknown>,
+  code: grpc.status,
+  details: string,
+): void {
+  call.emit('error', { code, details });
+}
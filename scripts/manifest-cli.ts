@@ -0,0 +1,545 @@
+import 'dotenv/config';
+
+import { bignum } from '@metaplex-foundation/beet';
+import {
+  ConfirmedSignatureInfo,
+  Connection,
+  PublicKey,
+  VersionedTransactionResponse,
+} from '@solana/web3.js';
+import {
+  FillLogResult,
+  ManifestLog,
+  Market,
+  OrderType,
+  parseManifestLogs,
+  PositionHistory,
+  reconstructPositionHistory,
+} from '../client/ts/src';
+import { toFillLogResult } from '../client/ts/src/fillFeed';
+import * as fs from 'fs';
+
+// Command line tools for traders.
+//
+// statement <wallet> [--from <date>] [--to <date>] [--format csv|json]
+//   Signed ledger of the wallet's deposits, withdrawals and fills across every
+//   market it touched. Amounts are in tokens, positive into the wallet's
+//   balance on the exchange. Fills where the wallet was the maker land in
+//   other traders' transactions, so each market found in the wallet's own
+//   history is scanned as well. Markets charge no fees and are spot, so there
+//   are no fee, funding or liquidation rows.
+//
+// replay <market> [--from <date>] [--to <date>] [--out <file>]
+//   Replay file of the market's history for the program tests, see
+//   programs/manifest/tests/cases/replay.rs for the format. Traders are
+//   numbered in order of appearance. Swaps are replayed as exact in with no
+//   minimum out, and cancels of orders placed before --from are dropped, so
+//   export from the market's creation when the replay has to be faithful.
+const { RPC_URL } = process.env;
+
+if (!RPC_URL) {
+  throw new Error('RPC_URL missing from env');
+}
+
+const SIGNATURES_PAGE_SIZE: number = 1_000;
+const TRANSACTIONS_CHUNK_SIZE: number = 100;
+
+const USAGE: string =
+  'Usage: tsx scripts/manifest-cli.ts statement <wallet> ' +
+  '[--from <date>] [--to <date>] [--format csv|json]\n' +
+  '       tsx scripts/manifest-cli.ts replay <market> ' +
+  '[--from <date>] [--to <date>] [--out <file>]';
+
+const REPLAY_VERSION: number = 1;
+
+type ReplayOperation = Record<string, string | number | boolean>;
+
+type LedgerRow = {
+  blockTime: number;
+  slot: number;
+  signature: string;
+  market: string;
+  type: 'deposit' | 'withdraw' | 'fill';
+  mint: string;
+  amount: string;
+  /** Quote tokens per base token, only on fills. */
+  price?: number;
+};
+
+const connection: Connection = new Connection(RPC_URL, 'confirmed');
+const mintDecimals: Map<string, number> = new Map();
+
+function getFlag(args: string[], name: string): string | undefined {
+  const index: number = args.indexOf(name);
+  return index == -1 ? undefined : args[index + 1];
+}
+
+function getPositional(args: string[]): string | undefined {
+  return args.find(
+    (arg, index) => !arg.startsWith('--') && !args[index - 1]?.startsWith('--'),
+  );
+}
+
+function parseTime(value: string | undefined, fallback: number): number {
+  if (value == undefined) {
+    return fallback;
+  }
+  const millis: number = Date.parse(value);
+  if (Number.isNaN(millis)) {
+    throw new Error(`Invalid date ${value}`);
+  }
+  return Math.floor(millis / 1_000);
+}
+
+async function getDecimals(mint: string): Promise<number> {
+  const cached: number | undefined = mintDecimals.get(mint);
+  if (cached != undefined) {
+    return cached;
+  }
+  const info = await connection.getParsedAccountInfo(new PublicKey(mint));
+  const data = info.value?.data;
+  if (!data || !('parsed' in data)) {
+    throw new Error(`Failed to load mint ${mint}`);
+  }
+  const decimals: number = data.parsed.info.decimals;
+  mintDecimals.set(mint, decimals);
+  return decimals;
+}
+
+/**
+ * Format signed atoms as tokens without going through floats.
+ */
+function formatAtoms(atoms: bigint, decimals: number): string {
+  const sign: string = atoms < 0n ? '-' : '';
+  const digits: string = (atoms < 0n ? -atoms : atoms)
+    .toString()
+    .padStart(decimals + 1, '0');
+  if (decimals == 0) {
+    return sign + digits;
+  }
+  const whole: string = digits.slice(0, -decimals);
+  const fraction: string = digits.slice(-decimals).replace(/0+$/, '');
+  return sign + whole + (fraction ? `.${fraction}` : '');
+}
+
+/**
+ * Price as the mantissa and exponent order params take. Prices on chain were
+ * placed that way, so stripping trailing zeros gets them back exactly.
+ */
+function toMantissaAndExponent(inner: bignum): [number, number] {
+  let mantissa: bigint = BigInt(inner.toString());
+  let exponent: number = -18;
+  while (mantissa > 0n && mantissa % 10n == 0n && exponent < 8) {
+    mantissa /= 10n;
+    exponent += 1;
+  }
+  return [Number(mantissa), exponent];
+}
+
+/**
+ * Successful signatures for an address with block time in [from, to],
+ * oldest first.
+ */
+async function getSignatures(
+  address: PublicKey,
+  from: number,
+  to: number,
+): Promise<ConfirmedSignatureInfo[]> {
+  const signatures: ConfirmedSignatureInfo[] = [];
+  let before: string | undefined;
+  while (true) {
+    const page: ConfirmedSignatureInfo[] =
+      await connection.getSignaturesForAddress(address, {
+        before,
+        limit: SIGNATURES_PAGE_SIZE,
+      });
+    for (const info of page) {
+      const blockTime: number = info.blockTime ?? 0;
+      if (info.err == null && blockTime >= from && blockTime <= to) {
+        signatures.push(info);
+      }
+    }
+    if (
+      page.length < SIGNATURES_PAGE_SIZE ||
+      (page[page.length - 1].blockTime ?? 0) < from
+    ) {
+      break;
+    }
+    before = page[page.length - 1].signature;
+  }
+  return signatures.reverse();
+}
+
+async function getTransactions(
+  signatures: string[],
+): Promise<VersionedTransactionResponse[]> {
+  const transactions: VersionedTransactionResponse[] = [];
+  for (let i = 0; i < signatures.length; i += TRANSACTIONS_CHUNK_SIZE) {
+    const chunk: (VersionedTransactionResponse | null)[] =
+      await connection.getTransactions(
+        signatures.slice(i, i + TRANSACTIONS_CHUNK_SIZE),
+        { maxSupportedTransactionVersion: 0 },
+      );
+    for (const tx of chunk) {
+      if (tx?.meta?.logMessages && tx.meta.err == null) {
+        transactions.push(tx);
+      }
+    }
+  }
+  return transactions;
+}
+
+async function toLedgerRows(
+  wallet: string,
+  tx: VersionedTransactionResponse,
+  logs: ManifestLog[],
+): Promise<LedgerRow[]> {
+  const rows: LedgerRow[] = [];
+  const base = {
+    blockTime: tx.blockTime ?? 0,
+    slot: tx.slot,
+    signature: tx.transaction.signatures[0],
+  };
+  for (const { type, log } of logs) {
+    if (type == 'deposit' || type == 'withdraw') {
+      if (log.trader.toBase58() != wallet) {
+        continue;
+      }
+      const mint: string = log.mint.toBase58();
+      const atoms: bigint = BigInt(log.amountAtoms.toString());
+      rows.push({
+        ...base,
+        market: log.market.toBase58(),
+        type,
+        mint,
+        amount: formatAtoms(
+          type == 'deposit' ? atoms : -atoms,
+          await getDecimals(mint),
+        ),
+      });
+    } else if (type == 'fill') {
+      const isMaker: boolean = log.maker.toBase58() == wallet;
+      const isTaker: boolean = log.taker.toBase58() == wallet;
+      if (isMaker == isTaker) {
+        continue;
+      }
+      const isBuy: boolean = isTaker ? log.takerIsBuy : !log.takerIsBuy;
+      const baseMint: string = log.baseMint.toBase58();
+      const quoteMint: string = log.quoteMint.toBase58();
+      const baseDecimals: number = await getDecimals(baseMint);
+      const quoteDecimals: number = await getDecimals(quoteMint);
+      const baseAtoms: bigint = BigInt(log.baseAtoms.inner.toString());
+      const quoteAtoms: bigint = BigInt(log.quoteAtoms.inner.toString());
+      const price: number =
+        (Number(quoteAtoms) / Number(baseAtoms)) *
+        10 ** (baseDecimals - quoteDecimals);
+      const market: string = log.market.toBase58();
+      rows.push({
+        ...base,
+        market,
+        type,
+        mint: baseMint,
+        amount: formatAtoms(isBuy ? baseAtoms : -baseAtoms, baseDecimals),
+        price,
+      });
+      rows.push({
+        ...base,
+        market,
+        type,
+        mint: quoteMint,
+        amount: formatAtoms(isBuy ? -quoteAtoms : quoteAtoms, quoteDecimals),
+        price,
+      });
+    }
+  }
+  return rows;
+}
+
+function toCsv(rows: LedgerRow[]): string {
+  const lines: string[] = [
+    'time,slot,signature,market,type,mint,amount,price',
+  ];
+  for (const row of rows) {
+    lines.push(
+      [
+        new Date(row.blockTime * 1_000).toISOString(),
+        row.slot,
+        row.signature,
+        row.market,
+        row.type,
+        row.mint,
+        row.amount,
+        row.price ?? '',
+      ].join(','),
+    );
+  }
+  return lines.join('\n') + '\n';
+}
+
+async function statement(args: string[]): Promise<void> {
+  const walletArg: string | undefined = getPositional(args);
+  if (!walletArg) {
+    console.error(USAGE);
+    process.exit(1);
+  }
+  const wallet: PublicKey = new PublicKey(walletArg);
+  const from: number = parseTime(getFlag(args, '--from'), 0);
+  const to: number = parseTime(
+    getFlag(args, '--to'),
+    Math.floor(Date.now() / 1_000),
+  );
+  const format: string = getFlag(args, '--format') ?? 'csv';
+  if (format != 'csv' && format != 'json') {
+    throw new Error(`Invalid format ${format}`);
+  }
+
+  // The wallet's own transactions, which also tell us the markets to scan
+  // for maker fills.
+  const seen: Set<string> = new Set();
+  const markets: Set<string> = new Set();
+  const parsed: [VersionedTransactionResponse, ManifestLog[]][] = [];
+  const walletSignatures: string[] = (
+    await getSignatures(wallet, from, to)
+  ).map((info) => info.signature);
+  for (const tx of await getTransactions(walletSignatures)) {
+    const logs: ManifestLog[] = parseManifestLogs(tx.meta!.logMessages!);
+    seen.add(tx.transaction.signatures[0]);
+    parsed.push([tx, logs]);
+    for (const { log } of logs) {
+      markets.add(log.market.toBase58());
+    }
+  }
+  for (const market of markets) {
+    const marketSignatures: string[] = (
+      await getSignatures(new PublicKey(market), from, to)
+    )
+      .map((info) => info.signature)
+      .filter((signature) => !seen.has(signature));
+    for (const tx of await getTransactions(marketSignatures)) {
+      seen.add(tx.transaction.signatures[0]);
+      parsed.push([tx, parseManifestLogs(tx.meta!.logMessages!)]);
+    }
+  }
+  parsed.sort(([a], [b]) => a.slot - b.slot);
+
+  const rows: LedgerRow[] = [];
+  const fills: FillLogResult[] = [];
+  for (const [tx, logs] of parsed) {
+    rows.push(...(await toLedgerRows(wallet.toBase58(), tx, logs)));
+    for (const { type, log } of logs) {
+      if (type == 'fill') {
+        fills.push(
+          toFillLogResult(
+            log,
+            tx.slot,
+            tx.transaction.signatures[0],
+            undefined,
+            undefined,
+            undefined,
+            undefined,
+            tx.blockTime ?? undefined,
+          ),
+        );
+      }
+    }
+  }
+
+  if (format == 'csv') {
+    process.stdout.write(toCsv(rows));
+    return;
+  }
+  const positions: PositionHistory[] = [...markets].map((market) =>
+    reconstructPositionHistory(fills, market, wallet.toBase58()),
+  );
+  process.stdout.write(
+    JSON.stringify(
+      {
+        wallet: wallet.toBase58(),
+        from,
+        to,
+        ledger: rows,
+        positions: positions.map((history) => {
+          const last = history.entries[history.entries.length - 1];
+          return {
+            market: history.market,
+            positionBaseAtoms: last?.positionBaseAtoms ?? '0',
+            averageEntryPriceAtoms: last?.averageEntryPriceAtoms ?? 0,
+            realizedPnlQuoteAtoms: last?.totalRealizedPnlQuoteAtoms ?? '0',
+          };
+        }),
+      },
+      null,
+      2,
+    ) + '\n',
+  );
+}
+
+async function replay(args: string[]): Promise<void> {
+  const marketArg: string | undefined = getPositional(args);
+  if (!marketArg) {
+    console.error(USAGE);
+    process.exit(1);
+  }
+  const marketKey: PublicKey = new PublicKey(marketArg);
+  const from: number = parseTime(getFlag(args, '--from'), 0);
+  const to: number = parseTime(
+    getFlag(args, '--to'),
+    Math.floor(Date.now() / 1_000),
+  );
+  const out: string | undefined = getFlag(args, '--out');
+  const market: Market = await Market.loadFromAddress({
+    connection,
+    address: marketKey,
+  });
+
+  const traders: Map<string, number> = new Map();
+  const traderIndex = (trader: PublicKey): number => {
+    const key: string = trader.toBase58();
+    if (!traders.has(key)) {
+      traders.set(key, traders.size);
+    }
+    return traders.get(key)!;
+  };
+  // On chain sequence number to the placeOrder operation that rested it.
+  const placedBy: Map<string, number> = new Map();
+  const operations: ReplayOperation[] = [];
+  let lastSlot: number | undefined;
+
+  const signatures: string[] = (
+    await getSignatures(marketKey, from, to)
+  ).map((info) => info.signature);
+  for (const tx of await getTransactions(signatures)) {
+    const logs: ManifestLog[] = parseManifestLogs(
+      tx.meta!.logMessages!,
+    ).filter(({ log }) => log.market.equals(marketKey));
+    if (logs.length == 0) {
+      continue;
+    }
+    if (lastSlot != undefined && tx.slot > lastSlot) {
+      operations.push({ op: 'advanceSlots', slots: tx.slot - lastSlot });
+    }
+    lastSlot = tx.slot;
+
+    // Fills without a placeOrder from the taker in the same transaction came
+    // from a swap.
+    const placers: Set<string> = new Set();
+    for (const { type, log } of logs) {
+      if (type == 'placeOrder') {
+        placers.add(log.trader.toBase58());
+      }
+    }
+    const swaps: Map<string, ReplayOperation> = new Map();
+    for (const { type, log } of logs) {
+      if (type == 'claimSeat') {
+        operations.push({ op: 'claimSeat', trader: traderIndex(log.trader) });
+      } else if (type == 'deposit' || type == 'withdraw') {
+        operations.push({
+          op: type,
+          trader: traderIndex(log.trader),
+          token: log.mint.equals(market.baseMint()) ? 'base' : 'quote',
+          atoms: log.amountAtoms.toString(),
+        });
+      } else if (type == 'placeOrder') {
+        const [priceMantissa, priceExponent] = toMantissaAndExponent(
+          log.price.inner,
+        );
+        const operation: ReplayOperation = {
+          op: 'placeOrder',
+          trader: traderIndex(log.trader),
+          side: log.isBid ? 'bid' : 'ask',
+          baseAtoms: log.baseAtoms.inner.toString(),
+          priceMantissa,
+          priceExponent,
+          orderType: OrderType[log.orderType],
+        };
+        // Reverse orders carry their spread in lastValidSlot.
+        if (
+          log.lastValidSlot == 0 ||
+          log.orderType == OrderType.Reverse ||
+          log.orderType == OrderType.ReverseTight
+        ) {
+          operation.lastValidSlot = log.lastValidSlot;
+        } else {
+          operation.expiresInSlots = log.lastValidSlot - tx.slot;
+        }
+        placedBy.set(log.orderSequenceNumber.toString(), operations.length);
+        operations.push(operation);
+      } else if (type == 'cancelOrder') {
+        const order: number | undefined = placedBy.get(
+          log.orderSequenceNumber.toString(),
+        );
+        if (order != undefined) {
+          operations.push({
+            op: 'cancelOrder',
+            trader: traderIndex(log.trader),
+            order,
+          });
+        }
+      } else if (type == 'fill') {
+        const taker: string = log.taker.toBase58();
+        if (placers.has(taker)) {
+          continue;
+        }
+        let swap: ReplayOperation | undefined = swaps.get(taker);
+        if (!swap) {
+          swap = {
+            op: 'swap',
+            trader: traderIndex(log.taker),
+            inAtoms: '0',
+            outAtoms: '0',
+            isBaseIn: !log.takerIsBuy,
+            isExactIn: true,
+          };
+          swaps.set(taker, swap);
+          operations.push(swap);
+        }
+        const inAtoms: bignum = log.takerIsBuy
+          ? log.quoteAtoms.inner
+          : log.baseAtoms.inner;
+        swap.inAtoms = (
+          BigInt(swap.inAtoms as string) + BigInt(inAtoms.toString())
+        ).toString();
+      }
+    }
+  }
+
+  const replayFile: string =
+    JSON.stringify(
+      {
+        version: REPLAY_VERSION,
+        description:
+          `Market ${marketKey.toBase58()} from ` +
+          `${new Date(from * 1_000).toISOString()} to ` +
+          `${new Date(to * 1_000).toISOString()}`,
+        traders: Math.max(traders.size, 1),
+        operations,
+      },
+      null,
+      2,
+    ) + '\n';
+  if (out) {
+    fs.writeFileSync(out, replayFile);
+  } else {
+    process.stdout.write(replayFile);
+  }
+}
+
+const run = async () => {
+  const [command, ...args] = process.argv.slice(2);
+  switch (command) {
+    case 'statement':
+      await statement(args);
+      break;
+    case 'replay':
+      await replay(args);
+      break;
+    default:
+      console.error(USAGE);
+      process.exit(1);
+  }
+};
+
+run().catch((e) => {
+  console.error('fatal error', e);
+  process.exit(1);
+});
@@ -10,8 +10,9 @@ function sleep(ms: number): Promise<void> {
 }
 import express from 'express';
 import promBundle from 'express-prom-bundle';
+import { GrpcFeedServer } from './feed_utils/grpcFeedServer';
 
-const { RPC_URL } = process.env;
+const { RPC_URL, GRPC_PORT } = process.env;
 
 if (!RPC_URL) {
   throw new Error('RPC_URL missing from env');
@@ -61,6 +62,15 @@ const run = async () => {
 
   const timeoutMs = 5_000;
 
+  // Optional gRPC feed. It outlives feed restarts so subscribers stay
+  // connected while the feed reconnects.
+  let grpcServer: GrpcFeedServer | undefined;
+  if (GRPC_PORT) {
+    grpcServer = new GrpcFeedServer(new Connection(rpcUrl, 'confirmed'));
+    await grpcServer.start(Number(GRPC_PORT));
+  }
+  const onFill = grpcServer?.publishFill.bind(grpcServer);
+
   console.log(
     `starting feed... (using ${useBlockFeed ? 'block' : 'GSFA'} feed)`,
   );
@@ -70,7 +80,9 @@ const run = async () => {
       console.log('setting up connection...');
       const conn = new Connection(rpcUrl, 'confirmed');
       console.log('setting up feed...');
-      feed = useBlockFeed ? new FillFeedBlockSub(conn) : new FillFeed(conn);
+      feed = useBlockFeed
+        ? new FillFeedBlockSub(conn, undefined, undefined, onFill)
+        : new FillFeed(conn, undefined, onFill);
 
       if (useBlockFeed) {
         await Promise.all([